@@ -0,0 +1,385 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Command line tool of pest-typed, for build systems other than cargo and quick debugging.
+//!
+//! - `pest-typed check <GRAMMAR>` lints the grammar, the same way as the derive macro does.
+//! - `pest-typed generate <GRAMMAR> [--name <NAME>] [--option <OPTION>]... [--out <FILE>]`
+//!   writes the codes that `#[derive(TypedParser)]` would generate.
+//! - `pest-typed parse <GRAMMAR> <RULE> <INPUT>` parses the input file with the rule,
+//!   and writes the token tree as JSON in the format of `pest_typed::json`.
+//! - `pest-typed export <GRAMMAR> [--antlr <NAME>]` writes the grammar as W3C EBNF,
+//!   or as an ANTLR 4 grammar named `NAME`.
+//! - `pest-typed import <ABNF>` writes the ABNF grammar as a pest grammar.
+//! - `pest-typed fmt <GRAMMAR> [--check]` formats the grammar in place,
+//!   or with `--check`, fails if it's not formatted.
+
+#![warn(
+    missing_docs,
+    rust_2018_idioms,
+    unused_qualifications,
+    future_incompatible
+)]
+
+use pest_typed::{json::write_error, json::write_token, vm::Vm};
+use pest_typed_generator::{
+    build::generate,
+    export::{to_antlr, to_ebnf},
+    format::format_grammar,
+    import::from_abnf,
+    lint_grammar,
+};
+use std::{env, fs, path::Path, process::ExitCode};
+
+const USAGE: &str = "\
+Usage:
+    pest-typed check <GRAMMAR>
+    pest-typed generate <GRAMMAR> [--name <NAME>] [--option <OPTION>]... [--out <FILE>]
+    pest-typed parse <GRAMMAR> <RULE> <INPUT>
+    pest-typed export <GRAMMAR> [--antlr <NAME>]
+    pest-typed import <ABNF>
+    pest-typed fmt <GRAMMAR> [--check]
+
+Commands:
+    check       Lint the grammar.
+    generate    Write codes generated from the grammar, to stdout by default.
+                Options are attributes of the derive macro, such as `emit_rule_reference`.
+    parse       Parse the input file with the rule, and write the token tree as JSON.
+    export      Write the grammar as W3C EBNF, or as an ANTLR 4 grammar with `--antlr`.
+    import      Write the ABNF grammar of RFC 5234 as a pest grammar.
+    fmt         Format the grammar in place, or check that it's formatted with `--check`.";
+
+/// Result of a command: text to write to stdout, and whether it succeeded.
+#[derive(Debug, PartialEq)]
+struct Output {
+    text: String,
+    success: bool,
+}
+
+impl Output {
+    fn ok(text: String) -> Self {
+        Self {
+            text,
+            success: true,
+        }
+    }
+    fn failed(text: String) -> Self {
+        Self {
+            text,
+            success: false,
+        }
+    }
+}
+
+fn read(path: &str) -> Result<String, String> {
+    fs::read_to_string(path).map_err(|error| format!("error opening {:?}: {}", path, error))
+}
+
+fn check(grammar: &str) -> Result<Output, String> {
+    let grammar = read(grammar)?;
+    match lint_grammar(&grammar) {
+        Ok(lints) => {
+            let mut text = String::new();
+            for lint in &lints {
+                text.push_str(&format!("warning[{}]: {}\n", lint.code, lint.error));
+            }
+            text.push_str(&format!("{} warning(s).", lints.len()));
+            Ok(Output::ok(text))
+        }
+        Err(error) => Ok(Output::failed(format!("error: {}", error))),
+    }
+}
+
+fn generate_codes(grammar: &str, args: &[String]) -> Result<Output, String> {
+    if !Path::new(grammar).is_file() {
+        return Err(format!("error opening {:?}: not a file", grammar));
+    }
+    let mut name = "Parser";
+    let mut options = vec![];
+    let mut out = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| format!("missing value of `{}`", arg))
+        };
+        match arg.as_str() {
+            "--name" => name = value()?,
+            "--option" => options.push(value()?.as_str()),
+            "--out" => out = Some(value()?),
+            _ => return Err(format!("unknown argument `{}`", arg)),
+        }
+    }
+    let absolute = fs::canonicalize(grammar).map_err(|error| error.to_string())?;
+    let mut input = format!("#[grammar = {:?}]\n", absolute.display().to_string());
+    for option in options {
+        input.push_str(&format!("#[{}]\n", option));
+    }
+    input.push_str(&format!("struct {};", name));
+    let codes = generate(&input);
+    match out {
+        Some(out) => {
+            fs::write(out, codes).map_err(|error| format!("error writing {:?}: {}", out, error))?;
+            Ok(Output::ok(String::new()))
+        }
+        None => Ok(Output::ok(codes)),
+    }
+}
+
+fn parse(grammar: &str, rule: &str, input: &str) -> Result<Output, String> {
+    let grammar = read(grammar)?;
+    let input = read(input)?;
+    let vm = Vm::new(&grammar).map_err(|errors| {
+        errors
+            .iter()
+            .map(|error| format!("error: {}", error))
+            .collect::<Vec<_>>()
+            .join("\n")
+    })?;
+    let mut text = String::new();
+    let success = match vm.parse(rule, &input) {
+        Ok(node) => {
+            text.push_str(r#"{"ok":["#);
+            for (i, token) in node.tokens().iter().enumerate() {
+                if i > 0 {
+                    text.push(',');
+                }
+                write_token(&mut text, token, &input).unwrap();
+            }
+            text.push_str("]}");
+            true
+        }
+        Err(error) => {
+            text.push_str(r#"{"error":"#);
+            write_error(&mut text, &error, &input).unwrap();
+            text.push('}');
+            false
+        }
+    };
+    Ok(Output { text, success })
+}
+
+fn export(grammar: &str, args: &[String]) -> Result<Output, String> {
+    let grammar = read(grammar)?;
+    let res = match args {
+        [] => to_ebnf(&grammar),
+        [flag, name] if flag == "--antlr" => to_antlr(&grammar, name),
+        _ => return Err(USAGE.to_owned()),
+    };
+    match res {
+        Ok(text) => Ok(Output::ok(text)),
+        Err(errors) => Ok(Output::failed(
+            errors
+                .iter()
+                .map(|error| format!("error: {}", error))
+                .collect::<Vec<_>>()
+                .join("\n"),
+        )),
+    }
+}
+
+fn import(abnf: &str) -> Result<Output, String> {
+    let abnf = read(abnf)?;
+    match from_abnf(&abnf) {
+        Ok(text) => Ok(Output::ok(text)),
+        Err(error) => Ok(Output::failed(format!("error: {}", error))),
+    }
+}
+
+fn fmt(path: &str, args: &[String]) -> Result<Output, String> {
+    let check = match args {
+        [] => false,
+        [flag] if flag == "--check" => true,
+        _ => return Err(USAGE.to_owned()),
+    };
+    let grammar = read(path)?;
+    let formatted = match format_grammar(&grammar) {
+        Ok(formatted) => formatted,
+        Err(error) => return Ok(Output::failed(format!("error: {}", error))),
+    };
+    if formatted == grammar {
+        return Ok(Output::ok(String::new()));
+    }
+    if check {
+        return Ok(Output::failed(format!("{} is not formatted.", path)));
+    }
+    fs::write(path, formatted).map_err(|error| format!("error writing {:?}: {}", path, error))?;
+    Ok(Output::ok(String::new()))
+}
+
+fn run(args: &[String]) -> Result<Output, String> {
+    match args {
+        [command, grammar] if command == "check" => check(grammar),
+        [command, grammar, rest @ ..] if command == "generate" => generate_codes(grammar, rest),
+        [command, grammar, rule, input] if command == "parse" => parse(grammar, rule, input),
+        [command, grammar, rest @ ..] if command == "export" => export(grammar, rest),
+        [command, abnf] if command == "import" => import(abnf),
+        [command, grammar, rest @ ..] if command == "fmt" => fmt(grammar, rest),
+        [help] if help == "help" || help == "--help" || help == "-h" => {
+            Ok(Output::ok(USAGE.to_owned()))
+        }
+        _ => Err(USAGE.to_owned()),
+    }
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    match run(&args) {
+        Ok(output) => {
+            if !output.text.is_empty() {
+                println!("{}", output.text);
+            }
+            if output.success {
+                ExitCode::SUCCESS
+            } else {
+                ExitCode::FAILURE
+            }
+        }
+        Err(message) => {
+            eprintln!("{}", message);
+            ExitCode::from(2)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{run, Output, USAGE};
+    use std::{env, fs, path::PathBuf};
+
+    fn file(name: &str, content: &str) -> String {
+        let path: PathBuf = env::temp_dir().join(format!("pest-typed-cli-{}", name));
+        fs::write(&path, content).unwrap();
+        path.display().to_string()
+    }
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn check() {
+        let grammar = file("check.pest", r#"a = { "a" | "a" ~ "b" }"#);
+        let output = run(&args(&["check", &grammar])).unwrap();
+        assert!(output.success);
+        assert!(output.text.starts_with("warning[shadowed_alternative]"));
+        assert!(output.text.ends_with("1 warning(s)."));
+
+        let grammar = file("invalid.pest", "a = {");
+        assert!(!run(&args(&["check", &grammar])).unwrap().success);
+    }
+
+    #[test]
+    fn generate() {
+        let grammar = file("generate.pest", r#"a = { "a" }"#);
+        let output = run(&args(&[
+            "generate",
+            &grammar,
+            "--option",
+            "emit_rule_reference",
+        ]))
+        .unwrap();
+        assert!(output.success);
+        assert!(output.text.contains("pub mod rules"));
+        assert!(run(&args(&["generate", &grammar, "--name"])).is_err());
+    }
+
+    #[test]
+    fn parse() {
+        let grammar = file("parse.pest", r#"a = { "a" ~ b } b = { "b" }"#);
+        let ok = file("ok.txt", "ab");
+        let error = file("error.txt", "aa");
+        let output = run(&args(&["parse", &grammar, "a", &ok])).unwrap();
+        assert_eq!(
+            output,
+            Output::ok(
+                [
+                    r#"{"ok":[{"rule":"a","#,
+                    r#""start":{"offset":0,"utf16":0,"line":1,"column":1},"#,
+                    r#""end":{"offset":2,"utf16":2,"line":1,"column":3},"#,
+                    r#""children":[{"rule":"b","#,
+                    r#""start":{"offset":1,"utf16":1,"line":1,"column":2},"#,
+                    r#""end":{"offset":2,"utf16":2,"line":1,"column":3},"#,
+                    r#""children":[]}]}]}"#,
+                ]
+                .concat()
+            )
+        );
+        let output = run(&args(&["parse", &grammar, "a", &error])).unwrap();
+        assert!(!output.success);
+        assert!(output.text.starts_with(r#"{"error":{"#));
+        assert!(output.text.contains(r#""start":{"offset":1,"#));
+    }
+
+    #[test]
+    fn export() {
+        let grammar = file(
+            "export.pest",
+            "// Items.\na = { \"a\" ~ b? }\nb = _{ 'b'..'c' }",
+        );
+        let output = run(&args(&["export", &grammar])).unwrap();
+        assert_eq!(
+            output,
+            Output::ok(String::from(
+                "/* Items. */\na ::= \"a\" b?\nb ::= [b-c] /* silent */\n"
+            ))
+        );
+        let output = run(&args(&["export", &grammar, "--antlr", "Items"])).unwrap();
+        assert!(output
+            .text
+            .starts_with("grammar Items;\n\n// Items.\na : 'a' b? ;\n"));
+        assert!(run(&args(&["export", &grammar, "--antlr"])).is_err());
+
+        let grammar = file("export-invalid.pest", "a = { b }");
+        assert!(!run(&args(&["export", &grammar])).unwrap().success);
+    }
+
+    #[test]
+    fn import() {
+        let abnf = file("import.abnf", "a = \"a\" / b-c\nb-c = %x62-63");
+        let output = run(&args(&["import", &abnf])).unwrap();
+        assert_eq!(
+            output,
+            Output::ok(String::from("a = { ^\"a\" | b_c }\nb_c = { 'b'..'c' }\n"))
+        );
+        let abnf = file("import-invalid.abnf", "a = <prose>");
+        let output = run(&args(&["import", &abnf])).unwrap();
+        assert_eq!(
+            output,
+            Output::failed(String::from(
+                "error: prose value `<prose>` can't be converted at line 1"
+            ))
+        );
+    }
+
+    #[test]
+    fn fmt() {
+        let grammar = file("fmt.pest", "a={\"a\"~b}\nb = { \"b\" }\n");
+        let output = run(&args(&["fmt", &grammar, "--check"])).unwrap();
+        assert_eq!(
+            output,
+            Output::failed(format!("{} is not formatted.", grammar))
+        );
+        assert!(run(&args(&["fmt", &grammar])).unwrap().success);
+        assert_eq!(
+            fs::read_to_string(&grammar).unwrap(),
+            "a = { \"a\" ~ b }\nb = { \"b\" }\n"
+        );
+        assert!(run(&args(&["fmt", &grammar, "--check"])).unwrap().success);
+
+        let grammar = file("fmt-invalid.pest", "a = {");
+        assert!(!run(&args(&["fmt", &grammar])).unwrap().success);
+    }
+
+    #[test]
+    fn usage() {
+        assert_eq!(run(&args(&["--help"])).unwrap().text, USAGE);
+        assert!(run(&args(&["unknown"])).is_err());
+    }
+}
@@ -1,41 +1,121 @@
-use criterion::{criterion_group, criterion_main, Criterion};
+//! Compare typed and untyped parsing on representative grammars.
+//!
+//! Run `cargo bench -- --save-baseline main` before a change and `cargo bench -- --baseline main` after it
+//! to see regressions in generated code.
+//! Allocation counts of a single parse are printed before each group.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
 use pest::Parser;
 use pest_typed::ParsableTypedNode;
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// Counts allocations, including reallocations.
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+fn allocations(f: impl FnOnce()) -> usize {
+    let start = ALLOCATIONS.load(Ordering::Relaxed);
+    f();
+    ALLOCATIONS.load(Ordering::Relaxed) - start
+}
+
+macro_rules! parsers {
+    ($name:ident, $path:literal) => {
+        pub mod $name {
+            pub mod typed {
+                use pest_typed_derive::TypedParser;
+                //
+                #[derive(TypedParser)]
+                #[grammar = $path]
+                #[emit_rule_reference]
+                pub struct Parser;
+            }
+            pub mod pest {
+                use pest_derive::Parser;
+                //
+                #[derive(Parser)]
+                #[grammar = $path]
+                pub struct Parser;
+            }
+        }
+    };
+}
+
+parsers!(json, "benches/json.pest");
+parsers!(toml, "benches/toml.pest");
+parsers!(arith, "benches/arith.pest");
+
+fn toml_input() -> String {
+    let mut input = String::from("# generated\ntitle = \"benchmark\"\n");
+    for i in 0..2000 {
+        input += &format!(
+            "\n[server.s{i}]\nhost = \"10.0.0.{}\" # comment\nports = [ 8000, 8001,\n  8002, ]\nratio = 1.5e-3\nenabled = true\nlimits = {{ cpu = 2, memory = 1_024 }}\n",
+            i % 256
+        );
+    }
+    input
+}
 
-pub mod json_typed {
-    use pest_typed_derive::TypedParser;
-    //
-    #[derive(TypedParser)]
-    #[grammar = "benches/json.pest"]
-    #[emit_rule_reference]
-    pub struct JsonParser;
+fn arith_input() -> String {
+    let mut input = String::new();
+    for i in 0..5000 {
+        input += &format!("(a{i} + 2.5) * -x ^ 2 ^ y / (3 - z % {i}) +\n");
+    }
+    input + "0"
 }
 
-pub mod json_pest {
-    use pest_derive::Parser;
-    //
-    #[derive(Parser)]
-    #[grammar = "benches/json.pest"]
-    pub struct JsonParser;
+macro_rules! bench {
+    ($c:expr, $name:ident, $rule:ident, $input:expr, $samples:expr) => {{
+        let input: &str = $input;
+        let typed = || {
+            black_box($name::typed::pairs::$rule::try_parse(black_box(input)).unwrap());
+        };
+        let untyped = || {
+            black_box(
+                $name::pest::Parser::parse($name::pest::Rule::$rule, black_box(input)).unwrap(),
+            );
+        };
+        eprintln!(
+            "{}: {} allocations (typed), {} allocations (pest)",
+            stringify!($name),
+            allocations(typed),
+            allocations(untyped),
+        );
+        let mut group = $c.benchmark_group(stringify!($name));
+        group.sample_size($samples);
+        group.throughput(Throughput::Bytes(input.len() as u64));
+        group.bench_function("typed", |b| b.iter(typed));
+        group.bench_function("pest", |b| b.iter(untyped));
+        group.finish();
+    }};
 }
 
 pub fn criterion_benchmark(c: &mut Criterion) {
-    let mut group = c.benchmark_group("sample-size-example");
-    group.sample_size(10);
-    group.bench_function("json_typed", |b| {
-        b.iter(|| {
-            let json_file =
-                include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/benches/Canada.json"));
-            let _ = json_typed::pairs::json::try_parse(json_file);
-        })
-    });
-    group.bench_function("json_pest", |b| {
-        b.iter(|| {
-            let json_file =
-                include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/benches/Canada.json"));
-            let pairs = json_pest::JsonParser::parse(json_pest::Rule::json, json_file).unwrap();
-        })
-    });
+    let json = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/benches/Canada.json"));
+    bench!(c, json, json, json, 10);
+    bench!(c, toml, toml, &toml_input(), 20);
+    bench!(c, arith, calc, &arith_input(), 20);
 }
 
 criterion_group!(benches, criterion_benchmark);
@@ -0,0 +1,14 @@
+use pest_typed::{error::Error, iterators::Pair, ParsableTypedNode};
+use pest_typed_derive::TypedParser;
+
+/// The grammar is shared with the benchmarks.
+#[derive(TypedParser)]
+#[grammar = "benches/arith.pest"]
+#[emit_rule_reference]
+struct Arith;
+
+fn main() -> Result<(), Error<Rule>> {
+    let calc = pairs::calc::try_parse("(a + 2.5) * -x ^ 2 ^ y / (3 - z % 4)")?;
+    println!("{:#?}", calc.as_token_tree());
+    Ok(())
+}
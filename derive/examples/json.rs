@@ -0,0 +1,24 @@
+use pest_typed::{error::Error, iterators::Pair, ParsableTypedNode};
+use pest_typed_derive::TypedParser;
+use std::iter::once;
+
+/// The grammar is shared with the benchmarks.
+#[derive(TypedParser)]
+#[grammar = "benches/json.pest"]
+#[emit_rule_reference]
+struct Json;
+
+fn main() -> Result<(), Error<Rule>> {
+    let json = pairs::json::try_parse(r#"{ "a": [1, 2.5e3, true], "b": { "c": null } }"#)?;
+    let (_, value, _) = json.as_ref();
+    let object = value.object().unwrap();
+    let keys: Vec<_> = object
+        .pair()
+        .into_iter()
+        .flat_map(|(first, following)| once(first).chain(following))
+        .map(|pair| pair.string().span.as_str())
+        .collect();
+    assert_eq!(keys, [r#""a""#, r#""b""#]);
+    println!("{:#?}", json.as_token_tree());
+    Ok(())
+}
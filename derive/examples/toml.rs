@@ -0,0 +1,21 @@
+use pest_typed::{error::Error, iterators::Pair, ParsableTypedNode};
+use pest_typed_derive::TypedParser;
+
+/// The grammar is shared with the benchmarks.
+#[derive(TypedParser)]
+#[grammar = "benches/toml.pest"]
+#[emit_rule_reference]
+struct Toml;
+
+fn main() -> Result<(), Error<Rule>> {
+    let toml = pairs::toml::try_parse(
+        r#"title = "example" # comment
+
+[server.http]
+ports = [ 80, 443, ]
+limits = { cpu = 2, memory = 1_024 }
+"#,
+    )?;
+    println!("{:#?}", toml.as_token_tree());
+    Ok(())
+}
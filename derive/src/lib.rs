@@ -42,6 +42,8 @@
 //!
 //! Note: the simulated [Pairs](#https://docs.rs/pest/latest/pest/iterators/struct.Pairs.html) API behaves a bit different from original version. An atomic rule will not contain inner pairs.
 //!
+//! Nodes can also be queried with a small CSS-like selector, e.g. `function.select("function > parameter ident")`. See `pest_typed::selector`.
+//!
 //! ### Accesser API
 //!
 //! See [`fn@derive_typed_parser`] for how to enable Accesser API.
@@ -85,6 +87,14 @@
 //! - Emit a span (atomic rule in **pest**).
 //! - Emit inner expression (silent rule in **pest**).
 //!
+//! #### Constructing Rule Structs
+//!
+//! Each **Rule Struct** and **Tag Struct** has a `new` function taking the emitted fields in the order above,
+//! so that trees can be created directly without parsing text, for example `<pairs::foo>::new(content, span)`.
+//!
+//! Use `pest_typed::Span::synthetic` to create spans that are not taken from a parsed input.
+//! Elements of sequences can be created with `Skipped::new` (or `.into()`), which fills skipped contents with defaults.
+//!
 //! #### Example for Rule Structs
 //!
 //! ```rust
@@ -211,6 +221,27 @@ use proc_macro::TokenStream;
         truncate_accesser_at_node_tag,
         simulate_pair_api,
         no_warnings,
+        emit_fold,
+        emit_reflection,
+        flatten_wrappers,
+        emit_display,
+        named_choices,
+        module_per_rule,
+        report_code_size,
+        emit_scanner,
+        emit_syntax,
+        emit_owned,
+        do_not_emit_boundaries,
+        emit_events,
+        emit_branches,
+        non_exhaustive_nodes,
+        emit_cache,
+        emit_dyn,
+        grammar_tests,
+        grammar_unit_tests,
+        emit_wasm,
+        allocation,
+        allow_unused_rules,
     )
 )]
 pub fn derive_typed_parser(input: TokenStream) -> TokenStream {
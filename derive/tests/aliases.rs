@@ -0,0 +1,43 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+#![allow(deprecated)]
+
+use pest_typed::{ParsableTypedNode as _, RuleWrapper};
+use pest_typed_derive::TypedParser;
+
+#[derive(TypedParser)]
+#[grammar_inline = r#"
+WHITESPACE = _{ " " }
+#[alias = "item"]
+#[alias = "entry"]
+element    = @{ ASCII_ALPHA+ }
+list       =  { element ~ ("," ~ element)* }
+"#]
+#[emit_rule_reference]
+struct Parser;
+
+#[test]
+fn types() {
+    let old: pairs::item<'_> = pairs::element::try_parse("abc").unwrap();
+    let new: rules::element<'_> = old.clone();
+    assert_eq!(new, old);
+    let entry = pairs::entry::try_parse("abc").unwrap();
+    assert_eq!(entry.span.as_str(), "abc");
+}
+
+#[test]
+fn variants() {
+    assert_eq!(Rule::item, Rule::element);
+    assert_eq!(Rule::entry, Rule::element);
+    assert!(matches!(
+        <pairs::element<'_> as RuleWrapper<Rule>>::RULE,
+        Rule::item
+    ));
+}
@@ -0,0 +1,115 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use core::{
+    cell::Cell,
+    ops::{Deref, DerefMut},
+};
+use pest_typed::{
+    allocation::{Allocation, Container},
+    ParsableTypedNode as _,
+};
+use pest_typed_derive::TypedParser;
+
+thread_local! {
+    static CREATED: Cell<usize> = const { Cell::new(0) };
+}
+
+/// A vector that counts how many times it's created.
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub struct Counted<T>(Vec<T>);
+
+impl<T> Deref for Counted<T> {
+    type Target = [T];
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+impl<T> DerefMut for Counted<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+impl<T> IntoIterator for Counted<T> {
+    type Item = T;
+    type IntoIter = std::vec::IntoIter<T>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+impl<T> Container<T> for Counted<T> {
+    fn new() -> Self {
+        CREATED.with(|created| created.set(created.get() + 1));
+        Self(Vec::new())
+    }
+    fn push(&mut self, item: T) {
+        self.0.push(item)
+    }
+    fn capacity(&self) -> usize {
+        self.0.capacity()
+    }
+}
+
+pub struct Counting;
+impl Allocation for Counting {
+    type Vec<T> = Counted<T>;
+}
+
+#[derive(TypedParser)]
+#[grammar_inline = r#"
+WHITESPACE = _{ " " }
+item       = @{ ASCII_ALPHA+ }
+list       =  { item ~ ("," ~ item)* }
+"#]
+#[emit_rule_reference]
+#[allocation = "crate::Counting"]
+struct Parser;
+
+#[test]
+fn custom() {
+    let before = CREATED.with(Cell::get);
+    let list = pairs::list::try_parse("a, b, c").unwrap();
+    // The repetition in `list`, `ASCII_ALPHA+` in each `item`, and skipped white spaces.
+    assert!(CREATED.with(Cell::get) - before >= 4);
+    let (first, rest) = list.get_matched();
+    assert_eq!(first.span.as_str(), "a");
+    let rest: Vec<_> = rest
+        .iter_matched()
+        .map(|seq| seq.get_matched().1.span.as_str())
+        .collect();
+    assert_eq!(rest, ["b", "c"]);
+    let (_, items) = list.item();
+    assert_eq!(items.len(), 2);
+}
+
+mod inline {
+    use pest_typed_derive::TypedParser;
+
+    #[derive(TypedParser)]
+    #[grammar_inline = r#"
+WHITESPACE = _{ " " }
+item       = @{ ASCII_ALPHA+ }
+#[inline_capacity = "2"]
+list       =  { item ~ ("," ~ item)* }
+"#]
+    #[emit_rule_reference]
+    pub struct Parser;
+}
+
+#[test]
+fn inline_capacity() {
+    let list = inline::pairs::list::try_parse("a, b").unwrap();
+    let (_, rest) = list.get_matched();
+    assert!(!rest.content.spilled());
+    let list = inline::pairs::list::try_parse("a, b, c, d").unwrap();
+    let (_, rest) = list.get_matched();
+    assert!(rest.content.spilled());
+    let (_, items) = list.item();
+    assert_eq!(items.len(), 3);
+}
@@ -0,0 +1,70 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use pest_typed::{iterators::Pair as _, ParsableTypedNode as _};
+
+mod kept {
+    use pest_typed_derive::TypedParser;
+
+    #[derive(TypedParser)]
+    #[grammar_inline = r#"
+WHITESPACE = _{ " " }
+item       = @{ ASCII_ALPHA+ }
+list       =  { SOI ~ item ~ ("," ~ item)* ~ EOI }
+"#]
+    #[emit_rule_reference]
+    pub struct Parser;
+}
+
+mod omitted {
+    use pest_typed_derive::TypedParser;
+
+    #[derive(TypedParser)]
+    #[grammar_inline = r#"
+WHITESPACE = _{ " " }
+item       = @{ ASCII_ALPHA+ }
+list       =  { SOI ~ item ~ ("," ~ item)* ~ EOI }
+single     =  { SOI ~ item ~ EOI }
+middle     =  { item ~ EOI ~ SOI ~ item }
+"#]
+    #[emit_rule_reference]
+    #[do_not_emit_boundaries]
+    pub struct Parser;
+}
+
+#[test]
+fn positions() {
+    let list = kept::pairs::list::try_parse("a, b ").unwrap();
+    let (soi, first, _, eoi) = list.get_matched();
+    assert_eq!(soi.position.pos(), 0);
+    assert_eq!(first.span.as_str(), "a");
+    assert_eq!(eoi.position.pos(), 5);
+    assert_eq!(eoi.span.start(), 5);
+    assert_eq!(list.inner().count(), 3);
+}
+
+#[test]
+fn omitted() {
+    let list = omitted::pairs::list::try_parse(" a, b ").unwrap();
+    let (first, rest) = list.get_matched();
+    assert_eq!(first.span.as_str(), "a");
+    assert_eq!(rest.content.len(), 1);
+    assert_eq!(list.item().1.len(), 1);
+    assert_eq!(list.inner().count(), 2);
+    assert!(omitted::pairs::list::try_parse("a b").is_err());
+
+    let single = omitted::pairs::single::try_parse(" a ").unwrap();
+    assert_eq!(single.item().span.as_str(), "a");
+    assert!(omitted::pairs::single::try_parse_partial("a b").is_err());
+
+    // Boundaries elsewhere are kept.
+    assert!(omitted::pairs::middle::try_parse("a").is_err());
+    let error = omitted::pairs::list::try_parse("a,").unwrap_err();
+    assert!(error.to_string().contains("item"), "{}", error);
+}
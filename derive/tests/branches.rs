@@ -0,0 +1,69 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use pest_typed::ParsableTypedNode as _;
+
+mod positional {
+    use pest_typed_derive::TypedParser;
+
+    #[derive(TypedParser)]
+    #[grammar_inline = r#"
+number = @{ ASCII_DIGIT+ }
+string = @{ "\"" ~ (!"\"" ~ ANY)* ~ "\"" }
+value  =  { number ~ "!" | number | string | "null" | "(" ~ value ~ ")" }
+"#]
+    #[emit_rule_reference]
+    #[emit_branches]
+    pub struct Parser;
+}
+
+mod named {
+    use pest_typed_derive::TypedParser;
+
+    #[derive(TypedParser)]
+    #[grammar_inline = r#"
+number = @{ ASCII_DIGIT+ }
+string = @{ "\"" ~ (!"\"" ~ ANY)* ~ "\"" }
+value  =  { number | string }
+"#]
+    #[emit_rule_reference]
+    #[emit_branches]
+    #[named_choices]
+    pub struct Parser;
+}
+
+fn describe(value: &positional::pairs::value<'_>) -> String {
+    use positional::branches::value as Branch;
+    match value.branch() {
+        Branch::_0(inner) => format!("{}!", inner.get_matched().0.span.as_str()),
+        Branch::number(number) => format!("number {}", number.span.as_str()),
+        Branch::string(string) => format!("string {}", string.span.as_str()),
+        Branch::_3(_) => "null".to_owned(),
+        Branch::_4(inner) => format!("({})", describe(inner.get_matched().1)),
+    }
+}
+
+#[test]
+fn positional() {
+    let describe = |input| describe(&positional::pairs::value::try_parse(input).unwrap());
+    assert_eq!(describe("12"), "number 12");
+    assert_eq!(describe(r#""s""#), r#"string "s""#);
+    assert_eq!(describe("null"), "null");
+    assert_eq!(describe("((null))"), "((null))");
+    assert_eq!(describe("3!"), "3!");
+}
+
+#[test]
+fn named() {
+    use named::branches::value as Branch;
+    let value = named::pairs::value::try_parse("42").unwrap();
+    assert!(matches!(value.branch(), Branch::number(n) if n.span.as_str() == "42"));
+    let value = named::pairs::value::try_parse(r#""""#).unwrap();
+    assert!(matches!(value.branch(), Branch::string(_)));
+}
@@ -0,0 +1,50 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use pest_typed::{predefined_node::Str, ParsableTypedNode, RuleStruct, Span};
+use pest_typed_derive::TypedParser;
+
+#[derive(TypedParser)]
+#[grammar_inline = r#"
+WHITESPACE = _{ " " }
+ident      = @{ ASCII_ALPHA+ }
+assign     =  { ident ~ "=" ~ ident }
+value      = _{ ident }
+"#]
+struct Parser;
+
+#[test]
+fn same_as_parsed() {
+    let input = "a=bc";
+    let parsed = pairs::assign::try_parse(input).unwrap();
+
+    let lhs = <pairs::ident>::new(Span::new(input, 0, 1).unwrap());
+    let rhs = <pairs::ident>::new(Span::new(input, 2, 4).unwrap());
+    let built = <pairs::assign>::new(
+        (lhs.into(), Str::from(()).into(), rhs.into()).into(),
+        Span::new(input, 0, 4).unwrap(),
+    );
+    assert_eq!(built, parsed);
+}
+
+#[test]
+fn synthetic() {
+    let ident = <pairs::ident>::new(Span::synthetic("foo"));
+    assert_eq!(ident.span().as_str(), "foo");
+    let value = <pairs::value>::new(ident.clone());
+    assert_eq!(*value.content, ident);
+
+    let assign = <pairs::assign>::new(
+        (ident.clone().into(), Str::from(()).into(), ident.into()).into(),
+        Span::synthetic("foo=foo"),
+    );
+    let (lhs, _, rhs) = assign.get_matched();
+    assert_eq!(lhs, rhs);
+    assert_eq!(assign.span().as_str(), "foo=foo");
+}
@@ -0,0 +1,48 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use pest_typed::cache::{Cache, Cacheable};
+use pest_typed_derive::TypedParser;
+use std::{fs, sync::Arc};
+
+#[derive(TypedParser)]
+#[grammar_inline = r#"
+list = { SOI ~ item ~ ("," ~ item)* ~ EOI }
+item = @{ ASCII_ALPHA+ }
+WHITESPACE = _{ " " }
+"#]
+#[emit_cache]
+struct Parser;
+
+#[test]
+fn cached() {
+    let dir = std::env::temp_dir().join(format!("pest-typed-derive-cache-{}", std::process::id()));
+    let cache = Cache::new(&dir);
+    let input: Arc<str> = Arc::from("a, b");
+    let parsed = owned::list::parse_cached(&cache, input.clone()).unwrap();
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+    let loaded = owned::list::parse_cached(&cache, input.clone()).unwrap();
+    assert_eq!(loaded, parsed);
+    assert!(Arc::ptr_eq(loaded.text.input(), &input));
+    let items: Vec<_> = loaded.item().map(|item| item.text.as_str()).collect();
+    assert_eq!(items, ["a", "b"]);
+    assert_eq!(owned::Node::list(loaded).rule_name(), "list");
+
+    // Another rule or input has a tree of its own.
+    let item = owned::item::parse_cached(&cache, Arc::from("a")).unwrap();
+    assert_eq!(item.text, "a");
+    assert!(owned::list::parse_cached(&cache, Arc::from("a,")).is_err());
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 2);
+    assert_ne!(
+        Cache::key::<owned::Node>("list", "a, b"),
+        Cache::key::<owned::Node>("item", "a, b"),
+    );
+    cache.clear().unwrap();
+}
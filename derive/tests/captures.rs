@@ -0,0 +1,39 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use pest_typed::ParsableTypedNode;
+use pest_typed_derive::TypedParser;
+
+#[derive(TypedParser)]
+#[grammar_inline = r#"
+WHITESPACE = _{ " " }
+ident      = @{ ASCII_ALPHA+ }
+number     = @{ ASCII_DIGIT+ }
+assign     =  { key: ident ~ "=" ~ value: (ident | number) ~ ":" ~ kind: ident }
+list       =  { head: number ~ tail: ("," ~ number)* }
+"#]
+#[emit_rule_reference]
+struct Parser;
+
+#[test]
+fn captures() {
+    let assign = pairs::assign::try_parse("a = 12 : int").unwrap();
+    assert_eq!(assign.key.span.as_str(), "a");
+    assert_eq!(assign.value._1().unwrap().span.as_str(), "12");
+    assert_eq!(assign.kind.span.as_str(), "int");
+    assert_eq!(assign._1.skipped.len(), 1);
+    let (key, _, _, _, kind) = assign.get_matched();
+    assert_eq!((key.span.as_str(), kind.span.as_str()), ("a", "int"));
+    assert_eq!(assign.ident().0.span.as_str(), "a");
+
+    let list = pairs::list::try_parse("1, 2,3").unwrap();
+    assert_eq!(list.head.span.as_str(), "1");
+    assert_eq!(list.tail.content.len(), 2);
+    assert_eq!(list.number().1.len(), 2);
+}
@@ -0,0 +1,81 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use pest_typed::completion::{complete, Candidate, Completion};
+use pest_typed_derive::TypedParser;
+
+#[derive(TypedParser)]
+#[grammar_inline = r#"
+program   = { SOI ~ statement* ~ EOI }
+statement = { assign | print }
+assign    = { "let" ~ ident ~ "=" ~ value ~ ";" }
+print     = { ^"print" ~ value ~ ";" }
+value     = { ident | number }
+ident     = @{ ASCII_ALPHA+ }
+number    = @{ ASCII_DIGIT+ }
+WHITESPACE = _{ " " }
+"#]
+#[emit_rule_reference]
+struct Parser;
+
+fn candidates(input: &str) -> Vec<Completion<Rule>> {
+    complete::<Rule, rules::program>(input, input.len()).candidates
+}
+
+fn literal(text: &str, insensitive: bool, parent: Rule) -> Completion<Rule> {
+    Completion {
+        candidate: Candidate::Literal {
+            text: text.to_owned(),
+            insensitive,
+        },
+        parent: Some(parent),
+    }
+}
+
+fn rule(rule: Rule, parent: Rule) -> Completion<Rule> {
+    Completion {
+        candidate: Candidate::Rule(rule),
+        parent: Some(parent),
+    }
+}
+
+#[test]
+fn statements() {
+    assert_eq!(
+        candidates("let a = 1; "),
+        [
+            literal("let", false, Rule::assign),
+            literal("print", true, Rule::print),
+        ]
+    );
+    let completions = complete::<Rule, rules::program>("let a = 1; PR", 13);
+    assert_eq!(completions.start, 11);
+    assert_eq!(completions.prefix, "PR");
+    assert_eq!(
+        completions.candidates,
+        [literal("print", true, Rule::print)]
+    );
+    assert_eq!(
+        completions.rule_stack,
+        [Rule::program, Rule::statement, Rule::assign]
+    );
+}
+
+#[test]
+fn values() {
+    assert_eq!(
+        candidates("let a = "),
+        [
+            rule(Rule::ident, Rule::assign),
+            rule(Rule::number, Rule::assign)
+        ]
+    );
+    assert_eq!(candidates("let a "), [literal("=", false, Rule::assign)]);
+    assert!(candidates("let a 1 ").is_empty());
+}
@@ -0,0 +1,70 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use pest_typed::{
+    controller::{ControlledError, ParseController},
+    ParsableTypedNode,
+};
+use pest_typed_derive::TypedParser;
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+#[derive(TypedParser)]
+#[grammar_inline = r#"
+item  = @{ ASCII_ALPHA }
+items =  { item* }
+"#]
+#[emit_rule_reference]
+struct Parser;
+
+#[test]
+fn progress() {
+    let reports = Arc::new(AtomicUsize::new(0));
+    let counter = reports.clone();
+    let controller = Arc::new(ParseController::new().with_progress(10, move |_| {
+        counter.fetch_add(1, Ordering::Relaxed);
+    }));
+    let input = "a".repeat(100);
+    let items = pairs::items::try_parse_with_controller(&input, &controller).unwrap();
+    assert_eq!(items.item().len(), 100);
+    // 0, 10, ..., 100.
+    assert_eq!(reports.load(Ordering::Relaxed), 11);
+
+    let error = pairs::items::try_parse_with_controller("a1", &controller).unwrap_err();
+    assert!(matches!(error, ControlledError::Failed(_)));
+}
+
+#[test]
+fn cancel() {
+    let input = "a".repeat(100);
+    let controller = Arc::new(ParseController::new());
+    controller.cancel();
+    assert_eq!(
+        pairs::items::try_parse_with_controller(&input, &controller).unwrap_err(),
+        ControlledError::Cancelled
+    );
+    controller.reset();
+    assert!(pairs::items::try_parse_with_controller(&input, &controller).is_ok());
+
+    // Cancel from the progress callback.
+    let controller = Arc::new_cyclic(|weak: &std::sync::Weak<ParseController>| {
+        let weak = weak.clone();
+        ParseController::new().with_progress(10, move |pos| {
+            if let (true, Some(controller)) = (pos >= 50, weak.upgrade()) {
+                controller.cancel();
+            }
+        })
+    });
+    assert_eq!(
+        pairs::items::try_parse_with_controller(&input, &controller).unwrap_err(),
+        ControlledError::Cancelled
+    );
+}
@@ -0,0 +1,56 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use pest_typed::{coverage::Coverage, ParsableTypedNode};
+use pest_typed_derive::TypedParser;
+
+#[derive(TypedParser)]
+#[grammar_inline = r#"
+WHITESPACE = _{ " " }
+ident      = @{ ASCII_ALPHA+ }
+number     = @{ ASCII_DIGIT+ }
+string     = @{ "\"" ~ (!"\"" ~ ANY)* ~ "\"" }
+value      =  { number | ident | string }
+list       =  { "[" ~ (value ~ ("," ~ value)*)? ~ "]" }
+"#]
+struct Parser;
+
+const RULES: [Rule; 5] = [
+    Rule::ident,
+    Rule::number,
+    Rule::string,
+    Rule::value,
+    Rule::list,
+];
+
+#[test]
+fn coverage() {
+    let mut coverage = Coverage::new();
+    pairs::list::try_parse_with_coverage("[1, aB]", &mut coverage).unwrap();
+    pairs::list::try_parse_with_coverage("[2]", &mut coverage).unwrap();
+    assert!(pairs::list::try_parse_with_coverage("[2,]", &mut coverage).is_err());
+    assert_eq!(coverage.hits(Rule::number), 3);
+    assert_eq!(coverage.hits(Rule::ident), 1);
+    assert_eq!(coverage.unhit_rules(&RULES), [Rule::string]);
+
+    let alternatives = coverage.unhit_alternatives();
+    assert_eq!(alternatives.len(), 1);
+    assert_eq!(alternatives[0].rule, Some(Rule::value));
+    assert_eq!((alternatives[0].index, alternatives[0].count), (2, 3));
+    assert_eq!(
+        coverage.report(&RULES).to_string(),
+        "4 of 5 rules matched.\nRule string is never matched.\nAlternative 3 of 3 in rule value is never matched.\n"
+    );
+
+    let mut other = Coverage::new();
+    pairs::value::try_parse_with_coverage("\"s\"", &mut other).unwrap();
+    coverage.merge(&other);
+    assert!(coverage.unhit_rules(&RULES).is_empty());
+    assert!(coverage.unhit_alternatives().is_empty());
+}
@@ -0,0 +1,53 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according toAI those terms.
+
+use pest_typed::{cursor::Cursor, ParsableTypedNode as _};
+use pest_typed_derive::TypedParser;
+
+#[derive(TypedParser)]
+#[grammar_inline = r#"
+WHITESPACE = _{ " " }
+header     =  { "[" ~ name ~ "]" }
+name       = @{ ASCII_ALPHA+ }
+"#]
+#[emit_rule_reference]
+struct Parser;
+
+#[test]
+fn partial() {
+    let (end, header) = rules::header::try_parse_partial("[ a ] rest").unwrap();
+    assert_eq!(header.name().span.as_str(), "a");
+    assert_eq!(end.rest(), " rest");
+}
+
+#[test]
+fn hybrid() {
+    // Sections are parsed by hand, and headers with generated rules.
+    let mut cursor = Cursor::<Rule>::new("[a]\n1\n2\n[b]\n3\n");
+    let mut sections = vec![];
+    while let Some(header) = cursor.parse::<rules::header>() {
+        let start = cursor.position();
+        loop {
+            let checkpoint = cursor.checkpoint();
+            if cursor.position_mut().match_string("\n[") || cursor.is_at_end() {
+                cursor.rewind(checkpoint);
+                break;
+            }
+            cursor.commit(checkpoint);
+            cursor.position_mut().skip(1);
+        }
+        sections.push((
+            header.name().span.as_str(),
+            start.span(&cursor.position()).as_str(),
+        ));
+        cursor.position_mut().match_string("\n");
+    }
+    assert_eq!(sections, [("a", "\n1\n2"), ("b", "\n3\n")]);
+    assert!(cursor.is_at_end());
+}
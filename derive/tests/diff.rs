@@ -0,0 +1,73 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use pest_typed::{diff::diff, ParsableTypedNode};
+use pest_typed_derive::TypedParser;
+
+#[derive(TypedParser)]
+#[grammar_inline = r#"
+WHITESPACE = _{ " " }
+ident      = @{ ASCII_ALPHA+ }
+number     = @{ ASCII_DIGIT+ }
+value      =  { ident | number }
+assign     =  { ident ~ "=" ~ value ~ ";" }
+program    =  { assign* }
+"#]
+struct Parser;
+
+fn differences(left: &str, right: &str) -> Vec<(&'static str, String, String)> {
+    let left = pairs::program::try_parse(left).unwrap();
+    let right = pairs::program::try_parse(right).unwrap();
+    diff(&left, &right)
+        .into_iter()
+        .map(|d| {
+            let kind = match (d.left_rules.is_empty(), d.right_rules.is_empty()) {
+                (true, _) => "inserted",
+                (_, true) => "removed",
+                _ => "changed",
+            };
+            (
+                kind,
+                d.left.as_str().to_owned(),
+                d.right.as_str().to_owned(),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn differences_in_source_order() {
+    assert!(differences("a = 1;", "a  =  1 ;").is_empty());
+    assert_eq!(
+        differences("a = 1; b = 2;", "a = x; b = 3;"),
+        [
+            ("changed", "1".to_owned(), "x".to_owned()),
+            ("changed", "2".to_owned(), "3".to_owned()),
+        ]
+    );
+    assert_eq!(
+        differences("a = 1; c = 3;", "a = 1; b = 2; c = 3;"),
+        [("inserted", String::new(), "b = 2;".to_owned())]
+    );
+    assert_eq!(
+        differences("a = 1; b = 2;", "b = 2;"),
+        [("removed", "a = 1;".to_owned(), String::new())]
+    );
+}
+
+#[test]
+fn spans_in_both_inputs() {
+    let left = pairs::program::try_parse("a = 1;").unwrap();
+    let right = pairs::program::try_parse("a = 1; b = 2;").unwrap();
+    let differences = diff(&left, &right);
+    assert_eq!(differences.len(), 1);
+    assert_eq!(differences[0].left.start(), 6);
+    assert_eq!(differences[0].right.start(), 7);
+    assert_eq!(differences[0].right_rules, [Rule::assign]);
+}
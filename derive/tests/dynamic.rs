@@ -0,0 +1,48 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use pest_typed_derive::TypedParser;
+
+#[derive(TypedParser)]
+#[grammar_inline = r#"
+expr   = { term ~ ("+" ~ term)* }
+term   = _{ number | "(" ~ expr ~ ")" }
+number = @{ ASCII_DIGIT+ }
+WHITESPACE = _{ " " }
+"#]
+#[emit_dyn]
+struct Parser;
+
+#[test]
+fn entry_points() {
+    assert_eq!(DYN_RULES, ["expr", "number"]);
+
+    let expr = parse_dyn("expr", "1 + (2 + 3)").unwrap();
+    assert_eq!(expr.rule(), Rule::expr);
+    assert_eq!(expr.as_str(), "1 + (2 + 3)");
+    let numbers: Vec<_> = expr
+        .children_of(Rule::number)
+        .map(|number| number.as_str())
+        .collect();
+    assert_eq!(numbers, ["1"]);
+    assert_eq!(expr.children()[1].span().start(), 5);
+    assert_eq!(expr.descendants().len(), 5);
+    assert_eq!(
+        format!("{expr:?}"),
+        "expr@0..11[number@0..1, expr@5..10[number@5..6, number@9..10]]"
+    );
+
+    let number = parse_dyn("number", "42").unwrap();
+    assert_eq!(number.rule(), Rule::number);
+    assert!(number.children().is_empty());
+    assert!(parse_dyn("number", "1 + 2").is_err());
+
+    let error = parse_dyn("term", "1").unwrap_err();
+    assert_eq!(error.variant.message(), "Rule term is not defined.");
+}
@@ -0,0 +1,72 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use pest_typed::{embed::Delegate, iterators::Pair as _, ParsableTypedNode, Span};
+use pest_typed_derive::TypedParser;
+
+mod object {
+    use pest_typed_derive::TypedParser;
+
+    #[derive(TypedParser)]
+    #[grammar_inline = r#"
+    WHITESPACE = _{ " " }
+    object     =  { "{" ~ (pair ~ ("," ~ pair)*)? ~ "}" }
+    pair       =  { key ~ ":" ~ number }
+    key        = @{ ASCII_ALPHA+ }
+    number     = @{ ASCII_DIGIT+ }
+    "#]
+    #[emit_rule_reference]
+    pub struct Parser;
+}
+
+/// Splits a region into words.
+pub struct Words;
+
+impl<'i> Delegate<'i> for Words {
+    type Output = Vec<&'i str>;
+    fn parse_region(span: Span<'i>) -> Option<Self::Output> {
+        let inner = span.as_str().strip_prefix('<')?.strip_suffix('>')?;
+        Some(inner.split_whitespace().collect())
+    }
+}
+
+#[derive(TypedParser)]
+#[grammar_inline = r#"
+text  = @{ (!("{" | "<") ~ ANY)+ }
+#[embed = "pest_typed::embed::Grammar<crate::object::Rule, crate::object::pairs::object<'i>>"]
+block = @{ "{" ~ (!"}" ~ ANY)* ~ "}" }
+#[embed = "crate::Words"]
+words =  { "<" ~ (!">" ~ ANY)* ~ ">" }
+doc   =  { SOI ~ text ~ block ~ text ~ words ~ EOI }
+"#]
+#[emit_rule_reference]
+struct Parser;
+
+#[test]
+fn embed() {
+    let input = "a {x: 1, y: 22} b <c d>";
+    let doc = pairs::doc::try_parse(input).unwrap();
+    let block = doc.block();
+    assert_eq!(block.content.span().as_str(), "{x: 1, y: 22}");
+    let (first, rest) = block.content.pair().unwrap();
+    assert_eq!(first.key().span.as_str(), "x");
+    // Spans of foreign nodes point into the whole input.
+    assert_eq!(rest[0].number().span.start(), 12);
+    // Foreign pairs have another rule type, so they are not iterated over.
+    assert_eq!(block.inner().count(), 0);
+
+    assert_eq!(*doc.words().content.get(), ["c", "d"]);
+}
+
+#[test]
+fn failed() {
+    // Regions that the delegate can't parse fail the host rule.
+    assert!(pairs::doc::try_parse("a {x} b <c>").is_err());
+    assert!(pairs::doc::try_parse("a {x: 1 y: 2} b <c>").is_err());
+}
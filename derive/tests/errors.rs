@@ -0,0 +1,83 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use pest_typed::{controller::ParseController, tracker::SpecialError, ParsableTypedNode};
+use pest_typed_derive::TypedParser;
+use std::sync::Arc;
+
+#[derive(TypedParser)]
+#[grammar_inline = r#"
+item  = @{ ASCII_ALPHA }
+items =  { item+ }
+"#]
+struct Parser;
+
+fn parse_with_context(input: &str) -> anyhow::Result<usize> {
+    Ok(pairs::items::try_parse_with_context(input)?
+        .span
+        .as_str()
+        .len())
+}
+
+fn parse_with_controller(input: &str, controller: &Arc<ParseController>) -> anyhow::Result<usize> {
+    Ok(pairs::items::try_parse_with_controller(input, controller)?
+        .span
+        .as_str()
+        .len())
+}
+
+#[test]
+fn std_errors() {
+    assert_eq!(parse_with_context("ab").unwrap(), 2);
+    let error = parse_with_context("a1").unwrap_err();
+    assert!(error.to_string().starts_with(" --> 1:2"), "{}", error);
+
+    let controller = Arc::new(ParseController::new());
+    controller.cancel();
+    let error = parse_with_controller("ab", &controller).unwrap_err();
+    assert_eq!(error.to_string(), "Parsing cancelled.");
+
+    let error: Box<dyn std::error::Error> = Box::new(SpecialError::EmptyStack);
+    assert_eq!(error.to_string(), "Nothing to pop or drop.");
+}
+
+fn parse_with_report(input: &str) -> miette::Result<usize> {
+    use pest_typed::report::WithSource;
+    Ok(pairs::items::try_parse_with_context(input)
+        .with_source(input)?
+        .span
+        .as_str()
+        .len())
+}
+
+#[test]
+fn miette_reports() {
+    use miette::{NarratableReportHandler, ReportHandler};
+
+    assert_eq!(parse_with_report("ab").unwrap(), 2);
+    let report = parse_with_report("a1").unwrap_err();
+    struct Narrated<'r>(&'r miette::Report);
+    impl std::fmt::Display for Narrated<'_> {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            NarratableReportHandler::new().debug(self.0.as_ref(), f)
+        }
+    }
+    let narrated = Narrated(&report).to_string();
+    assert!(narrated.contains("snippet line 1: a1"), "{}", narrated);
+    assert!(
+        narrated.contains("label at line 1, column 2"),
+        "{}",
+        narrated
+    );
+    assert!(
+        narrated.contains("diagnostic help: while parsing items > item"),
+        "{}",
+        narrated
+    );
+}
@@ -0,0 +1,92 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use pest_typed::{events::Event, ParsableTypedNode as _};
+use pest_typed_derive::TypedParser;
+
+#[derive(TypedParser)]
+#[grammar_inline = r##"
+WHITESPACE = _{ " " }
+COMMENT    = _{ "#" ~ (!"\n" ~ ANY)* }
+number     = @{ ASCII_DIGIT+ }
+name       = @{ ASCII_ALPHA+ }
+value      = _{ number | name }
+call       =  { name ~ "(" ~ (value ~ ("," ~ value)*)? ~ ")" }
+list       =  { SOI ~ (call | value)* ~ EOI }
+"##]
+#[emit_events]
+struct Parser;
+
+fn collect(input: &str) -> Result<Vec<String>, pest_typed::error::Error<Rule>> {
+    let mut res = vec![];
+    events::parse(Rule::list, input, &mut |event| {
+        res.push(match event {
+            Event::Enter(rule, pos) => format!("{:?}@{}", rule, pos.pos()),
+            Event::Exit(rule, span) => format!("/{:?} {:?}", rule, span.as_str()),
+            Event::Token(span) => format!("{:?}", span.as_str()),
+        })
+    })?;
+    Ok(res)
+}
+
+#[test]
+fn order() {
+    assert_eq!(
+        collect("f(1, x) 2 # c").unwrap(),
+        [
+            "list@0",
+            "call@0",
+            "name@0",
+            "/name \"f\"",
+            "\"(\"",
+            "number@2",
+            "/number \"1\"",
+            "\",\"",
+            "name@5",
+            "/name \"x\"",
+            "\")\"",
+            "/call \"f(1, x)\"",
+            "number@8",
+            "/number \"2\"",
+            "EOI@13",
+            "/EOI \"\"",
+            "/list \"f(1, x) 2 # c\"",
+        ]
+    );
+}
+
+#[test]
+fn backtracking() {
+    // `g` is tried as a call first, whose events are dropped.
+    assert_eq!(
+        collect("g").unwrap(),
+        [
+            "list@0",
+            "name@0",
+            "/name \"g\"",
+            "EOI@1",
+            "/EOI \"\"",
+            "/list \"g\""
+        ]
+    );
+}
+
+#[test]
+fn errors() {
+    // Errors are the same as those of the typed parser.
+    let error = collect("f(1,)").unwrap_err();
+    let expected = pairs::list::try_parse("f(1,)").unwrap_err();
+    assert_eq!(
+        error.line_col,
+        pest_typed::error::LineColLocation::Pos((1, 5))
+    );
+    assert_eq!(error.to_string(), expected.to_string());
+    assert!(events::parse(Rule::number, "12a", &mut |_| ()).is_err());
+    assert!(events::parse(Rule::number, "12", &mut |_| ()).is_ok());
+}
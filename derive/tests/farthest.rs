@@ -0,0 +1,112 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use pest_typed::ParsableTypedNode;
+use pest_typed_derive::TypedParser;
+
+#[derive(TypedParser)]
+#[grammar_inline = r#"
+WHITESPACE = _{ " " }
+ident      = @{ ASCII_ALPHA+ }
+call       =  { ident ~ "(" ~ ident ~ ")" }
+index      =  { ident ~ ^"at" ~ ident }
+expr       =  { call | index | ident }
+stmt       =  { expr ~ ";" }
+"#]
+struct Parser;
+
+#[test]
+fn farthest() {
+    // `call` fails after backtracking to `ident`, so the farthest failure is reported.
+    let error = pairs::stmt::try_parse("f(a;").unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        [
+            " --> 1:4",
+            "  |",
+            "1 | f(a;",
+            "  |    ^---",
+            "  |",
+            "  = f(a^---",
+            "    Expected [\")\"], by call.",
+        ]
+        .join("\n")
+    );
+    let error = pairs::stmt::try_parse("f ").unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        [
+            " --> 1:3",
+            "  |",
+            "1 | f ",
+            "  |   ^---",
+            "  |",
+            "  = f ^---",
+            "    Expected [\"(\"], by call.",
+            "    Expected [^\"at\"], by index.",
+            "    Expected [\";\"], by stmt.",
+        ]
+        .join("\n")
+    );
+}
+
+#[test]
+fn prefix() {
+    use pest_typed::iterators::Token;
+    let token = |rule, start, end, children| Token {
+        rule,
+        start,
+        end,
+        children,
+    };
+    let error = pairs::stmt::try_parse_with_prefix("f(a;", 8).unwrap_err();
+    let prefix = error.prefix().unwrap();
+    assert_eq!(prefix.end, 3);
+    assert_eq!(
+        prefix.roots,
+        [token(
+            Rule::stmt,
+            0,
+            3,
+            vec![token(
+                Rule::expr,
+                0,
+                3,
+                vec![token(
+                    Rule::call,
+                    0,
+                    3,
+                    vec![
+                        token(Rule::ident, 0, 1, vec![]),
+                        token(Rule::ident, 2, 3, vec![]),
+                    ]
+                )]
+            )]
+        )]
+    );
+
+    // Rules nested deeper than the limit are left out.
+    let error = pairs::stmt::try_parse_with_prefix("f(a;", 2).unwrap_err();
+    let prefix = error.prefix().unwrap();
+    assert_eq!(prefix.roots[0].children[0].rule, Rule::expr);
+    assert!(prefix.roots[0].children[0].children.is_empty());
+
+    // Kept after `call` fails and `index` is attempted.
+    let error = pairs::stmt::try_parse_with_prefix("f ", 8).unwrap_err();
+    let call = &error.prefix().unwrap().roots[0].children[0].children[0];
+    assert_eq!(
+        call,
+        &token(Rule::call, 0, 2, vec![token(Rule::ident, 0, 1, vec![])])
+    );
+
+    assert!(pairs::stmt::try_parse_with_context("f(a;")
+        .unwrap_err()
+        .prefix()
+        .is_none());
+}
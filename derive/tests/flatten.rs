@@ -0,0 +1,44 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use pest_typed::{iterators::Pairs, ParsableTypedNode, RuleStruct};
+use pest_typed_derive::TypedParser;
+
+#[derive(TypedParser)]
+#[grammar_inline = r#"
+WHITESPACE = _{ " " }
+number     = @{ ASCII_DIGIT+ }
+value      =  { number }
+item       = _{ value }
+atom       = @{ number }
+list       =  { item ~ ("," ~ item)* ~ ";" ~ atom }
+"#]
+#[emit_rule_reference]
+#[flatten_wrappers]
+struct Parser;
+
+#[test]
+fn flattened() {
+    let list = pairs::list::try_parse("1, 23 ; 4").unwrap();
+    // `item` and `value` are both flattened into `number`.
+    let (first, following) = list.number();
+    let numbers: Vec<&pairs::number<'_>> = core::iter::once(first).chain(following).collect();
+    let numbers: Vec<&str> = numbers.iter().map(|n| n.span().as_str()).collect();
+    assert_eq!(numbers, ["1", "23"]);
+    // Atomic rules are kept.
+    assert_eq!(list.atom().span().as_str(), "4");
+    let rules: Vec<Rule> = list.content.iter_pairs().map(|pair| pair.rule()).collect();
+    assert_eq!(rules, [Rule::number, Rule::number, Rule::atom]);
+
+    // Wrappers can still be parsed on their own.
+    let value = pairs::value::try_parse("5").unwrap();
+    assert_eq!(value.content.span().as_str(), "5");
+    let item: &pairs::number<'_> = &pairs::item::try_parse("6").unwrap().content;
+    assert_eq!(item.span().as_str(), "6");
+}
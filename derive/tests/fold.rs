@@ -0,0 +1,70 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use pest_typed::{fold::Foldable, ParsableTypedNode, RuleStruct, Span};
+use pest_typed_derive::TypedParser;
+
+#[derive(TypedParser)]
+#[grammar_inline = r#"
+WHITESPACE = _{ " " }
+num        = @{ ASCII_DIGIT+ }
+neg        =  { "-" ~ num }
+term       = _{ neg | num }
+sum        =  { term ~ ("+" ~ term)* }
+"#]
+#[emit_fold]
+struct Parser;
+
+/// Replace every number with `0` and count negations.
+#[derive(Default)]
+struct Zero {
+    negations: usize,
+}
+impl<'i> fold::Fold<'i> for Zero {
+    fn fold_num<const INHERITED: usize>(
+        &mut self,
+        _node: pairs::num<'i, INHERITED>,
+    ) -> pairs::num<'i, INHERITED> {
+        pairs::num::new(Span::synthetic("0"))
+    }
+    fn fold_neg<const INHERITED: usize>(
+        &mut self,
+        node: pairs::neg<'i, INHERITED>,
+    ) -> pairs::neg<'i, INHERITED> {
+        self.negations += 1;
+        node.fold_children(self)
+    }
+}
+
+#[test]
+fn rewrite() {
+    let sum = pairs::sum::try_parse("1 + -23 + 456 + -7").unwrap();
+    let span = sum.span();
+    let mut zero = Zero::default();
+    let sum = sum.fold_with(&mut zero);
+    assert_eq!(zero.negations, 2);
+    assert_eq!(sum.span(), span);
+
+    let show = |term: &pairs::term<'_>| match term.content.as_ref() {
+        generics::Choice2::_0(neg) => format!("-{}", neg.get_matched().1.span().as_str()),
+        generics::Choice2::_1(num) => num.span().as_str().to_owned(),
+    };
+    let (first, rest) = sum.get_matched();
+    let mut terms = vec![show(first)];
+    terms.extend(rest.iter_matched().map(|term| show(term.get_matched().1)));
+    assert_eq!(terms, ["0", "-0", "0", "-0"]);
+}
+
+#[test]
+fn identity() {
+    struct Id;
+    impl<'i> fold::Fold<'i> for Id {}
+    let sum = pairs::sum::try_parse("1+2").unwrap();
+    assert_eq!(sum.clone().fold_with(&mut Id), sum);
+}
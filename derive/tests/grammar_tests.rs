@@ -0,0 +1,60 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use pest_typed::ParsableTypedNode as _;
+
+/// Test cases are checked when the parser is generated.
+mod build_time {
+    use pest_typed_derive::TypedParser;
+
+    #[derive(TypedParser)]
+    #[grammar_inline = r#"
+WHITESPACE = _{ " " }
+//@ accept "1 + 2 * 3"
+//@ accept "(1)"
+//@ reject "1 +"
+expr = { term ~ (("+" | "*") ~ term)* }
+//@ accept "12"
+//@ reject "1 2"
+#[highlight = "number"]
+term = @{ ASCII_DIGIT+ | "(" ~ expr ~ ")" }
+//@ accept "\u{4e00} \t"
+chars = @{ (!"\t" ~ ANY)* ~ "\t" }
+"#]
+    #[grammar_tests]
+    #[emit_rule_reference]
+    pub struct Parser;
+}
+
+/// Test cases run as unit tests, so that rule attributes apply to them as well.
+mod unit {
+    use pest_typed_derive::TypedParser;
+
+    #[derive(TypedParser)]
+    #[grammar_inline = r#"
+WHITESPACE = _{ " " }
+term = @{ ASCII_DIGIT+ }
+//@ accept "1,2"
+//@ reject "1 , 2"
+#[trivia = ""]
+pair = { term ~ "," ~ term }
+"#]
+    #[grammar_unit_tests]
+    #[emit_rule_reference]
+    pub struct Parser;
+}
+
+#[test]
+fn checked() {
+    // Checked at build time, and agree with typed parsers.
+    assert!(build_time::rules::expr::try_parse("1 + 2 * 3").is_ok());
+    assert!(build_time::rules::expr::try_parse("1 +").is_err());
+    // Unit tests in `unit::grammar_tests`, such as `pair_reject_1`, run with the other tests.
+    assert!(unit::rules::pair::try_parse("1 , 2").is_err());
+}
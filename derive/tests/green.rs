@@ -0,0 +1,95 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use pest_typed::{
+    green::{AstNode, GreenCache, SyntaxNode},
+    ParsableTypedNode as _,
+};
+use pest_typed_derive::TypedParser;
+use std::sync::Arc;
+
+#[derive(TypedParser)]
+#[grammar_inline = r#"
+list  = { "[" ~ (item ~ ("," ~ item)*)? ~ "]" }
+item  = _{ list | atom }
+atom  = @{ ASCII_ALPHA+ }
+WHITESPACE = _{ " " }
+"#]
+#[emit_syntax]
+struct Parser;
+
+#[test]
+fn typed() {
+    let mut cache = GreenCache::new();
+    let input = "[a, [b,  c], d]";
+    let tree = pairs::list::try_parse(input).unwrap();
+    let green = cache.build(&tree);
+    assert_eq!(green.text(), input);
+    let root = syntax::list::cast(SyntaxNode::new_root(green)).unwrap();
+    let atoms: Vec<_> = root.atom().map(|atom| atom.syntax().text()).collect();
+    assert_eq!(atoms, ["a", "d"]);
+    let inner = root.list().next().unwrap();
+    assert_eq!(inner.syntax().text_range(), 4..11);
+    let c = inner.atom().nth(1).unwrap();
+    assert_eq!(&input[c.syntax().text_range()], "c");
+    assert_eq!(c.syntax().parent(), Some(inner.syntax().clone()));
+    assert_eq!(c.syntax().ancestors().count(), 2);
+    assert!(syntax::atom::cast(inner.syntax().clone()).is_none());
+    assert_eq!(
+        root.syntax().covering_node(9).map(|node| node.rule()),
+        Some(Rule::atom)
+    );
+    assert_eq!(root.syntax().descendants().count(), 6);
+}
+
+#[test]
+fn sharing() {
+    let mut cache = GreenCache::new();
+    let before = pairs::list::try_parse("[[a, b], c]").unwrap();
+    let before = cache.build(&before);
+    let after = pairs::list::try_parse("[[a, b], c, d]").unwrap();
+    let after = cache.build(&after);
+    assert!(!Arc::ptr_eq(&before, &after));
+    let first = |green: &Arc<pest_typed::green::GreenNode<Rule>>| {
+        SyntaxNode::new_root(green.clone())
+            .children()
+            .next()
+            .unwrap()
+            .green()
+            .clone()
+    };
+    assert!(Arc::ptr_eq(&first(&before), &first(&after)));
+}
+
+#[test]
+fn repetition() {
+    let input = format!("[{}]", vec!["[ab, cd]"; 1000].join(", "));
+    let tree = pairs::list::try_parse(&input).unwrap();
+
+    let mut cache = GreenCache::new();
+    let all = cache.build(&tree);
+    let sharing = cache.sharing();
+    // The root, the repeated list, and its two atoms.
+    assert_eq!(sharing.nodes, 1 + 1000 * 3);
+    assert_eq!(sharing.unique_nodes, 4);
+    // Most of what is allocated is the children of the root.
+    assert!(sharing.saved_bytes > 10 * sharing.allocated_bytes);
+
+    let mut leaves = GreenCache::leaves();
+    let shared = leaves.build(&tree);
+    let sharing = leaves.sharing();
+    assert_eq!(sharing.unique_nodes, 1 + 1000 + 2);
+    assert!(sharing.saved_bytes > sharing.allocated_bytes);
+    assert_eq!(shared, all);
+
+    let lists: Vec<_> = SyntaxNode::new_root(shared).children().collect();
+    assert!(!Arc::ptr_eq(lists[0].green(), lists[1].green()));
+    let atom = |list: &SyntaxNode<Rule>| list.children().next().unwrap().green().clone();
+    assert!(Arc::ptr_eq(&atom(&lists[0]), &atom(&lists[1])));
+}
@@ -0,0 +1,38 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use pest_typed::{highlight::Highlight, iterators::PairTree, ParsableTypedNode};
+use pest_typed_derive::TypedParser;
+
+#[derive(TypedParser)]
+#[grammar_inline = r#"
+WHITESPACE = _{ " " }
+#[highlight = "keyword"]
+keyword    = @{ "let" }
+ident      = @{ ASCII_ALPHA+ }
+#[highlight = "number"]
+number     = @{ ASCII_DIGIT+ ~ ("." ~ digits)? }
+digits     = @{ ASCII_DIGIT+ }
+statement  =  { keyword ~ ident ~ "=" ~ number }
+"#]
+struct Parser;
+
+#[test]
+fn highlights() {
+    assert_eq!(Rule::keyword.highlight(), Some("keyword"));
+    assert_eq!(Rule::ident.highlight(), None);
+    let statement = pairs::statement::try_parse("let x = 1.5").unwrap();
+    let ranges = statement
+        .highlights()
+        .into_iter()
+        .map(|range| (range.class, range.span.as_str()))
+        .collect::<Vec<_>>();
+    // Inner nodes of highlighted nodes are not visited.
+    assert_eq!(ranges, [("keyword", "let"), ("number", "1.5")]);
+}
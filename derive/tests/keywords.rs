@@ -0,0 +1,50 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use pest_typed::keyword::{Keyword, Keywords};
+use pest_typed_derive::TypedParser;
+
+#[derive(TypedParser)]
+#[grammar_inline = r#"
+#[keyword]
+control = { "if" | "else" | "while" | "if" }
+#[highlight = "keyword"]
+sql     = { ^"SELECT" ~ ^"From" }
+#[highlight = "string"]
+string  = { "\"" ~ (!"\"" ~ ANY)* ~ "\"" }
+ident   = @{ ASCII_ALPHA+ }
+"#]
+#[emit_rule_reference]
+struct Parser;
+
+#[test]
+fn keywords() {
+    let texts: Vec<_> = Rule::keywords()
+        .iter()
+        .map(|keyword| keyword.text)
+        .collect();
+    // Duplicates are removed, and cases are preserved.
+    assert_eq!(texts, ["if", "else", "while", "SELECT", "From"]);
+    assert_eq!(
+        Rule::keyword("select"),
+        Some(&Keyword {
+            text: "SELECT",
+            rule: Rule::sql,
+            insensitive: true,
+        }),
+    );
+    assert_eq!(Rule::keyword("If"), None);
+    assert_eq!(Rule::keyword("else").unwrap().rule, Rule::control);
+
+    let completions: Vec<_> = Rule::keywords_with_prefix("f")
+        .chain(Rule::keywords_with_prefix("wh"))
+        .map(|keyword| keyword.text)
+        .collect();
+    assert_eq!(completions, ["From", "while"]);
+}
@@ -0,0 +1,42 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use pest_typed::{iterators::Pair, ParsableTypedNode};
+use pest_typed_derive::TypedParser;
+
+#[derive(TypedParser)]
+#[grammar_inline = r#"
+WHITESPACE = _{ " " }
+name       = @{ ASCII_ALPHA+ }
+item       = @{ ASCII_DIGIT+ }
+#[lazy]
+body       =  { "{" ~ item* ~ "}" }
+section    =  { name ~ body }
+file       =  { section* }
+"#]
+#[emit_rule_reference]
+struct Parser;
+
+#[test]
+fn lazy() {
+    let file = pairs::file::try_parse("a { 1 2 } b { 3 }").unwrap();
+    let sections = file.section();
+    let (_, body) = sections[1].get_matched();
+    assert!(!body.content.is_parsed());
+    // Accessers build the subtree on first access.
+    let items: Vec<_> = body.item().iter().map(|item| item.span.as_str()).collect();
+    assert_eq!(items, ["3"]);
+    assert!(body.content.is_parsed());
+
+    let (_, body) = sections[0].get_matched();
+    assert_eq!(body.content.span().as_str(), "{ 1 2 }");
+    assert_eq!(body.inner().count(), 2);
+    // Errors inside lazy rules are still reported when parsing.
+    assert!(pairs::file::try_parse("a { 1 x }").is_err());
+}
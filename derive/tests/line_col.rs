@@ -0,0 +1,48 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use pest_typed::{line_index::LineIndex, ParsableTypedNode, RuleStruct};
+use pest_typed_derive::TypedParser;
+
+#[derive(TypedParser)]
+#[grammar_inline = r#"
+WHITESPACE = _{ " " | "\n" }
+item       = @{ (!WHITESPACE ~ ANY)+ }
+list       =  { item* }
+"#]
+#[emit_rule_reference]
+struct Parser;
+
+#[test]
+fn line_col_range() {
+    let input = "ab\n  cd\n嗨 ef";
+    let list = pairs::list::try_parse(input).unwrap();
+    let index = LineIndex::new(input);
+    let ranges: Vec<_> = list
+        .item()
+        .iter()
+        .map(|item| item.line_col_range(&index))
+        .collect();
+    assert_eq!(
+        ranges,
+        [
+            ((1, 1), (1, 3)),
+            ((2, 3), (2, 5)),
+            ((3, 1), (3, 2)),
+            ((3, 3), (3, 5)),
+        ],
+    );
+    assert_eq!(list.line_col_range(&index), ((1, 1), (3, 5)));
+    for item in list.item() {
+        assert_eq!(
+            item.span().start_pos().line_col(),
+            item.line_col_range(&index).0
+        );
+    }
+}
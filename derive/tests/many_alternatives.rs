@@ -0,0 +1,44 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use pest_typed::ParsableTypedNode;
+use pest_typed_derive::TypedParser;
+
+#[derive(TypedParser)]
+#[grammar_inline = r#"
+tail = { "tail" }
+keyword = {
+    "k000" | "k001" | "k002" | "k003" | "k004" | "k005" | "k006" | "k007" | "k008" | "k009" |
+    "k010" | "k011" | "k012" | "k013" | "k014" | "k015" | "k016" | "k017" | "k018" | "k019" |
+    "k020" | "k021" | "k022" | "k023" | "k024" | "k025" | "k026" | "k027" | "k028" | "k029" |
+    "k030" | "k031" | "k032" | "k033" | "k034" | "k035" | "k036" | "k037" | "k038" | "k039" |
+    "k040" | "k041" | "k042" | "k043" | "k044" | "k045" | "k046" | "k047" | "k048" | "k049" |
+    "k050" | "k051" | "k052" | "k053" | "k054" | "k055" | "k056" | "k057" | "k058" | "k059" |
+    "k060" | "k061" | "k062" | "k063" | "k064" | "k065" | "k066" | "k067" | "k068" | "k069" |
+    "k070" | "k071" | "k072" | "k073" | "k074" | "k075" | "k076" | "k077" | "k078" | "k079" |
+    "k080" | "k081" | "k082" | "k083" | "k084" | "k085" | "k086" | "k087" | "k088" | "k089" |
+    "k090" | "k091" | "k092" | "k093" | "k094" | "k095" | "k096" | "k097" | "k098" | "k099" |
+    tail
+}
+"#]
+#[emit_rule_reference]
+struct Parser;
+
+#[test]
+fn many_alternatives() {
+    for i in [0, 63, 64, 99] {
+        let input = format!("k{i:03}");
+        let keyword = pairs::keyword::try_parse(&input).unwrap();
+        assert_eq!(keyword.span.as_str(), input);
+        assert!(keyword.tail().is_none());
+    }
+    // Accessers go through nested choices.
+    let keyword = pairs::keyword::try_parse("tail").unwrap();
+    assert_eq!(keyword.tail().unwrap().span.as_str(), "tail");
+}
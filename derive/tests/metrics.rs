@@ -0,0 +1,43 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use pest_typed::{metrics::metrics, ParsableTypedNode as _};
+use pest_typed_derive::TypedParser;
+
+#[derive(TypedParser)]
+#[grammar_inline = r#"
+WHITESPACE = _{ " " }
+item       = @{ ASCII_ALPHA+ }
+list       =  { "[" ~ (element ~ ("," ~ element)*)? ~ "]" }
+element    = _{ item | list }
+"#]
+#[emit_rule_reference]
+struct Parser;
+
+#[test]
+fn nested() {
+    let list = pairs::list::try_parse("[a, [b, [c]], d]").unwrap();
+    let metrics = metrics(&list);
+    assert_eq!(metrics.nodes[&Rule::list], 3);
+    assert_eq!(metrics.nodes[&Rule::item], 4);
+    assert_eq!(metrics.total_nodes(), 7);
+    assert_eq!(metrics.max_depth, 4);
+    assert!(metrics.heap_bytes > 0);
+}
+
+#[test]
+fn grows_with_input() {
+    let short = pairs::list::try_parse("[a]").unwrap();
+    let long = pairs::list::try_parse("[a, b, c, d, e, f]").unwrap();
+    assert!(metrics(&short).heap_bytes < metrics(&long).heap_bytes);
+    assert_eq!(
+        metrics(&pairs::item::try_parse("abc").unwrap()).heap_bytes,
+        0
+    );
+}
@@ -0,0 +1,37 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use pest_typed::{fold::Foldable, ParsableTypedNode};
+use pest_typed_derive::TypedParser;
+
+#[derive(TypedParser)]
+#[grammar_inline = r#"
+WHITESPACE = _{ " " }
+key        = @{ ASCII_ALPHA+ }
+pair       =  { key ~ "=" ~ key }
+pairs      =  { pair ~ ("," ~ pair)* }
+"#]
+#[emit_rule_reference]
+#[emit_fold]
+#[module_per_rule]
+#[report_code_size]
+struct Parser;
+
+struct Id;
+impl<'i> fold::Fold<'i> for Id {}
+
+#[test]
+fn layout() {
+    let pairs: nodes::pairs::pairs<'_> = pairs::pairs::try_parse("a = b, c = d").unwrap();
+    let (first, following) = pairs.pair();
+    let (lhs, rhs) = first.key();
+    assert_eq!((lhs.span.as_str(), rhs.span.as_str()), ("a", "b"));
+    assert_eq!(following.len(), 1);
+    assert_eq!(pairs.clone().fold_with(&mut Id), pairs);
+}
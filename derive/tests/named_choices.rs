@@ -0,0 +1,56 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use pest_typed::ParsableTypedNode;
+use pest_typed_derive::TypedParser;
+
+#[derive(TypedParser)]
+#[grammar_inline = r#"
+WHITESPACE = _{ " " }
+number     = @{ ASCII_DIGIT+ }
+string     = @{ "\"" ~ (!"\"" ~ ANY)* ~ "\"" }
+type       = @{ "int" | "str" }
+value      =  { number | string | type }
+pair       =  { (number | string) ~ ":" ~ value ~ ("," | ";") }
+"#]
+#[emit_rule_reference]
+#[named_choices]
+struct Parser;
+
+fn show(value: &pairs::value<'_>) -> String {
+    match &*value.content {
+        generics::Choice_number_string_type::number(number) => {
+            format!("number {}", number.span.as_str())
+        }
+        generics::Choice_number_string_type::string(string) => {
+            format!("string {}", string.span.as_str())
+        }
+        generics::Choice_number_string_type::r#type(ty) => format!("type {}", ty.span.as_str()),
+    }
+}
+
+#[test]
+fn named() {
+    assert_eq!(show(&pairs::value::try_parse("12").unwrap()), "number 12");
+    assert_eq!(
+        show(&pairs::value::try_parse("\"a\"").unwrap()),
+        "string \"a\""
+    );
+    assert_eq!(show(&pairs::value::try_parse("int").unwrap()), "type int");
+
+    let pair = pairs::pair::try_parse("\"k\" : 1 ;").unwrap();
+    let (key, _, _, _) = pair.get_matched();
+    assert!(key.number().is_none());
+    assert_eq!(key.string().unwrap().span.as_str(), "\"k\"");
+    // Accesser functions go through variants by name.
+    assert_eq!(pair.string().unwrap().span.as_str(), "\"k\"");
+    // Other choices are still positional.
+    let (_, _, _, end) = pair.get_matched();
+    assert!(end._1().is_some());
+}
@@ -0,0 +1,44 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use pest::error::InputLocation;
+use pest_typed::ParsableTypedNode;
+use pest_typed_derive::TypedParser;
+
+#[derive(TypedParser)]
+#[grammar_inline = r#"
+WHITESPACE = _{ " " }
+keyword    = @{ ("let" | "fn") ~ !ASCII_ALPHA }
+ident      = @{ !keyword ~ ASCII_ALPHA+ }
+assign     =  { ident ~ "=" ~ ident }
+"#]
+struct Parser;
+
+#[test]
+fn unexpected() {
+    let error = pairs::assign::try_parse("a = let").unwrap_err();
+    // The span matched by `keyword` is reported.
+    assert_eq!(error.location, InputLocation::Span((4, 7)));
+    assert_eq!(
+        error.to_string(),
+        [
+            " --> 1:5",
+            "  |",
+            "1 | a = let",
+            "  |     ^-^",
+            "  |",
+            "  = a = ^---",
+            "    Unexpected [keyword], by assign.",
+            "    Unexpected `let`.",
+        ]
+        .join("\n")
+    );
+    // Keywords are only unexpected as whole words.
+    assert!(pairs::assign::try_parse("a = letter").is_ok());
+}
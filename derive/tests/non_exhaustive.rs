@@ -0,0 +1,47 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+/// Nodes can only be read with methods outside this module,
+/// the same way as outside the crate defining the parser.
+mod ast {
+    use pest_typed_derive::TypedParser;
+
+    #[derive(TypedParser)]
+    #[grammar_inline = r#"
+WHITESPACE = _{ " " }
+key        = @{ ASCII_ALPHA+ }
+value      =  { key | "(" ~ pair ~ ")" }
+pair       =  { key ~ "=" ~ value }
+hidden     = _{ key }
+"#]
+    #[emit_rule_reference]
+    #[non_exhaustive_nodes]
+    #[allow(dead_code)]
+    pub struct Parser;
+}
+
+use ast::pairs;
+use pest_typed::ParsableTypedNode as _;
+
+#[test]
+fn accessers() {
+    let pair = pairs::pair::try_parse("a = (b = c)").unwrap();
+    assert_eq!(pair.span().as_str(), "a = (b = c)");
+    let (key, _, value) = pair.content().get_matched();
+    assert_eq!(key.span().as_str(), "a");
+    assert_eq!(value.span().as_str(), "(b = c)");
+    let inner = value.pair().unwrap();
+    assert_eq!(inner.key().span().as_str(), "b");
+
+    let hidden = pairs::hidden::try_parse("x").unwrap();
+    assert_eq!(hidden.content().span().as_str(), "x");
+    // Nodes can still be created explicitly.
+    let key: pairs::key<'_> = pairs::key::new(key.span());
+    assert_eq!(key.span().as_str(), "a");
+}
@@ -0,0 +1,66 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use pest_typed::ParsableTypedNode as _;
+use pest_typed_derive::TypedParser;
+use std::sync::Arc;
+
+#[derive(TypedParser)]
+#[grammar_inline = r#"
+config = { SOI ~ (entry ~ NEWLINE)* ~ EOI }
+entry  = { key ~ "=" ~ value }
+value  = _{ number | key }
+key    = @{ ASCII_ALPHA+ }
+number = @{ ASCII_DIGIT+ }
+WHITESPACE = _{ " " }
+"#]
+#[emit_owned]
+struct Parser;
+
+fn load(input: &str) -> owned::config {
+    owned::config::parse(input).unwrap()
+}
+
+#[test]
+fn owned() {
+    let config = load(&String::from("a = 1\nb = c\n"));
+    let entries: Vec<_> = config.entry().collect();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].to_string(), "a = 1");
+    assert_eq!(entries[1].start, 6);
+    let keys: Vec<_> = entries[1].key().map(|key| key.text.as_str()).collect();
+    assert_eq!(keys, ["b", "c"]);
+    assert_eq!(entries[0].number().next().unwrap().text, "1");
+    assert_eq!(config.children[0].rule(), Rule::entry);
+    assert_eq!(config.children.last().unwrap().rule(), Rule::EOI);
+    assert!(owned::entry::parse("a = ").is_err());
+}
+
+#[test]
+fn shared() {
+    let input: Arc<str> = Arc::from("a = 1\n");
+    let config = owned::config::parse_shared(input.clone()).unwrap();
+    let entry = config.entry().next().unwrap();
+    assert!(Arc::ptr_eq(entry.text.input(), &input));
+    assert_eq!(entry.text.range(), 0..5);
+
+    let tree = rules::config::try_parse(&input).unwrap();
+    let owned::Node::config(copied) = owned::Node::from_pair(&tree) else {
+        unreachable!()
+    };
+    assert!(!Arc::ptr_eq(copied.text.input(), &input));
+    assert_eq!(copied, config);
+}
+
+#[test]
+#[should_panic]
+fn shared_elsewhere() {
+    let tree = rules::config::try_parse("a = 1\n").unwrap();
+    let _ = owned::Node::from_pair_shared(&tree, &Arc::from("a = 1\n"));
+}
@@ -0,0 +1,53 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use pest_typed::{match_node, ParsableTypedNode};
+use pest_typed_derive::TypedParser;
+
+#[derive(TypedParser)]
+#[grammar_inline = r##"
+WHITESPACE = _{ " " }
+key    = @{ ASCII_ALPHA+ }
+string = @{ "\"" ~ (!"\"" ~ ANY)* ~ "\"" }
+number = @{ ASCII_DIGIT+ }
+value  =  { string | number | "(" ~ value ~ ")" }
+pair   =  { key ~ ":" ~ value ~ ("#" ~ key)? }
+"##]
+struct Parser;
+
+fn describe(input: &str) -> String {
+    let pair = pairs::pair::try_parse(input).unwrap();
+    match_node!(&pair;
+        pair { key, _, value { _0(string) }, Some((_, comment)) } => {
+            format!("{} = text {} ({})", key.span.as_str(), string.span.as_str(), comment.span.as_str())
+        }
+        pair { key, _, value { _0(string) } } => format!("{} = text {}", key.span.as_str(), string.span.as_str()),
+        pair { key, _, value { _1(number) }, None } => format!("{} = number {}", key.span.as_str(), number.span.as_str()),
+        pair { _, _, value { _2((_, value { _1(number) })) } } => format!("number {} in parentheses", number.span.as_str()),
+        _ => String::from("other"),
+    )
+}
+
+#[test]
+fn match_node() {
+    assert_eq!(describe(r#"a: "x" # note"#), r#"a = text "x" (note)"#);
+    assert_eq!(describe(r#"a: "x""#), r#"a = text "x""#);
+    assert_eq!(describe("b: 12"), "b = number 12");
+    assert_eq!(describe("b: 12 # n"), "other");
+    assert_eq!(describe("c: (3)"), "number 3 in parentheses");
+    assert_eq!(describe("c: ((3))"), "other");
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "rule in `match_node!` doesn't match the node")]
+fn wrong_rule() {
+    let pair = pairs::pair::try_parse("a: 1").unwrap();
+    let _: bool = match_node!(&pair; value { _ } => true, _ => false);
+}
@@ -33,12 +33,12 @@ fn tree() {
     let b = rules::main::try_parse("foo Foo FOO Foo FOO Foo").unwrap_err();
     assert_eq!(
         format!("{b}"),
-        " --> 1:1
+        " --> 1:4
   |
 1 | foo Foo FOO Foo FOO Foo
-  | ^---
+  |    ^---
   |
-  = ^---
-    Expected [main]."
+  = foo^---
+    Expected [\"Foo\"], by main."
     );
 }
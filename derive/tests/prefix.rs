@@ -0,0 +1,46 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use pest_typed::{ParsableTypedNode as _, Position, TypedParser as _};
+use pest_typed_derive::TypedParser;
+
+#[derive(TypedParser)]
+#[grammar_inline = r#"
+front_matter = { "---\n" ~ (entry ~ "\n")* ~ "---\n" }
+entry        = { key ~ ":" ~ " "* ~ value }
+key          = @{ ASCII_ALPHA+ }
+value        = @{ (!"\n" ~ ANY)* }
+"#]
+#[emit_rule_reference]
+struct Parser;
+
+#[test]
+fn prefix() {
+    let input = "---\ntitle: a\n---\n# Body\n";
+    // The whole input is not a front matter.
+    assert!(rules::front_matter::try_parse(input).is_err());
+
+    let (front_matter, rest) = Parser::try_parse_prefix::<rules::front_matter>(input).unwrap();
+    assert_eq!(front_matter.entry().len(), 1);
+    assert_eq!(rest.pos(), 17);
+    assert_eq!(rest.rest(), "# Body\n");
+}
+
+#[test]
+fn prefix_at() {
+    let input = "{{ name: x\n }}";
+    let start = Position::new(input, 3).unwrap();
+    let (entry, rest) = rules::entry::try_parse_prefix_at(start).unwrap();
+    // Spans are offsets into the whole input.
+    assert_eq!(entry.key().span.start(), 3);
+    assert_eq!(rest.rest(), "\n }}");
+
+    let error = rules::entry::try_parse_prefix_at(Position::new(input, 0).unwrap()).unwrap_err();
+    assert_eq!(error.location, pest_typed::error::InputLocation::Pos(0));
+}
@@ -0,0 +1,54 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use pest_typed::{profile::ParseStats, ParsableTypedNode};
+use pest_typed_derive::TypedParser;
+
+#[derive(TypedParser)]
+#[grammar_inline = r#"
+WHITESPACE = _{ " " }
+ident      = @{ ASCII_ALPHA+ }
+number     = @{ ASCII_DIGIT+ }
+value      =  { number | ident }
+list       =  { "[" ~ (value ~ ("," ~ value)*)? ~ "]" }
+"#]
+struct Parser;
+
+#[test]
+fn stats() {
+    let mut stats = ParseStats::new();
+    pairs::list::try_parse_with_stats("[1, a, b]", &mut stats).unwrap();
+    assert!(pairs::list::try_parse_with_stats("[2,]", &mut stats).is_err());
+
+    let list = stats.get(Rule::list);
+    assert_eq!((list.calls, list.failures), (2, 1));
+    assert_eq!(stats.get(Rule::value).calls, 5);
+    let number = stats.get(Rule::number);
+    assert_eq!((number.calls, number.failures), (5, 3));
+    let ident = stats.get(Rule::ident);
+    assert_eq!((ident.calls, ident.failures), (3, 1));
+    assert!(list.time >= stats.get(Rule::value).time);
+    assert!(list.self_time <= list.time);
+
+    // `EOI` is attempted as well.
+    let hottest = stats.hottest();
+    assert_eq!(hottest.len(), 5);
+    assert!(hottest
+        .windows(2)
+        .all(|pair| pair[0].1.self_time >= pair[1].1.self_time));
+    let report = stats.report().to_string();
+    assert_eq!(report.lines().count(), 6);
+    assert!(report.starts_with("     calls   failures         time    self time  rule\n"));
+
+    let mut total = ParseStats::new();
+    total.merge(&stats);
+    total.merge(&stats);
+    assert_eq!(total.get(Rule::number).calls, 10);
+    assert_eq!(stats.get(Rule::WHITESPACE), Default::default());
+}
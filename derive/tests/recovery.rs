@@ -0,0 +1,75 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use pest_typed::{
+    recovery::{Provenance, Recovered as _},
+    ParsableTypedNode as _,
+};
+use pest_typed_derive::TypedParser;
+
+#[derive(TypedParser)]
+#[grammar_inline = r#"
+file    = { SOI ~ (item ~ NEWLINE?)* ~ EOI }
+item    = { string | block | word }
+#[recover]
+string  = @{ "\"" ~ (!("\"" | NEWLINE) ~ ANY)* ~ "\"" }
+#[recover]
+block   = { "{" ~ (word | string)* ~ "}" }
+word    = @{ ASCII_ALPHA+ }
+WHITESPACE = _{ " " }
+"#]
+#[emit_rule_reference]
+struct Parser;
+
+#[test]
+fn strings() {
+    let file = pairs::file::try_parse("\"closed\"\n\"open\nword").unwrap();
+    let items: Vec<_> = file.item().into_iter().collect();
+    assert_eq!(items.len(), 3);
+    let closed = items[0].string().unwrap();
+    assert!(!closed.is_synthesized());
+    assert_eq!(closed.span.as_str(), "\"closed\"");
+    let open = items[1].string().unwrap();
+    assert!(open.is_synthesized());
+    assert_eq!(open.span.as_str(), "\"open");
+    assert_eq!(items[2].word().unwrap().span.as_str(), "word");
+
+    // At the end of input.
+    assert!(pairs::string::try_parse("\"a").is_ok());
+    assert!(pairs::file::try_parse("\"a\" \"b").is_ok());
+}
+
+#[test]
+fn blocks() {
+    let block = pairs::block::try_parse("{ a b").unwrap();
+    assert!(block.is_synthesized());
+    assert_eq!(block.word().len(), 2);
+    let block = pairs::block::try_parse("{ a }").unwrap();
+    assert!(!block.is_synthesized());
+    assert!(pairs::block::try_parse("{ a 1 }").is_err());
+}
+
+#[test]
+fn provenance() {
+    let file = pairs::file::try_parse("word\n{ a \"b\" }\n{ \"c\n").unwrap();
+    assert_eq!(file.provenance(), Provenance::CONTAINS_RECOVERED);
+    let items: Vec<_> = file.item().into_iter().collect();
+    assert!(!items[0].is_recovered());
+    assert!(!items[1].is_recovered());
+    assert_eq!(items[2].provenance(), Provenance::CONTAINS_RECOVERED);
+    let block = items[2].block().unwrap();
+    assert!(block.is_synthesized());
+    assert!(block
+        .provenance()
+        .contains(Provenance::SYNTHESIZED | Provenance::CONTAINS_RECOVERED));
+    assert_eq!(
+        block.string()[0].unwrap().provenance(),
+        Provenance::SYNTHESIZED
+    );
+}
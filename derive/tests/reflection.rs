@@ -0,0 +1,68 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use pest_typed::reflection::{Expr, RuleKind, Terminal};
+use pest_typed_derive::TypedParser;
+
+#[derive(TypedParser)]
+#[grammar_inline = r#"
+WHITESPACE = _{ " " }
+num        = @{ ASCII_DIGIT+ }
+neg        =  { "-" ~ num }
+term       = _{ neg | num | "(" ~ sum ~ ")" }
+sum        =  { term ~ ("+" ~ term)* }
+"#]
+#[emit_reflection]
+struct Parser;
+
+#[test]
+fn rules() {
+    let names: Vec<_> = grammar::rules().iter().map(|rule| rule.name).collect();
+    assert_eq!(names, ["WHITESPACE", "num", "neg", "term", "sum"]);
+
+    let neg = grammar::rule("neg").unwrap();
+    assert_eq!(neg.kind, RuleKind::Normal);
+    assert_eq!(*neg.expr, Expr::Seq(&Expr::Str("-"), &Expr::Ident("num")));
+    assert_eq!(neg.first, [Terminal::Str("-")]);
+    assert!(!neg.nullable);
+
+    let num = grammar::rule("num").unwrap();
+    assert_eq!(num.kind, RuleKind::Atomic);
+    assert_eq!(num.first, [Terminal::Builtin("ASCII_DIGIT")]);
+
+    let sum = grammar::rule("sum").unwrap();
+    assert_eq!(grammar::rule("term").unwrap().kind, RuleKind::Silent);
+    assert_eq!(
+        sum.first,
+        [
+            Terminal::Str("("),
+            Terminal::Str("-"),
+            Terminal::Builtin("ASCII_DIGIT")
+        ]
+    );
+    assert!(grammar::rule("missing").is_none());
+}
+
+#[test]
+fn display() {
+    let sum = grammar::rule("sum").unwrap();
+    assert_eq!(sum.first[0].to_string(), r#""(""#);
+    assert_eq!(
+        grammar::rule("neg").unwrap().expr.to_string(),
+        r#"("-" ~ num)"#
+    );
+}
+
+#[test]
+fn railroad() {
+    let svg = pest_typed::railroad::to_svg(grammar::rule("sum").unwrap());
+    assert!(svg.contains(">sum</text>"));
+    assert!(svg.contains(">term</text>"));
+    assert!(svg.contains(">&quot;+&quot;</text>"));
+}
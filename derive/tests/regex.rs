@@ -0,0 +1,58 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+#![cfg(feature = "regex")]
+
+use pest_typed::{
+    regex::{Regex, RegexWrapper},
+    ParsableTypedNode as _,
+};
+use pest_typed_derive::TypedParser;
+
+#[derive(TypedParser)]
+#[grammar_inline = r#"
+WHITESPACE = _{ " " }
+date       = @{ re"\d{4}-\d{2}-\d{2}" }
+quoted     = @{ re"\"[^\"]*\"" }
+entry      =  { date ~ (quoted | re"[a-z]+") ~ re"[a-z]+"? }
+"#]
+#[emit_rule_reference]
+struct Parser;
+
+/// Pattern of a matched regular expression.
+fn pattern<T: RegexWrapper>(_: &Regex<'_, T>) -> &'static str {
+    T::PATTERN
+}
+
+#[test]
+fn dates() {
+    let date = pairs::date::try_parse("2023-10-01").unwrap();
+    assert_eq!(date.span.as_str(), "2023-10-01");
+    assert!(pairs::date::try_parse("2023-1-01").is_err());
+    // Matches start at the current position, and the rest of the input isn't skipped.
+    assert!(pairs::date::try_parse("x2023-10-01").is_err());
+    assert!(pairs::date::try_parse("2023-10-011").is_err());
+}
+
+#[test]
+fn entries() {
+    let entry = pairs::entry::try_parse(r#"2023-10-01 "a b""#).unwrap();
+    let (date, message, word) = entry.get_matched();
+    assert_eq!(date.span.as_str(), "2023-10-01");
+    assert_eq!(message._0().unwrap().span.as_str(), r#""a b""#);
+    assert!(word.is_none());
+
+    let entry = pairs::entry::try_parse("2023-10-01 abc de").unwrap();
+    let (_, message, word) = entry.get_matched();
+    let message = message._1().unwrap();
+    assert_eq!(message.as_str(), "abc");
+    assert_eq!(message.span.start(), 11);
+    assert_eq!(pattern(message), "[a-z]+");
+    assert_eq!(word.as_ref().unwrap().as_str(), "de");
+}
@@ -0,0 +1,107 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use pest_typed::{assert_round_trip, iterators::Pair, round_trip, round_trip::RoundTripError};
+use pest_typed_derive::TypedParser;
+
+#[derive(TypedParser)]
+#[grammar_inline = r#"
+list  = { "[" ~ (item ~ ("," ~ item)*)? ~ "]" }
+item  = _{ list | atom }
+atom  = @{ ASCII_ALPHA+ }
+WHITESPACE = _{ " " }
+"#]
+#[emit_rule_reference]
+struct Parser;
+
+/// Print nodes separated by `sep`.
+fn print<'i: 'n, 'n>(node: &'n dyn Pair<'i, 'n, Rule>, sep: &str) -> String {
+    match node.rule() {
+        Rule::list => {
+            let items: Vec<_> = node.inner().map(|item| print(item, sep)).collect();
+            format!("[{}]", items.join(sep))
+        }
+        _ => node.span().as_str().to_owned(),
+    }
+}
+
+const INPUTS: [&str; 5] = ["[]", "[a]", "[a,b ,  c]", "[[a, []], b]", "not a list"];
+
+#[test]
+fn lossless() {
+    assert_round_trip!(pairs::list<'_>, INPUTS, |list: &pairs::list<'_>| print(
+        list, ", "
+    ));
+    assert_eq!(
+        round_trip!(pairs::list<'_>, "[a]", |list: &pairs::list<'_>| print(
+            list, ","
+        )),
+        Ok(true)
+    );
+    assert_eq!(
+        round_trip!(pairs::list<'_>, "a", |list: &pairs::list<'_>| print(
+            list, ","
+        )),
+        Ok(false)
+    );
+}
+
+#[test]
+fn lossy() {
+    let res = round_trip!(pairs::list<'_>, "[[a]]", |list: &pairs::list<'_>| list
+        .span
+        .as_str()
+        .replace(['[', ']'], ""));
+    assert!(matches!(
+        res.map_err(|error| *error),
+        Err(RoundTripError::Reparse { .. })
+    ));
+    // Adjacent atoms are merged without separators.
+    let res = round_trip!(pairs::list<'_>, "[a, b]", |list: &pairs::list<'_>| print(
+        list, ""
+    ));
+    match res.map_err(|error| *error) {
+        Err(RoundTripError::Mismatch {
+            printed,
+            original,
+            reparsed,
+            ..
+        }) => {
+            assert_eq!(printed, "[ab]");
+            assert_eq!(original.children.len(), 2);
+            assert_eq!(reparsed.to_string(), r#"(list (atom "ab"))"#);
+        }
+        res => panic!("{:?}", res),
+    }
+}
+
+#[test]
+fn generated() {
+    use pest_typed::round_trip::proptest::{string::string_regex, test_runner::TestError};
+    use pest_typed::{proptest_round_trip, round_trip::check};
+
+    // Most of these inputs don't parse, and are skipped.
+    // Without whitespaces, as an empty list keeps its text, such as `[ ]`, in its shape.
+    let inputs = string_regex(r"[ab\[\],]{0,12}").unwrap();
+    proptest_round_trip!(pairs::list<'_>, inputs, |list: &pairs::list<'_>| print(
+        list, ", "
+    ));
+
+    let lists = string_regex(r"\[[ab]{1,3}(, ?[ab]{1,3}){1,3}\]").unwrap();
+    let res = check(lists, |input| {
+        round_trip!(pairs::list<'_>, input, |list: &pairs::list<'_>| print(
+            list, ""
+        ))
+    });
+    match res {
+        // Shrunk to two atoms of one letter.
+        Err(TestError::Fail(_, input)) => assert!(input.len() <= 6, "{:?}", input),
+        res => panic!("{:?}", res),
+    }
+}
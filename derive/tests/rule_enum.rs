@@ -0,0 +1,53 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use pest_typed::rule::UnknownRule;
+use pest_typed_derive::TypedParser;
+use std::collections::BTreeMap;
+
+#[derive(TypedParser)]
+#[grammar_inline = r#"
+list  = { "[" ~ (item ~ ("," ~ item)*)? ~ "]" }
+item  = _{ list | word }
+word  = @{ ASCII_ALPHA+ }
+"#]
+#[emit_rule_reference]
+struct Parser;
+
+#[test]
+fn all_rules() {
+    assert_eq!(
+        Rule::ALL_RULES,
+        [Rule::EOI, Rule::list, Rule::item, Rule::word]
+    );
+    let mut sorted = Rule::ALL_RULES.to_vec();
+    sorted.sort();
+    assert_eq!(sorted, Rule::ALL_RULES);
+    let counts: BTreeMap<Rule, usize> = Rule::ALL_RULES.iter().map(|&rule| (rule, 0)).collect();
+    assert_eq!(counts.len(), 4);
+}
+
+#[test]
+fn names() {
+    for &rule in Rule::ALL_RULES {
+        assert_eq!(rule.to_string().parse::<Rule>(), Ok(rule));
+    }
+    assert_eq!(Rule::word.name(), "word");
+    assert_eq!(format!("{}", Rule::EOI), "EOI");
+    assert_eq!(
+        "lists".parse::<Rule>(),
+        Err(UnknownRule {
+            name: "lists".to_owned()
+        })
+    );
+    assert_eq!(
+        "lists".parse::<Rule>().unwrap_err().to_string(),
+        "unknown rule `lists`"
+    );
+}
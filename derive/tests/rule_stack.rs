@@ -0,0 +1,34 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use pest_typed::ParsableTypedNode;
+use pest_typed_derive::TypedParser;
+
+#[derive(TypedParser)]
+#[grammar_inline = r#"
+WHITESPACE = _{ " " }
+ident      = @{ ASCII_ALPHA+ }
+params     =  { "(" ~ (ident ~ ("," ~ ident)*)? ~ ")" }
+function   =  { "fn" ~ ident ~ params }
+"#]
+struct Parser;
+
+#[test]
+fn rule_stack() {
+    let error = pairs::function::try_parse_with_context("fn foo(a, )").unwrap_err();
+    assert_eq!(
+        error.rule_stack(),
+        [Rule::function, Rule::params, Rule::ident]
+    );
+    // The error is the same as the one from `try_parse`.
+    assert_eq!(
+        error.into_error(),
+        pairs::function::try_parse("fn foo(a, )").unwrap_err()
+    );
+}
@@ -0,0 +1,56 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use pest_typed::scan::Scan;
+use pest_typed_derive::TypedParser;
+
+#[derive(TypedParser)]
+#[grammar_inline = r#"
+number = @{ ASCII_DIGIT+ }
+word   = @{ ^"let" | "嗨" }
+maybe  = { "x"? }
+any    = { ANY }
+"#]
+#[emit_rule_reference]
+#[emit_scanner]
+struct Parser;
+
+#[test]
+fn first_bytes() {
+    let digits = <rules::number<'_, 0> as Scan<'_, Rule>>::FIRST_BYTES.unwrap();
+    assert!(digits.contains(b'0'));
+    assert!(!digits.contains(b'a'));
+    let word = <rules::word<'_, 0> as Scan<'_, Rule>>::FIRST_BYTES.unwrap();
+    assert!(word.contains(b'l') && word.contains(b'L'));
+    assert!(word.contains("嗨".as_bytes()[0]));
+    assert!(<rules::maybe<'_, 0> as Scan<'_, Rule>>::FIRST_BYTES.is_none());
+    assert!(<rules::any<'_, 0> as Scan<'_, Rule>>::FIRST_BYTES.is_none());
+}
+
+#[test]
+fn find() {
+    let input = "ab 12 c 345";
+    let (pos, number) = find_next::<rules::number>(input, 0).unwrap();
+    assert_eq!(pos, 3);
+    assert_eq!(number.span.as_str(), "12");
+    let (pos, number) = find_next::<rules::number>(input, 4).unwrap();
+    assert_eq!(pos, 4);
+    assert_eq!(number.span.as_str(), "2");
+    let (pos, _) = find_next::<rules::number>(input, 5).unwrap();
+    assert_eq!(pos, 8);
+    assert!(find_next::<rules::number>(input, 11).is_none());
+
+    let input = "a嗨 LET";
+    assert_eq!(find_next::<rules::word>(input, 0).unwrap().0, 1);
+    assert_eq!(find_next::<rules::word>(input, 2).unwrap().0, 5);
+    // Empty matches are found at the first char boundary.
+    assert_eq!(find_next::<rules::maybe>(input, 2).unwrap().0, 4);
+    let (pos, any) = find_next::<rules::any>(input, 2).unwrap();
+    assert_eq!((pos, any.span.as_str()), (4, " "));
+}
@@ -0,0 +1,43 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use pest_typed::{iterators::PairTree, ParsableTypedNode};
+use pest_typed_derive::TypedParser;
+
+#[derive(TypedParser)]
+#[grammar_inline = r#"
+WHITESPACE = _{ " " }
+ident      = @{ ASCII_ALPHA+ }
+parameter  =  { ident }
+parameters = _{ "(" ~ (parameter ~ ("," ~ parameter)*)? ~ ")" }
+call       =  { ident ~ "(" ~ (ident ~ ("," ~ ident)*)? ~ ")" }
+function   =  { "fn" ~ ident ~ parameters ~ "{" ~ call* ~ "}" }
+"#]
+struct Parser;
+
+#[test]
+fn select() {
+    let function = pairs::function::try_parse("fn f(a, b) { g(a) h() }").unwrap();
+    let spans = |selector: &str| {
+        function
+            .select(selector)
+            .unwrap()
+            .into_iter()
+            .map(|pair| pair.span().as_str())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(spans("function > parameter ident"), ["a", "b"]);
+    assert_eq!(spans("function > ident"), ["f"]);
+    assert_eq!(spans("call > ident"), ["g", "a", "h"]);
+    assert_eq!(spans("function ident"), ["f", "a", "b", "g", "a", "h"]);
+    assert_eq!(spans("function > *"), ["f", "a", "b", "g(a)", "h()"]);
+    assert_eq!(spans("parameter, call"), ["a", "b", "g(a)", "h()"]);
+    assert_eq!(spans("call parameter"), Vec::<&str>::new());
+    assert!(function.select("function >").is_err());
+}
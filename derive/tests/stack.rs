@@ -0,0 +1,52 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use pest_typed::ParsableTypedNode as _;
+use pest_typed_derive::TypedParser;
+
+#[derive(TypedParser)]
+#[grammar_inline = r#"
+heredoc = ${ "<<" ~ PUSH(ASCII_ALPHA+) ~ "\n" ~ body ~ POP }
+body    = @{ (!PEEK ~ ANY)* }
+nested  = ${ PUSH("(")* ~ "x" ~ PEEK_ALL ~ POP_ALL }
+slice   = ${ PUSH("a") ~ PUSH("b") ~ PUSH("c") ~ PEEK[1..] }
+"#]
+#[emit_rule_reference]
+struct Parser;
+
+#[test]
+fn pop() {
+    let heredoc = pairs::heredoc::try_parse("<<EOF\nsome text\nEOF").unwrap();
+    let (_, _, _, body, pop) = heredoc.get_matched();
+    assert_eq!(body.span.as_str(), "some text\n");
+    assert_eq!(pop.span.start(), 16);
+    assert_eq!(pop.pushed.start(), 2);
+    assert_eq!(pop.pushed.as_str(), "EOF");
+}
+
+#[test]
+fn all() {
+    let nested = pairs::nested::try_parse("((x((((").unwrap();
+    let (_, _, peek, pop) = nested.get_matched();
+    let starts =
+        |pushed: &[pest_typed::Span<'_>]| pushed.iter().map(|s| s.start()).collect::<Vec<_>>();
+    assert_eq!(peek.span.as_str(), "((");
+    assert_eq!(starts(&peek.pushed), [1, 0]);
+    assert_eq!(pop.span.start(), 5);
+    assert_eq!(starts(&pop.pushed), [1, 0]);
+}
+
+#[test]
+fn slice() {
+    let slice = pairs::slice::try_parse("abcbc").unwrap();
+    let (_, _, _, peek) = slice.get_matched();
+    assert_eq!(peek.span.as_str(), "bc");
+    let starts: Vec<_> = peek.pushed.iter().map(|s| s.start()).collect();
+    assert_eq!(starts, [1, 2]);
+}
@@ -0,0 +1,42 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use pest_typed::{iterators::Pair, ParsableTypedNode};
+use pest_typed_derive::TypedParser;
+
+#[derive(TypedParser)]
+#[grammar_inline = r#"
+WHITESPACE = _{ " " }
+sign       =  { "-" }
+#[structured]
+number     = @{ sign? ~ ASCII_DIGIT+ }
+word       = @{ ASCII_ALPHA+ }
+#[opaque]
+words      =  { word+ }
+item       =  { number | words }
+"#]
+#[emit_rule_reference]
+struct Parser;
+
+#[test]
+fn structured() {
+    let number = pairs::number::try_parse("-12").unwrap();
+    assert_eq!(number.sign().unwrap().span.as_str(), "-");
+    // Atomicity is unchanged.
+    assert!(pairs::number::try_parse("- 12").is_err());
+}
+
+#[test]
+fn opaque() {
+    // Implicit whitespace is still skipped.
+    let item = pairs::item::try_parse("ab  cd").unwrap();
+    let words = item.words().unwrap();
+    assert_eq!(words.span.as_str(), "ab  cd");
+    assert_eq!(words.inner().len(), 0);
+}
@@ -0,0 +1,36 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use pest_typed::ParsableTypedNode;
+use pest_typed_derive::TypedParser;
+
+#[derive(TypedParser)]
+#[grammar_inline = r#"
+WHITESPACE = _{ " " }
+ident      = @{ ASCII_ALPHA+ }
+statement  =  { "while" ~ ident | ident ~ "=" ~ ident }
+"#]
+struct Parser;
+
+fn message(input: &str, max_distance: usize) -> String {
+    pairs::statement::try_parse_with_suggestions(input, max_distance)
+        .unwrap_err()
+        .to_string()
+}
+
+#[test]
+fn did_you_mean() {
+    assert!(message("whle x", 1).ends_with("Did you mean `while`?"));
+    assert!(!message("whle x", 0).contains("Did you mean"));
+    assert!(!message("wle x", 1).contains("Did you mean"));
+    assert!(message("wle x", 2).ends_with("Did you mean `while`?"));
+    // The word is not where parsing failed.
+    assert!(!message("whle = x y", 1).contains("Did you mean"));
+    assert!(pairs::statement::try_parse_with_suggestions("while x", 1).is_ok());
+}
@@ -0,0 +1,70 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use pest_typed::ParsableTypedNode as _;
+use pest_typed_derive::TypedParser;
+use static_assertions::assert_impl_all;
+use std::{sync::Arc, thread};
+
+#[derive(TypedParser)]
+#[grammar_inline = r#"
+WHITESPACE = _{ " " }
+name       = @{ ASCII_ALPHA+ }
+item       = @{ ASCII_DIGIT+ }
+#[lazy]
+body       =  { "{" ~ item* ~ "}" }
+section    =  { name ~ body }
+file       =  { section* }
+"#]
+#[emit_rule_reference]
+#[emit_owned]
+#[emit_syntax]
+struct Parser;
+
+assert_impl_all!(Rule: Send, Sync);
+assert_impl_all!(pairs::file<'static>: Send, Sync);
+assert_impl_all!(pairs::body<'static>: Send, Sync);
+assert_impl_all!(owned::file: Send, Sync);
+assert_impl_all!(owned::Node: Send, Sync);
+assert_impl_all!(syntax::file: Send, Sync);
+assert_impl_all!(pest_typed::green::SyntaxNode<Rule>: Send, Sync);
+assert_impl_all!(pest_typed::error::Error<Rule>: Send, Sync);
+assert_impl_all!(pest_typed::shared::SharedStr: Send, Sync);
+assert_impl_all!(pest_typed::line_index::LineIndex<'static>: Send, Sync);
+assert_impl_all!(pest_typed::offset_map::OffsetMap: Send, Sync);
+assert_impl_all!(pest_typed::metrics::Metrics<Rule>: Send, Sync);
+
+#[test]
+fn lazy_across_threads() {
+    let file = pairs::file::try_parse("a { 1 2 } b { 3 }").unwrap();
+    let sections = file.section();
+    let (_, body) = sections[0].get_matched();
+    // Subtrees of lazy nodes may be built by any of the threads sharing them.
+    let items: Vec<usize> = thread::scope(|scope| {
+        let workers: Vec<_> = (0..4).map(|_| scope.spawn(|| body.item().len())).collect();
+        workers.into_iter().map(|w| w.join().unwrap()).collect()
+    });
+    assert_eq!(items, [2; 4]);
+    assert!(body.content.is_parsed());
+}
+
+#[test]
+fn owned_across_threads() {
+    let input: Arc<str> = Arc::from("a { 1 2 } b { 3 }");
+    let file = Arc::new(owned::file::parse_shared(input.clone()).unwrap());
+    let workers: Vec<_> = (0..2)
+        .map(|i| {
+            let file = file.clone();
+            thread::spawn(move || file.section().nth(i).unwrap().text.to_string())
+        })
+        .collect();
+    let texts: Vec<_> = workers.into_iter().map(|w| w.join().unwrap()).collect();
+    assert_eq!(texts, ["a { 1 2 }", "b { 3 }"]);
+    assert!(Arc::ptr_eq(file.text.input(), &input));
+}
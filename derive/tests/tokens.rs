@@ -0,0 +1,57 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use pest_typed::{iterators::PairTree, tokens::Trivia, ParsableTypedNode};
+use pest_typed_derive::TypedParser;
+
+#[derive(TypedParser)]
+#[grammar_inline = r##"
+WHITESPACE = _{ " " | NEWLINE }
+COMMENT    =  { "#" ~ (!NEWLINE ~ ANY)* }
+ident      = @{ ASCII_ALPHA+ }
+number     = @{ ASCII_DIGIT+ }
+operator   =  { "+" | "-" }
+item       = _{ ident | number }
+expr       =  { item ~ (operator ~ item)* }
+"##]
+struct Parser;
+
+#[test]
+fn tokens() {
+    let expr = pairs::expr::try_parse("a + 1 #c\n- b").unwrap();
+    let tokens = |trivia| {
+        expr.tokens(trivia)
+            .map(|(rule, span)| (rule, span.as_str()))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        tokens(Trivia::Skip),
+        [
+            (Rule::ident, "a"),
+            (Rule::operator, "+"),
+            (Rule::number, "1"),
+            (Rule::operator, "-"),
+            (Rule::ident, "b"),
+        ]
+    );
+    assert_eq!(
+        tokens(Trivia::Include),
+        [
+            (Rule::ident, "a"),
+            (Rule::operator, "+"),
+            (Rule::number, "1"),
+            (Rule::COMMENT, "#c"),
+            (Rule::operator, "-"),
+            (Rule::ident, "b"),
+        ]
+    );
+    // A leaf yields itself.
+    let ident = pairs::ident::try_parse("a").unwrap();
+    assert_eq!(ident.tokens(Trivia::Skip).count(), 1);
+}
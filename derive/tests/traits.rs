@@ -0,0 +1,46 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use pest_typed::ParsableTypedNode;
+use pest_typed_derive::TypedParser;
+use std::collections::HashMap;
+
+#[derive(TypedParser)]
+#[grammar_inline = r#"
+WHITESPACE = _{ " " }
+key        = @{ ASCII_ALPHA+ }
+pair       =  { key ~ "=" ~ key }
+pairs      =  { pair ~ ("," ~ pair)* }
+"#]
+#[emit_rule_reference]
+#[emit_display]
+struct Parser;
+
+#[test]
+fn display() {
+    let pair = pairs::pair::try_parse("a =  b").unwrap();
+    assert_eq!(pair.to_string(), "a =  b");
+    let (lhs, rhs) = pair.key();
+    assert_eq!(format!("{lhs} -> {rhs}"), "a -> b");
+}
+
+#[test]
+fn hash_and_eq() {
+    let input = "a = b, c = d, a = b";
+    let pairs = pairs::pairs::try_parse(input).unwrap();
+    let (first, following) = pairs.pair();
+    let mut counts = HashMap::new();
+    for pair in core::iter::once(first).chain(following.iter().copied()) {
+        *counts.entry(pair.key().0.clone()).or_insert(0) += 1;
+    }
+    // Nodes are compared by their positions in the same input, so both `a` are different keys.
+    assert_eq!(counts.len(), 3);
+    assert_eq!(first.clone(), *first);
+    assert_ne!(first, following[1]);
+}
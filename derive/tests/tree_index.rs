@@ -0,0 +1,60 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use pest_typed::{iterators::PairTree as _, tree_index::NodeId, ParsableTypedNode as _};
+use pest_typed_derive::TypedParser;
+use std::collections::BTreeMap;
+
+#[derive(TypedParser)]
+#[grammar_inline = r#"
+list  = { "[" ~ (item ~ ("," ~ item)*)? ~ "]" }
+item  = { list | atom }
+atom  = @{ ASCII_ALPHA+ }
+WHITESPACE = _{ " " }
+"#]
+#[emit_rule_reference]
+struct Parser;
+
+#[test]
+fn index() {
+    let list = pairs::list::try_parse("[a, [b, c], d]").unwrap();
+    let index = list.tree_index();
+    let rules: Vec<_> = index.ids().map(|id| index.rule(id)).collect();
+    let (l, i, a) = (Rule::list, Rule::item, Rule::atom);
+    assert_eq!(rules, [l, i, a, i, l, i, a, i, a, i, a]);
+    assert_eq!(index.len(), 11);
+
+    let root = NodeId::ROOT;
+    assert_eq!(index.parent(root), None);
+    let items: Vec<_> = index.children(root).collect();
+    assert_eq!(items.len(), 3);
+    assert_eq!(index.span(items[1]).as_str(), "[b, c]");
+    assert!(items.iter().all(|&id| index.parent(id) == Some(root)));
+
+    let inner: Vec<_> = index
+        .children(index.children(items[1]).next().unwrap())
+        .collect();
+    assert_eq!(inner.len(), 2);
+    let c = index.children(inner[1]).next().unwrap();
+    assert_eq!(index.span(c).as_str(), "c");
+    assert_eq!(index.depth(c), 4);
+    assert_eq!(index.ancestors(c).last(), Some(root));
+    assert_eq!(index.descendants(items[1]).count(), 5);
+    assert_eq!(index.node_at(8), Some(c));
+    assert_eq!(index.node_at(6), Some(index.parent(inner[0]).unwrap()));
+
+    // Nodes can be found by reference, and IDs are keys of maps.
+    let (_, rest) = list.item().unwrap();
+    let d = rest.last().unwrap().atom().unwrap();
+    let d = index.id_of(d).unwrap();
+    assert_eq!(index.span(d).as_str(), "d");
+    let depths: BTreeMap<NodeId, usize> = index.ids().map(|id| (id, index.depth(id))).collect();
+    assert_eq!(depths[&d], 2);
+    assert_eq!(list.tree_index().ids().last(), Some(d));
+}
@@ -0,0 +1,90 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use pest_typed::{tracker::Tracker, ParsableTypedNode as _, Position};
+use pest_typed_derive::TypedParser;
+
+#[derive(TypedParser)]
+#[grammar_inline = r#"
+WHITESPACE = _{ " " | "\t" | NEWLINE }
+COMMENT    = _{ "/*" ~ (!"*/" ~ ANY)* ~ "*/" }
+word       = @{ ASCII_ALPHA+ }
+words      = { word ~ word* }
+#[trivia = "WHITESPACE COMMENT same_line"]
+line       = { word ~ word* }
+#[trivia = "COMMENT"]
+pair       = { word ~ "=" ~ word }
+#[trivia = ""]
+path       = { words ~ "." ~ words }
+token      = @{ ASCII_ALPHA+ | " " }
+#[lazy]
+block      = { "{" ~ token* ~ "}" }
+"#]
+#[emit_rule_reference]
+struct Parser;
+
+#[test]
+fn same_line() {
+    assert!(pairs::words::try_parse("a /* b */\n c").is_ok());
+    let line = pairs::line::try_parse("a /* b */ c\td").unwrap();
+    assert_eq!(line.word().1.len(), 2);
+    assert!(pairs::line::try_parse("a\nb").is_err());
+    assert!(pairs::line::try_parse("a /*\n*/ b").is_err());
+    assert!(pairs::line::try_parse("a b\n").is_err());
+}
+
+#[test]
+fn comment_only() {
+    assert!(pairs::pair::try_parse("a/* b */=/**/c").is_ok());
+    assert!(pairs::pair::try_parse("a = c").is_err());
+}
+
+#[test]
+fn nothing() {
+    assert!(pairs::path::try_parse("a b.c").is_ok());
+    assert!(pairs::path::try_parse("a . c").is_err());
+}
+
+#[test]
+fn raw() {
+    fn raw(input: &str) -> Tracker<'_, Rule> {
+        Tracker::new(Position::from_start(input)).with_raw()
+    }
+    assert!(pairs::words::try_parse_with_tracker("a b", &mut raw("a b")).is_err());
+    assert!(pairs::words::try_parse_with_tracker("a", &mut raw("a")).is_ok());
+    assert!(pairs::pair::try_parse_with_tracker("a/**/=b", &mut raw("a/**/=b")).is_err());
+    let pair = pairs::pair::try_parse_with_tracker("a=b", &mut raw("a=b")).unwrap();
+    // Same types as in a normal parse.
+    let _: &pairs::word = pair.word().1;
+
+    // Trailing trivia is not skipped either.
+    assert!(pairs::words::try_parse("a ").is_ok());
+    assert!(pairs::words::try_parse_with_tracker("a ", &mut raw("a ")).is_err());
+
+    let input = "a b {{c d}}";
+    let start = Position::new(input, 6).unwrap();
+    let (words, rest) =
+        pairs::words::try_parse_prefix_with_tracker(start, &mut raw(input)).unwrap();
+    assert_eq!(words.word().0.span.as_str(), "c");
+    assert!(words.word().1.is_empty());
+    assert_eq!(rest.rest(), " d}}");
+}
+
+#[test]
+fn raw_nested() {
+    let input = "{a b}";
+    let block = pairs::block::try_parse(input).unwrap();
+    assert_eq!(block.token().len(), 2);
+
+    // Repetitions and lazy subtrees are parsed in raw mode as well.
+    let mut tracker = Tracker::new(Position::from_start(input)).with_raw();
+    let block = pairs::block::try_parse_with_tracker(input, &mut tracker).unwrap();
+    let tokens: Vec<_> = block.token().iter().map(|t| t.span.as_str()).collect();
+    assert_eq!(tokens, ["a", " ", "b"]);
+}
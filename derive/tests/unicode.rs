@@ -0,0 +1,49 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use pest_typed::ParsableTypedNode as _;
+use pest_typed_derive::TypedParser;
+
+#[derive(TypedParser)]
+#[grammar_inline = r#"
+greek = @{ \p{Script=Greek}+ }
+ident = @{ \p{XID_Start} ~ \p{xid continue}* }
+upper = @{ \p{gc=Uppercase_Letter} }
+"#]
+#[emit_rule_reference]
+struct Parser;
+
+#[test]
+fn classes() {
+    assert_eq!(pairs::greek::try_parse("αβγ").unwrap().span.as_str(), "αβγ");
+    assert!(pairs::greek::try_parse("abc").is_err());
+    assert!(pairs::ident::try_parse("a_1").is_ok());
+    assert!(pairs::ident::try_parse("1a").is_err());
+    assert!(pairs::upper::try_parse("Ä").is_ok());
+    assert!(pairs::upper::try_parse("ä").is_err());
+}
+
+#[test]
+fn same_as_runtime() {
+    for c in ['a', 'α', '1', '_', 'Ä', '😀'] {
+        let input = c.to_string();
+        assert_eq!(
+            pairs::greek::try_parse(&input).is_ok(),
+            pest_typed::unicode::has_property("Script=Greek", c).unwrap(),
+        );
+        assert_eq!(
+            pairs::upper::try_parse(&input).is_ok(),
+            pest_typed::unicode::UPPERCASE_LETTER(c)
+        );
+        assert_eq!(
+            pairs::ident::try_parse(&input).is_ok(),
+            pest_typed::unicode::is_xid_start(c)
+        );
+    }
+}
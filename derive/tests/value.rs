@@ -0,0 +1,32 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use pest_typed::{iterators::PairTree as _, ParsableTypedNode as _};
+use pest_typed_derive::TypedParser;
+
+#[derive(TypedParser)]
+#[grammar_inline = r#"
+list  = { "[" ~ (item ~ ("," ~ item)*)? ~ "]" }
+item  = _{ list | atom }
+atom  = @{ ASCII_ALPHA+ }
+WHITESPACE = _{ " " }
+"#]
+#[emit_rule_reference]
+struct Parser;
+
+#[test]
+fn value() {
+    let list = pairs::list::try_parse("[a, [b, c], []]").unwrap();
+    let value = list.to_value();
+    assert_eq!(
+        value.to_string(),
+        r#"{"list":[{"atom":"a"},{"list":[{"atom":"b"},{"atom":"c"}]},{"list":"[]"}]}"#
+    );
+    assert_eq!(value["list"][1]["list"][0]["atom"], "b");
+}
@@ -0,0 +1,45 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use pest_typed::ParsableTypedNode as _;
+use pest_typed_derive::TypedParser;
+
+#[derive(TypedParser)]
+#[grammar_inline = r#"
+WHITESPACE = _{ " " }
+ident      = @{ ASCII_ALPHA+ }
+#[cfg(version >= 2)]
+async_kw   =  { "async" }
+#[cfg(version < 3)]
+print      =  { "print" ~ ident }
+function   =  { async_kw? ~ "fn" ~ ident }
+statement  =  { function | print }
+"#]
+#[emit_rule_reference]
+struct Parser;
+
+#[test]
+fn optional() {
+    let function = pairs::function::try_parse_with_version("async fn f", 2).unwrap();
+    assert!(function.async_kw().is_some());
+    assert_eq!(function.ident().span.as_str(), "f");
+    let function = pairs::function::try_parse_with_version("fn f", 1).unwrap();
+    assert!(function.async_kw().is_none());
+    assert!(pairs::function::try_parse_with_version("async fn f", 1).is_err());
+    // The latest version by default.
+    assert!(pairs::function::try_parse("async fn f").is_ok());
+}
+
+#[test]
+fn alternatives() {
+    let statement = pairs::statement::try_parse_with_version("print x", 2).unwrap();
+    assert_eq!(statement.print().unwrap().ident().span.as_str(), "x");
+    assert!(pairs::statement::try_parse_with_version("print x", 3).is_err());
+    assert!(pairs::statement::try_parse_with_version("fn f", 3).is_ok());
+}
@@ -0,0 +1,72 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use pest_typed::{
+    iterators::{Pair, Token},
+    vm::Vm,
+    ParsableTypedNode, RuleType,
+};
+use pest_typed_derive::TypedParser;
+
+const GRAMMAR: &str = r#"
+WHITESPACE = _{ " " }
+ident      = @{ ASCII_ALPHA+ }
+parameter  =  { ident }
+parameters = _{ "(" ~ (parameter ~ ("," ~ parameter)*)? ~ ")" }
+call       =  { ident ~ "(" ~ (ident ~ ("," ~ ident)*)? ~ ")" }
+function   =  { "fn" ~ ident ~ parameters ~ "{" ~ call* ~ "}" }
+"#;
+
+#[derive(TypedParser)]
+#[grammar_inline = r#"
+WHITESPACE = _{ " " }
+ident      = @{ ASCII_ALPHA+ }
+parameter  =  { ident }
+parameters = _{ "(" ~ (parameter ~ ("," ~ parameter)*)? ~ ")" }
+call       =  { ident ~ "(" ~ (ident ~ ("," ~ ident)*)? ~ ")" }
+function   =  { "fn" ~ ident ~ parameters ~ "{" ~ call* ~ "}" }
+"#]
+struct Parser;
+
+/// Render a token tree with rule names, which are the only thing the two rule types share.
+fn render<R: RuleType>(token: &Token<R>) -> String {
+    let children: Vec<_> = token.children.iter().map(render).collect();
+    format!(
+        "{:?}({}..{})[{}]",
+        token.rule,
+        token.start,
+        token.end,
+        children.join(", ")
+    )
+}
+
+fn same_as_generated(input: &str) {
+    let typed = pairs::function::try_parse(input)
+        .map(|function| render(&function.as_token_tree()))
+        .map_err(|e| e.to_string());
+    let vm = Vm::new(GRAMMAR).unwrap();
+    let interpreted = vm
+        .parse("function", input)
+        .map(|node| render(&node.tokens()[0]))
+        .map_err(|e| e.to_string());
+    assert_eq!(typed, interpreted);
+}
+
+#[test]
+fn same_tokens() {
+    same_as_generated("fn f(a, b) { g(a) h() }");
+    same_as_generated("fn f() {}");
+}
+
+#[test]
+fn same_errors() {
+    same_as_generated("fn f(a b) {}");
+    same_as_generated("fn (a) {}");
+    same_as_generated("fn f() { g(a }");
+}
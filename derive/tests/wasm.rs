@@ -0,0 +1,33 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use pest_typed_derive::TypedParser;
+
+#[derive(TypedParser)]
+#[grammar_inline = r#"
+expr   = { number ~ ("+" ~ number)* }
+number = @{ ASCII_DIGIT+ }
+WHITESPACE = _{ " " }
+"#]
+#[emit_wasm]
+struct Parser;
+
+#[test]
+fn parse() {
+    let output = wasm::parse("expr", "1 + 2");
+    assert!(output.ok());
+    let json = output.json();
+    assert!(json.starts_with(r#"{"ok":{"rule":"expr","start":{"offset":0"#));
+    assert_eq!(json.matches(r#""rule":"number""#).count(), 2);
+
+    let output = wasm::parse("number", "1 +");
+    assert!(!output.ok());
+    assert!(output.json().starts_with(r#"{"error":"#));
+    assert!(!wasm::parse("WHITESPACE", " ").ok());
+}
@@ -0,0 +1,173 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Static analysis on optimized rules.
+
+use pest_meta::optimizer::{OptimizedExpr, OptimizedRule};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// A terminal that may start a match.
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub(crate) enum Terminal<'g> {
+    Str(&'g str),
+    Insens(&'g str),
+    Range(char, char),
+    Builtin(&'g str),
+}
+
+/// First set of an expression.
+///
+/// Implicit `WHITESPACE` and `COMMENT` are ignored.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub(crate) struct First<'g> {
+    pub terminals: BTreeSet<Terminal<'g>>,
+    /// Whether it may succeed without consuming any input.
+    pub nullable: bool,
+}
+
+impl<'g> First<'g> {
+    fn empty() -> Self {
+        Self {
+            terminals: BTreeSet::new(),
+            nullable: true,
+        }
+    }
+    fn terminal(terminal: Terminal<'g>, nullable: bool) -> Self {
+        Self {
+            terminals: BTreeSet::from([terminal]),
+            nullable,
+        }
+    }
+    fn nullable(mut self) -> Self {
+        self.nullable = true;
+        self
+    }
+}
+
+/// First set of built-in rules.
+pub(crate) fn builtin(name: &str) -> First<'_> {
+    match name {
+        "SOI" | "DROP" => First::empty(),
+        "EOI" | "PEEK" | "PEEK_ALL" | "POP" | "POP_ALL" => {
+            First::terminal(Terminal::Builtin(name), true)
+        }
+        _ => First::terminal(Terminal::Builtin(name), false),
+    }
+}
+
+/// First set of `expr`, given first sets of defined rules.
+pub(crate) fn first_of<'g>(
+    expr: &'g OptimizedExpr,
+    rules: &BTreeMap<&'g str, First<'g>>,
+) -> First<'g> {
+    match expr {
+        OptimizedExpr::Str(s) if s.is_empty() => First::empty(),
+        OptimizedExpr::Insens(s) if s.is_empty() => First::empty(),
+        OptimizedExpr::Str(s) => First::terminal(Terminal::Str(s), false),
+        OptimizedExpr::Insens(s) => First::terminal(Terminal::Insens(s), false),
+        OptimizedExpr::Range(start, end) => {
+            let start = start.chars().next().unwrap();
+            let end = end.chars().next().unwrap();
+            First::terminal(Terminal::Range(start, end), false)
+        }
+        OptimizedExpr::Ident(name) => match rules.get(name.as_str()) {
+            Some(first) => first.clone(),
+            None => builtin(name),
+        },
+        OptimizedExpr::PeekSlice(_, _) => First::terminal(Terminal::Builtin("PEEK"), true),
+        OptimizedExpr::PosPred(_) | OptimizedExpr::NegPred(_) => First::empty(),
+        OptimizedExpr::Seq(lhs, rhs) => {
+            let mut res = first_of(lhs, rules);
+            if res.nullable {
+                let rhs = first_of(rhs, rules);
+                res.terminals.extend(rhs.terminals);
+                res.nullable = rhs.nullable;
+            }
+            res
+        }
+        OptimizedExpr::Choice(lhs, rhs) => {
+            let mut res = first_of(lhs, rules);
+            let rhs = first_of(rhs, rules);
+            res.terminals.extend(rhs.terminals);
+            res.nullable |= rhs.nullable;
+            res
+        }
+        OptimizedExpr::Opt(expr) | OptimizedExpr::Rep(expr) => first_of(expr, rules).nullable(),
+        #[cfg(feature = "grammar-extras")]
+        OptimizedExpr::RepOnce(expr) => first_of(expr, rules),
+        OptimizedExpr::Skip(_) => First::terminal(Terminal::Builtin("ANY"), true),
+        OptimizedExpr::Push(expr) | OptimizedExpr::RestoreOnErr(expr) => first_of(expr, rules),
+        #[cfg(feature = "grammar-extras")]
+        OptimizedExpr::NodeTag(expr, _) => first_of(expr, rules),
+        #[cfg(feature = "grammar-extras")]
+        OptimizedExpr::PushLiteral(_) => First::empty(),
+    }
+}
+
+/// First sets of all defined rules, computed to a fixed point.
+pub(crate) fn first_sets(rules: &[OptimizedRule]) -> BTreeMap<&str, First<'_>> {
+    let mut res: BTreeMap<&str, First<'_>> = rules
+        .iter()
+        .map(|rule| (rule.name.as_str(), First::default()))
+        .collect();
+    loop {
+        let mut changed = false;
+        for rule in rules {
+            let first = first_of(&rule.expr, &res);
+            if res[rule.name.as_str()] != first {
+                res.insert(rule.name.as_str(), first);
+                changed = true;
+            }
+        }
+        if !changed {
+            return res;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pest_meta::parse_and_optimize;
+
+    #[test]
+    fn first() {
+        let (_, rules) = parse_and_optimize(
+            r#"
+            a = { "x" ~ b | c? ~ 'a'..'z' }
+            b = { b_ ~ "y" | "" }
+            b_ = { "z"* }
+            c = { ^"c" ~ a | SOI ~ EOI }
+            d = { "(" ~ d ~ ")" | ASCII_DIGIT }
+            "#,
+        )
+        .unwrap();
+        let first = first_sets(&rules);
+        let terminals = |name: &str| first[name].terminals.iter().cloned().collect::<Vec<_>>();
+        assert_eq!(
+            terminals("a"),
+            [
+                Terminal::Str("x"),
+                Terminal::Insens("c"),
+                Terminal::Range('a', 'z'),
+                Terminal::Builtin("EOI"),
+            ]
+        );
+        assert!(!first["a"].nullable);
+        assert_eq!(terminals("b"), [Terminal::Str("y"), Terminal::Str("z")]);
+        assert!(first["b"].nullable);
+        assert!(first["b_"].nullable);
+        assert!(first["c"].nullable);
+        assert_eq!(
+            terminals("d"),
+            [Terminal::Str("("), Terminal::Builtin("ASCII_DIGIT")]
+        );
+        assert!(!first["d"].nullable);
+    }
+}
@@ -0,0 +1,435 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Attributes of rules, written in lines before rules, such as `#[highlight = "keyword"]`.
+//!
+//! - `#[highlight = "class"]` sets the highlight class of the rule.
+//! - `#[opaque]` only keeps the span of the rule, without inner typed nodes, like an atomic rule.
+//! - `#[structured]` keeps inner typed nodes of the rule, even if it's atomic.
+//! - `#[lazy]` only builds inner typed nodes of the rule on first access.
+//! - `#[keyword]` makes string literals in the rule keywords of it.
+//! - `#[embed = "Type"]` parses the region matched by the rule with a delegate of type `Type`.
+//! - `#[recover]` synthesizes the closing literal of the rule if it's missing at the end of a line or input.
+//! - `#[trivia = "COMMENT same_line"]` sets the implicit rules skipped between tokens of the rule,
+//!   among `WHITESPACE` and `COMMENT`, and with `same_line`, skipped contents can't contain line breaks.
+//! - `#[alias = "old"]` keeps deprecated aliases named `old` of the rule struct and the rule variant,
+//!   for rules that have been renamed.
+//! - `#[inline_capacity = "2"]` keeps up to 2 items of each repetition in the rule inline,
+//!   with `pest_typed::allocation::Inline` from feature `smallvec` of `pest_typed`.
+//! - `#[cfg(version >= 2)]` only matches the rule in language versions that satisfy the condition,
+//!   which compares `version` with a number with one of `>=`, `>`, `<=`, `<` and `==`.
+//!   Conditions of several such attributes should all be satisfied.
+//!
+//! Attribute lines are blanked out before the grammar is parsed,
+//! so that positions in error messages and warnings still point into the original grammar.
+
+use crate::captures::Captures;
+use crate::regex::Regexes;
+use pest_meta::optimizer::{OptimizedExpr, OptimizedRule};
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use std::collections::{BTreeMap, BTreeSet};
+
+enum Attribute<'s> {
+    Highlight(&'s str),
+    /// `true` for `#[opaque]`, and `false` for `#[structured]`.
+    Opaque(bool),
+    Lazy,
+    Keyword,
+    Embed(&'s str),
+    Recover,
+    Trivia(&'s str),
+    Alias(&'s str),
+    InlineCapacity(usize),
+    /// Inclusive range of versions.
+    Cfg(u32, u32),
+}
+
+/// Attributes of rules in a grammar.
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct Attributes {
+    /// Rules and their highlight classes, in the order they are defined.
+    pub highlights: Vec<(String, String)>,
+    /// Rules with `#[opaque]` (`true`) or `#[structured]` (`false`).
+    pub opaque: BTreeMap<String, bool>,
+    /// Rules with `#[lazy]`.
+    pub lazy: BTreeSet<String>,
+    /// Rules with `#[keyword]`.
+    pub keywords: BTreeSet<String>,
+    /// Rules with `#[embed = "Type"]` and their delegate types.
+    pub embed: BTreeMap<String, String>,
+    /// Rules with `#[recover]`.
+    pub recover: BTreeSet<String>,
+    /// Rules with `#[trivia = "..."]` and their trivia policies.
+    pub trivia: BTreeMap<String, String>,
+    /// Old names in `#[alias = "old"]` and the rules they are renamed to.
+    pub aliases: BTreeMap<String, String>,
+    /// Rules with `#[inline_capacity = "N"]` and their inline capacities.
+    pub inline_capacity: BTreeMap<String, usize>,
+    /// Rules with `#[cfg(version ...)]` and inclusive ranges of versions they are in.
+    pub versions: BTreeMap<String, (u32, u32)>,
+    /// Identifiers that `re"..."` literals are rewritten into, and their compiled regular expressions.
+    pub regexes: Regexes,
+    /// Rules with named captures, and items of their top-level sequences with their names.
+    pub captures: Captures,
+}
+
+/// Parse an attribute line.
+fn attribute(line: &str) -> Option<Attribute<'_>> {
+    let rest = line.trim().strip_prefix("#[")?.strip_suffix(']')?.trim();
+    match rest {
+        "opaque" => return Some(Attribute::Opaque(true)),
+        "structured" => return Some(Attribute::Opaque(false)),
+        "lazy" => return Some(Attribute::Lazy),
+        "keyword" => return Some(Attribute::Keyword),
+        "recover" => return Some(Attribute::Recover),
+        _ => (),
+    }
+    if let Some(condition) = rest.strip_prefix("cfg(") {
+        return versions(condition.strip_suffix(')')?).map(|(min, max)| Attribute::Cfg(min, max));
+    }
+    let (name, rest) = rest.split_once('=')?;
+    let value = rest.trim().strip_prefix('"')?.strip_suffix('"')?;
+    if value.contains('"') {
+        return None;
+    }
+    match name.trim_end() {
+        "highlight" => Some(Attribute::Highlight(value)),
+        "embed" => Some(Attribute::Embed(value)),
+        "trivia" => Some(Attribute::Trivia(value)),
+        "alias" => Some(Attribute::Alias(value)),
+        "inline_capacity" => value.parse().ok().map(Attribute::InlineCapacity),
+        _ => None,
+    }
+}
+
+/// Parse a condition such as `version >= 2` into an inclusive range of versions.
+fn versions(condition: &str) -> Option<(u32, u32)> {
+    let rest = condition.trim().strip_prefix("version")?.trim_start();
+    let (op, number) = [">=", "<=", "==", ">", "<"]
+        .into_iter()
+        .find_map(|op| Some((op, rest.strip_prefix(op)?)))?;
+    let number: u32 = number.trim().parse().ok()?;
+    match op {
+        ">=" => Some((number, u32::MAX)),
+        ">" => Some((number.checked_add(1)?, u32::MAX)),
+        "<=" => Some((0, number)),
+        "<" => Some((0, number.checked_sub(1)?)),
+        _ => Some((number, number)),
+    }
+}
+
+/// Remove attributes from a grammar, and collect them for rules after them.
+///
+/// Returns [`None`] if there is no attribute.
+pub(crate) fn extract(grammar: &str) -> Option<(String, Attributes)> {
+    let mut res = String::with_capacity(grammar.len());
+    let mut attributes = Attributes::default();
+    let mut pending = vec![];
+    for line in grammar.split_inclusive('\n') {
+        if let Some(attribute) = attribute(line) {
+            pending.push(attribute);
+            // One space for each byte, so that positions are kept.
+            res.extend(line.bytes().map(|b| match b {
+                b'\n' | b'\r' | b'\t' => char::from(b),
+                _ => ' ',
+            }));
+            continue;
+        }
+        let trimmed = line.trim_start();
+        if !trimmed.is_empty() && !trimmed.starts_with("//") && !pending.is_empty() {
+            let len = trimmed
+                .find(|c: char| !c.is_alphanumeric() && c != '_')
+                .unwrap_or(trimmed.len());
+            let rule = &trimmed[..len];
+            for attribute in pending.drain(..) {
+                match attribute {
+                    Attribute::Highlight(class) => attributes
+                        .highlights
+                        .push((rule.to_owned(), class.to_owned())),
+                    Attribute::Opaque(opaque) => {
+                        attributes.opaque.insert(rule.to_owned(), opaque);
+                    }
+                    Attribute::Lazy => {
+                        attributes.lazy.insert(rule.to_owned());
+                    }
+                    Attribute::Keyword => {
+                        attributes.keywords.insert(rule.to_owned());
+                    }
+                    Attribute::Embed(delegate) => {
+                        attributes
+                            .embed
+                            .insert(rule.to_owned(), delegate.to_owned());
+                    }
+                    Attribute::Recover => {
+                        attributes.recover.insert(rule.to_owned());
+                    }
+                    Attribute::Trivia(policy) => {
+                        attributes.trivia.insert(rule.to_owned(), policy.to_owned());
+                    }
+                    Attribute::Alias(old) => {
+                        attributes.aliases.insert(old.to_owned(), rule.to_owned());
+                    }
+                    Attribute::InlineCapacity(capacity) => {
+                        attributes.inline_capacity.insert(rule.to_owned(), capacity);
+                    }
+                    Attribute::Cfg(min, max) => {
+                        let range = attributes
+                            .versions
+                            .entry(rule.to_owned())
+                            .or_insert((0, u32::MAX));
+                        *range = (range.0.max(min), range.1.min(max));
+                    }
+                }
+            }
+        }
+        res.push_str(line);
+    }
+    (res != grammar).then_some((res, attributes))
+}
+
+/// Implement `pest_typed::highlight::Highlight` for `Rule`.
+pub(crate) fn generate_highlights(classes: &[(String, String)]) -> TokenStream {
+    let pest_typed = crate::graph::pest_typed();
+    let arms = classes.iter().map(|(rule, class)| {
+        let rule = format_ident!("r#{}", rule);
+        quote! { Self::#rule => ::core::option::Option::Some(#class), }
+    });
+    quote! {
+        impl #pest_typed::highlight::Highlight for Rule {
+            #[allow(unreachable_patterns)]
+            fn highlight(self) -> ::core::option::Option<&'static ::core::primitive::str> {
+                match self {
+                    #(#arms)*
+                    _ => ::core::option::Option::None,
+                }
+            }
+        }
+    }
+}
+
+/// Deprecated constants of `Rule` for old names of renamed rules.
+pub(crate) fn generate_aliases(aliases: &BTreeMap<String, String>) -> TokenStream {
+    let consts = aliases.iter().map(|(old, new)| {
+        let note = format!("Renamed to `{new}`.");
+        let (old, new) = (format_ident!("r#{}", old), format_ident!("r#{}", new));
+        quote! {
+            #[deprecated(note = #note)]
+            pub const #old: Self = Self::#new;
+        }
+    });
+    quote! {
+        #[allow(non_upper_case_globals)]
+        impl Rule {
+            #(#consts)*
+        }
+    }
+}
+
+/// Collect string literals in an expression, without following references to other rules.
+fn literals<'g>(expr: &'g OptimizedExpr, res: &mut Vec<(&'g str, bool)>) {
+    match expr {
+        OptimizedExpr::Str(s) | OptimizedExpr::Insens(s) if s.is_empty() => (),
+        OptimizedExpr::Str(s) => res.push((s, false)),
+        OptimizedExpr::Insens(s) => res.push((s, true)),
+        OptimizedExpr::Seq(lhs, rhs) | OptimizedExpr::Choice(lhs, rhs) => {
+            literals(lhs, res);
+            literals(rhs, res);
+        }
+        OptimizedExpr::PosPred(expr)
+        | OptimizedExpr::NegPred(expr)
+        | OptimizedExpr::Opt(expr)
+        | OptimizedExpr::Rep(expr)
+        | OptimizedExpr::Push(expr)
+        | OptimizedExpr::RestoreOnErr(expr) => literals(expr, res),
+        #[cfg(feature = "grammar-extras")]
+        OptimizedExpr::RepOnce(expr) | OptimizedExpr::NodeTag(expr, _) => literals(expr, res),
+        _ => (),
+    }
+}
+
+/// Implement `pest_typed::keyword::Keywords` for `Rule`,
+/// with literals in rules marked with `#[keyword]` or highlighted as `keyword`.
+pub(crate) fn generate_keywords(rules: &[OptimizedRule], attributes: &Attributes) -> TokenStream {
+    let pest_typed = crate::graph::pest_typed();
+    let marked = |name: &str| {
+        attributes.keywords.contains(name)
+            || attributes
+                .highlights
+                .iter()
+                .any(|(rule, class)| rule == name && class == "keyword")
+    };
+    let mut keywords = vec![];
+    for rule in rules.iter().filter(|rule| marked(&rule.name)) {
+        let mut found = vec![];
+        literals(&rule.expr, &mut found);
+        let name = format_ident!("r#{}", rule.name);
+        let mut seen = BTreeSet::new();
+        for (text, insensitive) in found {
+            if seen.insert((text, insensitive)) {
+                keywords.push(quote! {
+                    #pest_typed::keyword::Keyword {
+                        text: #text,
+                        rule: Self::#name,
+                        insensitive: #insensitive,
+                    }
+                });
+            }
+        }
+    }
+    if keywords.is_empty() {
+        return quote! {};
+    }
+    quote! {
+        impl #pest_typed::keyword::Keywords for Rule {
+            const KEYWORDS: &'static [#pest_typed::keyword::Keyword<Self>] = &[#(#keywords),*];
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::extract;
+
+    #[test]
+    fn highlights() {
+        assert_eq!(extract(r#"a = { "a" }"#), None);
+        let (grammar, attributes) = extract(
+            "#[highlight = \"keyword\"]\n/// Doc.\nkw = { \"if\" }\n  #[highlight=\"number\"]\nnum = @{ ASCII_DIGIT+ }\n",
+        )
+        .unwrap();
+        assert_eq!(
+            grammar,
+            "                        \n/// Doc.\nkw = { \"if\" }\n                       \nnum = @{ ASCII_DIGIT+ }\n",
+        );
+        assert_eq!(
+            attributes.highlights,
+            [
+                ("kw".to_owned(), "keyword".to_owned()),
+                ("num".to_owned(), "number".to_owned()),
+            ]
+        );
+        // Tags are not attributes.
+        assert_eq!(extract("a = { #b = c }"), None);
+    }
+
+    #[test]
+    fn positions() {
+        let grammar = "#[highlight = \"üüüü\"]\r\na = { \"ü\"key: \"x\" }";
+        let (rewritten, _) = extract(grammar).unwrap();
+        assert_eq!(rewritten.len(), grammar.len());
+        assert_eq!(rewritten.find("key"), grammar.find("key"));
+        assert!(rewritten.starts_with(&format!("{}\r\na", " ".repeat(25))));
+    }
+
+    #[test]
+    fn opaque() {
+        let (_, attributes) = extract(
+            "#[opaque]\n#[highlight = \"string\"]\n#[recover]\na = { b }\n#[structured]\n#[lazy]\nb = @{ \"b\" }\n",
+        )
+        .unwrap();
+        assert_eq!(
+            attributes.highlights,
+            [("a".to_owned(), "string".to_owned())]
+        );
+        assert_eq!(
+            attributes.opaque.into_iter().collect::<Vec<_>>(),
+            [("a".to_owned(), true), ("b".to_owned(), false)]
+        );
+        assert_eq!(attributes.lazy.into_iter().collect::<Vec<_>>(), ["b"]);
+        assert_eq!(attributes.recover.into_iter().collect::<Vec<_>>(), ["a"]);
+    }
+
+    #[test]
+    fn versions() {
+        let (_, attributes) = extract(
+            "#[cfg(version >= 2)]
+#[cfg(version<5)]
+a = { \"a\" }
+#[cfg(version == 3)]
+b = { \"b\" }
+#[cfg(version > 1)]
+c = { \"c\" }
+",
+        )
+        .unwrap();
+        assert_eq!(
+            attributes.versions.into_iter().collect::<Vec<_>>(),
+            [
+                ("a".to_owned(), (2, 4)),
+                ("b".to_owned(), (3, 3)),
+                ("c".to_owned(), (2, u32::MAX)),
+            ]
+        );
+        assert_eq!(
+            extract(
+                "#[cfg(feature = \"x\")]
+a = { \"a\" }
+"
+            ),
+            None
+        );
+        assert_eq!(
+            extract(
+                "#[cfg(version < 0)]
+a = { \"a\" }
+"
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn embed() {
+        let (_, attributes) = extract(
+            "#[embed = \"Grammar<json::Rule, json::rules::value<'i>>\"]\na = @{ \"{\" ~ (!\"}\" ~ ANY)* ~ \"}\" }\n#[embed \"b\"]\nb = { \"b\" }\n",
+        )
+        .unwrap();
+        assert_eq!(
+            attributes.embed.into_iter().collect::<Vec<_>>(),
+            [(
+                "a".to_owned(),
+                "Grammar<json::Rule, json::rules::value<'i>>".to_owned()
+            )]
+        );
+    }
+
+    #[test]
+    fn trivia() {
+        let (_, attributes) = extract(
+            "#[trivia = \"COMMENT same_line\"]\na = { b ~ b }\n#[trivia = \"\"]\nb = { \"b\" }\n",
+        )
+        .unwrap();
+        assert_eq!(
+            attributes.trivia.into_iter().collect::<Vec<_>>(),
+            [
+                ("a".to_owned(), "COMMENT same_line".to_owned()),
+                ("b".to_owned(), "".to_owned())
+            ]
+        );
+    }
+
+    #[test]
+    fn aliases() {
+        let (_, attributes) = extract(
+            "#[alias = \"item\"]
+#[alias = \"entry\"]
+element = { \"e\" }
+",
+        )
+        .unwrap();
+        assert_eq!(
+            attributes.aliases.into_iter().collect::<Vec<_>>(),
+            [
+                ("entry".to_owned(), "element".to_owned()),
+                ("item".to_owned(), "element".to_owned())
+            ]
+        );
+    }
+}
@@ -0,0 +1,108 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Generate typed parsers from build scripts instead of the derive macro.
+//!
+//! The derive macro runs again whenever the crate is compiled, and IDEs have to expand it as well.
+//! Large grammars can be generated ahead of time in `build.rs` instead:
+//!
+//! ```no_run
+//! // build.rs
+//! pest_typed_generator::build::write(
+//!     r#"
+//!         #[grammar = "src/grammar.pest"]
+//!         #[emit_rule_reference]
+//!         struct Parser;
+//!     "#,
+//!     "parser.rs",
+//! )
+//! .unwrap();
+//! ```
+//!
+//! And include the generated file next to the parser struct:
+//!
+//! ```ignore
+//! struct Parser;
+//! include!(concat!(env!("OUT_DIR"), "/parser.rs"));
+//! ```
+
+use crate::helper::{get_string, resolve_path};
+use crate::typed::derive_typed_parser;
+use proc_macro2::TokenStream;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use syn::DeriveInput;
+
+fn parse(input: &str) -> TokenStream {
+    match input.parse() {
+        Ok(tokens) => tokens,
+        Err(error) => panic!("invalid parser definition: {}", error),
+    }
+}
+
+/// Generate the same codes as `#[derive(TypedParser)]` on `input`,
+/// which is the definition of the parser struct with its attributes.
+///
+/// # Panics
+///
+/// Panics if `input` is not a valid struct definition, like the derive macro.
+pub fn generate(input: &str) -> String {
+    derive_typed_parser(parse(input), true).to_string()
+}
+
+/// Generate codes with [`generate`] and write them to `file` in `OUT_DIR`.
+///
+/// Also tells cargo to run the build script again when grammar files change.
+/// Returns the path of the written file.
+///
+/// # Panics
+///
+/// Panics if `OUT_DIR` is not set, which is only set for build scripts,
+/// or if `input` is not a valid struct definition.
+pub fn write(input: &str, file: impl AsRef<Path>) -> io::Result<PathBuf> {
+    let out_dir = env::var_os("OUT_DIR").expect("`OUT_DIR` is only set for build scripts");
+    let path = Path::new(&out_dir).join(file);
+    let tokens = parse(input);
+    let ast: DeriveInput = syn::parse2(tokens.clone()).unwrap();
+    for attr in &ast.attrs {
+        if attr.meta.path().is_ident("grammar") {
+            let grammar = resolve_path(&get_string(attr));
+            println!("cargo:rerun-if-changed={}", grammar.display());
+        }
+    }
+    fs::write(&path, derive_typed_parser(tokens, true).to_string())?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::generate;
+    use crate::derive_typed_parser;
+    use quote::quote;
+
+    #[test]
+    fn same_as_derive() {
+        let input = r#"
+            #[grammar_inline = "a = { \"a\" ~ b } b = @{ ASCII_DIGIT+ }"]
+            #[emit_rule_reference]
+            struct Parser;
+        "#;
+        let derived = derive_typed_parser(
+            quote! {
+                #[grammar_inline = "a = { \"a\" ~ b } b = @{ ASCII_DIGIT+ }"]
+                #[emit_rule_reference]
+                struct Parser;
+            },
+            true,
+        );
+        assert_eq!(generate(input), derived.to_string());
+    }
+}
@@ -0,0 +1,290 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Named captures such as `key: ident ~ "=" ~ value: expr`.
+//!
+//! Names are blanked out before the grammar is parsed,
+//! so that positions in error messages and warnings still point into the original grammar.
+//! Then items of the top-level sequence of each rule with named captures are optimized on their own,
+//! so that the optimizer doesn't rotate or merge them across their boundaries,
+//! and the rule is generated as a sequence with fields named after its captures.
+
+use pest::error::{Error, ErrorVariant};
+use pest::iterators::Pairs;
+use pest::Span;
+use pest_meta::ast::{Expr, Rule as AstRule};
+use pest_meta::optimizer::{optimize as optimize_rules, OptimizedExpr, OptimizedRule};
+use pest_meta::parser::Rule;
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+/// Rules with named captures, and items of their top-level sequences with their names.
+pub(crate) type Captures = BTreeMap<String, Vec<(Option<String>, OptimizedExpr)>>;
+
+/// A named capture in a grammar.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Capture {
+    /// Name of the capture.
+    pub name: String,
+    /// Range of the name and the colon after it, such as `key:`.
+    pub range: Range<usize>,
+}
+
+/// Blank out names of named captures in a grammar,
+/// and return the result with the captures in the order they are written.
+///
+/// Returns [`None`] if there is no named capture.
+pub(crate) fn rewrite(grammar: &str) -> Option<(String, Vec<Capture>)> {
+    let bytes = grammar.as_bytes();
+    let mut res = String::with_capacity(grammar.len());
+    let mut captures = Vec::new();
+    let mut i = 0;
+    let is_ident = |b: u8| b.is_ascii_alphanumeric() || b == b'_';
+    while i < bytes.len() {
+        let start = i;
+        match bytes[i] {
+            quote @ (b'"' | b'\'') => {
+                i += 1;
+                while i < bytes.len() && bytes[i] != quote {
+                    i += if bytes[i] == b'\\' { 2 } else { 1 };
+                }
+                i = (i + 1).min(bytes.len());
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'/') => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    i += 1;
+                }
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                i = grammar[i + 2..]
+                    .find("*/")
+                    .map_or(bytes.len(), |end| i + 2 + end + 2);
+            }
+            b if b.is_ascii_alphabetic() || b == b'_' => {
+                while i < bytes.len() && is_ident(bytes[i]) {
+                    i += 1;
+                }
+                let mut colon = i;
+                while colon < bytes.len() && matches!(bytes[colon], b' ' | b'\t') {
+                    colon += 1;
+                }
+                if bytes.get(colon) == Some(&b':') {
+                    captures.push(Capture {
+                        name: grammar[start..i].to_owned(),
+                        range: start..colon + 1,
+                    });
+                    i = colon + 1;
+                    res.extend(grammar[start..i].chars().map(|c| match c {
+                        '\t' => '\t',
+                        _ => ' ',
+                    }));
+                    continue;
+                }
+            }
+            _ => {
+                i += grammar[i..].chars().next().map_or(1, char::len_utf8);
+            }
+        }
+        res.push_str(&grammar[start..i]);
+    }
+    (!captures.is_empty()).then_some((res, captures))
+}
+
+fn error(grammar: &str, range: &Range<usize>, message: String) -> Error<Rule> {
+    let span = Span::new(grammar, range.start, range.end).unwrap();
+    Error::new_from_span(ErrorVariant::CustomError { message }, span)
+}
+
+/// Find the items that `captures` name in the rewritten grammar parsed into `pairs`,
+/// and return names of items of the top-level sequence of each rule with named captures.
+///
+/// Returns an error at the first capture that isn't on an item of a top-level sequence,
+/// or that has the same name as another capture in the same rule.
+pub(crate) fn collect(
+    grammar: &str,
+    pairs: Pairs<'_, Rule>,
+    captures: &[Capture],
+) -> Result<BTreeMap<String, Vec<Option<String>>>, Error<Rule>> {
+    let mut rules = Vec::new();
+    for pair in pairs.filter(|pair| pair.as_rule() == Rule::grammar_rule) {
+        let span = pair.as_span();
+        let mut inner = pair.into_inner();
+        let Some(name) = inner
+            .next()
+            .filter(|pair| pair.as_rule() == Rule::identifier)
+        else {
+            continue;
+        };
+        let expression = inner
+            .find(|pair| pair.as_rule() == Rule::expression)
+            .unwrap();
+        let mut items = expression.into_inner();
+        let sequence = items
+            .clone()
+            .all(|pair| pair.as_rule() != Rule::choice_operator);
+        let terms: Vec<_> = items
+            .by_ref()
+            .filter(|pair| pair.as_rule() == Rule::term)
+            .map(|term| term.as_span().start())
+            .collect();
+        rules.push((name.as_str(), span.start()..span.end(), sequence, terms));
+    }
+    let mut res = BTreeMap::<String, Vec<Option<String>>>::new();
+    for capture in captures {
+        let Capture { name, range } = capture;
+        let item = rules.iter().find_map(|(rule, span, sequence, terms)| {
+            if !span.contains(&range.start) || !sequence || terms.len() < 2 {
+                return None;
+            }
+            let index = terms.iter().position(|&start| {
+                start >= range.end && grammar[range.end..start].trim().is_empty()
+            })?;
+            Some((*rule, terms.len(), index))
+        });
+        let Some((rule, len, index)) = item else {
+            let message = String::from(
+                "named captures are only supported on items of top-level sequences of rules",
+            );
+            return Err(error(grammar, range, message));
+        };
+        let names = res
+            .entry(rule.to_owned())
+            .or_insert_with(|| vec![None; len]);
+        if names.iter().flatten().any(|used| used == name) {
+            let message = format!("named capture `{name}` is used more than once in rule `{rule}`");
+            return Err(error(grammar, range, message));
+        }
+        names[index] = Some(name.clone());
+    }
+    Ok(res)
+}
+
+/// Split a sequence into `len` items,
+/// as sequences are built from left to right by the parser of [`pest_meta`].
+fn split(mut expr: Expr, len: usize) -> Vec<Expr> {
+    let mut items = Vec::with_capacity(len);
+    for _ in 1..len {
+        match expr {
+            Expr::Seq(lhs, rhs) => {
+                items.push(*rhs);
+                expr = *lhs;
+            }
+            _ => unreachable!("sequence with {len} items"),
+        }
+    }
+    items.push(expr);
+    items.reverse();
+    items
+}
+
+/// Optimize rules, and items of top-level sequences of rules with named captures on their own.
+pub(crate) fn optimize(
+    mut rules: Vec<AstRule>,
+    names: BTreeMap<String, Vec<Option<String>>>,
+) -> (Vec<OptimizedRule>, Captures) {
+    // Items are optimized as extra rules, whose names can't be names of rules in grammars.
+    let mut items = Vec::new();
+    for rule in rules.iter().filter(|rule| names.contains_key(&rule.name)) {
+        let len = names[&rule.name].len();
+        items.extend(
+            split(rule.expr.clone(), len)
+                .into_iter()
+                .enumerate()
+                .map(|(i, expr)| AstRule {
+                    name: format!("{} {}", rule.name, i),
+                    ty: rule.ty,
+                    expr,
+                }),
+        );
+    }
+    let count = rules.len();
+    rules.extend(items);
+    let mut optimized = optimize_rules(rules);
+    let mut items = optimized.split_off(count).into_iter();
+    let captures = names
+        .into_iter()
+        .map(|(rule, names)| {
+            let items = names
+                .into_iter()
+                .zip(items.by_ref())
+                .map(|(name, item)| (name, item.expr))
+                .collect();
+            (rule, items)
+        })
+        .collect();
+    (optimized, captures)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{collect, optimize, rewrite, Capture};
+    use pest_meta::optimizer::OptimizedExpr;
+    use pest_meta::parser::{consume_rules, parse, Rule};
+
+    #[test]
+    fn captures() {
+        assert_eq!(rewrite(r#"a = { "a" ~ b }"#), None);
+        let (grammar, captures) = rewrite(r#"a = { key: b ~ ":" ~ value : (c | d) }"#).unwrap();
+        assert_eq!(grammar, r#"a = {      b ~ ":" ~         (c | d) }"#);
+        assert_eq!(
+            captures,
+            [
+                Capture {
+                    name: "key".to_owned(),
+                    range: 6..10,
+                },
+                Capture {
+                    name: "value".to_owned(),
+                    range: 21..28,
+                },
+            ],
+        );
+        assert_eq!(
+            rewrite("a = { x ~ k:b } // c: d\n/* e: f */").unwrap().0,
+            "a = { x ~   b } // c: d\n/* e: f */",
+        );
+        assert_eq!(
+            rewrite(r#"a = { 'a'..'z' ~ "\"好: " ~ k: b }"#).unwrap().0,
+            r#"a = { 'a'..'z' ~ "\"好: " ~    b }"#,
+        );
+    }
+
+    #[test]
+    fn items() {
+        let (grammar, captures) = rewrite(
+            "a = { k: (b ~ c) ~ d ~ v: e{2} }\nb = { \"b\" }\nc = { b }\nd = { b }\ne = { b }",
+        )
+        .unwrap();
+        let pairs = parse(Rule::grammar_rules, &grammar).unwrap();
+        let names = collect(&grammar, pairs.clone(), &captures).unwrap();
+        assert_eq!(
+            names["a"],
+            [Some("k".to_owned()), None, Some("v".to_owned())]
+        );
+        let (rules, captures) = optimize(consume_rules(pairs).unwrap(), names);
+        assert_eq!(rules.len(), 5);
+        let items = &captures["a"];
+        assert_eq!(items.len(), 3);
+        assert!(matches!(items[0].1, OptimizedExpr::Seq(_, _)));
+        assert_eq!(items[1].1, OptimizedExpr::Ident("d".to_owned()));
+        assert!(matches!(items[2].1, OptimizedExpr::Seq(_, _)));
+
+        for (grammar, message) in [
+            ("a = { k: b | c }", "only supported on items"),
+            ("a = { (k: b ~ c) ~ d }", "only supported on items"),
+            ("a = { k: b }", "only supported on items"),
+            ("a = { k: b ~ k: c }", "used more than once in rule `a`"),
+        ] {
+            let (grammar, captures) = rewrite(grammar).unwrap();
+            let pairs = parse(Rule::grammar_rules, &grammar).unwrap();
+            let error = collect(&grammar, pairs, &captures).unwrap_err();
+            assert!(error.to_string().contains(message), "{error}");
+        }
+    }
+}
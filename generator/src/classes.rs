@@ -0,0 +1,104 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Unicode property classes such as `\p{Script=Greek}`.
+//!
+//! They are rewritten into builtin rules of the properties (`GREEK`) before the grammar is parsed,
+//! padded with spaces, so that positions in error messages and warnings still point into the original grammar.
+//! Names are resolved with [`pest_typed::unicode::resolve`], the same as at runtime.
+
+use pest::{
+    error::{Error, ErrorVariant},
+    Span,
+};
+use pest_meta::parser::Rule;
+use pest_typed::unicode::resolve;
+
+/// Rewrite `\p{...}` classes in a grammar into builtin rules.
+///
+/// Returns `Ok(None)` if there is no class, and an error at the first unknown one.
+pub(crate) fn rewrite(grammar: &str) -> Result<Option<String>, Error<Rule>> {
+    let bytes = grammar.as_bytes();
+    let mut res = String::with_capacity(grammar.len());
+    let mut found = false;
+    let mut i = 0;
+    while i < bytes.len() {
+        let start = i;
+        match bytes[i] {
+            quote @ (b'"' | b'\'') => {
+                i += 1;
+                while i < bytes.len() && bytes[i] != quote {
+                    i += if bytes[i] == b'\\' { 2 } else { 1 };
+                }
+                i = (i + 1).min(bytes.len());
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'/') => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    i += 1;
+                }
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                i = grammar[i + 2..]
+                    .find("*/")
+                    .map_or(bytes.len(), |end| i + 2 + end + 2);
+            }
+            b'\\' if grammar[i..].starts_with("\\p{") => {
+                let end = grammar[i..].find('}').map(|end| i + end + 1);
+                let class = end.map(|end| &grammar[i + 3..end - 1]);
+                match end.zip(class.and_then(resolve).map(|(_, name)| name)) {
+                    Some((end, name)) => {
+                        found = true;
+                        res.push_str(name);
+                        res.extend(std::iter::repeat_n(' ', end - i - name.len()));
+                        i = end;
+                        continue;
+                    }
+                    None => {
+                        let end = end.unwrap_or(i + 3);
+                        let message = match class {
+                            Some(class) => format!("unknown Unicode property `{}`", class),
+                            None => "unclosed Unicode property class".to_owned(),
+                        };
+                        return Err(Error::new_from_span(
+                            ErrorVariant::CustomError { message },
+                            Span::new(grammar, i, end).unwrap(),
+                        ));
+                    }
+                }
+            }
+            _ => {
+                i += grammar[i..].chars().next().map_or(1, char::len_utf8);
+            }
+        }
+        res.push_str(&grammar[start..i]);
+    }
+    Ok(found.then_some(res))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::rewrite;
+
+    #[test]
+    fn classes() {
+        assert_eq!(rewrite(r#"a = { "\\p{Greek}" }"#).unwrap(), None);
+        assert_eq!(
+            rewrite(r#"a = { \p{Script=Greek}+ ~ \p{xid start} | \p{gc=Lowercase_Letter} }"#)
+                .unwrap()
+                .unwrap(),
+            r#"a = { GREEK           + ~ XID_START     | LOWERCASE_LETTER        }"#,
+        );
+        let error = rewrite("a = { 'a'..'z' | \\p{sc=Letter} }").unwrap_err();
+        assert_eq!(
+            error.variant.message(),
+            "unknown Unicode property `sc=Letter`"
+        );
+        assert!(rewrite("// \\p{x}\na = { \\p{Greek").is_err());
+    }
+}
@@ -15,6 +15,25 @@ pub(crate) struct Config {
     pub truncate_accesser_at_node_tag: bool,
     pub simulate_pair_api: bool,
     pub no_warnings: bool,
+    pub emit_fold: bool,
+    pub emit_reflection: bool,
+    pub flatten_wrappers: bool,
+    pub emit_display: bool,
+    pub named_choices: bool,
+    pub module_per_rule: bool,
+    pub report_code_size: bool,
+    pub emit_scanner: bool,
+    pub emit_syntax: bool,
+    pub emit_owned: bool,
+    pub do_not_emit_boundaries: bool,
+    pub emit_events: bool,
+    pub emit_branches: bool,
+    pub non_exhaustive_nodes: bool,
+    pub emit_cache: bool,
+    pub emit_dyn: bool,
+    pub grammar_tests: bool,
+    pub grammar_unit_tests: bool,
+    pub emit_wasm: bool,
 }
 impl Default for Config {
     fn default() -> Self {
@@ -25,6 +44,25 @@ impl Default for Config {
             truncate_accesser_at_node_tag: true,
             simulate_pair_api: false,
             no_warnings: false,
+            emit_fold: false,
+            emit_reflection: false,
+            flatten_wrappers: false,
+            emit_display: false,
+            named_choices: false,
+            module_per_rule: false,
+            report_code_size: false,
+            emit_scanner: false,
+            emit_syntax: false,
+            emit_owned: false,
+            do_not_emit_boundaries: false,
+            emit_events: false,
+            emit_branches: false,
+            non_exhaustive_nodes: false,
+            emit_cache: false,
+            emit_dyn: false,
+            grammar_tests: false,
+            grammar_unit_tests: false,
+            emit_wasm: false,
         }
     }
 }
@@ -0,0 +1,90 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Generate `parse_dyn`, which parses an input as a rule chosen by its name at runtime,
+//! and module `wasm`, which exports the same to JavaScript.
+
+use crate::graph::pest_typed;
+use pest_meta::{ast::RuleType, optimizer::OptimizedRule};
+use proc_macro2::{Ident, TokenStream};
+use quote::{format_ident, quote};
+
+/// Names and identifiers of rules that can be parsed by names.
+fn entry_rules(rules: &[OptimizedRule]) -> (Vec<&str>, Vec<Ident>) {
+    // Silent rules have no pairs, so they are not entry points.
+    rules
+        .iter()
+        .filter(|rule| rule.ty != RuleType::Silent)
+        .map(|rule| (rule.name.as_str(), format_ident!("r#{}", rule.name)))
+        .unzip()
+}
+
+pub(crate) fn generate_dyn(rules: &[OptimizedRule]) -> TokenStream {
+    let pest_typed = pest_typed();
+    let (names, idents) = entry_rules(rules);
+    let doc = format!(
+        "Rules that [`parse_dyn`] accepts: {}.",
+        names
+            .iter()
+            .map(|name| format!("`{name}`"))
+            .collect::<Vec<_>>()
+            .join(", "),
+    );
+    quote! {
+        #[doc = #doc]
+        pub const DYN_RULES: &[&::core::primitive::str] = &[#(#names),*];
+        #[doc = "Parse the whole input as the rule named `rule`, and view the tree as a `pest_typed::dynamic::DynNode`."]
+        #[doc = ""]
+        #[doc = "Returns an error at the start of the input if no rule in [`DYN_RULES`] is named `rule`."]
+        #[allow(dead_code)]
+        pub fn parse_dyn<'i>(
+            rule: &::core::primitive::str,
+            input: &'i ::core::primitive::str,
+        ) -> ::core::result::Result<#pest_typed::dynamic::DynNode<'i, Rule>, #pest_typed::error::Error<Rule>> {
+            match rule {
+                #(
+                    #names => {
+                        let tree = <rules::#idents<'i> as #pest_typed::ParsableTypedNode<'i, Rule>>::try_parse(input)?;
+                        ::core::result::Result::Ok(#pest_typed::dynamic::DynNode::from_pair(&tree))
+                    }
+                )*
+                _ => ::core::result::Result::Err(#pest_typed::dynamic::unknown_rule(rule, input)),
+            }
+        }
+    }
+}
+
+/// Generate module `wasm` with function `parse` exported to JavaScript. See `pest_typed::wasm`.
+pub(crate) fn generate_wasm(rules: &[OptimizedRule]) -> TokenStream {
+    let pest_typed = pest_typed();
+    let (names, idents) = entry_rules(rules);
+    quote! {
+        #[doc = "Parsing from JavaScript, with feature `wasm-bindgen` of `pest_typed`."]
+        pub mod wasm {
+            use #pest_typed::wasm::{wasm_bindgen::{self, prelude::wasm_bindgen}, ParseOutput};
+            #[doc = "Parse the whole input as the rule named `rule`, and convert the token tree or the error into JSON."]
+            #[wasm_bindgen(wasm_bindgen = wasm_bindgen)]
+            pub fn parse(rule: &::core::primitive::str, input: &::core::primitive::str) -> ParseOutput {
+                match rule {
+                    #(
+                        #names => ParseOutput::new(
+                            &<super::rules::#idents<'_> as #pest_typed::ParsableTypedNode<'_, super::Rule>>::try_parse(input)
+                                .map(|tree| #pest_typed::iterators::Pair::as_token_tree(&tree)),
+                            input,
+                        ),
+                    )*
+                    _ => ParseOutput::new::<super::Rule>(
+                        &::core::result::Result::Err(#pest_typed::dynamic::unknown_rule(rule, input)),
+                        input,
+                    ),
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,196 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Generate module `events`, which matches the grammar with `pest_typed::events::Driver` without building trees.
+
+use crate::graph::pest_typed;
+use pest::unicode::unicode_property_names;
+use pest_meta::{
+    ast::RuleType,
+    optimizer::{OptimizedExpr, OptimizedRule},
+};
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use std::collections::BTreeSet;
+
+fn builtin(name: &str) -> TokenStream {
+    let ascii = |f: TokenStream| quote! {d.char_by(|c: ::core::primitive::char| #f)};
+    match name {
+        "ANY" => quote! {d.any()},
+        "SOI" => quote! {d.soi()},
+        "NEWLINE" => quote! {d.newline()},
+        "PEEK" => quote! {d.peek(false)},
+        "POP" => quote! {d.peek(true)},
+        "PEEK_ALL" => quote! {d.peek_all(false)},
+        "POP_ALL" => quote! {d.peek_all(true)},
+        "DROP" => quote! {d.drop_top()},
+        "ASCII_DIGIT" => ascii(quote! {c.is_ascii_digit()}),
+        "ASCII_NONZERO_DIGIT" => ascii(quote! {('1'..='9').contains(&c)}),
+        "ASCII_BIN_DIGIT" => ascii(quote! {('0'..='1').contains(&c)}),
+        "ASCII_OCT_DIGIT" => ascii(quote! {('0'..='7').contains(&c)}),
+        "ASCII_HEX_DIGIT" => ascii(quote! {c.is_ascii_hexdigit()}),
+        "ASCII_ALPHA_LOWER" => ascii(quote! {c.is_ascii_lowercase()}),
+        "ASCII_ALPHA_UPPER" => ascii(quote! {c.is_ascii_uppercase()}),
+        "ASCII_ALPHA" => ascii(quote! {c.is_ascii_alphabetic()}),
+        "ASCII_ALPHANUMERIC" => ascii(quote! {c.is_ascii_alphanumeric()}),
+        "ASCII" => ascii(quote! {c.is_ascii()}),
+        name if unicode_property_names().any(|property| property == name) => {
+            let pest_typed = pest_typed();
+            let property = format_ident!("{}", name);
+            quote! {d.char_by(#pest_typed::unicode::#property)}
+        }
+        // Undefined `WHITESPACE` and `COMMENT`.
+        _ => quote! {false},
+    }
+}
+
+fn expr(expr: &OptimizedExpr, defined: &BTreeSet<&str>) -> TokenStream {
+    let closure = |e: &OptimizedExpr| {
+        let e = self::expr(e, defined);
+        quote! {|d| #e}
+    };
+    match expr {
+        OptimizedExpr::Str(s) => quote! {d.string(#s)},
+        OptimizedExpr::Insens(s) => quote! {d.insensitive(#s)},
+        OptimizedExpr::Range(start, end) => {
+            let start = start.chars().next().unwrap();
+            let end = end.chars().next().unwrap();
+            quote! {d.range(#start, #end)}
+        }
+        OptimizedExpr::Ident(name) if defined.contains(name.as_str()) || name == "EOI" => {
+            let name = format_ident!("r#{}", name);
+            quote! {#name(d)}
+        }
+        OptimizedExpr::Ident(name) => builtin(name),
+        OptimizedExpr::PeekSlice(start, end) => {
+            let end = match end {
+                Some(end) => quote! {::core::option::Option::Some(#end)},
+                None => quote! {::core::option::Option::None},
+            };
+            quote! {d.peek_slice(#start, #end)}
+        }
+        OptimizedExpr::PosPred(e) => {
+            let f = closure(e);
+            quote! {d.positive(#f)}
+        }
+        OptimizedExpr::NegPred(e) => {
+            let f = closure(e);
+            quote! {d.negative(#f)}
+        }
+        OptimizedExpr::Seq(lhs, rhs) => {
+            let (lhs, rhs) = (self::expr(lhs, defined), self::expr(rhs, defined));
+            quote! {(#lhs && d.skip() && #rhs)}
+        }
+        OptimizedExpr::Choice(lhs, rhs) => {
+            let (lhs, rhs) = (closure(lhs), self::expr(rhs, defined));
+            quote! {(d.attempt(#lhs) || #rhs)}
+        }
+        OptimizedExpr::Opt(e) => {
+            let f = closure(e);
+            quote! {{ d.attempt(#f); true }}
+        }
+        OptimizedExpr::Rep(e) => {
+            let f = closure(e);
+            quote! {d.repeat(0, #f)}
+        }
+        #[cfg(feature = "grammar-extras")]
+        OptimizedExpr::RepOnce(e) => {
+            let f = closure(e);
+            quote! {d.repeat(1, #f)}
+        }
+        OptimizedExpr::Skip(strings) => quote! {d.skip_until(&[#(#strings),*])},
+        OptimizedExpr::Push(e) => {
+            let f = closure(e);
+            quote! {d.push(#f)}
+        }
+        #[cfg(feature = "grammar-extras")]
+        OptimizedExpr::PushLiteral(_) => panic!("`PUSH_LITERAL` is not supported yet."),
+        #[cfg(feature = "grammar-extras")]
+        OptimizedExpr::NodeTag(e, _) => self::expr(e, defined),
+        OptimizedExpr::RestoreOnErr(e) => {
+            let f = closure(e);
+            quote! {d.attempt(#f)}
+        }
+    }
+}
+
+pub(crate) fn generate_events(rules: &[OptimizedRule]) -> TokenStream {
+    let pest_typed = pest_typed();
+    let root = quote! {#pest_typed::events};
+    let (driver, generics) = (
+        |rule: &TokenStream| quote! {#root::Driver<'i, '_, #rule, S>},
+        |rule: &TokenStream| quote! {<'i, S: #root::Sink<'i, #rule> + ?::core::marker::Sized>},
+    );
+    // Functions of rules are in a module of their own, so that they don't conflict with others.
+    let (rule_enum, rules_driver, rules_generics) = {
+        let rule_enum = quote! {super::super::Rule};
+        (rule_enum.clone(), driver(&rule_enum), generics(&rule_enum))
+    };
+    let defined: BTreeSet<_> = rules.iter().map(|rule| rule.name.as_str()).collect();
+    let functions = rules.iter().map(|rule| {
+        let name = format_ident!("r#{}", rule.name);
+        let kind = match rule.ty {
+            RuleType::Normal => quote! {Normal},
+            RuleType::Silent => quote! {Silent},
+            RuleType::Atomic => quote! {Atomic},
+            RuleType::CompoundAtomic => quote! {CompoundAtomic},
+            RuleType::NonAtomic => quote! {NonAtomic},
+        };
+        let e = expr(&rule.expr, &defined);
+        quote! {
+            #[allow(non_snake_case, unused_parens)]
+            pub(super) fn #name #rules_generics(d: &mut #rules_driver) -> ::core::primitive::bool {
+                d.rule(#rule_enum::#name, #pest_typed::reflection::RuleKind::#kind, |d| #e)
+            }
+        }
+    });
+    let arms = rules.iter().map(|rule| {
+        let name = format_ident!("r#{}", rule.name);
+        let atomic = matches!(rule.ty, RuleType::Atomic | RuleType::CompoundAtomic);
+        quote! {super::Rule::#name => (rules::#name(&mut d), #atomic),}
+    });
+    let trivia = ["WHITESPACE", "COMMENT"]
+        .into_iter()
+        .filter(|name| defined.contains(name))
+        .map(|name| {
+            let name = format_ident!("{}", name);
+            quote! {rules::#name(d)}
+        });
+    let (driver, generics) = {
+        let rule_enum = quote! {super::Rule};
+        (driver(&rule_enum), generics(&rule_enum))
+    };
+    quote! {
+        #[doc = "Parse without building trees, and report matched rules and terminals as events."]
+        pub mod events {
+            mod rules {
+                #(#functions)*
+                pub(super) fn EOI #rules_generics(d: &mut #rules_driver) -> ::core::primitive::bool {
+                    d.rule(#rule_enum::EOI, #pest_typed::reflection::RuleKind::Normal, |d| d.eoi())
+                }
+            }
+            fn trivia #generics(d: &mut #driver) -> ::core::primitive::bool {
+                false #(|| #trivia)*
+            }
+            #[doc = "Parse the whole input as `rule`, and send events of it to `sink`."]
+            pub fn parse #generics(
+                rule: super::Rule,
+                input: &'i ::core::primitive::str,
+                sink: &mut S,
+            ) -> ::core::result::Result<(), #pest_typed::error::Error<super::Rule>> {
+                let mut d = #root::Driver::new(input, sink, trivia::<S>);
+                let (matched, atomic) = match rule {
+                    #(#arms)*
+                    super::Rule::EOI => (rules::EOI(&mut d), false),
+                };
+                d.finish(matched, atomic, super::Rule::EOI)
+            }
+        }
+    }
+}
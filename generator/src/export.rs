@@ -0,0 +1,530 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Export grammars to notations of other toolchains, such as for documentation.
+//!
+//! - [`to_ebnf`] writes EBNF in the notation of the W3C XML specification, `name ::= expression`.
+//! - [`to_antlr`] writes a combined ANTLR 4 grammar.
+//!
+//! Rule names, comments and doc comments between rules are kept.
+//! PEG constructs without counterparts are approximated:
+//! choices are not ordered, predicates and stack operations become comments,
+//! and implicit whitespaces are only skipped in ANTLR, where `WHITESPACE` and `COMMENT` are lexer rules.
+//! The only exception is `!x ~ ANY` with a set of characters `x`, which becomes a negated set.
+
+use crate::prepare::prepare_grammar_with;
+use pest::{error::Error, iterators::Pair};
+use pest_meta::{
+    ast::{Expr, RuleType},
+    parser::{consume_rules, parse, Rule},
+    validator::validate_pairs,
+};
+
+#[derive(Clone, Copy, PartialEq)]
+enum Dialect {
+    Ebnf,
+    Antlr,
+}
+
+/// Precedence of a printed expression, from loosest to tightest.
+#[derive(Clone, Copy, PartialEq, PartialOrd)]
+enum Prec {
+    Choice,
+    Seq,
+    Atom,
+}
+
+/// Whether a character can be written as itself in a W3C character class.
+fn plain(c: char) -> bool {
+    c.is_ascii_alphanumeric()
+}
+
+impl Dialect {
+    fn string(self, s: &str) -> (String, Prec) {
+        match self {
+            Dialect::Ebnf => {
+                // W3C EBNF has no escapes, so other characters are written as `#xN`.
+                let quote = if s.contains('"') && !s.contains('\'') {
+                    '\''
+                } else {
+                    '"'
+                };
+                let mut items = vec![];
+                let mut run = String::new();
+                for c in s.chars() {
+                    if c == quote || c.is_control() {
+                        if !run.is_empty() {
+                            items.push(format!("{quote}{run}{quote}"));
+                            run.clear();
+                        }
+                        items.push(format!("#x{:X}", c as u32));
+                    } else {
+                        run.push(c);
+                    }
+                }
+                if !run.is_empty() || items.is_empty() {
+                    items.push(format!("{quote}{run}{quote}"));
+                }
+                let prec = if items.len() == 1 {
+                    Prec::Atom
+                } else {
+                    Prec::Seq
+                };
+                (items.join(" "), prec)
+            }
+            Dialect::Antlr => (format!("'{}'", self.escape(s, false)), Prec::Atom),
+        }
+    }
+    /// Escape characters in an ANTLR string, or in a set if `set`.
+    fn escape(self, s: &str, set: bool) -> String {
+        let mut res = String::new();
+        for c in s.chars() {
+            match c {
+                '\'' if !set => res.push_str("\\'"),
+                '\\' => res.push_str("\\\\"),
+                ']' | '-' if set => {
+                    res.push('\\');
+                    res.push(c);
+                }
+                '\n' => res.push_str("\\n"),
+                '\r' => res.push_str("\\r"),
+                '\t' => res.push_str("\\t"),
+                c if c.is_control() => res.push_str(&format!("\\u{{{:X}}}", c as u32)),
+                c => res.push(c),
+            }
+        }
+        res
+    }
+    /// A character in a set.
+    fn set_char(self, c: char) -> String {
+        match self {
+            Dialect::Ebnf if plain(c) => c.to_string(),
+            Dialect::Ebnf => format!("#x{:X}", c as u32),
+            Dialect::Antlr => self.escape(&c.to_string(), true),
+        }
+    }
+    /// Builtin rules that can be written in the dialect.
+    fn builtin(self, name: &str) -> Option<&'static str> {
+        let set = match name {
+            "ASCII_DIGIT" => "[0-9]",
+            "ASCII_NONZERO_DIGIT" => "[1-9]",
+            "ASCII_BIN_DIGIT" => "[01]",
+            "ASCII_OCT_DIGIT" => "[0-7]",
+            "ASCII_HEX_DIGIT" => "[0-9a-fA-F]",
+            "ASCII_ALPHA_LOWER" => "[a-z]",
+            "ASCII_ALPHA_UPPER" => "[A-Z]",
+            "ASCII_ALPHA" => "[a-zA-Z]",
+            "ASCII_ALPHANUMERIC" => "[a-zA-Z0-9]",
+            _ => "",
+        };
+        if !set.is_empty() {
+            return Some(set);
+        }
+        match (self, name) {
+            (Dialect::Ebnf, "ANY") => Some("[#x0-#x10FFFF]"),
+            (Dialect::Ebnf, "ASCII") => Some("[#x0-#x7F]"),
+            (Dialect::Ebnf, "NEWLINE") => Some("(#xA | #xD #xA | #xD)"),
+            (Dialect::Antlr, "ANY") => Some("."),
+            (Dialect::Antlr, "ASCII") => Some("[\\u0000-\\u007F]"),
+            (Dialect::Antlr, "NEWLINE") => Some("('\\n' | '\\r\\n' | '\\r')"),
+            (Dialect::Antlr, "EOI") => Some("EOF"),
+            _ => None,
+        }
+    }
+}
+
+/// A comment inside an expression.
+fn comment(text: &str) -> (String, Prec) {
+    (format!("/* {} */", text.replace("*/", "* /")), Prec::Atom)
+}
+
+fn parenthesize((text, prec): (String, Prec), min: Prec) -> String {
+    if prec < min {
+        format!("({})", text)
+    } else {
+        text
+    }
+}
+
+fn flatten_seq<'e>(expr: &'e Expr, res: &mut Vec<&'e Expr>) {
+    match expr {
+        Expr::Seq(lhs, rhs) => {
+            flatten_seq(lhs, res);
+            flatten_seq(rhs, res);
+        }
+        expr => res.push(expr),
+    }
+}
+
+fn flatten_choice<'e>(expr: &'e Expr, res: &mut Vec<&'e Expr>) {
+    match expr {
+        Expr::Choice(lhs, rhs) => {
+            flatten_choice(lhs, res);
+            flatten_choice(rhs, res);
+        }
+        expr => res.push(expr),
+    }
+}
+
+/// Items of a set of single characters matched by `expr`, such as `a-z` and `"`.
+fn set_items(dialect: Dialect, expr: &Expr, res: &mut Vec<String>) -> bool {
+    let single = |s: &str| {
+        let mut chars = s.chars();
+        chars.next().filter(|_| chars.next().is_none())
+    };
+    match expr {
+        Expr::Str(s) => match single(s) {
+            Some(c) => {
+                res.push(dialect.set_char(c));
+                true
+            }
+            None => false,
+        },
+        Expr::Range(start, end) => match (single(start), single(end)) {
+            (Some(start), Some(end)) => {
+                res.push(format!(
+                    "{}-{}",
+                    dialect.set_char(start),
+                    dialect.set_char(end)
+                ));
+                true
+            }
+            _ => false,
+        },
+        Expr::Choice(lhs, rhs) => set_items(dialect, lhs, res) && set_items(dialect, rhs, res),
+        _ => false,
+    }
+}
+
+fn repeat(dialect: Dialect, inner: &Expr, min: u32, max: Option<u32>) -> (String, Prec) {
+    let atom = parenthesize(print(dialect, inner), Prec::Atom);
+    let mut items: Vec<_> = (0..min).map(|_| atom.clone()).collect();
+    match max {
+        None => items.push(format!("{}*", atom)),
+        Some(max) => items.extend((min..max).map(|_| format!("{}?", atom))),
+    }
+    match items.len() {
+        0 => (String::from("()"), Prec::Atom),
+        1 => (items.pop().unwrap(), Prec::Atom),
+        _ => (items.join(" "), Prec::Seq),
+    }
+}
+
+fn print(dialect: Dialect, expr: &Expr) -> (String, Prec) {
+    match expr {
+        Expr::Str(s) => dialect.string(s),
+        Expr::Insens(s) => {
+            let mut items = vec![];
+            let mut run = String::new();
+            for c in s.chars() {
+                let (lower, upper) = (c.to_ascii_lowercase(), c.to_ascii_uppercase());
+                if lower == upper {
+                    run.push(c);
+                    continue;
+                }
+                if !run.is_empty() {
+                    items.push(dialect.string(&run).0);
+                    run.clear();
+                }
+                items.push(format!("[{}{}]", lower, upper));
+            }
+            if !run.is_empty() || items.is_empty() {
+                items.push(dialect.string(&run).0);
+            }
+            match items.len() {
+                1 => (items.pop().unwrap(), Prec::Atom),
+                _ => (items.join(" "), Prec::Seq),
+            }
+        }
+        Expr::Range(_, _) => {
+            let mut items = vec![];
+            set_items(dialect, expr, &mut items);
+            (format!("[{}]", items.concat()), Prec::Atom)
+        }
+        Expr::Ident(name) => match dialect.builtin(name) {
+            Some(builtin) => (String::from(builtin), Prec::Atom),
+            None if matches!(
+                name.as_str(),
+                "SOI" | "EOI" | "PEEK" | "PEEK_ALL" | "POP" | "POP_ALL" | "DROP"
+            ) =>
+            {
+                comment(name)
+            }
+            None => (name.clone(), Prec::Atom),
+        },
+        Expr::PeekSlice(start, end) => match end {
+            Some(end) => comment(&format!("PEEK[{}..{}]", start, end)),
+            None => comment(&format!("PEEK[{}..]", start)),
+        },
+        Expr::PosPred(inner) => comment(&format!(
+            "&{}",
+            parenthesize(print(dialect, inner), Prec::Atom)
+        )),
+        Expr::NegPred(inner) => comment(&format!(
+            "!{}",
+            parenthesize(print(dialect, inner), Prec::Atom)
+        )),
+        Expr::Seq(_, _) => {
+            let mut items = vec![];
+            flatten_seq(expr, &mut items);
+            let mut res = vec![];
+            let mut i = 0;
+            while i < items.len() {
+                if let (Expr::NegPred(inner), Some(Expr::Ident(any))) = (items[i], items.get(i + 1))
+                {
+                    let mut set = vec![];
+                    if any == "ANY" && set_items(dialect, inner, &mut set) {
+                        let set = match dialect {
+                            Dialect::Ebnf => format!("[^{}]", set.concat()),
+                            Dialect::Antlr => format!("~[{}]", set.concat()),
+                        };
+                        res.push((set, Prec::Atom));
+                        i += 2;
+                        continue;
+                    }
+                }
+                res.push(print(dialect, items[i]));
+                i += 1;
+            }
+            match res.len() {
+                1 => res.pop().unwrap(),
+                _ => {
+                    let res: Vec<_> = res
+                        .into_iter()
+                        .map(|item| parenthesize(item, Prec::Seq))
+                        .collect();
+                    (res.join(" "), Prec::Seq)
+                }
+            }
+        }
+        Expr::Choice(_, _) => {
+            let mut items = vec![];
+            flatten_choice(expr, &mut items);
+            let items: Vec<_> = items
+                .into_iter()
+                .map(|item| parenthesize(print(dialect, item), Prec::Seq))
+                .collect();
+            (items.join(" | "), Prec::Choice)
+        }
+        Expr::Opt(inner) => repeat(dialect, inner, 0, Some(1)),
+        Expr::Rep(inner) => repeat(dialect, inner, 0, None),
+        Expr::RepOnce(inner) => (
+            format!("{}+", parenthesize(print(dialect, inner), Prec::Atom)),
+            Prec::Atom,
+        ),
+        Expr::RepExact(inner, n) => repeat(dialect, inner, *n, Some(*n)),
+        Expr::RepMin(inner, min) => repeat(dialect, inner, *min, None),
+        Expr::RepMax(inner, max) => repeat(dialect, inner, 0, Some(*max)),
+        Expr::RepMinMax(inner, min, max) => repeat(dialect, inner, *min, Some(*max)),
+        Expr::Skip(strings) => comment(&format!("skip until one of {:?}", strings)),
+        Expr::Push(inner) => (
+            format!(
+                "{} {}",
+                comment("PUSH").0,
+                parenthesize(print(dialect, inner), Prec::Atom)
+            ),
+            Prec::Seq,
+        ),
+        #[cfg(feature = "grammar-extras")]
+        Expr::PushLiteral(s) => comment(&format!("PUSH_LITERAL({:?})", s)),
+        #[cfg(feature = "grammar-extras")]
+        Expr::NodeTag(inner, _) => print(dialect, inner),
+    }
+}
+
+/// Comments in `gap`, which is text between rules.
+fn comments(gap: &str) -> Vec<&str> {
+    let mut res = vec![];
+    let mut rest = gap;
+    while let Some(start) = rest.find('/') {
+        rest = &rest[start..];
+        if let Some(line) = rest.strip_prefix("//") {
+            let end = line.find('\n').unwrap_or(line.len());
+            res.push(line[..end].trim());
+            rest = &line[end..];
+        } else if let Some(block) = rest.strip_prefix("/*") {
+            // Block comments nest in pest.
+            let mut depth = 1;
+            let mut end = block.len();
+            let mut i = 0;
+            while i < block.len() {
+                if block[i..].starts_with("/*") {
+                    depth += 1;
+                    i += 2;
+                } else if block[i..].starts_with("*/") {
+                    depth -= 1;
+                    if depth == 0 {
+                        end = i;
+                        break;
+                    }
+                    i += 2;
+                } else {
+                    i += block[i..].chars().next().map_or(1, char::len_utf8);
+                }
+            }
+            res.push(block[..end].trim());
+            rest = block.get(end + 2..).unwrap_or("");
+        } else {
+            rest = &rest[1..];
+        }
+    }
+    res
+}
+
+fn doc(pair: Pair<'_, Rule>) -> &str {
+    pair.into_inner()
+        .find(|pair| pair.as_rule() == Rule::inner_doc)
+        .map_or("", |pair| pair.as_str())
+}
+
+fn export(grammar: &str, dialect: Dialect, name: &str) -> Result<String, Vec<Error<Rule>>> {
+    let prepared = prepare_grammar_with(grammar, |_| {
+        Err("regular expression literals can't be exported".to_owned())
+    })
+    .map_err(|error| vec![error])?;
+    let grammar = prepared.grammar.as_str();
+    let pairs = parse(Rule::grammar_rules, grammar).map_err(|error| vec![error])?;
+    validate_pairs(pairs.clone())?;
+    let mut rules = consume_rules(pairs.clone())?.into_iter();
+
+    let mut res = String::new();
+    let write_comment = |res: &mut String, text: &str| {
+        let text = text.replace("*/", "* /");
+        match dialect {
+            Dialect::Ebnf => res.push_str(&format!("/* {} */\n", text)),
+            Dialect::Antlr => {
+                for line in text.lines() {
+                    res.push_str(format!("// {}", line.trim()).trim_end());
+                    res.push('\n');
+                }
+                if text.is_empty() {
+                    res.push_str("//\n");
+                }
+            }
+        }
+    };
+    if dialect == Dialect::Antlr {
+        res.push_str(&format!("grammar {};\n\n", name));
+    }
+    let mut prev = 0;
+    for pair in pairs {
+        let span = pair.as_span();
+        for text in comments(&grammar[prev..span.start()]) {
+            write_comment(&mut res, text);
+        }
+        prev = span.end();
+        match pair.as_rule() {
+            Rule::grammar_doc => write_comment(&mut res, doc(pair)),
+            Rule::grammar_rule => {
+                let inner = pair.into_inner().next().unwrap();
+                if inner.as_rule() == Rule::line_doc {
+                    write_comment(&mut res, doc(inner));
+                    continue;
+                }
+                let rule = rules.next().unwrap();
+                let expr = print(dialect, &rule.expr);
+                let ty = match rule.ty {
+                    RuleType::Normal => "",
+                    RuleType::Silent => " /* silent */",
+                    RuleType::Atomic => " /* atomic */",
+                    RuleType::CompoundAtomic => " /* compound atomic */",
+                    RuleType::NonAtomic => " /* non-atomic */",
+                };
+                match dialect {
+                    Dialect::Ebnf => res.push_str(&format!("{} ::= {}{}\n", rule.name, expr.0, ty)),
+                    // Lexer commands only apply to the last alternative.
+                    Dialect::Antlr if matches!(rule.name.as_str(), "WHITESPACE" | "COMMENT") => {
+                        let expr = parenthesize(expr, Prec::Seq);
+                        res.push_str(&format!("{} : {} -> skip ;{}\n", rule.name, expr, ty))
+                    }
+                    Dialect::Antlr => {
+                        res.push_str(&format!("{} : {} ;{}\n", rule.name, expr.0, ty))
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+    Ok(res)
+}
+
+/// Convert a grammar into EBNF in the notation of the W3C XML specification.
+/// See [module-level documentation](self).
+///
+/// Returns errors if the grammar is invalid.
+pub fn to_ebnf(grammar: &str) -> Result<String, Vec<Error<Rule>>> {
+    export(grammar, Dialect::Ebnf, "")
+}
+
+/// Convert a grammar into a combined ANTLR 4 grammar named `name`.
+/// See [module-level documentation](self).
+///
+/// Returns errors if the grammar is invalid.
+pub fn to_antlr(grammar: &str, name: &str) -> Result<String, Vec<Error<Rule>>> {
+    export(grammar, Dialect::Antlr, name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{to_antlr, to_ebnf};
+
+    const GRAMMAR: &str = r#"//! A list.
+
+// Lists of items.
+/// List.
+list = { "[" ~ (item ~ ("," ~ item)*)? ~ "]" ~ EOI }
+item = _{ string | number | list }
+/* Strings, /* without escapes */ */
+string = @{ "\"" ~ (!("\"" | "\\") ~ ANY)* ~ "\"" }
+number = @{ ASCII_DIGIT{1, 3} ~ ^"e"? ~ 'a'..'z'+ }
+WHITESPACE = _{ " " | "\t" }
+"#;
+
+    #[test]
+    fn ebnf() {
+        assert_eq!(
+            to_ebnf(GRAMMAR).unwrap(),
+            r#"/* A list. */
+/* Lists of items. */
+/* List. */
+list ::= "[" (item ("," item)*)? "]" /* EOI */
+item ::= string | number | list /* silent */
+/* Strings, /* without escapes * / */
+string ::= '"' [^#x22#x5C]* '"' /* atomic */
+number ::= [0-9] [0-9]? [0-9]? [eE]? [a-z]+ /* atomic */
+WHITESPACE ::= " " | #x9 /* silent */
+"#
+        );
+    }
+
+    #[test]
+    fn antlr() {
+        assert_eq!(
+            to_antlr(GRAMMAR, "List").unwrap(),
+            r#"grammar List;
+
+// A list.
+// Lists of items.
+// List.
+list : '[' (item (',' item)*)? ']' EOF ;
+item : string | number | list ; /* silent */
+// Strings, /* without escapes * /
+string : '"' ~["\\]* '"' ; /* atomic */
+number : [0-9] [0-9]? [0-9]? [eE]? [a-z]+ ; /* atomic */
+WHITESPACE : (' ' | '\t') -> skip ; /* silent */
+"#
+        );
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(to_ebnf("a = {").is_err());
+        assert!(to_antlr("a = { b }", "A").is_err());
+    }
+}
@@ -0,0 +1,378 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Canonical formatting of grammars, to keep large grammars consistent and their diffs small.
+//!
+//! [`format_grammar`] writes each rule as `name = { expression }`,
+//! with single spaces around `~`, `|` and `=`,
+//! and none inside parentheses, brackets and repetition braces, or before postfix operators.
+//! Rules that are longer than [`MAX_WIDTH`] characters or have line comments inside
+//! are broken into one line for each top-level alternative:
+//!
+//! ```pest
+//! item = _{
+//!     string
+//!   | number // Integers only.
+//!   | list
+//! }
+//! ```
+//!
+//! Comments, doc comments and attributes such as `#[recover]` are kept where they are,
+//! and blank lines between rules are kept, at most one in a row.
+//! Tokens are kept as written, so formatting never changes what the grammar means,
+//! and formatting a formatted grammar changes nothing.
+
+use crate::prepare::prepare_grammar_with;
+use crate::regex::Regex;
+use pest::error::Error;
+use pest_meta::parser::{parse, Rule};
+
+/// Width of rules that are kept in a single line.
+pub const MAX_WIDTH: usize = 100;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Kind {
+    /// Identifiers and numbers.
+    Word,
+    /// String literals, character literals, `re"..."` and `\p{...}`.
+    Literal,
+    Punct,
+    LineComment,
+    BlockComment,
+    /// `///` and `//!`.
+    Doc,
+    /// Attribute lines before rules.
+    Attribute,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Token<'g> {
+    kind: Kind,
+    text: &'g str,
+    /// Line breaks between the token and the one before it.
+    newlines: usize,
+}
+
+/// Length of the quoted literal at the start of `rest`, which begins with its quote.
+fn quoted(rest: &str) -> usize {
+    let quote = rest.as_bytes()[0];
+    let bytes = rest.as_bytes();
+    let mut i = 1;
+    while i < bytes.len() && bytes[i] != quote {
+        i += if bytes[i] == b'\\' { 2 } else { 1 };
+    }
+    (i + 1).min(bytes.len())
+}
+
+/// Length of the nested block comment at the start of `rest`.
+fn block_comment(rest: &str) -> usize {
+    let mut depth = 0;
+    let mut i = 0;
+    while i < rest.len() {
+        if rest[i..].starts_with("/*") {
+            depth += 1;
+            i += 2;
+        } else if rest[i..].starts_with("*/") {
+            depth -= 1;
+            i += 2;
+            if depth == 0 {
+                return i;
+            }
+        } else {
+            i += rest[i..].chars().next().map_or(1, char::len_utf8);
+        }
+    }
+    rest.len()
+}
+
+fn is_word(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+fn tokenize(grammar: &str) -> Vec<Token<'_>> {
+    let mut res = vec![];
+    let mut newlines = 0;
+    let mut depth = 0usize;
+    let mut i = 0;
+    while let Some(c) = grammar[i..].chars().next() {
+        let rest = &grammar[i..];
+        if c.is_whitespace() {
+            newlines += usize::from(c == '\n');
+            i += c.len_utf8();
+            continue;
+        }
+        let line = rest.find('\n').unwrap_or(rest.len());
+        let (kind, len) = if rest.starts_with("//") {
+            let doc =
+                (rest.starts_with("///") && !rest.starts_with("////")) || rest.starts_with("//!");
+            let kind = if doc { Kind::Doc } else { Kind::LineComment };
+            (kind, line)
+        } else if rest.starts_with("/*") {
+            (Kind::BlockComment, block_comment(rest))
+        } else if c == '#' && depth == 0 {
+            (Kind::Attribute, line)
+        } else if c == '"' || c == '\'' {
+            (Kind::Literal, quoted(rest))
+        } else if rest.starts_with("^\"") {
+            (Kind::Literal, 1 + quoted(&rest[1..]))
+        } else if rest.starts_with("re\"") && !grammar[..i].ends_with(is_word) {
+            (Kind::Literal, 2 + quoted(&rest[2..]))
+        } else if rest.starts_with("\\p{") || rest.starts_with("\\P{") {
+            (
+                Kind::Literal,
+                rest.find('}').map_or(rest.len(), |end| end + 1),
+            )
+        } else if is_word(c) || (c == '-' && rest[1..].starts_with(|c: char| c.is_ascii_digit())) {
+            let len = rest[1..]
+                .find(|c| !is_word(c))
+                .map_or(rest.len(), |end| end + 1);
+            (Kind::Word, len)
+        } else if rest.starts_with("..") {
+            (Kind::Punct, 2)
+        } else {
+            match c {
+                '{' => depth += 1,
+                '}' => depth = depth.saturating_sub(1),
+                _ => (),
+            }
+            (Kind::Punct, c.len_utf8())
+        };
+        res.push(Token {
+            kind,
+            text: rest[..len].trim_end(),
+            newlines,
+        });
+        newlines = 0;
+        i += len;
+    }
+    res
+}
+
+/// Whether a space is written between two tokens in an expression.
+fn spaced(prev: &Token<'_>, next: &Token<'_>) -> bool {
+    let punct = |token: &Token<'_>, texts: &[&str]| {
+        token.kind == Kind::Punct && texts.contains(&token.text)
+    };
+    let glued = punct(prev, &["(", "[", "{", "!", "&", "#", ".."])
+        || punct(next, &[")", "]", "}", "?", "*", "+", ",", ":", "..", "{"])
+        || (punct(next, &["("]) && matches!(prev.text, "PUSH" | "PUSH_LITERAL"))
+        || (punct(next, &["["]) && prev.text == "PEEK");
+    !glued
+}
+
+/// Join tokens of an expression in a line.
+fn join(tokens: &[Token<'_>]) -> String {
+    let mut res = String::new();
+    for (i, token) in tokens.iter().enumerate() {
+        if i > 0 && spaced(&tokens[i - 1], token) {
+            res.push(' ');
+        }
+        res.push_str(token.text);
+    }
+    res
+}
+
+/// Lines of an alternative in a broken rule, which are split by line comments.
+fn lines<'g>(alternative: &[Token<'g>]) -> Vec<String> {
+    let mut res = vec![];
+    let mut current: Vec<Token<'g>> = vec![];
+    for token in alternative {
+        if token.kind != Kind::LineComment {
+            current.push(*token);
+            continue;
+        }
+        // Comments after tokens in the same line stay there.
+        if token.newlines == 0 && !current.is_empty() {
+            res.push(format!("{} {}", join(&current), token.text));
+        } else {
+            if !current.is_empty() {
+                res.push(join(&current));
+            }
+            res.push(token.text.to_owned());
+        }
+        current.clear();
+    }
+    if !current.is_empty() {
+        res.push(join(&current));
+    }
+    res
+}
+
+/// Write a rule with its header, such as `name = _{`, and the tokens of its expression.
+fn write_rule(res: &mut String, header: &str, body: &[Token<'_>]) {
+    let single = format!("{header} {} }}", join(body));
+    if single.chars().count() <= MAX_WIDTH && body.iter().all(|t| t.kind != Kind::LineComment) {
+        res.push_str(&single);
+        res.push('\n');
+        return;
+    }
+    let mut alternatives = vec![vec![]];
+    let mut depth = 0usize;
+    for token in body {
+        if token.kind == Kind::Punct {
+            match token.text {
+                "(" | "[" | "{" => depth += 1,
+                ")" | "]" | "}" => depth = depth.saturating_sub(1),
+                "|" if depth == 0 => {
+                    alternatives.push(vec![]);
+                    continue;
+                }
+                _ => (),
+            }
+        }
+        alternatives.last_mut().unwrap().push(*token);
+    }
+    res.push_str(header);
+    res.push('\n');
+    for (i, alternative) in alternatives.iter().enumerate() {
+        for (j, line) in lines(alternative).iter().enumerate() {
+            let prefix = if i > 0 && j == 0 { "  | " } else { "    " };
+            res.push_str(prefix);
+            res.push_str(line);
+            res.push('\n');
+        }
+    }
+    res.push_str("}\n");
+}
+
+/// Check that the grammar can be parsed, with extensions of this crate.
+fn check(grammar: &str) -> Result<(), Error<Rule>> {
+    // Patterns are written back as they are, so they are not compiled.
+    let prepared = prepare_grammar_with(grammar, |pattern| {
+        Ok(Regex {
+            pattern: pattern.to_owned(),
+            little_endian: vec![],
+            big_endian: vec![],
+        })
+    })?;
+    parse(Rule::grammar_rules, &prepared.grammar)?;
+    Ok(())
+}
+
+/// Format a grammar. See [module-level documentation](self).
+///
+/// Returns an error if the grammar can't be parsed.
+pub fn format_grammar(grammar: &str) -> Result<String, Error<Rule>> {
+    check(grammar)?;
+    let tokens = tokenize(grammar);
+    let mut res = String::with_capacity(grammar.len());
+    let mut i = 0;
+    while i < tokens.len() {
+        let token = tokens[i];
+        if !res.is_empty() {
+            let comment = matches!(token.kind, Kind::LineComment | Kind::BlockComment);
+            if comment && token.newlines == 0 {
+                res.pop();
+                res.push(' ');
+                res.push_str(token.text);
+                res.push('\n');
+                i += 1;
+                continue;
+            }
+            if token.newlines > 1 {
+                res.push('\n');
+            }
+        }
+        if token.kind != Kind::Word {
+            res.push_str(token.text);
+            res.push('\n');
+            i += 1;
+            continue;
+        }
+        // A rule, `name = modifier{ ... }`, which is valid as the grammar has been parsed.
+        let mut header = format!("{} =", token.text);
+        i += 2;
+        if tokens[i].text != "{" {
+            header.push(' ');
+            header.push_str(tokens[i].text);
+            i += 1;
+        } else {
+            header.push(' ');
+        }
+        header.push('{');
+        let start = i + 1;
+        let mut depth = 0usize;
+        let mut end = start;
+        for (j, token) in tokens.iter().enumerate().skip(start) {
+            match (token.kind, token.text) {
+                (Kind::Punct, "{") => depth += 1,
+                (Kind::Punct, "}") if depth == 0 => {
+                    end = j;
+                    break;
+                }
+                (Kind::Punct, "}") => depth -= 1,
+                _ => (),
+            }
+        }
+        write_rule(&mut res, &header, &tokens[start..end]);
+        i = end + 1;
+    }
+    Ok(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::format_grammar;
+
+    const GRAMMAR: &str = r#"//! Lists.
+
+
+// Items.
+/// A list.
+list={"["~(item~(","~item)*)?~"]"}
+#[recover]
+item = _{string|number // Integers.
+  |list}
+
+number=@{ASCII_DIGIT{1,3}~^"e"?~'a'..'z'+}  // Trailing.
+string = @{ "\"" ~ (!("\"" | "\\") ~ ANY)* ~ "\"" } /* Done. */
+stack = { PUSH ( "a" ) ~ PEEK [ -1 .. ] ~ DROP ~ re"[0-9]+" ~ \p{Greek} ~ key : item }
+"#;
+
+    const FORMATTED: &str = r#"//! Lists.
+
+// Items.
+/// A list.
+list = { "[" ~ (item ~ ("," ~ item)*)? ~ "]" }
+#[recover]
+item = _{
+    string
+  | number // Integers.
+  | list
+}
+
+number = @{ ASCII_DIGIT{1, 3} ~ ^"e"? ~ 'a'..'z'+ } // Trailing.
+string = @{ "\"" ~ (!("\"" | "\\") ~ ANY)* ~ "\"" } /* Done. */
+stack = { PUSH("a") ~ PEEK[-1..] ~ DROP ~ re"[0-9]+" ~ \p{Greek} ~ key: item }
+"#;
+
+    #[test]
+    fn canonical() {
+        assert_eq!(format_grammar(GRAMMAR).unwrap(), FORMATTED);
+        assert_eq!(format_grammar(FORMATTED).unwrap(), FORMATTED);
+        assert!(format_grammar("a = {").is_err());
+    }
+
+    #[test]
+    fn long() {
+        let alternatives: Vec<_> = (0..12).map(|i| format!("\"keyword{i}\"")).collect();
+        let grammar = format!(
+            "keyword = {{\n// Keywords.\n{} }}",
+            alternatives.join(" | ")
+        );
+        let formatted = format_grammar(&grammar).unwrap();
+        let mut expected = String::from("keyword = {\n    // Keywords.\n    \"keyword0\"\n");
+        for alternative in &alternatives[1..] {
+            expected.push_str(&format!("  | {alternative}\n"));
+        }
+        expected.push_str("}\n");
+        assert_eq!(formatted, expected);
+        assert_eq!(format_grammar(&formatted).unwrap(), formatted);
+    }
+}
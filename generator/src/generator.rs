@@ -10,7 +10,7 @@
 //! Copied from **pest/generator/src/generator.rs** (commit ac0aed3eecf435fd93ba575a39704aaa88a375b7)
 //! and modified.
 
-use std::path::PathBuf;
+use std::{iter, path::PathBuf};
 
 use proc_macro2::TokenStream;
 use quote::{format_ident, quote};
@@ -19,6 +19,7 @@ use syn::{self, Ident};
 use pest_meta::optimizer::*;
 
 use super::docs::DocComment;
+use crate::graph::pest_typed;
 
 /// Generate Rust `include_str!` for grammar files, then Cargo will watch changes in grammars.
 pub(crate) fn generate_include(name: &Ident, paths: Vec<PathBuf>) -> TokenStream {
@@ -50,6 +51,17 @@ pub(crate) fn generate_include(name: &Ident, paths: Vec<PathBuf>) -> TokenStream
     }
 }
 pub(crate) fn generate_enum(rules: &[OptimizedRule], doc_comment: &DocComment) -> TokenStream {
+    let pest_typed = pest_typed();
+    let names: Vec<_> = iter::once("EOI")
+        .chain(rules.iter().map(|rule| rule.name.as_str()))
+        .collect();
+    let idents: Vec<_> = names
+        .iter()
+        .map(|name| match *name {
+            "EOI" => format_ident!("EOI"),
+            name => format_ident!("r#{}", name),
+        })
+        .collect();
     let rules = rules.iter().map(|rule| {
         let rule_name = format_ident!("r#{}", rule.name);
 
@@ -73,6 +85,34 @@ pub(crate) fn generate_enum(rules: &[OptimizedRule], doc_comment: &DocComment) -
             EOI,
             #( #rules, )*
         }
+        #[allow(dead_code)]
+        impl Rule {
+            #[doc = "All rules, `EOI` first and then in the order they are defined, which is also their order by `Ord`."]
+            pub const ALL_RULES: &'static [Rule] = &[#(Rule::#idents),*];
+            #[doc = "Name of the rule in the grammar."]
+            pub fn name(self) -> &'static ::core::primitive::str {
+                match self {
+                    #(Rule::#idents => #names,)*
+                }
+            }
+        }
+        impl ::core::fmt::Display for Rule {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                f.write_str(self.name())
+            }
+        }
+        impl ::core::str::FromStr for Rule {
+            type Err = #pest_typed::rule::UnknownRule;
+
+            fn from_str(s: &::core::primitive::str) -> ::core::result::Result<Self, Self::Err> {
+                match s {
+                    #(#names => ::core::result::Result::Ok(Rule::#idents),)*
+                    _ => ::core::result::Result::Err(#pest_typed::rule::UnknownRule {
+                        name: ::core::convert::From::from(s),
+                    }),
+                }
+            }
+        }
     }
 }
 
@@ -109,6 +149,36 @@ mod tests {
                     #[doc = "This is rule comment"]
                     r#f,
                 }
+                #[allow(dead_code)]
+                impl Rule {
+                    #[doc = "All rules, `EOI` first and then in the order they are defined, which is also their order by `Ord`."]
+                    pub const ALL_RULES: &'static [Rule] = &[Rule::EOI, Rule::r#f];
+                    #[doc = "Name of the rule in the grammar."]
+                    pub fn name(self) -> &'static ::core::primitive::str {
+                        match self {
+                            Rule::EOI => "EOI",
+                            Rule::r#f => "f",
+                        }
+                    }
+                }
+                impl ::core::fmt::Display for Rule {
+                    fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                        f.write_str(self.name())
+                    }
+                }
+                impl ::core::str::FromStr for Rule {
+                    type Err = ::pest_typed::rule::UnknownRule;
+
+                    fn from_str(s: &::core::primitive::str) -> ::core::result::Result<Self, Self::Err> {
+                        match s {
+                            "EOI" => ::core::result::Result::Ok(Rule::EOI),
+                            "f" => ::core::result::Result::Ok(Rule::r#f),
+                            _ => ::core::result::Result::Err(::pest_typed::rule::UnknownRule {
+                                name: ::core::convert::From::from(s),
+                            }),
+                        }
+                    }
+                }
             }
             .to_string()
         );
@@ -0,0 +1,371 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Test cases of rules written in grammars, checked at build time or run as unit tests of the generated parser.
+//!
+//! Comment lines such as `//@ accept "1 + 2"` and `//@ reject "1 +"`
+//! before a rule are inputs that the whole rule should and should not match.
+//! With `#[grammar_tests]`, they are parsed with the [`Vm`] when the parser is generated,
+//! and each failing one is a compile error with the test case and where parsing failed in its input.
+//! The grammar is the one with extensions rewritten, as the generated parser sees it,
+//! but rule attributes that change what is matched, such as `#[trivia]`, are not known by the [`Vm`],
+//! so grammars with them are rejected.
+//! With `#[grammar_unit_tests]`, each one is a `#[test]` function that parses the input with the generated rule struct,
+//! so that all attributes of rules are respected,
+//! and panics with the test case and where parsing failed in its input.
+//!
+//! Inputs are written in double quotes, with escapes `\\`, `\"`, `\n`, `\r`, `\t` and `\u{...}`.
+
+use crate::attributes::Attributes;
+use crate::graph::pest_typed;
+use crate::report::{relocate, report, Source};
+use pest::{
+    error::{Error, ErrorVariant, LineColLocation},
+    Span,
+};
+use pest_meta::parser::Rule;
+use pest_typed::vm::Vm;
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote, quote_spanned};
+use std::collections::BTreeMap;
+
+/// A test case of a rule.
+#[derive(Debug, PartialEq)]
+struct GrammarTest {
+    rule: String,
+    input: String,
+    /// Whether the rule should match the input.
+    accept: bool,
+    /// Span of the test case in the grammar.
+    start: usize,
+    end: usize,
+}
+
+/// Parse a string in double quotes. Returns [`None`] if it's not closed or has an unknown escape.
+fn string(source: &str) -> Option<String> {
+    let mut chars = source.strip_prefix('"')?.strip_suffix('"')?.chars();
+    let mut res = String::new();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => return None,
+            '\\' => res.push(match chars.next()? {
+                '\\' => '\\',
+                '"' => '"',
+                'n' => '\n',
+                'r' => '\r',
+                't' => '\t',
+                'u' => {
+                    let rest = chars.as_str().strip_prefix('{')?;
+                    let (digits, rest) = rest.split_once('}')?;
+                    chars = rest.chars();
+                    char::from_u32(u32::from_str_radix(digits, 16).ok()?)?
+                }
+                _ => return None,
+            }),
+            c => res.push(c),
+        }
+    }
+    Some(res)
+}
+
+/// An error at `start..end` in `grammar`.
+fn error_at(message: String, grammar: &str, start: usize, end: usize) -> Error<Rule> {
+    Error::new_from_span(
+        ErrorVariant::CustomError { message },
+        Span::new(grammar, start, end).unwrap(),
+    )
+}
+
+/// Collect test cases for rules after them.
+fn extract(grammar: &str) -> Result<Vec<GrammarTest>, Vec<Error<Rule>>> {
+    let mut tests = vec![];
+    let mut errors = vec![];
+    // Test cases waiting for their rule, with an empty rule name.
+    let mut pending = vec![];
+    let mut offset = 0;
+    for line in grammar.split_inclusive('\n') {
+        let trimmed = line.trim();
+        let start = offset + line.len() - line.trim_start().len();
+        let end = start + trimmed.len();
+        offset += line.len();
+        if let Some(case) = trimmed.strip_prefix("//@") {
+            let case = case.trim_start();
+            let test =
+                [("accept", true), ("reject", false)]
+                    .into_iter()
+                    .find_map(|(kind, accept)| {
+                        let input = string(case.strip_prefix(kind)?.trim())?;
+                        Some(GrammarTest {
+                            rule: String::new(),
+                            input,
+                            accept,
+                            start,
+                            end,
+                        })
+                    });
+            match test {
+                Some(test) => pending.push(test),
+                None => errors.push(error_at(
+                    String::from(
+                        r#"expected a test case such as `//@ accept "..."` or `//@ reject "..."`"#,
+                    ),
+                    grammar,
+                    start,
+                    end,
+                )),
+            }
+            continue;
+        }
+        if !trimmed.is_empty() && !trimmed.starts_with("//") && !pending.is_empty() {
+            let len = trimmed
+                .find(|c: char| !c.is_alphanumeric() && c != '_')
+                .unwrap_or(trimmed.len());
+            for mut test in pending.drain(..) {
+                test.rule = trimmed[..len].to_owned();
+                tests.push(test);
+            }
+        }
+    }
+    errors.extend(pending.into_iter().map(|test| {
+        error_at(
+            String::from("test case is not followed by a rule"),
+            grammar,
+            test.start,
+            test.end,
+        )
+    }));
+    if errors.is_empty() {
+        Ok(tests)
+    } else {
+        Err(errors)
+    }
+}
+
+/// Rule attributes that the [`Vm`] doesn't know, but change what rules match.
+fn unsupported(attributes: &Attributes) -> Option<&'static str> {
+    [
+        (
+            !attributes.regexes.is_empty(),
+            "regular expression literals",
+        ),
+        (!attributes.keywords.is_empty(), "`#[keyword]`"),
+        (!attributes.trivia.is_empty(), "`#[trivia]`"),
+        (!attributes.embed.is_empty(), "`#[embed]`"),
+        (!attributes.lazy.is_empty(), "`#[lazy]`"),
+        (!attributes.recover.is_empty(), "`#[recover]`"),
+        (!attributes.versions.is_empty(), "`#[cfg(version ...)]`"),
+    ]
+    .into_iter()
+    .find_map(|(used, name)| used.then_some(name))
+}
+
+/// Check test cases in `grammar` with the [`Vm`],
+/// and generate a `compile_error!` for each one that fails or is malformed.
+pub(crate) fn check(grammar: &str, attributes: &Attributes, sources: &[Source]) -> TokenStream {
+    let tests = match extract(grammar) {
+        Ok(tests) => tests,
+        Err(errors) => return report(errors, grammar, sources),
+    };
+    if tests.is_empty() {
+        return TokenStream::new();
+    }
+    let span = |index: usize| {
+        sources
+            .get(index)
+            .map_or_else(proc_macro2::Span::call_site, |source| source.span)
+    };
+    if let Some(name) = unsupported(attributes) {
+        let message = format!(
+            "`grammar_tests` can't check grammars with {} at build time, use `grammar_unit_tests` instead",
+            name
+        );
+        return syn::Error::new(span(0), message).to_compile_error();
+    }
+    let vm = match Vm::new(grammar) {
+        Ok(vm) => vm,
+        Err(errors) => return report(errors, grammar, sources),
+    };
+    tests
+        .into_iter()
+        .filter_map(|test| {
+            let (expected, detail) = match (test.accept, vm.parse(&test.rule, &test.input)) {
+                (true, Err(error)) => ("accept", format!("\n\n{}", error)),
+                (false, Ok(_)) => ("reject", String::new()),
+                _ => return None,
+            };
+            let message = format!(
+                "`{}` should {} {:?}, but it doesn't",
+                test.rule, expected, test.input
+            );
+            let error = error_at(message, grammar, test.start, test.end);
+            let (index, error) = relocate(error, grammar, sources);
+            let message = format!("grammar test failed\n\n{}{}", error, detail);
+            Some(quote_spanned! {span(index)=>
+                ::core::compile_error!(#message);
+            })
+        })
+        .collect()
+}
+
+/// Generate a module of `#[test]` functions for test cases in `grammar`,
+/// or a `compile_error!` for each malformed one.
+pub(crate) fn generate(grammar: &str, sources: &[Source]) -> TokenStream {
+    let tests = match extract(grammar) {
+        Ok(tests) => tests,
+        Err(errors) => return report(errors, grammar, sources),
+    };
+    if tests.is_empty() {
+        return TokenStream::new();
+    }
+    let pest_typed = pest_typed();
+    // Number of test cases of each kind for each rule, to name tests.
+    let mut counts = BTreeMap::new();
+    let tests = tests.into_iter().map(|test| {
+        let kind = if test.accept { "accept" } else { "reject" };
+        let count = counts.entry((test.rule.clone(), kind)).or_insert(0usize);
+        *count += 1;
+        let name = format_ident!("{}_{}_{}", test.rule, kind, count);
+        let error = error_at(String::new(), grammar, test.start, test.end);
+        let (index, error) = relocate(error, grammar, sources);
+        let line = match error.line_col {
+            LineColLocation::Pos((line, _)) | LineColLocation::Span((line, _), _) => line,
+        };
+        let location = match sources.get(index).and_then(|source| source.path.as_ref()) {
+            Some(path) => format!("{}:{}", path.display(), line),
+            None => format!("line {}", line),
+        };
+        let message = format!(
+            "`{}` should {} {:?} ({}), but it doesn't",
+            test.rule, kind, test.input, location
+        );
+        let rule = format_ident!("r#{}", test.rule);
+        let input = &test.input;
+        let check = match test.accept {
+            true => quote! {
+                if let ::core::result::Result::Err(error) = res {
+                    ::core::panic!("{}\n\n{}", #message, error);
+                }
+            },
+            false => quote! {
+                if res.is_ok() {
+                    ::core::panic!("{}", #message);
+                }
+            },
+        };
+        quote! {
+            #[test]
+            fn #name() {
+                let res = <super::rules::#rule as #pest_typed::ParsableTypedNode<'_, super::Rule>>::try_parse(#input);
+                #check
+            }
+        }
+    });
+    quote! {
+        #[cfg(test)]
+        #[allow(non_snake_case)]
+        #[doc = "Test cases written in the grammar."]
+        mod grammar_tests {
+            #(#tests)*
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check, extract, generate, string, GrammarTest};
+    use crate::{attributes::Attributes, report::Source};
+
+    #[test]
+    fn strings() {
+        assert_eq!(
+            string(r#""a\"b\\\n\u{4e00}""#).as_deref(),
+            Some("a\"b\\\n一")
+        );
+        assert_eq!(string(r#""a"b""#), None);
+        assert_eq!(string(r#""\q""#), None);
+        assert_eq!(string(r#""a"#), None);
+    }
+
+    #[test]
+    fn extracted() {
+        let grammar = "//@ accept \"a\"\n// Comment.\n  //@ reject \"\"\nexpr = { \"a\" }\n";
+        let tests = extract(grammar).unwrap();
+        assert_eq!(
+            tests,
+            [
+                GrammarTest {
+                    rule: String::from("expr"),
+                    input: String::from("a"),
+                    accept: true,
+                    start: 0,
+                    end: 14,
+                },
+                GrammarTest {
+                    rule: String::from("expr"),
+                    input: String::new(),
+                    accept: false,
+                    start: 29,
+                    end: 42,
+                },
+            ]
+        );
+
+        let errors = extract("//@ accepts \"a\"\nexpr = { \"a\" }\n//@ reject \"b\"").unwrap_err();
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[0].line(), "//@ accepts \"a\"");
+        assert_eq!(errors[1].line(), "//@ reject \"b\"");
+    }
+
+    #[test]
+    fn checked() {
+        let sources = [Source {
+            start: 0,
+            path: None,
+            span: proc_macro2::Span::call_site(),
+        }];
+        let attributes = Attributes::default();
+        let grammar =
+            "//@ accept \"a a\"\n//@ reject \"b\"\nexpr = { \"a\"+ }\nWHITESPACE = _{ \" \" }";
+        assert!(check(grammar, &attributes, &sources).is_empty());
+
+        let grammar =
+            "//@ accept \"a b\"\n//@ reject \"a\"\nexpr = { \"a\"+ }\nWHITESPACE = _{ \" \" }";
+        let output = check(grammar, &attributes, &sources).to_string();
+        assert_eq!(output.matches("compile_error").count(), 2);
+        assert!(output.contains("`expr` should accept \\\"a b\\\", but it doesn't"));
+        assert!(output.contains("--> 1:1"));
+        assert!(output.contains("1 | a b"));
+        assert!(output.contains("`expr` should reject \\\"a\\\""));
+
+        let mut attributes = Attributes::default();
+        attributes.trivia.insert("expr".to_owned(), String::new());
+        let output = check(grammar, &attributes, &sources).to_string();
+        assert!(output.contains("can't check grammars with `#[trivia]` at build time"));
+    }
+
+    #[test]
+    fn generated() {
+        let sources = [Source {
+            start: 0,
+            path: None,
+            span: proc_macro2::Span::call_site(),
+        }];
+        assert!(generate("expr = { \"a\"+ }", &sources).is_empty());
+
+        let grammar = "//@ accept \"a a\"\n//@ reject \"b\"\n//@ reject \"\"\nexpr = { \"a\"+ }";
+        let output = generate(grammar, &sources).to_string();
+        assert!(output.contains("fn expr_accept_1"));
+        assert!(output.contains("fn expr_reject_1"));
+        assert!(output.contains("fn expr_reject_2"));
+        assert!(output.contains("`expr` should reject \\\"b\\\" (line 2), but it doesn't"));
+
+        let output = generate("//@ accepts \"a\"\nexpr = { \"a\" }", &sources).to_string();
+        assert!(output.starts_with(":: core :: compile_error !"));
+    }
+}
@@ -7,18 +7,21 @@
 // option. All files in the project carrying such notice may not be copied,
 // modified, or distributed except according to those terms.
 
+use crate::attributes::Attributes;
 use crate::config::Config;
 use crate::docs::DocComment;
+use crate::regex::{Regex, Regexes};
 use crate::types::{box_type, option_type, result_type, vec_type};
 use pest::unicode::unicode_property_names;
 use pest_meta::{
     ast::RuleType,
     optimizer::{OptimizedExpr, OptimizedRule},
 };
-use proc_macro2::{Ident, TokenStream};
+use proc_macro2::{Ident, Literal, TokenStream};
 use quote::{format_ident, quote, ToTokens, TokenStreamExt};
 use std::collections::{btree_map, BTreeMap, BTreeSet};
-use syn::Index;
+use std::ptr;
+use syn::{Index, Type};
 
 pub fn pest() -> TokenStream {
     quote! {::pest_typed}
@@ -69,13 +72,83 @@ fn ignore(root: &TokenStream) -> TokenStream {
     }
 }
 
+/// Implicit rules skipped between tokens of a rule.
+#[derive(Clone, Copy)]
+struct Trivia {
+    whitespace: bool,
+    comment: bool,
+    /// Skipped contents can't contain line breaks.
+    same_line: bool,
+}
+impl Trivia {
+    /// Parse the policy in `#[trivia = "..."]`.
+    fn parse(policy: &str, rule_name: &str, defined: &BTreeSet<&str>) -> Self {
+        let mut res = Self {
+            whitespace: false,
+            comment: false,
+            same_line: false,
+        };
+        for word in policy.split_whitespace() {
+            let flag = match word {
+                "WHITESPACE" => &mut res.whitespace,
+                "COMMENT" => &mut res.comment,
+                "same_line" => &mut res.same_line,
+                _ => panic!("Invalid trivia of rule `{rule_name}`: unknown `{word}`."),
+            };
+            *flag = true;
+            if word != "same_line" && !defined.contains(word) {
+                panic!("Invalid trivia of rule `{rule_name}`: `{word}` is not defined.");
+            }
+        }
+        res
+    }
+    /// Type of skipped contents, with `predefined_node` the path to `pest_typed::predefined_node`.
+    fn skipped(self, root: &TokenStream, predefined_node: &TokenStream) -> TokenStream {
+        let pest_typed = pest_typed();
+        let rules_mod = rules_mod();
+        let item = match (self.whitespace, self.comment) {
+            (true, true) => quote! {
+                #pest_typed::choices::Choice2<
+                    #root::#rules_mod::WHITESPACE<'i, 0>,
+                    #root::#rules_mod::COMMENT<'i, 0>,
+                >
+            },
+            (true, false) => quote! {
+                #root::#rules_mod::WHITESPACE<'i, 0>
+            },
+            (false, true) => quote! {
+                #root::#rules_mod::COMMENT<'i, 0>
+            },
+            (false, false) => {
+                return quote! {
+                    #predefined_node::Empty<'i>
+                }
+            }
+        };
+        let item = match self.same_line {
+            true => quote! {#predefined_node::SameLine<#item>},
+            false => item,
+        };
+        let generics = generics();
+        quote! {
+            #predefined_node::AtomicRep<
+                #item,
+                #root::#generics::Allocation,
+            >
+        }
+    }
+}
+
 #[derive(Clone)]
 enum Edge {
     // Type remained.
     Content,
     ContentI(usize),
+    /// Field of a sequence with named captures.
+    Field(Ident),
     // Type wrapped by Option.
-    ChoiceI(usize),
+    /// Accessed by method with given name.
+    ChoiceI(Ident),
     Optional,
     // Type wrapped by Vec.
     Contents,
@@ -96,10 +169,13 @@ enum Node<'g> {
     /// - Type: `#inner`
     /// - Path: `.content.#index.matched`
     SequenceI(usize, Box<Self>),
+    /// - Type: `#inner`
+    /// - Path: `.#field.matched`
+    Field(Ident, Box<Self>),
     // Type wrapped by Option.
     /// - Type: `#opt::<#inner>`
-    /// - Path: `._#index().and_then(|res| Some(#inner)) #flat`
-    ChoiceI(usize, bool, Box<Self>),
+    /// - Path: `.#variant().and_then(|res| Some(#inner)) #flat`
+    ChoiceI(Ident, bool, Box<Self>),
     /// - Type: `#opt::<#inner>`
     /// - Path: `.as_ref().and_then(|res| Some(#inner)) #flat`
     Optional(bool, Box<Self>),
@@ -126,7 +202,9 @@ impl<'g> Node<'g> {
             Node::Rule(_, _, _) => false,
             #[cfg(feature = "grammar-extras")]
             Node::Tag(_) => false,
-            Node::Content(inner) | Node::SequenceI(_, inner) => inner.flattenable(),
+            Node::Content(inner) | Node::SequenceI(_, inner) | Node::Field(_, inner) => {
+                inner.flattenable()
+            }
             Node::ChoiceI(_, false, _) | Node::Optional(false, _) => true,
             Node::ChoiceI(_, true, inner) | Node::Optional(true, inner) => inner.flattenable(),
             Node::Contents(_) | Node::Tuple(_) => false,
@@ -136,6 +214,7 @@ impl<'g> Node<'g> {
         match edge {
             Edge::Content => Self::Content(Box::new(self)),
             Edge::ContentI(i) => Self::SequenceI(i, Box::new(self)),
+            Edge::Field(field) => Self::Field(field, Box::new(self)),
             Edge::ChoiceI(i) => Self::ChoiceI(i, self.flattenable(), Box::new(self)),
             Edge::Optional => Self::Optional(self.flattenable(), Box::new(self)),
             Edge::Contents => Self::Contents(Box::new(self)),
@@ -224,6 +303,10 @@ impl<'g> Node<'g> {
                     quote! {#ty},
                 )
             }
+            Node::Field(field, inner) => {
+                let (pa, ty) = inner.expand(root, config);
+                (quote! {{let res = &res.#field.matched; #pa}}, quote! {#ty})
+            }
             Node::Optional(flatten, inner) => {
                 let (pa, ty) = inner.expand(root, config);
                 let flat = flat(flatten);
@@ -232,9 +315,8 @@ impl<'g> Node<'g> {
                     opt(flatten, ty),
                 )
             }
-            Node::ChoiceI(index, flatten, inner) => {
+            Node::ChoiceI(func, flatten, inner) => {
                 let (pa, ty) = inner.expand(root, config);
-                let func = format_ident!("_{}", index);
                 let flat = flat(flatten);
                 (
                     quote! {{let res = res.#func().map(|res| #pa) #flat; res}},
@@ -285,14 +367,17 @@ impl<'g> Accesser<'g> {
     pub fn content_i(self, i: usize) -> Self {
         self.prepend(Edge::ContentI(i))
     }
+    pub fn field(self, field: Ident) -> Self {
+        self.prepend(Edge::Field(field))
+    }
     pub fn contents(self) -> Self {
         self.prepend(Edge::Contents)
     }
     pub fn optional(self) -> Self {
         self.prepend(Edge::Optional)
     }
-    pub fn choice(self, i: usize) -> Self {
-        self.prepend(Edge::ChoiceI(i))
+    pub fn choice(self, variant: Ident) -> Self {
+        self.prepend(Edge::ChoiceI(variant))
     }
     #[inline]
     fn prepend(mut self, edge: Edge) -> Self {
@@ -320,6 +405,12 @@ impl<'g> Accesser<'g> {
     }
     pub fn collect(&self, root: &TokenStream, config: &RuleConfig<'g>) -> TokenStream {
         let accessers = self.accessers.iter().map(|(name, node)| {
+            if config.non_exhaustive && matches!(*name, "content" | "span") {
+                panic!(
+                    "Accesser function `{name}` of rule `{}` conflicts with the method reading the field of the same name.",
+                    config.rule_name
+                );
+            }
             let id = ident(name);
             let (paths, types) = node.expand(root, config);
             let src = quote! {
@@ -395,14 +486,51 @@ impl ToTokens for Emission {
 struct RuleConfig<'g> {
     pub atomicity: Option<bool>,
     pub rule_id: Ident,
-    #[allow(dead_code)]
     pub rule_name: &'g str,
     pub rule_desc: String,
     pub rule_doc: Option<&'g str>,
     pub defined: &'g BTreeSet<&'g str>,
     pub builtins_without_lifetime: &'g BTreeSet<&'g str>,
+    /// Wrapper rules and the rules they are flattened into.
+    pub wrappers: &'g BTreeMap<&'g str, &'g str>,
+    /// Identifiers of `re"..."` literals and their compiled regular expressions.
+    pub regexes: &'g Regexes,
+    /// Implement [`Display`](core::fmt::Display) for rules with spans.
+    pub display: bool,
+    /// Mark the rule struct `#[non_exhaustive]` and read its fields with methods.
+    pub non_exhaustive: bool,
+    /// Path to the root module from the module that the rule struct is defined in.
+    pub root: TokenStream,
+    /// Wrap the inner expression in `pest_typed::lazy::Lazy`.
+    pub lazy: bool,
+    /// Delegate the region matched by the inner expression to this type with `pest_typed::embed::Embed`.
+    pub embed: Option<TokenStream>,
+    /// Closing literal of a rule with `#[recover]`, to be wrapped in `pest_typed::recovery::Closer`,
+    /// and the path to it from the rule struct.
+    pub closer: Option<(&'g OptimizedExpr, TokenStream)>,
+    /// Implement `pest_typed::recovery::Recovered`, as some rule is marked with `#[recover]`.
+    pub recovery: bool,
+    /// Inclusive range of versions of a rule with `#[cfg(version ...)]`,
+    /// whose inner expression is wrapped in `pest_typed::version::Gated`.
+    pub versions: Option<(u32, u32)>,
+    /// Trivia of a rule with `#[trivia = "..."]`, instead of `WHITESPACE` and `COMMENT` defined in the grammar.
+    pub trivia: Option<Trivia>,
+    /// Inline capacity of repetitions of a rule with `#[inline_capacity = "N"]`.
+    pub inline_capacity: Option<usize>,
+    /// Items of the top-level sequence of a rule with named captures, and their names.
+    pub captures: Option<&'g [(Option<String>, OptimizedExpr)]>,
 }
 impl<'g> RuleConfig<'g> {
+    /// Type of contents skipped between tokens of the rule.
+    fn skipped(&self, root: &TokenStream) -> TokenStream {
+        match self.trivia {
+            Some(trivia) => {
+                let pest_typed = pest_typed();
+                trivia.skipped(root, &quote! {#pest_typed::predefined_node})
+            }
+            None => ignore(root),
+        }
+    }
     fn get_doc<'s>(&'s self) -> impl Iterator<Item = &'s str>
     where
         'g: 's,
@@ -420,10 +548,10 @@ fn rule<'g>(
     accessers: &Accesser<'g>,
     emission: Emission,
 ) -> TokenStream {
-    let root = quote! {super};
+    let root = &rule_config.root;
     let _bool = _bool();
     let accessers = match emission {
-        Emission::Both | Emission::Expression => accessers.collect(&root, rule_config),
+        Emission::Both | Emission::Expression => accessers.collect(root, rule_config),
         Emission::Span => quote! {},
     };
     #[allow(clippy::needless_lifetimes)]
@@ -433,7 +561,7 @@ fn rule<'g>(
         inner_type: &TokenStream,
         emission: Emission,
     ) -> TokenStream {
-        let root = quote! {super};
+        let root = &rule_config.root;
         let pest_typed = pest_typed();
         let name = &rule_config.rule_id;
         let atomicity = match rule_config.atomicity {
@@ -442,12 +570,27 @@ fn rule<'g>(
             None => quote! {INHERITED},
         };
         let docs = rule_config.get_doc();
-        let ignore = ignore(&root);
+        let ignore = rule_config.skipped(root);
+        let display = match emission {
+            Emission::Both | Emission::Span if rule_config.display => quote! {
+                impl<'i, const INHERITED: usize> ::core::fmt::Display for #name<'i, INHERITED> {
+                    fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                        f.write_str(self.span.as_str())
+                    }
+                }
+            },
+            _ => quote! {},
+        };
+        let non_exhaustive = match rule_config.non_exhaustive {
+            true => quote! {, non_exhaustive},
+            false => quote! {},
+        };
         quote! {
-            #pest_typed::rule!(#name, #(#docs)*, #root::Rule, #root::Rule::#name, #inner_type, #ignore, #atomicity, #emission);
+            #pest_typed::rule!(#name, #(#docs)*, #root::Rule, #root::Rule::#name, #inner_type, #ignore, #atomicity, #emission #non_exhaustive);
             impl<'i, const INHERITED: usize> #name<'i, INHERITED> {
                 #accesser_impl
             }
+            #display
         }
     }
     create(rule_config, accessers, type_name, emission)
@@ -461,9 +604,21 @@ struct Output {
     tagged_nodes: BTreeMap<Ident, Vec<TokenStream>>,
     sequences: BTreeSet<usize>,
     choices: BTreeSet<usize>,
+    /// Choices of named rules, from variant names to type names.
+    named_choices: BTreeMap<Vec<String>, Ident>,
+    /// Sequences with named captures, from field names to type names.
+    named_sequences: BTreeMap<Vec<String>, Ident>,
+    /// Methods of trait `Fold`, if enabled.
+    folds: Option<Vec<TokenStream>>,
+    /// Enums of alternatives of rules that are choices, if enabled.
+    branches: Option<Vec<TokenStream>>,
+    /// Definitions in the module of each rule, if enabled.
+    modules: Option<BTreeMap<Ident, Vec<TokenStream>>>,
+    /// Size of generated code of each rule, in bytes.
+    sizes: BTreeMap<String, usize>,
 }
 impl Output {
-    fn new() -> Self {
+    fn new(config: Config) -> Self {
         Self {
             content: Vec::new(),
             wrappers: Vec::new(),
@@ -472,6 +627,12 @@ impl Output {
             tagged_nodes: BTreeMap::new(),
             sequences: BTreeSet::new(),
             choices: BTreeSet::new(),
+            named_choices: BTreeMap::new(),
+            named_sequences: BTreeMap::new(),
+            folds: config.emit_fold.then(Vec::new),
+            branches: config.emit_branches.then(Vec::new),
+            modules: config.module_per_rule.then(BTreeMap::new),
+            sizes: BTreeMap::new(),
         }
     }
     /// Record usage of Seq* generics.
@@ -482,6 +643,37 @@ impl Output {
     fn record_choice(&mut self, index: usize) {
         self.choices.insert(index);
     }
+    /// Record usage of a choice of named rules, and return its type name.
+    fn record_named_choice(&mut self, names: Vec<&str>) -> Ident {
+        let names: Vec<String> = names.into_iter().map(String::from).collect();
+        if let Some(name) = self.named_choices.get(&names) {
+            return name.clone();
+        }
+        let base = format!("Choice_{}", names.join("_"));
+        let mut name = format_ident!("{}", base);
+        let mut i = 1usize;
+        while self.named_choices.values().any(|used| *used == name) {
+            name = format_ident!("{}_{}", base, i);
+            i += 1;
+        }
+        self.named_choices.insert(names, name.clone());
+        name
+    }
+    /// Record usage of a sequence with named captures, and return its type name.
+    fn record_named_seq(&mut self, fields: Vec<String>) -> Ident {
+        if let Some(name) = self.named_sequences.get(&fields) {
+            return name.clone();
+        }
+        let base = format!("Seq_{}", fields.join("_"));
+        let mut name = format_ident!("{}", base);
+        let mut i = 1usize;
+        while self.named_sequences.values().any(|used| *used == name) {
+            name = format_ident!("{}_{}", base, i);
+            i += 1;
+        }
+        self.named_sequences.insert(fields, name.clone());
+        name
+    }
     /// Used sequences.
     fn seq(&self) -> &BTreeSet<usize> {
         &self.sequences
@@ -490,14 +682,144 @@ impl Output {
     fn choices(&self) -> &BTreeSet<usize> {
         &self.choices
     }
-    /// Insert rule struct to rule module.
+    /// Used choices of named rules.
+    fn named_choices(&self) -> &BTreeMap<Vec<String>, Ident> {
+        &self.named_choices
+    }
+    /// Used sequences with named captures.
+    fn named_seq(&self) -> &BTreeMap<Vec<String>, Ident> {
+        &self.named_sequences
+    }
+    /// Size of generated code of each rule, in bytes.
+    fn sizes(&self) -> &BTreeMap<String, usize> {
+        &self.sizes
+    }
+    /// Insert builtin structs to rule module.
     fn insert(&mut self, tokens: TokenStream) {
         self.content.push(tokens);
     }
+    /// Insert definitions of given rule to rule module, or its own module if enabled.
+    fn insert_rule(&mut self, rule_id: &Ident, rule_name: &str, tokens: TokenStream) {
+        *self.sizes.entry(rule_name.to_owned()).or_default() += tokens.to_string().len();
+        match &mut self.modules {
+            Some(modules) => modules.entry(rule_id.clone()).or_default().push(tokens),
+            None => self.content.push(tokens),
+        }
+    }
+    /// Insert a method into trait `Fold` for given rule,
+    /// and implement [`Foldable`](pest_typed::fold::Foldable) for its rule struct.
+    fn insert_fold(&mut self, rule_config: &RuleConfig<'_>) {
+        let rule_id = &rule_config.rule_id;
+        let rule_name = rule_config.rule_name;
+        let root = &rule_config.root;
+        if let Some(folds) = &mut self.folds {
+            let pest_typed = pest_typed();
+            let rules = rules_mod();
+            let method = format_ident!("fold_{}", rule_name);
+            let doc = format!("Fold [`{}`](super::{}::{}).", rule_name, rules, rule_name);
+            folds.push(quote! {
+                #[doc = #doc]
+                #[allow(non_snake_case)]
+                fn #method<const INHERITED: usize>(
+                    &mut self,
+                    node: super::#rules::#rule_id<'i, INHERITED>,
+                ) -> super::#rules::#rule_id<'i, INHERITED> {
+                    node.fold_children(self)
+                }
+            });
+            let def = quote! {
+                impl<'i, const INHERITED: usize, F: #root::fold::Fold<'i> + ?::core::marker::Sized>
+                    #pest_typed::fold::Foldable<F> for #rule_id<'i, INHERITED>
+                {
+                    fn fold_with(self, folder: &mut F) -> Self {
+                        folder.#method(self)
+                    }
+                }
+            };
+            self.insert_rule(rule_id, rule_name, def);
+        }
+    }
+    /// Insert an enum of alternatives into module `branches` for given rule that's a choice,
+    /// and a method `branch` for its rule struct that borrows the matched alternative.
+    ///
+    /// `choice` is the type of the choice, and `variants` are its variants.
+    fn insert_branch(
+        &mut self,
+        rule_config: &RuleConfig<'_>,
+        choice: &TokenStream,
+        variants: &[Ident],
+        alternatives: &[&OptimizedExpr],
+        types: &[TokenStream],
+    ) {
+        let Some(branches) = &mut self.branches else {
+            return;
+        };
+        let rule_id = &rule_config.rule_id;
+        let rule_name = rule_config.rule_name;
+        let root = &rule_config.root;
+        let rules = rules_mod();
+        // Alternatives that are a single rule are named after the rule, unless it's referenced twice.
+        let names: Vec<Ident> = alternatives
+            .iter()
+            .enumerate()
+            .map(|(i, alternative)| match alternative {
+                OptimizedExpr::Ident(name)
+                    if alternatives
+                        .iter()
+                        .filter(|other| matches!(other, OptimizedExpr::Ident(n) if n == name))
+                        .count()
+                        == 1
+                        && !name.starts_with('_') =>
+                {
+                    ident(name)
+                }
+                _ => format_ident!("_{}", i),
+            })
+            .collect();
+        let generics: Vec<_> = (0..names.len()).map(|i| format_ident!("T{}", i)).collect();
+        let doc = format!(
+            "Alternatives of [`{}`](super::{}::{}), in the order they are in the grammar.",
+            rule_name, rules, rule_name
+        );
+        let variant_docs = alternatives
+            .iter()
+            .map(|alternative| format!("`{}`.", alternative));
+        branches.push(quote! {
+            #[doc = #doc]
+            #[allow(non_camel_case_types)]
+            #[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+            pub enum #rule_id<#(#generics, )*> {
+                #(
+                    #[doc = #variant_docs]
+                    #names(#generics),
+                )*
+            }
+        });
+        let def = quote! {
+            impl<'i, const INHERITED: usize> #rule_id<'i, INHERITED> {
+                /// The matched alternative.
+                ///
+                /// Match on it to handle all alternatives,
+                /// so that adding one to the grammar is reported where it's not handled.
+                #[allow(clippy::type_complexity)]
+                pub fn branch(&self) -> #root::branches::#rule_id<#(&#types, )*> {
+                    match &*self.content {
+                        #(
+                            #choice::#variants(node) => #root::branches::#rule_id::#names(node),
+                        )*
+                    }
+                }
+            }
+        };
+        self.insert_rule(rule_id, rule_name, def);
+    }
     /// Insert tag struct to tag module.
     /// Return the module path relative to module root.
     #[cfg(feature = "grammar-extras")]
     fn insert_tag(&mut self, rule_name: &Ident, tokens: TokenStream) -> TokenStream {
+        let name = rule_name.to_string();
+        let name = name.trim_start_matches("r#").to_owned();
+        *self.sizes.entry(name).or_default() += tokens.to_string().len();
         let entry = self.tagged_nodes.entry(rule_name.clone());
         match entry {
             btree_map::Entry::Vacant(entry) => {
@@ -552,6 +874,39 @@ impl Output {
         self.wrappers.push(wrapper);
         quote! {#wrapper_mod::#s}
     }
+    /// Insert a wrapper of a compiled regular expression to corresponding module.
+    /// Return the module path relative to module root.
+    fn insert_regex_wrapper(&mut self, regex: &Regex) -> TokenStream {
+        let s = ident(&format!("w_{}", self.wrapper_counter));
+        self.wrapper_counter += 1;
+        let doc = format!("A wrapper for `re{:?}`.", regex.pattern);
+        let pattern = &regex.pattern;
+        let (little_endian, big_endian) = (
+            Literal::byte_string(&regex.little_endian),
+            Literal::byte_string(&regex.big_endian),
+        );
+        let str = _str();
+        let wrapper_mod = constant_wrappers();
+        let pest_typed = pest_typed();
+        let wrapper = quote! {
+            #[doc = #doc]
+            #[allow(non_camel_case_types)]
+            #[derive(Clone, Hash, PartialEq, Eq)]
+            pub struct #s;
+            impl #pest_typed::regex::RegexWrapper for #s {
+                const PATTERN: &'static #str = #pattern;
+                fn compiled() -> &'static #pest_typed::regex::Compiled {
+                    #[cfg(target_endian = "little")]
+                    static COMPILED: #pest_typed::regex::Compiled = #pest_typed::regex::Compiled::new(#little_endian);
+                    #[cfg(target_endian = "big")]
+                    static COMPILED: #pest_typed::regex::Compiled = #pest_typed::regex::Compiled::new(#big_endian);
+                    &COMPILED
+                }
+            }
+        };
+        self.wrappers.push(wrapper);
+        quote! {#wrapper_mod::#s}
+    }
     /// (nodes, wrappers)
     fn collect(&self) -> TokenStream {
         let content = &self.content;
@@ -577,15 +932,68 @@ impl Output {
         };
         #[cfg(not(feature = "grammar-extras"))]
         let mod_tags = quote! {};
+        let mod_nodes = match &self.modules {
+            Some(modules) => {
+                let names = modules.keys();
+                let mods = modules.iter().map(|(name, def)| {
+                    let doc = format!("Definitions of rule [super::super::{}::{}].", rules, name);
+                    quote! {
+                        #[doc = #doc]
+                        pub mod #name {
+                            #(#def)*
+                        }
+                    }
+                });
+                quote! {
+                    #[doc = "Generated structs in modules, one for each rule."]
+                    pub mod nodes {
+                        #(#mods)*
+                    }
+                    #[doc = "Definitions of statically typed nodes generated by pest-generator."]
+                    pub mod #rules {
+                        #(pub use super::nodes::#names::#names;)*
+                        #(#content)*
+                    }
+                }
+            }
+            None => quote! {
+                #[doc = "Definitions of statically typed nodes generated by pest-generator."]
+                pub mod #rules {
+                    #(#content)*
+                }
+            },
+        };
+        let mod_fold = match &self.folds {
+            Some(folds) => quote! {
+                #[doc = "Tree transformation over generated rule structs."]
+                pub mod fold {
+                    #[doc = "Rebuild a typed tree, node by node."]
+                    #[doc = ""]
+                    #[doc = "By default every method rebuilds the node from its folded children."]
+                    pub trait Fold<'i> {
+                        #(#folds)*
+                    }
+                }
+            },
+            None => quote! {},
+        };
+        let mod_branches = match &self.branches {
+            Some(branches) => quote! {
+                #[doc = "Alternatives of rules that are choices, one enum for each rule."]
+                pub mod branches {
+                    #(#branches)*
+                }
+            },
+            None => quote! {},
+        };
         quote! {
             mod #wrapper_mod {
                 #(#wrappers)*
             }
             #mod_tags
-            #[doc = "Definitions of statically typed nodes generated by pest-generator."]
-            pub mod #rules {
-                #(#content)*
-            }
+            #mod_fold
+            #mod_branches
+            #mod_nodes
         }
     }
 }
@@ -602,8 +1010,65 @@ fn process_single_alias<'g>(
 ) -> (TokenStream, Accesser<'g>) {
     if explicit {
         let rule_id = &rule_config.rule_id;
+        let type_name = match emission {
+            Emission::Both | Emission::Expression if rule_config.embed.is_some() => {
+                let pest_typed = pest_typed();
+                let delegate = &rule_config.embed;
+                quote! {#pest_typed::embed::Embed::<'i, #root::Rule, #type_name, #delegate>}
+            }
+            Emission::Both | Emission::Expression if rule_config.lazy => {
+                let pest_typed = pest_typed();
+                quote! {#pest_typed::lazy::Lazy::<'i, #root::Rule, #type_name>}
+            }
+            _ => type_name,
+        };
+        let type_name = match rule_config.versions {
+            Some((min, max)) => {
+                let pest_typed = pest_typed();
+                quote! {#pest_typed::version::Gated::<#type_name, #min, #max>}
+            }
+            None => type_name,
+        };
+        // Nodes inside embedded regions are foreign, so there are no accessers into them.
+        let accessers = match rule_config.embed {
+            Some(_) => Accesser::new(),
+            None => accessers,
+        };
         let def = rule(rule_config, &type_name, &accessers, emission);
-        map.insert(def);
+        map.insert_rule(rule_id, rule_config.rule_name, def);
+        if let Some((_, path)) = &rule_config.closer {
+            let def = quote! {
+                impl<'i, const INHERITED: usize> #rule_id<'i, INHERITED> {
+                    /// Whether the closer of this rule is missing in the input and synthesized.
+                    pub fn is_synthesized(&self) -> ::core::primitive::bool {
+                        #path.is_synthesized()
+                    }
+                }
+            };
+            map.insert_rule(rule_id, rule_config.rule_name, def);
+        }
+        if rule_config.recovery {
+            let pest_typed = pest_typed();
+            let synthesized = match &rule_config.closer {
+                Some((_, path)) => quote! { #path.is_synthesized() },
+                None => quote! { false },
+            };
+            let content = match emission {
+                Emission::Span => quote! { #pest_typed::recovery::Provenance::PARSED },
+                Emission::Both | Emission::Expression => {
+                    quote! { #pest_typed::recovery::Recovered::provenance(&*self.content) }
+                }
+            };
+            let def = quote! {
+                impl<'i, const INHERITED: usize> #pest_typed::recovery::Recovered for #rule_id<'i, INHERITED> {
+                    fn provenance(&self) -> #pest_typed::recovery::Provenance {
+                        #pest_typed::recovery::rule_provenance(#synthesized, #content)
+                    }
+                }
+            };
+            map.insert_rule(rule_id, rule_config.rule_name, def);
+        }
+        map.insert_fold(rule_config);
         let rules = rules_mod();
         (quote! {#root::#rules::#rule_id::<'i>}, accessers)
     } else {
@@ -611,6 +1076,156 @@ fn process_single_alias<'g>(
     }
 }
 
+/// Names of variants if all alternatives of a choice are distinct rules.
+fn variant_names<'g>(alternatives: &[&'g OptimizedExpr]) -> Option<Vec<&'g str>> {
+    /// Names of methods and helper variants of choices.
+    const RESERVED: [&str; 5] = ["Res", "reference", "if_then", "consume", "consume_if_then"];
+    let mut names = Vec::with_capacity(alternatives.len());
+    for alternative in alternatives {
+        match alternative {
+            OptimizedExpr::Ident(name)
+                if !names.contains(&name.as_str()) && !RESERVED.contains(&name.as_str()) =>
+            {
+                names.push(name.as_str())
+            }
+            _ => return None,
+        }
+    }
+    Some(names)
+}
+
+/// Maximal number of alternatives of a generated choice type.
+///
+/// Macros defining choice types recurse once per alternative,
+/// so larger choices are split into nested ones to stay within the recursion limit.
+const MAX_CHOICE_ARITY: usize = 64;
+
+/// Returns type name of a choice of given alternatives.
+///
+/// Named variants are only used if the choice is not split.
+fn generate_choice<'g>(
+    alternatives: Vec<(TokenStream, Accesser<'g>)>,
+    names: Option<Vec<&'g str>>,
+    map: &mut Output,
+    root: &TokenStream,
+) -> (TokenStream, Accesser<'g>) {
+    if alternatives.len() > MAX_CHOICE_ARITY {
+        let groups = alternatives.len().div_ceil(MAX_CHOICE_ARITY);
+        let size = alternatives.len().div_ceil(groups);
+        let mut alternatives = alternatives.into_iter();
+        let nested = (0..groups)
+            .map(|_| generate_choice(alternatives.by_ref().take(size).collect(), None, map, root))
+            .collect();
+        return generate_choice(nested, None, map, root);
+    }
+    let generics = generics();
+    let mut types = Vec::<TokenStream>::with_capacity(alternatives.len());
+    let mut accesser = Accesser::new();
+    for (i, (child, acc)) in alternatives.into_iter().enumerate() {
+        types.push(child);
+        let variant = match &names {
+            Some(names) => ident(names[i]),
+            None => format_ident!("_{}", i),
+        };
+        accesser = accesser.join(acc.choice(variant));
+    }
+    let choice = match names {
+        Some(names) => map.record_named_choice(names),
+        None => {
+            map.record_choice(types.len());
+            format_ident!("Choice{}", types.len())
+        }
+    };
+    (
+        quote! { #root::#generics::#choice::<#(#types, )*> },
+        accesser,
+    )
+}
+
+/// Returns type name of a sequence of given items,
+/// which is a sequence with named fields if some items are named captures.
+fn generate_seq<'g>(
+    mut vec: Vec<(&'g OptimizedExpr, Option<&'g str>)>,
+    rule_config: &RuleConfig<'g>,
+    map: &mut Output,
+    explicit: bool,
+    emission: Emission,
+    config: Config,
+    root: &TokenStream,
+) -> (TokenStream, Accesser<'g>) {
+    let generics = generics();
+    let skip = match rule_config.atomicity {
+        Some(true) => quote! {0},
+        Some(false) => quote! {1},
+        None => quote! {INHERITED},
+    };
+    let pest_typed = pest_typed();
+    let skipped = rule_config.skipped(root);
+    // Leading `SOI` and trailing `EOI` are checked by `Bounded` instead of being kept in the sequence.
+    let is_builtin = |expr: &OptimizedExpr, name: &str| matches!(expr, OptimizedExpr::Ident(id) if id == name && !rule_config.defined.contains(name));
+    let start = config.do_not_emit_boundaries && is_builtin(vec[0].0, "SOI");
+    let end = config.do_not_emit_boundaries && is_builtin(vec[vec.len() - 1].0, "EOI");
+    let end = match end && vec.len() > usize::from(start) + 1 {
+        true => vec.pop().map(|(expr, _)| {
+            generate_graph_node(expr, rule_config, map, false, emission, config, root).0
+        }),
+        false => None,
+    };
+    let start = start && vec.len() > 1;
+    if start {
+        vec.remove(0);
+    }
+    let fields: Option<Vec<String>> = vec.iter().any(|(_, name)| name.is_some()).then(|| {
+        vec.iter()
+            .enumerate()
+            .map(|(i, (_, name))| name.map_or_else(|| format!("_{i}"), String::from))
+            .collect()
+    });
+    let mut types = Vec::<TokenStream>::with_capacity(vec.len());
+    let mut accesser = Accesser::new();
+    let single = vec.len() == 1 && fields.is_none();
+    for (i, (expr, _)) in vec.into_iter().enumerate() {
+        let (child, acc) =
+            generate_graph_node(expr, rule_config, map, false, emission, config, root);
+        types.push(child);
+        accesser = accesser.join(match (&fields, single) {
+            (Some(fields), _) => acc.field(ident(&fields[i])),
+            (None, true) => acc,
+            (None, false) => acc.content_i(i),
+        });
+    }
+    let inner = match single {
+        true => types.pop().unwrap(),
+        false => {
+            let seq = match fields {
+                Some(fields) => map.record_named_seq(fields),
+                None => {
+                    map.record_seq(types.len());
+                    format_ident!("Seq{}", types.len())
+                }
+            };
+            let args = types
+                .iter()
+                .map(|t| quote! {(#pest_typed::predefined_node::Skipped<#t, #skipped, #skip>)});
+            quote! { #root::#generics::#seq::<#(#args, )*> }
+        }
+    };
+    let (node, accesser) = match (start, end) {
+        (false, None) => (inner, accesser),
+        (start, end) => {
+            let has_end = end.is_some();
+            let end = end.unwrap_or_else(|| quote! {#pest_typed::predefined_node::Empty<'i>});
+            (
+                quote! {
+                    #pest_typed::predefined_node::Bounded::<#inner, #end, #skipped, #skip, #start, #has_end>
+                },
+                accesser.content(),
+            )
+        }
+    };
+    process_single_alias(map, rule_config, node, accesser, root, emission, explicit)
+}
+
 /// Returns type name.
 fn generate_graph_node<'g>(
     expr: &'g OptimizedExpr,
@@ -644,12 +1259,18 @@ fn generate_graph_node<'g>(
     match expr {
         OptimizedExpr::Str(content) => {
             let wrapper = map.insert_string_wrapper(content.as_str());
+            let type_name = quote! { #root::#generics::Str::<#root::#wrapper> };
+            let type_name = match rule_config.closer {
+                Some((closer, _)) if ptr::eq(closer, expr) => {
+                    let pest_typed = pest_typed();
+                    quote! { #pest_typed::recovery::Closer::<'i, #type_name> }
+                }
+                _ => type_name,
+            };
             process_single_alias(
                 map,
                 rule_config,
-                quote! {
-                    #root::#generics::Str::<#root::#wrapper>
-                },
+                type_name,
                 Accesser::new(),
                 root,
                 emission,
@@ -675,10 +1296,10 @@ fn generate_graph_node<'g>(
             rule_config,
             match end {
                 Some(end) => quote! {
-                    #root::#generics::PeekSlice2::<#start, #end>
+                    #root::#generics::PeekSlice2::<'i, #start, #end>
                 },
                 None => quote! {
-                    #root::#generics::PeekSlice1::<#start>
+                    #root::#generics::PeekSlice1::<'i, #start>
                 },
             },
             Accesser::new(),
@@ -701,13 +1322,15 @@ fn generate_graph_node<'g>(
                 explicit,
             )
         }
+        #[cfg(feature = "grammar-extras")]
+        OptimizedExpr::PushLiteral(_) => panic!("`PUSH_LITERAL` is not supported yet."),
         OptimizedExpr::Skip(strings) => {
             let wrapper = map.insert_string_array_wrapper(strings);
             process_single_alias(
                 map,
                 rule_config,
                 quote! {
-                    #root::#generics::Skip::<#root::#wrapper>
+                    #root::#generics::Skip::<'i, #root::#wrapper>
                 },
                 Accesser::new(),
                 root,
@@ -730,12 +1353,28 @@ fn generate_graph_node<'g>(
                 explicit,
             )
         }
+        OptimizedExpr::Ident(id) if rule_config.regexes.contains_key(id.as_str()) => {
+            let wrapper = map.insert_regex_wrapper(&rule_config.regexes[id.as_str()]);
+            let pest_typed = pest_typed();
+            process_single_alias(
+                map,
+                rule_config,
+                quote! {
+                    #pest_typed::regex::Regex::<'i, #root::#wrapper>
+                },
+                Accesser::new(),
+                root,
+                emission,
+                explicit,
+            )
+        }
         OptimizedExpr::Ident(id) => {
+            let id = rule_config.wrappers.get(id.as_str()).copied().unwrap_or(id);
             let inner = ident(id);
             let rules = rules_mod();
-            let has_life_time = rule_config.defined.contains(id.as_str())
-                || !rule_config.builtins_without_lifetime.contains(id.as_str());
-            let has_skip = rule_config.defined.contains(id.as_str());
+            let has_life_time = rule_config.defined.contains(id)
+                || !rule_config.builtins_without_lifetime.contains(id);
+            let has_skip = rule_config.defined.contains(id);
             let generics = match (has_life_time, has_skip) {
                 (true, true) => quote! {::<'i, #skip>},
                 (true, false) => quote! {::<'i>},
@@ -743,7 +1382,7 @@ fn generate_graph_node<'g>(
                 (false, false) => quote! {},
             };
             let accessers = if config.emit_rule_reference {
-                Accesser::from_rule(id, id.as_str(), has_life_time, has_skip)
+                Accesser::from_rule(id, id, has_life_time, has_skip)
             } else {
                 Accesser::new()
             };
@@ -793,53 +1432,45 @@ fn generate_graph_node<'g>(
             generate_graph_node(inner, rule_config, map, explicit, emission, config, root)
         }
         OptimizedExpr::Seq(_, _) => {
-            let vec = walk!(expr, Seq);
-            let mut types = Vec::<TokenStream>::with_capacity(vec.len());
-            let mut accesser = Accesser::new();
-            for (i, expr) in vec.into_iter().enumerate() {
-                let (child, acc) =
-                    generate_graph_node(expr, rule_config, map, false, emission, config, root);
-                types.push(child);
-                accesser = accesser.join(acc.content_i(i));
-            }
-            let seq = format_ident!("Seq{}", types.len());
-            map.record_seq(types.len());
-
-            let pest_typed = pest_typed();
-            let args = types.iter().map(
-                |t| quote! {(#pest_typed::predefined_node::Skipped<#t, #root::generics::Skipped<'i>, #skip>)},
-            );
-            process_single_alias(
-                map,
-                rule_config,
-                quote! { #root::#generics::#seq::<#(#args, )*> },
-                accesser,
-                root,
-                emission,
-                explicit,
-            )
+            let vec = walk!(expr, Seq)
+                .into_iter()
+                .map(|expr| (expr, None))
+                .collect();
+            generate_seq(vec, rule_config, map, explicit, emission, config, root)
         }
         OptimizedExpr::Choice(_, _) => {
             let vec = walk!(expr, Choice);
-            let mut types = Vec::<TokenStream>::with_capacity(vec.len());
-            let mut accesser = Accesser::new();
-            for (i, expr) in vec.into_iter().enumerate() {
-                let (child, acc) =
-                    generate_graph_node(expr, rule_config, map, false, emission, config, root);
-                types.push(child);
-                accesser = accesser.join(acc.choice(i));
+            let names = if config.named_choices {
+                variant_names(&vec)
+            } else {
+                None
+            };
+            let alternatives: Vec<_> = vec
+                .iter()
+                .map(|expr| {
+                    generate_graph_node(expr, rule_config, map, false, emission, config, root)
+                })
+                .collect();
+            let branch = config.emit_branches
+                && explicit
+                && !matches!(emission, Emission::Span)
+                && rule_config.embed.is_none()
+                && !rule_config.lazy
+                && rule_config.versions.is_none()
+                && vec.len() <= MAX_CHOICE_ARITY;
+            let branch = branch.then(|| {
+                let types: Vec<_> = alternatives.iter().map(|(ty, _)| ty.clone()).collect();
+                let variants: Vec<_> = match &names {
+                    Some(names) => names.iter().map(|name| ident(name)).collect(),
+                    None => (0..types.len()).map(|i| format_ident!("_{}", i)).collect(),
+                };
+                (types, variants)
+            });
+            let (choice, accesser) = generate_choice(alternatives, names, map, root);
+            if let Some((types, variants)) = branch {
+                map.insert_branch(rule_config, &choice, &variants, &vec, &types);
             }
-            let choice = format_ident!("Choice{}", types.len());
-            map.record_choice(types.len());
-            process_single_alias(
-                map,
-                rule_config,
-                quote! { #root::#generics::#choice::<#(#types, )*> },
-                accesser,
-                root,
-                emission,
-                explicit,
-            )
+            process_single_alias(map, rule_config, choice, accesser, root, emission, explicit)
         }
         OptimizedExpr::Opt(inner) => {
             let (inner_name, accessers) =
@@ -859,10 +1490,22 @@ fn generate_graph_node<'g>(
         OptimizedExpr::Rep(inner) => {
             let (inner_name, accessers) =
                 generate_graph_node(inner, rule_config, map, false, emission, config, root);
+            let rep = match (rule_config.trivia, rule_config.inline_capacity) {
+                (None, None) => quote! { #root::#generics::Rep::<'i, #skip, #inner_name> },
+                (_, capacity) => {
+                    let pest_typed = pest_typed();
+                    let skipped = rule_config.skipped(root);
+                    let allocation = match capacity {
+                        Some(capacity) => quote! { #pest_typed::allocation::Inline<#capacity> },
+                        None => quote! { #root::#generics::Allocation },
+                    };
+                    quote! { #pest_typed::predefined_node::Rep::<#inner_name, #skipped, #skip, #allocation> }
+                }
+            };
             process_single_alias(
                 map,
                 rule_config,
-                quote! { #root::#generics::Rep::<'i, #skip, #inner_name> },
+                rep,
                 accessers.contents(),
                 root,
                 emission,
@@ -873,10 +1516,22 @@ fn generate_graph_node<'g>(
         OptimizedExpr::RepOnce(inner) => {
             let (inner_name, accessers) =
                 generate_graph_node(inner, rule_config, map, false, emission, config, root);
+            let rep = match (rule_config.trivia, rule_config.inline_capacity) {
+                (None, None) => quote! { #root::#generics::RepOnce::<'i, #skip, #inner_name> },
+                (_, capacity) => {
+                    let pest_typed = pest_typed();
+                    let skipped = rule_config.skipped(root);
+                    let allocation = match capacity {
+                        Some(capacity) => quote! { #pest_typed::allocation::Inline<#capacity> },
+                        None => quote! { #root::#generics::Allocation },
+                    };
+                    quote! { #pest_typed::predefined_node::RepOnce::<#inner_name, #skipped, #skip, #allocation> }
+                }
+            };
             process_single_alias(
                 map,
                 rule_config,
-                quote! { #root::#generics::RepOnce::<'i, #skip, #inner_name> },
+                rep,
                 accessers.contents(),
                 root,
                 emission,
@@ -901,12 +1556,23 @@ fn generate_graph_node<'g>(
                 let rule_id = &rule_config.rule_id;
                 let accessers_def = accesser.collect(new_root, rule_config);
                 let comment = format!("Tag {} referenced by {}.", tag, rule_config.rule_name);
+                let recovered = match rule_config.recovery {
+                    true => quote! {
+                        impl<'i, const INHERITED: usize> #pest_typed::recovery::Recovered for #tag_id<'i, INHERITED> {
+                            fn provenance(&self) -> #pest_typed::recovery::Provenance {
+                                #pest_typed::recovery::Recovered::provenance(&self.content)
+                            }
+                        }
+                    },
+                    false => quote! {},
+                };
                 let def = quote! {
                     #[doc = #comment]
                     #pest_typed::tag!(#tag_id, #new_root::Rule, #inner);
                     impl<'i, const INHERITED: usize> #tag_id<'i, INHERITED> {
                         #accessers_def
                     }
+                    #recovered
                 };
                 let tag_module = map.insert_tag(rule_id, def);
                 let new_accesser = Accesser::from_tag(tag.as_str(), tag.as_str());
@@ -941,10 +1607,17 @@ fn generate_graph<'g: 'f, 'f>(
     rules: &'g [OptimizedRule],
     defined: &'f BTreeSet<&'g str>,
     builtins_without_lifetime: &'f BTreeSet<&'g str>,
+    wrappers: &'f BTreeMap<&'g str, &'g str>,
     config: Config,
     doc: &DocComment,
+    attributes: &Attributes,
 ) -> Output {
-    let mut res = Output::new();
+    let mut res = Output::new(config);
+    let root = if config.module_per_rule {
+        quote! {super::super}
+    } else {
+        quote! {super}
+    };
     for rule in rules.iter() {
         let rule_name = rule.name.as_str();
         let (atomicity, emission) = match rule.ty {
@@ -954,6 +1627,23 @@ fn generate_graph<'g: 'f, 'f>(
             RuleType::CompoundAtomic => (Some(true), Emission::Both),
             RuleType::Atomic => (Some(true), Emission::Span),
         };
+        let embed = attributes.embed.get(rule_name).map(|delegate| {
+            match syn::parse_str::<Type>(delegate) {
+                Ok(delegate) => quote! {#delegate},
+                Err(error) => panic!("Invalid delegate type of rule `{rule_name}`: {error}."),
+            }
+        });
+        let closer = attributes
+            .recover
+            .contains(rule_name)
+            .then(|| closer(rule, embed.is_some() || attributes.lazy.contains(rule_name)));
+        let emission = match (emission, attributes.opaque.get(rule_name)) {
+            (Emission::Expression, _) => emission,
+            _ if embed.is_some() || closer.is_some() => Emission::Both,
+            (_, None) => emission,
+            (_, Some(true)) => Emission::Span,
+            (_, Some(false)) => Emission::Both,
+        };
         let atomicity_doc = match atomicity {
             Some(true) => "Atomic rule.",
             Some(false) => "Non-atomic rule.",
@@ -972,20 +1662,95 @@ fn generate_graph<'g: 'f, 'f>(
             rule_doc,
             defined,
             builtins_without_lifetime,
+            wrappers,
+            regexes: &attributes.regexes,
+            display: config.emit_display,
+            non_exhaustive: config.non_exhaustive_nodes,
+            root: root.clone(),
+            lazy: attributes.lazy.contains(rule_name),
+            embed,
+            closer,
+            recovery: !attributes.recover.is_empty(),
+            versions: attributes.versions.get(rule_name).map(|&(min, max)| {
+                if min > max {
+                    panic!("Rule `{rule_name}` is in no version, as its conditions can't be satisfied together.");
+                }
+                (min, max)
+            }),
+            trivia: attributes
+                .trivia
+                .get(rule_name)
+                .map(|policy| Trivia::parse(policy, rule_name, defined)),
+            inline_capacity: attributes.inline_capacity.get(rule_name).copied(),
+            captures: attributes.captures.get(rule_name).map(Vec::as_slice),
         };
-        generate_graph_node(
-            &rule.expr,
-            &rule_config,
-            &mut res,
-            true,
-            emission,
-            config,
-            &quote! {super},
-        );
+        match rule_config.captures {
+            Some(_) if rule_config.closer.is_some() => {
+                panic!("Rule `{rule_name}` with `#[recover]` can't have named captures.")
+            }
+            Some(items) => {
+                let items = items
+                    .iter()
+                    .map(|(name, expr)| (expr, name.as_deref()))
+                    .collect();
+                generate_seq(items, &rule_config, &mut res, true, emission, config, &root);
+            }
+            None => {
+                generate_graph_node(
+                    &rule.expr,
+                    &rule_config,
+                    &mut res,
+                    true,
+                    emission,
+                    config,
+                    &root,
+                );
+            }
+        }
+    }
+    for (old, new) in &attributes.aliases {
+        if defined.contains(old.as_str()) {
+            panic!("Alias `{old}` of rule `{new}` conflicts with a rule of the same name.");
+        }
+        let doc = format!("Renamed to [`{new}`].");
+        let note = format!("Renamed to `{new}`.");
+        let (old, new) = (ident(old), ident(new));
+        res.insert(quote! {
+            #[doc = #doc]
+            #[deprecated(note = #note)]
+            #[allow(non_camel_case_types)]
+            pub type #old<'i, const INHERITED: ::core::primitive::usize = 1> = #new<'i, INHERITED>;
+        });
     }
     res
 }
 
+/// Closing literal of a rule with `#[recover]`, which ends the inner sequence,
+/// and the path to it from the rule struct.
+fn closer(rule: &OptimizedRule, wrapped: bool) -> (&OptimizedExpr, TokenStream) {
+    let name = &rule.name;
+    if wrapped {
+        panic!("Rule `{name}` with `#[recover]` can't be lazy or embedded.");
+    }
+    let mut last = 0;
+    let mut current = &rule.expr;
+    while let OptimizedExpr::Seq(_, rhs) = current {
+        current = rhs;
+        last += 1;
+    }
+    if !matches!(current, OptimizedExpr::Str(_)) {
+        panic!("Rule `{name}` with `#[recover]` should end with a string literal.");
+    }
+    let path = match last {
+        0 => quote! { self.content },
+        _ => {
+            let last = Index::from(last);
+            quote! { self.content.content.#last.matched }
+        }
+    };
+    (current, path)
+}
+
 fn collect_used_rule<'s>(rule: &'s OptimizedRule, res: &mut BTreeSet<&'s str>) {
     let mut exprs = vec![&rule.expr];
     while let Some(expr) = exprs.pop() {
@@ -1006,6 +1771,8 @@ fn collect_used_rule<'s>(rule: &'s OptimizedRule, res: &mut BTreeSet<&'s str>) {
             OptimizedExpr::Skip(_) => (),
             OptimizedExpr::Push(expr) | OptimizedExpr::RestoreOnErr(expr) => exprs.push(expr),
             #[cfg(feature = "grammar-extras")]
+            OptimizedExpr::PushLiteral(_) => (),
+            #[cfg(feature = "grammar-extras")]
             OptimizedExpr::NodeTag(expr, _) => exprs.push(expr),
         }
     }
@@ -1020,6 +1787,35 @@ fn collect_used_rules<'s>(rules: &'s [OptimizedRule]) -> BTreeSet<&'s str> {
     res
 }
 
+/// Collect rules that only reference another rule and are not atomic,
+/// and map each of them to the first rule in the chain that is not such one.
+fn collect_wrappers(rules: &[OptimizedRule]) -> BTreeMap<&str, &str> {
+    let direct: BTreeMap<&str, &str> = rules
+        .iter()
+        .filter_map(|rule| match (&rule.ty, &rule.expr) {
+            (RuleType::Normal | RuleType::Silent, OptimizedExpr::Ident(inner)) => {
+                Some((rule.name.as_str(), inner.as_str()))
+            }
+            _ => None,
+        })
+        .collect();
+    direct
+        .iter()
+        .filter_map(|(&wrapper, &inner)| {
+            let mut target = inner;
+            let mut visited = BTreeSet::from([wrapper]);
+            while let Some(&next) = direct.get(target) {
+                // Left-recursive rules are rejected by the validator, but never loop here.
+                if !visited.insert(target) {
+                    return None;
+                }
+                target = next;
+            }
+            Some((wrapper, target))
+        })
+        .collect()
+}
+
 #[allow(dead_code)]
 fn collect_reachability<'g>(rules: &'g [OptimizedRule]) -> BTreeMap<&'g str, BTreeSet<&'g str>> {
     let mut res: BTreeMap<&'g str, BTreeSet<&'g str>> = BTreeMap::new();
@@ -1046,6 +1842,8 @@ pub(crate) fn generate_typed_pair_from_rule(
     rules: &[OptimizedRule],
     doc: &DocComment,
     config: Config,
+    attributes: &Attributes,
+    allocation: Option<&Type>,
 ) -> TokenStream {
     let pest_typed = pest_typed();
 
@@ -1062,23 +1860,44 @@ pub(crate) fn generate_typed_pair_from_rule(
         &mut builtins_without_lifetime,
     );
 
+    let wrappers = if config.flatten_wrappers {
+        collect_wrappers(rules)
+    } else {
+        BTreeMap::new()
+    };
+
     let mut graph = generate_graph(
         rules,
         &defined_rules,
         &builtins_without_lifetime,
+        &wrappers,
         config,
         doc,
+        attributes,
     );
 
     graph.insert(quote! {
         #builtin
     });
 
+    if config.report_code_size {
+        let mut sizes: Vec<_> = graph.sizes().iter().collect();
+        sizes.sort_by(|(_, l), (_, r)| r.cmp(l));
+        let total: usize = sizes.iter().map(|(_, size)| **size).sum();
+        eprintln!(
+            "note: generated {} bytes of code for {} rules",
+            total,
+            sizes.len()
+        );
+        for (rule, size) in sizes {
+            eprintln!("  {:>8} bytes  {}", size, rule);
+        }
+    }
+
     let mods = graph.collect();
     let unicode = unicode_mod();
     let generics = {
         let root = quote! {super};
-        let rules_mod = rules_mod();
         let _i32 = _i32();
         let fill = |set: &BTreeSet<usize>,
                     target: &mut Vec<TokenStream>,
@@ -1139,46 +1958,58 @@ pub(crate) fn generate_typed_pair_from_rule(
             Some("choice"),
             false,
         );
+        for (fields, name) in graph.named_seq() {
+            let number = Literal::usize_unsuffixed(fields.len());
+            let (types, fields): (Vec<_>, Vec<_>) = fields
+                .iter()
+                .enumerate()
+                .map(|(i, field)| (format_ident!("T{}", i), ident(field)))
+                .unzip();
+            seq.push(quote! {
+                pest_typed::named_seq!(#name, pest_typed, #number, #(#types, #fields, )*);
+            });
+        }
+        for (names, name) in graph.named_choices() {
+            let module = format_ident!("{}", name.to_string().to_lowercase());
+            let number = Literal::usize_unsuffixed(names.len());
+            let (types, variants): (Vec<_>, Vec<_>) = names
+                .iter()
+                .enumerate()
+                .map(|(i, variant)| (format_ident!("T{}", i), ident(variant)))
+                .unzip();
+            chs.push(quote! {
+                pest_typed::choices!(#name, pest_typed, #module, #number, #(#types, #variants, )*);
+            });
+        }
 
         let has_white_space = defined_rules.contains("WHITESPACE");
         let has_comment = defined_rules.contains("COMMENT");
-        let skip = match (has_white_space, has_comment) {
-            (true, true) => quote! {
-                predefined_node::AtomicRep<
-                    #pest_typed::choices::Choice2<
-                        #root::#rules_mod::WHITESPACE<'i, 0>,
-                        #root::#rules_mod::COMMENT<'i, 0>,
-                    >,
-                >
-            },
-            (true, false) => quote! {
-                predefined_node::AtomicRep<
-                    #root::#rules_mod::WHITESPACE<'i, 0>,
-                >
-            },
-            (false, true) => quote! {
-                predefined_node::AtomicRep<
-                    #root::#rules_mod::COMMENT<'i, 0>,
-                >
-            },
-            (false, false) => quote! {
-                predefined_node::Empty<'i>
-            },
+        let skip = Trivia {
+            whitespace: has_white_space,
+            comment: has_comment,
+            same_line: false,
+        }
+        .skipped(&root, &quote! {predefined_node});
+        let allocation = match allocation {
+            Some(ty) => quote! {#ty},
+            None => quote! {#pest_typed::allocation::Global},
         };
 
         quote! {
             #[doc = "Used generics."]
             pub mod generics {
                 use #pest_typed::{predefined_node, StringArrayWrapper, StringWrapper, TypedNode};
+                /// Storage of repetitions.
+                pub type Allocation = #allocation;
                 /// Skipped content.
                 pub type Skipped<'i> = #skip;
                 pub use predefined_node::{Str, Insens, PeekSlice1, PeekSlice2, Push, Skip, CharRange, Positive, Negative};
                 #(#seq)*
                 #(#chs)*
                 /// Repeat arbitrary times.
-                pub type Rep<'i, const SKIP: usize, T> = predefined_node::Rep<T, Skipped<'i>, SKIP>;
+                pub type Rep<'i, const SKIP: usize, T> = predefined_node::Rep<T, Skipped<'i>, SKIP, Allocation>;
                 /// Repeat at least once.
-                pub type RepOnce<'i, const SKIP: usize, T> = predefined_node::RepOnce<T, Skipped<'i>, SKIP>;
+                pub type RepOnce<'i, const SKIP: usize, T> = predefined_node::RepOnce<T, Skipped<'i>, SKIP, Allocation>;
             }
         }
     };
@@ -1259,6 +2090,21 @@ fn generate_unicode(
                         }
                     }
                 }
+                impl<F: ?::core::marker::Sized> #pest_typed::fold::Foldable<F> for #property_ident {
+                    fn fold_with(self, _folder: &mut F) -> Self {
+                        self
+                    }
+                }
+                impl #pest_typed::metrics::HeapSize for #property_ident {
+                    fn heap_size(&self) -> ::core::primitive::usize {
+                        0
+                    }
+                }
+                impl #pest_typed::recovery::Recovered for #property_ident {
+                    fn provenance(&self) -> #pest_typed::recovery::Provenance {
+                        #pest_typed::recovery::Provenance::PARSED
+                    }
+                }
                 impl ::core::fmt::Debug for #property_ident {
                     fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
                         f.debug_struct(#property)
@@ -1324,7 +2170,7 @@ fn generate_builtin(
     });
 
     insert_builtin!("ANY", ANY);
-    insert_builtin!("SOI", SOI);
+    insert_builtin_with_lifetime!("SOI", SOI);
     insert_builtin_with_lifetime!("PEEK", PEEK);
     insert_builtin_with_lifetime!("PEEK_ALL", PEEK_ALL);
     insert_builtin_with_lifetime!("POP", POP);
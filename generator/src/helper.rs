@@ -17,28 +17,20 @@ use std::path::{Path, PathBuf};
 
 use syn::{Attribute, Expr, ExprLit, Lit, Meta};
 
-pub(crate) fn collect_data(contents: Vec<GrammarSource>) -> (String, Vec<PathBuf>) {
+/// Returns concatenated grammars, paths of grammar files,
+/// and where each grammar starts in the concatenated grammars (with its path if it's a file).
+#[allow(clippy::type_complexity)]
+pub(crate) fn collect_data(
+    contents: Vec<GrammarSource>,
+) -> (String, Vec<PathBuf>, Vec<(usize, Option<PathBuf>)>) {
     let mut data = String::new();
     let mut paths = vec![];
+    let mut starts = vec![];
 
     for content in contents {
         let (_data, _path) = match content {
             GrammarSource::File(ref path) => {
-                let root = env::var("CARGO_MANIFEST_DIR").unwrap_or_else(|_| ".".into());
-
-                // Check whether we can find a file at the path relative to the CARGO_MANIFEST_DIR
-                // first.
-                //
-                // If we cannot find the expected file over there, fallback to the
-                // `CARGO_MANIFEST_DIR/src`, which is the old default and kept for convenience
-                // reasons.
-                // TODO: This could be refactored once `std::path::absolute()` get's stabilized.
-                // https://doc.rust-lang.org/std/path/fn.absolute.html
-                let path = if Path::new(&root).join(path).exists() {
-                    Path::new(&root).join(path)
-                } else {
-                    Path::new(&root).join("src/").join(path)
-                };
+                let path = resolve_path(path);
 
                 let file_name = match path.file_name() {
                     Some(file_name) => file_name,
@@ -54,13 +46,33 @@ pub(crate) fn collect_data(contents: Vec<GrammarSource>) -> (String, Vec<PathBuf
             GrammarSource::Inline(content) => (content, None),
         };
 
+        starts.push((data.len(), _path.clone()));
         data.push_str(&_data);
         if let Some(path) = _path {
             paths.push(path);
         }
     }
 
-    (data, paths)
+    (data, paths, starts)
+}
+
+/// Resolve the path in a `grammar` attribute.
+pub(crate) fn resolve_path(path: &str) -> PathBuf {
+    let root = env::var("CARGO_MANIFEST_DIR").unwrap_or_else(|_| ".".into());
+
+    // Check whether we can find a file at the path relative to the CARGO_MANIFEST_DIR
+    // first.
+    //
+    // If we cannot find the expected file over there, fallback to the
+    // `CARGO_MANIFEST_DIR/src`, which is the old default and kept for convenience
+    // reasons.
+    // TODO: This could be refactored once `std::path::absolute()` get's stabilized.
+    // https://doc.rust-lang.org/std/path/fn.absolute.html
+    if Path::new(&root).join(path).exists() {
+        Path::new(&root).join(path)
+    } else {
+        Path::new(&root).join("src/").join(path)
+    }
 }
 
 #[derive(Debug, PartialEq)]
@@ -0,0 +1,611 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Import grammars in other notations as pest grammars.
+//!
+//! [`from_abnf`] converts ABNF of [RFC 5234](https://www.rfc-editor.org/rfc/rfc5234),
+//! with case-sensitive strings of [RFC 7405](https://www.rfc-editor.org/rfc/rfc7405).
+//!
+//! - Rule names are case-insensitive in ABNF,
+//!   so references are written as the rule is defined, and `-` is replaced with `_`.
+//!   Names reserved in pest, such as `ANY` and `WHITESPACE`, get a trailing `_`.
+//! - Incremental alternatives (`=/`) are merged into the rule.
+//! - Quoted strings are case-insensitive (`^"..."`) unless they are marked with `%s`.
+//! - Core rules of RFC 5234, such as `ALPHA` and `CRLF`, are appended as silent rules if they are used but not defined.
+//! - Comments are kept before the rules they are in or before.
+//!
+//! ABNF matches alternatives by the longest match, while pest takes the first one that matches,
+//! so alternatives that are prefixes of later ones may need to be reordered.
+
+use pest::unicode::unicode_property_names;
+use std::{collections::BTreeMap, fmt};
+
+/// An error in an ABNF grammar.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AbnfError {
+    /// 1-based line of the rule with the error.
+    pub line: usize,
+    /// Description.
+    pub message: String,
+}
+
+impl fmt::Display for AbnfError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at line {}", self.message, self.line)
+    }
+}
+
+impl std::error::Error for AbnfError {}
+
+enum Expr {
+    Alt(Vec<Expr>),
+    Cat(Vec<Expr>),
+    Rep(u32, Option<u32>, Box<Expr>),
+    /// Name of rule, in lowercase.
+    Name(String),
+    Str(String, bool),
+    Range(char, char),
+}
+
+/// Core rules of RFC 5234, with rules they use.
+const CORE: &[(&str, &str, &[&str])] = &[
+    ("ALPHA", r#"'A'..'Z' | 'a'..'z'"#, &[]),
+    ("BIT", r#""0" | "1""#, &[]),
+    ("CHAR", r#"'\u{01}'..'\u{7F}'"#, &[]),
+    ("CR", r#""\r""#, &[]),
+    ("CRLF", r#""\r\n""#, &[]),
+    ("CTL", r#"'\u{00}'..'\u{1F}' | "\u{7F}""#, &[]),
+    ("DIGIT", r#"'0'..'9'"#, &[]),
+    ("DQUOTE", r#""\"""#, &[]),
+    (
+        "HEXDIG",
+        r#"'0'..'9' | ^"A" | ^"B" | ^"C" | ^"D" | ^"E" | ^"F""#,
+        &[],
+    ),
+    ("HTAB", r#""\t""#, &[]),
+    ("LF", r#""\n""#, &[]),
+    ("LWSP", r#"(WSP | CRLF ~ WSP)*"#, &["wsp", "crlf"]),
+    ("OCTET", r#"'\u{00}'..'\u{FF}'"#, &[]),
+    ("SP", r#"" ""#, &[]),
+    ("VCHAR", r#"'\u{21}'..'\u{7E}'"#, &[]),
+    ("WSP", r#"" " | "\t""#, &[]),
+];
+
+const RUST_KEYWORDS: &[&str] = &[
+    "abstract", "alignof", "as", "become", "box", "break", "const", "continue", "crate", "do",
+    "else", "enum", "extern", "false", "final", "fn", "for", "if", "impl", "in", "let", "loop",
+    "macro", "match", "mod", "move", "mut", "offsetof", "override", "priv", "proc", "pure", "pub",
+    "ref", "return", "Self", "self", "sizeof", "static", "struct", "super", "trait", "true",
+    "type", "typeof", "unsafe", "unsized", "use", "virtual", "where", "while", "yield",
+];
+
+const PEST_NAMES: &[&str] = &[
+    "ANY",
+    "DROP",
+    "EOI",
+    "PEEK",
+    "PEEK_ALL",
+    "POP",
+    "POP_ALL",
+    "PUSH",
+    "SOI",
+    "ASCII_DIGIT",
+    "ASCII_NONZERO_DIGIT",
+    "ASCII_BIN_DIGIT",
+    "ASCII_OCT_DIGIT",
+    "ASCII_HEX_DIGIT",
+    "ASCII_ALPHA_LOWER",
+    "ASCII_ALPHA_UPPER",
+    "ASCII_ALPHA",
+    "ASCII_ALPHANUMERIC",
+    "ASCII",
+    "NEWLINE",
+    "WHITESPACE",
+    "COMMENT",
+];
+
+fn reserved(name: &str) -> bool {
+    RUST_KEYWORDS.contains(&name)
+        || PEST_NAMES.contains(&name)
+        || unicode_property_names().any(|property| property == name)
+}
+
+/// Parser of the expression of a rule.
+struct Parser<'s> {
+    rest: &'s str,
+    line: usize,
+}
+
+impl<'s> Parser<'s> {
+    fn error<T>(&self, message: String) -> Result<T, AbnfError> {
+        Err(AbnfError {
+            line: self.line,
+            message,
+        })
+    }
+    fn skip_whitespaces(&mut self) {
+        self.rest = self.rest.trim_start();
+    }
+    fn eat(&mut self, prefix: &str) -> bool {
+        self.skip_whitespaces();
+        match self.rest.strip_prefix(prefix) {
+            Some(rest) => {
+                self.rest = rest;
+                true
+            }
+            None => false,
+        }
+    }
+    fn take_while(&mut self, f: impl Fn(char) -> bool) -> &'s str {
+        let end = self.rest.find(|c| !f(c)).unwrap_or(self.rest.len());
+        let (res, rest) = self.rest.split_at(end);
+        self.rest = rest;
+        res
+    }
+    fn name(&mut self) -> Option<&'s str> {
+        self.skip_whitespaces();
+        if !self.rest.starts_with(|c: char| c.is_ascii_alphabetic()) {
+            return None;
+        }
+        Some(self.take_while(|c| c.is_ascii_alphanumeric() || c == '-'))
+    }
+    fn alternation(&mut self) -> Result<Expr, AbnfError> {
+        let mut items = vec![self.concatenation()?];
+        while self.eat("/") {
+            items.push(self.concatenation()?);
+        }
+        Ok(match items.len() {
+            1 => items.pop().unwrap(),
+            _ => Expr::Alt(items),
+        })
+    }
+    fn concatenation(&mut self) -> Result<Expr, AbnfError> {
+        let mut items = vec![];
+        loop {
+            self.skip_whitespaces();
+            match self.rest.chars().next() {
+                None | Some('/' | ')' | ']') => break,
+                _ => items.push(self.repetition()?),
+            }
+        }
+        match items.len() {
+            0 => self.error(String::from("expected an element")),
+            1 => Ok(items.pop().unwrap()),
+            _ => Ok(Expr::Cat(items)),
+        }
+    }
+    fn number(&mut self) -> Option<u32> {
+        self.take_while(|c| c.is_ascii_digit()).parse().ok()
+    }
+    fn repetition(&mut self) -> Result<Expr, AbnfError> {
+        let min = self.number();
+        let repeat = if self.rest.starts_with('*') {
+            self.rest = &self.rest[1..];
+            Some((min.unwrap_or(0), self.number()))
+        } else {
+            min.map(|n| (n, Some(n)))
+        };
+        let element = self.element()?;
+        Ok(match repeat {
+            Some((min, max)) => Expr::Rep(min, max, Box::new(element)),
+            None => element,
+        })
+    }
+    fn element(&mut self) -> Result<Expr, AbnfError> {
+        self.skip_whitespaces();
+        if let Some(name) = self.name() {
+            return Ok(Expr::Name(name.to_ascii_lowercase()));
+        }
+        if self.eat("(") {
+            let inner = self.alternation()?;
+            return match self.eat(")") {
+                true => Ok(inner),
+                false => self.error(String::from("expected `)`")),
+            };
+        }
+        if self.eat("[") {
+            let inner = self.alternation()?;
+            return match self.eat("]") {
+                true => Ok(Expr::Rep(0, Some(1), Box::new(inner))),
+                false => self.error(String::from("expected `]`")),
+            };
+        }
+        if self.rest.starts_with('"') {
+            return self.string(true);
+        }
+        if self.rest.starts_with('<') {
+            let end = self.rest.find('>').map_or(self.rest.len(), |end| end + 1);
+            return self.error(format!(
+                "prose value `{}` can't be converted",
+                &self.rest[..end]
+            ));
+        }
+        if let Some(rest) = self.rest.strip_prefix('%') {
+            self.rest = rest;
+            return self.numeric();
+        }
+        match self.rest.chars().next() {
+            Some(c) => self.error(format!("unexpected `{}`", c)),
+            None => self.error(String::from("unexpected end of rule")),
+        }
+    }
+    fn string(&mut self, insensitive: bool) -> Result<Expr, AbnfError> {
+        let Some(end) = self.rest[1..].find('"') else {
+            return self.error(String::from("unterminated string"));
+        };
+        let text = String::from(&self.rest[1..end + 1]);
+        self.rest = &self.rest[end + 2..];
+        Ok(Expr::Str(text, insensitive))
+    }
+    fn numeric(&mut self) -> Result<Expr, AbnfError> {
+        let mut chars = self.rest.chars();
+        let radix = match chars.next() {
+            Some('b' | 'B') => 2,
+            Some('d' | 'D') => 10,
+            Some('x' | 'X') => 16,
+            Some(c @ ('s' | 'S' | 'i' | 'I')) if chars.next() == Some('"') => {
+                self.rest = &self.rest[1..];
+                return self.string(c.eq_ignore_ascii_case(&'i'));
+            }
+            _ => return self.error(String::from("expected `b`, `d`, `x`, `s` or `i` after `%`")),
+        };
+        self.rest = &self.rest[1..];
+        let first = self.value(radix)?;
+        if let Some(rest) = self.rest.strip_prefix('-') {
+            self.rest = rest;
+            let last = self.value(radix)?;
+            return Ok(Expr::Range(first, last));
+        }
+        let mut text = String::from(first);
+        while let Some(rest) = self.rest.strip_prefix('.') {
+            self.rest = rest;
+            text.push(self.value(radix)?);
+        }
+        Ok(Expr::Str(text, false))
+    }
+    fn value(&mut self, radix: u32) -> Result<char, AbnfError> {
+        let digits = self.take_while(|c| c.is_digit(radix));
+        match u32::from_str_radix(digits, radix)
+            .ok()
+            .and_then(char::from_u32)
+        {
+            Some(c) => Ok(c),
+            None => self.error(format!("invalid character value `{}`", digits)),
+        }
+    }
+}
+
+/// Escape a string or character literal in pest.
+fn escape(s: &str, quote: char) -> String {
+    let mut res = String::new();
+    for c in s.chars() {
+        match c {
+            '\\' => res.push_str("\\\\"),
+            c if c == quote => {
+                res.push('\\');
+                res.push(c);
+            }
+            ' '..='~' => res.push(c),
+            '\n' => res.push_str("\\n"),
+            '\r' => res.push_str("\\r"),
+            '\t' => res.push_str("\\t"),
+            c => res.push_str(&format!("\\u{{{:02X}}}", c as u32)),
+        }
+    }
+    res
+}
+
+/// Print `expr` in pest, with parentheses if it's looser than `prec`
+/// (0 for choices, 1 for sequences and 2 for atoms).
+fn print(expr: &Expr, names: &BTreeMap<String, String>, prec: u8) -> String {
+    let (res, own) = match expr {
+        Expr::Alt(items) => {
+            let items: Vec<_> = items.iter().map(|item| print(item, names, 1)).collect();
+            (items.join(" | "), 0)
+        }
+        Expr::Cat(items) => {
+            let items: Vec<_> = items.iter().map(|item| print(item, names, 2)).collect();
+            (items.join(" ~ "), 1)
+        }
+        Expr::Rep(min, max, inner) => {
+            let inner = print(inner, names, 3);
+            let suffix = match (min, max) {
+                (0, None) => String::from("*"),
+                (1, None) => String::from("+"),
+                (0, Some(1)) => String::from("?"),
+                (min, None) => format!("{{{},}}", min),
+                (0, Some(max)) => format!("{{, {}}}", max),
+                (min, Some(max)) if min == max => format!("{{{}}}", min),
+                (min, Some(max)) => format!("{{{}, {}}}", min, max),
+            };
+            (format!("{}{}", inner, suffix), 2)
+        }
+        Expr::Name(name) => (names[name].clone(), 3),
+        Expr::Str(text, insensitive) => {
+            let prefix = match *insensitive && text.chars().any(|c| c.is_ascii_alphabetic()) {
+                true => "^",
+                false => "",
+            };
+            (format!("{}\"{}\"", prefix, escape(text, '"')), 3)
+        }
+        Expr::Range(start, end) => (
+            format!(
+                "'{}'..'{}'",
+                escape(&start.to_string(), '\''),
+                escape(&end.to_string(), '\'')
+            ),
+            3,
+        ),
+    };
+    match own < prec {
+        true => format!("({})", res),
+        false => res,
+    }
+}
+
+fn references<'e>(expr: &'e Expr, res: &mut Vec<&'e str>) {
+    match expr {
+        Expr::Alt(items) | Expr::Cat(items) => {
+            for item in items {
+                references(item, res);
+            }
+        }
+        Expr::Rep(_, _, inner) => references(inner, res),
+        Expr::Name(name) => res.push(name),
+        Expr::Str(_, _) | Expr::Range(_, _) => (),
+    }
+}
+
+/// Text of `line` before its comment, and the comment.
+fn split_comment(line: &str) -> (&str, Option<&str>) {
+    let mut quoted = None;
+    for (i, c) in line.char_indices() {
+        match (quoted, c) {
+            (None, '"') => quoted = Some('"'),
+            (None, '<') => quoted = Some('>'),
+            (Some(end), c) if c == end => quoted = None,
+            (None, ';') => return (&line[..i], Some(line[i + 1..].trim())),
+            _ => (),
+        }
+    }
+    (line, None)
+}
+
+struct Definition<'s> {
+    comments: Vec<&'s str>,
+    line: usize,
+    text: String,
+}
+
+struct Rule<'s> {
+    /// Name in lowercase.
+    key: String,
+    /// Name as it's defined, with `-` replaced.
+    name: String,
+    comments: Vec<&'s str>,
+    alternatives: Vec<Expr>,
+    line: usize,
+}
+
+/// Convert an ABNF grammar into a pest grammar. See [module-level documentation](self).
+pub fn from_abnf(abnf: &str) -> Result<String, AbnfError> {
+    // Rules continue on lines that start with whitespaces.
+    let mut definitions: Vec<Definition<'_>> = vec![];
+    let mut comments = vec![];
+    for (i, line) in abnf.lines().enumerate() {
+        let (text, comment) = split_comment(line);
+        let continued = line.starts_with(char::is_whitespace) && !text.trim().is_empty();
+        match definitions.last_mut() {
+            Some(definition) if continued => {
+                definition.comments.extend(comment);
+                definition.text.push(' ');
+                definition.text.push_str(text);
+            }
+            _ if text.trim().is_empty() => comments.extend(comment),
+            _ => {
+                comments.extend(comment);
+                definitions.push(Definition {
+                    comments: std::mem::take(&mut comments),
+                    line: i + 1,
+                    text: String::from(text),
+                });
+            }
+        }
+    }
+
+    // Rules in the order they are defined.
+    let mut rules: Vec<Rule<'_>> = vec![];
+    for definition in definitions {
+        let mut parser = Parser {
+            rest: &definition.text,
+            line: definition.line,
+        };
+        let Some(name) = parser.name() else {
+            return parser.error(String::from("expected a rule name"));
+        };
+        let incremental = if parser.eat("=/") {
+            true
+        } else if parser.eat("=") {
+            false
+        } else {
+            return parser.error(String::from("expected `=` or `=/`"));
+        };
+        let expr = parser.alternation()?;
+        if !parser.rest.trim().is_empty() {
+            return parser.error(format!("unexpected `{}`", parser.rest.trim()));
+        }
+        let key = name.to_ascii_lowercase();
+        match rules.iter_mut().find(|rule| rule.key == key) {
+            Some(rule) if incremental => {
+                rule.comments.extend(definition.comments);
+                rule.alternatives.push(expr);
+            }
+            Some(_) => return parser.error(format!("rule `{}` is already defined", name)),
+            None if incremental => {
+                return parser.error(format!("rule `{}` is extended before it's defined", name))
+            }
+            None => rules.push(Rule {
+                key,
+                name: name.replace('-', "_"),
+                comments: definition.comments,
+                alternatives: vec![expr],
+                line: definition.line,
+            }),
+        }
+    }
+
+    let mut names = BTreeMap::new();
+    for rule in &rules {
+        let mut name = rule.name.clone();
+        while reserved(&name) || names.values().any(|other| *other == name) {
+            name.push('_');
+        }
+        names.insert(rule.key.clone(), name);
+    }
+    let mut core = vec![];
+    let mut pending = vec![];
+    for rule in &rules {
+        let mut refs = vec![];
+        for expr in &rule.alternatives {
+            references(expr, &mut refs);
+        }
+        for reference in refs {
+            if names.contains_key(reference) || pending.contains(&reference) {
+                continue;
+            }
+            match CORE
+                .iter()
+                .any(|(core, _, _)| core.eq_ignore_ascii_case(reference))
+            {
+                true => pending.push(reference),
+                false => {
+                    return Err(AbnfError {
+                        line: rule.line,
+                        message: format!(
+                            "rule `{}` used in `{}` is not defined",
+                            reference, rule.name
+                        ),
+                    })
+                }
+            }
+        }
+    }
+    while let Some(reference) = pending.pop() {
+        if names.contains_key(reference) {
+            continue;
+        }
+        let &(name, body, deps) = CORE
+            .iter()
+            .find(|(core, _, _)| core.eq_ignore_ascii_case(reference))
+            .unwrap();
+        names.insert(String::from(reference), String::from(name));
+        core.push((name, body));
+        pending.extend(deps);
+    }
+    core.sort();
+
+    let mut res = String::new();
+    for Rule {
+        key,
+        comments,
+        alternatives,
+        ..
+    } in rules
+    {
+        for comment in comments {
+            res.push_str(format!("// {}", comment).trim_end());
+            res.push('\n');
+        }
+        let expr = match alternatives.len() {
+            1 => alternatives.into_iter().next().unwrap(),
+            _ => Expr::Alt(alternatives),
+        };
+        res.push_str(&format!(
+            "{} = {{ {} }}\n",
+            names[&key],
+            print(&expr, &names, 0)
+        ));
+    }
+    for comment in comments {
+        res.push_str(format!("// {}", comment).trim_end());
+        res.push('\n');
+    }
+    if !core.is_empty() {
+        res.push_str("\n// Core rules of RFC 5234.\n");
+        for (name, body) in core {
+            res.push_str(&format!("{} = _{{ {} }}\n", name, body));
+        }
+    }
+    Ok(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{from_abnf, AbnfError};
+    use pest_meta::parse_and_optimize;
+
+    #[test]
+    fn abnf() {
+        let abnf = r#"; A URI, simplified.
+URI         = scheme ":" hier-part [ "?" query ]
+scheme      = ALPHA *( ALPHA / DIGIT / "+" / "-" / "." ) ; RFC 3986
+hier-part   = "//" 1*pchar
+hier-part   =/ 0*1pchar
+query       = *( pchar / "/" / "?" )
+pchar       = %x41-5A / %s"%" 2HEXDIG / %d59.58 / type
+type        = 2*3%b1100001 *4 DQUOTE
+; End.
+"#;
+        let pest = from_abnf(abnf).unwrap();
+        assert_eq!(
+            pest,
+            r#"// A URI, simplified.
+URI = { scheme ~ ":" ~ hier_part ~ ("?" ~ query)? }
+// RFC 3986
+scheme = { ALPHA ~ (ALPHA | DIGIT | "+" | "-" | ".")* }
+hier_part = { "//" ~ pchar+ | pchar? }
+query = { (pchar | "/" | "?")* }
+pchar = { 'A'..'Z' | "%" ~ HEXDIG{2} | ";:" | type_ }
+type_ = { "a"{2, 3} ~ DQUOTE{, 4} }
+// End.
+
+// Core rules of RFC 5234.
+ALPHA = _{ 'A'..'Z' | 'a'..'z' }
+DIGIT = _{ '0'..'9' }
+DQUOTE = _{ "\"" }
+HEXDIG = _{ '0'..'9' | ^"A" | ^"B" | ^"C" | ^"D" | ^"E" | ^"F" }
+"#
+        );
+        parse_and_optimize(&pest).unwrap();
+
+        let pest = from_abnf("a = \"Ab\" / %i\"c\"\r\n  / LWSP").unwrap();
+        assert!(pest.starts_with("a = { ^\"Ab\" | ^\"c\" | LWSP }\n"));
+        assert!(pest.contains("LWSP = _{ (WSP | CRLF ~ WSP)* }\nWSP = _{"));
+        parse_and_optimize(&pest).unwrap();
+    }
+
+    #[test]
+    fn errors() {
+        let error = |abnf: &str| from_abnf(abnf).unwrap_err();
+        assert_eq!(
+            error("a = b\n\nb = <prose>"),
+            AbnfError {
+                line: 3,
+                message: String::from("prose value `<prose>` can't be converted"),
+            }
+        );
+        assert_eq!(
+            error("a = b").to_string(),
+            "rule `b` used in `a` is not defined at line 1"
+        );
+        assert_eq!(error("a = \"x\"\nA = \"y\"").line, 2);
+        assert_eq!(error("a =/ \"x\"").line, 1);
+        assert_eq!(error("a = ( \"x\"").message, "expected `)`");
+    }
+}
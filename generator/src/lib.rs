@@ -15,7 +15,10 @@
 //!
 //!    Enables extra grammars of [`pest`].
 //!
-//! It can create corresponding type definitions from pest grammar files.
+//! It can create corresponding type definitions from pest grammar files,
+//! either in the derive macro of `pest_typed_derive` or in build scripts with [`build`].
+//! Grammars can also be exported to other notations with [`export`], and imported from them with [`import`],
+//! and formatted with [`format`].
 //!
 //! See [pest_typed](https://docs.rs/pest_typed/latest/pest_typed/) for related traits and types.
 
@@ -27,10 +30,30 @@
     future_incompatible
 )]
 
+mod analysis;
+mod attributes;
+pub mod build;
+mod captures;
+mod classes;
 mod config;
+mod dynamic;
+mod events;
+pub mod export;
+pub mod format;
+mod grammar_tests;
 mod graph;
+pub mod import;
+mod lint;
 mod match_choices;
+mod owned;
+mod prepare;
+mod reflection;
+mod regex;
+mod report;
+mod scanner;
+mod syntax;
 mod typed;
+pub use lint::{lint_grammar, GrammarLint};
 pub use match_choices::match_choices;
 pub use typed::derive_typed_parser;
 
@@ -0,0 +1,494 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Lints on grammars, reported with spans into grammar files.
+//!
+//! - A choice alternative after one that never fails is unreachable.
+//! - A choice alternative is shadowed by an earlier one that is the same, or a string that is a prefix of it.
+//! - An unbounded repetition of an expression that may match empty input risks an infinite loop.
+//! - A silent rule is unused if it can't be reached from entry rules,
+//!   which are rules that are not silent, as any of them may be parsed from, and implicit `WHITESPACE` and `COMMENT`.
+//!
+//! References to undefined rules are errors reported by [pest_meta] when the grammar is validated.
+
+use crate::analysis::builtin;
+use pest::{
+    error::{Error, ErrorVariant},
+    iterators::{Pair, Pairs},
+    Span,
+};
+use pest_meta::parser::{parse, Rule};
+use std::collections::{BTreeMap, BTreeSet};
+
+enum Expr<'i> {
+    /// Raw (escaped) content of a string.
+    Str(&'i str),
+    /// Raw (escaped) content of a case-insensitive string.
+    Insens(&'i str),
+    Ident(&'i str),
+    Range,
+    Peek,
+    PushLiteral,
+    Seq(Vec<Node<'i>>),
+    Choice(Vec<Node<'i>>),
+    Pred(bool, Box<Node<'i>>),
+    Repeat(Box<Node<'i>>, u32, Option<u32>),
+    Push(Box<Node<'i>>),
+}
+
+struct Node<'i> {
+    expr: Expr<'i>,
+    span: Span<'i>,
+}
+
+fn join<'i>(start: &Span<'i>, end: &Span<'i>) -> Span<'i> {
+    start.start_pos().span(&end.end_pos())
+}
+
+fn number(pair: Pair<'_, Rule>) -> u32 {
+    pair.into_inner()
+        .find(|pair| pair.as_rule() == Rule::number)
+        .map_or(0, |pair| pair.as_str().parse().unwrap_or(0))
+}
+
+fn numbers(pair: Pair<'_, Rule>) -> Vec<u32> {
+    pair.into_inner()
+        .filter(|pair| pair.as_rule() == Rule::number)
+        .map(|pair| pair.as_str().parse().unwrap_or(0))
+        .collect()
+}
+
+fn string(pair: Pair<'_, Rule>) -> &str {
+    pair.into_inner()
+        .find(|pair| pair.as_rule() == Rule::inner_str)
+        .map_or("", |pair| pair.as_str())
+}
+
+fn consume_terminal(pair: Pair<'_, Rule>) -> Node<'_> {
+    let span = pair.as_span();
+    let expr = match pair.as_rule() {
+        Rule::expression => return consume_expression(pair),
+        Rule::identifier => Expr::Ident(pair.as_str()),
+        Rule::string => Expr::Str(string(pair)),
+        Rule::insensitive_string => Expr::Insens(pair.into_inner().next().map_or("", string)),
+        Rule::range => Expr::Range,
+        Rule::peek_slice => Expr::Peek,
+        Rule::_push => {
+            let inner = pair
+                .into_inner()
+                .find(|pair| pair.as_rule() == Rule::expression)
+                .unwrap();
+            Expr::Push(Box::new(consume_expression(inner)))
+        }
+        _ => Expr::PushLiteral,
+    };
+    Node { expr, span }
+}
+
+fn consume_term(pair: Pair<'_, Rule>) -> Node<'_> {
+    let span = pair.as_span();
+    let mut prefixes = vec![];
+    let mut node = None;
+    for pair in pair.into_inner() {
+        match (pair.as_rule(), node.take()) {
+            (Rule::tag_id | Rule::assignment_operator, None) => (),
+            (Rule::positive_predicate_operator, None) => prefixes.push(true),
+            (Rule::negative_predicate_operator, None) => prefixes.push(false),
+            (Rule::opening_paren | Rule::closing_paren, prev) => node = prev,
+            (_, None) => node = Some(consume_terminal(pair)),
+            (rule, Some(inner)) => {
+                let (min, max) = match rule {
+                    Rule::optional_operator => (0, Some(1)),
+                    Rule::repeat_operator => (0, None),
+                    Rule::repeat_once_operator => (1, None),
+                    Rule::repeat_exact => {
+                        let n = number(pair.clone());
+                        (n, Some(n))
+                    }
+                    Rule::repeat_min => (number(pair.clone()), None),
+                    Rule::repeat_max => (0, Some(number(pair.clone()))),
+                    _ => match numbers(pair.clone())[..] {
+                        [min, max] => (min, Some(max)),
+                        _ => (0, None),
+                    },
+                };
+                let span = join(&inner.span, &pair.as_span());
+                node = Some(Node {
+                    expr: Expr::Repeat(Box::new(inner), min, max),
+                    span,
+                });
+            }
+        }
+    }
+    let mut node = node.unwrap();
+    for positive in prefixes.into_iter().rev() {
+        node = Node {
+            expr: Expr::Pred(positive, Box::new(node)),
+            span,
+        };
+    }
+    node
+}
+
+fn consume_expression(pair: Pair<'_, Rule>) -> Node<'_> {
+    let span = pair.as_span();
+    let mut choices: Vec<Vec<Node<'_>>> = vec![vec![]];
+    for pair in pair.into_inner() {
+        match pair.as_rule() {
+            Rule::choice_operator => {
+                if !choices.last().unwrap().is_empty() {
+                    choices.push(vec![]);
+                }
+            }
+            Rule::sequence_operator => (),
+            _ => choices.last_mut().unwrap().push(consume_term(pair)),
+        }
+    }
+    let mut choices: Vec<_> = choices
+        .into_iter()
+        .map(|mut seq| {
+            if seq.len() == 1 {
+                seq.pop().unwrap()
+            } else {
+                let span = join(&seq[0].span, &seq[seq.len() - 1].span);
+                Node {
+                    expr: Expr::Seq(seq),
+                    span,
+                }
+            }
+        })
+        .collect();
+    if choices.len() == 1 {
+        choices.pop().unwrap()
+    } else {
+        Node {
+            expr: Expr::Choice(choices),
+            span,
+        }
+    }
+}
+
+/// Whether a node may match empty input, and whether it never fails.
+type Properties<'i> = BTreeMap<&'i str, (bool, bool)>;
+
+impl<'i> Node<'i> {
+    fn nullable(&self, rules: &Properties<'i>) -> bool {
+        match &self.expr {
+            Expr::Str(s) | Expr::Insens(s) => s.is_empty(),
+            Expr::Ident(name) => match rules.get(name) {
+                Some((nullable, _)) => *nullable,
+                None => builtin(name).nullable,
+            },
+            Expr::Range => false,
+            Expr::Peek | Expr::PushLiteral | Expr::Pred(_, _) => true,
+            Expr::Seq(items) => items.iter().all(|item| item.nullable(rules)),
+            Expr::Choice(items) => items.iter().any(|item| item.nullable(rules)),
+            Expr::Repeat(inner, min, _) => *min == 0 || inner.nullable(rules),
+            Expr::Push(inner) => inner.nullable(rules),
+        }
+    }
+    fn never_fails(&self, rules: &Properties<'i>) -> bool {
+        match &self.expr {
+            Expr::Str(s) | Expr::Insens(s) => s.is_empty(),
+            Expr::Ident(name) => rules.get(name).is_some_and(|(_, never_fails)| *never_fails),
+            Expr::Range | Expr::Peek => false,
+            Expr::PushLiteral => true,
+            Expr::Pred(positive, inner) => *positive && inner.never_fails(rules),
+            Expr::Seq(items) => items.iter().all(|item| item.never_fails(rules)),
+            Expr::Choice(items) => items.iter().any(|item| item.never_fails(rules)),
+            Expr::Repeat(inner, min, _) => *min == 0 || inner.never_fails(rules),
+            Expr::Push(inner) => inner.never_fails(rules),
+        }
+    }
+    /// The string this node starts with, and whether it's case-insensitive.
+    fn leading(&self) -> Option<(&'i str, bool)> {
+        match &self.expr {
+            Expr::Str(s) => Some((s, false)),
+            Expr::Insens(s) => Some((s, true)),
+            Expr::Seq(items) => items[0].leading(),
+            Expr::Push(inner) => inner.leading(),
+            _ => None,
+        }
+    }
+    /// Collect rules referenced by this node.
+    fn references(&self, res: &mut Vec<&'i str>) {
+        match &self.expr {
+            Expr::Ident(name) => res.push(name),
+            Expr::Seq(items) | Expr::Choice(items) => {
+                for item in items {
+                    item.references(res);
+                }
+            }
+            Expr::Pred(_, inner) | Expr::Repeat(inner, _, _) | Expr::Push(inner) => {
+                inner.references(res)
+            }
+            _ => (),
+        }
+    }
+    fn lint(&self, rules: &Properties<'i>, res: &mut Vec<GrammarLint>) {
+        let mut warn = |span: Span<'_>, code: &'static str, message: String| {
+            res.push(GrammarLint {
+                code,
+                error: Error::new_from_span(ErrorVariant::CustomError { message }, span),
+            })
+        };
+        match &self.expr {
+            Expr::Choice(items) => {
+                if let Some(i) = items.iter().position(|item| item.never_fails(rules)) {
+                    for item in &items[i + 1..] {
+                        warn(
+                            item.span,
+                            "unreachable_alternative",
+                            format!(
+                                "unreachable alternative: `{}` never fails",
+                                items[i].span.as_str()
+                            ),
+                        );
+                    }
+                }
+                for (j, later) in items.iter().enumerate() {
+                    let shadowing = items[..j].iter().find(|earlier| {
+                        if earlier.span.as_str() == later.span.as_str() {
+                            return true;
+                        }
+                        match (&earlier.expr, later.leading()) {
+                            (Expr::Str(prefix), Some((s, false))) => s.starts_with(prefix),
+                            (Expr::Insens(prefix), Some((s, _))) => s
+                                .to_ascii_lowercase()
+                                .starts_with(&prefix.to_ascii_lowercase()),
+                            _ => false,
+                        }
+                    });
+                    if let Some(earlier) = shadowing {
+                        warn(
+                            later.span,
+                            "shadowed_alternative",
+                            format!(
+                                "alternative is shadowed by an earlier alternative `{}`",
+                                earlier.span.as_str()
+                            ),
+                        );
+                    }
+                }
+            }
+            Expr::Repeat(inner, _, None) if inner.nullable(rules) => warn(
+                self.span,
+                "empty_repetition",
+                format!(
+                    "`{}` may match empty input inside a repetition, which can loop forever",
+                    inner.span.as_str()
+                ),
+            ),
+            _ => (),
+        }
+        match &self.expr {
+            Expr::Seq(items) | Expr::Choice(items) => {
+                for item in items {
+                    item.lint(rules, res);
+                }
+            }
+            Expr::Pred(_, inner) | Expr::Repeat(inner, _, _) | Expr::Push(inner) => {
+                inner.lint(rules, res)
+            }
+            _ => (),
+        }
+    }
+}
+
+/// A warning on a grammar.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct GrammarLint {
+    /// Name of the lint, such as `unreachable_alternative`, `shadowed_alternative`, `empty_repetition` or `unused_rule`.
+    pub code: &'static str,
+    /// Message and location in the grammar.
+    pub error: Error<Rule>,
+}
+
+/// Silent rules that can't be reached from entry rules, and spans of their names.
+fn unused<'i>(rules: &[(&'i str, Node<'i>)], names: &[(Span<'i>, bool)]) -> Vec<Span<'i>> {
+    let references: BTreeMap<&str, Vec<&str>> = rules
+        .iter()
+        .map(|(name, node)| {
+            let mut res = vec![];
+            node.references(&mut res);
+            (*name, res)
+        })
+        .collect();
+    let mut pending: Vec<&str> = rules
+        .iter()
+        .zip(names)
+        .filter(|(_, (_, silent))| !silent)
+        .map(|((name, _), _)| *name)
+        .chain(["WHITESPACE", "COMMENT"])
+        .collect();
+    let mut reachable = BTreeSet::new();
+    while let Some(name) = pending.pop() {
+        if reachable.insert(name) {
+            pending.extend(references.get(name).into_iter().flatten());
+        }
+    }
+    rules
+        .iter()
+        .zip(names)
+        .filter(|((name, _), _)| !reachable.contains(name))
+        .map(|(_, (span, _))| *span)
+        .collect()
+}
+
+/// Lint all rules in `pairs`, parsed with [`Rule::grammar_rules`].
+///
+/// Rules in `allow_unused` are not reported as unused.
+pub(crate) fn lint(pairs: Pairs<'_, Rule>, allow_unused: &BTreeSet<String>) -> Vec<GrammarLint> {
+    let (rules, names): (Vec<_>, Vec<_>) = pairs
+        .filter(|pair| pair.as_rule() == Rule::grammar_rule)
+        .filter_map(|pair| {
+            let mut inner = pair.into_inner();
+            let name = inner.next()?;
+            let silent = inner
+                .clone()
+                .any(|pair| pair.as_rule() == Rule::silent_modifier);
+            let expr = inner.find(|pair| pair.as_rule() == Rule::expression)?;
+            Some((
+                (name.as_str(), consume_expression(expr)),
+                (name.as_span(), silent),
+            ))
+        })
+        .unzip();
+    let mut properties: Properties<'_> = rules
+        .iter()
+        .map(|(name, _)| (*name, (false, false)))
+        .collect();
+    loop {
+        let mut changed = false;
+        for (name, node) in &rules {
+            let new = (node.nullable(&properties), node.never_fails(&properties));
+            if properties[name] != new {
+                properties.insert(name, new);
+                changed = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+    let mut res = vec![];
+    for (_, node) in &rules {
+        node.lint(&properties, &mut res);
+    }
+    for span in unused(&rules, &names) {
+        if allow_unused.contains(span.as_str()) {
+            continue;
+        }
+        let message = format!("rule `{}` is never used", span.as_str());
+        res.push(GrammarLint {
+            code: "unused_rule",
+            error: Error::new_from_span(ErrorVariant::CustomError { message }, span),
+        });
+    }
+    res
+}
+
+/// Lint a grammar, the same way as the derive macro does before generation.
+///
+/// Returns an error if the grammar can't be parsed.
+pub fn lint_grammar(grammar: &str) -> Result<Vec<GrammarLint>, Error<Rule>> {
+    let prepared = crate::prepare::prepare_grammar(grammar)?;
+    let pairs = parse(Rule::grammar_rules, &prepared.grammar)?;
+    Ok(lint(pairs, &BTreeSet::new()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::lint_grammar;
+    use pest::error::{ErrorVariant, LineColLocation};
+
+    fn warnings(grammar: &str) -> Vec<(usize, usize, &'static str, String)> {
+        lint_grammar(grammar)
+            .unwrap()
+            .into_iter()
+            .map(|lint| {
+                let (line, col) = match lint.error.line_col {
+                    LineColLocation::Pos(pos) | LineColLocation::Span(pos, _) => pos,
+                };
+                match lint.error.variant {
+                    ErrorVariant::CustomError { message } => (line, col, lint.code, message),
+                    _ => unreachable!(),
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn unreachable() {
+        let res = warnings("a = { \"x\"? | \"y\" }\nb = { c | \"z\" }\nc = _{ a* }");
+        let expected = [
+            (
+                1,
+                14,
+                "unreachable_alternative",
+                "unreachable alternative: `\"x\"?` never fails",
+            ),
+            (
+                2,
+                11,
+                "unreachable_alternative",
+                "unreachable alternative: `c` never fails",
+            ),
+            (
+                3,
+                8,
+                "empty_repetition",
+                "`a` may match empty input inside a repetition, which can loop forever",
+            ),
+        ];
+        assert_eq!(res.len(), expected.len());
+        for (res, expected) in res.iter().zip(expected) {
+            assert_eq!((res.0, res.1, res.2, res.3.as_str()), expected);
+        }
+    }
+
+    #[test]
+    fn shadowed() {
+        let res = warnings(r#"a = { "a" | ^"A" | "ab" ~ b | b | b | ^"b" | "B" }  b = { "b" }"#);
+        assert!(res
+            .iter()
+            .all(|(_, _, code, _)| *code == "shadowed_alternative"));
+        let res: Vec<_> = res.into_iter().map(|(_, col, _, _)| col).collect();
+        assert_eq!(res, [20, 35, 46]);
+    }
+
+    #[test]
+    fn unused() {
+        let res = warnings("a = { b }\nb = _{ \"b\" }\nc = _{ \"c\" }\nd = _{ e }\ne = _{ d | d ~ d }\nf = { g }\ng = { f | \"g\" }\nWHITESPACE = _{ \" \" }");
+        let res: Vec<_> = res
+            .into_iter()
+            .map(|(line, col, code, message)| {
+                assert_eq!(code, "unused_rule");
+                (line, col, message)
+            })
+            .collect();
+        assert_eq!(
+            res,
+            [
+                (3, 1, "rule `c` is never used".to_owned()),
+                (4, 1, "rule `d` is never used".to_owned()),
+                (5, 1, "rule `e` is never used".to_owned()),
+            ]
+        );
+        // Rules that are not silent are entries, even if they reference themselves.
+        assert!(warnings("e = { \"(\" ~ e ~ \")\" | \"x\" }").is_empty());
+        assert_eq!(warnings("e = _{ \"(\" ~ e ~ \")\" | \"x\" }").len(), 1);
+    }
+
+    #[test]
+    fn clean() {
+        assert!(warnings(r#"a = { ("a" | "b")* ~ "ab"? ~ ("c" ~ &"d")+ | "e" }"#).is_empty());
+        assert!(lint_grammar("a = {").is_err());
+    }
+}
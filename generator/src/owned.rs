@@ -0,0 +1,226 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Generate module `owned`, node types that own their matched texts and have no lifetimes.
+//!
+//! Texts are ranges of one shared copy of the input, so that trees are `Send` and `Sync`, and cheap to clone.
+
+use crate::graph::pest_typed;
+use crate::syntax::children;
+use pest_meta::{ast::RuleType, optimizer::OptimizedRule};
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use std::collections::BTreeMap;
+
+/// Hash rules with 128-bit FNV-1a, the same as `pest_typed::cache::fingerprint`,
+/// so that cached trees are invalidated when rules change, but not when comments do.
+fn fingerprint(rules: &[OptimizedRule]) -> u128 {
+    let mut hash: u128 = 0x6c62272e07bb014262b821756295c58d;
+    for rule in rules {
+        let line = format!("{} {:?} {}\n", rule.name, rule.ty, rule.expr);
+        for byte in line.bytes() {
+            hash = (hash ^ u128::from(byte)).wrapping_mul(0x0000000001000000000000000000013b);
+        }
+    }
+    hash
+}
+
+pub(crate) fn generate_owned(rules: &[OptimizedRule], cache: bool) -> TokenStream {
+    let pest_typed = pest_typed();
+    let arc = quote! {#pest_typed::re_exported::Arc};
+    let map: BTreeMap<_, _> = rules
+        .iter()
+        .map(|rule| (rule.name.as_str(), rule))
+        .collect();
+    // `EOI` is not defined in the grammar, but has pairs.
+    let visible: Vec<_> = rules
+        .iter()
+        .filter(|rule| rule.ty != RuleType::Silent)
+        .map(|rule| (rule.name.as_str(), children(rule, &map), true))
+        .chain([("EOI", vec![], false)])
+        .collect();
+    let names: Vec<_> = visible
+        .iter()
+        .map(|(rule, _, _)| format_ident!("r#{}", rule))
+        .collect();
+    let nodes = visible.iter().zip(&names).map(|((rule, children, defined), name)| {
+        let doc = format!("Owned node of rule `{}`.", rule);
+        let accessers = children.iter().map(|child| {
+            let doc = format!("Inner nodes of rule `{}`.", child);
+            let child = format_ident!("r#{}", child);
+            quote! {
+                #[doc = #doc]
+                pub fn #child(&self) -> impl ::core::iter::Iterator<Item = &#child> {
+                    self.children.iter().filter_map(|node| match node {
+                        Node::#child(node) => ::core::option::Option::Some(node),
+                        #[allow(unreachable_patterns)]
+                        _ => ::core::option::Option::None,
+                    })
+                }
+            }
+        });
+        let cached = (cache && *defined).then(|| quote! {
+            #[doc = "Load the tree of `input` from `cache`, or parse the whole input and store the tree in `cache`."]
+            pub fn parse_cached(
+                cache: &#pest_typed::cache::Cache,
+                input: #arc<::core::primitive::str>,
+            ) -> ::core::result::Result<Self, #pest_typed::error::Error<super::Rule>> {
+                let node = cache.get_or_parse(#rule, input, |input| Self::parse_shared(input).map(Node::#name))?;
+                match node {
+                    Node::#name(node) => ::core::result::Result::Ok(node),
+                    #[allow(unreachable_patterns)]
+                    _ => ::core::unreachable!(),
+                }
+            }
+        });
+        let parse = defined.then(|| quote! {
+            #[doc = "Parse the whole input, and convert the tree into owned nodes."]
+            pub fn parse(input: &::core::primitive::str) -> ::core::result::Result<Self, #pest_typed::error::Error<super::Rule>> {
+                Self::parse_shared(#arc::from(input))
+            }
+            #[doc = "Parse the whole input, and convert the tree into owned nodes that share `input` without copying it."]
+            pub fn parse_shared(input: #arc<::core::primitive::str>) -> ::core::result::Result<Self, #pest_typed::error::Error<super::Rule>> {
+                let tree = <super::rules::#name<'_> as #pest_typed::ParsableTypedNode<'_, super::Rule>>::try_parse(&input)?;
+                match Node::from_pair_shared(&tree, &input) {
+                    Node::#name(node) => ::core::result::Result::Ok(node),
+                    #[allow(unreachable_patterns)]
+                    _ => ::core::unreachable!(),
+                }
+            }
+        });
+        quote! {
+            #[doc = #doc]
+            #[allow(non_camel_case_types)]
+            #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+            pub struct #name {
+                #[doc = "Matched text."]
+                pub text: #pest_typed::shared::SharedStr,
+                #[doc = "Byte offset of the start in the input."]
+                pub start: ::core::primitive::usize,
+                #[doc = "Byte offset of the end in the input."]
+                pub end: ::core::primitive::usize,
+                #[doc = "Inner nodes, in source order."]
+                pub children: #pest_typed::re_exported::Vec<Node>,
+            }
+            #[allow(non_snake_case)]
+            impl #name {
+                #parse
+                #cached
+                #(#accessers)*
+            }
+            impl ::core::fmt::Display for #name {
+                fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                    f.write_str(&self.text)
+                }
+            }
+        }
+    });
+    let cacheable = cache.then(|| {
+        let grammar = fingerprint(rules);
+        let rules = visible.iter().map(|(rule, _, _)| *rule);
+        let rebuilt = rules.clone();
+        quote! {
+            impl #pest_typed::cache::Cacheable for Node {
+                const GRAMMAR: ::core::primitive::u128 = #grammar;
+                fn rule_name(&self) -> &'static ::core::primitive::str {
+                    match self {
+                        #(Self::#names(_) => #rules,)*
+                    }
+                }
+                fn text(&self) -> &#pest_typed::shared::SharedStr {
+                    match self {
+                        #(Self::#names(node) => &node.text,)*
+                    }
+                }
+                fn children(&self) -> &[Self] {
+                    match self {
+                        #(Self::#names(node) => &node.children,)*
+                    }
+                }
+                fn rebuild(
+                    rule: &::core::primitive::str,
+                    text: #pest_typed::shared::SharedStr,
+                    children: #pest_typed::re_exported::Vec<Self>,
+                ) -> ::core::option::Option<Self> {
+                    let ::core::ops::Range { start, end } = text.range();
+                    match rule {
+                        #(#rebuilt => ::core::option::Option::Some(Self::#names(#names { text, start, end, children })),)*
+                        _ => ::core::option::Option::None,
+                    }
+                }
+            }
+        }
+    });
+    quote! {
+        #[doc = "Node types that own their matched texts, without lifetimes."]
+        pub mod owned {
+            #(#nodes)*
+            #[doc = "An owned node of any rule."]
+            #[allow(non_camel_case_types)]
+            #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+            pub enum Node {
+                #(
+                    #[doc = "A node of the rule."]
+                    #names(#names),
+                )*
+            }
+            impl Node {
+                #[doc = "Convert a pair and its inner pairs into owned nodes, with a copy of the input."]
+                pub fn from_pair<'i: 'n, 'n>(pair: &'n dyn #pest_typed::iterators::Pair<'i, 'n, super::Rule>) -> Self {
+                    Self::convert(pair, &#arc::from(pair.span().get_input()))
+                }
+                #[doc = "Convert a pair and its inner pairs into owned nodes that share `input`."]
+                #[doc = ""]
+                #[doc = "# Panics"]
+                #[doc = ""]
+                #[doc = "Panics if the pair isn't parsed from `input`."]
+                pub fn from_pair_shared<'i: 'n, 'n>(
+                    pair: &'n dyn #pest_typed::iterators::Pair<'i, 'n, super::Rule>,
+                    input: &#arc<::core::primitive::str>,
+                ) -> Self {
+                    ::core::assert!(
+                        ::core::ptr::eq(pair.span().get_input(), &**input),
+                        "The pair isn't parsed from the shared input.",
+                    );
+                    Self::convert(pair, input)
+                }
+                #[doc = "Convert with `input` that has the same content as the input of the pair."]
+                fn convert<'i: 'n, 'n>(
+                    pair: &'n dyn #pest_typed::iterators::Pair<'i, 'n, super::Rule>,
+                    input: &#arc<::core::primitive::str>,
+                ) -> Self {
+                    let span = pair.span();
+                    let (start, end) = (span.start(), span.end());
+                    let text = #pest_typed::shared::SharedStr::slice(input, start..end);
+                    let children = pair.inner().map(|child| Self::convert(child, input)).collect();
+                    match pair.rule() {
+                        #(
+                            super::Rule::#names => Self::#names(#names { text, start, end, children }),
+                        )*
+                        #[allow(unreachable_patterns)]
+                        rule => ::core::panic!("Rule {:?} has no pairs.", rule),
+                    }
+                }
+                #[doc = "Rule of the node."]
+                pub fn rule(&self) -> super::Rule {
+                    match self {
+                        #(Self::#names(_) => super::Rule::#names,)*
+                    }
+                }
+                #[doc = "Matched text."]
+                pub fn text(&self) -> &::core::primitive::str {
+                    match self {
+                        #(Self::#names(node) => &node.text,)*
+                    }
+                }
+            }
+            #cacheable
+        }
+    }
+}
@@ -0,0 +1,62 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Rewriting extensions of this crate in a grammar into what [pest_meta] parses.
+//!
+//! Rule attributes are stripped, Unicode property classes and regular expression literals are rewritten,
+//! and names of named captures are blanked out, in this order.
+//! Each step keeps positions, so that errors on the result still point into the original grammar.
+
+use crate::attributes::{extract, Attributes};
+use crate::captures::Capture;
+use crate::regex::{compile, rewrite_with, Regex};
+use pest::error::Error;
+use pest_meta::parser::Rule;
+
+/// A grammar with extensions rewritten.
+pub(crate) struct Prepared {
+    /// The rewritten grammar, with placeholder rules of regular expressions appended.
+    pub grammar: String,
+    /// Attributes of rules, and compiled regular expressions in [`Attributes::regexes`].
+    pub attributes: Attributes,
+    /// Named captures, whose names are blanked out, in the order they are written.
+    pub captures: Vec<Capture>,
+}
+
+/// Rewrite extensions in `grammar`, compiling regular expressions.
+///
+/// Returns an error at the first unknown class or invalid regular expression.
+pub(crate) fn prepare_grammar(grammar: &str) -> Result<Prepared, Error<Rule>> {
+    prepare_grammar_with(grammar, compile)
+}
+
+/// Rewrite extensions like [`prepare_grammar`], compiling regular expressions with `compile`.
+pub(crate) fn prepare_grammar_with(
+    grammar: &str,
+    compile: impl Fn(&str) -> Result<Regex, String>,
+) -> Result<Prepared, Error<Rule>> {
+    let (mut data, mut attributes) =
+        extract(grammar).unwrap_or_else(|| (grammar.to_owned(), Attributes::default()));
+    if let Some(rewritten) = crate::classes::rewrite(&data)? {
+        data = rewritten;
+    }
+    if let Some((rewritten, regexes)) = rewrite_with(&data, compile)? {
+        data = rewritten;
+        attributes.regexes = regexes;
+    }
+    let (grammar, captures) = match crate::captures::rewrite(&data) {
+        Some((rewritten, captures)) => (rewritten, captures),
+        None => (data, Vec::new()),
+    };
+    Ok(Prepared {
+        grammar,
+        attributes,
+        captures,
+    })
+}
@@ -0,0 +1,143 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Generate module `grammar`, a static description of the grammar.
+
+use crate::analysis::{first_sets, Terminal};
+use crate::graph::pest_typed;
+use pest_meta::{
+    ast::RuleType,
+    optimizer::{OptimizedExpr, OptimizedRule},
+};
+use proc_macro2::TokenStream;
+use quote::quote;
+
+fn expr(expr: &OptimizedExpr, root: &TokenStream) -> TokenStream {
+    let boxed = |e: &OptimizedExpr| {
+        let e = self::expr(e, root);
+        quote! {&#e}
+    };
+    match expr {
+        OptimizedExpr::Str(s) => quote! {#root::Expr::Str(#s)},
+        OptimizedExpr::Insens(s) => quote! {#root::Expr::Insens(#s)},
+        OptimizedExpr::Range(start, end) => {
+            let start = start.chars().next().unwrap();
+            let end = end.chars().next().unwrap();
+            quote! {#root::Expr::Range(#start, #end)}
+        }
+        OptimizedExpr::Ident(name) => quote! {#root::Expr::Ident(#name)},
+        OptimizedExpr::PeekSlice(start, end) => {
+            let end = match end {
+                Some(end) => quote! {::core::option::Option::Some(#end)},
+                None => quote! {::core::option::Option::None},
+            };
+            quote! {#root::Expr::PeekSlice(#start, #end)}
+        }
+        OptimizedExpr::PosPred(e) => {
+            let e = boxed(e);
+            quote! {#root::Expr::PosPred(#e)}
+        }
+        OptimizedExpr::NegPred(e) => {
+            let e = boxed(e);
+            quote! {#root::Expr::NegPred(#e)}
+        }
+        OptimizedExpr::Seq(lhs, rhs) => {
+            let (lhs, rhs) = (boxed(lhs), boxed(rhs));
+            quote! {#root::Expr::Seq(#lhs, #rhs)}
+        }
+        OptimizedExpr::Choice(lhs, rhs) => {
+            let (lhs, rhs) = (boxed(lhs), boxed(rhs));
+            quote! {#root::Expr::Choice(#lhs, #rhs)}
+        }
+        OptimizedExpr::Opt(e) => {
+            let e = boxed(e);
+            quote! {#root::Expr::Opt(#e)}
+        }
+        OptimizedExpr::Rep(e) => {
+            let e = boxed(e);
+            quote! {#root::Expr::Rep(#e)}
+        }
+        #[cfg(feature = "grammar-extras")]
+        OptimizedExpr::RepOnce(e) => {
+            let e = boxed(e);
+            quote! {#root::Expr::RepOnce(#e)}
+        }
+        OptimizedExpr::Skip(strings) => quote! {#root::Expr::Skip(&[#(#strings),*])},
+        OptimizedExpr::Push(e) => {
+            let e = boxed(e);
+            quote! {#root::Expr::Push(#e)}
+        }
+        #[cfg(feature = "grammar-extras")]
+        OptimizedExpr::PushLiteral(s) => {
+            quote! {#root::Expr::Push(&#root::Expr::Str(#s))}
+        }
+        #[cfg(feature = "grammar-extras")]
+        OptimizedExpr::NodeTag(e, tag) => {
+            let e = boxed(e);
+            quote! {#root::Expr::NodeTag(#e, #tag)}
+        }
+        OptimizedExpr::RestoreOnErr(e) => {
+            let e = boxed(e);
+            quote! {#root::Expr::RestoreOnErr(#e)}
+        }
+    }
+}
+
+fn terminal(terminal: &Terminal<'_>, root: &TokenStream) -> TokenStream {
+    match terminal {
+        Terminal::Str(s) => quote! {#root::Terminal::Str(#s)},
+        Terminal::Insens(s) => quote! {#root::Terminal::Insens(#s)},
+        Terminal::Range(start, end) => quote! {#root::Terminal::Range(#start, #end)},
+        Terminal::Builtin(name) => quote! {#root::Terminal::Builtin(#name)},
+    }
+}
+
+pub(crate) fn generate_reflection(rules: &[OptimizedRule]) -> TokenStream {
+    let pest_typed = pest_typed();
+    let root = quote! {#pest_typed::reflection};
+    let firsts = first_sets(rules);
+    let len = rules.len();
+    let infos = rules.iter().map(|rule| {
+        let name = rule.name.as_str();
+        let kind = match rule.ty {
+            RuleType::Normal => quote! {Normal},
+            RuleType::Silent => quote! {Silent},
+            RuleType::Atomic => quote! {Atomic},
+            RuleType::CompoundAtomic => quote! {CompoundAtomic},
+            RuleType::NonAtomic => quote! {NonAtomic},
+        };
+        let e = expr(&rule.expr, &root);
+        let first = &firsts[name];
+        let terminals = first.terminals.iter().map(|t| terminal(t, &root));
+        let nullable = first.nullable;
+        quote! {
+            #root::RuleInfo {
+                name: #name,
+                kind: #root::RuleKind::#kind,
+                expr: &#e,
+                first: &[#(#terminals),*],
+                nullable: #nullable,
+            }
+        }
+    });
+    quote! {
+        #[doc = "Static description of the grammar."]
+        pub mod grammar {
+            static RULES: [#root::RuleInfo; #len] = [#(#infos),*];
+            #[doc = "All rules, in the order they are defined."]
+            pub fn rules() -> &'static [#root::RuleInfo] {
+                &RULES
+            }
+            #[doc = "Find a rule by its name."]
+            pub fn rule(name: &::core::primitive::str) -> ::core::option::Option<&'static #root::RuleInfo> {
+                RULES.iter().find(|rule| rule.name == name)
+            }
+        }
+    }
+}
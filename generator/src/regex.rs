@@ -0,0 +1,223 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Regular expression literals such as `re"\d{4}-\d{2}-\d{2}"`.
+//!
+//! Patterns are taken as written, except that `\"` stands for `"`,
+//! and are compiled into anchored DFAs with feature `regex`.
+//! Literals are rewritten into identifiers before the grammar is parsed, padded with spaces,
+//! so that positions in error messages and warnings still point into the original grammar,
+//! and placeholder rules of these identifiers are appended for the validator,
+//! which should be removed once the grammar is parsed.
+
+use pest::{
+    error::{Error, ErrorVariant},
+    Span,
+};
+use pest_meta::parser::Rule;
+use std::collections::BTreeMap;
+
+/// A compiled regular expression.
+#[derive(Debug, PartialEq)]
+pub(crate) struct Regex {
+    /// The pattern as written in the grammar.
+    pub pattern: String,
+    /// The sparse DFA serialized in little endian.
+    pub little_endian: Vec<u8>,
+    /// The sparse DFA serialized in big endian.
+    pub big_endian: Vec<u8>,
+}
+
+/// Identifiers of literals and their compiled regular expressions.
+pub(crate) type Regexes = BTreeMap<String, Regex>;
+
+/// Compile a pattern into an anchored DFA.
+#[cfg(feature = "regex")]
+pub(crate) fn compile(pattern: &str) -> Result<Regex, String> {
+    use regex_automata::dfa::{dense, StartKind};
+    // Errors of syntax are the innermost ones, where the last line describes the problem.
+    let invalid = |error: dense::BuildError| {
+        let mut innermost: &dyn std::error::Error = &error;
+        while let Some(source) = innermost.source() {
+            innermost = source;
+        }
+        let message = innermost.to_string();
+        let message = message.lines().last().unwrap_or_default();
+        let message = message.strip_prefix("error: ").unwrap_or(message);
+        format!("invalid regular expression: {message}")
+    };
+    let dfa = dense::Builder::new()
+        .configure(dense::Config::new().start_kind(StartKind::Anchored))
+        .build(pattern)
+        .map_err(invalid)?
+        .to_sparse()
+        .map_err(invalid)?;
+    Ok(Regex {
+        pattern: pattern.to_owned(),
+        little_endian: dfa.to_bytes_little_endian(),
+        big_endian: dfa.to_bytes_big_endian(),
+    })
+}
+
+/// Compiling requires feature `regex`.
+#[cfg(not(feature = "regex"))]
+pub(crate) fn compile(_pattern: &str) -> Result<Regex, String> {
+    Err("regular expression literals require feature `regex`".to_owned())
+}
+
+/// Rewrite `re"..."` literals in a grammar into identifiers of regular expressions compiled with `compile`.
+///
+/// Returns `Ok(None)` if there is no literal, and an error at the first invalid one.
+/// Literals with the same pattern share an identifier,
+/// which is padded with spaces to the length of the literal in bytes, so that positions are kept.
+pub(crate) fn rewrite_with(
+    grammar: &str,
+    compile: impl Fn(&str) -> Result<Regex, String>,
+) -> Result<Option<(String, Regexes)>, Error<Rule>> {
+    let bytes = grammar.as_bytes();
+    let mut res = String::with_capacity(grammar.len());
+    let mut regexes = Regexes::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let start = i;
+        match bytes[i] {
+            b'r' if grammar[i..].starts_with("re\"")
+                && !(i > 0 && (bytes[i - 1].is_ascii_alphanumeric() || bytes[i - 1] == b'_')) =>
+            {
+                let mut end = i + 3;
+                while end < bytes.len() && bytes[end] != b'"' {
+                    end += if bytes[end] == b'\\' { 2 } else { 1 };
+                }
+                let error = |message: String, end: usize| {
+                    Error::new_from_span(
+                        ErrorVariant::CustomError { message },
+                        Span::new(grammar, start, end.min(grammar.len())).unwrap(),
+                    )
+                };
+                if end >= bytes.len() {
+                    return Err(error(
+                        "unclosed regular expression literal".to_owned(),
+                        bytes.len(),
+                    ));
+                }
+                end += 1;
+                let pattern = grammar[i + 3..end - 1].replace("\\\"", "\"");
+                let name = match regexes.iter().find(|(_, regex)| regex.pattern == pattern) {
+                    Some((name, _)) => name.clone(),
+                    None => {
+                        let regex = compile(&pattern).map_err(|message| error(message, end))?;
+                        // The first unused name that fits in the literal.
+                        let name = (0..)
+                            .map(|counter| format!("re_{counter}"))
+                            .take_while(|name| name.len() <= end - i)
+                            .find(|name| !regexes.contains_key(name) && !grammar.contains(name))
+                            .ok_or_else(|| {
+                                error(
+                                    "too many regular expression literals as short as this one"
+                                        .to_owned(),
+                                    end,
+                                )
+                            })?;
+                        regexes.insert(name.clone(), regex);
+                        name
+                    }
+                };
+                res.push_str(&name);
+                let padding = &bytes[i + name.len()..end];
+                res.extend(padding.iter().map(|&b| if b == b'\n' { '\n' } else { ' ' }));
+                i = end;
+                continue;
+            }
+            quote @ (b'"' | b'\'') => {
+                i += 1;
+                while i < bytes.len() && bytes[i] != quote {
+                    i += if bytes[i] == b'\\' { 2 } else { 1 };
+                }
+                i = (i + 1).min(bytes.len());
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'/') => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    i += 1;
+                }
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                i = grammar[i + 2..]
+                    .find("*/")
+                    .map_or(bytes.len(), |end| i + 2 + end + 2);
+            }
+            _ => {
+                i += grammar[i..].chars().next().map_or(1, char::len_utf8);
+            }
+        }
+        res.push_str(&grammar[start..i]);
+    }
+    if regexes.is_empty() {
+        return Ok(None);
+    }
+    for name in regexes.keys() {
+        res.push_str(&format!("\n{name} = _{{ ANY }}"));
+    }
+    Ok(Some((res, regexes)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{compile, rewrite_with, Regexes};
+    use pest::error::Error;
+    use pest_meta::parser::Rule;
+
+    fn rewrite(grammar: &str) -> Result<Option<(String, Regexes)>, Error<Rule>> {
+        rewrite_with(grammar, compile)
+    }
+
+    #[test]
+    fn literals() {
+        assert_eq!(rewrite(r#"a = { "re\"x\"" ~ pre }"#).unwrap(), None);
+        let result = rewrite(r#"re_0 = { re"[0-9]+" ~ "-" ~ re"\"[0-9]+" ~ re"[0-9]+" }"#);
+        if cfg!(not(feature = "regex")) {
+            let error = result.unwrap_err();
+            assert_eq!(
+                error.variant.message(),
+                "regular expression literals require feature `regex`"
+            );
+            return;
+        }
+        let (grammar, regexes) = result.unwrap().unwrap();
+        assert_eq!(
+            grammar,
+            "re_0 = { re_1       ~ \"-\" ~ re_2         ~ re_1       }\nre_1 = _{ ANY }\nre_2 = _{ ANY }",
+        );
+        assert_eq!(regexes["re_1"].pattern, "[0-9]+");
+        assert_eq!(regexes["re_2"].pattern, "\"[0-9]+");
+        let error = rewrite(r#"a = { re"(" }"#).unwrap_err();
+        assert_eq!(
+            error.variant.message(),
+            "invalid regular expression: unclosed group"
+        );
+        assert!(rewrite(r#"a = { re"x }"#).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "regex")]
+    fn positions() {
+        let grammar = r#"a = { re"éé" ~ "ü"key: "x" }"#;
+        let (rewritten, _) = rewrite(grammar).unwrap().unwrap();
+        let key = grammar.find("key").unwrap();
+        assert_eq!(rewritten.find("key"), Some(key));
+        assert_eq!(&rewritten[..key], "a = { re_0     ~ \"ü\"");
+
+        // Names fit in short literals, even after ten of them.
+        let literals: Vec<_> = (0..12).map(|i| format!("re\"{}\"", i)).collect();
+        let grammar = format!("a = {{ re\"\" ~ {} }}", literals.join(" ~ "));
+        let (rewritten, regexes) = rewrite(&grammar).unwrap().unwrap();
+        assert_eq!(regexes.len(), 13);
+        assert_eq!(rewritten.find('}'), grammar.find('}'));
+        assert!(rewritten.starts_with("a = { re_0 ~ re_1  ~ "));
+    }
+}
@@ -0,0 +1,121 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Report errors in grammars.
+//!
+//! Grammars from all attributes are concatenated before parsing,
+//! so errors are relocated into the grammar they come from before being reported.
+
+use pest::{
+    error::{Error, InputLocation},
+    Position, Span,
+};
+use pest_meta::parser::Rule;
+use proc_macro2::TokenStream;
+use quote::quote_spanned;
+use std::path::PathBuf;
+
+/// A grammar in the concatenated grammars.
+pub(crate) struct Source {
+    /// Start offset in the concatenated grammars.
+    pub start: usize,
+    /// Path to the grammar file, if it's not inline.
+    pub path: Option<PathBuf>,
+    /// Span of the attribute that provides the grammar.
+    pub span: proc_macro2::Span,
+}
+
+/// Relocate `error` in `data` into the source it comes from.
+///
+/// Returns the index of the source and the relocated error.
+pub(crate) fn relocate(error: Error<Rule>, data: &str, sources: &[Source]) -> (usize, Error<Rule>) {
+    let (start, end) = match error.location {
+        InputLocation::Pos(pos) => (pos, None),
+        InputLocation::Span((start, end)) => (start, Some(end)),
+    };
+    let index = sources
+        .iter()
+        .rposition(|source| source.start <= start)
+        .unwrap_or(0);
+    let (begin, finish) = match sources.get(index) {
+        Some(source) => (
+            source.start,
+            sources.get(index + 1).map_or(data.len(), |next| next.start),
+        ),
+        None => return (index, error),
+    };
+    let input = &data[begin..finish];
+    let local = |pos: usize| pos.clamp(begin, finish) - begin;
+    let relocated = match end {
+        Some(end) => Span::new(input, local(start), local(end).max(local(start)))
+            .map(|span| Error::new_from_span(error.variant.clone(), span)),
+        None => Position::new(input, local(start))
+            .map(|pos| Error::new_from_pos(error.variant.clone(), pos)),
+    };
+    let relocated = match (relocated, &sources[index].path) {
+        (Some(relocated), Some(path)) => relocated.with_path(&path.to_string_lossy()),
+        (Some(relocated), None) => relocated,
+        (None, _) => error,
+    };
+    (index, relocated)
+}
+
+/// Generate a `compile_error!` for each error, spanned at the attribute providing the grammar.
+pub(crate) fn report(errors: Vec<Error<Rule>>, data: &str, sources: &[Source]) -> TokenStream {
+    errors
+        .into_iter()
+        .map(|error| {
+            let (index, error) = relocate(error, data, sources);
+            let span = sources
+                .get(index)
+                .map_or_else(proc_macro2::Span::call_site, |source| source.span);
+            let message = format!("grammar error\n\n{}", error);
+            quote_spanned! {span=>
+                ::core::compile_error!(#message);
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{relocate, Source};
+    use pest::error::{Error, ErrorVariant, LineColLocation};
+    use pest::Position;
+    use pest_meta::parser::Rule;
+    use std::path::PathBuf;
+
+    #[test]
+    fn relocated() {
+        let data = "a = { \"a\" }\nb = { \"b\" }\n\nc = { }";
+        let sources = [
+            Source {
+                start: 0,
+                path: None,
+                span: proc_macro2::Span::call_site(),
+            },
+            Source {
+                start: 12,
+                path: Some(PathBuf::from("b.pest")),
+                span: proc_macro2::Span::call_site(),
+            },
+        ];
+        let error = Error::<Rule>::new_from_pos(
+            ErrorVariant::CustomError {
+                message: String::from("bad"),
+            },
+            Position::new(data, data.len() - 1).unwrap(),
+        );
+        let (index, error) = relocate(error, data, &sources);
+        assert_eq!(index, 1);
+        assert_eq!(error.line_col, LineColLocation::Pos((3, 7)));
+        assert_eq!(error.path(), Some("b.pest"));
+        assert_eq!(error.line(), "c = { }");
+    }
+}
@@ -0,0 +1,158 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Implement `pest_typed::scan::Scan` for rule structs, and generate `find_next`.
+
+use crate::analysis::{first_sets, First, Terminal};
+use crate::graph::pest_typed;
+use pest_meta::optimizer::OptimizedRule;
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+
+/// Bits of `pest_typed::scan::ByteSet`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct Bytes([u64; 4]);
+
+impl Bytes {
+    fn insert_range(&mut self, start: u8, end: u8) {
+        for byte in start..=end {
+            self.0[(byte / 64) as usize] |= 1 << (byte % 64);
+        }
+    }
+    fn insert(&mut self, byte: u8) {
+        self.insert_range(byte, byte)
+    }
+    /// Insert leading bytes of all non-ASCII chars.
+    fn insert_non_ascii(&mut self) {
+        self.insert_range(0xC2, 0xF4)
+    }
+}
+
+fn first_byte(c: char) -> u8 {
+    let mut buf = [0; 4];
+    c.encode_utf8(&mut buf).as_bytes()[0]
+}
+
+/// Bytes that a non-empty match may start with, or [`None`] if any byte can.
+fn first_bytes(first: &First<'_>) -> Option<Bytes> {
+    if first.nullable {
+        return None;
+    }
+    let mut res = Bytes::default();
+    for terminal in &first.terminals {
+        match terminal {
+            Terminal::Str(s) => res.insert(s.as_bytes()[0]),
+            Terminal::Insens(s) => {
+                let c = s.chars().next().unwrap();
+                res.insert(first_byte(c));
+                if c.is_ascii() {
+                    res.insert(c.to_ascii_lowercase() as u8);
+                    res.insert(c.to_ascii_uppercase() as u8);
+                }
+                // Some non-ASCII chars are the same as ASCII ones ignoring cases, such as `K` (Kelvin sign).
+                if c.is_alphabetic() {
+                    res.insert_non_ascii();
+                }
+            }
+            Terminal::Range(start, end) => res.insert_range(first_byte(*start), first_byte(*end)),
+            Terminal::Builtin("NEWLINE") => {
+                res.insert(b'\n');
+                res.insert(b'\r');
+            }
+            Terminal::Builtin("ASCII_DIGIT") => res.insert_range(b'0', b'9'),
+            Terminal::Builtin("ASCII_NONZERO_DIGIT") => res.insert_range(b'1', b'9'),
+            Terminal::Builtin("ASCII_BIN_DIGIT") => res.insert_range(b'0', b'1'),
+            Terminal::Builtin("ASCII_OCT_DIGIT") => res.insert_range(b'0', b'7'),
+            Terminal::Builtin("ASCII_HEX_DIGIT") => {
+                res.insert_range(b'0', b'9');
+                res.insert_range(b'a', b'f');
+                res.insert_range(b'A', b'F');
+            }
+            Terminal::Builtin("ASCII_ALPHA_LOWER") => res.insert_range(b'a', b'z'),
+            Terminal::Builtin("ASCII_ALPHA_UPPER") => res.insert_range(b'A', b'Z'),
+            Terminal::Builtin("ASCII_ALPHA") => {
+                res.insert_range(b'a', b'z');
+                res.insert_range(b'A', b'Z');
+            }
+            Terminal::Builtin("ASCII_ALPHANUMERIC") => {
+                res.insert_range(b'0', b'9');
+                res.insert_range(b'a', b'z');
+                res.insert_range(b'A', b'Z');
+            }
+            Terminal::Builtin("ASCII") => res.insert_range(0, 0x7F),
+            Terminal::Builtin(_) => return None,
+        }
+    }
+    Some(res)
+}
+
+pub(crate) fn generate_scanner(rules: &[OptimizedRule]) -> TokenStream {
+    let pest_typed = pest_typed();
+    let firsts = first_sets(rules);
+    let impls = rules.iter().map(|rule| {
+        let name = format_ident!("r#{}", rule.name);
+        let bytes = match first_bytes(&firsts[rule.name.as_str()]) {
+            Some(Bytes(bits)) => {
+                quote! {::core::option::Option::Some(#pest_typed::scan::ByteSet::from_bits([#(#bits),*]))}
+            }
+            None => quote! {::core::option::Option::None},
+        };
+        quote! {
+            impl<'i, const INHERITED: ::core::primitive::usize> #pest_typed::scan::Scan<'i, Rule> for rules::#name<'i, INHERITED> {
+                const FIRST_BYTES: ::core::option::Option<#pest_typed::scan::ByteSet> = #bytes;
+            }
+        }
+    });
+    quote! {
+        #(#impls)*
+        #[doc = "Find the first offset at or after `from` where `T` matches, and return the match. See [`pest_typed::scan::find_next`]."]
+        #[allow(dead_code)]
+        pub fn find_next<'i, T: #pest_typed::scan::Scan<'i, Rule>>(
+            input: &'i ::core::primitive::str,
+            from: ::core::primitive::usize,
+        ) -> ::core::option::Option<(::core::primitive::usize, T)> {
+            #pest_typed::scan::find_next::<Rule, T>(input, from)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{first_bytes, Bytes};
+    use crate::analysis::first_sets;
+    use pest_meta::parse_and_optimize;
+
+    #[test]
+    fn bytes() {
+        let (_, rules) = parse_and_optimize(
+            r#"
+            a = { "x" | ^"k" | '0'..'9' ~ ANY }
+            b = { NEWLINE | "\t" }
+            c = { ANY }
+            d = { "y"? }
+            "#,
+        )
+        .unwrap();
+        let first = first_sets(&rules);
+        let mut expected = Bytes::default();
+        expected.insert(b'x');
+        expected.insert(b'k');
+        expected.insert(b'K');
+        expected.insert_non_ascii();
+        expected.insert_range(b'0', b'9');
+        assert_eq!(first_bytes(&first["a"]), Some(expected));
+        let mut expected = Bytes::default();
+        expected.insert(b'\n');
+        expected.insert(b'\r');
+        expected.insert(b'\t');
+        assert_eq!(first_bytes(&first["b"]), Some(expected));
+        assert_eq!(first_bytes(&first["c"]), None);
+        assert_eq!(first_bytes(&first["d"]), None);
+    }
+}
@@ -0,0 +1,150 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Generate module `syntax`, typed wrappers of `pest_typed::green::SyntaxNode`.
+
+use crate::graph::pest_typed;
+use pest_meta::{
+    ast::RuleType,
+    optimizer::{OptimizedExpr, OptimizedRule},
+};
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Rules referenced in `expr`, in the order they are first referenced.
+fn references<'r>(expr: &'r OptimizedExpr, res: &mut Vec<&'r str>) {
+    match expr {
+        OptimizedExpr::Ident(name) if !res.contains(&name.as_str()) => res.push(name),
+        OptimizedExpr::Seq(lhs, rhs) | OptimizedExpr::Choice(lhs, rhs) => {
+            references(lhs, res);
+            references(rhs, res);
+        }
+        OptimizedExpr::PosPred(expr)
+        | OptimizedExpr::Opt(expr)
+        | OptimizedExpr::Rep(expr)
+        | OptimizedExpr::Push(expr)
+        | OptimizedExpr::RestoreOnErr(expr) => references(expr, res),
+        #[cfg(feature = "grammar-extras")]
+        OptimizedExpr::RepOnce(expr) | OptimizedExpr::NodeTag(expr, _) => references(expr, res),
+        _ => (),
+    }
+}
+
+/// Rules whose nodes may be children of nodes of `rule`.
+///
+/// Silent rules have no nodes, so rules in them are children instead,
+/// and atomic rules have no children.
+pub(crate) fn children<'r>(
+    rule: &'r OptimizedRule,
+    rules: &BTreeMap<&str, &'r OptimizedRule>,
+) -> Vec<&'r str> {
+    if rule.ty == RuleType::Atomic {
+        return vec![];
+    }
+    let mut res = vec![];
+    let mut visited = BTreeSet::new();
+    let mut pending = vec![];
+    references(&rule.expr, &mut pending);
+    pending.reverse();
+    while let Some(name) = pending.pop() {
+        let Some(child) = rules.get(name) else {
+            continue;
+        };
+        if !visited.insert(name) {
+            continue;
+        }
+        match child.ty {
+            RuleType::Silent => {
+                let mut inner = vec![];
+                references(&child.expr, &mut inner);
+                pending.extend(inner.into_iter().rev());
+            }
+            _ => res.push(name),
+        }
+    }
+    res
+}
+
+pub(crate) fn generate_syntax(rules: &[OptimizedRule]) -> TokenStream {
+    let pest_typed = pest_typed();
+    let map: BTreeMap<_, _> = rules
+        .iter()
+        .map(|rule| (rule.name.as_str(), rule))
+        .collect();
+    let nodes = rules
+        .iter()
+        .filter(|rule| rule.ty != RuleType::Silent)
+        .map(|rule| {
+            let name = format_ident!("r#{}", rule.name);
+            let doc = format!("Syntax node of rule `{}`.", rule.name);
+            let accessers = children(rule, &map).into_iter().map(|child| {
+                let doc = format!("Inner nodes of rule `{}`.", child);
+                let child = format_ident!("r#{}", child);
+                quote! {
+                    #[doc = #doc]
+                    pub fn #child(&self) -> impl ::core::iter::Iterator<Item = #child> {
+                        self.0.children().filter_map(#pest_typed::green::AstNode::cast)
+                    }
+                }
+            });
+            quote! {
+                #[doc = #doc]
+                #[allow(non_camel_case_types)]
+                #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+                pub struct #name(#pest_typed::green::SyntaxNode<super::Rule>);
+                impl #pest_typed::green::AstNode<super::Rule> for #name {
+                    const RULE: super::Rule = super::Rule::#name;
+                    fn cast(node: #pest_typed::green::SyntaxNode<super::Rule>) -> ::core::option::Option<Self> {
+                        (node.rule() == Self::RULE).then(|| Self(node))
+                    }
+                    fn syntax(&self) -> &#pest_typed::green::SyntaxNode<super::Rule> {
+                        &self.0
+                    }
+                }
+                #[allow(non_snake_case)]
+                impl #name {
+                    #(#accessers)*
+                }
+            }
+        });
+    quote! {
+        #[doc = "Typed wrappers of syntax nodes in red-green trees. See [`pest_typed::green`]."]
+        pub mod syntax {
+            #(#nodes)*
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::children;
+    use pest_meta::parse_and_optimize;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn silent() {
+        let (_, rules) = parse_and_optimize(
+            r#"
+            a = { b ~ (c | b)* ~ d ~ ANY }
+            b = @{ c }
+            c = { "c" }
+            d = _{ e | c }
+            e = { "e" }
+            "#,
+        )
+        .unwrap();
+        let map: BTreeMap<_, _> = rules
+            .iter()
+            .map(|rule| (rule.name.as_str(), rule))
+            .collect();
+        assert_eq!(children(map["a"], &map), ["b", "c", "e"]);
+        assert!(children(map["b"], &map).is_empty());
+    }
+}
@@ -18,20 +18,34 @@
 
 //! Adapted from [generator.rs](./generator.rs) (commit ac0aed3eecf435fd93ba575a39704aaa88a375b7).
 
+use super::attributes::{generate_aliases, generate_highlights, generate_keywords, Attributes};
 use super::docs::{consume, DocComment};
 use super::generator::{generate_enum, generate_include};
 use super::helper::{collect_data, get_string, GrammarSource};
+use crate::captures::{collect as collect_captures, optimize as optimize_captures};
 use crate::config::Config;
+use crate::dynamic::{generate_dyn, generate_wasm};
+use crate::events::generate_events;
+use crate::grammar_tests::{check as check_grammar_tests, generate as generate_grammar_tests};
 use crate::graph::{generate_typed_pair_from_rule, pest_typed};
 use crate::helper::get_bool;
+use crate::lint::lint;
+use crate::owned::generate_owned;
+use crate::prepare::prepare_grammar;
+use crate::reflection::generate_reflection;
+use crate::report::{relocate, report, Source};
+use crate::scanner::generate_scanner;
+use crate::syntax::generate_syntax;
 use pest_meta::optimizer::OptimizedRule;
 use pest_meta::parser::{consume_rules, parse, rename_meta_rule, Rule};
-use pest_meta::{optimizer::optimize, unwrap_or_report};
 use proc_macro2::TokenStream;
 use quote::quote;
+use std::collections::BTreeSet;
 use std::path::PathBuf;
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
 use syn::DeriveInput;
-use syn::{self, Generics, Ident};
+use syn::{self, Generics, Ident, LitStr, Token, Type};
 
 /// Processes the derive/proc macro input and generates the corresponding typed parser and nodes
 /// based on the parsed grammar. It will generate an explicit "include_str" statement.
@@ -39,20 +53,81 @@ use syn::{self, Generics, Ident};
 #[doc = include_str!("../Usage.md")]
 pub fn derive_typed_parser(input: TokenStream, include_grammar: bool) -> TokenStream {
     let ast: DeriveInput = syn::parse2(input).unwrap();
-    let (name, generics, contents, config) = parse_typed_derive(ast);
+    let spans: Vec<_> = ast
+        .attrs
+        .iter()
+        .filter(|attr| {
+            let path = attr.meta.path();
+            path.is_ident("grammar") || path.is_ident("grammar_inline")
+        })
+        .map(|attr| attr.span())
+        .collect();
+    let (name, generics, contents, config, options) = parse_typed_derive(ast);
 
-    let (data, paths) = collect_data(contents);
+    let (grammar, paths, starts) = collect_data(contents);
+    let sources: Vec<_> = starts
+        .into_iter()
+        .zip(spans)
+        .map(|((start, path), span)| Source { start, path, span })
+        .collect();
+    let (data, mut attributes, captures) = match prepare_grammar(&grammar) {
+        Ok(prepared) => (prepared.grammar, prepared.attributes, prepared.captures),
+        Err(error) => return report(vec![error], &grammar, &sources),
+    };
 
     let pairs = match parse(Rule::grammar_rules, &data) {
         Ok(pairs) => pairs,
-        Err(error) => panic!("error parsing \n{}", error.renamed_rules(rename_meta_rule)),
+        Err(error) => return report(vec![error.renamed_rules(rename_meta_rule)], &data, &sources),
+    };
+    // Errors of named captures are reported on the grammar before their names are blanked out.
+    let captures = match collect_captures(&grammar, pairs.clone(), &captures) {
+        Ok(captures) => captures,
+        Err(error) => return report(vec![error], &grammar, &sources),
     };
 
+    if !config.no_warnings {
+        for warning in lint(pairs.clone(), &options.allow_unused_rules) {
+            let (_, error) = relocate(warning.error, &data, &sources);
+            eprintln!("warning[{}]: {}", warning.code, error);
+        }
+    }
+
     let doc_comment = consume(pairs.clone());
-    let ast = unwrap_or_report(consume_rules(pairs));
-    let optimized = optimize(ast);
+    let mut ast = match consume_rules(pairs) {
+        Ok(ast) => ast,
+        Err(errors) => {
+            let errors = errors
+                .into_iter()
+                .map(|error| error.renamed_rules(rename_meta_rule))
+                .collect();
+            return report(errors, &data, &sources);
+        }
+    };
+    // Placeholder rules of regular expressions are only for the validator.
+    ast.retain(|rule| !attributes.regexes.contains_key(&rule.name));
+    if !attributes.regexes.is_empty()
+        && (config.emit_events || config.emit_reflection || config.emit_scanner)
+    {
+        let span = sources
+            .first()
+            .map_or_else(proc_macro2::Span::call_site, |source| source.span);
+        return syn::Error::new(
+            span,
+            "regular expression literals are not supported with `emit_events`, `emit_reflection` or `emit_scanner`",
+        )
+        .to_compile_error();
+    }
+    let mut grammar_tests = TokenStream::new();
+    if config.grammar_tests {
+        grammar_tests.extend(check_grammar_tests(&data, &attributes, &sources));
+    }
+    if config.grammar_unit_tests {
+        grammar_tests.extend(generate_grammar_tests(&data, &sources));
+    }
+    let (optimized, captures) = optimize_captures(ast, captures);
+    attributes.captures = captures;
 
-    generate_typed(
+    let mut res = generate_typed(
         name,
         &generics,
         paths,
@@ -60,15 +135,35 @@ pub fn derive_typed_parser(input: TokenStream, include_grammar: bool) -> TokenSt
         &doc_comment,
         include_grammar,
         config,
-    )
+        &attributes,
+        options.allocation.as_ref(),
+    );
+    if !attributes.highlights.is_empty() {
+        res.extend(generate_highlights(&attributes.highlights));
+    }
+    if !attributes.aliases.is_empty() {
+        res.extend(generate_aliases(&attributes.aliases));
+    }
+    res.extend(grammar_tests);
+    res
+}
+
+/// Options of the derive macro that are not flags in [`Config`].
+#[derive(Default)]
+struct Options {
+    /// Type in `allocation`.
+    allocation: Option<Type>,
+    /// Rules in `allow_unused_rules`.
+    allow_unused_rules: BTreeSet<String>,
 }
 
-fn parse_typed_derive(ast: DeriveInput) -> (Ident, Generics, Vec<GrammarSource>, Config) {
+fn parse_typed_derive(ast: DeriveInput) -> (Ident, Generics, Vec<GrammarSource>, Config, Options) {
     let name = ast.ident;
     let generics = ast.generics;
 
     let mut grammar_sources = vec![];
     let mut config = Config::default();
+    let mut options = Options::default();
     for attr in ast.attrs.iter() {
         let path = attr.meta.path();
         if path.is_ident("grammar") {
@@ -90,6 +185,57 @@ fn parse_typed_derive(ast: DeriveInput) -> (Ident, Generics, Vec<GrammarSource>,
             config.simulate_pair_api = get_bool(attr);
         } else if path.is_ident("no_warnings") {
             config.no_warnings = get_bool(attr);
+        } else if path.is_ident("emit_fold") {
+            config.emit_fold = get_bool(attr);
+        } else if path.is_ident("emit_reflection") {
+            config.emit_reflection = get_bool(attr);
+        } else if path.is_ident("flatten_wrappers") {
+            config.flatten_wrappers = get_bool(attr);
+        } else if path.is_ident("emit_display") {
+            config.emit_display = get_bool(attr);
+        } else if path.is_ident("named_choices") {
+            config.named_choices = get_bool(attr);
+        } else if path.is_ident("module_per_rule") {
+            config.module_per_rule = get_bool(attr);
+        } else if path.is_ident("report_code_size") {
+            config.report_code_size = get_bool(attr);
+        } else if path.is_ident("emit_scanner") {
+            config.emit_scanner = get_bool(attr);
+        } else if path.is_ident("emit_syntax") {
+            config.emit_syntax = get_bool(attr);
+        } else if path.is_ident("emit_owned") {
+            config.emit_owned = get_bool(attr);
+        } else if path.is_ident("do_not_emit_boundaries") {
+            config.do_not_emit_boundaries = get_bool(attr);
+        } else if path.is_ident("emit_events") {
+            config.emit_events = get_bool(attr);
+        } else if path.is_ident("emit_branches") {
+            config.emit_branches = get_bool(attr);
+        } else if path.is_ident("non_exhaustive_nodes") {
+            config.non_exhaustive_nodes = get_bool(attr);
+        } else if path.is_ident("emit_cache") {
+            config.emit_cache = get_bool(attr);
+        } else if path.is_ident("emit_dyn") {
+            config.emit_dyn = get_bool(attr);
+        } else if path.is_ident("grammar_tests") {
+            config.grammar_tests = get_bool(attr);
+        } else if path.is_ident("grammar_unit_tests") {
+            config.grammar_unit_tests = get_bool(attr);
+        } else if path.is_ident("emit_wasm") {
+            config.emit_wasm = get_bool(attr);
+        } else if path.is_ident("allocation") {
+            let ty = get_string(attr);
+            match syn::parse_str(&ty) {
+                Ok(ty) => options.allocation = Some(ty),
+                Err(error) => panic!("Invalid allocation type `{ty}`: {error}."),
+            }
+        } else if path.is_ident("allow_unused_rules") {
+            let rules = attr
+                .parse_args_with(Punctuated::<LitStr, Token![,]>::parse_terminated)
+                .expect("`allow_unused_rules` should be a list of rule names in strings.");
+            options
+                .allow_unused_rules
+                .extend(rules.iter().map(LitStr::value));
         }
     }
 
@@ -97,10 +243,11 @@ fn parse_typed_derive(ast: DeriveInput) -> (Ident, Generics, Vec<GrammarSource>,
         panic!("A grammar file needs to be provided with the #[grammar = \"PATH\"] or #[grammar_inline = \"GRAMMAR CONTENTS\"] attribute.");
     }
 
-    (name, generics, grammar_sources, config)
+    (name, generics, grammar_sources, config, options)
 }
 
 /// Generate codes for Parser.
+#[allow(clippy::too_many_arguments)]
 fn generate_typed(
     name: Ident,
     generics: &Generics,
@@ -109,6 +256,8 @@ fn generate_typed(
     doc_comment: &DocComment,
     include_grammar: bool,
     config: Config,
+    attributes: &Attributes,
+    allocation: Option<&Type>,
 ) -> TokenStream {
     let include_fix = if include_grammar {
         generate_include(&name, paths)
@@ -116,11 +265,55 @@ fn generate_typed(
         quote!()
     };
     let rule_enum = generate_enum(&rules, doc_comment);
-    let pairs = generate_typed_pair_from_rule(&rules, doc_comment, config);
+    let pairs = generate_typed_pair_from_rule(&rules, doc_comment, config, attributes, allocation);
     let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
 
     let pest_typed = pest_typed();
 
+    let reflection = if config.emit_reflection {
+        generate_reflection(&rules)
+    } else {
+        quote!()
+    };
+
+    let scanner = if config.emit_scanner {
+        generate_scanner(&rules)
+    } else {
+        quote!()
+    };
+
+    let syntax = if config.emit_syntax {
+        generate_syntax(&rules)
+    } else {
+        quote!()
+    };
+
+    let owned = if config.emit_owned || config.emit_cache {
+        generate_owned(&rules, config.emit_cache)
+    } else {
+        quote!()
+    };
+
+    let dynamic = if config.emit_dyn {
+        generate_dyn(&rules)
+    } else {
+        quote!()
+    };
+
+    let wasm = if config.emit_wasm {
+        generate_wasm(&rules)
+    } else {
+        quote!()
+    };
+
+    let events = if config.emit_events {
+        generate_events(&rules)
+    } else {
+        quote!()
+    };
+
+    let keywords = generate_keywords(&rules, attributes);
+
     let parser_impl = quote! {
         #[allow(clippy::all)]
         impl #impl_generics #pest_typed::TypedParser<Rule> for #name #ty_generics #where_clause {}
@@ -130,6 +323,14 @@ fn generate_typed(
         #include_fix
         #rule_enum
         #pairs
+        #reflection
+        #scanner
+        #syntax
+        #owned
+        #dynamic
+        #wasm
+        #events
+        #keywords
         #parser_impl
     };
     res
@@ -146,13 +347,15 @@ mod tests {
             struct x;
         })
         .unwrap();
-        let (name, _, contents, config) = parse_typed_derive(ast);
+        let (name, _, contents, config, options) = parse_typed_derive(ast);
         assert_eq!(name, format_ident!("x"));
         assert_eq!(
             contents,
             vec![GrammarSource::Inline(r#"x = { "x" }"#.to_owned())]
         );
         assert_eq!(config, Config::default());
+        assert!(options.allocation.is_none());
+        assert!(options.allow_unused_rules.is_empty());
         assert_eq!(
             format!("{:?}", config.clone()),
             format!("{:?}", Config::default())
@@ -169,7 +372,7 @@ mod tests {
             struct x;
         })
         .unwrap();
-        let (_, _, _, config) = parse_typed_derive(ast);
+        let (_, _, _, config, _) = parse_typed_derive(ast);
         assert_eq!(
             config,
             Config {
@@ -178,11 +381,49 @@ mod tests {
                 do_not_emit_span: false,
                 truncate_accesser_at_node_tag: false,
                 simulate_pair_api: false,
-                no_warnings: true
+                no_warnings: true,
+                emit_fold: false,
+                emit_reflection: false,
+                flatten_wrappers: false,
+                emit_display: false,
+                named_choices: false,
+                module_per_rule: false,
+                report_code_size: false,
+                emit_scanner: false,
+                emit_syntax: false,
+                emit_owned: false,
+                do_not_emit_boundaries: false,
+                emit_events: false,
+                emit_branches: false,
+                non_exhaustive_nodes: false,
+                emit_cache: false,
+                emit_dyn: false,
+                grammar_tests: false,
+                grammar_unit_tests: false,
+                emit_wasm: false,
             }
         );
     }
 
+    #[test]
+    fn test_options() {
+        let ast: DeriveInput = syn::parse2(quote! {
+            #[grammar_inline = "x = { \"x\" }"]
+            #[allocation = "crate::Pool"]
+            #[allow_unused_rules("a", "b")]
+            #[allow_unused_rules("c")]
+            struct x;
+        })
+        .unwrap();
+        let (_, _, _, _, options) = parse_typed_derive(ast);
+        let allocation = options.allocation.unwrap();
+        assert_eq!(quote! {#allocation}.to_string(), "crate :: Pool");
+        assert_eq!(
+            options.allow_unused_rules.into_iter().collect::<Vec<_>>(),
+            ["a", "b", "c"]
+        );
+    }
+
     #[test]
     #[should_panic]
     fn invalid_path() {
@@ -220,17 +461,90 @@ mod tests {
     }
 
     #[test]
-    #[should_panic]
     fn parse_failure() {
-        let _ = derive_typed_parser(
+        let output = derive_typed_parser(
             quote! {
-                #[grammar_inline = "x = { }"]
+                #[grammar_inline = "x = { \"x\" }\ny = { }"]
                 struct x;
             },
             false,
+        )
+        .to_string();
+        assert!(output.starts_with(":: core :: compile_error !"));
+        assert!(output.contains("--> 2:7"));
+        assert!(output.contains("y = { }"));
+    }
+
+    #[test]
+    #[cfg(feature = "regex")]
+    fn regex_unsupported() {
+        let input = quote! {
+            #[grammar_inline = "x = { re\"[0-9]+\" }"]
+            #[emit_events]
+            struct x;
+        };
+        let output = derive_typed_parser(input, false).to_string();
+        assert!(output.starts_with(":: core :: compile_error !"));
+        assert!(output.contains("not supported with `emit_events`"));
+    }
+
+    #[test]
+    fn captures() {
+        let input = quote! {
+            #[grammar_inline = "x = { key: \"x\" ~ \"=\" ~ value: \"y\" }"]
+            struct x;
+        };
+        let output = derive_typed_parser(input, false).to_string();
+        assert!(output.contains("named_seq ! (Seq_key__1_value , pest_typed , 3"));
+
+        let input = quote! {
+            #[grammar_inline = "x = { key: \"x\" }"]
+            struct x;
+        };
+        let output = derive_typed_parser(input, false).to_string();
+        assert!(output.starts_with(":: core :: compile_error !"));
+        assert!(
+            output.contains("named captures are only supported on items of top-level sequences")
         );
     }
 
+    #[test]
+    fn grammar_tests() {
+        let input = quote! {
+            #[grammar_inline = "//@ reject \"x\"\nx = { \"x\" }"]
+            #[grammar_tests]
+            struct x;
+        };
+        let output = derive_typed_parser(input, false).to_string();
+        assert!(output.contains("`x` should reject \\\"x\\\", but it doesn't"));
+        assert!(!output.contains("mod grammar_tests"));
+
+        let input = quote! {
+            #[grammar_inline = "//@ reject \"x\"\nx = { \"x\" }"]
+            #[grammar_unit_tests]
+            struct x;
+        };
+        let output = derive_typed_parser(input, false).to_string();
+        assert!(!output.contains("compile_error"));
+        assert!(output.contains("mod grammar_tests"));
+        assert!(output.contains("fn x_reject_1"));
+    }
+
+    #[test]
+    fn validation_failure() {
+        let output = derive_typed_parser(
+            quote! {
+                #[grammar_inline = "x = { \"x\" }"]
+                #[grammar_inline = "y = { y }"]
+                struct x;
+            },
+            false,
+        )
+        .to_string();
+        assert!(output.contains("--> 1:7"));
+        assert!(output.contains("left-recursive"));
+    }
+
     #[test]
     #[should_panic]
     fn bool_attribute_format_error() {
@@ -0,0 +1,212 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Where repetitions store their items.
+//!
+//! Repetitions, such as [`RepMin`](crate::predefined_node::RepMin),
+//! collect matched items into [`Allocation::Vec`] of their last generic argument,
+//! which is [`Global`] by default and allocates with the global allocator.
+//!
+//! Implement [`Allocation`] with a [`Container`] of your own to keep parsing out of the global allocator,
+//! such as a vector in a bump or pool allocator,
+//! and set it for the generated parser with `allocation` (see [pest_typed_derive](https://docs.rs/pest_typed_derive)).
+//! With feature `allocator_api` on nightly, `In` does this for any [`Allocator`](core::alloc::Allocator).
+//!
+//! With feature `smallvec`, `Inline<N>` keeps up to `N` items in the node itself with [smallvec](https://docs.rs/smallvec),
+//! and only allocates for repetitions with more items,
+//! which suits rules that mostly repeat a few times.
+//! It can also be set for repetitions of a single rule with `#[inline_capacity = "N"]`.
+//!
+//! Boxes of rule contents and errors are still allocated with the global allocator.
+
+use alloc::vec::Vec;
+use core::ops::{Deref, DerefMut};
+#[cfg(feature = "allocator_api")]
+use core::{alloc::Allocator, marker::PhantomData};
+#[cfg(feature = "smallvec")]
+use smallvec::SmallVec;
+
+/// A growable sequence of items.
+pub trait Container<T>: Deref<Target = [T]> + DerefMut + IntoIterator<Item = T> {
+    /// Create an empty container.
+    fn new() -> Self;
+    /// Append an item.
+    fn push(&mut self, item: T);
+    /// Number of items that can be held without allocating again.
+    fn capacity(&self) -> usize;
+    /// Number of items that allocated memory can hold, which leaves out items held inline.
+    #[inline]
+    fn heap_capacity(&self) -> usize {
+        self.capacity()
+    }
+}
+
+#[cfg(not(feature = "allocator_api"))]
+impl<T> Container<T> for Vec<T> {
+    #[inline]
+    fn new() -> Self {
+        Vec::new()
+    }
+    #[inline]
+    fn push(&mut self, item: T) {
+        Vec::push(self, item)
+    }
+    #[inline]
+    fn capacity(&self) -> usize {
+        Vec::capacity(self)
+    }
+}
+
+#[cfg(feature = "allocator_api")]
+impl<T, A: Allocator + Default> Container<T> for Vec<T, A> {
+    #[inline]
+    fn new() -> Self {
+        Vec::new_in(A::default())
+    }
+    #[inline]
+    fn push(&mut self, item: T) {
+        Vec::push(self, item)
+    }
+    #[inline]
+    fn capacity(&self) -> usize {
+        Vec::capacity(self)
+    }
+}
+
+/// Storage of repetitions.
+pub trait Allocation {
+    /// Container of items of type `T`.
+    type Vec<T>: Container<T>;
+}
+
+/// Store repetitions in [`Vec`]s with the global allocator.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Global;
+
+impl Allocation for Global {
+    type Vec<T> = Vec<T>;
+}
+
+#[cfg(feature = "smallvec")]
+impl<T, const N: usize> Container<T> for SmallVec<[T; N]> {
+    #[inline]
+    fn new() -> Self {
+        SmallVec::new()
+    }
+    #[inline]
+    fn push(&mut self, item: T) {
+        SmallVec::push(self, item)
+    }
+    #[inline]
+    fn capacity(&self) -> usize {
+        SmallVec::capacity(self)
+    }
+    #[inline]
+    fn heap_capacity(&self) -> usize {
+        match self.spilled() {
+            true => SmallVec::capacity(self),
+            false => 0,
+        }
+    }
+}
+
+/// Store up to `N` items of each repetition inline in [`SmallVec`]s, and the rest with the global allocator.
+#[cfg(feature = "smallvec")]
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Inline<const N: usize>;
+
+#[cfg(feature = "smallvec")]
+impl<const N: usize> Allocation for Inline<N> {
+    type Vec<T> = SmallVec<[T; N]>;
+}
+
+/// Store repetitions in [`Vec`]s with allocator `A`, which is created with [`Default::default`].
+///
+/// Handles to shared allocators, such as a reference to a thread-local arena, fit here.
+#[cfg(feature = "allocator_api")]
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct In<A>(PhantomData<fn() -> A>);
+
+#[cfg(feature = "allocator_api")]
+impl<A: Allocator + Default> Allocation for In<A> {
+    type Vec<T> = Vec<T, A>;
+}
+
+#[cfg(all(test, feature = "allocator_api"))]
+mod tests {
+    use super::{Container, In};
+    use crate::predefined_node::{CharRange, Empty, Rep};
+    use crate::{tracker::Tracker, Position, Stack, TypedNode};
+    use alloc::alloc::Global;
+
+    #[test]
+    fn allocator() {
+        type Digits = Rep<CharRange<'0', '9'>, Empty<'static>, 0, In<Global>>;
+        let input = Position::from_start("123a");
+        let mut tracker = Tracker::<&str>::new(input);
+        let (next, digits) =
+            Digits::try_parse_with(input, &mut Stack::new(), &mut tracker).unwrap();
+        assert_eq!(next.pos(), 3);
+        assert_eq!(digits.content.len(), 3);
+        assert!(digits.content.capacity() >= 3);
+    }
+}
+
+#[cfg(all(test, feature = "smallvec"))]
+mod inline_tests {
+    use super::{Container, Inline};
+    use crate::predefined_node::{CharRange, Empty, Rep};
+    use crate::{tracker::Tracker, Position, Stack, TypedNode};
+    use alloc::{rc::Rc, vec, vec::Vec};
+    use smallvec::SmallVec;
+
+    #[test]
+    fn spill() {
+        let mut items = <SmallVec<[Rc<usize>; 2]> as Container<_>>::new();
+        let counted = Rc::new(0);
+        for _ in 0..2 {
+            items.push(counted.clone());
+        }
+        assert!(!items.spilled());
+        assert_eq!(items.heap_capacity(), 0);
+        assert_eq!(Rc::strong_count(&counted), 3);
+        let cloned = items.clone();
+        items.push(counted.clone());
+        assert!(items.spilled());
+        assert_eq!(items.len(), 3);
+        drop(items);
+        assert_eq!(Rc::strong_count(&counted), 3);
+
+        // Items not taken from the iterator are dropped with it.
+        let mut iter = cloned.into_iter();
+        assert!(iter.next().is_some());
+        drop(iter);
+        assert_eq!(Rc::strong_count(&counted), 1);
+    }
+
+    #[test]
+    fn repetition() {
+        type Digits<const N: usize> = Rep<CharRange<'0', '9'>, Empty<'static>, 0, Inline<N>>;
+        let parse = |input| {
+            let input = Position::from_start(input);
+            let mut tracker = Tracker::<&str>::new(input);
+            Digits::<2>::try_parse_with(input, &mut Stack::new(), &mut tracker)
+                .unwrap()
+                .1
+        };
+        let digits = parse("12a");
+        assert!(!digits.content.spilled());
+        let chars: Vec<char> = digits.content.iter().map(|c| c.matched.content).collect();
+        assert_eq!(chars, vec!['1', '2']);
+        let digits = parse("123");
+        assert!(digits.content.spilled());
+        assert_eq!(digits.content.len(), 3);
+        assert_eq!(digits, parse("123"));
+    }
+}
@@ -0,0 +1,213 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Owned trees cached on disk, for build tools that parse the same files again and again.
+//!
+//! A [`Cache`] keeps trees in a directory, in files named after a hash of the grammar,
+//! the rule that the input is parsed as, and the content of the input.
+//! A tree is loaded from its file instead of parsed if the file exists,
+//! and an edited input or grammar simply misses the cache.
+//!
+//! Trees are owned nodes that implement [`Cacheable`],
+//! which are generated with `emit_cache` (see [pest_typed_derive](https://docs.rs/pest_typed_derive)).
+//!
+//! ```ignore
+//! let cache = Cache::new("target/parse-cache");
+//! let file = owned::file::parse_cached(&cache, Arc::from(input))?;
+//! ```
+
+use crate::shared::SharedStr;
+use alloc::{string::ToString, sync::Arc, vec, vec::Vec};
+use serde_json::Value;
+use std::{fs, io, path::PathBuf};
+
+/// Version of the format of cache files.
+const FORMAT: u64 = 1;
+
+/// Hash `bytes` with 128-bit FNV-1a, which is stable across platforms and compiler versions.
+pub fn fingerprint(bytes: &[u8]) -> u128 {
+    const OFFSET: u128 = 0x6c62272e07bb014262b821756295c58d;
+    const PRIME: u128 = 0x0000000001000000000000000000013b;
+    bytes.iter().fold(OFFSET, |hash, &byte| {
+        (hash ^ u128::from(byte)).wrapping_mul(PRIME)
+    })
+}
+
+/// An owned node that can be stored in a [`Cache`].
+pub trait Cacheable: Sized {
+    /// Fingerprint of the grammar, which changes when rules change.
+    const GRAMMAR: u128;
+    /// Name of the rule of the node.
+    fn rule_name(&self) -> &'static str;
+    /// Matched text.
+    fn text(&self) -> &SharedStr;
+    /// Inner nodes, in source order.
+    fn children(&self) -> &[Self];
+    /// Rebuild a node of the rule named `rule`, or return [`None`] if there is no such rule.
+    fn rebuild(rule: &str, text: SharedStr, children: Vec<Self>) -> Option<Self>;
+}
+
+/// A directory of cached trees. See [module-level documentation](self).
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Cache {
+    dir: PathBuf,
+}
+
+impl Cache {
+    /// Keep trees in `dir`, which is created when the first tree is stored.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+    /// Key of the tree of `input` parsed as `rule`.
+    pub fn key<N: Cacheable>(rule: &str, input: &str) -> u128 {
+        let mut bytes = Vec::with_capacity(16 + rule.len() + 1 + input.len());
+        bytes.extend(N::GRAMMAR.to_le_bytes());
+        bytes.extend(rule.as_bytes());
+        bytes.push(0);
+        bytes.extend(input.as_bytes());
+        fingerprint(&bytes)
+    }
+    fn path(&self, key: u128) -> PathBuf {
+        self.dir.join(alloc::format!("{key:032x}.json"))
+    }
+    /// Load the tree of `input` parsed as `rule`, whose texts share `input`.
+    ///
+    /// Returns [`None`] if it's not cached, or the cache file is unreadable or invalid,
+    /// so the root of a loaded tree is always a node of `rule`.
+    pub fn load<N: Cacheable>(&self, rule: &str, input: &Arc<str>) -> Option<N> {
+        let content = fs::read_to_string(self.path(Self::key::<N>(rule, input))).ok()?;
+        let value: Value = serde_json::from_str(&content).ok()?;
+        let header = (value.get("format")?, value.get("length")?);
+        if header.0.as_u64()? != FORMAT || header.1.as_u64()? != input.len() as u64 {
+            return None;
+        }
+        let node: N = decode(value.get("tree")?, input)?;
+        (node.rule_name() == rule).then_some(node)
+    }
+    /// Store the tree of its input parsed as `rule`.
+    pub fn store<N: Cacheable>(&self, rule: &str, node: &N) -> io::Result<()> {
+        let input = node.text().input();
+        let value = serde_json::json!({
+            "format": FORMAT,
+            "length": input.len(),
+            "tree": encode(node),
+        });
+        fs::create_dir_all(&self.dir)?;
+        let path = self.path(Self::key::<N>(rule, input));
+        // Written to another file first, so that readers never see a partial file.
+        let temporary = path.with_extension(alloc::format!("{}.tmp", std::process::id()));
+        fs::write(&temporary, value.to_string())?;
+        fs::rename(&temporary, &path)
+    }
+    /// Load the tree of `input` parsed as `rule`, or parse it with `parse` and store the result.
+    ///
+    /// Failures to store the tree are ignored, as the cache only saves time.
+    pub fn get_or_parse<N: Cacheable, E>(
+        &self,
+        rule: &str,
+        input: Arc<str>,
+        parse: impl FnOnce(Arc<str>) -> Result<N, E>,
+    ) -> Result<N, E> {
+        if let Some(node) = self.load(rule, &input) {
+            return Ok(node);
+        }
+        let node = parse(input)?;
+        let _ = self.store(rule, &node);
+        Ok(node)
+    }
+    /// Remove all cached trees.
+    pub fn clear(&self) -> io::Result<()> {
+        match fs::remove_dir_all(&self.dir) {
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(()),
+            res => res,
+        }
+    }
+}
+
+/// Encode a node as `[rule, start, end, children...]`.
+fn encode<N: Cacheable>(node: &N) -> Value {
+    let range = node.text().range();
+    let mut res = vec![
+        Value::from(node.rule_name()),
+        Value::from(range.start),
+        Value::from(range.end),
+    ];
+    res.extend(node.children().iter().map(encode));
+    Value::Array(res)
+}
+
+fn decode<N: Cacheable>(value: &Value, input: &Arc<str>) -> Option<N> {
+    let [rule, start, end, children @ ..] = value.as_array()?.as_slice() else {
+        return None;
+    };
+    let start = usize::try_from(start.as_u64()?).ok()?;
+    let end = usize::try_from(end.as_u64()?).ok()?;
+    input.get(start..end)?;
+    let text = SharedStr::slice(input, start..end);
+    let children = children
+        .iter()
+        .map(|child| decode(child, input))
+        .collect::<Option<_>>()?;
+    N::rebuild(rule.as_str()?, text, children)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{fingerprint, Cache, Cacheable};
+    use crate::shared::SharedStr;
+    use alloc::{sync::Arc, vec, vec::Vec};
+
+    #[derive(Debug, PartialEq)]
+    struct Node(&'static str, SharedStr, Vec<Node>);
+
+    impl Cacheable for Node {
+        const GRAMMAR: u128 = 1;
+        fn rule_name(&self) -> &'static str {
+            self.0
+        }
+        fn text(&self) -> &SharedStr {
+            &self.1
+        }
+        fn children(&self) -> &[Self] {
+            &self.2
+        }
+        fn rebuild(rule: &str, text: SharedStr, children: Vec<Self>) -> Option<Self> {
+            let rule = ["pair", "key"].into_iter().find(|name| *name == rule)?;
+            Some(Self(rule, text, children))
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        assert_eq!(fingerprint(b""), 0x6c62272e07bb014262b821756295c58d);
+        assert_eq!(fingerprint(b"a"), 0xd228cb696f1a8caf78912b704e4a8964);
+
+        let dir =
+            std::env::temp_dir().join(alloc::format!("pest-typed-cache-{}", std::process::id()));
+        let cache = Cache::new(&dir);
+        let input: Arc<str> = Arc::from("a=1");
+        let tree = Node(
+            "pair",
+            SharedStr::new(input.clone()),
+            vec![Node("key", SharedStr::slice(&input, 0..1), vec![])],
+        );
+        assert_eq!(cache.load::<Node>("pair", &input), None);
+        cache.store("pair", &tree).unwrap();
+        let loaded = cache.load::<Node>("pair", &input).unwrap();
+        assert_eq!(loaded, tree);
+        assert!(Arc::ptr_eq(loaded.2[0].1.input(), &input));
+        assert_eq!(cache.load::<Node>("key", &input), None);
+        assert_eq!(cache.load::<Node>("pair", &Arc::from("a=2")), None);
+
+        let res: Result<Node, ()> = cache.get_or_parse("pair", input, |_| unreachable!());
+        assert_eq!(res.unwrap(), tree);
+        cache.clear().unwrap();
+        assert!(!dir.exists());
+    }
+}
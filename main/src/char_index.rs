@@ -0,0 +1,131 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Precomputed char counts of an input, for converting many offsets between bytes and chars.
+//!
+//! Some editors and other languages, such as Python, index strings by chars rather than bytes,
+//! and counting chars from the start of the input for every offset is quadratic.
+//! A [`CharIndex`] is built once in linear time, and each conversion only counts chars in a block of the input.
+//!
+//! ```
+//! # use pest_typed::{char_index::CharIndex, Span};
+//! let input = "嗨, a😀b";
+//! let index = CharIndex::new(input);
+//! assert_eq!(index.char_offset(5), 3);
+//! assert_eq!(index.byte_offset(5), Some(10));
+//! let span = Span::new(input, 6, 10).unwrap();
+//! assert_eq!(span.char_range(&index), 4..5);
+//! assert_eq!(span.end_pos().char_offset(&index), 5);
+//! ```
+
+use alloc::vec::Vec;
+
+/// Bytes in a block of the input.
+const BLOCK: usize = 64;
+
+/// Whether a byte starts a char in UTF-8, that is, is not a continuation byte.
+const fn starts_char(byte: u8) -> bool {
+    (byte as i8) >= -0x40
+}
+
+/// Char counts of an input. See [module-level documentation](self).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CharIndex<'i> {
+    input: &'i str,
+    /// Number of chars before the start of each block and in the whole input, or nothing if the input is ASCII.
+    blocks: Vec<usize>,
+}
+
+impl<'i> CharIndex<'i> {
+    /// Index chars of `input`.
+    pub fn new(input: &'i str) -> Self {
+        let mut blocks = Vec::new();
+        if !input.is_ascii() {
+            let mut count = 0;
+            for block in input.as_bytes().chunks(BLOCK) {
+                blocks.push(count);
+                count += block.iter().filter(|&&byte| starts_char(byte)).count();
+            }
+            blocks.push(count);
+        }
+        Self { input, blocks }
+    }
+    /// Indexed input.
+    pub fn input(&self) -> &'i str {
+        self.input
+    }
+    /// Number of chars in the input.
+    pub fn len(&self) -> usize {
+        self.char_offset(self.input.len())
+    }
+    /// Whether the input is empty.
+    pub fn is_empty(&self) -> bool {
+        self.input.is_empty()
+    }
+    /// Number of chars before byte offset `pos`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `pos` is out of the input or not on a char boundary.
+    pub fn char_offset(&self, pos: usize) -> usize {
+        assert!(
+            self.input.is_char_boundary(pos),
+            "position out of bounds or inside a char"
+        );
+        if self.blocks.is_empty() {
+            return pos;
+        }
+        let block = pos / BLOCK;
+        let rest = &self.input.as_bytes()[block * BLOCK..pos];
+        let count = rest.iter().filter(|&&byte| starts_char(byte)).count();
+        self.blocks[block] + count
+    }
+    /// Byte offset of the char at char offset `offset`, or the end of the input if `offset` is the number of chars.
+    ///
+    /// Returns [`None`] if `offset` is beyond the end of the input.
+    pub fn byte_offset(&self, offset: usize) -> Option<usize> {
+        if self.blocks.is_empty() {
+            return (offset <= self.input.len()).then_some(offset);
+        }
+        // The last block that starts at or before the char.
+        let block = self.blocks.partition_point(|&before| before <= offset) - 1;
+        let mut count = self.blocks[block];
+        let bytes = self.input.as_bytes();
+        for (pos, &byte) in bytes.iter().enumerate().skip(block * BLOCK) {
+            if starts_char(byte) {
+                if count == offset {
+                    return Some(pos);
+                }
+                count += 1;
+            }
+        }
+        (count == offset).then_some(bytes.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CharIndex;
+    use alloc::{format, string::String};
+
+    #[test]
+    fn same_as_counting() {
+        let long: String = (0..100).map(|i| format!("a{i}嗨😀\n")).collect();
+        for input in ["", "abc", "嗨\n\na😀b\n", &long] {
+            let index = CharIndex::new(input);
+            let boundaries = (0..=input.len()).filter(|&pos| input.is_char_boundary(pos));
+            for (offset, pos) in boundaries.enumerate() {
+                assert_eq!(index.char_offset(pos), offset, "{input:?} at {pos}");
+                assert_eq!(index.byte_offset(offset), Some(pos), "{input:?} at {pos}");
+            }
+            assert_eq!(index.len(), input.chars().count());
+            assert_eq!(index.byte_offset(index.len() + 1), None);
+        }
+    }
+}
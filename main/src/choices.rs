@@ -102,7 +102,7 @@ macro_rules! choices {
         pub use $mod::$name;
         #[doc = ::core::stringify!(Types for choices type [$name].)]
         pub mod $mod {
-            #![allow(clippy::style)]
+            #![allow(clippy::style, non_camel_case_types)]
 
             #[doc = ::core::stringify!(Match one of $number expressions.)]
             #[derive(Clone, Hash, PartialEq, Eq)]
@@ -172,11 +172,15 @@ macro_rules! choices {
                 ) -> ::core::result::Result<($pest_typed::Position<'i>, Self), ()> {
                     let res = $pest_typed::predefined_node::restore_on_err(stack, |stack| $V0::try_parse_with(input, stack, tracker));
                     if let Ok((input, res)) = res {
+                        tracker.choice_matched::<Self>(0, $number);
                         return Ok((input, Self::$v0(res)));
                     }
+                    let mut _index = 0usize;
                     $(
+                        _index += 1;
                         let res = $pest_typed::predefined_node::restore_on_err(stack, |stack| $V::try_parse_with(input, stack, tracker));
                         if let Ok((input, res)) = res {
+                            tracker.choice_matched::<Self>(_index, $number);
                             return Ok((input, Self::$v(res)));
                         }
                     )*
@@ -209,6 +213,42 @@ macro_rules! choices {
                     }
                 }
             }
+            impl<F: ?::core::marker::Sized, $V0: $pest_typed::fold::Foldable<F>, $($V: $pest_typed::fold::Foldable<F>, )* >
+                $pest_typed::fold::Foldable<F> for $name<$V0, $($V, )* >
+            {
+                fn fold_with(self, folder: &mut F) -> Self {
+                    match self {
+                        Self::$v0($v0) => Self::$v0($v0.fold_with(folder)),
+                        $(
+                            Self::$v($v) => Self::$v($v.fold_with(folder)),
+                        )*
+                    }
+                }
+            }
+            impl<$V0: $pest_typed::metrics::HeapSize, $($V: $pest_typed::metrics::HeapSize, )* >
+                $pest_typed::metrics::HeapSize for $name<$V0, $($V, )* >
+            {
+                fn heap_size(&self) -> ::core::primitive::usize {
+                    match self {
+                        Self::$v0($v0) => $v0.heap_size(),
+                        $(
+                            Self::$v($v) => $v.heap_size(),
+                        )*
+                    }
+                }
+            }
+            impl<$V0: $pest_typed::recovery::Recovered, $($V: $pest_typed::recovery::Recovered, )* >
+                $pest_typed::recovery::Recovered for $name<$V0, $($V, )* >
+            {
+                fn provenance(&self) -> $pest_typed::recovery::Provenance {
+                    match self {
+                        Self::$v0($v0) => $v0.provenance(),
+                        $(
+                            Self::$v($v) => $v.provenance(),
+                        )*
+                    }
+                }
+            }
             impl<$V0: ::core::fmt::Debug, $($V: ::core::fmt::Debug, )* >
                 ::core::fmt::Debug for $name<$V0, $($V, )* >
             {
@@ -0,0 +1,197 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Completion candidates at a cursor, for editors.
+//!
+//! [`complete`] parses the input before the cursor, and collects what could continue there:
+//! rules that were attempted at the cursor, and literals that start them,
+//! which are the first sets of what's being parsed, together with the rules being parsed.
+//! The word being typed at the cursor is not parsed, but used to filter literals.
+//!
+//! ```
+//! # use pest_typed::{completion::{complete, Candidate}, predefined_node::Insens, StringWrapper};
+//! #[derive(Clone, Debug, Hash, PartialEq, Eq)]
+//! struct Select;
+//! impl StringWrapper for Select {
+//!     const CONTENT: &'static str = "select";
+//! }
+//! let completions = complete::<(), Insens<'_, Select>>("SEL", 3);
+//! assert_eq!(completions.start, 0);
+//! assert_eq!(completions.prefix, "SEL");
+//! assert_eq!(
+//!     completions.candidates[0].candidate,
+//!     Candidate::Literal { text: "select".into(), insensitive: true },
+//! );
+//! ```
+
+use crate::{
+    position::Position,
+    tracker::{is_word_char, Tracker},
+    RuleType, Stack, TypedNode,
+};
+use alloc::{borrow::Cow, string::String, vec::Vec};
+
+/// What could be inserted at the cursor.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Candidate<R> {
+    /// A rule that was attempted at the cursor, with neither rules nor literals attempted in it there.
+    Rule(R),
+    /// A string literal.
+    Literal {
+        /// The literal, as written in the grammar.
+        text: String,
+        /// Whether it's case-insensitive.
+        insensitive: bool,
+    },
+}
+
+/// A completion candidate.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Completion<R> {
+    /// What could be inserted.
+    pub candidate: Candidate<R>,
+    /// Rule that the candidate is attempted in, or [`None`] if it's not in any rule.
+    ///
+    /// Literals are attempted in the innermost rule,
+    /// and rules in the innermost rule that starts before the cursor.
+    pub parent: Option<R>,
+}
+
+/// Completion candidates at a cursor. See [`complete`].
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Completions<'i, R> {
+    /// Byte offset of the word being typed, which candidates replace.
+    pub start: usize,
+    /// The word being typed, from [`Completions::start`] to the cursor.
+    pub prefix: &'i str,
+    /// Candidates, with literals before rules in each parent rule.
+    pub candidates: Vec<Completion<R>>,
+    /// Rules being parsed at the cursor, from the outermost one.
+    pub rule_stack: Vec<R>,
+}
+
+/// Collect completion candidates of `T` at byte offset `offset` in `input`.
+///
+/// Rules are not filtered with the word being typed, as they may match any word.
+/// There is no candidate if parsing fails before the word being typed.
+///
+/// # Panics
+///
+/// Panics if `offset` is not a character boundary of `input`.
+pub fn complete<'i, R: RuleType, T: TypedNode<'i, R>>(
+    input: &'i str,
+    offset: usize,
+) -> Completions<'i, R> {
+    let before = &input[..offset];
+    let start = before
+        .char_indices()
+        .rev()
+        .take_while(|(_, c)| is_word_char(*c))
+        .last()
+        .map_or(offset, |(i, _)| i);
+    let prefix = &input[start..offset];
+
+    let pos = Position::from_start(&input[..start]);
+    let mut stack = Stack::new();
+    let mut tracker = Tracker::new(pos).completing();
+    let _ = T::try_parse_with(pos, &mut stack, &mut tracker);
+
+    let rule_stack = tracker.rule_stack().to_vec();
+    let mut literals = tracker.expected_literals().clone();
+    let (failed, attempts) = tracker.finish();
+    let mut completions = Completions {
+        start,
+        prefix,
+        candidates: Vec::new(),
+        rule_stack,
+    };
+    if failed.pos() != start {
+        completions.rule_stack.clear();
+        return completions;
+    }
+    for (parent, (mut positives, _, _)) in attempts {
+        // Rules are replaced with literals in them.
+        positives.retain(|rule| !literals.contains_key(&Some(*rule)));
+        let literals = literals.remove(&parent).unwrap_or_default();
+        append(&mut completions, parent, literals, &mut positives);
+    }
+    for (parent, literals) in literals {
+        append(&mut completions, parent, literals, &mut Vec::new());
+    }
+    completions
+}
+
+fn append<R: RuleType>(
+    completions: &mut Completions<'_, R>,
+    parent: Option<R>,
+    literals: Vec<(Cow<'static, str>, bool)>,
+    rules: &mut Vec<R>,
+) {
+    let prefix = completions.prefix;
+    let candidates = literals
+        .into_iter()
+        .filter(|(text, insensitive)| match text.get(..prefix.len()) {
+            Some(head) if *insensitive => head.eq_ignore_ascii_case(prefix),
+            Some(head) => head == prefix,
+            None => false,
+        })
+        .map(|(text, insensitive)| Candidate::Literal {
+            text: text.into_owned(),
+            insensitive,
+        });
+    rules.sort();
+    rules.dedup();
+    let candidates = candidates.chain(rules.drain(..).map(Candidate::Rule));
+    completions
+        .candidates
+        .extend(candidates.map(|candidate| Completion { candidate, parent }));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{complete, Candidate};
+    use crate::{choices::Choice2, predefined_node::Str, StringWrapper};
+    use alloc::vec::Vec;
+
+    macro_rules! strings {
+        ($($name:ident => $content:literal,)*) => {
+            $(
+                #[derive(Clone, Debug, Hash, PartialEq, Eq)]
+                struct $name;
+                impl StringWrapper for $name {
+                    const CONTENT: &'static str = $content;
+                }
+            )*
+        };
+    }
+    strings! {
+        If => "if",
+        Else => "else",
+        While => "while",
+    }
+
+    #[test]
+    fn literals() {
+        let texts = |input: &str, offset: usize| {
+            complete::<(), Choice2<Str<If>, Choice2<Str<While>, Str<Else>>>>(input, offset)
+                .candidates
+                .into_iter()
+                .map(|completion| match completion.candidate {
+                    Candidate::Literal { text, .. } => text,
+                    Candidate::Rule(()) => unreachable!(),
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(texts("", 0), ["if", "while", "else"]);
+        assert_eq!(texts("wh", 2), ["while"]);
+        assert_eq!(texts("whx", 2), ["while"]);
+        assert!(texts("x", 1).is_empty());
+        assert!(texts("x ", 2).is_empty());
+    }
+}
@@ -0,0 +1,111 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Cancellation and progress reports of long parses.
+//!
+//! Share a [`ParseController`] between the parsing thread and others,
+//! and parse with [`ParsableTypedNode::try_parse_with_controller`](crate::ParsableTypedNode::try_parse_with_controller).
+//! The flag is checked each time a rule is entered,
+//! so a cancelled parse stops shortly after [`ParseController::cancel`] is called.
+
+use crate::error::Error;
+use alloc::boxed::Box;
+use core::{
+    fmt,
+    sync::atomic::{AtomicBool, Ordering},
+};
+use pest::RuleType;
+
+type Progress = Box<dyn Fn(usize) + Send + Sync>;
+
+/// Cancellation flag with an optional progress callback.
+#[derive(Default)]
+pub struct ParseController {
+    cancelled: AtomicBool,
+    progress: Option<(usize, Progress)>,
+}
+
+impl ParseController {
+    /// Create a controller without progress callback.
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Call `callback` with the farthest position reached, every time it passes `interval` more bytes.
+    ///
+    /// The callback may call [`ParseController::cancel`].
+    pub fn with_progress(
+        mut self,
+        interval: usize,
+        callback: impl Fn(usize) + Send + Sync + 'static,
+    ) -> Self {
+        self.progress = Some((interval.max(1), Box::new(callback)));
+        self
+    }
+    /// Request cancellation of parses using this controller.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+    /// Whether cancellation is requested.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+    /// Clear the cancellation flag, so that the controller can be used again.
+    pub fn reset(&self) {
+        self.cancelled.store(false, Ordering::Relaxed);
+    }
+    /// Report progress if `pos` reaches `next`, and update `next`.
+    #[inline]
+    pub(crate) fn report(&self, pos: usize, next: &mut usize) {
+        if let Some((interval, callback)) = &self.progress {
+            if pos >= *next {
+                callback(pos);
+                *next = (pos / interval + 1) * interval;
+            }
+        }
+    }
+}
+
+impl fmt::Debug for ParseController {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ParseController")
+            .field("cancelled", &self.is_cancelled())
+            .field(
+                "interval",
+                &self.progress.as_ref().map(|(interval, _)| interval),
+            )
+            .finish()
+    }
+}
+
+/// Error from a parse with a [`ParseController`].
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum ControlledError<R: RuleType> {
+    /// The parse is cancelled.
+    Cancelled,
+    /// The parse failed.
+    Failed(Error<R>),
+}
+
+impl<R: RuleType> fmt::Display for ControlledError<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Cancelled => f.write_str("Parsing cancelled."),
+            Self::Failed(error) => error.fmt(f),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: RuleType> std::error::Error for ControlledError<R> {}
+
+impl<R: RuleType> From<Error<R>> for ControlledError<R> {
+    fn from(error: Error<R>) -> Self {
+        Self::Failed(error)
+    }
+}
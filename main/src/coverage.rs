@@ -0,0 +1,159 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Grammar coverage, that is, which rules and which alternatives of choices are matched.
+//!
+//! Collect coverage of many parses into one [`Coverage`] with
+//! [`ParsableTypedNode::try_parse_with_coverage`](crate::ParsableTypedNode::try_parse_with_coverage),
+//! then list rules and alternatives that are never matched.
+//!
+//! Alternatives are only known for choices that are attempted at least once.
+//! Choices of the same shape in the same rule are counted together.
+
+use alloc::{collections::BTreeMap, vec, vec::Vec};
+use core::fmt;
+use pest::RuleType;
+
+/// An alternative of a choice.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Alternative<R> {
+    /// Innermost rule that contains the choice, or [`None`] if the choice is parsed directly.
+    pub rule: Option<R>,
+    /// Type name of the choice.
+    pub choice: &'static str,
+    /// Index of the alternative, from 0.
+    pub index: usize,
+    /// Number of alternatives of the choice.
+    pub count: usize,
+}
+
+/// Numbers of matches of rules and alternatives.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Coverage<R> {
+    rules: BTreeMap<R, usize>,
+    choices: BTreeMap<(Option<R>, &'static str), Vec<usize>>,
+}
+
+impl<R> Default for Coverage<R> {
+    fn default() -> Self {
+        Self {
+            rules: BTreeMap::new(),
+            choices: BTreeMap::new(),
+        }
+    }
+}
+
+impl<R: RuleType> Coverage<R> {
+    /// Create empty coverage.
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub(crate) fn rule(&mut self, rule: R) {
+        *self.rules.entry(rule).or_default() += 1;
+    }
+    pub(crate) fn choice(
+        &mut self,
+        rule: Option<R>,
+        choice: &'static str,
+        index: usize,
+        count: usize,
+    ) {
+        let counts = self
+            .choices
+            .entry((rule, choice))
+            .or_insert_with(|| vec![0; count]);
+        counts[index] += 1;
+    }
+    /// Number of matches of `rule`.
+    pub fn hits(&self, rule: R) -> usize {
+        self.rules.get(&rule).copied().unwrap_or(0)
+    }
+    /// Rules in `rules` that are never matched.
+    pub fn unhit_rules(&self, rules: &[R]) -> Vec<R> {
+        rules
+            .iter()
+            .copied()
+            .filter(|rule| self.hits(*rule) == 0)
+            .collect()
+    }
+    /// Alternatives that are never matched in attempted choices.
+    pub fn unhit_alternatives(&self) -> Vec<Alternative<R>> {
+        let mut res = vec![];
+        for ((rule, choice), counts) in &self.choices {
+            for (index, hits) in counts.iter().enumerate() {
+                if *hits == 0 {
+                    res.push(Alternative {
+                        rule: *rule,
+                        choice,
+                        index,
+                        count: counts.len(),
+                    });
+                }
+            }
+        }
+        res
+    }
+    /// Add counts in `other`.
+    pub fn merge(&mut self, other: &Self) {
+        for (rule, hits) in &other.rules {
+            *self.rules.entry(*rule).or_default() += hits;
+        }
+        for (key, counts) in &other.choices {
+            let entry = self
+                .choices
+                .entry(*key)
+                .or_insert_with(|| vec![0; counts.len()]);
+            for (total, hits) in entry.iter_mut().zip(counts) {
+                *total += hits;
+            }
+        }
+    }
+    /// Display rules in `rules` and alternatives that are never matched.
+    pub fn report<'c>(&'c self, rules: &'c [R]) -> Report<'c, R> {
+        Report {
+            coverage: self,
+            rules,
+        }
+    }
+}
+
+/// See [`Coverage::report`].
+#[derive(Clone, Copy, Debug)]
+pub struct Report<'c, R> {
+    coverage: &'c Coverage<R>,
+    rules: &'c [R],
+}
+
+impl<R: RuleType> fmt::Display for Report<'_, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let rules = self.coverage.unhit_rules(self.rules);
+        writeln!(
+            f,
+            "{} of {} rules matched.",
+            self.rules.len() - rules.len(),
+            self.rules.len()
+        )?;
+        for rule in rules {
+            writeln!(f, "Rule {:?} is never matched.", rule)?;
+        }
+        for alternative in self.coverage.unhit_alternatives() {
+            write!(
+                f,
+                "Alternative {} of {}",
+                alternative.index + 1,
+                alternative.count
+            )?;
+            if let Some(rule) = alternative.rule {
+                write!(f, " in rule {:?}", rule)?;
+            }
+            writeln!(f, " is never matched.")?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,219 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Drive parsing by hand, mixing typed nodes with hand-written code.
+//!
+//! A [`Cursor`] owns the position, the stack and the tracker that typed nodes are parsed with.
+//! Typed nodes, such as generated rules or [predefined nodes](crate::predefined_node),
+//! are parsed at the cursor with [`Cursor::parse`],
+//! and the input can be consumed by hand with methods of [`Position`] through [`Cursor::position_mut`].
+//!
+//! [`Cursor::checkpoint`] saves the position and the stack,
+//! and [`Cursor::rewind`] goes back to a saved one.
+//!
+//! ```
+//! # use pest_typed::{cursor::Cursor, predefined_node::Str, StringWrapper};
+//! #[derive(Clone, Debug, Hash, PartialEq, Eq)]
+//! struct Dashes;
+//! impl StringWrapper for Dashes {
+//!     const CONTENT: &'static str = "---\n";
+//! }
+//! let mut cursor = Cursor::<()>::new("---\ntitle: a\n---\nbody");
+//! // Parse the front matter with typed nodes, and the rest by hand.
+//! cursor.parse::<Str<Dashes>>().unwrap();
+//! let header = cursor.position();
+//! assert!(cursor.position_mut().skip_until(&["---\n"]));
+//! assert_eq!(header.span(&cursor.position()).as_str(), "title: a\n");
+//! cursor.parse::<Str<Dashes>>().unwrap();
+//! assert_eq!(cursor.rest(), "body");
+//! ```
+
+use crate::{
+    error::Error, position::Position, predefined_node::restore_on_err, tracker::Tracker, RuleType,
+    Span, Stack, TypedNode,
+};
+
+/// A saved state of a [`Cursor`]. See [`Cursor::checkpoint`].
+#[derive(Debug)]
+#[must_use = "a checkpoint should be passed to `Cursor::rewind` or `Cursor::commit`"]
+pub struct Checkpoint<'i> {
+    position: Position<'i>,
+    depth: usize,
+}
+
+impl<'i> Checkpoint<'i> {
+    /// Position when the checkpoint was saved.
+    pub fn position(&self) -> Position<'i> {
+        self.position
+    }
+}
+
+/// Position, stack and tracker for parsing by hand. See [module-level documentation](self).
+pub struct Cursor<'i, R: RuleType> {
+    position: Position<'i>,
+    stack: Stack<Span<'i>>,
+    tracker: Tracker<'i, R>,
+    depth: usize,
+}
+
+impl<'i, R: RuleType> Cursor<'i, R> {
+    /// Create a cursor at the start of `input`.
+    pub fn new(input: &'i str) -> Self {
+        Self::at(Position::from_start(input))
+    }
+    /// Create a cursor at `position`.
+    pub fn at(position: Position<'i>) -> Self {
+        Self {
+            position,
+            stack: Stack::new(),
+            tracker: Tracker::new(position),
+            depth: 0,
+        }
+    }
+    /// Record attempts in `tracker`, such as one in raw mode from [`Tracker::with_raw`].
+    pub fn with_tracker(mut self, tracker: Tracker<'i, R>) -> Self {
+        self.tracker = tracker;
+        self
+    }
+    /// Current position.
+    pub fn position(&self) -> Position<'i> {
+        self.position
+    }
+    /// Current position, to be moved by hand, such as with [`Position::match_string`].
+    pub fn position_mut(&mut self) -> &mut Position<'i> {
+        &mut self.position
+    }
+    /// Rest of the input from the current position.
+    pub fn rest(&self) -> &'i str {
+        self.position.rest()
+    }
+    /// Whether the whole input has been consumed.
+    pub fn is_at_end(&self) -> bool {
+        self.position.at_end()
+    }
+    /// Stack used by `PUSH`, `POP` and `PEEK`.
+    pub fn stack_mut(&mut self) -> &mut Stack<Span<'i>> {
+        &mut self.stack
+    }
+    /// Parse `T` at the current position, and move past it if it matches.
+    ///
+    /// The stack is restored if `T` fails, and failures are recorded for [`Cursor::into_error`].
+    pub fn parse<T: TypedNode<'i, R>>(&mut self) -> Option<T> {
+        let (next, res) = restore_on_err(&mut self.stack, |stack| {
+            T::try_parse_with(self.position, stack, &mut self.tracker)
+        })
+        .ok()?;
+        self.position = next;
+        Some(res)
+    }
+    /// Save the current position and stack.
+    ///
+    /// Checkpoints are nested, and the latest one should be passed to [`Cursor::rewind`]
+    /// or [`Cursor::commit`] before earlier ones.
+    pub fn checkpoint(&mut self) -> Checkpoint<'i> {
+        self.stack.snapshot();
+        self.depth += 1;
+        Checkpoint {
+            position: self.position,
+            depth: self.depth,
+        }
+    }
+    /// Go back to a checkpoint, restoring its position and stack.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `checkpoint` is not the latest one.
+    pub fn rewind(&mut self, checkpoint: Checkpoint<'i>) {
+        self.pop(&checkpoint);
+        self.stack.restore();
+        self.position = checkpoint.position;
+    }
+    /// Keep what's parsed since a checkpoint, and discard it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `checkpoint` is not the latest one.
+    pub fn commit(&mut self, checkpoint: Checkpoint<'i>) {
+        self.pop(&checkpoint);
+        self.stack.clear_snapshot();
+    }
+    fn pop(&mut self, checkpoint: &Checkpoint<'i>) {
+        assert_eq!(
+            checkpoint.depth, self.depth,
+            "checkpoints should be rewound or committed from the latest one"
+        );
+        self.depth -= 1;
+    }
+    /// Run `f`, and rewind if it returns an error.
+    pub fn attempt<T, E>(&mut self, f: impl FnOnce(&mut Self) -> Result<T, E>) -> Result<T, E> {
+        let checkpoint = self.checkpoint();
+        let res = f(self);
+        match res {
+            Ok(_) => self.commit(checkpoint),
+            Err(_) => self.rewind(checkpoint),
+        }
+        res
+    }
+    /// Error at the farthest position where typed nodes failed to match.
+    pub fn into_error(self) -> Error<R> {
+        self.tracker.collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Cursor;
+    use crate::{
+        predefined_node::{Push, Str, PEEK},
+        StringWrapper,
+    };
+
+    #[derive(Clone, Debug, Hash, PartialEq, Eq)]
+    struct A;
+    impl StringWrapper for A {
+        const CONTENT: &'static str = "a";
+    }
+
+    #[test]
+    fn checkpoints() {
+        let mut cursor = Cursor::<()>::new("aab");
+        let outer = cursor.checkpoint();
+        cursor.parse::<Push<Str<A>>>().unwrap();
+        let inner = cursor.checkpoint();
+        cursor.parse::<PEEK<'_>>().unwrap();
+        assert_eq!(cursor.rest(), "b");
+        cursor.rewind(inner);
+        assert_eq!(cursor.rest(), "ab");
+        cursor.commit(outer);
+        assert_eq!(cursor.stack_mut().len(), 1);
+
+        let res = cursor.attempt(|cursor| {
+            cursor.parse::<Str<A>>().unwrap();
+            cursor.parse::<Str<A>>().ok_or(())
+        });
+        assert!(res.is_err());
+        assert_eq!(cursor.rest(), "ab");
+        assert!(cursor.position_mut().match_string("ab"));
+        assert!(cursor.is_at_end());
+        assert!(cursor.parse::<Str<A>>().is_none());
+        assert_eq!(
+            cursor.into_error().location,
+            pest::error::InputLocation::Pos(3)
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn out_of_order() {
+        let mut cursor = Cursor::<()>::new("a");
+        let outer = cursor.checkpoint();
+        let _inner = cursor.checkpoint();
+        cursor.rewind(outer);
+    }
+}
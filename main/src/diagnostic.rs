@@ -0,0 +1,330 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Diagnostics with severities, labeled spans and trailing notes, displayed in the layout of rustc.
+//!
+//! Diagnostics may carry [`Suggestion`]s, which can be applied to the input with [`apply_suggestions`].
+//!
+//! ```
+//! # use pest_typed::{diagnostic::Diagnostic, source::SourceMap, Span};
+//! let mut map = SourceMap::new();
+//! let id = map.add("main.txt", "let x = 1;\nlet x = 2;\n");
+//! let input = map.input(id);
+//! let diagnostic = Diagnostic::warning("`x` is shadowed")
+//!     .with_code("W01")
+//!     .with_primary(Span::new(input, 15, 16).unwrap(), "shadows the previous one")
+//!     .with_secondary(Span::new(input, 4, 5).unwrap(), "previous one")
+//!     .with_help("rename one of them");
+//! assert_eq!(
+//!     diagnostic.display(&map).to_string(),
+//!     [
+//!         "warning[W01]: `x` is shadowed",
+//!         " --> main.txt:2:5",
+//!         "  |",
+//!         "2 | let x = 2;␊",
+//!         "  |     ^ shadows the previous one",
+//!         "  |",
+//!         "1 | let x = 1;␊",
+//!         "  |     - previous one",
+//!         "  |",
+//!         "  = help: rename one of them",
+//!         "",
+//!     ]
+//!     .join("\n"),
+//! );
+//! ```
+
+use crate::{
+    source::{Label, SourceMap},
+    Span,
+};
+use alloc::{string::String, vec::Vec};
+use core::fmt;
+use pest::{
+    error::{Error, InputLocation},
+    RuleType,
+};
+
+/// Severity of a diagnostic or a trailing note.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Severity {
+    /// Error.
+    Error,
+    /// Warning.
+    Warning,
+    /// Note.
+    Note,
+    /// Help.
+    Help,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Error => "error",
+            Self::Warning => "warning",
+            Self::Note => "note",
+            Self::Help => "help",
+        })
+    }
+}
+
+/// A machine-applicable fix that replaces a span with some text.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Suggestion<'i> {
+    /// Replaced span. Empty for insertions.
+    pub span: Span<'i>,
+    /// Replacement. Empty for removals.
+    pub replacement: String,
+}
+
+impl<'i> Suggestion<'i> {
+    /// Create a suggestion.
+    pub fn new(span: Span<'i>, replacement: impl Into<String>) -> Self {
+        Self {
+            span,
+            replacement: replacement.into(),
+        }
+    }
+}
+
+impl fmt::Display for Suggestion<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.span.as_str(), self.replacement.as_str()) {
+            ("", new) => write!(f, "insert `{}`", new),
+            (old, "") => write!(f, "remove `{}`", old),
+            (old, new) => write!(f, "replace `{}` with `{}`", old, new),
+        }
+    }
+}
+
+/// Apply suggestions to `input`, which their spans point into.
+///
+/// Suggestions are applied in the order of their spans.
+/// Those overlapping an earlier one, or not pointing into `input`, are skipped.
+///
+/// ```
+/// # use pest_typed::{diagnostic::{apply_suggestions, Suggestion}, Span};
+/// let input = "let  x = 1;;";
+/// let suggestions = [
+///     Suggestion::new(Span::new(input, 11, 12).unwrap(), ""),
+///     Suggestion::new(Span::new(input, 3, 5).unwrap(), " "),
+///     Suggestion::new(Span::new(input, 4, 6).unwrap(), "y"),
+/// ];
+/// assert_eq!(apply_suggestions(input, &suggestions), "let x = 1;");
+/// ```
+pub fn apply_suggestions(input: &str, suggestions: &[Suggestion<'_>]) -> String {
+    let mut sorted: Vec<_> = suggestions
+        .iter()
+        .filter(|suggestion| core::ptr::eq(suggestion.span.get_input(), input))
+        .collect();
+    sorted.sort_by_key(|suggestion| (suggestion.span.start(), suggestion.span.end()));
+    let mut res = String::with_capacity(input.len());
+    let mut last = 0;
+    for suggestion in sorted {
+        if suggestion.span.start() < last {
+            continue;
+        }
+        res.push_str(&input[last..suggestion.span.start()]);
+        res.push_str(&suggestion.replacement);
+        last = suggestion.span.end();
+    }
+    res.push_str(&input[last..]);
+    res
+}
+
+/// A diagnostic.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Diagnostic<'i> {
+    /// Severity.
+    pub severity: Severity,
+    /// Code, written after the severity.
+    pub code: Option<String>,
+    /// Message.
+    pub message: String,
+    /// Labeled spans, displayed in order.
+    pub labels: Vec<Label<'i>>,
+    /// Trailing lines, such as `= help: ...`.
+    pub notes: Vec<(Severity, String)>,
+    /// Suggested fixes, displayed after notes.
+    pub suggestions: Vec<Suggestion<'i>>,
+}
+
+impl<'i> Diagnostic<'i> {
+    /// Create a diagnostic without labels.
+    pub fn new(severity: Severity, message: impl Into<String>) -> Self {
+        Self {
+            severity,
+            code: None,
+            message: message.into(),
+            labels: Vec::new(),
+            notes: Vec::new(),
+            suggestions: Vec::new(),
+        }
+    }
+    /// Create an error.
+    pub fn error(message: impl Into<String>) -> Self {
+        Self::new(Severity::Error, message)
+    }
+    /// Create a warning.
+    pub fn warning(message: impl Into<String>) -> Self {
+        Self::new(Severity::Warning, message)
+    }
+    /// Create a note.
+    pub fn note(message: impl Into<String>) -> Self {
+        Self::new(Severity::Note, message)
+    }
+    /// Create a help message.
+    pub fn help(message: impl Into<String>) -> Self {
+        Self::new(Severity::Help, message)
+    }
+    /// Convert an error produced when parsing `input`.
+    pub fn from_error<R: RuleType>(error: &Error<R>, input: &'i str) -> Self {
+        let (start, end) = match error.location {
+            InputLocation::Pos(pos) => (pos, pos),
+            InputLocation::Span(span) => span,
+        };
+        let mut res = Self::error(error.variant.message());
+        if let Some(span) = Span::new(input, start, end) {
+            res = res.with_primary(span, "");
+        }
+        res
+    }
+    /// Set [`Diagnostic::code`].
+    pub fn with_code(mut self, code: impl Into<String>) -> Self {
+        self.code = Some(code.into());
+        self
+    }
+    /// Add a primary label.
+    pub fn with_primary(mut self, span: Span<'i>, message: impl Into<String>) -> Self {
+        self.labels.push(Label::primary(span, message));
+        self
+    }
+    /// Add a secondary label.
+    pub fn with_secondary(mut self, span: Span<'i>, message: impl Into<String>) -> Self {
+        self.labels.push(Label::secondary(span, message));
+        self
+    }
+    /// Add a trailing note.
+    pub fn with_note(mut self, message: impl Into<String>) -> Self {
+        self.notes.push((Severity::Note, message.into()));
+        self
+    }
+    /// Add a trailing help line.
+    pub fn with_help(mut self, message: impl Into<String>) -> Self {
+        self.notes.push((Severity::Help, message.into()));
+        self
+    }
+    /// Add a suggested fix, displayed as `= help: replace ...`.
+    pub fn with_suggestion(mut self, span: Span<'i>, replacement: impl Into<String>) -> Self {
+        self.suggestions.push(Suggestion::new(span, replacement));
+        self
+    }
+    /// Display with paths of sources in `map`.
+    pub fn display<'d>(&'d self, map: &'d SourceMap) -> DisplayDiagnostic<'d, 'i> {
+        DisplayDiagnostic {
+            diagnostic: self,
+            map,
+        }
+    }
+}
+
+/// Displays locations of spans without paths.
+impl fmt::Display for Diagnostic<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        static EMPTY: SourceMap = SourceMap::new();
+        self.display(&EMPTY).fmt(f)
+    }
+}
+
+/// A diagnostic with sources of its spans. See [`Diagnostic::display`].
+#[derive(Clone, Copy, Debug)]
+pub struct DisplayDiagnostic<'d, 'i> {
+    diagnostic: &'d Diagnostic<'i>,
+    map: &'d SourceMap,
+}
+
+impl fmt::Display for DisplayDiagnostic<'_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let diagnostic = self.diagnostic;
+        write!(f, "{}", diagnostic.severity)?;
+        if let Some(code) = &diagnostic.code {
+            write!(f, "[{}]", code)?;
+        }
+        writeln!(f, ": {}", diagnostic.message)?;
+        let labels = self.map.display_labels(&diagnostic.labels);
+        let width = labels.width();
+        labels.display_with_width(width, f)?;
+        if !diagnostic.notes.is_empty() || !diagnostic.suggestions.is_empty() {
+            let spacing = " ".repeat(width);
+            if !diagnostic.labels.is_empty() {
+                writeln!(f, "{} |", spacing)?;
+            }
+            for (severity, message) in &diagnostic.notes {
+                writeln!(f, "{} = {}: {}", spacing, severity, message)?;
+            }
+            for suggestion in &diagnostic.suggestions {
+                writeln!(f, "{} = {}: {}", spacing, Severity::Help, suggestion)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{apply_suggestions, Diagnostic, Severity};
+    use crate::{
+        error::{Error, ErrorVariant},
+        Span,
+    };
+    use alloc::string::ToString;
+
+    #[test]
+    fn from_error() {
+        let input = "a\nbc";
+        let error = Error::<&str>::new_from_pos(
+            ErrorVariant::ParsingError {
+                positives: ["x"].into(),
+                negatives: [].into(),
+            },
+            pest::Position::new(input, 3).unwrap(),
+        );
+        let diagnostic = Diagnostic::from_error(&error, input).with_note("in a test");
+        assert_eq!(diagnostic.severity, Severity::Error);
+        assert_eq!(
+            diagnostic.to_string(),
+            "error: expected \"x\"\n  |\n2 | bc\n  |  ^\n  |\n  = note: in a test\n",
+        );
+        assert_eq!(
+            Diagnostic::help("no labels")
+                .with_note("only notes")
+                .to_string(),
+            "help: no labels\n = note: only notes\n",
+        );
+    }
+
+    #[test]
+    fn suggestions() {
+        let input = "a;;b";
+        let diagnostic = Diagnostic::warning("redundant semicolon")
+            .with_primary(Span::new(input, 2, 3).unwrap(), "")
+            .with_suggestion(Span::new(input, 2, 3).unwrap(), "")
+            .with_suggestion(Span::new(input, 4, 4).unwrap(), ";")
+            .with_suggestion(Span::new(input, 0, 1).unwrap(), "c");
+        assert_eq!(
+            diagnostic.to_string(),
+            "warning: redundant semicolon\n  |\n1 | a;;b\n  |   ^\n  |\n  = help: remove `;`\n  = help: insert `;`\n  = help: replace `a` with `c`\n",
+        );
+        assert_eq!(apply_suggestions(input, &diagnostic.suggestions), "c;b;");
+        // Suggestions for other inputs are skipped.
+        let other = input.to_string();
+        assert_eq!(apply_suggestions(&other, &diagnostic.suggestions), "a;;b");
+    }
+}
@@ -0,0 +1,104 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Structural comparison of two trees of the same grammar.
+//!
+//! Trees are compared through the [Pairs API](crate::iterators::Pairs), so silent rules are transparent.
+//! Two nodes are the same if they have the same rule and the same text,
+//! and children of different nodes are aligned by skipping their common prefix and suffix.
+
+use crate::{iterators::Pair, Span};
+use alloc::{vec, vec::Vec};
+use pest::RuleType;
+
+/// Differing nodes in both trees.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Difference<'l, 'r, R: RuleType> {
+    /// Span covering differing nodes in the left tree. Empty if nodes are only in the right tree.
+    pub left: Span<'l>,
+    /// Rules of differing nodes in the left tree.
+    pub left_rules: Vec<R>,
+    /// Span covering differing nodes in the right tree. Empty if nodes are only in the left tree.
+    pub right: Span<'r>,
+    /// Rules of differing nodes in the right tree.
+    pub right_rules: Vec<R>,
+}
+
+fn same<'i: 'n, 'n, 'j: 'm, 'm, R: RuleType + 'n + 'm>(
+    left: &'n dyn Pair<'i, 'n, R>,
+    right: &'m dyn Pair<'j, 'm, R>,
+) -> bool {
+    left.rule() == right.rule() && left.span().as_str() == right.span().as_str()
+}
+
+/// Span from the start of the first node to the end of the last one, or an empty span at `pos`.
+fn cover<'i: 'n, 'n, R: RuleType + 'n>(
+    input: &'i str,
+    pos: usize,
+    nodes: &[&'n dyn Pair<'i, 'n, R>],
+) -> Span<'i> {
+    match (nodes.first(), nodes.last()) {
+        (Some(first), Some(last)) => first.span().start_pos().span(&last.span().end_pos()),
+        _ => Span::new(input, pos, pos).unwrap(),
+    }
+}
+
+/// Compare two trees, and return the differences in source order.
+///
+/// Returns nothing if the trees are the same.
+pub fn diff<'l: 'a, 'a, 'r: 'b, 'b, R: RuleType + 'a + 'b>(
+    left: &'a dyn Pair<'l, 'a, R>,
+    right: &'b dyn Pair<'r, 'b, R>,
+) -> Vec<Difference<'l, 'r, R>> {
+    let mut res = vec![];
+    let mut stack = vec![(vec![left], vec![right], left.span(), right.span())];
+    while let Some((lefts, rights, left_parent, right_parent)) = stack.pop() {
+        let prefix = lefts
+            .iter()
+            .zip(&rights)
+            .take_while(|(l, r)| same(**l, **r))
+            .count();
+        let suffix = lefts[prefix..]
+            .iter()
+            .rev()
+            .zip(rights[prefix..].iter().rev())
+            .take_while(|(l, r)| same(**l, **r))
+            .count();
+        // Empty spans are placed after the common prefix.
+        let left_pos = prefix
+            .checked_sub(1)
+            .map_or(left_parent.start(), |i| lefts[i].span().end());
+        let right_pos = prefix
+            .checked_sub(1)
+            .map_or(right_parent.start(), |i| rights[i].span().end());
+        let lefts = &lefts[prefix..lefts.len() - suffix];
+        let rights = &rights[prefix..rights.len() - suffix];
+        if lefts.is_empty() && rights.is_empty() {
+            continue;
+        }
+        let descend = lefts.len() == rights.len()
+            && lefts.iter().zip(rights).all(|(l, r)| {
+                l.rule() == r.rule() && (l.inner().len() != 0 || r.inner().len() != 0)
+            });
+        if descend {
+            // Visit in reverse so that differences are in source order.
+            for (l, r) in lefts.iter().zip(rights).rev() {
+                stack.push((l.inner().collect(), r.inner().collect(), l.span(), r.span()));
+            }
+            continue;
+        }
+        res.push(Difference {
+            left: cover(left_parent.get_input(), left_pos, lefts),
+            left_rules: lefts.iter().map(|node| node.rule()).collect(),
+            right: cover(right_parent.get_input(), right_pos, rights),
+            right_rules: rights.iter().map(|node| node.rule()).collect(),
+        });
+    }
+    res
+}
@@ -0,0 +1,106 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Dynamically typed views of typed trees, for entry points chosen at runtime.
+//!
+//! A [`DynNode`] is a rule, its span and its inner nodes, in the shape of [`Pair`]s.
+//! With `emit_dyn` (see [pest_typed_derive](https://docs.rs/pest_typed_derive)),
+//! `parse_dyn` parses an input as a rule named by a string, such as one from a test file or a command line,
+//! and returns a [`DynNode`], so that not every entry point has to be written down at compile time.
+//!
+//! ```ignore
+//! let expr = parse_dyn("expr", "1 + 2")?;
+//! assert_eq!(expr.rule(), Rule::expr);
+//! assert_eq!(expr.children_of(Rule::number).count(), 2);
+//! ```
+
+use crate::{
+    error::{Error, ErrorVariant},
+    iterators::Pair,
+    RuleType, Span,
+};
+use alloc::{format, vec::Vec};
+use core::fmt;
+
+/// A node of any rule. See [module-level documentation](self).
+#[derive(Clone, Eq, Hash, PartialEq)]
+pub struct DynNode<'i, R> {
+    rule: R,
+    span: Span<'i>,
+    children: Vec<Self>,
+}
+
+impl<'i, R: RuleType> DynNode<'i, R> {
+    /// Convert a pair and its inner pairs.
+    pub fn from_pair<'n>(pair: &'n dyn Pair<'i, 'n, R>) -> Self
+    where
+        'i: 'n,
+    {
+        Self {
+            rule: pair.rule(),
+            span: pair.span(),
+            children: pair.inner().map(Self::from_pair).collect(),
+        }
+    }
+    /// Rule of the node.
+    pub fn rule(&self) -> R {
+        self.rule
+    }
+    /// Matched span.
+    pub fn span(&self) -> Span<'i> {
+        self.span
+    }
+    /// Matched text.
+    pub fn as_str(&self) -> &'i str {
+        self.span.as_str()
+    }
+    /// Inner nodes, in source order.
+    pub fn children(&self) -> &[Self] {
+        &self.children
+    }
+    /// Inner nodes of `rule`, in source order.
+    pub fn children_of(&self, rule: R) -> impl Iterator<Item = &Self> + '_ {
+        self.children.iter().filter(move |child| child.rule == rule)
+    }
+    /// The node and all nodes inside it, in pre-order.
+    pub fn descendants(&self) -> Vec<&Self> {
+        let mut res = Vec::new();
+        let mut stack = alloc::vec![self];
+        while let Some(node) = stack.pop() {
+            res.push(node);
+            stack.extend(node.children.iter().rev());
+        }
+        res
+    }
+}
+
+impl<R: RuleType> fmt::Debug for DynNode<'_, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:?}@{:?}",
+            self.rule,
+            self.span.start()..self.span.end()
+        )?;
+        if !self.children.is_empty() {
+            f.debug_list().entries(&self.children).finish()?;
+        }
+        Ok(())
+    }
+}
+
+/// Error of `parse_dyn` for a rule name that is not an entry point.
+pub fn unknown_rule<R: RuleType>(rule: &str, input: &str) -> Error<R> {
+    Error::new_from_pos(
+        ErrorVariant::CustomError {
+            message: format!("Rule {} is not defined.", rule),
+        },
+        pest::Position::from_start(input),
+    )
+}
@@ -0,0 +1,160 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Regions of the input delegated to other parsers.
+//!
+//! The generator wraps inner expressions of rules marked with `#[embed = "Type"]` in [`Embed`].
+//! The expression matches a raw region, such as text in balanced braces,
+//! and then the [`Delegate`] named by the attribute parses the region into a foreign node,
+//! which is kept in the host tree.
+//!
+//! [`Grammar`] delegates regions to a rule of another grammar.
+
+use crate::{
+    iterators::{Pair, Pairs},
+    position::Position,
+    span::Span,
+    tracker::Tracker,
+    ParsableTypedNode, RuleType, Stack, TypedNode,
+};
+use alloc::boxed::Box;
+use core::{fmt, hash, iter, marker::PhantomData, ops::Deref};
+
+/// A parser for regions of the input.
+pub trait Delegate<'i> {
+    /// Node parsed from a region.
+    type Output: Clone + fmt::Debug + Eq + hash::Hash;
+    /// Parse the whole region, or return [`None`] if it can't be parsed.
+    ///
+    /// Spans in the output should point into the input of `span`.
+    fn parse_region(span: Span<'i>) -> Option<Self::Output>;
+}
+
+/// Delegate regions to `T`, a node of another grammar whose rule type is `R`.
+///
+/// The whole region should match `T`, and implicit whitespaces of `T` are not skipped around it.
+pub struct Grammar<R, T> {
+    _phantom: PhantomData<(R, T)>,
+}
+
+impl<'i, R, T> Delegate<'i> for Grammar<R, T>
+where
+    R: RuleType,
+    T: ParsableTypedNode<'i, R> + Eq + hash::Hash,
+{
+    type Output = T;
+    fn parse_region(span: Span<'i>) -> Option<T> {
+        match T::try_parse_prefix_at(span.start_pos()) {
+            Ok((res, end)) if end == span.end_pos() => Some(res),
+            _ => None,
+        }
+    }
+}
+
+/// A node parsed by `D` from the region matched by `T`. See [module-level documentation](self).
+///
+/// Pairs of the foreign node are not iterated over, as they have another rule type.
+pub struct Embed<'i, R, T, D: Delegate<'i>> {
+    // Named so that they are not confused with fields of the foreign node when dereferenced.
+    extent: Span<'i>,
+    node: D::Output,
+    _phantom: PhantomData<(R, T)>,
+}
+
+impl<'i, R, T, D: Delegate<'i>> Embed<'i, R, T, D> {
+    /// Create a node from a region and the foreign node parsed from it.
+    pub fn new(span: Span<'i>, node: D::Output) -> Self {
+        Self {
+            extent: span,
+            node,
+            _phantom: PhantomData,
+        }
+    }
+    /// Region matched by `T`.
+    pub fn span(&self) -> Span<'i> {
+        self.extent
+    }
+    /// The foreign node.
+    pub fn get(&self) -> &D::Output {
+        &self.node
+    }
+    /// Take the foreign node.
+    pub fn into_inner(self) -> D::Output {
+        self.node
+    }
+}
+
+impl<'i, R, T, D: Delegate<'i>> Deref for Embed<'i, R, T, D> {
+    type Target = D::Output;
+    fn deref(&self) -> &D::Output {
+        &self.node
+    }
+}
+
+/// Fails where `T` fails, or where `D` can't parse the region.
+impl<'i, R, T, D> TypedNode<'i, R> for Embed<'i, R, T, D>
+where
+    R: RuleType,
+    T: TypedNode<'i, R>,
+    D: Delegate<'i>,
+{
+    #[inline]
+    fn try_parse_with(
+        input: Position<'i>,
+        stack: &mut Stack<Span<'i>>,
+        tracker: &mut Tracker<'i, R>,
+    ) -> Result<(Position<'i>, Self), ()> {
+        let (end, _) = T::try_parse_with(input, stack, tracker)?;
+        let span = input.span(&end);
+        let node = D::parse_region(span).ok_or(())?;
+        Ok((end, Self::new(span, node)))
+    }
+}
+
+impl<'i: 'n, 'n, R: RuleType + 'n, T, D: Delegate<'i>> Pairs<'i, 'n, R> for Embed<'i, R, T, D> {
+    type Iter = iter::Empty<&'n dyn Pair<'i, 'n, R>>;
+    type IntoIter = iter::Empty<Box<dyn Pair<'i, 'n, R> + 'n>>;
+
+    fn iter_pairs(&'n self) -> Self::Iter {
+        iter::empty()
+    }
+    fn into_iter_pairs(self) -> Self::IntoIter {
+        iter::empty()
+    }
+}
+
+impl<'i, R, T, D: Delegate<'i>> Clone for Embed<'i, R, T, D> {
+    fn clone(&self) -> Self {
+        Self::new(self.extent, self.node.clone())
+    }
+}
+
+impl<'i, R, T, D: Delegate<'i>> fmt::Debug for Embed<'i, R, T, D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Embed")
+            .field("span", &self.extent)
+            .field("node", &self.node)
+            .finish()
+    }
+}
+
+impl<'i, R, T, D: Delegate<'i>> PartialEq for Embed<'i, R, T, D> {
+    fn eq(&self, other: &Self) -> bool {
+        self.extent == other.extent && self.node == other.node
+    }
+}
+
+impl<'i, R, T, D: Delegate<'i>> Eq for Embed<'i, R, T, D> {}
+
+impl<'i, R, T, D: Delegate<'i>> hash::Hash for Embed<'i, R, T, D> {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.extent.hash(state);
+        self.node.hash(state);
+    }
+}
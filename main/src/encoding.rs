@@ -0,0 +1,220 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Prepare raw bytes, such as contents of files, for parsing.
+//!
+//! Parsers take UTF-8 texts, while files may start with a byte order mark, or be in UTF-16 or UTF-32.
+//! [`prepare`] detects the encoding with [`detect`], strips a UTF-8 byte order mark,
+//! and rejects other encodings with an [`EncodingError`] that names them,
+//! rather than failing to parse at the first byte.
+//! The stripped mark is recorded in an [`OffsetMap`],
+//! so that spans of the parsed tree can be moved back onto the original bytes,
+//! where the snippet formatter shows the same lines and columns as editors do.
+//!
+//! ```
+//! # use pest_typed::{encoding::{prepare, Encoding, EncodingError}, Span};
+//! let bytes = "\u{feff}key = value".as_bytes();
+//! let prepared = prepare(bytes).unwrap();
+//! assert_eq!(prepared.encoding(), Encoding::Utf8Bom);
+//! assert_eq!(prepared.text(), "key = value");
+//! let span = Span::new(prepared.text(), 6, 11).unwrap();
+//! assert_eq!(prepared.to_original(&span).start(), 9);
+//!
+//! let error = prepare(b"\xff\xfek\0e\0y\0").unwrap_err();
+//! assert_eq!(error, EncodingError::Unsupported(Encoding::Utf16Le));
+//! ```
+
+use crate::{offset_map::OffsetMap, Span};
+use core::{fmt, str};
+
+/// Encoding of raw bytes, as told by their byte order mark or their zero bytes.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Encoding {
+    /// UTF-8 without a byte order mark.
+    Utf8,
+    /// UTF-8 with a byte order mark.
+    Utf8Bom,
+    /// UTF-16 in little endian.
+    Utf16Le,
+    /// UTF-16 in big endian.
+    Utf16Be,
+    /// UTF-32 in little endian.
+    Utf32Le,
+    /// UTF-32 in big endian.
+    Utf32Be,
+}
+
+impl Encoding {
+    /// Name of the encoding, such as `UTF-16LE`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Utf8 => "UTF-8",
+            Self::Utf8Bom => "UTF-8 with BOM",
+            Self::Utf16Le => "UTF-16LE",
+            Self::Utf16Be => "UTF-16BE",
+            Self::Utf32Le => "UTF-32LE",
+            Self::Utf32Be => "UTF-32BE",
+        }
+    }
+    /// Length of the byte order mark in this encoding.
+    pub fn bom_len(self) -> usize {
+        match self {
+            Self::Utf8 => 0,
+            Self::Utf8Bom => 3,
+            Self::Utf16Le | Self::Utf16Be => 2,
+            Self::Utf32Le | Self::Utf32Be => 4,
+        }
+    }
+}
+
+impl fmt::Display for Encoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Detect the encoding of `bytes`.
+///
+/// Byte order marks are checked first.
+/// Without one, an input starting with an ASCII char encoded in UTF-16 or UTF-32,
+/// that is, a non-zero byte next to zero bytes, is taken as in that encoding,
+/// and anything else as UTF-8, which may still be invalid.
+pub fn detect(bytes: &[u8]) -> Encoding {
+    match bytes {
+        [0xEF, 0xBB, 0xBF, ..] => Encoding::Utf8Bom,
+        [0xFF, 0xFE, 0, 0, ..] | [_, 0, 0, 0, ..] if bytes[0] != 0 => Encoding::Utf32Le,
+        [0, 0, 0xFE, 0xFF, ..] | [0, 0, 0, _, ..] if bytes[3] != 0 => Encoding::Utf32Be,
+        [0xFF, 0xFE, ..] | [_, 0, ..] if bytes[0] != 0 => Encoding::Utf16Le,
+        [0xFE, 0xFF, ..] | [0, _, ..] if bytes[1] != 0 => Encoding::Utf16Be,
+        _ => Encoding::Utf8,
+    }
+}
+
+/// Error of [`prepare`].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum EncodingError {
+    /// The input is in an encoding other than UTF-8.
+    Unsupported(Encoding),
+    /// The input is not valid UTF-8 from byte offset `offset` in the original bytes.
+    InvalidUtf8 {
+        /// Byte offset of the first invalid byte.
+        offset: usize,
+    },
+}
+
+impl fmt::Display for EncodingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unsupported(encoding) => write!(
+                f,
+                "input is encoded in {}, but only UTF-8 is supported; convert it to UTF-8 first",
+                encoding
+            ),
+            Self::InvalidUtf8 { offset } => {
+                write!(f, "input is not valid UTF-8 at byte offset {}", offset)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for EncodingError {}
+
+/// An input ready for parsing. See [module-level documentation](self).
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Prepared<'i> {
+    original: &'i str,
+    text: &'i str,
+    encoding: Encoding,
+    map: OffsetMap,
+}
+
+impl<'i> Prepared<'i> {
+    /// The original input, including its byte order mark.
+    pub fn original(&self) -> &'i str {
+        self.original
+    }
+    /// The text to parse.
+    pub fn text(&self) -> &'i str {
+        self.text
+    }
+    /// Detected encoding of the original input.
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+    /// Offsets stripped from the original input.
+    pub fn offset_map(&self) -> &OffsetMap {
+        &self.map
+    }
+    /// Move a span of [`Prepared::text`] onto [`Prepared::original`].
+    pub fn to_original(&self, span: &Span<'_>) -> Span<'i> {
+        debug_assert!(core::ptr::eq(span.get_input(), self.text));
+        let range = self.map.span_to_original(span);
+        // Stripped bytes are a whole char at the start, so offsets stay on char boundaries.
+        Span::new(self.original, range.start, range.end).unwrap()
+    }
+}
+
+/// Detect the encoding of `bytes`, check that they are UTF-8, and strip the byte order mark.
+pub fn prepare(bytes: &[u8]) -> Result<Prepared<'_>, EncodingError> {
+    let encoding = detect(bytes);
+    if !matches!(encoding, Encoding::Utf8 | Encoding::Utf8Bom) {
+        return Err(EncodingError::Unsupported(encoding));
+    }
+    let original = str::from_utf8(bytes).map_err(|error| EncodingError::InvalidUtf8 {
+        offset: error.valid_up_to(),
+    })?;
+    let (text, map) = OffsetMap::strip_bom(original);
+    Ok(Prepared {
+        original,
+        text,
+        encoding,
+        map,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{detect, prepare, Encoding, EncodingError};
+    use alloc::string::ToString;
+
+    #[test]
+    fn encodings() {
+        let cases: [(&[u8], Encoding); 10] = [
+            (b"", Encoding::Utf8),
+            (b"a", Encoding::Utf8),
+            ("\u{feff}a".as_bytes(), Encoding::Utf8Bom),
+            (b"\xff\xfe", Encoding::Utf16Le),
+            (b"a\0b\0", Encoding::Utf16Le),
+            (b"\xfe\xff\0a", Encoding::Utf16Be),
+            (b"\0a", Encoding::Utf16Be),
+            (b"\xff\xfe\0\0", Encoding::Utf32Le),
+            (b"a\0\0\0", Encoding::Utf32Le),
+            (b"\0\0\0a", Encoding::Utf32Be),
+        ];
+        for (bytes, encoding) in cases {
+            assert_eq!(detect(bytes), encoding, "{bytes:?}");
+        }
+        assert_eq!(detect(b"\0\0\0\0"), Encoding::Utf8);
+    }
+
+    #[test]
+    fn errors() {
+        let error = prepare(b"\xfe\xff\0a").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "input is encoded in UTF-16BE, but only UTF-8 is supported; convert it to UTF-8 first"
+        );
+        let error = prepare(b"ab\xffc").unwrap_err();
+        assert_eq!(error, EncodingError::InvalidUtf8 { offset: 2 });
+        let prepared = prepare(b"ab").unwrap();
+        assert_eq!(prepared.text(), prepared.original());
+        assert!(prepared.offset_map().is_identity());
+    }
+}
@@ -0,0 +1,374 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Parse without building trees, and report what's matched as [`Event`]s.
+//!
+//! With `emit_events` (see [pest_typed_derive](https://docs.rs/pest_typed_derive)),
+//! a module `events` is generated with a function `parse`,
+//! which matches the grammar with a [`Driver`] and sends events to a [`Sink`],
+//! so that large inputs can be aggregated without allocating any node.
+//!
+//! Events of an expression that may be backtracked, such as an alternative of a choice,
+//! are held until it can no longer be backtracked, and dropped if it fails.
+//! If the whole parse fails, events that have been sent are not taken back.
+
+use crate::{
+    error::Error,
+    predefined_node::{peek_spans, stack_slice},
+    reflection::RuleKind,
+    tracker::Tracker,
+    Position, RuleType, Span, Stack,
+};
+use alloc::vec::Vec;
+
+/// Something matched during parsing.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Event<'i, R> {
+    /// Start matching a rule that's not silent.
+    Enter(R, Position<'i>),
+    /// A rule that's not silent is matched.
+    Exit(R, Span<'i>),
+    /// A non-empty terminal, such as a string or a character, is matched outside atomic rules.
+    ///
+    /// Terminals inside atomic rules are covered by the rules,
+    /// and skipped comments and white spaces are not reported.
+    Token(Span<'i>),
+}
+
+/// Receiver of [`Event`]s.
+pub trait Sink<'i, R> {
+    /// Receive an event.
+    fn event(&mut self, event: Event<'i, R>);
+}
+
+impl<'i, R, F: FnMut(Event<'i, R>)> Sink<'i, R> for F {
+    fn event(&mut self, event: Event<'i, R>) {
+        self(event)
+    }
+}
+
+/// State of parsing in the generated module `events`.
+///
+/// Each method matches an expression at the current position, returns whether it's matched,
+/// and moves the position to the end of the match.
+pub struct Driver<'i, 's, R: RuleType, S: ?Sized> {
+    input: Position<'i>,
+    stack: Stack<Span<'i>>,
+    tracker: Tracker<'i, R>,
+    sink: &'s mut S,
+    trivia: fn(&mut Self) -> bool,
+    /// Events that may still be backtracked.
+    pending: Vec<Event<'i, R>>,
+    /// Number of expressions being tried that may be backtracked.
+    attempts: usize,
+    /// Number of predicates, atomic rules and skipped contents being matched, where no event is reported.
+    quiet: usize,
+    atomic: bool,
+}
+
+impl<'i, 's, R: RuleType, S: Sink<'i, R> + ?Sized> Driver<'i, 's, R, S> {
+    /// Start at the start of `input`.
+    ///
+    /// `trivia` matches a comment or a white space once.
+    pub fn new(input: &'i str, sink: &'s mut S, trivia: fn(&mut Self) -> bool) -> Self {
+        let input = Position::from_start(input);
+        Self {
+            input,
+            stack: Stack::new(),
+            tracker: Tracker::new(input),
+            sink,
+            trivia,
+            pending: Vec::new(),
+            attempts: 0,
+            quiet: 0,
+            atomic: false,
+        }
+    }
+    /// Record attempts in `tracker`, such as one in raw mode from [`Tracker::with_raw`].
+    pub fn with_tracker(mut self, tracker: Tracker<'i, R>) -> Self {
+        self.tracker = tracker;
+        self
+    }
+    /// Current position.
+    pub fn position(&self) -> Position<'i> {
+        self.input
+    }
+    fn emit(&mut self, event: Event<'i, R>) {
+        match (self.quiet, self.attempts) {
+            (0, 0) => self.sink.event(event),
+            (0, _) => self.pending.push(event),
+            _ => (),
+        }
+    }
+    fn matched(&mut self, start: Position<'i>, matched: bool) -> bool {
+        if matched && self.input != start {
+            self.emit(Event::Token(start.span(&self.input)));
+        }
+        matched
+    }
+    /// Match a rule whose expression is matched by `f`.
+    pub fn rule(&mut self, rule: R, kind: RuleKind, f: impl FnOnce(&mut Self) -> bool) -> bool {
+        let start = self.input;
+        let atomic = self.atomic;
+        self.atomic = match kind {
+            RuleKind::Atomic | RuleKind::CompoundAtomic => true,
+            RuleKind::NonAtomic => false,
+            RuleKind::Normal | RuleKind::Silent => atomic,
+        };
+        let matched = match kind {
+            RuleKind::Silent => f(self),
+            _ => {
+                if !self.tracker.enter(rule, start) {
+                    self.atomic = atomic;
+                    return false;
+                }
+                let pending = self.pending.len();
+                self.emit(Event::Enter(rule, start));
+                let quiet = usize::from(kind == RuleKind::Atomic);
+                self.quiet += quiet;
+                let matched = f(self);
+                self.quiet -= quiet;
+                self.tracker
+                    .leave(rule, start, matched.then_some(self.input));
+                match matched {
+                    true => self.emit(Event::Exit(rule, start.span(&self.input))),
+                    false => self.pending.truncate(pending),
+                }
+                matched
+            }
+        };
+        self.atomic = atomic;
+        matched
+    }
+    /// Match `f`, or restore the position, the stack and pending events if it fails.
+    pub fn attempt(&mut self, f: impl FnOnce(&mut Self) -> bool) -> bool {
+        let start = self.input;
+        let pending = self.pending.len();
+        self.stack.snapshot();
+        self.attempts += 1;
+        let matched = f(self);
+        self.attempts -= 1;
+        if matched {
+            self.stack.clear_snapshot();
+            if self.attempts == 0 {
+                for event in core::mem::take(&mut self.pending) {
+                    self.sink.event(event);
+                }
+            }
+        } else {
+            self.stack.restore();
+            self.input = start;
+            self.pending.truncate(pending);
+        }
+        matched
+    }
+    /// Match `f` as a predicate, without moving the position, changing the stack or reporting events.
+    fn predicate(&mut self, positive: bool, f: impl FnOnce(&mut Self) -> bool) -> bool {
+        let start = self.input;
+        let original = self.tracker.set_positive(positive);
+        self.stack.snapshot();
+        self.quiet += 1;
+        let matched = f(self);
+        self.quiet -= 1;
+        self.stack.restore();
+        self.tracker.set_positive(original);
+        if matched && !positive {
+            self.tracker.negative_matched(start, self.input);
+        }
+        self.input = start;
+        matched == positive
+    }
+    /// Positive predicate `&e`.
+    pub fn positive(&mut self, f: impl FnOnce(&mut Self) -> bool) -> bool {
+        self.predicate(true, f)
+    }
+    /// Negative predicate `!e`.
+    pub fn negative(&mut self, f: impl FnOnce(&mut Self) -> bool) -> bool {
+        self.predicate(false, f)
+    }
+    /// Match `f` repeatedly for at least `min` times, with skipped contents in between.
+    pub fn repeat(&mut self, min: usize, mut f: impl FnMut(&mut Self) -> bool) -> bool {
+        let mut count = 0;
+        loop {
+            let start = self.input;
+            if !self.attempt(|d| (count == 0 || d.skip()) && f(d)) {
+                break;
+            }
+            count += 1;
+            if self.input == start {
+                break;
+            }
+        }
+        count >= min
+    }
+    /// Skip comments and white spaces outside atomic rules and raw mode. Always matches.
+    pub fn skip(&mut self) -> bool {
+        if self.atomic || self.tracker.is_raw() {
+            return true;
+        }
+        let nested = self.tracker.nested(self.input);
+        let tracker = core::mem::replace(&mut self.tracker, nested);
+        let atomic = core::mem::replace(&mut self.atomic, true);
+        self.quiet += 1;
+        loop {
+            let start = self.input;
+            let trivia = self.trivia;
+            if !self.attempt(trivia) || self.input == start {
+                break;
+            }
+        }
+        self.quiet -= 1;
+        self.atomic = atomic;
+        self.tracker = tracker;
+        true
+    }
+    /// Match a string.
+    pub fn string(&mut self, s: &'static str) -> bool {
+        let start = self.input;
+        let matched = self.input.match_string(s);
+        if !matched {
+            self.tracker.expect_literal(start, s, false);
+        }
+        self.matched(start, matched)
+    }
+    /// Match a string case insensitively.
+    pub fn insensitive(&mut self, s: &'static str) -> bool {
+        let start = self.input;
+        let matched = self.input.match_insensitive(s);
+        if !matched {
+            self.tracker.expect_literal(start, s, true);
+        }
+        self.matched(start, matched)
+    }
+    /// Match a character in `min..=max`.
+    pub fn range(&mut self, min: char, max: char) -> bool {
+        let start = self.input;
+        let matched = self.input.match_range(min..max);
+        self.matched(start, matched)
+    }
+    /// Match a character satisfying `f`.
+    pub fn char_by(&mut self, f: impl FnOnce(char) -> bool) -> bool {
+        let start = self.input;
+        let matched = self.input.match_char_by(f);
+        self.matched(start, matched)
+    }
+    /// `ANY`.
+    pub fn any(&mut self) -> bool {
+        let start = self.input;
+        let matched = self.input.skip(1);
+        self.matched(start, matched)
+    }
+    /// `SOI`.
+    pub fn soi(&mut self) -> bool {
+        self.input.at_start()
+    }
+    /// `EOI`.
+    pub fn eoi(&mut self) -> bool {
+        self.input.at_end()
+    }
+    /// `NEWLINE`.
+    pub fn newline(&mut self) -> bool {
+        let start = self.input;
+        let matched = self.input.match_string("\r\n")
+            || self.input.match_string("\n")
+            || self.input.match_string("\r");
+        self.matched(start, matched)
+    }
+    /// `PEEK`, or `POP` if `pop`.
+    pub fn peek(&mut self, pop: bool) -> bool {
+        let start = self.input;
+        let top = match pop {
+            true => self.stack.pop(),
+            false => self.stack.peek().copied(),
+        };
+        let matched = match top {
+            Some(span) => self.input.match_string(span.as_str()),
+            None => {
+                self.tracker.empty_stack(start);
+                false
+            }
+        };
+        self.matched(start, matched)
+    }
+    /// `PEEK_ALL`, or `POP_ALL` if `pop`.
+    pub fn peek_all(&mut self, pop: bool) -> bool {
+        let start = self.input;
+        let spans = self.stack[0..self.stack.len()].iter().rev();
+        let matched = match peek_spans(start, spans, &mut self.tracker) {
+            Ok((next, _)) => {
+                self.input = next;
+                if pop {
+                    while self.stack.pop().is_some() {}
+                }
+                true
+            }
+            Err(()) => false,
+        };
+        self.matched(start, matched)
+    }
+    /// `DROP`.
+    pub fn drop_top(&mut self) -> bool {
+        match self.stack.pop() {
+            Some(_) => true,
+            None => {
+                self.tracker.empty_stack(self.input);
+                false
+            }
+        }
+    }
+    /// `PEEK[start..end]`.
+    pub fn peek_slice(&mut self, start: i32, end: Option<i32>) -> bool {
+        let begin = self.input;
+        let matched = match stack_slice(begin, start, end, &self.stack, &mut self.tracker) {
+            Ok(spans) => match peek_spans(begin, spans, &mut self.tracker) {
+                Ok((next, _)) => {
+                    self.input = next;
+                    true
+                }
+                Err(()) => false,
+            },
+            Err(()) => false,
+        };
+        self.matched(begin, matched)
+    }
+    /// `(!(s1 | s2 | ...) ~ ANY)*`.
+    pub fn skip_until(&mut self, strings: &[&str]) -> bool {
+        let start = self.input;
+        let matched = self.input.skip_until(strings);
+        self.matched(start, matched)
+    }
+    /// `PUSH(e)`, where `e` is matched by `f`.
+    pub fn push(&mut self, f: impl FnOnce(&mut Self) -> bool) -> bool {
+        let start = self.input;
+        let matched = f(self);
+        if matched {
+            self.stack.push(start.span(&self.input));
+        }
+        matched
+    }
+    /// Finish parsing with the result of the outermost rule, which requires the end of input after it.
+    pub fn finish(mut self, matched: bool, atomic: bool, eoi: R) -> Result<(), Error<R>> {
+        if !matched {
+            return Err(self.tracker.collect());
+        }
+        if !atomic {
+            self.skip();
+        }
+        let end = self.input;
+        if !self.tracker.enter(eoi, end) {
+            return Err(self.tracker.collect());
+        }
+        let matched = end.at_end();
+        self.tracker.leave(eoi, end, matched.then_some(end));
+        match matched {
+            true => Ok(()),
+            false => Err(self.tracker.collect()),
+        }
+    }
+}
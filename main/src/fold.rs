@@ -0,0 +1,212 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Structural tree transformation.
+//!
+//! [Foldable] rebuilds a typed node from its children.
+//! Rule structs call the corresponding method of the generated `Fold` trait (see `emit_fold` in [pest_typed_derive](https://docs.rs/pest_typed_derive)),
+//! while all the other nodes just pass the folder to their children.
+
+use crate::predefined_node::{
+    AlwaysFail, AtomicRep, Bounded, CharRange, Empty, Insens, Negative, PeekSlice1, PeekSlice2,
+    Positive, Push, RepMin, RepMinMax, Skip, SkipChar, Skipped, Str, ANY, DROP, EOI, NEWLINE, PEEK,
+    PEEK_ALL, POP, POP_ALL, SOI,
+};
+use crate::{
+    allocation::{Allocation, Container},
+    embed::{Delegate, Embed},
+    lazy::Lazy,
+    recovery::Closer,
+    RuleType, StringArrayWrapper, StringWrapper, TypedNode,
+};
+use alloc::boxed::Box;
+
+/// A node which can be rebuilt by a folder `F`.
+pub trait Foldable<F: ?Sized>: Sized {
+    /// Consume the node and return a possibly-modified node.
+    fn fold_with(self, folder: &mut F) -> Self;
+}
+
+macro_rules! impl_leaf {
+    ($( ($($generics:tt)*) $ty:ty ),* $(,)?) => {
+        $(
+            impl<$($generics)* F: ?Sized> Foldable<F> for $ty {
+                #[inline]
+                fn fold_with(self, _folder: &mut F) -> Self {
+                    self
+                }
+            }
+        )*
+    };
+}
+
+impl_leaf!(
+    (T: StringWrapper, ) Str<T>,
+    ('i, T: StringWrapper, ) Insens<'i, T>,
+    ('i, Strings: StringArrayWrapper, ) Skip<'i, Strings>,
+    ('i, const N: usize, ) SkipChar<'i, N>,
+    (const MIN: char, const MAX: char, ) CharRange<MIN, MAX>,
+    (T, ) Negative<T>,
+    () ANY,
+    ('i, ) SOI<'i>,
+    ('i, ) EOI<'i>,
+    () NEWLINE,
+    ('i, ) PEEK_ALL<'i>,
+    ('i, ) PEEK<'i>,
+    () DROP,
+    ('i, ) POP<'i>,
+    ('i, ) POP_ALL<'i>,
+    ('i, ) AlwaysFail<'i>,
+    ('i, ) Empty<'i>,
+    ('i, R, T, D: Delegate<'i>, ) Embed<'i, R, T, D>,
+    ('i, T, ) Closer<'i, T>,
+    ('i, const START: i32, const END: i32, ) PeekSlice2<'i, START, END>,
+    ('i, const START: i32, ) PeekSlice1<'i, START>,
+);
+
+impl<F: ?Sized, T: Foldable<F>> Foldable<F> for Option<T> {
+    #[inline]
+    fn fold_with(self, folder: &mut F) -> Self {
+        self.map(|inner| inner.fold_with(folder))
+    }
+}
+
+impl<F: ?Sized, T: Foldable<F>> Foldable<F> for Box<T> {
+    #[inline]
+    fn fold_with(self, folder: &mut F) -> Self {
+        Box::new((*self).fold_with(folder))
+    }
+}
+
+impl<F: ?Sized, T1: Foldable<F>, T2: Foldable<F>> Foldable<F> for (T1, T2) {
+    #[inline]
+    fn fold_with(self, folder: &mut F) -> Self {
+        (self.0.fold_with(folder), self.1.fold_with(folder))
+    }
+}
+
+impl<F: ?Sized, T: Foldable<F>, const N: usize> Foldable<F> for [T; N] {
+    #[inline]
+    fn fold_with(self, folder: &mut F) -> Self {
+        self.map(|inner| inner.fold_with(folder))
+    }
+}
+
+/// Skipped contents are kept as is.
+impl<F: ?Sized, T: Foldable<F>, IGNORED, const SKIP: usize> Foldable<F>
+    for Skipped<T, IGNORED, SKIP>
+{
+    #[inline]
+    fn fold_with(self, folder: &mut F) -> Self {
+        Self {
+            skipped: self.skipped,
+            matched: self.matched.fold_with(folder),
+        }
+    }
+}
+
+impl<
+        F: ?Sized,
+        T: Foldable<F>,
+        End,
+        Skip,
+        const SKIP: usize,
+        const START: bool,
+        const END: bool,
+    > Foldable<F> for Bounded<T, End, Skip, SKIP, START, END>
+{
+    #[inline]
+    fn fold_with(self, folder: &mut F) -> Self {
+        Self::from(self.content.fold_with(folder))
+    }
+}
+
+impl<F: ?Sized, N: Foldable<F>> Foldable<F> for Positive<N> {
+    #[inline]
+    fn fold_with(self, folder: &mut F) -> Self {
+        Self::from(self.content.fold_with(folder))
+    }
+}
+
+impl<F: ?Sized, T: Foldable<F>> Foldable<F> for Push<T> {
+    #[inline]
+    fn fold_with(self, folder: &mut F) -> Self {
+        Self::from(self.content.fold_with(folder))
+    }
+}
+
+/// Builds the subtree if not yet.
+impl<'i, F: ?Sized, R: RuleType, T: TypedNode<'i, R> + Foldable<F>> Foldable<F> for Lazy<'i, R, T> {
+    #[inline]
+    fn fold_with(self, folder: &mut F) -> Self {
+        let span = self.span();
+        Self::new(span, self.into_inner().fold_with(folder))
+    }
+}
+
+impl<F: ?Sized, T: Foldable<F>, A: Allocation> Foldable<F> for AtomicRep<T, A> {
+    #[inline]
+    fn fold_with(self, folder: &mut F) -> Self {
+        let mut content: A::Vec<T> = Container::new();
+        for inner in self.content {
+            content.push(inner.fold_with(folder));
+        }
+        Self { content }
+    }
+}
+
+impl<F: ?Sized, T: Foldable<F>, const MIN: usize, A: Allocation> Foldable<F> for RepMin<T, MIN, A> {
+    #[inline]
+    fn fold_with(self, folder: &mut F) -> Self {
+        let mut content: A::Vec<T> = Container::new();
+        for inner in self.content {
+            content.push(inner.fold_with(folder));
+        }
+        Self { content }
+    }
+}
+
+impl<F: ?Sized, T: Foldable<F>, const MIN: usize, const MAX: usize, A: Allocation> Foldable<F>
+    for RepMinMax<T, MIN, MAX, A>
+{
+    #[inline]
+    fn fold_with(self, folder: &mut F) -> Self {
+        let mut content: A::Vec<T> = Container::new();
+        for inner in self.content {
+            content.push(inner.fold_with(folder));
+        }
+        Self { content }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Foldable;
+    use crate::predefined_node::{CharRange, RepMin, Skipped};
+    use alloc::vec;
+
+    struct Upper;
+    impl Foldable<Upper> for char {
+        fn fold_with(self, _folder: &mut Upper) -> Self {
+            self.to_ascii_uppercase()
+        }
+    }
+
+    #[test]
+    fn containers() {
+        let rep = RepMin::<Skipped<char, (), 0>, 0> {
+            content: vec![Skipped::new('a'), Skipped::new('b')],
+        };
+        let rep = rep.fold_with(&mut Upper);
+        assert_eq!(rep.iter_matched().collect::<alloc::string::String>(), "AB");
+
+        let opt = Some(('x', [CharRange::<'a', 'z'> { content: 'y' }])).fold_with(&mut Upper);
+        assert_eq!(opt, Some(('X', [CharRange { content: 'y' }])));
+    }
+}
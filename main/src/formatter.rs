@@ -1,14 +1,26 @@
-use crate::{Position, Span};
-use alloc::{format, string::String, vec::Vec};
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Options to display [`Span`]s and [`Position`]s as snippets,
+//! with [`Span::display`] and [`Position::display`].
+
+use crate::{Indexing, Position, Span};
+use alloc::{borrow::Cow, format, string::String, string::ToString, vec::Vec};
 use core::{fmt, marker::PhantomData};
-use unicode_width::UnicodeWidthStr;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
 struct Pos {
     line: usize,
     col: usize,
 }
 
-/// Visualize white spaces and control characters.
+/// Visualize a white space or control character, and keep other characters.
 ///
 /// ␀␁␂␃␄␅␆␇␈␉␊␋␌␍␎␏
 /// ␐␑␒␓␔␕␖␗␘␙␚␛␜␝␞␟
@@ -16,48 +28,183 @@ struct Pos {
 /// ␡
 /// \r ␍
 /// \n ␊
-fn visualize_ws_and_cntrl(line: &str) -> String {
-    line.chars()
-        .map(|c| match c {
-            '\u{0}' => '␀',
-            '\u{1}' => '␁',
-            '\u{2}' => '␂',
-            '\u{3}' => '␃',
-            '\u{4}' => '␄',
-            '\u{5}' => '␅',
-            '\u{6}' => '␆',
-            '\u{7}' => '␇',
-            '\u{8}' => '␈',
-            '\u{9}' => '␉',
-            '\u{a}' => '␊',
-            '\u{b}' => '␋',
-            '\u{c}' => '␌',
-            '\u{d}' => '␍',
-            '\u{e}' => '␎',
-            '\u{f}' => '␏',
-            '\u{10}' => '␐',
-            '\u{11}' => '␑',
-            '\u{12}' => '␒',
-            '\u{13}' => '␓',
-            '\u{14}' => '␔',
-            '\u{15}' => '␕',
-            '\u{16}' => '␖',
-            '\u{17}' => '␗',
-            '\u{18}' => '␘',
-            '\u{19}' => '␙',
-            '\u{1a}' => '␚',
-            '\u{1b}' => '␛',
-            '\u{1c}' => '␜',
-            '\u{1d}' => '␝',
-            '\u{1e}' => '␞',
-            '\u{1f}' => '␟',
-            /*
-            '\u{20}' => '␠',
-            */
-            '\u{7f}' => '␡',
-            _ => c,
-        })
-        .collect()
+///
+/// See [`Visualization::glyph`].
+pub fn visualize_char(c: char) -> char {
+    match c {
+        '\u{0}' => '␀',
+        '\u{1}' => '␁',
+        '\u{2}' => '␂',
+        '\u{3}' => '␃',
+        '\u{4}' => '␄',
+        '\u{5}' => '␅',
+        '\u{6}' => '␆',
+        '\u{7}' => '␇',
+        '\u{8}' => '␈',
+        '\u{9}' => '␉',
+        '\u{a}' => '␊',
+        '\u{b}' => '␋',
+        '\u{c}' => '␌',
+        '\u{d}' => '␍',
+        '\u{e}' => '␎',
+        '\u{f}' => '␏',
+        '\u{10}' => '␐',
+        '\u{11}' => '␑',
+        '\u{12}' => '␒',
+        '\u{13}' => '␓',
+        '\u{14}' => '␔',
+        '\u{15}' => '␕',
+        '\u{16}' => '␖',
+        '\u{17}' => '␗',
+        '\u{18}' => '␘',
+        '\u{19}' => '␙',
+        '\u{1a}' => '␚',
+        '\u{1b}' => '␛',
+        '\u{1c}' => '␜',
+        '\u{1d}' => '␝',
+        '\u{1e}' => '␞',
+        '\u{1f}' => '␟',
+        /*
+        '\u{20}' => '␠',
+        */
+        '\u{7f}' => '␡',
+        _ => c,
+    }
+}
+
+/// How line terminators and invisible characters in snippets are displayed.
+#[derive(Clone, Copy, Debug)]
+pub struct Visualization {
+    /// Treat `\r` before `\n` as part of the line terminator, so that it is not displayed on its own.
+    pub crlf_as_terminator: bool,
+    /// Replace white spaces and control characters with [`Visualization::glyph`].
+    ///
+    /// If disabled, line terminators are not displayed and other characters are written as they are.
+    pub visualize: bool,
+    /// Replacement of each character. Defaults to [`visualize_char`].
+    pub glyph: fn(char) -> char,
+}
+
+impl Default for Visualization {
+    fn default() -> Self {
+        Self {
+            crlf_as_terminator: false,
+            visualize: true,
+            glyph: visualize_char,
+        }
+    }
+}
+
+impl Visualization {
+    /// Split a line into its content and the displayed part of its terminator.
+    fn split<'s>(&self, line: &'s str) -> (&'s str, &'s str) {
+        let (content, terminator) = match line.strip_suffix('\n') {
+            Some(rest) if self.crlf_as_terminator && rest.ends_with('\r') => {
+                (&rest[..rest.len() - 1], "\n")
+            }
+            Some(rest) => (rest, "\n"),
+            None => (line, ""),
+        };
+        (content, if self.visualize { terminator } else { "" })
+    }
+    /// Displayed part of a line, before replacing characters.
+    fn shown<'s>(&self, line: &'s str) -> Cow<'s, str> {
+        let (content, terminator) = self.split(line);
+        let skipped = line.len() - content.len() - terminator.len();
+        if terminator.is_empty() || skipped == 0 {
+            Cow::Borrowed(&line[..content.len() + terminator.len()])
+        } else {
+            Cow::Owned(format!("{}{}", content, terminator))
+        }
+    }
+    /// Column in [`Visualization::shown`] of byte column `col` in `line`.
+    fn col(&self, line: &str, col: usize) -> usize {
+        let (content, terminator) = self.split(line);
+        if col <= content.len() {
+            col
+        } else {
+            let skipped = line.len() - content.len() - terminator.len();
+            let skipped = if terminator.is_empty() { 0 } else { skipped };
+            (content.len() + (col - content.len()).saturating_sub(skipped))
+                .min(content.len() + terminator.len())
+        }
+    }
+    /// Replace characters.
+    fn render(&self, s: &str) -> String {
+        if self.visualize {
+            s.chars().map(self.glyph).collect()
+        } else {
+            s.to_string()
+        }
+    }
+    /// Display a whole line.
+    fn line(&self, line: &str) -> String {
+        self.render(&self.shown(line))
+    }
+}
+
+/// Longest prefix of `s` no wider than `width`.
+fn prefix_within(s: &str, width: usize) -> &str {
+    let mut total = 0;
+    for (i, c) in s.char_indices() {
+        total += c.width_cjk().unwrap_or(0);
+        if total > width {
+            return &s[..i];
+        }
+    }
+    s
+}
+
+/// Longest suffix of `s` no wider than `width`.
+fn suffix_within(s: &str, width: usize) -> &str {
+    let mut total = 0;
+    for (i, c) in s.char_indices().rev() {
+        total += c.width_cjk().unwrap_or(0);
+        if total > width {
+            return &s[i + c.len_utf8()..];
+        }
+    }
+    s
+}
+
+/// Truncate a line split at the highlighted part, so that it's no wider than `max`.
+///
+/// The highlighted part is kept, and the rest of the width is shared by both sides of it.
+/// Truncated text is replaced with `…`, whose width is counted the same way as markers are placed.
+fn fit(former: String, middle: String, latter: String, max: usize) -> (String, String, String) {
+    let (wf, wm, wl) = (former.width_cjk(), middle.width_cjk(), latter.width_cjk());
+    let ellipsis = "…".width_cjk();
+    if wf + wm + wl <= max {
+        return (former, middle, latter);
+    }
+    if wm >= max {
+        let former = if wf == 0 { "" } else { "…" };
+        let width = max.saturating_sub(former.width_cjk() + ellipsis);
+        let middle = format!("{}…", prefix_within(&middle, width));
+        return (former.to_string(), middle, String::new());
+    }
+    let rem = max - wm;
+    let (left, right) = if wf <= rem / 2 {
+        (wf, rem - wf)
+    } else if wl <= rem - rem / 2 {
+        (rem - wl, wl)
+    } else {
+        (rem / 2, rem - rem / 2)
+    };
+    let former = if left >= wf {
+        former
+    } else {
+        format!("…{}", suffix_within(&former, left.saturating_sub(ellipsis)))
+    };
+    let latter = if right >= wl {
+        latter
+    } else {
+        format!(
+            "{}…",
+            prefix_within(&latter, right.saturating_sub(ellipsis))
+        )
+    };
+    (former, middle, latter)
 }
 
 struct Partition2<'i> {
@@ -68,12 +215,23 @@ struct Partition2<'i> {
     _p: PhantomData<&'i str>,
 }
 impl<'i> Partition2<'i> {
-    fn new<'s: 'i>(line: usize, s: &'s str, col_start: usize, col_end: usize) -> Self {
+    fn new(
+        line: usize,
+        s: &str,
+        col_start: usize,
+        col_end: usize,
+        visualization: &Visualization,
+    ) -> Self {
+        let (col_start, col_end) = (
+            visualization.col(s, col_start),
+            visualization.col(s, col_end),
+        );
+        let s = visualization.shown(s);
         let (former, latter) = s.split_at(col_end);
         let (former, middle) = former.split_at(col_start);
-        let former = visualize_ws_and_cntrl(former);
-        let middle = visualize_ws_and_cntrl(middle);
-        let latter = visualize_ws_and_cntrl(latter);
+        let former = visualization.render(former);
+        let middle = visualization.render(middle);
+        let latter = visualization.render(latter);
         let _p = PhantomData;
         Self {
             line,
@@ -83,6 +241,20 @@ impl<'i> Partition2<'i> {
             _p,
         }
     }
+    fn fit(self, max: Option<usize>) -> Self {
+        match max {
+            Some(max) => {
+                let (former, middle, latter) = fit(self.former, self.middle, self.latter, max);
+                Self {
+                    former,
+                    middle,
+                    latter,
+                    ..self
+                }
+            }
+            None => self,
+        }
+    }
 }
 
 struct Partition<'i> {
@@ -92,10 +264,12 @@ struct Partition<'i> {
     _p: PhantomData<&'i str>,
 }
 impl<'i> Partition<'i> {
-    fn new<'s: 'i>(line: usize, s: &'s str, col: usize) -> Self {
+    fn new(line: usize, s: &str, col: usize, visualization: &Visualization) -> Self {
+        let col = visualization.col(s, col);
+        let s = visualization.shown(s);
         let (former, latter) = s.split_at(col);
-        let former = visualize_ws_and_cntrl(former);
-        let latter = visualize_ws_and_cntrl(latter);
+        let former = visualization.render(former);
+        let latter = visualization.render(latter);
         let _p = PhantomData;
         Self {
             line,
@@ -104,26 +278,353 @@ impl<'i> Partition<'i> {
             _p,
         }
     }
+    fn fit(self, max: Option<usize>) -> Self {
+        match max {
+            Some(max) => {
+                let (former, _, latter) = fit(self.former, String::new(), self.latter, max);
+                Self {
+                    former,
+                    latter,
+                    ..self
+                }
+            }
+            None => self,
+        }
+    }
+}
+
+/// Markers drawn under spans in snippets. See [`FormatOption::marker_style`].
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum MarkerStyle {
+    /// `^^^` under primary spans and `---` under secondary ones, like rustc.
+    #[default]
+    Caret,
+    /// `^~~` under primary spans and `~~~` under secondary ones, like Clang.
+    CaretTilde,
+    /// `───` under primary spans and `╌╌╌` under secondary ones,
+    /// with `▲` and `△` pointing at positions, and `▼` at starts of multi-line spans.
+    Underline,
+}
+
+impl MarkerStyle {
+    /// Markers under `width` columns.
+    fn underline(self, primary: bool, width: usize) -> String {
+        let (first, rest) = match (self, primary) {
+            (Self::Caret, true) => ('^', '^'),
+            (Self::Caret, false) => ('-', '-'),
+            (Self::CaretTilde, true) => ('^', '~'),
+            (Self::CaretTilde, false) => ('~', '~'),
+            (Self::Underline, true) => ('─', '─'),
+            (Self::Underline, false) => ('╌', '╌'),
+        };
+        let mut res = String::new();
+        res.extend(
+            core::iter::once(first)
+                .chain(core::iter::repeat(rest))
+                .take(width),
+        );
+        res
+    }
+    /// Marker pointing at a position or an end of a span.
+    fn point(self, primary: bool) -> &'static str {
+        match (self, primary) {
+            (Self::Caret | Self::CaretTilde, true) => "^",
+            (Self::Caret, false) => "-",
+            (Self::CaretTilde, false) => "~",
+            (Self::Underline, true) => "▲",
+            (Self::Underline, false) => "△",
+        }
+    }
+    /// Marker above the start of a multi-line span.
+    fn start(self) -> &'static str {
+        match self {
+            Self::Caret | Self::CaretTilde => "v",
+            Self::Underline => "▼",
+        }
+    }
+}
+
+/// Gutters between line numbers and lines in snippets, and bars along them.
+/// See [`FormatOption::gutter_style`].
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum GutterStyle {
+    /// `|`, with bars drawn with `/`, `|` and `_`.
+    #[default]
+    Ascii,
+    /// `│`, with bars drawn with `╭`, `│`, `╰` and `─`.
+    BoxDrawing,
+}
+
+impl GutterStyle {
+    /// Gutter and vertical bar.
+    fn vertical(self) -> &'static str {
+        match self {
+            Self::Ascii => "|",
+            Self::BoxDrawing => "│",
+        }
+    }
+    /// Start of a bar at the start of a line.
+    fn top(self) -> &'static str {
+        match self {
+            Self::Ascii => "/",
+            Self::BoxDrawing => "╭",
+        }
+    }
+    /// Start of a bar under `width` columns of a line, ending with `point`, and spaces before it.
+    fn top_at(self, width: usize, point: &str) -> (&'static str, String) {
+        match self {
+            Self::Ascii => ("  ", format!("{}{}", "_".repeat(width + 1), point)),
+            Self::BoxDrawing => (" ", format!("╭{}{}", "─".repeat(width + 1), point)),
+        }
+    }
+    /// End of a bar, reaching `width` columns into the line.
+    fn bottom(self, width: usize, point: &str) -> String {
+        match self {
+            Self::Ascii => format!("|{}{}", "_".repeat(width), point),
+            Self::BoxDrawing => format!("╰{}{}", "─".repeat(width), point),
+        }
+    }
+}
+
+/// How a span is annotated in a snippet.
+#[derive(Clone, Copy)]
+pub(crate) struct Annotation<'a> {
+    /// Written before the path, `-->` for primary spans and `:::` for secondary ones.
+    pub arrow: &'a str,
+    /// Path of the source, written in the header if given.
+    pub path: Option<&'a str>,
+    /// Whether the span is primary, marked with `^` rather than `-` by default.
+    pub primary: bool,
+    /// Written after markers.
+    pub label: Option<&'a str>,
+    /// Minimal width of line numbers, so that snippets of a diagnostic are aligned.
+    pub width: usize,
+    /// Write a marker under empty spans.
+    pub mark_empty: bool,
+}
+impl Annotation<'_> {
+    pub const PLAIN: Self = Self {
+        arrow: "-->",
+        path: None,
+        primary: true,
+        label: None,
+        width: 0,
+        mark_empty: false,
+    };
 }
 
 /// Formatter options for [Span](crate::Span).
 pub struct FormatOption<SpanFormatter, MarkerFormatter, NumberFormatter> {
+    /// Writes the text covered by the span.
     pub span_formatter: SpanFormatter,
+    /// Writes markers under the span.
     pub marker_formatter: MarkerFormatter,
+    /// Writes line numbers and gutters.
     pub number_formatter: NumberFormatter,
+    /// How line terminators and invisible characters are displayed.
+    pub visualization: Visualization,
+    /// Connect the start and the end of multi-line spans with bars along the gutter, like rustc.
+    ///
+    /// ```text
+    ///   |
+    /// 1 |   a = {
+    ///   |  _____^
+    /// 2 | |   b
+    /// 3 | | }
+    ///   | |_^ label
+    /// ```
+    pub multi_line_bars: bool,
+    /// Maximal width of displayed lines, not including line numbers and gutters.
+    ///
+    /// Longer lines are truncated around the span with `…`, so that markers stay aligned.
+    pub max_line_width: Option<usize>,
+    /// Numbers of the first line and column, for line numbers and `--> path:line:col` headers.
+    pub indexing: Indexing,
+    /// Markers under spans.
+    ///
+    /// ```text
+    ///   |
+    /// 1 | a = b
+    ///   |     ^~~
+    /// ```
+    pub marker_style: MarkerStyle,
+    /// Gutters and bars of multi-line spans.
+    ///
+    /// ```text
+    ///   │
+    /// 1 │ ╭ a = {
+    /// 2 │ │ }
+    ///   │ ╰─^
+    /// ```
+    pub gutter_style: GutterStyle,
 }
 
-type FmtPtr<Writer> = fn(&str, &mut Writer) -> fmt::Result;
+/// Type of formatters used by default, which write their input as it is.
+pub type FmtPtr<Writer> = fn(&str, &mut Writer) -> fmt::Result;
+fn plain<Writer: fmt::Write>(s: &str, f: &mut Writer) -> fmt::Result {
+    f.write_str(s)
+}
 impl<Writer: fmt::Write> Default for FormatOption<FmtPtr<Writer>, FmtPtr<Writer>, FmtPtr<Writer>> {
     fn default() -> Self {
         Self {
-            span_formatter: |s, f| write!(f, "{s}"),
-            marker_formatter: |m, f| write!(f, "{m}"),
-            number_formatter: |n, f| write!(f, "{n}"),
+            span_formatter: plain,
+            marker_formatter: plain,
+            number_formatter: plain,
+            visualization: Visualization::default(),
+            multi_line_bars: false,
+            max_line_width: None,
+            indexing: Indexing::ONE_BASED,
+            marker_style: MarkerStyle::Caret,
+            gutter_style: GutterStyle::Ascii,
+        }
+    }
+}
+
+impl<Writer: fmt::Write> FormatOption<FmtPtr<Writer>, FmtPtr<Writer>, FmtPtr<Writer>> {
+    /// Build an option, where formatters that are not given write their input as it is.
+    ///
+    /// ```
+    /// # use pest_typed::{formatter::FormatOption, Span};
+    /// let option = FormatOption::builder()
+    ///     .marker(|m: &str, f: &mut String| {
+    ///         f.push_str(&m.replace('^', "~"));
+    ///         Ok(())
+    ///     })
+    ///     .build();
+    /// let mut buf = String::new();
+    /// Span::new("abc", 1, 3).unwrap().display(&mut buf, option).unwrap();
+    /// assert_eq!(buf, "  |\n1 | abc\n  |  ~~\n");
+    /// ```
+    pub fn builder() -> FormatOptionBuilder<FmtPtr<Writer>, FmtPtr<Writer>, FmtPtr<Writer>> {
+        FormatOptionBuilder {
+            option: Self::default(),
         }
     }
 }
 
+/// Builder of [`FormatOption`]. See [`FormatOption::builder`].
+pub struct FormatOptionBuilder<SpanFormatter, MarkerFormatter, NumberFormatter> {
+    option: FormatOption<SpanFormatter, MarkerFormatter, NumberFormatter>,
+}
+
+impl<SF, MF, NF> FormatOptionBuilder<SF, MF, NF> {
+    /// Set [`FormatOption::span_formatter`].
+    pub fn span<F>(self, span_formatter: F) -> FormatOptionBuilder<F, MF, NF> {
+        let FormatOption {
+            marker_formatter,
+            number_formatter,
+            visualization,
+            multi_line_bars,
+            max_line_width,
+            indexing,
+            marker_style,
+            gutter_style,
+            ..
+        } = self.option;
+        FormatOptionBuilder {
+            option: FormatOption {
+                span_formatter,
+                marker_formatter,
+                number_formatter,
+                visualization,
+                multi_line_bars,
+                max_line_width,
+                indexing,
+                marker_style,
+                gutter_style,
+            },
+        }
+    }
+    /// Set [`FormatOption::marker_formatter`].
+    pub fn marker<F>(self, marker_formatter: F) -> FormatOptionBuilder<SF, F, NF> {
+        let FormatOption {
+            span_formatter,
+            number_formatter,
+            visualization,
+            multi_line_bars,
+            max_line_width,
+            indexing,
+            marker_style,
+            gutter_style,
+            ..
+        } = self.option;
+        FormatOptionBuilder {
+            option: FormatOption {
+                span_formatter,
+                marker_formatter,
+                number_formatter,
+                visualization,
+                multi_line_bars,
+                max_line_width,
+                indexing,
+                marker_style,
+                gutter_style,
+            },
+        }
+    }
+    /// Set [`FormatOption::number_formatter`].
+    pub fn number<F>(self, number_formatter: F) -> FormatOptionBuilder<SF, MF, F> {
+        let FormatOption {
+            span_formatter,
+            marker_formatter,
+            visualization,
+            multi_line_bars,
+            max_line_width,
+            indexing,
+            marker_style,
+            gutter_style,
+            ..
+        } = self.option;
+        FormatOptionBuilder {
+            option: FormatOption {
+                span_formatter,
+                marker_formatter,
+                number_formatter,
+                visualization,
+                multi_line_bars,
+                max_line_width,
+                indexing,
+                marker_style,
+                gutter_style,
+            },
+        }
+    }
+    /// Set [`FormatOption::visualization`].
+    pub fn visualization(mut self, visualization: Visualization) -> Self {
+        self.option.visualization = visualization;
+        self
+    }
+    /// Set [`FormatOption::multi_line_bars`].
+    pub fn multi_line_bars(mut self, multi_line_bars: bool) -> Self {
+        self.option.multi_line_bars = multi_line_bars;
+        self
+    }
+    /// Set [`FormatOption::max_line_width`].
+    pub fn max_line_width(mut self, max_line_width: usize) -> Self {
+        self.option.max_line_width = Some(max_line_width);
+        self
+    }
+    /// Set [`FormatOption::indexing`].
+    pub fn indexing(mut self, indexing: Indexing) -> Self {
+        self.option.indexing = indexing;
+        self
+    }
+    /// Set [`FormatOption::marker_style`].
+    pub fn marker_style(mut self, marker_style: MarkerStyle) -> Self {
+        self.option.marker_style = marker_style;
+        self
+    }
+    /// Set [`FormatOption::gutter_style`].
+    pub fn gutter_style(mut self, gutter_style: GutterStyle) -> Self {
+        self.option.gutter_style = gutter_style;
+        self
+    }
+    /// Finish building.
+    pub fn build(self) -> FormatOption<SF, MF, NF> {
+        self.option
+    }
+}
+
 impl<SF, MF, NF> FormatOption<SF, MF, NF> {
     /// Create option with given functions.
     pub fn new<Writer>(span_formatter: SF, marker_formatter: MF, number_formatter: NF) -> Self
@@ -137,6 +638,54 @@ impl<SF, MF, NF> FormatOption<SF, MF, NF> {
             span_formatter,
             marker_formatter,
             number_formatter,
+            visualization: Visualization::default(),
+            multi_line_bars: false,
+            max_line_width: None,
+            indexing: Indexing::ONE_BASED,
+            marker_style: MarkerStyle::Caret,
+            gutter_style: GutterStyle::Ascii,
+        }
+    }
+    /// Set [`FormatOption::visualization`].
+    pub fn with_visualization(mut self, visualization: Visualization) -> Self {
+        self.visualization = visualization;
+        self
+    }
+    /// Set [`FormatOption::multi_line_bars`].
+    pub fn with_multi_line_bars(mut self, multi_line_bars: bool) -> Self {
+        self.multi_line_bars = multi_line_bars;
+        self
+    }
+    /// Set [`FormatOption::max_line_width`].
+    pub fn with_max_line_width(mut self, max_line_width: usize) -> Self {
+        self.max_line_width = Some(max_line_width);
+        self
+    }
+    /// Set [`FormatOption::indexing`].
+    pub fn with_indexing(mut self, indexing: Indexing) -> Self {
+        self.indexing = indexing;
+        self
+    }
+    /// Set [`FormatOption::marker_style`].
+    pub fn with_marker_style(mut self, marker_style: MarkerStyle) -> Self {
+        self.marker_style = marker_style;
+        self
+    }
+    /// Set [`FormatOption::gutter_style`].
+    pub fn with_gutter_style(mut self, gutter_style: GutterStyle) -> Self {
+        self.gutter_style = gutter_style;
+        self
+    }
+    /// Number of the line at zero-based `index`.
+    fn line_number(&self, index: usize) -> usize {
+        index + self.indexing.first_line
+    }
+    /// Display a whole line, truncated to [`FormatOption::max_line_width`].
+    fn covered_line(&self, line: &str) -> String {
+        let line = self.visualization.line(line);
+        match self.max_line_width {
+            Some(max) => fit(String::new(), String::new(), line, max).2,
+            None => line,
         }
     }
     fn ceil_log10(num: usize) -> usize {
@@ -160,26 +709,27 @@ impl<SF, MF, NF> FormatOption<SF, MF, NF> {
         MF: FnMut(&str, &mut Writer) -> fmt::Result,
         NF: FnMut(&str, &mut Writer) -> fmt::Result,
     {
+        let gutter = self.gutter_style;
         let spacing = " ".repeat(index_digit);
         write!(f, "{} ", spacing)?;
-        (self.number_formatter)("|", f)?;
+        (self.number_formatter)(gutter.vertical(), f)?;
         writeln!(f)?;
 
-        let number = format!("{:w$}", line.line + 1, w = index_digit);
+        let number = format!("{:w$}", self.line_number(line.line), w = index_digit);
         (self.number_formatter)(&number, f)?;
         write!(f, " ")?;
-        (self.number_formatter)("|", f)?;
+        (self.number_formatter)(gutter.vertical(), f)?;
         write!(f, " {}{}", line.former, line.latter)?;
         writeln!(f)?;
 
         write!(f, "{} ", spacing)?;
-        (self.number_formatter)("|", f)?;
+        (self.number_formatter)(gutter.vertical(), f)?;
         write!(
             f,
             " {}",
             " ".repeat(UnicodeWidthStr::width_cjk(line.former.as_str())),
         )?;
-        (self.marker_formatter)("^", f)?;
+        (self.marker_formatter)(self.marker_style.point(true), f)?;
         writeln!(f)?;
 
         Ok(())
@@ -189,6 +739,7 @@ impl<SF, MF, NF> FormatOption<SF, MF, NF> {
         f: &mut Writer,
         index_digit: usize,
         line: Partition2<'_>,
+        annotation: Annotation<'_>,
     ) -> fmt::Result
     where
         Writer: fmt::Write,
@@ -196,35 +747,45 @@ impl<SF, MF, NF> FormatOption<SF, MF, NF> {
         MF: FnMut(&str, &mut Writer) -> fmt::Result,
         NF: FnMut(&str, &mut Writer) -> fmt::Result,
     {
+        let gutter = self.gutter_style;
         let spacing = " ".repeat(index_digit);
         write!(f, "{} ", spacing)?;
-        (self.number_formatter)("|", f)?;
+        (self.number_formatter)(gutter.vertical(), f)?;
         writeln!(f)?;
 
-        let number = format!("{:w$}", line.line + 1, w = index_digit);
+        let number = format!("{:w$}", self.line_number(line.line), w = index_digit);
         (self.number_formatter)(&number, f)?;
         write!(f, " ")?;
-        (self.number_formatter)("|", f)?;
+        (self.number_formatter)(gutter.vertical(), f)?;
         write!(f, " {}", line.former)?;
         (self.span_formatter)(&line.middle, f)?;
         write!(f, "{}", line.latter)?;
         writeln!(f)?;
 
         write!(f, "{} ", spacing)?;
-        (self.number_formatter)("|", f)?;
+        (self.number_formatter)(gutter.vertical(), f)?;
         write!(
             f,
             " {}",
             " ".repeat(UnicodeWidthStr::width_cjk(line.former.as_str())),
         )?;
-        (self.marker_formatter)(
-            &"^".repeat(UnicodeWidthStr::width_cjk(line.middle.as_str())),
-            f,
-        )?;
-        writeln!(f)?;
+        let width =
+            UnicodeWidthStr::width_cjk(line.middle.as_str()).max(annotation.mark_empty as usize);
+        let markers = self.marker_style.underline(annotation.primary, width);
+        (self.marker_formatter)(&markers, f)?;
+        Self::display_label(f, annotation)?;
 
         Ok(())
     }
+    fn display_label<Writer: fmt::Write>(
+        f: &mut Writer,
+        annotation: Annotation<'_>,
+    ) -> fmt::Result {
+        match annotation.label {
+            Some(label) => writeln!(f, " {}", label),
+            None => writeln!(f),
+        }
+    }
     fn display_full_covered_snippet<Writer>(
         &mut self,
         f: &mut Writer,
@@ -238,11 +799,16 @@ impl<SF, MF, NF> FormatOption<SF, MF, NF> {
         MF: FnMut(&str, &mut Writer) -> fmt::Result,
         NF: FnMut(&str, &mut Writer) -> fmt::Result,
     {
-        let number = format!("{:w$}", line, w = index_digit);
+        let gutter = self.gutter_style;
+        let number = format!("{:w$}", self.line_number(line), w = index_digit);
         (self.number_formatter)(&number, f)?;
         write!(f, " ")?;
-        (self.number_formatter)("|", f)?;
+        (self.number_formatter)(gutter.vertical(), f)?;
         write!(f, " ")?;
+        if self.multi_line_bars {
+            (self.marker_formatter)(gutter.vertical(), f)?;
+            write!(f, " ")?;
+        }
         (self.span_formatter)(line_content, f)?;
         writeln!(f)?;
         Ok(())
@@ -258,6 +824,7 @@ impl<SF, MF, NF> FormatOption<SF, MF, NF> {
         // 111
         // 101
         inner: (Option<&str>, Option<&str>, bool, Option<&str>),
+        annotation: Annotation<'_>,
     ) -> fmt::Result
     where
         Writer: fmt::Write,
@@ -265,63 +832,111 @@ impl<SF, MF, NF> FormatOption<SF, MF, NF> {
         MF: FnMut(&str, &mut Writer) -> fmt::Result,
         NF: FnMut(&str, &mut Writer) -> fmt::Result,
     {
+        let gutter = self.gutter_style;
         let spacing = " ".repeat(index_digit);
+        let start_width = UnicodeWidthStr::width_cjk(start.former.as_str());
+        let bars = self.multi_line_bars;
         write!(f, "{} ", spacing)?;
-        (self.number_formatter)("|", f)?;
-        write!(
-            f,
-            " {}",
-            " ".repeat(UnicodeWidthStr::width_cjk(start.former.as_str()))
-        )?;
-        (self.marker_formatter)("v", f)?;
+        (self.number_formatter)(gutter.vertical(), f)?;
+        if !bars {
+            write!(f, " {}", " ".repeat(start_width))?;
+            (self.marker_formatter)(self.marker_style.start(), f)?;
+        }
         writeln!(f)?;
 
-        let number = format!("{:w$}", start.line + 1, w = index_digit);
+        let number = format!("{:w$}", self.line_number(start.line), w = index_digit);
         (self.number_formatter)(&number, f)?;
         write!(f, " ")?;
-        (self.number_formatter)("|", f)?;
-        write!(f, " {}", start.former)?;
+        (self.number_formatter)(gutter.vertical(), f)?;
+        write!(f, " ")?;
+        match (bars, start_width) {
+            (false, _) => (),
+            (true, 0) => {
+                (self.marker_formatter)(gutter.top(), f)?;
+                write!(f, " ")?;
+            }
+            (true, _) => write!(f, "  ")?,
+        }
+        write!(f, "{}", start.former)?;
         (self.span_formatter)(&start.latter, f)?;
         writeln!(f)?;
 
+        if bars && start_width > 0 {
+            write!(f, "{} ", spacing)?;
+            (self.number_formatter)(gutter.vertical(), f)?;
+            let (spaces, top) =
+                gutter.top_at(start_width, self.marker_style.point(annotation.primary));
+            write!(f, "{}", spaces)?;
+            (self.marker_formatter)(&top, f)?;
+            writeln!(f)?;
+        }
+
         if let Some(line) = inner.0 {
-            self.display_full_covered_snippet(f, index_digit, start.line + 2, line)?;
+            self.display_full_covered_snippet(f, index_digit, start.line + 1, line)?;
         }
 
         if let Some(line) = inner.1 {
-            self.display_full_covered_snippet(f, index_digit, start.line + 3, line)?;
+            self.display_full_covered_snippet(f, index_digit, start.line + 2, line)?;
         } else if inner.2 {
             write!(f, "{} ", spacing)?;
-            (self.number_formatter)("|", f)?;
-            writeln!(f, " ...")?;
+            (self.number_formatter)(gutter.vertical(), f)?;
+            write!(f, " ")?;
+            if bars {
+                (self.marker_formatter)(gutter.vertical(), f)?;
+                write!(f, " ")?;
+            }
+            writeln!(f, "...")?;
         }
 
         if let Some(line) = inner.3 {
-            self.display_full_covered_snippet(f, index_digit, end.line, line)?;
+            self.display_full_covered_snippet(f, index_digit, end.line - 1, line)?;
         }
 
-        let number = format!("{:w$}", end.line + 1, w = index_digit);
+        let number = format!("{:w$}", self.line_number(end.line), w = index_digit);
         (self.number_formatter)(&number, f)?;
         write!(f, " ")?;
-        (self.number_formatter)("|", f)?;
+        (self.number_formatter)(gutter.vertical(), f)?;
         write!(f, " ")?;
+        if bars {
+            (self.marker_formatter)(gutter.vertical(), f)?;
+            write!(f, " ")?;
+        }
         (self.span_formatter)(&end.former, f)?;
         writeln!(f, "{}", end.latter)?;
 
+        let end_width = UnicodeWidthStr::width_cjk(end.former.as_str());
         write!(f, "{} ", spacing)?;
-        (self.number_formatter)("|", f)?;
-        write!(
-            f,
-            " {}",
-            " ".repeat(UnicodeWidthStr::width_cjk(end.former.as_str()).saturating_sub(1))
-        )?;
-        (self.marker_formatter)("^", f)?;
-        writeln!(f)?;
+        (self.number_formatter)(gutter.vertical(), f)?;
+        write!(f, " ")?;
+        if bars {
+            let point = self.marker_style.point(annotation.primary);
+            (self.marker_formatter)(&gutter.bottom(end_width, point), f)?;
+        } else {
+            write!(f, "{}", " ".repeat(end_width.saturating_sub(1)))?;
+            (self.marker_formatter)(self.marker_style.point(annotation.primary), f)?;
+        }
+        Self::display_label(f, annotation)?;
 
         Ok(())
     }
     #[allow(clippy::needless_lifetimes)]
     pub(crate) fn display_span<'i, Writer>(self, span: &Span<'i>, f: &mut Writer) -> fmt::Result
+    where
+        Writer: fmt::Write,
+        SF: FnMut(&str, &mut Writer) -> fmt::Result,
+        MF: FnMut(&str, &mut Writer) -> fmt::Result,
+        NF: FnMut(&str, &mut Writer) -> fmt::Result,
+    {
+        self.display_span_in(span, Annotation::PLAIN, f)
+    }
+    /// Display span with a `--> path:line:col` header if a path is given.
+    #[allow(clippy::needless_lifetimes)]
+    pub(crate) fn display_span_in<'i, Writer>(
+        mut self,
+        span: &Span<'i>,
+        annotation: Annotation<'_>,
+        f: &mut Writer,
+    ) -> fmt::Result
     where
         Writer: fmt::Write,
         SF: FnMut(&str, &mut Writer) -> fmt::Result,
@@ -334,7 +949,8 @@ impl<SF, MF, NF> FormatOption<SF, MF, NF> {
         let input = Span::new(span.get_input(), 0, span.get_input().len()).unwrap();
         let mut iter = input.lines().enumerate().peekable();
         while let Some((index, line)) = iter.peek() {
-            if pos + line.len() >= span.start() {
+            let line_end = pos + line.len();
+            if line_end > span.start() || (line_end == span.start() && !line.ends_with('\n')) {
                 start = Some(Pos {
                     line: *index,
                     col: span.start() - pos,
@@ -361,29 +977,44 @@ impl<SF, MF, NF> FormatOption<SF, MF, NF> {
             .skip(start.line)
             .take(end.line - start.line + 1)
             .peekable();
-        let index_digit = Self::ceil_log10(end.line + 1);
+        let index_digit = Self::ceil_log10(self.line_number(end.line)).max(annotation.width);
+        if let Some(path) = annotation.path {
+            let (line, col) = span.start_pos().line_col_with(self.indexing);
+            write!(f, "{}", " ".repeat(index_digit))?;
+            (self.number_formatter)(annotation.arrow, f)?;
+            writeln!(f, " {}:{}:{}", path, line, col)?;
+        }
         if start.line == end.line {
             let cur_line = lines.next().unwrap();
-            let line = Partition2::new(start.line, cur_line, start.col, end.col);
-            self.display_snippet_single_line(f, index_digit, line)?;
+            let line = Partition2::new(
+                start.line,
+                cur_line,
+                start.col,
+                end.col,
+                &self.visualization,
+            )
+            .fit(self.max_line_width);
+            self.display_snippet_single_line(f, index_digit, line, annotation)?;
         } else {
             let lines: Vec<_> = lines.collect();
             let start_line = lines.first().unwrap();
             let end_line = lines.last().unwrap();
-            let start = Partition::new(start.line, start_line, start.col);
-            let end = Partition::new(end.line, end_line, end.col);
+            let start = Partition::new(start.line, start_line, start.col, &self.visualization)
+                .fit(self.max_line_width);
+            let end = Partition::new(end.line, end_line, end.col, &self.visualization)
+                .fit(self.max_line_width);
             let inner_first = if lines.len() >= 3 {
-                Some(visualize_ws_and_cntrl(lines[1]))
+                Some(self.covered_line(lines[1]))
             } else {
                 None
             };
             let inner_mid = match lines.len() {
                 6.. => (None, true),
-                5 => (Some(visualize_ws_and_cntrl(lines[2])), false),
+                5 => (Some(self.covered_line(lines[2])), false),
                 _ => (None, false),
             };
             let inner_last = if lines.len() >= 4 {
-                Some(visualize_ws_and_cntrl(lines[lines.len() - 2]))
+                Some(self.covered_line(lines[lines.len() - 2]))
             } else {
                 None
             };
@@ -393,7 +1024,7 @@ impl<SF, MF, NF> FormatOption<SF, MF, NF> {
                 inner_mid.1,
                 inner_last.as_deref(),
             );
-            self.display_snippet_multi_line(f, index_digit, start, end, inner)?;
+            self.display_snippet_multi_line(f, index_digit, start, end, inner, annotation)?;
         }
         Ok(())
     }
@@ -417,7 +1048,7 @@ impl<SF, MF, NF> FormatOption<SF, MF, NF> {
                 let l = *index;
                 let c = position.pos() - pos;
                 let index_digit = Self::ceil_log10(l + 1);
-                let line = Partition::new(l, line, c);
+                let line = Partition::new(l, line, c, &self.visualization).fit(self.max_line_width);
                 self.display_snippet_single_pos(f, index_digit, line)?;
                 break;
             }
@@ -432,12 +1063,13 @@ impl<SF, MF, NF> FormatOption<SF, MF, NF> {
 mod control {
     #[test]
     fn visualize_ws_and_cntrl() {
-        let s = super::visualize_ws_and_cntrl(
-            "\
+        let s: alloc::string::String = "\
             \u{00}\u{01}\u{02}\u{03}\u{04}\u{05}\u{06}\u{07}\u{08}\u{09}\u{0a}\u{0b}\u{0c}\u{0d}\u{0e}\u{0f}\
             \u{10}\u{11}\u{12}\u{13}\u{14}\u{15}\u{16}\u{17}\u{18}\u{19}\u{1a}\u{1b}\u{1c}\u{1d}\u{1e}\u{1f}\
-            \u{20}\u{7f}",
-        );
+            \u{20}\u{7f}"
+            .chars()
+            .map(super::visualize_char)
+            .collect();
         assert_eq!(s, "␀␁␂␃␄␅␆␇␈␉␊␋␌␍␎␏␐␑␒␓␔␕␖␗␘␙␚␛␜␝␞␟ ␡");
     }
 }
@@ -493,6 +1125,66 @@ mod span {
     use alloc::string::{String, ToString};
     use core::fmt::Write;
 
+    fn display_with(span: Span<'_>, visualization: Visualization) -> String {
+        let mut buf = String::new();
+        span.display(
+            &mut buf,
+            FormatOption::default().with_visualization(visualization),
+        )
+        .unwrap();
+        buf
+    }
+
+    #[test]
+    fn builder() {
+        let option = FormatOption::builder()
+            .span(|s: &str, f: &mut String| write!(f, "[{}]", s))
+            .number(|n: &str, f: &mut String| write!(f, "{}", n.replace('|', ":")))
+            .visualization(Visualization {
+                visualize: false,
+                ..Visualization::default()
+            })
+            .build();
+        let mut buf = String::new();
+        Span::new("ab\n", 1, 2)
+            .unwrap()
+            .display(&mut buf, option)
+            .unwrap();
+        assert_eq!(buf, "  :\n1 : a[b]\n  :  ^\n");
+    }
+
+    #[test]
+    fn display_span_crlf() {
+        let span = Span::new("ab\r\ncd\r\n", 1, 5).unwrap();
+        assert_eq!(
+            display_with(span, Visualization::default()),
+            "  |  v\n1 | ab␍␊\n2 | cd␍␊\n  | ^\n",
+        );
+        let crlf = Visualization {
+            crlf_as_terminator: true,
+            ..Visualization::default()
+        };
+        assert_eq!(
+            display_with(span, crlf),
+            "  |  v\n1 | ab␊\n2 | cd␊\n  | ^\n",
+        );
+        // The carriage return is not marked.
+        let span = Span::new("ab\r\ncd", 2, 3).unwrap();
+        assert_eq!(display_with(span, crlf), "  |\n1 | ab␊\n  |   \n");
+        let plain = Visualization {
+            crlf_as_terminator: true,
+            visualize: false,
+            ..Visualization::default()
+        };
+        let span = Span::new("a\tb\r\ncd", 1, 3).unwrap();
+        assert_eq!(display_with(span, plain), "  |\n1 | a\tb\n  |  ^^\n");
+        let glyphs = Visualization {
+            glyph: |c| if c == '\t' { '>' } else { visualize_char(c) },
+            ..Visualization::default()
+        };
+        assert_eq!(display_with(span, glyphs), "  |\n1 | a>b␍␊\n  |  ^^\n");
+    }
+
     #[test]
     fn display_span_first_line() {
         let msg = Span::new("123\n456\n789\n", 1, 2).unwrap().to_string();
@@ -660,6 +1352,125 @@ mod span {
         );
     }
 
+    #[test]
+    fn display_span_bars() {
+        let span = Span::new("123\n456\n789\nabc\ndef\nghi\n", 2, 23).unwrap();
+        let mut msg = String::new();
+        span.display_with_label(
+            &mut msg,
+            FormatOption::default().with_multi_line_bars(true),
+            "label",
+        )
+        .unwrap();
+        assert_eq!(
+            msg,
+            "  \
+  |
+1 |   123␊
+  |  ___^
+2 | | 456␊
+  | | ...
+5 | | def␊
+6 | | ghi␊
+  | |___^ label
+"
+        );
+        // Labels are also written after plain markers.
+        let mut msg = String::new();
+        span.display_with_label(&mut msg, FormatOption::default(), "label")
+            .unwrap();
+        assert!(msg.ends_with("6 | ghi␊\n  |   ^ label\n"));
+    }
+
+    #[test]
+    fn display_span_zero_based() {
+        let span = Span::new("123\n456\n789\n", 2, 11).unwrap();
+        let mut msg = String::new();
+        span.display(
+            &mut msg,
+            FormatOption::default().with_indexing(Indexing::ZERO_BASED),
+        )
+        .unwrap();
+        assert_eq!(msg, "  |   v\n0 | 123␊\n1 | 456␊\n2 | 789␊\n  |   ^\n");
+    }
+
+    #[test]
+    fn display_span_styles() {
+        let display = |start, end, option: FormatOption<_, _, _>| {
+            let mut buf = String::new();
+            let span = Span::new("a = {\n  b\n}", start, end).unwrap();
+            span.display(&mut buf, option.with_multi_line_bars(true))
+                .unwrap();
+            buf
+        };
+        let tilde = FormatOption::default().with_marker_style(MarkerStyle::CaretTilde);
+        assert_eq!(display(0, 5, tilde), "  |\n1 | a = {␊\n  | ^~~~~\n");
+        let underline = FormatOption::default()
+            .with_marker_style(MarkerStyle::Underline)
+            .with_gutter_style(GutterStyle::BoxDrawing);
+        assert_eq!(display(0, 5, underline), "  │\n1 │ a = {␊\n  │ ─────\n");
+        let boxed = FormatOption::default().with_gutter_style(GutterStyle::BoxDrawing);
+        assert_eq!(
+            display(4, 11, boxed),
+            "  │\n1 │   a = {␊\n  │ ╭─────^\n2 │ │   b␊\n3 │ │ }\n  │ ╰─^\n",
+        );
+        let boxed = FormatOption::default().with_gutter_style(GutterStyle::BoxDrawing);
+        assert_eq!(
+            display(0, 11, boxed),
+            "  │\n1 │ ╭ a = {␊\n2 │ │   b␊\n3 │ │ }\n  │ ╰─^\n",
+        );
+        assert_eq!(
+            display(4, 11, FormatOption::default()),
+            "  |\n1 |   a = {␊\n  |  _____^\n2 | |   b␊\n3 | | }\n  | |_^\n",
+        );
+
+        let mut buf = String::new();
+        let option = FormatOption::builder()
+            .marker_style(MarkerStyle::Underline)
+            .build();
+        Position::new("ab", 1)
+            .unwrap()
+            .display(&mut buf, option)
+            .unwrap();
+        assert_eq!(buf, "  |\n1 | ab\n  |  ▲\n");
+    }
+
+    #[test]
+    fn display_span_truncated() {
+        let input = "0123456789abcdefghij\nklmnopqrstuvwxyz\n";
+        let display = |start, end, max| {
+            let mut buf = String::new();
+            Span::new(input, start, end)
+                .unwrap()
+                .display(&mut buf, FormatOption::default().with_max_line_width(max))
+                .unwrap();
+            buf
+        };
+        assert_eq!(display(10, 12, 8), "  |\n1 | …9abc…\n  |    ^^\n");
+        // Context is given to the other side if one side is short.
+        assert_eq!(display(1, 2, 6), "  |\n1 | 0123…\n  |  ^\n");
+        assert_eq!(display(18, 19, 6), "  |\n1 | …hij␊\n  |    ^\n");
+        // Spans wider than the limit are truncated themselves.
+        assert_eq!(display(2, 12, 5), "  |\n1 | …2…\n  |   ^^^\n");
+        // Multi-line spans and lines covered by them.
+        let input = "0123456789\n0123456789\n0123456789\n";
+        let mut buf = String::new();
+        Span::new(input, 5, 27)
+            .unwrap()
+            .display(&mut buf, FormatOption::default().with_max_line_width(8))
+            .unwrap();
+        assert_eq!(
+            buf,
+            "  |     v\n1 | …3456…\n2 | 012345…\n3 | …3456…\n  |    ^\n",
+        );
+        let mut buf = String::new();
+        Position::new(input, 5)
+            .unwrap()
+            .display(&mut buf, FormatOption::default().with_max_line_width(8))
+            .unwrap();
+        assert_eq!(buf, "  |\n1 | …3456…\n  |     ^\n");
+    }
+
     #[test]
     fn display_span_unicode() {
         let msg = Span::new("ß\n∆\n中\n", 2, 10).unwrap().to_string();
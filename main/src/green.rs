@@ -0,0 +1,401 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Red-green trees, which are immutable, cheap to clone, and independent of the input.
+//!
+//! A [`GreenNode`] is a rule and its children: nested green nodes,
+//! and texts between them that no inner rule matches.
+//! Green nodes own their texts and only know their lengths, not their offsets,
+//! so identical subtrees are shared with [`GreenCache`], even across edits of the input.
+//! Inputs with a lot of repetition, such as generated code and data files,
+//! keep one allocation for each distinct subtree, as [`GreenCache::sharing`] measures.
+//!
+//! A [`SyntaxNode`] is a red node, that is, a green node with its offset and parent,
+//! created from the root on demand.
+//! Typed wrappers of syntax nodes implement [`AstNode`], and are generated with `emit_syntax`.
+//!
+//! ```
+//! # use pest_typed::green::{GreenCache, GreenChild, SyntaxNode};
+//! # use std::sync::Arc;
+//! let mut cache = GreenCache::new();
+//! let a = cache.leaf(0u8, "a");
+//! let text = GreenChild::Text(cache.text(", "));
+//! let list = cache.node(1, vec![GreenChild::Node(a.clone()), text, GreenChild::Node(a.clone())]);
+//! assert!(Arc::ptr_eq(&a, &cache.leaf(0, "a")));
+//!
+//! let root = SyntaxNode::new_root(list);
+//! assert_eq!(root.text(), "a, a");
+//! let second = root.children().nth(1).unwrap();
+//! assert_eq!(second.text_range(), 3..4);
+//! assert_eq!(second.parent(), Some(root));
+//! ```
+
+use crate::iterators::Pair;
+use alloc::{
+    collections::{btree_map::Entry, BTreeMap, BTreeSet},
+    string::String,
+    sync::Arc,
+    vec,
+    vec::Vec,
+};
+use core::{fmt, hash, mem::size_of, ops::Range};
+use pest::RuleType;
+
+/// A child of a [`GreenNode`].
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum GreenChild<R> {
+    /// An inner node.
+    Node(Arc<GreenNode<R>>),
+    /// Text that no inner node matches, such as literals and skipped whitespaces.
+    Text(Arc<str>),
+}
+
+impl<R> GreenChild<R> {
+    /// Length of the text, in bytes.
+    pub fn text_len(&self) -> usize {
+        match self {
+            Self::Node(node) => node.text_len,
+            Self::Text(text) => text.len(),
+        }
+    }
+}
+
+/// A node without its position. See [module-level documentation](self).
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct GreenNode<R> {
+    rule: R,
+    text_len: usize,
+    children: Vec<GreenChild<R>>,
+}
+
+impl<R: Copy> GreenNode<R> {
+    /// Create a node. Use [`GreenCache::node`] to share identical nodes.
+    pub fn new(rule: R, children: Vec<GreenChild<R>>) -> Self {
+        let text_len = children.iter().map(GreenChild::text_len).sum();
+        Self {
+            rule,
+            text_len,
+            children,
+        }
+    }
+    /// Rule of the node.
+    pub fn rule(&self) -> R {
+        self.rule
+    }
+    /// Length of the text, in bytes.
+    pub fn text_len(&self) -> usize {
+        self.text_len
+    }
+    /// Children, in source order.
+    pub fn children(&self) -> &[GreenChild<R>] {
+        &self.children
+    }
+    /// Text of the node.
+    pub fn text(&self) -> String {
+        let mut buf = String::with_capacity(self.text_len);
+        self.write_text(&mut buf);
+        buf
+    }
+    fn write_text(&self, buf: &mut String) {
+        for child in &self.children {
+            match child {
+                GreenChild::Node(node) => node.write_text(buf),
+                GreenChild::Text(text) => buf.push_str(text),
+            }
+        }
+    }
+}
+
+/// Key of a child in [`GreenCache`], which is the address of the interned node or text.
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+enum ChildKey {
+    Node(usize),
+    Text(usize),
+}
+
+/// How much a [`GreenCache`] has shared.
+///
+/// Bytes are those of nodes, their children and texts, and the reference counts of their [`Arc`]s,
+/// excluding bookkeeping of the allocator and of the cache itself.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Sharing {
+    /// Nodes requested.
+    pub nodes: usize,
+    /// Distinct nodes allocated.
+    pub unique_nodes: usize,
+    /// Texts requested.
+    pub texts: usize,
+    /// Distinct texts allocated.
+    pub unique_texts: usize,
+    /// Bytes allocated for distinct nodes and texts.
+    pub allocated_bytes: usize,
+    /// Bytes that would have been allocated for copies of them without sharing.
+    pub saved_bytes: usize,
+}
+
+/// Interned green nodes and texts, so that identical ones are shared.
+///
+/// Nodes built with the same cache are the same [`Arc`] if they are identical,
+/// so that subtrees not affected by an edit can be compared with [`Arc::ptr_eq`] after parsing again.
+/// Everything interned is kept alive until the cache is dropped.
+///
+/// A cache from [`GreenCache::leaves`] only interns texts and nodes without inner nodes,
+/// which is where most repetition is, so that it doesn't keep a key for every node of large trees.
+pub struct GreenCache<R> {
+    nodes: BTreeMap<(R, Vec<ChildKey>), Arc<GreenNode<R>>>,
+    texts: BTreeSet<Arc<str>>,
+    leaves_only: bool,
+    sharing: Sharing,
+}
+
+impl<R: RuleType> Default for GreenCache<R> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<R: RuleType> GreenCache<R> {
+    /// Create an empty cache.
+    pub fn new() -> Self {
+        Self {
+            nodes: BTreeMap::new(),
+            texts: BTreeSet::new(),
+            leaves_only: false,
+            sharing: Sharing::default(),
+        }
+    }
+    /// Create an empty cache that only interns texts and nodes without inner nodes.
+    pub fn leaves() -> Self {
+        Self {
+            leaves_only: true,
+            ..Self::new()
+        }
+    }
+    /// How much the cache has shared since it was created.
+    pub fn sharing(&self) -> Sharing {
+        self.sharing
+    }
+    /// Intern a text.
+    pub fn text(&mut self, text: &str) -> Arc<str> {
+        let size = 2 * size_of::<usize>() + text.len();
+        self.sharing.texts += 1;
+        match self.texts.get(text) {
+            Some(text) => {
+                self.sharing.saved_bytes += size;
+                text.clone()
+            }
+            None => {
+                self.sharing.unique_texts += 1;
+                self.sharing.allocated_bytes += size;
+                let text: Arc<str> = Arc::from(text);
+                self.texts.insert(text.clone());
+                text
+            }
+        }
+    }
+    /// Intern a node.
+    ///
+    /// Nodes are identical if their children are the same [`Arc`]s,
+    /// so child nodes should be interned by the same cache to be shared.
+    /// Nodes with inner nodes are allocated without being interned by a cache from [`GreenCache::leaves`].
+    pub fn node(&mut self, rule: R, children: Vec<GreenChild<R>>) -> Arc<GreenNode<R>> {
+        let children: Vec<_> = children
+            .into_iter()
+            .map(|child| match child {
+                GreenChild::Text(text) => GreenChild::Text(self.text(&text)),
+                node => node,
+            })
+            .collect();
+        let size = 2 * size_of::<usize>()
+            + size_of::<GreenNode<R>>()
+            + children.len() * size_of::<GreenChild<R>>();
+        self.sharing.nodes += 1;
+        let leaf = children
+            .iter()
+            .all(|child| matches!(child, GreenChild::Text(_)));
+        if self.leaves_only && !leaf {
+            self.sharing.unique_nodes += 1;
+            self.sharing.allocated_bytes += size;
+            return Arc::new(GreenNode::new(rule, children));
+        }
+        let key = children
+            .iter()
+            .map(|child| match child {
+                GreenChild::Node(node) => ChildKey::Node(Arc::as_ptr(node) as usize),
+                GreenChild::Text(text) => ChildKey::Text(Arc::as_ptr(text) as *const u8 as usize),
+            })
+            .collect();
+        let sharing = &mut self.sharing;
+        match self.nodes.entry((rule, key)) {
+            Entry::Occupied(entry) => {
+                sharing.saved_bytes += size;
+                entry.get().clone()
+            }
+            Entry::Vacant(entry) => {
+                sharing.unique_nodes += 1;
+                sharing.allocated_bytes += size;
+                entry
+                    .insert(Arc::new(GreenNode::new(rule, children)))
+                    .clone()
+            }
+        }
+    }
+    /// Intern a node without inner nodes.
+    pub fn leaf(&mut self, rule: R, text: &str) -> Arc<GreenNode<R>> {
+        let text = self.text(text);
+        self.node(rule, vec![GreenChild::Text(text)])
+    }
+    /// Build the green tree of a typed tree.
+    ///
+    /// Texts between inner pairs are kept as [`GreenChild::Text`], so the text of the tree is the span of `pair`.
+    pub fn build<'i: 'n, 'n>(&mut self, pair: &'n dyn Pair<'i, 'n, R>) -> Arc<GreenNode<R>> {
+        let span = pair.span();
+        let input = span.get_input();
+        let mut children = Vec::new();
+        let mut pos = span.start();
+        for inner in pair.inner() {
+            let inner_span = inner.span();
+            if inner_span.start() > pos {
+                let text = self.text(&input[pos..inner_span.start()]);
+                children.push(GreenChild::Text(text));
+            }
+            children.push(GreenChild::Node(self.build(inner)));
+            pos = pos.max(inner_span.end());
+        }
+        if span.end() > pos || children.is_empty() {
+            let text = self.text(&input[pos..span.end()]);
+            children.push(GreenChild::Text(text));
+        }
+        self.node(pair.rule(), children)
+    }
+}
+
+struct NodeData<R> {
+    green: Arc<GreenNode<R>>,
+    offset: usize,
+    parent: Option<SyntaxNode<R>>,
+}
+
+/// A green node with its offset and parent. See [module-level documentation](self).
+///
+/// Cloning is cheap, and two syntax nodes are equal if they are the same green node at the same offset.
+pub struct SyntaxNode<R>(Arc<NodeData<R>>);
+
+impl<R> Clone for SyntaxNode<R> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<R: RuleType> SyntaxNode<R> {
+    /// Create the root of a tree.
+    pub fn new_root(green: Arc<GreenNode<R>>) -> Self {
+        Self(Arc::new(NodeData {
+            green,
+            offset: 0,
+            parent: None,
+        }))
+    }
+    /// The green node.
+    pub fn green(&self) -> &Arc<GreenNode<R>> {
+        &self.0.green
+    }
+    /// Rule of the node.
+    pub fn rule(&self) -> R {
+        self.0.green.rule
+    }
+    /// Range of byte offsets of the node, relative to the root.
+    pub fn text_range(&self) -> Range<usize> {
+        self.0.offset..self.0.offset + self.0.green.text_len
+    }
+    /// Text of the node.
+    pub fn text(&self) -> String {
+        self.0.green.text()
+    }
+    /// Parent of the node, or [`None`] for the root.
+    pub fn parent(&self) -> Option<Self> {
+        self.0.parent.clone()
+    }
+    /// Ancestors of the node, from its parent to the root.
+    pub fn ancestors(&self) -> impl Iterator<Item = Self> {
+        core::iter::successors(self.parent(), Self::parent)
+    }
+    /// Inner nodes, in source order.
+    pub fn children(&self) -> vec::IntoIter<Self> {
+        let mut offset = self.0.offset;
+        let mut res = Vec::new();
+        for child in &self.0.green.children {
+            if let GreenChild::Node(green) = child {
+                res.push(Self(Arc::new(NodeData {
+                    green: green.clone(),
+                    offset,
+                    parent: Some(self.clone()),
+                })));
+            }
+            offset += child.text_len();
+        }
+        res.into_iter()
+    }
+    /// The node and all nodes inside it, in pre-order.
+    pub fn descendants(&self) -> vec::IntoIter<Self> {
+        let mut res = Vec::new();
+        let mut stack = vec![self.clone()];
+        while let Some(node) = stack.pop() {
+            let children: Vec<_> = node.children().collect();
+            res.push(node);
+            stack.extend(children.into_iter().rev());
+        }
+        res.into_iter()
+    }
+    /// Innermost node whose range contains byte offset `offset`.
+    pub fn covering_node(&self, offset: usize) -> Option<Self> {
+        if !self.text_range().contains(&offset) {
+            return None;
+        }
+        let mut current = self.clone();
+        while let Some(child) = current
+            .children()
+            .find(|child| child.text_range().contains(&offset))
+        {
+            current = child;
+        }
+        Some(current)
+    }
+}
+
+impl<R> PartialEq for SyntaxNode<R> {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0.green, &other.0.green) && self.0.offset == other.0.offset
+    }
+}
+
+impl<R> Eq for SyntaxNode<R> {}
+
+impl<R> hash::Hash for SyntaxNode<R> {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        Arc::as_ptr(&self.0.green).hash(state);
+        self.0.offset.hash(state);
+    }
+}
+
+impl<R: RuleType> fmt::Debug for SyntaxNode<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}@{:?}", self.rule(), self.text_range())
+    }
+}
+
+/// A typed wrapper of [`SyntaxNode`] of a rule.
+pub trait AstNode<R: RuleType>: Sized {
+    /// Rule of the wrapped nodes.
+    const RULE: R;
+    /// Wrap `node` if it's of [`AstNode::RULE`].
+    fn cast(node: SyntaxNode<R>) -> Option<Self>;
+    /// The wrapped node.
+    fn syntax(&self) -> &SyntaxNode<R>;
+}
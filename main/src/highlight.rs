@@ -0,0 +1,60 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Syntax highlighting with classes of rules.
+//!
+//! Write `#[highlight = "class"]` in the line before a rule in the grammar,
+//! and the generated `Rule` implements [`Highlight`].
+//! Then [`PairTree::highlights`](crate::iterators::PairTree::highlights) collects classified ranges,
+//! ready to be mapped into TextMate scopes,
+//! or encoded as semantic tokens with `lsp::semantic_tokens` with feature `lsp`.
+
+use crate::{iterators::Pair, Span};
+use alloc::{vec, vec::Vec};
+use pest::RuleType;
+
+/// Highlight classes of rules.
+pub trait Highlight: RuleType {
+    /// Class of the rule, such as `"keyword"`.
+    fn highlight(self) -> Option<&'static str>;
+}
+
+/// A span with a highlight class.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct HighlightRange<'i> {
+    /// Highlighted span.
+    pub span: Span<'i>,
+    /// Class of the rule that matched the span.
+    pub class: &'static str,
+}
+
+/// Collect ranges of nodes with highlight classes in source order.
+///
+/// Inner nodes of a classified node are not visited, so ranges never overlap.
+pub(crate) fn highlights<'i: 'n, 'n, R: Highlight + 'n>(
+    root: &'n dyn Pair<'i, 'n, R>,
+) -> Vec<HighlightRange<'i>> {
+    let mut res = vec![];
+    let mut stack = vec![vec![root].into_iter()];
+    while let Some(pairs) = stack.last_mut() {
+        match pairs.next() {
+            Some(pair) => match pair.rule().highlight() {
+                Some(class) => res.push(HighlightRange {
+                    span: pair.span(),
+                    class,
+                }),
+                None => stack.push(pair.inner()),
+            },
+            None => {
+                stack.pop();
+            }
+        }
+    }
+    res
+}
@@ -10,11 +10,16 @@
 //! Simulates [`pest::iterators`].
 
 use crate::{
+    allocation::Allocation,
+    highlight::{highlights, Highlight, HighlightRange},
     predefined_node::{
-        AlwaysFail, AtomicRep, CharRange, Empty, Insens, Negative, PeekSlice1, PeekSlice2,
-        Positive, Push, RepMin, RepMinMax, Skip, Skipped, Str, ANY, DROP, NEWLINE, PEEK, PEEK_ALL,
-        POP, POP_ALL, SOI,
+        AlwaysFail, AtomicRep, Bounded, CharRange, Empty, Insens, Negative, PeekSlice1, PeekSlice2,
+        Positive, Push, RepMin, RepMinMax, SameLine, Skip, Skipped, Str, ANY, DROP, NEWLINE, PEEK,
+        PEEK_ALL, POP, POP_ALL, SOI,
     },
+    selector::{Selector, SelectorError},
+    tokens::{Tokens, Trivia},
+    tree_index::TreeIndex,
     typed_node::RuleStruct,
     StringArrayWrapper, StringWrapper, TypedNode,
 };
@@ -26,7 +31,7 @@ use core::{
 use pest::RuleType;
 
 /// Token.
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
 pub struct Token<R: RuleType> {
     /// Rule.
     pub rule: R,
@@ -177,6 +182,45 @@ pub trait PairTree<'i: 'n, 'n, R: RuleType + 'n>: Pair<'i, 'n, R> + Sized {
         self.write_tree_to(&mut buf)?;
         Ok(buf)
     }
+
+    /// Select nodes matching `selector`, such as `"function > parameter ident"`.
+    ///
+    /// See [`crate::selector`] for the syntax.
+    fn select(&'n self, selector: &str) -> Result<Vec<&'n dyn Pair<'i, 'n, R>>, SelectorError> {
+        Ok(Selector::parse(selector)?.select(self))
+    }
+
+    /// Iterate on nodes without inner nodes in source order, as `(rule, span)`.
+    ///
+    /// See [`crate::tokens`].
+    fn tokens(&'n self, trivia: Trivia) -> Tokens<'i, 'n, R> {
+        Tokens::new(self, trivia)
+    }
+
+    /// Index nodes of the tree, with their IDs and parents.
+    ///
+    /// See [`crate::tree_index`].
+    fn tree_index(&'n self) -> TreeIndex<'i, 'n, R> {
+        TreeIndex::new(self)
+    }
+
+    /// Convert the tree into a JSON value keyed by names of rules.
+    ///
+    /// See [`crate::json::to_value`].
+    #[cfg(feature = "serde")]
+    fn to_value(&'n self) -> serde_json::Value {
+        crate::json::to_value(self)
+    }
+
+    /// Collect ranges of nodes whose rules have highlight classes.
+    ///
+    /// See [`crate::highlight`].
+    fn highlights(&'n self) -> Vec<HighlightRange<'i>>
+    where
+        R: Highlight,
+    {
+        highlights(self)
+    }
 }
 
 impl<'i: 'n, 'n, R: RuleType + 'n, T: RuleStruct<'i, R> + Pairs<'i, 'n, R> + Pair<'i, 'n, R>>
@@ -220,9 +264,32 @@ macro_rules! impl_forward_inner {
 
 impl_empty!(Str<T>, T: StringWrapper);
 impl_empty!(Insens<'i, T>, T: StringWrapper);
-impl_empty!(PeekSlice2<START, END>, const START: i32, const END: i32);
-impl_empty!(PeekSlice1<START>, const START: i32);
+impl_empty!(PeekSlice2<'i, START, END>, const START: i32, const END: i32);
+impl_empty!(PeekSlice1<'i, START>, const START: i32);
 impl_forward_inner!(Push);
+impl_forward_inner!(SameLine);
+impl<
+        'i: 'n,
+        'n,
+        R: RuleType + 'n,
+        T: Pairs<'i, 'n, R>,
+        End,
+        Skip,
+        const SKIP: usize,
+        const START: bool,
+        const END: bool,
+    > Pairs<'i, 'n, R> for Bounded<T, End, Skip, SKIP, START, END>
+{
+    type Iter = T::Iter;
+    type IntoIter = T::IntoIter;
+
+    fn iter_pairs(&'n self) -> Self::Iter {
+        self.content.iter_pairs()
+    }
+    fn into_iter_pairs(self) -> Self::IntoIter {
+        self.content.into_iter_pairs()
+    }
+}
 impl_empty!(Skip<'i, Strings>, Strings: StringArrayWrapper);
 impl_empty!(CharRange<MIN, MAX>, const MIN: char, const MAX: char);
 impl_empty!(Positive<T>, T: TypedNode<'i, R>);
@@ -315,7 +382,8 @@ macro_rules! impl_with_vec {
                 R: RuleType + 'n,
                 T: Pairs<'i, 'n, R> + 'n,
                 $(const $args: $t, )*
-            > Pairs<'i, 'n, R> for $name<T, $($args, )*>
+                A: Allocation,
+            > Pairs<'i, 'n, R> for $name<T, $($args, )* A>
         {
             type Iter = FlatMap<
                 core::slice::Iter<'n, T>,
@@ -323,7 +391,7 @@ macro_rules! impl_with_vec {
                 fn(&'n T) -> T::Iter,
             >;
             type IntoIter = FlatMap<
-                vec::IntoIter<T>,
+                <A::Vec<T> as IntoIterator>::IntoIter,
                 T::IntoIter,
                 fn(T) -> T::IntoIter,
             >;
@@ -378,7 +446,7 @@ macro_rules! impl_with_lifetime {
 }
 
 impl_without_lifetime!(ANY);
-impl_without_lifetime!(SOI);
+impl_with_lifetime!(SOI);
 impl_without_lifetime!(NEWLINE);
 impl_with_lifetime!(PEEK);
 impl_with_lifetime!(PEEK_ALL);
@@ -0,0 +1,236 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! JSON output of parsing results, such as for JavaScript in WebAssembly.
+//!
+//! This crate builds on `wasm32-unknown-unknown` (with or without feature `std`),
+//! and with feature `wasm-bindgen`, module `wasm` passes these to JavaScript.
+//!
+//! Every position is an object with the byte `offset`, the `utf16` offset (for indexing JavaScript strings),
+//! and the 1-based `line` and `column` (in UTF-16 code units).
+//!
+//! With feature `serde`, [`to_value`] converts a typed tree into a [`serde_json::Value`]
+//! shaped by the rules instead, for tools that consume JSON without knowing positions.
+
+#[cfg(feature = "serde")]
+use crate::iterators::Pair;
+use crate::{iterators::Token, Position};
+use alloc::string::String;
+use core::fmt::{self, Write};
+use pest::{
+    error::{Error, ErrorVariant, InputLocation},
+    RuleType,
+};
+
+struct Str<'s>(&'s str);
+
+impl fmt::Display for Str<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_char('"')?;
+        for c in self.0.chars() {
+            match c {
+                '"' => f.write_str("\\\"")?,
+                '\\' => f.write_str("\\\\")?,
+                '\n' => f.write_str("\\n")?,
+                '\r' => f.write_str("\\r")?,
+                '\t' => f.write_str("\\t")?,
+                c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+                c => f.write_char(c)?,
+            }
+        }
+        f.write_char('"')
+    }
+}
+
+fn write_pos(out: &mut impl Write, input: &str, offset: usize) -> fmt::Result {
+    let offset = offset.min(input.len());
+    let (line, column) = Position::new(input, offset).map_or((1, 1), |pos| pos.line_col_utf16());
+    let utf16 = input.get(..offset).map_or(0, |s| s.encode_utf16().count());
+    write!(
+        out,
+        r#"{{"offset":{},"utf16":{},"line":{},"column":{}}}"#,
+        offset, utf16, line, column
+    )
+}
+
+fn write_rules<R: RuleType>(out: &mut impl Write, rules: &[R]) -> fmt::Result {
+    out.write_char('[')?;
+    for (i, rule) in rules.iter().enumerate() {
+        if i > 0 {
+            out.write_char(',')?;
+        }
+        write!(out, "{}", Str(&alloc::format!("{:?}", rule)))?;
+    }
+    out.write_char(']')
+}
+
+/// Write a token tree produced from `input` as JSON.
+///
+/// Each node is `{"rule": ..., "start": ..., "end": ..., "children": [...]}`.
+pub fn write_token<R: RuleType>(
+    out: &mut impl Write,
+    token: &Token<R>,
+    input: &str,
+) -> fmt::Result {
+    write!(
+        out,
+        r#"{{"rule":{},"start":"#,
+        Str(&alloc::format!("{:?}", token.rule))
+    )?;
+    write_pos(out, input, token.start)?;
+    out.write_str(r#","end":"#)?;
+    write_pos(out, input, token.end)?;
+    out.write_str(r#","children":["#)?;
+    for (i, child) in token.children.iter().enumerate() {
+        if i > 0 {
+            out.write_char(',')?;
+        }
+        write_token(out, child, input)?;
+    }
+    out.write_str("]}")
+}
+
+/// Write an error produced when parsing `input` as JSON.
+///
+/// The object is `{"message": ..., "start": ..., "end": ..., "expected": [...], "unexpected": [...], "display": ...}`,
+/// where `display` is the formatted error, and `expected` and `unexpected` are names of rules.
+pub fn write_error<R: RuleType>(
+    out: &mut impl Write,
+    error: &Error<R>,
+    input: &str,
+) -> fmt::Result {
+    let (start, end) = match error.location {
+        InputLocation::Pos(pos) => (pos, pos),
+        InputLocation::Span(span) => span,
+    };
+    write!(
+        out,
+        r#"{{"message":{},"start":"#,
+        Str(&error.variant.message())
+    )?;
+    write_pos(out, input, start)?;
+    out.write_str(r#","end":"#)?;
+    write_pos(out, input, end)?;
+    let (positives, negatives): (&[R], &[R]) = match &error.variant {
+        ErrorVariant::ParsingError {
+            positives,
+            negatives,
+        } => (positives, negatives),
+        ErrorVariant::CustomError { .. } => (&[], &[]),
+    };
+    out.write_str(r#","expected":"#)?;
+    write_rules(out, positives)?;
+    out.write_str(r#","unexpected":"#)?;
+    write_rules(out, negatives)?;
+    write!(out, r#","display":{}}}"#, Str(&alloc::format!("{}", error)))
+}
+
+/// Token tree as JSON. See [`write_token`].
+pub fn token_to_json<R: RuleType>(token: &Token<R>, input: &str) -> String {
+    let mut res = String::new();
+    write_token(&mut res, token, input).unwrap();
+    res
+}
+
+/// Error as JSON. See [`write_error`].
+pub fn error_to_json<R: RuleType>(error: &Error<R>, input: &str) -> String {
+    let mut res = String::new();
+    write_error(&mut res, error, input).unwrap();
+    res
+}
+
+/// `{"ok": token}` or `{"error": error}`. See [`write_token`] and [`write_error`].
+pub fn result_to_json<R: RuleType>(result: &Result<Token<R>, Error<R>>, input: &str) -> String {
+    let mut res = String::new();
+    match result {
+        Ok(token) => {
+            res.push_str(r#"{"ok":"#);
+            write_token(&mut res, token, input).unwrap();
+        }
+        Err(error) => {
+            res.push_str(r#"{"error":"#);
+            write_error(&mut res, error, input).unwrap();
+        }
+    }
+    res.push('}');
+    res
+}
+
+/// Convert a tree into a JSON value keyed by names of rules.
+///
+/// A node without inner nodes is `{"rule": "matched text"}`,
+/// and other nodes are `{"rule": [inner nodes...]}`.
+#[cfg(feature = "serde")]
+pub fn to_value<'i: 'n, 'n, R: RuleType + 'n>(pair: &'n dyn Pair<'i, 'n, R>) -> serde_json::Value {
+    use serde_json::{Map, Value};
+    let mut inner = pair.inner().peekable();
+    let value = match inner.peek() {
+        None => Value::String(String::from(pair.span().as_str())),
+        Some(_) => Value::Array(inner.map(to_value).collect()),
+    };
+    let mut map = Map::new();
+    map.insert(alloc::format!("{:?}", pair.rule()), value);
+    Value::Object(map)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{error_to_json, result_to_json, token_to_json};
+    use crate::iterators::Token;
+    use alloc::vec;
+    use pest::error::{Error, ErrorVariant};
+
+    #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+    enum Rule {
+        A,
+        B,
+    }
+
+    #[test]
+    fn token() {
+        let input = "💖\"x";
+        let token = Token {
+            rule: Rule::A,
+            start: 0,
+            end: 6,
+            children: vec![Token {
+                rule: Rule::B,
+                start: 4,
+                end: 5,
+                children: vec![],
+            }],
+        };
+        assert_eq!(
+            token_to_json(&token, input),
+            concat!(
+                r#"{"rule":"A","start":{"offset":0,"utf16":0,"line":1,"column":1},"#,
+                r#""end":{"offset":6,"utf16":4,"line":1,"column":5},"children":["#,
+                r#"{"rule":"B","start":{"offset":4,"utf16":2,"line":1,"column":3},"#,
+                r#""end":{"offset":5,"utf16":3,"line":1,"column":4},"children":[]}]}"#,
+            )
+        );
+    }
+
+    #[test]
+    fn error() {
+        let input = "a\n\"b";
+        let error = Error::new_from_pos(
+            ErrorVariant::ParsingError {
+                positives: vec![Rule::A],
+                negatives: vec![Rule::A, Rule::B],
+            },
+            pest::Position::new(input, 3).unwrap(),
+        );
+        let json = error_to_json(&error, input);
+        assert!(json.starts_with(r#"{"message":"unexpected A or B; expected A","start":{"offset":3,"utf16":3,"line":2,"column":2},"#));
+        assert!(json.contains(r#""expected":["A"],"unexpected":["A","B"],"display":" --> 2:2\n"#));
+        assert!(json.contains(r#"2 | \"b\n"#));
+        assert!(result_to_json(&Err(error), input).starts_with(r#"{"error":{"message""#));
+    }
+}
@@ -0,0 +1,65 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Keywords of a grammar, for completion engines and highlighters.
+//!
+//! Write `#[keyword]` in the line before a rule in the grammar,
+//! or highlight it as `#[highlight = "keyword"]`,
+//! and string literals in the rule are keywords of it.
+//! The generated `Rule` then implements [`Keywords`] with all of them, in the order they are written.
+
+use pest::RuleType;
+
+/// A string literal in a keyword rule.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Keyword<R> {
+    /// The literal, as written in the grammar.
+    pub text: &'static str,
+    /// Rule that the literal is in.
+    pub rule: R,
+    /// Whether the literal is case-insensitive, such as `^"select"`.
+    pub insensitive: bool,
+}
+
+impl<R> Keyword<R> {
+    /// Whether `word` is the keyword, ignoring ASCII cases if the keyword is case-insensitive.
+    pub fn matches(&self, word: &str) -> bool {
+        match self.insensitive {
+            true => self.text.eq_ignore_ascii_case(word),
+            false => self.text == word,
+        }
+    }
+}
+
+/// Keywords of rules.
+pub trait Keywords: RuleType + 'static {
+    /// All keywords, in the order they are written in the grammar.
+    const KEYWORDS: &'static [Keyword<Self>];
+
+    /// All keywords. See [`Keywords::KEYWORDS`].
+    fn keywords() -> &'static [Keyword<Self>] {
+        Self::KEYWORDS
+    }
+    /// Find the first keyword that `word` is.
+    fn keyword(word: &str) -> Option<&'static Keyword<Self>> {
+        Self::KEYWORDS.iter().find(|keyword| keyword.matches(word))
+    }
+    /// Keywords starting with `prefix`, ignoring ASCII cases if they are case-insensitive.
+    fn keywords_with_prefix(prefix: &str) -> impl Iterator<Item = &'static Keyword<Self>> + '_ {
+        Self::KEYWORDS.iter().filter(move |keyword| {
+            keyword.text.len() >= prefix.len()
+                && keyword.text.is_char_boundary(prefix.len())
+                && Keyword {
+                    text: &keyword.text[..prefix.len()],
+                    ..**keyword
+                }
+                .matches(prefix)
+        })
+    }
+}
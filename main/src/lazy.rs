@@ -0,0 +1,171 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Nodes whose inner typed subtrees are only built on first access.
+//!
+//! The generator wraps inner expressions of rules marked with `#[lazy]` in [`Lazy`].
+//! The expression is still matched when parsing, to find where it ends,
+//! but its typed nodes are dropped and only the span is kept.
+//! On first access, the span is parsed again and the subtree is cached,
+//! in the same raw mode and language version as the original parse.
+//!
+//! With feature `std`, the cache is a [`std::sync::OnceLock`],
+//! so that lazy nodes are [`Sync`] and can be shared by threads if their subtrees are.
+//! Without it, the cache is a [`core::cell::OnceCell`], and lazy nodes are not [`Sync`].
+
+use crate::{
+    iterators::Pairs,
+    position::Position,
+    span::Span,
+    tracker::{Mode, Tracker},
+    RuleType, Stack, TypedNode,
+};
+#[cfg(not(feature = "std"))]
+use core::cell::OnceCell;
+use core::{fmt, hash, marker::PhantomData, ops::Deref};
+#[cfg(feature = "std")]
+use std::sync::OnceLock as OnceCell;
+
+/// A subtree of type `T` that is parsed on first access. See [module-level documentation](self).
+///
+/// It's parsed again with an empty stack,
+/// so `T` should not peek or pop what's pushed outside of it.
+pub struct Lazy<'i, R, T> {
+    // Named so that they are not confused with fields of `T` when dereferenced.
+    extent: Span<'i>,
+    cell: OnceCell<T>,
+    mode: Mode,
+    _phantom: PhantomData<R>,
+}
+
+impl<'i, R: RuleType, T: TypedNode<'i, R>> Lazy<'i, R, T> {
+    /// Create a node from a span and its subtree, without parsing.
+    pub fn new(span: Span<'i>, content: T) -> Self {
+        Self {
+            extent: span,
+            cell: OnceCell::from(content),
+            mode: Mode::default(),
+            _phantom: PhantomData,
+        }
+    }
+    /// Span matched by `T`.
+    pub fn span(&self) -> Span<'i> {
+        self.extent
+    }
+    /// Whether the subtree has been built.
+    pub fn is_parsed(&self) -> bool {
+        self.cell.get().is_some()
+    }
+    /// Build the subtree if not yet, and return it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the span fails to match `T` again,
+    /// which may happen if `T` depends on the stack.
+    pub fn get(&self) -> &T {
+        self.cell
+            .get_or_init(|| Self::parse_span(self.extent, self.mode))
+    }
+    /// Build the subtree if not yet, and take it. See [`Lazy::get`].
+    pub fn into_inner(self) -> T {
+        match self.cell.into_inner() {
+            Some(content) => content,
+            None => Self::parse_span(self.extent, self.mode),
+        }
+    }
+    fn parse_span(span: Span<'i>, mode: Mode) -> T {
+        let start = span.start_pos();
+        let mut stack = Stack::new();
+        let mut tracker = Tracker::new(start).with_mode(mode);
+        match T::try_parse_with(start, &mut stack, &mut tracker) {
+            Ok((end, content)) if end == span.end_pos() => content,
+            _ => panic!("lazy node at {:?} fails to match again", span),
+        }
+    }
+}
+
+impl<'i, R, T> Deref for Lazy<'i, R, T>
+where
+    R: RuleType,
+    T: TypedNode<'i, R>,
+{
+    type Target = T;
+    fn deref(&self) -> &T {
+        self.get()
+    }
+}
+
+impl<'i, R: RuleType, T: TypedNode<'i, R>> TypedNode<'i, R> for Lazy<'i, R, T> {
+    #[inline]
+    fn try_parse_with(
+        input: Position<'i>,
+        stack: &mut Stack<Span<'i>>,
+        tracker: &mut Tracker<'i, R>,
+    ) -> Result<(Position<'i>, Self), ()> {
+        let (end, _) = T::try_parse_with(input, stack, tracker)?;
+        let res = Self {
+            extent: input.span(&end),
+            cell: OnceCell::new(),
+            mode: tracker.mode(),
+            _phantom: PhantomData,
+        };
+        Ok((end, res))
+    }
+}
+
+impl<'i: 'n, 'n, R, T> Pairs<'i, 'n, R> for Lazy<'i, R, T>
+where
+    R: RuleType + 'n,
+    T: TypedNode<'i, R> + Pairs<'i, 'n, R>,
+{
+    type Iter = T::Iter;
+    type IntoIter = T::IntoIter;
+
+    fn iter_pairs(&'n self) -> Self::Iter {
+        self.get().iter_pairs()
+    }
+    fn into_iter_pairs(self) -> Self::IntoIter {
+        self.into_inner().into_iter_pairs()
+    }
+}
+
+impl<R, T: Clone> Clone for Lazy<'_, R, T> {
+    fn clone(&self) -> Self {
+        Self {
+            extent: self.extent,
+            cell: self.cell.clone(),
+            mode: self.mode,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<R, T: fmt::Debug> fmt::Debug for Lazy<'_, R, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Lazy")
+            .field("span", &self.extent)
+            .field("content", &self.cell.get())
+            .finish()
+    }
+}
+
+/// Compared by spans, so that comparing does not build subtrees.
+impl<R, T> PartialEq for Lazy<'_, R, T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.extent == other.extent
+    }
+}
+
+impl<R, T> Eq for Lazy<'_, R, T> {}
+
+impl<R, T> hash::Hash for Lazy<'_, R, T> {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.extent.hash(state)
+    }
+}
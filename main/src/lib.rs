@@ -17,12 +17,39 @@
 //!
 //!   Without this feature, we'll use [core] and [alloc].
 //!
+//! - `lsp`: include module `lsp`, which converts errors into diagnostics shaped like those in the Language Server Protocol.
+//!
+//! - `coverage`: include module `coverage`, which records rules and alternatives of choices matched during parsing.
+//!
+//! - `profile`: include module `profile`, which records attempts, failures and time of rules during parsing. Implies `std`.
+//!
+//! - `vm`: include module `vm`, which parses with grammars loaded at runtime. Implies `std`.
+//!
+//! - `grammar-extras`: accept node tags in grammars loaded by module `vm`.
+//!
+//! - `serde`: include `json::to_value`, which converts typed trees into [serde_json](https://docs.rs/serde_json) values.
+//!
+//!   With `std`, also include module `cache`, which keeps owned trees on disk.
+//!
+//! - `allocator_api`: include `allocation::In`, which collects repetitions into vectors in a custom allocator.
+//!   Requires a nightly compiler.
+//!
+//! - `smallvec`: include `allocation::Inline`, which keeps a few items of each repetition inline in a [smallvec](https://docs.rs/smallvec), without allocating.
+//!
+//! - `regex`: include module `regex`, which matches regular expressions compiled by the generator. Implies `std`.
+//!
+//! - `wasm-bindgen`: include module `wasm`, which passes results of parsing to JavaScript. Implies `std`.
+//!
+//! - `proptest`: include `round_trip::check`, which checks round trips of inputs generated by [proptest](https://docs.rs/proptest) strategies. Implies `std`.
+//!
+//! - `miette`: include module `report`, which reports errors and diagnostics with their inputs by [miette](https://docs.rs/miette). Implies `std`.
+//!
 //! It's suggested that you use [pest_typed_derive](https://crates.io/pest_typed_derive) to automatically generate types from your grammar.
 //!
 //! And though we have a lot of macros in this crate, only some of them are designed for usage outside the crate. They're listed below:
 //!
 //! - Choices: [choices!].
-//! - Sequence: [seq!].
+//! - Sequence: [seq!], and [named_seq!] with named fields.
 //! - Rules:
 //!     - Atomic rule: [atomic_rule!].
 //!     - Non-atomic rule: [non_atomic_rule!].
@@ -31,8 +58,11 @@
 //!     - Silent rule: [silent_rule!].
 //!     - End-Of-Input rule: [rule_eoi!].
 //!     - Tag: [tag!].
+//! - Pattern matching: [match_node!].
+//! - Round trips of printers: [round_trip!], [assert_round_trip!], and `proptest_round_trip!` with feature `proptest`.
 
 #![no_std]
+#![cfg_attr(feature = "allocator_api", feature(allocator_api))]
 #![warn(
     missing_docs,
     rust_2018_idioms,
@@ -53,16 +83,67 @@ pub use wrapper::{
     TypeWrapper,
 };
 
+pub mod allocation;
+#[cfg(all(feature = "std", feature = "serde"))]
+pub mod cache;
+pub mod char_index;
 pub mod choices;
-mod formatter;
+pub mod completion;
+pub mod controller;
+#[cfg(feature = "coverage")]
+pub mod coverage;
+pub mod cursor;
+pub mod diagnostic;
+pub mod diff;
+pub mod dynamic;
+pub mod embed;
+pub mod encoding;
+pub mod events;
+pub mod fold;
+pub mod formatter;
+pub mod green;
+pub mod highlight;
 pub mod iterators;
+pub mod json;
+pub mod keyword;
+pub mod lazy;
+pub mod line_index;
+pub mod literal;
+#[cfg(feature = "lsp")]
+pub mod lsp;
+pub mod metrics;
+pub mod offset_map;
+pub mod pattern;
 mod pratt;
 pub mod predefined_node;
+pub mod prefix;
+#[cfg(feature = "profile")]
+pub mod profile;
+pub mod railroad;
 pub mod re_exported;
+pub mod recovery;
+pub mod reflection;
+#[cfg(feature = "regex")]
+pub mod regex;
+#[cfg(feature = "miette")]
+pub mod report;
+pub mod round_trip;
 pub mod rule;
+pub mod scan;
+pub mod selector;
 pub mod sequence;
+pub mod shared;
+pub mod source;
+pub mod tokens;
 pub mod tracker;
+pub mod tree_index;
 mod typed_node;
+pub mod unicode;
+pub mod version;
+#[cfg(feature = "vm")]
+pub mod vm;
+#[cfg(feature = "wasm-bindgen")]
+pub mod wasm;
 mod wrapper;
 pub use alloc::boxed::Box;
 
@@ -73,12 +154,9 @@ mod span;
 
 pub use pest::error;
 pub use pest::Stack;
-pub use position::Position;
+pub use position::{Indexing, Position, Snap};
 pub use span::{merge_spans, Span};
 
-// Re-export unicode.
-pub use pest::unicode;
-
 /// A trait with a single method that parses strings into typed concrete syntax tree.
 pub trait TypedParser<R: RuleType> {
     /// Try to parse a `&str` into a tree starting from T.
@@ -86,6 +164,14 @@ pub trait TypedParser<R: RuleType> {
     fn try_parse<'i, T: ParsableTypedNode<'i, R>>(input: &'i str) -> Result<T, error::Error<R>> {
         T::try_parse(input)
     }
+    /// Try to parse a prefix of a `&str` into a tree starting from T,
+    /// and return it with the position where the rest of the input starts.
+    #[allow(clippy::perf)]
+    fn try_parse_prefix<'i, T: ParsableTypedNode<'i, R>>(
+        input: &'i str,
+    ) -> Result<(T, Position<'i>), error::Error<R>> {
+        T::try_parse_prefix(input)
+    }
     /// Parse a `&str` into a tree starting from T.
     #[allow(clippy::perf)]
     fn parse<'i, T: NeverFailedParsableTypedNode<'i, R>>(input: &'i str) -> T {
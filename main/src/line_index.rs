@@ -0,0 +1,108 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Precomputed line starts of an input, for looking up lines and columns of many offsets.
+//!
+//! [`Position::line_col`](crate::Position::line_col) scans the input from its start,
+//! so calling it for every node in a tree is quadratic.
+//! A [`LineIndex`] is built once and shared, and each lookup is a binary search.
+//!
+//! ```
+//! # use pest_typed::{line_index::LineIndex, Span};
+//! let input = "a\nbc\n嗨d";
+//! let index = LineIndex::new(input);
+//! assert_eq!(index.line_col(3), (2, 2));
+//! assert_eq!(index.line_col(8), (3, 2));
+//! let span = Span::new(input, 3, 8).unwrap();
+//! assert_eq!(index.line_col_range(&span), ((2, 2), (3, 2)));
+//! ```
+
+use crate::Span;
+use alloc::vec::Vec;
+
+/// Line starts of an input. See [module-level documentation](self).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LineIndex<'i> {
+    input: &'i str,
+    /// Byte offset of each line, and whether the line is ASCII so that columns are byte offsets.
+    lines: Vec<(usize, bool)>,
+}
+
+impl<'i> LineIndex<'i> {
+    /// Index lines of `input`, separated by `\n`.
+    pub fn new(input: &'i str) -> Self {
+        let mut lines = Vec::new();
+        let mut start = 0;
+        for line in input.split_inclusive('\n') {
+            lines.push((start, line.is_ascii()));
+            start += line.len();
+        }
+        if input.is_empty() || input.ends_with('\n') {
+            lines.push((start, true));
+        }
+        Self { input, lines }
+    }
+    /// Indexed input.
+    pub fn input(&self) -> &'i str {
+        self.input
+    }
+    /// Number of lines. An input ending with a line terminator has an empty last line.
+    pub fn len(&self) -> usize {
+        self.lines.len()
+    }
+    /// Whether there is no line, which never holds as an empty input has an empty line.
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+    /// 1-based line and column in chars of a byte offset, as [`Position::line_col`](crate::Position::line_col).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `pos` is out of the input or not on a char boundary.
+    pub fn line_col(&self, pos: usize) -> (usize, usize) {
+        assert!(
+            self.input.is_char_boundary(pos),
+            "position out of bounds or inside a char"
+        );
+        let line = self.lines.partition_point(|&(start, _)| start <= pos) - 1;
+        let (start, ascii) = self.lines[line];
+        let col = if ascii {
+            pos - start
+        } else {
+            self.input[start..pos].chars().count()
+        };
+        (line + 1, col + 1)
+    }
+    /// Lines and columns of both ends of a span from the indexed input.
+    pub fn line_col_range(&self, span: &Span<'_>) -> ((usize, usize), (usize, usize)) {
+        debug_assert!(core::ptr::eq(span.get_input(), self.input));
+        (self.line_col(span.start()), self.line_col(span.end()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LineIndex;
+    use crate::Position;
+
+    #[test]
+    fn same_as_position() {
+        for input in ["", "\n", "a\nb", "ab\r\ncd\n", "嗨\n\na😀b\n"] {
+            let index = LineIndex::new(input);
+            for pos in (0..=input.len()).filter(|&pos| input.is_char_boundary(pos)) {
+                if input[..pos].ends_with('\r') {
+                    continue;
+                }
+                let expected = Position::new(input, pos).unwrap().line_col();
+                assert_eq!(index.line_col(pos), expected, "{input:?} at {pos}");
+            }
+        }
+        assert_eq!(LineIndex::new("a\n").len(), 2);
+    }
+}
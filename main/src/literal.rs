@@ -0,0 +1,529 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Helpers for converting matched literals into values, such as strings with escapes and numbers.
+//!
+//! Errors carry the span of the offending part of the literal,
+//! and can be converted into [pest errors](crate::error::Error) for reporting.
+
+use crate::{
+    error::{Error, ErrorVariant},
+    Span,
+};
+use alloc::{borrow::Cow, string::String};
+use core::fmt;
+use pest::RuleType;
+
+/// Kind of [`LiteralError`].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum LiteralErrorKind {
+    /// An escape that is not in the table.
+    UnknownEscape(char),
+    /// The escape character at the end of the literal.
+    UnterminatedEscape,
+    /// A malformed `\xHH` escape, or one out of the ASCII range.
+    InvalidHexEscape,
+    /// A malformed unicode escape, or one out of the unicode range.
+    InvalidUnicodeEscape,
+    /// A surrogate that is not part of a surrogate pair.
+    LoneSurrogate,
+    /// A number without digits.
+    Empty,
+    /// A character that is not a digit in the radix.
+    InvalidDigit(char),
+    /// A number that doesn't fit in the target type.
+    Overflow,
+    /// A malformed floating point number.
+    InvalidFloat,
+}
+
+impl fmt::Display for LiteralErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownEscape(c) => write!(f, "unknown escape {:?}", c),
+            Self::UnterminatedEscape => write!(f, "unterminated escape"),
+            Self::InvalidHexEscape => write!(f, "invalid hexadecimal escape"),
+            Self::InvalidUnicodeEscape => write!(f, "invalid unicode escape"),
+            Self::LoneSurrogate => write!(f, "lone surrogate in unicode escape"),
+            Self::Empty => write!(f, "number without digits"),
+            Self::InvalidDigit(c) => write!(f, "invalid digit {:?}", c),
+            Self::Overflow => write!(f, "number out of range"),
+            Self::InvalidFloat => write!(f, "invalid floating point number"),
+        }
+    }
+}
+
+/// An error occurred when converting a literal.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct LiteralError<'i> {
+    /// The offending part of the literal.
+    pub span: Span<'i>,
+    /// What is wrong.
+    pub kind: LiteralErrorKind,
+}
+
+impl<'i> LiteralError<'i> {
+    /// Convert into an error pointing at [`LiteralError::span`].
+    pub fn to_error<R: RuleType>(&self) -> Error<R> {
+        let span = pest::Span::new(self.span.get_input(), self.span.start(), self.span.end())
+            .unwrap_or_else(|| pest::Span::new("", 0, 0).unwrap());
+        Error::new_from_span(
+            ErrorVariant::CustomError {
+                message: alloc::format!("{}", self.kind),
+            },
+            span,
+        )
+    }
+}
+
+impl fmt::Display for LiteralError<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at {:?}", self.kind, self.span.as_str())
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for LiteralError<'_> {}
+
+/// Form of unicode escapes.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum UnicodeEscape {
+    /// No unicode escapes.
+    None,
+    /// `\uXXXX`, with surrogate pairs for characters beyond the basic multilingual plane, as in JSON.
+    Fixed4,
+    /// `\u{X}` to `\u{XXXXXX}`, as in Rust.
+    Braced,
+}
+
+/// A table of escapes.
+///
+/// The span to unescape shouldn't contain the quotes.
+///
+/// ```
+/// # use pest_typed::{literal::Escapes, Span};
+/// let input = r#"a\tbé"#;
+/// let span = Span::new(input, 0, input.len()).unwrap();
+/// assert_eq!(Escapes::JSON.unescape(span).unwrap(), "a\tbé");
+///
+/// let plain = Span::new(input, 0, 1).unwrap();
+/// assert!(matches!(Escapes::JSON.unescape(plain), Ok(std::borrow::Cow::Borrowed("a"))));
+/// ```
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Escapes<'e> {
+    /// The character that starts an escape.
+    pub escape: char,
+    /// Escapes of a single character, and what they stand for.
+    pub simple: &'e [(char, char)],
+    /// Whether `\xHH` (up to `\x7F`) is allowed.
+    pub hex: bool,
+    /// Form of unicode escapes.
+    pub unicode: UnicodeEscape,
+}
+
+impl Escapes<'static> {
+    /// Escapes in JSON strings.
+    pub const JSON: Self = Self {
+        escape: '\\',
+        simple: &[
+            ('"', '"'),
+            ('\\', '\\'),
+            ('/', '/'),
+            ('b', '\u{8}'),
+            ('f', '\u{c}'),
+            ('n', '\n'),
+            ('r', '\r'),
+            ('t', '\t'),
+        ],
+        hex: false,
+        unicode: UnicodeEscape::Fixed4,
+    };
+    /// Escapes in Rust strings, except for line continuations.
+    pub const RUST: Self = Self {
+        escape: '\\',
+        simple: &[
+            ('"', '"'),
+            ('\'', '\''),
+            ('\\', '\\'),
+            ('0', '\0'),
+            ('n', '\n'),
+            ('r', '\r'),
+            ('t', '\t'),
+        ],
+        hex: true,
+        unicode: UnicodeEscape::Braced,
+    };
+}
+
+impl Default for Escapes<'static> {
+    fn default() -> Self {
+        Self::JSON
+    }
+}
+
+/// Parse `len` hexadecimal digits at the start of `s`.
+fn hex_digits(s: &str, len: usize) -> Option<u32> {
+    let digits = s.get(..len)?;
+    match digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+        true => u32::from_str_radix(digits, 16).ok(),
+        false => None,
+    }
+}
+
+impl<'e> Escapes<'e> {
+    /// Unescape the literal in `span`.
+    ///
+    /// The result is borrowed from the input if there is no escape.
+    pub fn unescape<'i>(&self, span: Span<'i>) -> Result<Cow<'i, str>, LiteralError<'i>> {
+        let s = span.as_str();
+        let Some(first) = s.find(self.escape) else {
+            return Ok(Cow::Borrowed(s));
+        };
+        let mut res = String::with_capacity(s.len());
+        res.push_str(&s[..first]);
+        let mut i = first;
+        while i < s.len() {
+            let rest = &s[i..];
+            let c = rest.chars().next().unwrap();
+            if c != self.escape {
+                res.push(c);
+                i += c.len_utf8();
+                continue;
+            }
+            let (c, len) = self.unescape_one(&rest[c.len_utf8()..], c.len_utf8());
+            let error = |len: usize, kind| LiteralError {
+                span: span.get(i..(i + len).min(s.len())).unwrap_or(span),
+                kind,
+            };
+            match c {
+                Ok(c) => res.push(c),
+                Err(kind) => return Err(error(len, kind)),
+            }
+            i += len;
+        }
+        Ok(Cow::Owned(res))
+    }
+    /// Unescape a single escape whose escape character occupies `start` bytes,
+    /// and return the character and the length of the whole escape.
+    fn unescape_one(&self, rest: &str, start: usize) -> (Result<char, LiteralErrorKind>, usize) {
+        let Some(c) = rest.chars().next() else {
+            return (Err(LiteralErrorKind::UnterminatedEscape), start);
+        };
+        let len = start + c.len_utf8();
+        if let Some((_, to)) = self.simple.iter().find(|(from, _)| *from == c) {
+            return (Ok(*to), len);
+        }
+        let rest = &rest[c.len_utf8()..];
+        match (c, self.unicode) {
+            ('x', _) if self.hex => match hex_digits(rest, 2) {
+                Some(code) if code <= 0x7f => (Ok(char::from(code as u8)), len + 2),
+                _ => (
+                    Err(LiteralErrorKind::InvalidHexEscape),
+                    len + rest.len().min(2),
+                ),
+            },
+            ('u', UnicodeEscape::Fixed4) => {
+                let Some(high) = hex_digits(rest, 4) else {
+                    return (
+                        Err(LiteralErrorKind::InvalidUnicodeEscape),
+                        len + rest.len().min(4),
+                    );
+                };
+                let len = len + 4;
+                match high {
+                    0xD800..=0xDBFF => {
+                        let rest = &rest[4..];
+                        let low = rest
+                            .strip_prefix(self.escape)
+                            .and_then(|rest| rest.strip_prefix('u'))
+                            .and_then(|rest| hex_digits(rest, 4));
+                        match low {
+                            Some(low @ 0xDC00..=0xDFFF) => {
+                                let code = 0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00);
+                                let pair = self.escape.len_utf8() + 5;
+                                (
+                                    char::from_u32(code).ok_or(LiteralErrorKind::LoneSurrogate),
+                                    len + pair,
+                                )
+                            }
+                            _ => (Err(LiteralErrorKind::LoneSurrogate), len),
+                        }
+                    }
+                    0xDC00..=0xDFFF => (Err(LiteralErrorKind::LoneSurrogate), len),
+                    code => (
+                        char::from_u32(code).ok_or(LiteralErrorKind::InvalidUnicodeEscape),
+                        len,
+                    ),
+                }
+            }
+            ('u', UnicodeEscape::Braced) => {
+                let digits = rest
+                    .strip_prefix('{')
+                    .and_then(|rest| rest.find('}').map(|end| &rest[..end]));
+                match digits {
+                    Some(digits) => {
+                        let len = len + digits.len() + 2;
+                        let code = match (1..=6).contains(&digits.len()) {
+                            true => hex_digits(digits, digits.len()),
+                            false => None,
+                        };
+                        match code.and_then(char::from_u32) {
+                            Some(c) => (Ok(c), len),
+                            None => (Err(LiteralErrorKind::InvalidUnicodeEscape), len),
+                        }
+                    }
+                    None => (Err(LiteralErrorKind::InvalidUnicodeEscape), len),
+                }
+            }
+            (c, _) => (Err(LiteralErrorKind::UnknownEscape(c)), len),
+        }
+    }
+}
+
+/// Integer types that [`parse_int`] can produce.
+pub trait Integer: Copy {
+    /// Zero.
+    const ZERO: Self;
+    /// Whether the type can be negative.
+    const SIGNED: bool;
+    /// `self * radix + digit`, or `self * radix - digit` if `negative`. `None` on overflow.
+    fn push_digit(self, radix: u32, digit: u32, negative: bool) -> Option<Self>;
+}
+
+macro_rules! impl_integer {
+    ($signed:expr, $($t:ty),*) => {
+        $(
+            impl Integer for $t {
+                const ZERO: Self = 0;
+                const SIGNED: bool = $signed;
+                #[inline]
+                fn push_digit(self, radix: u32, digit: u32, negative: bool) -> Option<Self> {
+                    let shifted = self.checked_mul(<$t>::try_from(radix).ok()?)?;
+                    let digit = <$t>::try_from(digit).ok()?;
+                    match negative {
+                        true => shifted.checked_sub(digit),
+                        false => shifted.checked_add(digit),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_integer!(true, i8, i16, i32, i64, i128, isize);
+impl_integer!(false, u8, u16, u32, u64, u128, usize);
+
+/// Parse an integer with an optional sign, radix prefix (`0x`, `0o` or `0b`) and `_` as separators.
+///
+/// ```
+/// # use pest_typed::{literal::{parse_int, LiteralErrorKind}, Span};
+/// let parse = |s| parse_int::<i16>(Span::new(s, 0, s.len()).unwrap());
+/// assert_eq!(parse("-0x7f_ff"), Ok(-0x7fff));
+/// assert_eq!(parse("1_000"), Ok(1000));
+/// assert_eq!(parse("0b102").unwrap_err().span.as_str(), "2");
+/// assert_eq!(parse("40000").unwrap_err().kind, LiteralErrorKind::Overflow);
+/// ```
+pub fn parse_int<'i, T: Integer>(span: Span<'i>) -> Result<T, LiteralError<'i>> {
+    let s = span.as_str();
+    let sign = match s.as_bytes().first() {
+        Some(b'+' | b'-') => 1,
+        _ => 0,
+    };
+    let (radix, prefix) = match s.get(sign..sign + 2) {
+        Some("0x" | "0X") => (16, 2),
+        Some("0o" | "0O") => (8, 2),
+        Some("0b" | "0B") => (2, 2),
+        _ => (10, 0),
+    };
+    let digits = span.get(sign + prefix..).unwrap_or(span);
+    parse_digits(span, digits, s.starts_with('-'), radix)
+}
+
+/// Parse an integer in `radix` with an optional sign and `_` as separators, but without a radix prefix.
+///
+/// # Panics
+///
+/// Panics if `radix` is not in the range from 2 to 36.
+pub fn parse_int_radix<'i, T: Integer>(span: Span<'i>, radix: u32) -> Result<T, LiteralError<'i>> {
+    let s = span.as_str();
+    let sign = usize::from(s.starts_with(['+', '-']));
+    let digits = span.get(sign..).unwrap_or(span);
+    parse_digits(span, digits, s.starts_with('-'), radix)
+}
+
+fn parse_digits<'i, T: Integer>(
+    span: Span<'i>,
+    digits: Span<'i>,
+    negative: bool,
+    radix: u32,
+) -> Result<T, LiteralError<'i>> {
+    let error = |span, kind| LiteralError { span, kind };
+    if negative && !T::SIGNED {
+        let minus = span.get(..1).unwrap_or(span);
+        return Err(error(minus, LiteralErrorKind::InvalidDigit('-')));
+    }
+    let mut value = T::ZERO;
+    let mut empty = true;
+    for (i, c) in digits.as_str().char_indices() {
+        if c == '_' {
+            continue;
+        }
+        let Some(digit) = c.to_digit(radix) else {
+            let at = digits.get(i..i + c.len_utf8()).unwrap_or(digits);
+            return Err(error(at, LiteralErrorKind::InvalidDigit(c)));
+        };
+        empty = false;
+        value = value
+            .push_digit(radix, digit, negative)
+            .ok_or_else(|| error(span, LiteralErrorKind::Overflow))?;
+    }
+    match empty {
+        true => Err(error(span, LiteralErrorKind::Empty)),
+        false => Ok(value),
+    }
+}
+
+/// Parse a floating point number with `_` as separators.
+///
+/// Other than separators, the syntax is the same as [`f64`'s `FromStr`](core::str::FromStr),
+/// so `inf` and `NaN` are accepted as well.
+pub fn parse_float(span: Span<'_>) -> Result<f64, LiteralError<'_>> {
+    let s = span.as_str();
+    let s = match s.contains('_') {
+        true => Cow::Owned(s.replace('_', "")),
+        false => Cow::Borrowed(s),
+    };
+    s.parse().map_err(|_| LiteralError {
+        span,
+        kind: LiteralErrorKind::InvalidFloat,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_float, parse_int, parse_int_radix, Escapes, LiteralErrorKind};
+    use crate::Span;
+    use alloc::borrow::Cow;
+
+    fn span(input: &str) -> Span<'_> {
+        Span::new(input, 0, input.len()).unwrap()
+    }
+
+    #[test]
+    fn unescape() {
+        assert_eq!(
+            Escapes::JSON.unescape(span(r#"\"\\\/\b\f\n\r\tA😀"#)),
+            Ok(Cow::Owned("\"\\/\u{8}\u{c}\n\r\tA😀".into()))
+        );
+        assert_eq!(
+            Escapes::RUST.unescape(span(r"\x41\u{1F600}\0\'")),
+            Ok(Cow::Owned("A😀\0'".into()))
+        );
+        assert!(matches!(
+            Escapes::RUST.unescape(span("plain")),
+            Ok(Cow::Borrowed("plain"))
+        ));
+    }
+
+    #[test]
+    fn errors() {
+        let check = |escapes: Escapes<'_>, input: &str, part: &str, kind| {
+            let error = escapes.unescape(span(input)).unwrap_err();
+            assert_eq!((error.span.as_str(), error.kind), (part, kind));
+        };
+        check(
+            Escapes::JSON,
+            r"ab\q",
+            r"\q",
+            LiteralErrorKind::UnknownEscape('q'),
+        );
+        check(
+            Escapes::JSON,
+            r"ab\",
+            r"\",
+            LiteralErrorKind::UnterminatedEscape,
+        );
+        check(
+            Escapes::JSON,
+            r"\u12",
+            r"\u12",
+            LiteralErrorKind::InvalidUnicodeEscape,
+        );
+        check(
+            Escapes::JSON,
+            r"\uD83Dx",
+            r"\uD83D",
+            LiteralErrorKind::LoneSurrogate,
+        );
+        check(
+            Escapes::JSON,
+            r"\uDE00",
+            r"\uDE00",
+            LiteralErrorKind::LoneSurrogate,
+        );
+        check(
+            Escapes::RUST,
+            r"\x80",
+            r"\x80",
+            LiteralErrorKind::InvalidHexEscape,
+        );
+        check(
+            Escapes::RUST,
+            r"a\u{110000}",
+            r"\u{110000}",
+            LiteralErrorKind::InvalidUnicodeEscape,
+        );
+        check(
+            Escapes::RUST,
+            r"\u{41",
+            r"\u",
+            LiteralErrorKind::InvalidUnicodeEscape,
+        );
+
+        let input = "x\n\"a\\qb\"";
+        let error = Escapes::JSON
+            .unescape(Span::new(input, 3, 7).unwrap())
+            .unwrap_err()
+            .to_error::<()>();
+        assert_eq!(
+            error.line_col,
+            pest::error::LineColLocation::Span((2, 3), (2, 5))
+        );
+    }
+
+    #[test]
+    fn numbers() {
+        assert_eq!(parse_int::<i8>(span("-128")), Ok(-128));
+        assert_eq!(parse_int::<u8>(span("+0xFF")), Ok(255));
+        assert_eq!(parse_int::<u32>(span("0o17")), Ok(15));
+        assert_eq!(parse_int::<u64>(span("1_000_000")), Ok(1_000_000));
+        assert_eq!(parse_int_radix::<i32>(span("-zz"), 36), Ok(-1295));
+        assert_eq!(parse_float(span("1_000.5e-1")), Ok(100.05));
+
+        let check = |result: Result<u8, super::LiteralError<'_>>, part: &str, kind| {
+            let error = result.unwrap_err();
+            assert_eq!((error.span.as_str(), error.kind), (part, kind));
+        };
+        check(parse_int(span("256")), "256", LiteralErrorKind::Overflow);
+        check(
+            parse_int(span("-1")),
+            "-",
+            LiteralErrorKind::InvalidDigit('-'),
+        );
+        check(parse_int(span("0x")), "0x", LiteralErrorKind::Empty);
+        check(parse_int(span("_")), "_", LiteralErrorKind::Empty);
+        check(
+            parse_int(span("12é")),
+            "é",
+            LiteralErrorKind::InvalidDigit('é'),
+        );
+        let error = parse_float(span("1..2")).unwrap_err();
+        assert_eq!(error.kind, LiteralErrorKind::InvalidFloat);
+    }
+}
@@ -0,0 +1,282 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Diagnostics shaped like those in the [Language Server Protocol](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#diagnostic).
+//!
+//! Positions are zero-based, and characters are counted in UTF-16 code units,
+//! so that fields can be copied into `lsp_types` directly.
+//!
+//! Grammar lints from `pest_typed_generator::lint_grammar` are [pest errors](pest::error::Error) as well,
+//! so they can be converted in the same way.
+//!
+//! Highlighted ranges can be encoded as semantic tokens with [`semantic_tokens`].
+
+use crate::{highlight::HighlightRange, Indexing};
+use alloc::{string::String, vec::Vec};
+use pest::{
+    error::{Error, InputLocation},
+    RuleType,
+};
+
+/// Position in a text document.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Position {
+    /// Zero-based line number.
+    pub line: u32,
+    /// Zero-based offset in the line, in UTF-16 code units.
+    pub character: u32,
+}
+
+impl Position {
+    /// Position of byte offset `offset` in `input`.
+    ///
+    /// Offsets beyond the end are clamped, and those inside a character are moved to the start of it.
+    pub fn from_offset(input: &str, offset: usize) -> Self {
+        let mut offset = offset.min(input.len());
+        while !input.is_char_boundary(offset) {
+            offset -= 1;
+        }
+        let (line, col) =
+            crate::Position::new(input, offset).map_or((1, 1), |pos| pos.line_col_utf16());
+        Self {
+            line: line as u32 - 1,
+            character: col as u32 - 1,
+        }
+    }
+    /// Line and character counted with `indexing`, such as 1-based ones for messages.
+    pub fn indexed(&self, indexing: Indexing) -> (usize, usize) {
+        Indexing::ZERO_BASED
+            .to_one_based((self.line as usize, self.character as usize))
+            .map_or((0, 0), |one_based| indexing.from_one_based(one_based))
+    }
+    /// Position of a line and a character counted with `indexing`.
+    ///
+    /// Returns [`None`] if they are before the first line or character.
+    pub fn from_indexed(line: usize, character: usize, indexing: Indexing) -> Option<Self> {
+        let (line, character) =
+            Indexing::ZERO_BASED.from_one_based(indexing.to_one_based((line, character))?);
+        Some(Self {
+            line: line as u32,
+            character: character as u32,
+        })
+    }
+}
+
+/// Range in a text document. The end is exclusive.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Range {
+    /// Start position.
+    pub start: Position,
+    /// End position.
+    pub end: Position,
+}
+
+impl Range {
+    /// Range of byte offsets `start..end` in `input`.
+    pub fn from_offsets(input: &str, start: usize, end: usize) -> Self {
+        Self {
+            start: Position::from_offset(input, start),
+            end: Position::from_offset(input, end),
+        }
+    }
+}
+
+/// Severity of a diagnostic, with the same values as in the protocol.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[repr(u8)]
+pub enum DiagnosticSeverity {
+    /// Error.
+    Error = 1,
+    /// Warning.
+    Warning = 2,
+    /// Information.
+    Information = 3,
+    /// Hint.
+    Hint = 4,
+}
+
+/// A diagnostic.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Diagnostic {
+    /// Range the diagnostic applies to.
+    pub range: Range,
+    /// Severity.
+    pub severity: Option<DiagnosticSeverity>,
+    /// Code.
+    pub code: Option<String>,
+    /// A human-readable string describing the source, such as the language name.
+    pub source: Option<String>,
+    /// Message.
+    pub message: String,
+}
+
+impl Diagnostic {
+    /// Convert an error produced when parsing `input`.
+    ///
+    /// Errors at a single position are converted to empty ranges.
+    pub fn from_error<R: RuleType>(
+        error: &Error<R>,
+        input: &str,
+        severity: DiagnosticSeverity,
+    ) -> Self {
+        let range = match error.location {
+            InputLocation::Pos(pos) => Range::from_offsets(input, pos, pos),
+            InputLocation::Span((start, end)) => Range::from_offsets(input, start, end),
+        };
+        Self {
+            range,
+            severity: Some(severity),
+            code: None,
+            source: None,
+            message: String::from(error.variant.message()),
+        }
+    }
+    /// Set [`Diagnostic::code`].
+    pub fn with_code(mut self, code: impl Into<String>) -> Self {
+        self.code = Some(code.into());
+        self
+    }
+    /// Set [`Diagnostic::source`].
+    pub fn with_source(mut self, source: impl Into<String>) -> Self {
+        self.source = Some(source.into());
+        self
+    }
+}
+
+/// Encode highlighted ranges as the data of [semantic tokens](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocument_semanticTokens),
+/// five integers per token.
+///
+/// The token type is the index of the class in `legend`, and ranges with other classes are skipped.
+/// Ranges must be in source order, as returned by [`PairTree::highlights`](crate::iterators::PairTree::highlights).
+/// Ranges spanning multiple lines are split into one token per line.
+pub fn semantic_tokens(ranges: &[HighlightRange<'_>], legend: &[&str]) -> Vec<u32> {
+    let mut res = Vec::new();
+    let mut last = Position::default();
+    for range in ranges {
+        let Some(ty) = legend.iter().position(|class| *class == range.class) else {
+            continue;
+        };
+        let input = range.span.get_input();
+        let mut offset = range.span.start();
+        for line in range.span.as_str().split_inclusive('\n') {
+            let text = line.trim_end_matches(['\r', '\n']);
+            if !text.is_empty() {
+                let start = Position::from_offset(input, offset);
+                let delta_start = if start.line == last.line {
+                    start.character - last.character
+                } else {
+                    start.character
+                };
+                let length = text.encode_utf16().count() as u32;
+                res.extend([start.line - last.line, delta_start, length, ty as u32, 0]);
+                last = start;
+            }
+            offset += line.len();
+        }
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{semantic_tokens, Diagnostic, DiagnosticSeverity, Position, Range};
+    use crate::highlight::HighlightRange;
+    use crate::Indexing;
+    use pest::{error::Error, error::ErrorVariant, Span};
+
+    #[test]
+    fn tokens() {
+        let input = "if é\n\"a\nb\" x";
+        let range = |start, end, class| HighlightRange {
+            span: crate::Span::new(input, start, end).unwrap(),
+            class,
+        };
+        let ranges = [
+            range(0, 2, "keyword"),
+            range(3, 5, "unknown"),
+            range(6, 11, "string"),
+            range(12, 13, "keyword"),
+        ];
+        assert_eq!(
+            semantic_tokens(&ranges, &["string", "keyword"]),
+            [0, 0, 2, 1, 0, 1, 0, 2, 0, 0, 1, 0, 2, 0, 0, 0, 3, 1, 1, 0],
+        );
+    }
+
+    #[test]
+    fn utf16() {
+        let input = "ab\n𝄞é\nx";
+        assert_eq!(
+            Position::from_offset(input, 3),
+            Position {
+                line: 1,
+                character: 0
+            }
+        );
+        // `𝄞` takes two UTF-16 code units.
+        assert_eq!(Position::from_offset(input, 7).character, 2);
+        assert_eq!(Position::from_offset(input, 5).character, 0);
+        assert_eq!(Position::from_offset(input, 9).character, 3);
+        assert_eq!(
+            Position::from_offset(input, 100),
+            Position {
+                line: 2,
+                character: 1
+            }
+        );
+    }
+
+    #[test]
+    fn indexing() {
+        let pos = Position {
+            line: 1,
+            character: 2,
+        };
+        assert_eq!(pos.indexed(Indexing::ONE_BASED), (2, 3));
+        assert_eq!(pos.indexed(Indexing::ZERO_BASED), (1, 2));
+        let python = Indexing {
+            first_line: 1,
+            first_column: 0,
+        };
+        assert_eq!(pos.indexed(python), (2, 2));
+        assert_eq!(Position::from_indexed(2, 2, python), Some(pos));
+        assert_eq!(Position::from_indexed(0, 1, Indexing::ONE_BASED), None);
+    }
+
+    #[test]
+    fn from_error() {
+        let input = "a\n𝄞bc";
+        let error = Error::<()>::new_from_span(
+            ErrorVariant::CustomError {
+                message: "unexpected".into(),
+            },
+            Span::new(input, 6, 8).unwrap(),
+        );
+        let diagnostic = Diagnostic::from_error(&error, input, DiagnosticSeverity::Warning)
+            .with_code("E1")
+            .with_source("test");
+        assert_eq!(
+            diagnostic.range,
+            Range {
+                start: Position {
+                    line: 1,
+                    character: 2
+                },
+                end: Position {
+                    line: 1,
+                    character: 4
+                },
+            }
+        );
+        assert_eq!(diagnostic.severity, Some(DiagnosticSeverity::Warning));
+        assert_eq!(diagnostic.code.as_deref(), Some("E1"));
+        assert_eq!(diagnostic.source.as_deref(), Some("test"));
+        assert_eq!(diagnostic.message, "unexpected");
+    }
+}
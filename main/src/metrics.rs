@@ -0,0 +1,278 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Size of typed trees.
+//!
+//! [`metrics`] counts rule nodes in a tree, measures how deep they are nested,
+//! and adds up bytes allocated on the heap for the tree,
+//! which helps to estimate memory used by cached parse results,
+//! or to compare trees generated with different options.
+//!
+//! Heap bytes are computed from types of nodes with [`HeapSize`],
+//! so bookkeeping of the allocator and unused capacities of boxes are not included,
+//! while unused capacities of vectors are.
+//! Foreign nodes in [`Embed`] are not counted either, as their types are unknown here.
+
+use crate::predefined_node::{
+    AlwaysFail, AtomicRep, Bounded, CharRange, Empty, Insens, Negative, PeekSlice1, PeekSlice2,
+    Positive, Push, RepMin, RepMinMax, SameLine, Skip, SkipChar, Skipped, Str, ANY, DROP, EOI,
+    NEWLINE, PEEK, PEEK_ALL, POP, POP_ALL, SOI,
+};
+use crate::{
+    allocation::{Allocation, Container},
+    embed::{Delegate, Embed},
+    iterators::Pair,
+    lazy::Lazy,
+    recovery::Closer,
+    RuleType, Span, StringArrayWrapper, StringWrapper, TypedNode,
+};
+use alloc::{boxed::Box, collections::BTreeMap, vec::Vec};
+use core::mem::size_of;
+
+/// A node that knows how many bytes it has allocated on the heap.
+pub trait HeapSize {
+    /// Bytes allocated on the heap by the node and its children, excluding the node itself.
+    fn heap_size(&self) -> usize;
+}
+
+macro_rules! impl_leaf {
+    ($( ($($generics:tt)*) $ty:ty ),* $(,)?) => {
+        $(
+            impl<$($generics)*> HeapSize for $ty {
+                #[inline]
+                fn heap_size(&self) -> usize {
+                    0
+                }
+            }
+        )*
+    };
+}
+
+impl_leaf!(
+    () (),
+    (T: StringWrapper) Str<T>,
+    ('i, T: StringWrapper) Insens<'i, T>,
+    ('i, Strings: StringArrayWrapper) Skip<'i, Strings>,
+    ('i, const N: usize) SkipChar<'i, N>,
+    (const MIN: char, const MAX: char) CharRange<MIN, MAX>,
+    (T) Negative<T>,
+    () ANY,
+    ('i) SOI<'i>,
+    ('i) EOI<'i>,
+    () NEWLINE,
+    ('i) PEEK<'i>,
+    () DROP,
+    ('i) POP<'i>,
+    ('i) AlwaysFail<'i>,
+    ('i) Empty<'i>,
+    ('i, R, T, D: Delegate<'i>) Embed<'i, R, T, D>,
+);
+
+impl<T: HeapSize> HeapSize for Option<T> {
+    #[inline]
+    fn heap_size(&self) -> usize {
+        self.as_ref().map_or(0, T::heap_size)
+    }
+}
+
+impl<T: HeapSize> HeapSize for Box<T> {
+    #[inline]
+    fn heap_size(&self) -> usize {
+        size_of::<T>() + T::heap_size(self)
+    }
+}
+
+impl<T: HeapSize> HeapSize for Vec<T> {
+    #[inline]
+    fn heap_size(&self) -> usize {
+        container_size(self)
+    }
+}
+
+impl<T1: HeapSize, T2: HeapSize> HeapSize for (T1, T2) {
+    #[inline]
+    fn heap_size(&self) -> usize {
+        self.0.heap_size() + self.1.heap_size()
+    }
+}
+
+impl<T: HeapSize, const N: usize> HeapSize for [T; N] {
+    #[inline]
+    fn heap_size(&self) -> usize {
+        self.iter().map(T::heap_size).sum()
+    }
+}
+
+impl<T: HeapSize, Skip: HeapSize, const SKIP: usize> HeapSize for Skipped<T, Skip, SKIP> {
+    #[inline]
+    fn heap_size(&self) -> usize {
+        self.skipped.heap_size() + self.matched.heap_size()
+    }
+}
+
+impl<T: HeapSize, End, Skip, const SKIP: usize, const START: bool, const END: bool> HeapSize
+    for Bounded<T, End, Skip, SKIP, START, END>
+{
+    #[inline]
+    fn heap_size(&self) -> usize {
+        self.content.heap_size()
+    }
+}
+
+impl<N: HeapSize> HeapSize for Positive<N> {
+    #[inline]
+    fn heap_size(&self) -> usize {
+        self.content.heap_size()
+    }
+}
+
+impl<T: HeapSize> HeapSize for Push<T> {
+    #[inline]
+    fn heap_size(&self) -> usize {
+        self.content.heap_size()
+    }
+}
+
+impl<T: HeapSize> HeapSize for SameLine<T> {
+    #[inline]
+    fn heap_size(&self) -> usize {
+        self.content.heap_size()
+    }
+}
+
+fn container_size<T: HeapSize, C: Container<T>>(content: &C) -> usize {
+    content.heap_capacity() * size_of::<T>() + content.iter().map(T::heap_size).sum::<usize>()
+}
+
+impl<T: HeapSize, A: Allocation> HeapSize for AtomicRep<T, A> {
+    #[inline]
+    fn heap_size(&self) -> usize {
+        container_size(&self.content)
+    }
+}
+
+impl<T: HeapSize, const MIN: usize, A: Allocation> HeapSize for RepMin<T, MIN, A> {
+    #[inline]
+    fn heap_size(&self) -> usize {
+        container_size(&self.content)
+    }
+}
+
+impl<T: HeapSize, const MIN: usize, const MAX: usize, A: Allocation> HeapSize
+    for RepMinMax<T, MIN, MAX, A>
+{
+    #[inline]
+    fn heap_size(&self) -> usize {
+        container_size(&self.content)
+    }
+}
+
+macro_rules! impl_pushed {
+    ($( ($($generics:tt)*) $ty:ty ),* $(,)?) => {
+        $(
+            impl<$($generics)*> HeapSize for $ty {
+                #[inline]
+                fn heap_size(&self) -> usize {
+                    self.pushed.capacity() * size_of::<Span<'i>>()
+                }
+            }
+        )*
+    };
+}
+
+impl_pushed!(
+    ('i) PEEK_ALL<'i>,
+    ('i) POP_ALL<'i>,
+    ('i, const START: i32, const END: i32) PeekSlice2<'i, START, END>,
+    ('i, const START: i32) PeekSlice1<'i, START>,
+);
+
+/// Subtrees that are not built yet take no space.
+impl<'i, R: RuleType, T: TypedNode<'i, R> + HeapSize> HeapSize for Lazy<'i, R, T> {
+    #[inline]
+    fn heap_size(&self) -> usize {
+        match self.is_parsed() {
+            true => self.get().heap_size(),
+            false => 0,
+        }
+    }
+}
+
+impl<T: HeapSize> HeapSize for Closer<'_, T> {
+    #[inline]
+    fn heap_size(&self) -> usize {
+        self.matched.heap_size()
+    }
+}
+
+/// Size of a typed tree. See [`metrics`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Metrics<R> {
+    /// Number of nodes of each rule, including the root.
+    pub nodes: BTreeMap<R, usize>,
+    /// Number of rule nodes on the longest path from the root to a leaf, which is `1` for a single node.
+    pub max_depth: usize,
+    /// Bytes allocated on the heap by the tree, excluding the root itself.
+    pub heap_bytes: usize,
+}
+
+impl<R> Metrics<R> {
+    /// Number of rule nodes in the tree.
+    pub fn total_nodes(&self) -> usize {
+        self.nodes.values().sum()
+    }
+}
+
+fn count<'i: 'n, 'n, R: RuleType + 'n>(
+    pair: &'n dyn Pair<'i, 'n, R>,
+    depth: usize,
+    metrics: &mut Metrics<R>,
+) {
+    *metrics.nodes.entry(pair.rule()).or_default() += 1;
+    metrics.max_depth = metrics.max_depth.max(depth);
+    for child in pair.inner() {
+        count(child, depth + 1, metrics);
+    }
+}
+
+/// Count rule nodes and heap bytes of `tree`.
+pub fn metrics<'i: 'n, 'n, R: RuleType + 'n, T: Pair<'i, 'n, R> + HeapSize>(
+    tree: &'n T,
+) -> Metrics<R> {
+    let mut res = Metrics {
+        nodes: BTreeMap::new(),
+        max_depth: 0,
+        heap_bytes: tree.heap_size(),
+    };
+    count(tree, 1, &mut res);
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::HeapSize;
+    use crate::predefined_node::{CharRange, RepMin, Skipped};
+    use alloc::{boxed::Box, vec::Vec};
+    use core::mem::size_of;
+
+    #[test]
+    fn containers() {
+        type Item = Skipped<CharRange<'a', 'z'>, (), 0>;
+        let mut content = Vec::with_capacity(4);
+        content.push(Skipped::new(CharRange { content: 'a' }));
+        let rep = RepMin::<Item, 0> { content };
+        assert_eq!(rep.heap_size(), 4 * size_of::<Item>());
+
+        let boxed = Box::new(Some(rep));
+        assert_eq!(
+            boxed.heap_size(),
+            size_of::<Option<RepMin<Item, 0>>>() + 4 * size_of::<Item>()
+        );
+    }
+}
@@ -0,0 +1,225 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Translate offsets in a pre-processed input back to the original input.
+//!
+//! When an input is rewritten before parsing, such as stripping the byte order mark or normalizing line terminators,
+//! spans of the parsed tree point into the rewritten text.
+//! An [`OffsetMap`] records what's been replaced, so that they can be reported in the original file.
+//!
+//! ```
+//! # use pest_typed::{offset_map::OffsetMap, Span};
+//! let original = "\u{feff}a\r\nbc";
+//! let (input, map) = OffsetMap::normalize(original);
+//! assert_eq!(input, "a\nbc");
+//! let span = Span::new(&input, 2, 4).unwrap();
+//! assert_eq!(&original[map.span_to_original(&span)], "bc");
+//! ```
+
+use crate::Span;
+use alloc::{borrow::Cow, string::String, vec::Vec};
+use core::ops::Range;
+
+/// A replacement of a range in the original input.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+struct Edit {
+    /// Replaced range in the original input.
+    original: (usize, usize),
+    /// Range of its replacement in the rewritten input.
+    rewritten: (usize, usize),
+}
+
+/// Replacements made to an input. See [module-level documentation](self).
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct OffsetMap {
+    /// Edits in the order of their positions.
+    edits: Vec<Edit>,
+}
+
+impl OffsetMap {
+    /// Create a map without replacements, which maps each offset to itself.
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Record that `original` in the original input is replaced by `len` bytes.
+    ///
+    /// Replacements should be recorded in the order of their positions.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `original` starts before the end of the last replacement.
+    #[track_caller]
+    pub fn replace(&mut self, original: Range<usize>, len: usize) {
+        let (end, rewritten_end) = self
+            .edits
+            .last()
+            .map_or((0, 0), |last| (last.original.1, last.rewritten.1));
+        assert!(
+            end <= original.start && original.start <= original.end,
+            "Replacement of {:?} overlaps with or is before the last one ending at {}.",
+            original,
+            end,
+        );
+        let start = rewritten_end + (original.start - end);
+        self.edits.push(Edit {
+            original: (original.start, original.end),
+            rewritten: (start, start + len),
+        });
+    }
+    /// Whether no replacement is recorded.
+    pub fn is_identity(&self) -> bool {
+        self.edits.is_empty()
+    }
+    /// Translate an offset in the rewritten input.
+    ///
+    /// An offset where something is removed is mapped to the end of the removed text,
+    /// as is suitable for starts of spans,
+    /// and one inside a replacement is mapped to the start of the replaced text.
+    pub fn to_original(&self, offset: usize) -> usize {
+        let index = self
+            .edits
+            .partition_point(|edit| edit.rewritten.0 <= offset);
+        self.translate(index, offset, false)
+    }
+    /// Translate an offset in the rewritten input as the end of a range.
+    ///
+    /// Unlike [`OffsetMap::to_original`], an offset where something is removed is mapped to the start of the removed text,
+    /// so that ranges don't cover it,
+    /// and one inside a replacement is mapped to the end of the replaced text.
+    pub fn end_to_original(&self, offset: usize) -> usize {
+        let index = self.edits.partition_point(|edit| edit.rewritten.0 < offset);
+        self.translate(index, offset, true)
+    }
+    /// Translate with the last edit before `index`.
+    fn translate(&self, index: usize, offset: usize, is_end: bool) -> usize {
+        let Some(edit) = index.checked_sub(1).map(|i| self.edits[i]) else {
+            return offset;
+        };
+        let (start, end) = edit.original;
+        match offset.checked_sub(edit.rewritten.1) {
+            Some(after) => end + after,
+            None if is_end => end,
+            None => start,
+        }
+    }
+    /// Translate a range in the rewritten input.
+    pub fn range_to_original(&self, range: Range<usize>) -> Range<usize> {
+        let start = self.to_original(range.start);
+        let end = match range.is_empty() {
+            true => start,
+            false => self.end_to_original(range.end),
+        };
+        start..end
+    }
+    /// Translate a span in the rewritten input.
+    pub fn span_to_original(&self, span: &Span<'_>) -> Range<usize> {
+        self.range_to_original(span.start()..span.end())
+    }
+    /// Remove the leading byte order mark of `input` if there is one.
+    pub fn strip_bom(input: &str) -> (&str, Self) {
+        let mut map = Self::new();
+        match input.strip_prefix('\u{feff}') {
+            Some(rest) => {
+                map.replace(0..'\u{feff}'.len_utf8(), 0);
+                (rest, map)
+            }
+            None => (input, map),
+        }
+    }
+    /// Replace `\r\n` and `\r` in `input` with `\n`.
+    pub fn normalize_newlines(input: &str) -> (Cow<'_, str>, Self) {
+        let mut map = Self::new();
+        if !input.contains('\r') {
+            return (Cow::Borrowed(input), map);
+        }
+        let mut res = String::with_capacity(input.len());
+        let mut rest = 0;
+        let bytes = input.as_bytes();
+        for (i, _) in input.match_indices('\r') {
+            res.push_str(&input[rest..i]);
+            res.push('\n');
+            rest = i + 1;
+            if bytes.get(rest) == Some(&b'\n') {
+                map.replace(i..i + 2, 1);
+                rest += 1;
+            }
+        }
+        res.push_str(&input[rest..]);
+        (Cow::Owned(res), map)
+    }
+    /// Strip the leading byte order mark and normalize line terminators of `input`,
+    /// with [`OffsetMap::strip_bom`] and [`OffsetMap::normalize_newlines`].
+    pub fn normalize(input: &str) -> (Cow<'_, str>, Self) {
+        let (stripped, bom) = Self::strip_bom(input);
+        let (res, newlines) = Self::normalize_newlines(stripped);
+        let mut map = bom;
+        let offset = input.len() - stripped.len();
+        for edit in newlines.edits {
+            let (start, end) = edit.original;
+            map.replace(
+                start + offset..end + offset,
+                edit.rewritten.1 - edit.rewritten.0,
+            );
+        }
+        (res, map)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OffsetMap;
+
+    #[test]
+    fn newlines() {
+        let original = "a\r\nb\rc\r\n";
+        let (input, map) = OffsetMap::normalize_newlines(original);
+        assert_eq!(input, "a\nb\nc\n");
+        // `\r` alone is replaced with a character of the same length, so nothing is recorded.
+        assert_eq!(map.edits.len(), 2);
+        assert_eq!(&original[map.range_to_original(0..1)], "a");
+        assert_eq!(&original[map.range_to_original(1..2)], "\r\n");
+        assert_eq!(&original[map.range_to_original(2..5)], "b\rc");
+        assert_eq!(map.to_original(6), original.len());
+    }
+
+    #[test]
+    fn removed() {
+        let original = "\u{feff}ab";
+        let (input, map) = OffsetMap::strip_bom(original);
+        assert_eq!(input, "ab");
+        assert_eq!(map.to_original(0), 3);
+        assert_eq!(map.end_to_original(0), 0);
+        assert_eq!(map.range_to_original(0..0), 3..3);
+        assert_eq!(map.range_to_original(1..2), 4..5);
+
+        let mut map = OffsetMap::new();
+        map.replace(2..4, 0);
+        assert_eq!(map.range_to_original(0..2), 0..2);
+        assert_eq!(map.range_to_original(2..3), 4..5);
+    }
+
+    #[test]
+    fn longer() {
+        let mut map = OffsetMap::new();
+        // "a\tb" ->  "a    b"
+        map.replace(1..2, 4);
+        assert_eq!(map.range_to_original(1..5), 1..2);
+        assert_eq!(map.range_to_original(2..3), 1..2);
+        assert_eq!(map.range_to_original(5..6), 2..3);
+        assert_eq!(map.to_original(0), 0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn unordered() {
+        let mut map = OffsetMap::new();
+        map.replace(3..5, 1);
+        map.replace(2..3, 1);
+    }
+}
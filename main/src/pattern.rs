@@ -0,0 +1,146 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Pattern matching on typed nodes with [`match_node!`](crate::match_node).
+//!
+//! Patterns:
+//!
+//! - `x` binds a reference to the node to `x`, and `_` ignores it.
+//! - `rule { p }` matches a rule struct of `rule` whose content matches `p`,
+//!   and `rule { p0, p1, ... }` matches one whose content is a sequence.
+//!   Rules that only contain a span, such as atomic rules, can only be bound.
+//! - `(p0, p1, ...)` matches a sequence. Skipped comments and white spaces are left out.
+//!   Trailing items without patterns are ignored.
+//! - `_0(p)`, `_1(p)`, ... match the corresponding alternative of a choice.
+//! - `Some(p)` and `None` match optional nodes.
+//!
+//! Arms are tried in order, and it panics if no arm matches, so the last arm is normally `_ => ...`.
+//! Rule names are checked in debug builds.
+
+use crate::RuleWrapper;
+use pest::RuleType;
+
+/// Check the name of the rule of a rule struct.
+#[doc(hidden)]
+#[inline]
+pub fn check_rule<R: RuleType, T: RuleWrapper<R>>(_node: &T, name: &str) {
+    if cfg!(debug_assertions) {
+        let actual = alloc::format!("{:?}", T::RULE);
+        let expected = name.strip_prefix("r#").unwrap_or(name);
+        assert_eq!(
+            actual, expected,
+            "rule in `match_node!` doesn't match the node"
+        );
+    }
+}
+
+/// Match a reference to a typed node against patterns in order, and evaluate the first arm that matches.
+///
+/// Please see [`pattern`](crate::pattern) for the syntax of patterns.
+///
+/// ```ignore
+/// // pair = { key ~ ":" ~ value }
+/// // value = { string | number }
+/// let text = match_node!(pair;
+///     pair { key, _, value { _0(string) } } => format!("{} is text {}", key.span.as_str(), string.span.as_str()),
+///     pair { key, _, _ } => format!("{} is a number", key.span.as_str()),
+/// );
+/// ```
+#[macro_export]
+macro_rules! match_node {
+    ($node:expr; $($arms:tt)*) => {{
+        let node = $node;
+        #[allow(unreachable_code)]
+        let res = '__match_node: {
+            $crate::__match_node!(@arms '__match_node, node; []; $($arms)*);
+            ::core::unreachable!("no arm in `match_node!` matched")
+        };
+        res
+    }};
+}
+
+/// Implementation of [`match_node!`](crate::match_node).
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __match_node {
+    // Split arms.
+    (@arms $label:lifetime, $node:ident; [$($pat:tt)*]; => $body:block $(,)? $($rest:tt)*) => {
+        $crate::__match_node!(@pat $node, [$($pat)*], { break $label $body; });
+        $crate::__match_node!(@arms $label, $node; []; $($rest)*);
+    };
+    (@arms $label:lifetime, $node:ident; [$($pat:tt)*]; => $body:expr, $($rest:tt)*) => {
+        $crate::__match_node!(@pat $node, [$($pat)*], { break $label $body; });
+        $crate::__match_node!(@arms $label, $node; []; $($rest)*);
+    };
+    (@arms $label:lifetime, $node:ident; [$($pat:tt)*]; => $body:expr) => {
+        $crate::__match_node!(@pat $node, [$($pat)*], { break $label $body; });
+    };
+    (@arms $label:lifetime, $node:ident; [$($pat:tt)*]; $t:tt $($rest:tt)*) => {
+        $crate::__match_node!(@arms $label, $node; [$($pat)* $t]; $($rest)*);
+    };
+    (@arms $label:lifetime, $node:ident; [];) => {};
+
+    // Patterns.
+    (@pat $node:expr, [_], $k:block) => {
+        $k
+    };
+    (@pat $node:expr, [None], $k:block) => {
+        if $node.is_none() $k
+    };
+    (@pat $node:expr, [Some ( $($p:tt)* )], $k:block) => {
+        if let ::core::option::Option::Some(inner) = $node.as_ref() {
+            $crate::__match_node!(@pat inner, [$($p)*], $k);
+        }
+    };
+    (@pat $node:expr, [$choice:ident ( $($p:tt)* )], $k:block) => {
+        if let ::core::option::Option::Some(inner) = $node.$choice() {
+            $crate::__match_node!(@pat inner, [$($p)*], $k);
+        }
+    };
+    (@pat $node:expr, [$rule:ident { $($p:tt)* }], $k:block) => {{
+        let rule = $node;
+        $crate::pattern::check_rule(rule, ::core::stringify!($rule));
+        let content = &*rule.content;
+        $crate::__match_node!(@split content, [], [], $($p)*, $k);
+    }};
+    (@pat $node:expr, [( $($p:tt)* )], $k:block) => {{
+        let content = $node;
+        $crate::__match_node!(@split content, [], [], $($p)*, $k);
+    }};
+    (@pat $node:expr, [$x:ident], $k:block) => {{
+        let $x = $node;
+        $k
+    }};
+
+    // Split patterns separated by commas, then match them on a single node or a sequence.
+    (@split $node:ident, [$($done:tt)*], [$($cur:tt)+], , $($rest:tt)*) => {
+        $crate::__match_node!(@split $node, [$($done)* [$($cur)+]], [], $($rest)*)
+    };
+    (@split $node:ident, [$($done:tt)*], [], , $k:block) => {
+        $crate::__match_node!(@split $node, [$($done)*], [], $k)
+    };
+    (@split $node:ident, [$($done:tt)*], [$($cur:tt)*], $t:tt $($rest:tt)+) => {
+        $crate::__match_node!(@split $node, [$($done)*], [$($cur)* $t], $($rest)+)
+    };
+    (@split $node:ident, [$single:tt], [], $k:block) => {
+        $crate::__match_node!(@pat $node, $single, $k)
+    };
+    (@split $node:ident, [$($done:tt)+], [], $k:block) => {{
+        let matched = $node.get_matched();
+        $crate::__match_node!(@seq matched, [0 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15], [$($done)+], $k)
+    }};
+    (@seq $tuple:ident, [$i:tt $($is:tt)*], [$first:tt $($pats:tt)*], $k:block) => {
+        $crate::__match_node!(@pat $tuple.$i, $first, {
+            $crate::__match_node!(@seq $tuple, [$($is)*], [$($pats)*], $k)
+        })
+    };
+    (@seq $tuple:ident, [$($is:tt)*], [], $k:block) => {
+        $k
+    };
+}
@@ -17,10 +17,65 @@ use core::ops::Range;
 use core::ptr;
 use core::str;
 
+use crate::char_index::CharIndex;
 use crate::formatter::FormatOption;
 
 use super::span;
 
+/// Direction to round an offset that is not on a char boundary, see [`Position::snap_to_char_boundary`].
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum Snap {
+    /// Round down to the start of the char containing the offset.
+    #[default]
+    Backward,
+    /// Round up to the end of the char containing the offset.
+    Forward,
+    /// Round to the closer one of both boundaries, or down if they are equally close.
+    Nearest,
+}
+
+/// Numbers of the first line and the first column, see [`Position::line_col_with`].
+///
+/// Lines and columns are 1-based by default, as in [`Position::line_col`] and messages of errors.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Indexing {
+    /// Number of the first line.
+    pub first_line: usize,
+    /// Number of the first column in a line.
+    pub first_column: usize,
+}
+
+impl Indexing {
+    /// Lines and columns counted from `0`, as in the Language Server Protocol.
+    pub const ZERO_BASED: Self = Self {
+        first_line: 0,
+        first_column: 0,
+    };
+    /// Lines and columns counted from `1`, as in messages of compilers.
+    pub const ONE_BASED: Self = Self {
+        first_line: 1,
+        first_column: 1,
+    };
+    /// Convert a 1-based line and column.
+    pub fn from_one_based(&self, (line, col): (usize, usize)) -> (usize, usize) {
+        (line - 1 + self.first_line, col - 1 + self.first_column)
+    }
+    /// Convert a line and column into 1-based ones,
+    /// or return [`None`] if they are before the first line or column.
+    pub fn to_one_based(&self, (line, col): (usize, usize)) -> Option<(usize, usize)> {
+        Some((
+            line.checked_sub(self.first_line)? + 1,
+            col.checked_sub(self.first_column)? + 1,
+        ))
+    }
+}
+
+impl Default for Indexing {
+    fn default() -> Self {
+        Self::ONE_BASED
+    }
+}
+
 /// A cursor position in a `&str` which provides useful methods to manually parse that string.
 #[derive(Clone, Copy)]
 pub struct Position<'i> {
@@ -58,6 +113,43 @@ impl<'i> Position<'i> {
         input.get(pos..).map(|_| Position { input, pos })
     }
 
+    /// Creates a `Position` at the char boundary next to `pos` in given direction.
+    ///
+    /// Offsets beyond the end of the input are clamped to its end.
+    /// Useful for offsets computed outside, such as from an editor.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pest_typed::{Position, Snap};
+    /// let input = "a💖b";
+    /// assert_eq!(Position::snap_to_char_boundary(input, 2, Snap::Backward).pos(), 1);
+    /// assert_eq!(Position::snap_to_char_boundary(input, 2, Snap::Forward).pos(), 5);
+    /// assert_eq!(Position::snap_to_char_boundary(input, 4, Snap::Nearest).pos(), 5);
+    /// assert_eq!(Position::snap_to_char_boundary(input, 9, Snap::Nearest).pos(), 6);
+    /// ```
+    pub fn snap_to_char_boundary(input: &str, pos: usize, snap: Snap) -> Position<'_> {
+        let pos = pos.min(input.len());
+        let floor = (0..=pos)
+            .rev()
+            .find(|&i| input.is_char_boundary(i))
+            .unwrap_or(0);
+        let pos = if floor == pos {
+            pos
+        } else {
+            let ceil = (pos..=input.len())
+                .find(|&i| input.is_char_boundary(i))
+                .unwrap_or(input.len());
+            match snap {
+                Snap::Backward => floor,
+                Snap::Forward => ceil,
+                Snap::Nearest if ceil - pos < pos - floor => ceil,
+                Snap::Nearest => floor,
+            }
+        };
+        Position { input, pos }
+    }
+
     /// Creates a `Position` at the start of a `&str`.
     ///
     /// # Examples
@@ -182,6 +274,151 @@ impl<'i> Position<'i> {
         line_col
     }
 
+    /// Returns the line number and the column of this `Position`, counted with `indexing`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pest_typed::{Indexing, Position};
+    /// let pos = Position::new("a\nbc", 3).unwrap();
+    /// assert_eq!(pos.line_col_with(Indexing::ONE_BASED), (2, 2));
+    /// assert_eq!(pos.line_col_with(Indexing::ZERO_BASED), (1, 1));
+    /// ```
+    #[inline]
+    pub fn line_col_with(&self, indexing: Indexing) -> (usize, usize) {
+        indexing.from_one_based(self.line_col())
+    }
+
+    /// Returns the line number and the column of this `Position` in UTF-16 code units, both 1-based.
+    ///
+    /// Characters outside the Basic Multilingual Plane take two columns.
+    /// Lines are separated by `\n` (or `\r\n`), the same as [`Position::line_col`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pest_typed::Position;
+    /// let input = "a\n💖b";
+    /// let pos = Position::new(input, input.len() - 1).unwrap();
+    /// assert_eq!(pos.line_col(), (2, 2));
+    /// assert_eq!(pos.line_col_utf16(), (2, 3));
+    /// ```
+    #[inline]
+    pub fn line_col_utf16(&self) -> (usize, usize) {
+        if self.pos > self.input.len() {
+            panic!("position out of bounds");
+        }
+        let before = &self.input[..self.pos];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        (
+            before.matches('\n').count() + 1,
+            before[line_start..].encode_utf16().count() + 1,
+        )
+    }
+
+    /// Number of chars before the `Position`, looked up in `index` of its input.
+    ///
+    /// Each lookup only counts chars in a block of the input,
+    /// so converting offsets of all nodes in a tree is linear in the size of the input.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pest_typed::{char_index::CharIndex, Position};
+    /// let input = "a\n💖b";
+    /// let index = CharIndex::new(input);
+    /// let pos = Position::new(input, input.len() - 1).unwrap();
+    /// assert_eq!(pos.char_offset(&index), 3);
+    /// ```
+    #[inline]
+    pub fn char_offset(&self, index: &CharIndex<'_>) -> usize {
+        debug_assert!(ptr::eq(self.input, index.input()));
+        index.char_offset(self.pos)
+    }
+
+    /// Creates a `Position` from a 1-based line number and a 1-based column in code points,
+    /// the inverse of [`Position::line_col`].
+    ///
+    /// Returns `None` if the line or the column is out of range.
+    /// The column may point to the line break or the end of the line.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pest_typed::Position;
+    /// let input = "a\n💖b";
+    /// assert_eq!(Position::from_line_col(input, 2, 2).unwrap().pos(), 6);
+    /// assert_eq!(Position::from_line_col(input, 2, 4), None);
+    /// ```
+    pub fn from_line_col(input: &str, line: usize, col: usize) -> Option<Position<'_>> {
+        Self::from_line_col_by(input, line, col, |_| 1)
+    }
+
+    /// Creates a `Position` from a line number and a column in code points counted with `indexing`,
+    /// the inverse of [`Position::line_col_with`].
+    ///
+    /// Returns `None` if the line or the column is out of range.
+    pub fn from_line_col_with(
+        input: &str,
+        line: usize,
+        col: usize,
+        indexing: Indexing,
+    ) -> Option<Position<'_>> {
+        let (line, col) = indexing.to_one_based((line, col))?;
+        Self::from_line_col(input, line, col)
+    }
+
+    /// Creates a `Position` from a 1-based line number and a 1-based column in UTF-16 code units,
+    /// the inverse of [`Position::line_col_utf16`].
+    ///
+    /// Returns `None` if the line or the column is out of range,
+    /// or if the column points into the middle of a surrogate pair.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pest_typed::Position;
+    /// let input = "a\n💖b";
+    /// assert_eq!(Position::from_line_col_utf16(input, 2, 3).unwrap().pos(), 6);
+    /// assert_eq!(Position::from_line_col_utf16(input, 2, 2), None);
+    /// ```
+    pub fn from_line_col_utf16(input: &str, line: usize, col: usize) -> Option<Position<'_>> {
+        Self::from_line_col_by(input, line, col, char::len_utf16)
+    }
+
+    fn from_line_col_by(
+        input: &str,
+        line: usize,
+        col: usize,
+        width: impl Fn(char) -> usize,
+    ) -> Option<Position<'_>> {
+        let line_start = match line {
+            0 => return None,
+            1 => 0,
+            _ => input.match_indices('\n').nth(line - 2)?.0 + 1,
+        };
+        let mut remaining = col.checked_sub(1)?;
+        for (i, c) in input[line_start..].char_indices() {
+            if remaining == 0 {
+                return Some(Position {
+                    input,
+                    pos: line_start + i,
+                });
+            }
+            if c == '\n' {
+                return None;
+            }
+            remaining = remaining.checked_sub(width(c))?;
+        }
+        match remaining {
+            0 => Some(Position {
+                input,
+                pos: input.len(),
+            }),
+            _ => None,
+        }
+    }
+
     /// Returns the entire line of the input that contains this `Position`.
     ///
     /// # Examples
@@ -243,22 +480,36 @@ impl<'i> Position<'i> {
         }
     }
 
+    /// Returns the rest of the input from the `Position`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pest_typed::Position;
+    /// let input = "ab";
+    /// assert_eq!(Position::new(input, 1).unwrap().rest(), "b");
+    /// ```
+    #[inline]
+    pub fn rest(&self) -> &'i str {
+        &self.input[self.pos..]
+    }
+
     /// Returns `true` when the `Position` points to the start of the input `&str`.
     #[inline]
-    pub(crate) fn at_start(&self) -> bool {
+    pub fn at_start(&self) -> bool {
         self.pos == 0
     }
 
     /// Returns `true` when the `Position` points to the end of the input `&str`.
     #[inline]
-    pub(crate) fn at_end(&self) -> bool {
+    pub fn at_end(&self) -> bool {
         self.pos == self.input.len()
     }
 
     /// Skips `n` `char`s from the `Position` and returns `true` if the skip was possible or `false`
     /// otherwise. If the return value is `false`, `pos` will not be updated.
     #[inline]
-    pub(crate) fn skip(&mut self, n: usize) -> bool {
+    pub fn skip(&mut self, n: usize) -> bool {
         let skipped = {
             let mut len = 0;
             // Position's pos is always a UTF-8 border.
@@ -280,8 +531,7 @@ impl<'i> Position<'i> {
     /// Goes back `n` `char`s from the `Position` and returns `true` if the skip was possible or `false`
     /// otherwise. If the return value is `false`, `pos` will not be updated.
     #[inline]
-    #[allow(dead_code)]
-    pub(crate) fn skip_back(&mut self, n: usize) -> bool {
+    pub fn skip_back(&mut self, n: usize) -> bool {
         let skipped = {
             let mut len = 0;
             // Position's pos is always a UTF-8 border.
@@ -303,7 +553,7 @@ impl<'i> Position<'i> {
     /// Skips until one of the given `strings` is found. If none of the `strings` can be found,
     /// this function will return `false` but its `pos` will *still* be updated.
     #[inline]
-    pub(crate) fn skip_until(&mut self, strings: &[&str]) -> bool {
+    pub fn skip_until(&mut self, strings: &[&str]) -> bool {
         #[cfg(not(feature = "memchr"))]
         {
             self.skip_until_basic(strings)
@@ -382,16 +632,14 @@ impl<'i> Position<'i> {
     /// was made. If no match was made, returns `false`.
     /// `pos` will not be updated in either case.
     #[inline]
-    #[allow(dead_code)]
-    pub(crate) fn match_char(&self, c: char) -> bool {
+    pub fn match_char(&self, c: char) -> bool {
         matches!(self.input[self.pos..].chars().next(), Some(cc) if c == cc)
     }
 
     /// Matches the char at the `Position` against a filter function and returns `true` if a match
     /// was made. If no match was made, returns `false` and `pos` will not be updated.
     #[inline]
-    #[allow(dead_code)]
-    pub(crate) fn match_char_by<F>(&mut self, f: F) -> bool
+    pub fn match_char_by<F>(&mut self, f: F) -> bool
     where
         F: FnOnce(char) -> bool,
     {
@@ -410,7 +658,7 @@ impl<'i> Position<'i> {
     /// Matches `string` from the `Position` and returns `true` if a match was made or `false`
     /// otherwise. If no match was made, `pos` will not be updated.
     #[inline]
-    pub(crate) fn match_string(&mut self, string: &str) -> bool {
+    pub fn match_string(&mut self, string: &str) -> bool {
         let to = self.pos + string.len();
 
         if Some(string.as_bytes()) == self.input.as_bytes().get(self.pos..to) {
@@ -424,7 +672,7 @@ impl<'i> Position<'i> {
     /// Case-insensitively matches `string` from the `Position` and returns `true` if a match was
     /// made or `false` otherwise. If no match was made, `pos` will not be updated.
     #[inline]
-    pub(crate) fn match_insensitive(&mut self, string: &str) -> bool {
+    pub fn match_insensitive(&mut self, string: &str) -> bool {
         let matched = {
             let slice = &self.input[self.pos..];
             if let Some(slice) = slice.get(0..string.len()) {
@@ -445,7 +693,7 @@ impl<'i> Position<'i> {
     /// Matches `char` `range` from the `Position` and returns `true` if a match was made or `false`
     /// otherwise. If no match was made, `pos` will not be updated.
     #[inline]
-    pub(crate) fn match_range(&mut self, range: Range<char>) -> bool {
+    pub fn match_range(&mut self, range: Range<char>) -> bool {
         if let Some(c) = self.input[self.pos..].chars().next() {
             if range.start <= c && c <= range.end {
                 self.pos += c.len_utf8();
@@ -554,6 +802,78 @@ mod tests {
         assert_eq!(Position::new(input, 7).unwrap().line_col(), (1, 6));
     }
 
+    #[test]
+    fn line_col_with() {
+        let input = "ab\ncd";
+        let pos = Position::new(input, 4).unwrap();
+        assert_eq!(pos.line_col_with(Indexing::ONE_BASED), (2, 2));
+        assert_eq!(pos.line_col_with(Indexing::ZERO_BASED), (1, 1));
+        assert_eq!(
+            Position::from_line_col_with(input, 1, 1, Indexing::ZERO_BASED),
+            Some(pos)
+        );
+        assert_eq!(Indexing::ONE_BASED.to_one_based((0, 1)), None);
+    }
+
+    #[test]
+    fn line_col_utf16() {
+        let input = "a\rb\nc\r\nd嗨💖e";
+
+        for (pos, line_col) in [
+            (0, (1, 1)),
+            (3, (1, 4)),
+            (4, (2, 1)),
+            (6, (2, 3)),
+            (7, (3, 1)),
+            (11, (3, 3)),
+            (15, (3, 5)),
+            (16, (3, 6)),
+        ] {
+            let position = Position::new(input, pos).unwrap();
+            assert_eq!(position.line_col_utf16(), line_col);
+            assert_eq!(
+                Position::from_line_col_utf16(input, line_col.0, line_col.1),
+                Some(position)
+            );
+        }
+        assert_eq!(Position::from_line_col_utf16(input, 3, 4), None);
+        assert_eq!(Position::from_line_col_utf16(input, 3, 7), None);
+        assert_eq!(Position::from_line_col_utf16(input, 2, 4), None);
+        assert_eq!(Position::from_line_col_utf16(input, 4, 1), None);
+        assert_eq!(Position::from_line_col_utf16(input, 0, 1), None);
+        assert_eq!(Position::from_line_col_utf16(input, 1, 0), None);
+    }
+
+    #[test]
+    fn from_line_col() {
+        let input = "a\rb\nc\r\nd嗨💖e";
+
+        for pos in [0, 1, 2, 3, 4, 5, 6, 7, 8, 11, 15, 16] {
+            let position = Position::new(input, pos).unwrap();
+            let (line, col) = position.line_col();
+            assert_eq!(Position::from_line_col(input, line, col), Some(position));
+        }
+        assert_eq!(Position::from_line_col(input, 3, 6), None);
+    }
+
+    #[test]
+    fn snap_to_char_boundary() {
+        let input = "嗨a";
+        let snap = |pos, snap| Position::snap_to_char_boundary(input, pos, snap).pos();
+        for pos in [0, 3, 4] {
+            assert_eq!(snap(pos, Snap::Nearest), pos);
+        }
+        assert_eq!(snap(1, Snap::Nearest), 0);
+        assert_eq!(snap(2, Snap::Nearest), 3);
+        assert_eq!(snap(1, Snap::Forward), 3);
+        assert_eq!(snap(2, Snap::Backward), 0);
+        assert_eq!(snap(5, Snap::Forward), 4);
+        assert_eq!(
+            Position::snap_to_char_boundary("", 1, Snap::Nearest).pos(),
+            0
+        );
+    }
+
     #[test]
     fn line_of() {
         let input = "a\rb\nc\r\nd嗨";
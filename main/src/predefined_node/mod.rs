@@ -21,6 +21,7 @@ use super::{
     wrapper::{StringArrayWrapper, StringWrapper},
     RuleType, TypedNode,
 };
+use alloc::vec::Vec;
 use core::ops::{Deref, DerefMut};
 use core::{fmt::Debug, marker::PhantomData};
 use custom_debug_derive::Debug as Dbg;
@@ -50,16 +51,28 @@ impl<'i, R: RuleType, T: StringWrapper> TypedNode<'i, R> for Str<T> {
     fn try_parse_with(
         mut input: Position<'i>,
         _stack: &mut Stack<Span<'i>>,
-        _tracker: &mut Tracker<'i, R>,
+        tracker: &mut Tracker<'i, R>,
     ) -> Result<(Position<'i>, Self), ()> {
-        if input.match_string(Self::CONTENT) {
-            Ok((input, Self::from(())))
-        } else {
-            Err(())
+        match literal(&mut input, tracker, Self::CONTENT) {
+            true => Ok((input, Self::from(()))),
+            false => Err(()),
         }
     }
 }
 
+/// Match `content` case sensitively, and report it to `tracker` if it's not matched.
+fn literal<'i, R: RuleType>(
+    input: &mut Position<'i>,
+    tracker: &mut Tracker<'i, R>,
+    content: &'static str,
+) -> bool {
+    let matched = input.match_string(content);
+    if !matched {
+        tracker.literal_failed(*input, content);
+    }
+    matched
+}
+
 /// Match given string case insensitively.
 ///
 /// - The field `content` is the matched string.
@@ -90,13 +103,14 @@ impl<'i, R: RuleType, T: StringWrapper> TypedNode<'i, R> for Insens<'i, T> {
     fn try_parse_with(
         mut input: Position<'i>,
         _stack: &mut Stack<Span<'i>>,
-        _tracker: &mut Tracker<'i, R>,
+        tracker: &mut Tracker<'i, R>,
     ) -> Result<(Position<'i>, Self), ()> {
         let start = input;
         if input.match_insensitive(Self::CONTENT) {
             let span = start.span(&input);
             Ok((input, Self::from(span.as_str())))
         } else {
+            tracker.insensitive_literal_failed(input, Self::CONTENT);
             Err(())
         }
     }
@@ -188,7 +202,7 @@ impl<'i, R: RuleType, const MIN: char, const MAX: char> TypedNode<'i, R> for Cha
 
 /// Try to create stack slice.
 #[inline]
-fn stack_slice<'i, 's, R: RuleType>(
+pub(crate) fn stack_slice<'i, 's, R: RuleType>(
     input: Position<'i>,
     start: i32,
     end: Option<i32>,
@@ -212,7 +226,7 @@ fn stack_slice<'i, 's, R: RuleType>(
 /// Match a part of the stack without popping.
 /// Will match (consume) input.
 #[inline]
-fn peek_spans<'s, 'i: 's, R: RuleType>(
+pub(crate) fn peek_spans<'s, 'i: 's, R: RuleType>(
     input: Position<'i>,
     iter: impl Iterator<Item = &'s Span<'i>>,
     _tracker: &mut Tracker<'i, R>,
@@ -299,8 +313,9 @@ impl<'i, R: RuleType, T: TypedNode<'i, R>> TypedNode<'i, R> for Negative<T> {
         tracker.negative_during(|tracker| {
             stack.snapshot();
             match T::try_parse_with(input, stack, tracker) {
-                Ok(_) => {
+                Ok((end, _)) => {
                     stack.restore();
+                    tracker.negative_matched(input, end);
                     Err(())
                 }
                 Err(_) => {
@@ -338,8 +353,11 @@ impl<'i, R: RuleType> TypedNode<'i, R> for ANY {
 
 /// Match the start of input.
 #[derive(Clone, Debug, Hash, PartialEq, Eq)]
-pub struct SOI;
-impl<'i, R: RuleType> TypedNode<'i, R> for SOI {
+pub struct SOI<'i> {
+    /// Matched position, which is the start of input.
+    pub position: Position<'i>,
+}
+impl<'i, R: RuleType> TypedNode<'i, R> for SOI<'i> {
     #[inline]
     fn try_parse_with(
         input: Position<'i>,
@@ -347,7 +365,7 @@ impl<'i, R: RuleType> TypedNode<'i, R> for SOI {
         _tracker: &mut Tracker<'i, R>,
     ) -> Result<(Position<'i>, Self), ()> {
         if input.at_start() {
-            Ok((input, Self))
+            Ok((input, Self { position: input }))
         } else {
             Err(())
         }
@@ -358,8 +376,11 @@ impl<'i, R: RuleType> TypedNode<'i, R> for SOI {
 ///
 /// [`EOI`] will record its rule if not matched.
 #[derive(Clone, Debug, Hash, PartialEq, Eq)]
-pub struct EOI;
-impl<'i, R: RuleType> TypedNode<'i, R> for EOI {
+pub struct EOI<'i> {
+    /// Matched position, which is the end of input.
+    pub position: Position<'i>,
+}
+impl<'i, R: RuleType> TypedNode<'i, R> for EOI<'i> {
     #[inline]
     fn try_parse_with(
         input: Position<'i>,
@@ -367,13 +388,81 @@ impl<'i, R: RuleType> TypedNode<'i, R> for EOI {
         _tracker: &mut Tracker<'i, R>,
     ) -> Result<(Position<'i>, Self), ()> {
         if input.at_end() {
-            Ok((input, Self))
+            Ok((input, Self { position: input }))
         } else {
             Err(())
         }
     }
 }
 
+/// Match `T` after the start of input if `START`, and then the end of input as `End` if `END`.
+///
+/// Generated for sequences that start with `SOI` or end with `EOI` if `do_not_emit_boundaries` is set,
+/// so that the boundaries are checked but not kept in the tree.
+/// Trivia between `T` and the boundaries is skipped as in a sequence.
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub struct Bounded<T, End, Skip, const SKIP: usize, const START: bool, const END: bool> {
+    /// Matched content.
+    pub content: T,
+    _phantom: PhantomData<(End, Skip)>,
+}
+impl<T, End, Skip, const SKIP: usize, const START: bool, const END: bool> From<T>
+    for Bounded<T, End, Skip, SKIP, START, END>
+{
+    fn from(content: T) -> Self {
+        Self {
+            content,
+            _phantom: PhantomData,
+        }
+    }
+}
+impl<T, End, Skip, const SKIP: usize, const START: bool, const END: bool> Deref
+    for Bounded<T, End, Skip, SKIP, START, END>
+{
+    type Target = T;
+    fn deref(&self) -> &Self::Target {
+        &self.content
+    }
+}
+impl<T, End, Skip, const SKIP: usize, const START: bool, const END: bool> DerefMut
+    for Bounded<T, End, Skip, SKIP, START, END>
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.content
+    }
+}
+impl<
+        'i,
+        R: RuleType,
+        T: TypedNode<'i, R>,
+        End: TypedNode<'i, R>,
+        Skip: NeverFailedTypedNode<'i, R>,
+        const SKIP: usize,
+        const START: bool,
+        const END: bool,
+    > TypedNode<'i, R> for Bounded<T, End, Skip, SKIP, START, END>
+{
+    #[inline]
+    fn try_parse_with(
+        mut input: Position<'i>,
+        stack: &mut Stack<Span<'i>>,
+        tracker: &mut Tracker<'i, R>,
+    ) -> Result<(Position<'i>, Self), ()> {
+        if START {
+            if !input.at_start() {
+                return Err(());
+            }
+            skip::<R, Skip, SKIP>(&mut input, stack, tracker);
+        }
+        let (mut input, content) = T::try_parse_with(input, stack, tracker)?;
+        if END {
+            skip::<R, Skip, SKIP>(&mut input, stack, tracker);
+            (input, _) = End::try_parse_with(input, stack, tracker)?;
+        }
+        Ok((input, Self::from(content)))
+    }
+}
+
 /// Type of eol.
 #[derive(Clone, Debug, Hash, PartialEq, Eq)]
 pub enum NewLineType {
@@ -419,6 +508,8 @@ impl<'i, R: RuleType> TypedNode<'i, R> for NEWLINE {
 pub struct PEEK_ALL<'i> {
     /// Pair span.
     pub span: Span<'i>,
+    /// Pushed spans that are matched, from the top to the bottom of the stack.
+    pub pushed: Vec<Span<'i>>,
 }
 impl<'i, R: RuleType> TypedNode<'i, R> for PEEK_ALL<'i> {
     #[inline]
@@ -428,8 +519,9 @@ impl<'i, R: RuleType> TypedNode<'i, R> for PEEK_ALL<'i> {
         tracker: &mut Tracker<'i, R>,
     ) -> Result<(Position<'i>, Self), ()> {
         let spans = stack[0..stack.len()].iter().rev();
-        let (input, span) = peek_spans::<R>(input, spans, tracker)?;
-        Ok((input, Self { span }))
+        let (input, span) = peek_spans::<R>(input, spans.clone(), tracker)?;
+        let pushed = spans.copied().collect();
+        Ok((input, Self { span, pushed }))
     }
 }
 
@@ -440,11 +532,8 @@ impl<'i, R: RuleType> TypedNode<'i, R> for PEEK_ALL<'i> {
 pub struct PEEK<'i> {
     /// Pair span.
     pub span: Span<'i>,
-}
-impl<'i> From<Span<'i>> for PEEK<'i> {
-    fn from(span: Span<'i>) -> Self {
-        Self { span }
-    }
+    /// The top of the stack that's matched, which is where its content was pushed.
+    pub pushed: Span<'i>,
 }
 impl<'i, R: RuleType> TypedNode<'i, R> for PEEK<'i> {
     #[inline]
@@ -455,8 +544,14 @@ impl<'i, R: RuleType> TypedNode<'i, R> for PEEK<'i> {
     ) -> Result<(Position<'i>, Self), ()> {
         let start = input;
         match stack.peek() {
-            Some(string) => match input.match_string(string.as_str()) {
-                true => Ok((input, Self::from(start.span(&input)))),
+            Some(&pushed) => match input.match_string(pushed.as_str()) {
+                true => Ok((
+                    input,
+                    Self {
+                        span: start.span(&input),
+                        pushed,
+                    },
+                )),
                 false => Err(()),
             },
             None => {
@@ -475,6 +570,31 @@ pub struct Skipped<T, Skip, const SKIP: usize> {
     /// Matched content.
     pub matched: T,
 }
+impl<T, Skip: Default, const SKIP: usize> Skipped<T, Skip, SKIP> {
+    /// Wrap `matched` with default (normally empty) skipped contents.
+    pub fn new(matched: T) -> Self {
+        Self {
+            skipped: core::array::from_fn(|_| Skip::default()),
+            matched,
+        }
+    }
+}
+impl<T, Skip: Default, const SKIP: usize> From<T> for Skipped<T, Skip, SKIP> {
+    fn from(matched: T) -> Self {
+        Self::new(matched)
+    }
+}
+impl<T, Skip, const SKIP: usize> Deref for Skipped<T, Skip, SKIP> {
+    type Target = T;
+    fn deref(&self) -> &Self::Target {
+        &self.matched
+    }
+}
+impl<T, Skip, const SKIP: usize> DerefMut for Skipped<T, Skip, SKIP> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.matched
+    }
+}
 impl<T: Debug, Skip: Debug, const SKIP: usize> Debug for Skipped<T, Skip, SKIP> {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         if SKIP > 0 {
@@ -488,6 +608,63 @@ impl<T: Debug, Skip: Debug, const SKIP: usize> Debug for Skipped<T, Skip, SKIP>
     }
 }
 
+/// Skip contents by `Skip` for `SKIP` times, which is used between parts of sequences and repetitions.
+///
+/// Nothing is skipped in raw mode. See [`Tracker::with_raw`].
+pub fn skip<'i, R: RuleType, Skip: NeverFailedTypedNode<'i, R>, const SKIP: usize>(
+    input: &mut Position<'i>,
+    stack: &mut Stack<Span<'i>>,
+    tracker: &Tracker<'i, R>,
+) -> [Skip; SKIP] {
+    if tracker.is_raw() {
+        return core::array::from_fn(|_| Skip::default());
+    }
+    core::array::from_fn(|_| {
+        let (next, skipped) = Skip::parse_with(*input, stack, tracker);
+        *input = next;
+        skipped
+    })
+}
+
+/// Match `T` if the matched content doesn't contain line breaks.
+///
+/// Used in skipped contents of rules where newlines don't count as trivia.
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub struct SameLine<T> {
+    /// Matched content.
+    pub content: T,
+}
+impl<T> From<T> for SameLine<T> {
+    fn from(content: T) -> Self {
+        Self { content }
+    }
+}
+impl<T> Deref for SameLine<T> {
+    type Target = T;
+    fn deref(&self) -> &Self::Target {
+        &self.content
+    }
+}
+impl<T> DerefMut for SameLine<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.content
+    }
+}
+impl<'i, R: RuleType, T: TypedNode<'i, R>> TypedNode<'i, R> for SameLine<T> {
+    #[inline]
+    fn try_parse_with(
+        input: Position<'i>,
+        stack: &mut Stack<Span<'i>>,
+        tracker: &mut Tracker<'i, R>,
+    ) -> Result<(Position<'i>, Self), ()> {
+        let (end, content) = T::try_parse_with(input, stack, tracker)?;
+        match input.span(&end).as_str().contains(['\n', '\r']) {
+            true => Err(()),
+            false => Ok((end, Self::from(content))),
+        }
+    }
+}
+
 /// Drop the top of the stack.
 ///
 /// Fail if there is no span in the stack.
@@ -515,13 +692,10 @@ impl<'i, R: RuleType> TypedNode<'i, R> for DROP {
 pub struct POP<'i> {
     /// Matched span.
     pub span: Span<'i>,
+    /// The popped span, which is where its content was pushed.
+    pub pushed: Span<'i>,
 }
 
-impl<'i> From<Span<'i>> for POP<'i> {
-    fn from(span: Span<'i>) -> Self {
-        Self { span }
-    }
-}
 impl<'i, R: RuleType> TypedNode<'i, R> for POP<'i> {
     #[inline]
     fn try_parse_with(
@@ -529,9 +703,16 @@ impl<'i, R: RuleType> TypedNode<'i, R> for POP<'i> {
         stack: &mut Stack<Span<'i>>,
         tracker: &mut Tracker<'i, R>,
     ) -> Result<(Position<'i>, Self), ()> {
+        let start = input;
         match stack.pop() {
-            Some(span) => match input.match_string(span.as_str()) {
-                true => Ok((input, Self::from(span))),
+            Some(pushed) => match input.match_string(pushed.as_str()) {
+                true => Ok((
+                    input,
+                    Self {
+                        span: start.span(&input),
+                        pushed,
+                    },
+                )),
                 false => Err(()),
             },
             None => {
@@ -548,11 +729,8 @@ impl<'i, R: RuleType> TypedNode<'i, R> for POP<'i> {
 pub struct POP_ALL<'i> {
     /// Matched span.
     pub span: Span<'i>,
-}
-impl<'i> From<Span<'i>> for POP_ALL<'i> {
-    fn from(span: Span<'i>) -> Self {
-        Self { span }
-    }
+    /// Popped spans, from the top to the bottom of the stack.
+    pub pushed: Vec<Span<'i>>,
 }
 impl<'i, R: RuleType> TypedNode<'i, R> for POP_ALL<'i> {
     #[inline]
@@ -561,9 +739,9 @@ impl<'i, R: RuleType> TypedNode<'i, R> for POP_ALL<'i> {
         stack: &mut Stack<Span<'i>>,
         tracker: &mut Tracker<'i, R>,
     ) -> Result<(Position<'i>, Self), ()> {
-        let (input, res) = PEEK_ALL::try_parse_with(input, stack, tracker)?;
+        let (input, PEEK_ALL { span, pushed }) = PEEK_ALL::try_parse_with(input, stack, tracker)?;
         while stack.pop().is_some() {}
-        Ok((input, Self::from(res.span)))
+        Ok((input, Self { span, pushed }))
     }
 }
 
@@ -596,7 +774,11 @@ impl<'i> Default for Empty<'i> {
 }
 impl<'i, R: RuleType> NeverFailedTypedNode<'i, R> for Empty<'i> {
     #[inline]
-    fn parse_with(input: Position<'i>, _stack: &mut Stack<Span<'i>>) -> (Position<'i>, Self) {
+    fn parse_with(
+        input: Position<'i>,
+        _stack: &mut Stack<Span<'i>>,
+        _tracker: &Tracker<'i, R>,
+    ) -> (Position<'i>, Self) {
         (input, Self::default())
     }
 }
@@ -605,10 +787,10 @@ impl<'i, R: RuleType> TypedNode<'i, R> for Empty<'i> {
     fn try_parse_with(
         input: Position<'i>,
         stack: &mut Stack<Span<'i>>,
-        _tracker: &mut Tracker<'i, R>,
+        tracker: &mut Tracker<'i, R>,
     ) -> Result<(Position<'i>, Self), ()> {
         Ok(<Self as NeverFailedTypedNode<'i, R>>::parse_with(
-            input, stack,
+            input, stack, tracker,
         ))
     }
 }
@@ -650,10 +832,15 @@ impl<T> DerefMut for Push<T> {
 }
 
 /// Match `[START..END]` in top-to-bottom order of the stack.
-#[derive(Clone, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
-pub struct PeekSlice2<const START: i32, const END: i32>;
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub struct PeekSlice2<'i, const START: i32, const END: i32> {
+    /// Matched span.
+    pub span: Span<'i>,
+    /// Pushed spans that are matched, in the order they're matched.
+    pub pushed: Vec<Span<'i>>,
+}
 impl<'i, R: RuleType, const START: i32, const END: i32> TypedNode<'i, R>
-    for PeekSlice2<START, END>
+    for PeekSlice2<'i, START, END>
 {
     #[inline]
     fn try_parse_with(
@@ -662,15 +849,21 @@ impl<'i, R: RuleType, const START: i32, const END: i32> TypedNode<'i, R>
         tracker: &mut Tracker<'i, R>,
     ) -> Result<(Position<'i>, Self), ()> {
         let spans = stack_slice(input, START, Some(END), stack, tracker)?;
-        let (input, _) = peek_spans::<R>(input, spans, tracker)?;
-        Ok((input, Self))
+        let (input, span) = peek_spans::<R>(input, spans.clone(), tracker)?;
+        let pushed = spans.copied().collect();
+        Ok((input, Self { span, pushed }))
     }
 }
 
 /// Match `[START..]` in top-to-bottom order of the stack.
-#[derive(Clone, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
-pub struct PeekSlice1<const START: i32>;
-impl<'i, R: RuleType, const START: i32> TypedNode<'i, R> for PeekSlice1<START> {
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub struct PeekSlice1<'i, const START: i32> {
+    /// Matched span.
+    pub span: Span<'i>,
+    /// Pushed spans that are matched, in the order they're matched.
+    pub pushed: Vec<Span<'i>>,
+}
+impl<'i, R: RuleType, const START: i32> TypedNode<'i, R> for PeekSlice1<'i, START> {
     #[inline]
     fn try_parse_with(
         input: Position<'i>,
@@ -678,8 +871,9 @@ impl<'i, R: RuleType, const START: i32> TypedNode<'i, R> for PeekSlice1<START> {
         tracker: &mut Tracker<'i, R>,
     ) -> Result<(Position<'i>, Self), ()> {
         let spans = stack_slice(input, START, None, stack, tracker)?;
-        let (input, _) = peek_spans::<R>(input, spans, tracker)?;
-        Ok((input, Self))
+        let (input, span) = peek_spans::<R>(input, spans.clone(), tracker)?;
+        let pushed = spans.copied().collect();
+        Ok((input, Self { span, pushed }))
     }
 }
 
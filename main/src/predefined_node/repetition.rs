@@ -11,85 +11,153 @@
 //! The generator may use this for convenience.
 //! Normally you don't need to reference this module by yourself.
 
-use core::ops::{Deref, DerefMut};
+use core::{
+    fmt::Debug,
+    hash::{Hash, Hasher},
+    ops::{Deref, DerefMut},
+};
 
 use crate::{
-    predefined_node::{restore_on_err, Skipped},
+    allocation::{Allocation, Container, Global},
+    predefined_node::{restore_on_err, skip, Skipped},
     tracker::Tracker,
     wrapper::BoundWrapper,
     NeverFailedTypedNode, Position, RuleType, Span, Stack, TypedNode,
 };
-use alloc::vec::Vec;
 
 type Iter<'n, T, IGNORED, const SKIP: usize> = core::iter::Map<
     alloc::slice::Iter<'n, Skipped<T, IGNORED, SKIP>>,
     fn(&'n Skipped<T, IGNORED, SKIP>) -> &'n T,
 >;
-type IntoIter<T, IGNORED, const SKIP: usize> = core::iter::Map<
-    alloc::vec::IntoIter<Skipped<T, IGNORED, SKIP>>,
+type IntoIter<T, IGNORED, const SKIP: usize, A> = core::iter::Map<
+    <<A as Allocation>::Vec<Skipped<T, IGNORED, SKIP>> as IntoIterator>::IntoIter,
     fn(Skipped<T, IGNORED, SKIP>) -> T,
 >;
 
+/// Implement traits by those of the container, so that allocations don't need to implement them.
+macro_rules! impl_by_content {
+    ($name:ident, $($args:ident: $t:ty),*) => {
+        impl<T, $(const $args: $t, )* A: Allocation> Clone for $name<T, $($args, )* A>
+        where
+            A::Vec<T>: Clone,
+        {
+            fn clone(&self) -> Self {
+                let content = self.content.clone();
+                Self { content }
+            }
+        }
+        impl<T, $(const $args: $t, )* A: Allocation> Debug for $name<T, $($args, )* A>
+        where
+            A::Vec<T>: Debug,
+        {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                f.debug_struct(stringify!($name))
+                    .field("content", &self.content)
+                    .finish()
+            }
+        }
+        impl<T, $(const $args: $t, )* A: Allocation> Hash for $name<T, $($args, )* A>
+        where
+            A::Vec<T>: Hash,
+        {
+            fn hash<H: Hasher>(&self, state: &mut H) {
+                self.content.hash(state)
+            }
+        }
+        impl<T, $(const $args: $t, )* A: Allocation> PartialEq for $name<T, $($args, )* A>
+        where
+            A::Vec<T>: PartialEq,
+        {
+            fn eq(&self, other: &Self) -> bool {
+                self.content == other.content
+            }
+        }
+        impl<T, $(const $args: $t, )* A: Allocation> Eq for $name<T, $($args, )* A> where
+            A::Vec<T>: Eq
+        {
+        }
+    };
+}
+
+impl_by_content!(AtomicRep,);
+impl_by_content!(RepMin, MIN: usize);
+impl_by_content!(RepMinMax, MIN: usize, MAX: usize);
+
 /// Repeatably match `T` at least `MIN` times.
-#[derive(Clone, Debug, Hash, PartialEq, Eq)]
-pub struct AtomicRep<T> {
+pub struct AtomicRep<T, A: Allocation = Global> {
     /// Skipped and Matched expressions.
-    pub content: Vec<T>,
+    pub content: A::Vec<T>,
 }
-impl<T> Default for AtomicRep<T> {
+impl<T, A: Allocation> Default for AtomicRep<T, A> {
     fn default() -> Self {
-        let content = Vec::new();
+        let content = Container::new();
         Self { content }
     }
 }
-impl<'i, R: RuleType, T: TypedNode<'i, R>> NeverFailedTypedNode<'i, R> for AtomicRep<T> {
-    fn parse_with(mut input: Position<'i>, stack: &mut Stack<Span<'i>>) -> (Position<'i>, Self) {
-        let mut vec = Vec::new();
-        let mut tracker = Tracker::new(input);
-
-        for _ in 0usize.. {
-            match restore_on_err(stack, |stack| T::try_parse_with(input, stack, &mut tracker)) {
-                Ok((next, matched)) => {
-                    input = next;
-                    vec.push(matched);
-                }
-                Err(_) => break,
-            }
-        }
+impl<'i, R: RuleType, T: TypedNode<'i, R>, A: Allocation> NeverFailedTypedNode<'i, R>
+    for AtomicRep<T, A>
+where
+    Self: Debug + Clone + PartialEq,
+{
+    fn parse_with(
+        input: Position<'i>,
+        stack: &mut Stack<Span<'i>>,
+        tracker: &Tracker<'i, R>,
+    ) -> (Position<'i>, Self) {
+        let mut vec: A::Vec<T> = Container::new();
+        let mut tracker = tracker.nested(input);
+        let input = repeat(
+            input,
+            stack,
+            &mut tracker,
+            0,
+            usize::MAX,
+            |input, stack, tracker, _| {
+                let (next, matched) = T::try_parse_with(input, stack, tracker)?;
+                vec.push(matched);
+                Ok(next)
+            },
+        )
+        .unwrap_or(input);
         (input, Self { content: vec })
     }
 }
-impl<'i, R: RuleType, T: TypedNode<'i, R>> TypedNode<'i, R> for AtomicRep<T> {
+impl<'i, R: RuleType, T: TypedNode<'i, R>, A: Allocation> TypedNode<'i, R> for AtomicRep<T, A>
+where
+    Self: Debug + Clone + PartialEq,
+{
     #[inline]
     fn try_parse_with(
         input: Position<'i>,
         stack: &mut Stack<Span<'i>>,
-        _tracker: &mut Tracker<'i, R>,
+        tracker: &mut Tracker<'i, R>,
     ) -> Result<(Position<'i>, Self), ()> {
-        Ok(Self::parse_with(input, stack))
+        Ok(Self::parse_with(input, stack, tracker))
     }
 }
-impl<T> Deref for AtomicRep<T> {
-    type Target = Vec<T>;
+impl<T, A: Allocation> Deref for AtomicRep<T, A> {
+    type Target = A::Vec<T>;
     fn deref(&self) -> &Self::Target {
         &self.content
     }
 }
-impl<T> DerefMut for AtomicRep<T> {
+impl<T, A: Allocation> DerefMut for AtomicRep<T, A> {
     fn deref_mut(&mut self) -> &mut Self::Target {
         &mut self.content
     }
 }
-impl<T: Clone + PartialEq> BoundWrapper for AtomicRep<T> {
+impl<T, A: Allocation> BoundWrapper for AtomicRep<T, A>
+where
+    Self: Clone + PartialEq,
+{
     const MIN: usize = 0;
     const MAX: usize = usize::MAX;
 }
 
 /// Repeatably match `T` at least `MIN` times.
-#[derive(Clone, Debug, Hash, PartialEq, Eq)]
-pub struct RepMin<T, const MIN: usize> {
+pub struct RepMin<T, const MIN: usize, A: Allocation = Global> {
     /// Skipped and Matched expressions.
-    pub content: Vec<T>,
+    pub content: A::Vec<T>,
 }
 impl<
         'i,
@@ -97,27 +165,37 @@ impl<
         T: TypedNode<'i, R>,
         Skip: NeverFailedTypedNode<'i, R>,
         const SKIP: usize,
-    > NeverFailedTypedNode<'i, R> for RepMin<Skipped<T, Skip, SKIP>, 0>
+        A: Allocation,
+    > NeverFailedTypedNode<'i, R> for RepMin<Skipped<T, Skip, SKIP>, 0, A>
+where
+    Self: Debug + Clone + PartialEq,
 {
-    fn parse_with(mut input: Position<'i>, stack: &mut Stack<Span<'i>>) -> (Position<'i>, Self) {
-        let mut vec = Vec::new();
-        let mut tracker = Tracker::new(input);
-
-        for i in 0usize.. {
-            match restore_on_err(stack, |stack| try_parse_unit(input, stack, &mut tracker, i)) {
-                Ok((next, matched)) => {
-                    input = next;
-                    vec.push(matched);
-                }
-                Err(_) => break,
-            }
-        }
+    fn parse_with(
+        input: Position<'i>,
+        stack: &mut Stack<Span<'i>>,
+        tracker: &Tracker<'i, R>,
+    ) -> (Position<'i>, Self) {
+        let mut vec: A::Vec<_> = Container::new();
+        let mut tracker = tracker.nested(input);
+        let input = repeat(
+            input,
+            stack,
+            &mut tracker,
+            0,
+            usize::MAX,
+            |input, stack, tracker, i| {
+                let (next, matched) = try_parse_unit(input, stack, tracker, i)?;
+                vec.push(matched);
+                Ok(next)
+            },
+        )
+        .unwrap_or(input);
         (input, Self { content: vec })
     }
 }
-impl<T> Default for RepMin<T, 0> {
+impl<T, A: Allocation> Default for RepMin<T, 0, A> {
     fn default() -> Self {
-        let content = Vec::new();
+        let content = Container::new();
         Self { content }
     }
 }
@@ -128,73 +206,75 @@ impl<
         Skip: NeverFailedTypedNode<'i, R>,
         const SKIP: usize,
         const MIN: usize,
-    > TypedNode<'i, R> for RepMin<Skipped<T, Skip, SKIP>, MIN>
+        A: Allocation,
+    > TypedNode<'i, R> for RepMin<Skipped<T, Skip, SKIP>, MIN, A>
+where
+    Self: Debug + Clone + PartialEq,
 {
     #[inline]
     fn try_parse_with(
-        mut input: Position<'i>,
+        input: Position<'i>,
         stack: &mut Stack<Span<'i>>,
         tracker: &mut Tracker<'i, R>,
     ) -> Result<(Position<'i>, Self), ()> {
-        let mut vec = Vec::new();
-
-        for i in 0usize.. {
-            match restore_on_err(stack, |stack| try_parse_unit(input, stack, tracker, i)) {
-                Ok((next, matched)) => {
-                    input = next;
-                    vec.push(matched);
-                }
-                Err(err) => {
-                    if i < MIN {
-                        return Err(err);
-                    } else {
-                        break;
-                    }
-                }
-            }
-        }
-
+        let mut vec: A::Vec<_> = Container::new();
+        let input = repeat(
+            input,
+            stack,
+            tracker,
+            MIN,
+            usize::MAX,
+            |input, stack, tracker, i| {
+                let (next, matched) = try_parse_unit(input, stack, tracker, i)?;
+                vec.push(matched);
+                Ok(next)
+            },
+        )?;
         Ok((input, Self { content: vec }))
     }
 }
-impl<T, IGNORED, const SKIP: usize, const MIN: usize> RepMin<Skipped<T, IGNORED, SKIP>, MIN> {
+impl<T, IGNORED, const SKIP: usize, const MIN: usize, A: Allocation>
+    RepMin<Skipped<T, IGNORED, SKIP>, MIN, A>
+{
     /// Returns an iterator over all matched expressions by reference.
     #[allow(clippy::needless_lifetimes)]
     pub fn iter_matched<'n>(&'n self) -> Iter<'n, T, IGNORED, SKIP> {
         self.content.iter().map(|s| &s.matched)
     }
     /// Returns an iterator over all matched expressions by value.
-    pub fn into_iter_matched(self) -> IntoIter<T, IGNORED, SKIP> {
+    pub fn into_iter_matched(self) -> IntoIter<T, IGNORED, SKIP, A> {
         self.content.into_iter().map(|s| s.matched)
     }
 }
-impl<T, const MIN: usize> RepMin<T, MIN> {
+impl<T, const MIN: usize, A: Allocation> RepMin<T, MIN, A> {
     /// Returns an iterator over all skipped or matched expressions by reference.
     #[allow(clippy::needless_lifetimes)]
-    pub fn iter_all<'n>(&'n self) -> alloc::slice::Iter<'n, T> {
+    pub fn iter_all<'n>(&'n self) -> core::slice::Iter<'n, T> {
         self.content.iter()
     }
     /// Returns an iterator over all skipped or matched expressions by value.
-    pub fn into_iter_all(self) -> alloc::vec::IntoIter<T> {
+    pub fn into_iter_all(self) -> <A::Vec<T> as IntoIterator>::IntoIter {
         self.content.into_iter()
     }
 }
-impl<T: Clone + PartialEq, const MIN: usize> BoundWrapper for RepMin<T, MIN> {
+impl<T, const MIN: usize, A: Allocation> BoundWrapper for RepMin<T, MIN, A>
+where
+    Self: Clone + PartialEq,
+{
     const MIN: usize = MIN;
     const MAX: usize = usize::MAX;
 }
 
 /// Repeatably match `T` at least `MIN` times and at most `MAX` times.
-#[derive(Clone, Debug, Hash, PartialEq, Eq)]
-pub struct RepMinMax<T, const MIN: usize, const MAX: usize> {
+pub struct RepMinMax<T, const MIN: usize, const MAX: usize, A: Allocation = Global> {
     /// Skipped and Matched expressions.
-    pub content: Vec<T>,
+    pub content: A::Vec<T>,
 }
 
-impl<T, const MAX: usize> Default for RepMinMax<T, 0, MAX> {
+impl<T, const MAX: usize, A: Allocation> Default for RepMinMax<T, 0, MAX, A> {
     fn default() -> Self {
         Self {
-            content: Vec::new(),
+            content: Container::new(),
         }
     }
 }
@@ -205,26 +285,32 @@ impl<
         Skip: NeverFailedTypedNode<'i, R>,
         const SKIP: usize,
         const MAX: usize,
-    > NeverFailedTypedNode<'i, R> for RepMinMax<Skipped<T, Skip, SKIP>, 0, MAX>
+        A: Allocation,
+    > NeverFailedTypedNode<'i, R> for RepMinMax<Skipped<T, Skip, SKIP>, 0, MAX, A>
+where
+    Self: Debug + Clone + PartialEq,
 {
     #[inline]
-    fn parse_with(mut input: Position<'i>, stack: &mut Stack<Span<'i>>) -> (Position<'i>, Self) {
-        let mut vec = Vec::new();
-
-        let mut tracker = Tracker::new(input);
-
-        for i in 0..MAX {
-            match restore_on_err(stack, |stack| try_parse_unit(input, stack, &mut tracker, i)) {
-                Ok((next, matched)) => {
-                    input = next;
-                    vec.push(matched);
-                }
-                Err(_) => {
-                    break;
-                }
-            }
-        }
-
+    fn parse_with(
+        input: Position<'i>,
+        stack: &mut Stack<Span<'i>>,
+        tracker: &Tracker<'i, R>,
+    ) -> (Position<'i>, Self) {
+        let mut vec: A::Vec<_> = Container::new();
+        let mut tracker = tracker.nested(input);
+        let input = repeat(
+            input,
+            stack,
+            &mut tracker,
+            0,
+            MAX,
+            |input, stack, tracker, i| {
+                let (next, matched) = try_parse_unit(input, stack, tracker, i)?;
+                vec.push(matched);
+                Ok(next)
+            },
+        )
+        .unwrap_or(input);
         (input, Self { content: vec })
     }
 }
@@ -236,37 +322,35 @@ impl<
         const SKIP: usize,
         const MIN: usize,
         const MAX: usize,
-    > TypedNode<'i, R> for RepMinMax<Skipped<T, Skip, SKIP>, MIN, MAX>
+        A: Allocation,
+    > TypedNode<'i, R> for RepMinMax<Skipped<T, Skip, SKIP>, MIN, MAX, A>
+where
+    Self: Debug + Clone + PartialEq,
 {
     #[inline]
     fn try_parse_with(
-        mut input: Position<'i>,
+        input: Position<'i>,
         stack: &mut Stack<Span<'i>>,
         tracker: &mut Tracker<'i, R>,
     ) -> Result<(Position<'i>, Self), ()> {
-        let mut vec = Vec::new();
-
-        for i in 0..MAX {
-            match restore_on_err(stack, |stack| try_parse_unit(input, stack, tracker, i)) {
-                Ok((next, matched)) => {
-                    input = next;
-                    vec.push(matched);
-                }
-                Err(err) => {
-                    if i < MIN {
-                        return Err(err);
-                    } else {
-                        break;
-                    }
-                }
-            }
-        }
-
+        let mut vec: A::Vec<_> = Container::new();
+        let input = repeat(
+            input,
+            stack,
+            tracker,
+            MIN,
+            MAX,
+            |input, stack, tracker, i| {
+                let (next, matched) = try_parse_unit(input, stack, tracker, i)?;
+                vec.push(matched);
+                Ok(next)
+            },
+        )?;
         Ok((input, Self { content: vec }))
     }
 }
-impl<T, IGNORED, const SKIP: usize, const MIN: usize, const MAX: usize>
-    RepMinMax<Skipped<T, IGNORED, SKIP>, MIN, MAX>
+impl<T, IGNORED, const SKIP: usize, const MIN: usize, const MAX: usize, A: Allocation>
+    RepMinMax<Skipped<T, IGNORED, SKIP>, MIN, MAX, A>
 {
     /// Returns an iterator over all matched expressions by reference.
     #[allow(clippy::needless_lifetimes)]
@@ -274,32 +358,62 @@ impl<T, IGNORED, const SKIP: usize, const MIN: usize, const MAX: usize>
         self.content.iter().map(|s| &s.matched)
     }
     /// Returns an iterator over all matched expressions by value.
-    pub fn into_iter_matched(self) -> IntoIter<T, IGNORED, SKIP> {
+    pub fn into_iter_matched(self) -> IntoIter<T, IGNORED, SKIP, A> {
         self.content.into_iter().map(|s| s.matched)
     }
 }
-impl<T, const MIN: usize, const MAX: usize> RepMinMax<T, MIN, MAX> {
+impl<T, const MIN: usize, const MAX: usize, A: Allocation> RepMinMax<T, MIN, MAX, A> {
     /// Returns an iterator over all skipped or matched expressions by reference.
     #[allow(clippy::needless_lifetimes)]
-    pub fn iter_all<'n>(&'n self) -> alloc::slice::Iter<'n, T> {
+    pub fn iter_all<'n>(&'n self) -> core::slice::Iter<'n, T> {
         self.content.iter()
     }
     /// Returns an iterator over all skipped or matched expressions by value.
-    pub fn into_iter_all(self) -> alloc::vec::IntoIter<T> {
+    pub fn into_iter_all(self) -> <A::Vec<T> as IntoIterator>::IntoIter {
         self.content.into_iter()
     }
 }
-impl<T: Clone + PartialEq, const MIN: usize, const MAX: usize> BoundWrapper
-    for RepMinMax<T, MIN, MAX>
+impl<T, const MIN: usize, const MAX: usize, A: Allocation> BoundWrapper
+    for RepMinMax<T, MIN, MAX, A>
+where
+    Self: Clone + PartialEq,
 {
     const MIN: usize = MIN;
     const MAX: usize = MAX;
 }
 
 /// Repeat arbitrary times.
-pub type Rep<T, IGNORED, const SKIP: usize> = RepMin<Skipped<T, IGNORED, SKIP>, 0>;
+pub type Rep<T, IGNORED, const SKIP: usize, A = Global> = RepMin<Skipped<T, IGNORED, SKIP>, 0, A>;
 /// Repeat at least one times.
-pub type RepOnce<T, IGNORED, const SKIP: usize> = RepMin<Skipped<T, IGNORED, SKIP>, 1>;
+pub type RepOnce<T, IGNORED, const SKIP: usize, A = Global> =
+    RepMin<Skipped<T, IGNORED, SKIP>, 1, A>;
+
+/// Match `unit` at least `min` times and at most `max` times.
+///
+/// `unit` parses the `i`-th unit and stores it by itself.
+#[inline]
+fn repeat<'i, R: RuleType>(
+    mut input: Position<'i>,
+    stack: &mut Stack<Span<'i>>,
+    tracker: &mut Tracker<'i, R>,
+    min: usize,
+    max: usize,
+    mut unit: impl FnMut(
+        Position<'i>,
+        &mut Stack<Span<'i>>,
+        &mut Tracker<'i, R>,
+        usize,
+    ) -> Result<Position<'i>, ()>,
+) -> Result<Position<'i>, ()> {
+    for i in 0..max {
+        match restore_on_err(stack, |stack| unit(input, stack, tracker, i)) {
+            Ok(next) => input = next,
+            Err(err) if i < min => return Err(err),
+            Err(_) => break,
+        }
+    }
+    Ok(input)
+}
 
 fn try_parse_unit<
     'i,
@@ -313,15 +427,10 @@ fn try_parse_unit<
     tracker: &mut Tracker<'i, R>,
     i: usize,
 ) -> Result<(Position<'i>, Skipped<T, Skip, SKIP>), ()> {
-    let skipped = core::array::from_fn(|_| {
-        if i == 0 {
-            Skip::default()
-        } else {
-            let (next, skipped) = Skip::parse_with(input, stack);
-            input = next;
-            skipped
-        }
-    });
+    let skipped = match i {
+        0 => core::array::from_fn(|_| Skip::default()),
+        _ => skip::<R, Skip, SKIP>(&mut input, stack, tracker),
+    };
     let (next, matched) = T::try_parse_with(input, stack, tracker)?;
     input = next;
     let res = Skipped { skipped, matched };
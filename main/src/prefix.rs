@@ -0,0 +1,177 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! The longest prefix of an input that parsed, for showing how far parsing got before failing.
+//!
+//! With [`Tracker::with_prefix`](crate::tracker::Tracker::with_prefix),
+//! or [`ParsableTypedNode::try_parse_with_prefix`](crate::ParsableTypedNode::try_parse_with_prefix),
+//! a [`ContextualError`](crate::tracker::ContextualError) has a [`Prefix`]:
+//! the tree of rules matched and being attempted when parsing got to where it failed,
+//! up to a depth, which tools can show as "parsed up to here".
+//!
+//! ```ignore
+//! let error = rules::list::try_parse_with_prefix("[a, b", 4).unwrap_err();
+//! let prefix = error.prefix().unwrap();
+//! assert_eq!(prefix.roots[0].children.len(), 2);
+//! ```
+
+use crate::{iterators::Token, RuleType};
+use alloc::vec::Vec;
+
+/// The longest parsed prefix of an input. See [module-level documentation](self).
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Prefix<R: RuleType> {
+    /// Outermost rules, in source order.
+    ///
+    /// Rules being attempted end at [`Prefix::end`], each as the last child of the one outside it,
+    /// and rules nested deeper than the depth limit are left out.
+    pub roots: Vec<Token<R>>,
+    /// Byte offset where parsing failed, which is the position of the error.
+    pub end: usize,
+}
+
+/// A matched rule.
+#[derive(Clone, Copy, Debug)]
+struct Matched<R> {
+    rule: R,
+    start: usize,
+    end: usize,
+    depth: usize,
+}
+
+/// Matched rules saved at the farthest failure.
+#[derive(Debug)]
+enum Saved<R> {
+    /// The first matched rules in the log, which are still there.
+    Len(usize),
+    /// A copy of them, as some have been removed from the log since.
+    Copied(Vec<Matched<R>>),
+}
+
+/// Farthest failure.
+#[derive(Debug)]
+struct Failure<R> {
+    pos: usize,
+    /// Rules being attempted there, with their starts.
+    open: Vec<(R, usize)>,
+    matched: Saved<R>,
+}
+
+/// Keeps the tree of rules that have matched in attempts that haven't failed,
+/// and saves it when parsing gets farther.
+#[derive(Debug)]
+pub(crate) struct PrefixTracker<R> {
+    max_depth: usize,
+    /// Matched rules in post-order.
+    ///
+    /// Rules matched inside a failed attempt are removed, so that only the current tree is left.
+    log: Vec<Matched<R>>,
+    /// Rules being attempted, with their starts and lengths of the log when they were entered.
+    open: Vec<(R, usize, usize)>,
+    failure: Option<Failure<R>>,
+}
+
+impl<R: RuleType> PrefixTracker<R> {
+    pub(crate) fn new(max_depth: usize) -> Self {
+        Self {
+            max_depth,
+            log: Vec::new(),
+            open: Vec::new(),
+            failure: None,
+        }
+    }
+    /// Enter a rule nested in `depth` rules.
+    pub(crate) fn enter(&mut self, rule: R, start: usize, depth: usize) {
+        if depth < self.max_depth {
+            self.open.push((rule, start, self.log.len()));
+        }
+    }
+    /// Leave a rule nested in `depth` rules, which matched until `end` if it did.
+    pub(crate) fn leave(&mut self, depth: usize, end: Option<usize>) {
+        if depth >= self.max_depth {
+            return;
+        }
+        let Some((rule, start, len)) = self.open.pop() else {
+            return;
+        };
+        match end {
+            Some(end) => self.log.push(Matched {
+                rule,
+                start,
+                end,
+                depth,
+            }),
+            None => {
+                // Copied before the saved tree is removed from the log.
+                if let Some(Failure { matched, .. }) = &mut self.failure {
+                    if let Saved::Len(saved) = *matched {
+                        if len < saved {
+                            *matched = Saved::Copied(self.log[..saved].to_vec());
+                        }
+                    }
+                }
+                self.log.truncate(len);
+            }
+        }
+    }
+    /// Save the current tree, as parsing has got to `pos`, farther than before.
+    pub(crate) fn failed_at(&mut self, pos: usize) {
+        self.failure = Some(Failure {
+            pos,
+            open: self
+                .open
+                .iter()
+                .map(|&(rule, start, _)| (rule, start))
+                .collect(),
+            matched: Saved::Len(self.log.len()),
+        });
+    }
+    /// The tree saved at the farthest failure.
+    pub(crate) fn finish(self) -> Option<Prefix<R>> {
+        let failure = self.failure?;
+        let matched = match failure.matched {
+            Saved::Len(len) => &self.log[..len],
+            Saved::Copied(ref matched) => matched,
+        };
+        // Children of rules at each depth, waiting for their parents.
+        let mut pending: Vec<Vec<Token<R>>> = Vec::new();
+        let children_at = |pending: &mut Vec<Vec<Token<R>>>, depth: usize| {
+            if pending.len() <= depth + 1 {
+                pending.resize_with(depth + 2, Vec::new);
+            }
+            core::mem::take(&mut pending[depth + 1])
+        };
+        for m in matched {
+            let children = children_at(&mut pending, m.depth);
+            pending[m.depth].push(Token {
+                rule: m.rule,
+                start: m.start,
+                end: m.end,
+                children,
+            });
+        }
+        let mut inner = None;
+        for (depth, &(rule, start)) in failure.open.iter().enumerate().rev() {
+            let mut children = children_at(&mut pending, depth);
+            children.extend(inner);
+            inner = Some(Token {
+                rule,
+                start,
+                end: failure.pos,
+                children,
+            });
+        }
+        let mut roots = pending.into_iter().next().unwrap_or_default();
+        roots.extend(inner);
+        Some(Prefix {
+            roots,
+            end: failure.pos,
+        })
+    }
+}
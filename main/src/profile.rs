@@ -0,0 +1,143 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Statistics of rules during parsing, that is, how many times each rule is attempted, how many times it fails,
+//! and how long it takes.
+//!
+//! Collect statistics of many parses into one [`ParseStats`] with
+//! [`ParsableTypedNode::try_parse_with_stats`](crate::ParsableTypedNode::try_parse_with_stats),
+//! then look for rules that take the most time, or that are attempted many times but mostly fail.
+//!
+//! Time of a rule includes time of rules inside it, and is counted again for each recursive attempt.
+//! Self time excludes time of rules inside it.
+
+use alloc::{collections::BTreeMap, vec::Vec};
+use core::fmt;
+use pest::RuleType;
+use std::time::{Duration, Instant};
+
+/// Statistics of a rule.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct RuleStats {
+    /// Number of attempts.
+    pub calls: usize,
+    /// Number of failed attempts.
+    pub failures: usize,
+    /// Total time of attempts, including rules inside it.
+    pub time: Duration,
+    /// Total time of attempts, excluding rules inside it.
+    pub self_time: Duration,
+}
+
+impl RuleStats {
+    /// Add numbers in `other`.
+    pub fn merge(&mut self, other: &Self) {
+        self.calls += other.calls;
+        self.failures += other.failures;
+        self.time += other.time;
+        self.self_time += other.self_time;
+    }
+}
+
+/// Statistics of all attempted rules.
+#[derive(Clone, Debug)]
+pub struct ParseStats<R> {
+    rules: BTreeMap<R, RuleStats>,
+    /// Start of each rule being attempted, and time of rules inside it so far.
+    running: Vec<(Instant, Duration)>,
+}
+
+impl<R> Default for ParseStats<R> {
+    fn default() -> Self {
+        Self {
+            rules: BTreeMap::new(),
+            running: Vec::new(),
+        }
+    }
+}
+
+impl<R: RuleType> ParseStats<R> {
+    /// Create empty statistics.
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub(crate) fn enter(&mut self) {
+        self.running.push((Instant::now(), Duration::ZERO));
+    }
+    pub(crate) fn leave(&mut self, rule: R, succeeded: bool) {
+        let Some((start, inner)) = self.running.pop() else {
+            return;
+        };
+        let time = start.elapsed();
+        if let Some((_, outer)) = self.running.last_mut() {
+            *outer += time;
+        }
+        let stats = self.rules.entry(rule).or_default();
+        stats.calls += 1;
+        stats.failures += usize::from(!succeeded);
+        stats.time += time;
+        stats.self_time += time.saturating_sub(inner);
+    }
+    /// Statistics of `rule`, which are all zero if it's never attempted.
+    pub fn get(&self, rule: R) -> RuleStats {
+        self.rules.get(&rule).copied().unwrap_or_default()
+    }
+    /// Attempted rules and their statistics, in the order of rules.
+    pub fn iter(&self) -> impl Iterator<Item = (R, &RuleStats)> + '_ {
+        self.rules.iter().map(|(rule, stats)| (*rule, stats))
+    }
+    /// Attempted rules and their statistics, the most self time first.
+    pub fn hottest(&self) -> Vec<(R, RuleStats)> {
+        let mut res: Vec<_> = self
+            .rules
+            .iter()
+            .map(|(rule, stats)| (*rule, *stats))
+            .collect();
+        res.sort_by_key(|(_, stats)| core::cmp::Reverse(stats.self_time));
+        res
+    }
+    /// Add statistics in `other`.
+    pub fn merge(&mut self, other: &Self) {
+        for (rule, stats) in &other.rules {
+            self.rules.entry(*rule).or_default().merge(stats);
+        }
+    }
+    /// Display a table of attempted rules, the most self time first.
+    pub fn report(&self) -> Report<'_, R> {
+        Report { stats: self }
+    }
+}
+
+/// See [`ParseStats::report`].
+#[derive(Clone, Copy, Debug)]
+pub struct Report<'s, R> {
+    stats: &'s ParseStats<R>,
+}
+
+impl<R: RuleType> fmt::Display for Report<'_, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:>10} {:>10} {:>12} {:>12}  rule",
+            "calls", "failures", "time", "self time"
+        )?;
+        for (rule, stats) in self.stats.hottest() {
+            writeln!(
+                f,
+                "{:>10} {:>10} {:>12} {:>12}  {:?}",
+                stats.calls,
+                stats.failures,
+                alloc::format!("{:?}", stats.time),
+                alloc::format!("{:?}", stats.self_time),
+                rule
+            )?;
+        }
+        Ok(())
+    }
+}
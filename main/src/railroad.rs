@@ -0,0 +1,360 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Railroad (syntax) diagrams in SVG.
+//!
+//! Diagrams are drawn from [reflection](crate::reflection) data, so enable `emit_reflection` and use:
+//!
+//! ```ignore
+//! let svg = pest_typed::railroad::to_svg(grammar::rule("sum").unwrap());
+//! ```
+//!
+//! Terminals are drawn in rounded boxes, referenced rules in square boxes,
+//! and predicates, `PUSH` and tags in dashed boxes before the expression they apply to.
+
+use crate::reflection::{Expr, RuleInfo};
+use alloc::{boxed::Box, format, string::String, vec, vec::Vec};
+use core::fmt::{self, Write};
+
+const CHAR_WIDTH: u32 = 8;
+const PADDING: u32 = 10;
+const HALF_HEIGHT: u32 = 12;
+const GAP: u32 = 10;
+const ARC: u32 = 10;
+const V_SPACE: u32 = 10;
+const MARGIN: u32 = 20;
+
+const STYLE: &str = "path,rect{fill:none;stroke:#222;stroke-width:1.5}\
+rect.terminal{fill:#efe}rect.nonterminal{fill:#eef}rect.marker{stroke-dasharray:4 2}\
+text{font:13px monospace;text-anchor:middle}text.title{text-anchor:start;font-weight:bold}";
+
+enum Node {
+    Terminal(String),
+    NonTerminal(String),
+    Marker(String),
+    Seq(Vec<Node>),
+    Choice(Vec<Node>),
+    /// One or more.
+    Loop(Box<Node>),
+    Empty,
+}
+
+fn list(expr: &Expr, seq: bool, res: &mut Vec<Node>) {
+    match (expr, seq) {
+        (Expr::Seq(lhs, rhs), true) | (Expr::Choice(lhs, rhs), false) => {
+            list(lhs, seq, res);
+            list(rhs, seq, res);
+        }
+        _ => res.push(Node::from(expr)),
+    }
+}
+
+fn optional(node: Node) -> Node {
+    Node::Choice(vec![Node::Empty, node])
+}
+
+fn prefixed(marker: String, expr: &Expr) -> Node {
+    Node::Seq(vec![Node::Marker(marker), Node::from(expr)])
+}
+
+impl From<&Expr> for Node {
+    fn from(expr: &Expr) -> Self {
+        match expr {
+            Expr::Str(s) => Self::Terminal(format!("{:?}", s)),
+            Expr::Insens(s) => Self::Terminal(format!("^{:?}", s)),
+            Expr::Range(start, end) => Self::Terminal(format!("{:?}..{:?}", start, end)),
+            Expr::Ident(name) => Self::NonTerminal(String::from(*name)),
+            Expr::PeekSlice(_, _) | Expr::Skip(_) => Self::NonTerminal(format!("{}", expr)),
+            Expr::PosPred(expr) => prefixed(String::from("&"), expr),
+            Expr::NegPred(expr) => prefixed(String::from("!"), expr),
+            Expr::Seq(_, _) => {
+                let mut res = Vec::new();
+                list(expr, true, &mut res);
+                Self::Seq(res)
+            }
+            Expr::Choice(_, _) => {
+                let mut res = Vec::new();
+                list(expr, false, &mut res);
+                Self::Choice(res)
+            }
+            Expr::Opt(expr) => optional(Self::from(*expr)),
+            Expr::Rep(expr) => optional(Self::Loop(Box::new(Self::from(*expr)))),
+            Expr::RepOnce(expr) => Self::Loop(Box::new(Self::from(*expr))),
+            Expr::Push(expr) => prefixed(String::from("PUSH"), expr),
+            Expr::NodeTag(expr, tag) => prefixed(format!("#{}", tag), expr),
+            Expr::RestoreOnErr(expr) => Self::from(*expr),
+        }
+    }
+}
+
+/// Vertical offsets of branches in a choice, relative to the main line.
+fn offsets(items: &[Node]) -> Vec<u32> {
+    let mut res = Vec::with_capacity(items.len());
+    let mut offset = 0;
+    for (i, item) in items.iter().enumerate() {
+        if i > 0 {
+            offset += (items[i - 1].down() + V_SPACE + item.up()).max(2 * ARC);
+        }
+        res.push(offset);
+    }
+    res
+}
+
+fn escape(s: &str) -> String {
+    let mut res = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => res.push_str("&amp;"),
+            '<' => res.push_str("&lt;"),
+            '>' => res.push_str("&gt;"),
+            '"' => res.push_str("&quot;"),
+            c => res.push(c),
+        }
+    }
+    res
+}
+
+impl Node {
+    fn width(&self) -> u32 {
+        match self {
+            Self::Terminal(text) | Self::NonTerminal(text) | Self::Marker(text) => {
+                text.chars().count() as u32 * CHAR_WIDTH + 2 * PADDING
+            }
+            Self::Seq(items) => {
+                items.iter().map(Self::width).sum::<u32>()
+                    + GAP * (items.len() as u32).saturating_sub(1)
+            }
+            Self::Choice(items) => items.iter().map(Self::width).max().unwrap_or(0) + 4 * ARC,
+            Self::Loop(item) => item.width() + 4 * ARC,
+            Self::Empty => 0,
+        }
+    }
+    /// Height above the main line.
+    fn up(&self) -> u32 {
+        match self {
+            Self::Terminal(_) | Self::NonTerminal(_) | Self::Marker(_) => HALF_HEIGHT,
+            Self::Seq(items) => items.iter().map(Self::up).max().unwrap_or(0),
+            Self::Choice(items) => items.first().map_or(0, Self::up),
+            Self::Loop(item) => item.up(),
+            Self::Empty => 0,
+        }
+    }
+    /// Height below the main line.
+    fn down(&self) -> u32 {
+        match self {
+            Self::Terminal(_) | Self::NonTerminal(_) | Self::Marker(_) => HALF_HEIGHT,
+            Self::Seq(items) => items.iter().map(Self::down).max().unwrap_or(0),
+            Self::Choice(items) => match (offsets(items).last(), items.last()) {
+                (Some(offset), Some(last)) => offset + last.down(),
+                _ => 0,
+            },
+            Self::Loop(item) => item.down() + V_SPACE,
+            Self::Empty => 0,
+        }
+    }
+    /// Draw with the main line entering at `(x, y)`.
+    fn render(&self, x: u32, y: u32, out: &mut impl Write) -> fmt::Result {
+        let (class, rx) = match self {
+            Self::Terminal(_) => ("terminal", HALF_HEIGHT),
+            Self::NonTerminal(_) => ("nonterminal", 0),
+            Self::Marker(_) => ("marker", 0),
+            _ => ("", 0),
+        };
+        match self {
+            Self::Terminal(text) | Self::NonTerminal(text) | Self::Marker(text) => {
+                let width = self.width();
+                writeln!(
+                    out,
+                    r#"<rect class="{}" x="{}" y="{}" width="{}" height="{}" rx="{}"/>"#,
+                    class,
+                    x,
+                    y - HALF_HEIGHT,
+                    width,
+                    2 * HALF_HEIGHT,
+                    rx
+                )?;
+                writeln!(
+                    out,
+                    r#"<text x="{}" y="{}">{}</text>"#,
+                    x + width / 2,
+                    y + 4,
+                    escape(text)
+                )
+            }
+            Self::Seq(items) => {
+                let mut x = x;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        writeln!(out, r#"<path d="M{} {}h{}"/>"#, x, y, GAP)?;
+                        x += GAP;
+                    }
+                    item.render(x, y, out)?;
+                    x += item.width();
+                }
+                Ok(())
+            }
+            Self::Choice(items) => {
+                let width = self.width();
+                let (left, right) = (x + 2 * ARC, x + width - 2 * ARC);
+                for (item, offset) in items.iter().zip(offsets(items)) {
+                    let y1 = y + offset;
+                    if offset == 0 {
+                        writeln!(out, r#"<path d="M{} {}H{}"/>"#, x, y, left)?;
+                    } else {
+                        writeln!(
+                            out,
+                            r#"<path d="M{} {}q{a} 0 {a} {a}V{}q0 {a} {a} {a}"/>"#,
+                            x,
+                            y,
+                            y1 - ARC,
+                            a = ARC
+                        )?;
+                    }
+                    item.render(left, y1, out)?;
+                    if offset == 0 {
+                        writeln!(
+                            out,
+                            r#"<path d="M{} {}H{}"/>"#,
+                            left + item.width(),
+                            y,
+                            x + width
+                        )?;
+                    } else {
+                        writeln!(
+                            out,
+                            r#"<path d="M{} {}H{}q{a} 0 {a} -{a}V{}q0 -{a} {a} -{a}"/>"#,
+                            left + item.width(),
+                            y1,
+                            right,
+                            y + ARC,
+                            a = ARC
+                        )?;
+                    }
+                }
+                Ok(())
+            }
+            Self::Loop(item) => {
+                let (left, right) = (x + 2 * ARC, x + 2 * ARC + item.width());
+                let bottom = y + item.down() + V_SPACE;
+                writeln!(out, r#"<path d="M{} {}H{}"/>"#, x, y, left)?;
+                item.render(left, y, out)?;
+                writeln!(out, r#"<path d="M{} {}H{}"/>"#, right, y, right + 2 * ARC)?;
+                writeln!(
+                    out,
+                    r#"<path d="M{} {}q{a} 0 {a} {a}V{}q0 {a} -{a} {a}H{}q-{a} 0 -{a} -{a}V{}q0 -{a} {a} -{a}"/>"#,
+                    right,
+                    y,
+                    bottom - ARC,
+                    left,
+                    y + ARC,
+                    a = ARC
+                )
+            }
+            Self::Empty => Ok(()),
+        }
+    }
+}
+
+/// Write the railroad diagram of `expr` as an SVG document, with an optional title.
+pub fn write_svg(expr: &Expr, title: Option<&str>, out: &mut impl Write) -> fmt::Result {
+    let node = Node::from(expr);
+    let title_height = if title.is_some() { 2 * MARGIN } else { 0 };
+    let width = node.width() + 2 * MARGIN + 2 * GAP;
+    let height = node.up() + node.down() + 2 * MARGIN + title_height;
+    let y = MARGIN + title_height + node.up();
+    writeln!(
+        out,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}">"#,
+        w = width,
+        h = height
+    )?;
+    writeln!(out, "<style>{}</style>", STYLE)?;
+    if let Some(title) = title {
+        writeln!(
+            out,
+            r#"<text class="title" x="{}" y="{}">{}</text>"#,
+            MARGIN,
+            MARGIN + 4,
+            escape(title)
+        )?;
+    }
+    let (tick, end) = (HALF_HEIGHT - 2, MARGIN + GAP + node.width());
+    writeln!(
+        out,
+        r#"<path d="M{m} {}v{}M{m} {}h{}"/>"#,
+        y - tick,
+        2 * tick,
+        y,
+        GAP,
+        m = MARGIN
+    )?;
+    node.render(MARGIN + GAP, y, out)?;
+    writeln!(
+        out,
+        r#"<path d="M{} {}h{}m0 -{t}v{}"/>"#,
+        end,
+        y,
+        GAP,
+        2 * tick,
+        t = tick
+    )?;
+    writeln!(out, "</svg>")
+}
+
+/// Railroad diagram of a rule, titled with its name.
+pub fn to_svg(rule: &RuleInfo) -> String {
+    let mut res = String::new();
+    write_svg(rule.expr, Some(rule.name), &mut res).unwrap();
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{write_svg, Node};
+    use crate::reflection::Expr;
+    use alloc::string::String;
+
+    static EXPR: Expr = Expr::Seq(
+        &Expr::Str("<"),
+        &Expr::Seq(
+            &Expr::Rep(&Expr::Choice(&Expr::Ident("a"), &Expr::Range('0', '9'))),
+            &Expr::NegPred(&Expr::Insens("b")),
+        ),
+    );
+
+    #[test]
+    fn layout() {
+        let node = Node::from(&EXPR);
+        let items = match &node {
+            Node::Seq(items) => items,
+            _ => panic!(),
+        };
+        assert_eq!(items.len(), 3);
+        // `"<"`, `(a | '0'..'9')*` and `!^"b"`, with gaps between them.
+        assert_eq!(
+            node.width(),
+            44 + (84 + 4 * 10 * 3) + (28 + 10 + 52) + 2 * 10
+        );
+        assert_eq!(node.up(), 12);
+        assert!(node.down() > 12);
+    }
+
+    #[test]
+    fn svg() {
+        let mut svg = String::new();
+        write_svg(&EXPR, Some("x<y"), &mut svg).unwrap();
+        assert!(svg.starts_with("<svg "));
+        assert!(svg.trim_end().ends_with("</svg>"));
+        assert!(svg.contains(">x&lt;y</text>"));
+        assert!(svg.contains(">&quot;&lt;&quot;</text>"));
+        assert!(svg.contains(r#"<rect class="nonterminal""#));
+        assert!(svg.contains(">!</text>"));
+        assert_eq!(svg.matches("<rect").count(), 5);
+    }
+}
@@ -38,3 +38,15 @@ pub use ::std::vec::Vec;
 pub use ::alloc::vec;
 #[cfg(feature = "std")]
 pub use ::std::vec;
+
+/// `String` in corresponding context.
+#[cfg(not(feature = "std"))]
+pub use ::alloc::string::String;
+#[cfg(feature = "std")]
+pub use ::std::string::String;
+
+/// `Arc` in corresponding context.
+#[cfg(not(feature = "std"))]
+pub use ::alloc::sync::Arc;
+#[cfg(feature = "std")]
+pub use ::std::sync::Arc;
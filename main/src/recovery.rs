@@ -0,0 +1,362 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Recovery from unterminated constructs, such as strings and blocks without their closers.
+//!
+//! The generator wraps the closing literal of rules marked with `#[recover]` in [`Closer`].
+//! If the closer is missing at the end of a line or at the end of input,
+//! an empty closer is synthesized there and parsing goes on,
+//! so that an unterminated construct doesn't make everything after it fail.
+//!
+//! ```
+//! # use pest_typed::{recovery::Closer, predefined_node::Str, Position, Stack, StringWrapper, TypedNode};
+//! # use pest_typed::tracker::Tracker;
+//! #[derive(Clone, Debug, Hash, PartialEq, Eq)]
+//! struct Quote;
+//! impl StringWrapper for Quote {
+//!     const CONTENT: &'static str = "\"";
+//! }
+//! let input = Position::from_start("\nrest");
+//! let mut tracker = Tracker::<()>::new(input);
+//! let (next, closer) =
+//!     Closer::<Str<Quote>>::try_parse_with(input, &mut Stack::new(), &mut tracker).unwrap();
+//! assert!(closer.is_synthesized());
+//! assert_eq!(next, input);
+//! ```
+//!
+//! In recovery mode, that is if any rule is marked with `#[recover]`,
+//! rule structs also implement [`Recovered`],
+//! whose [`provenance`](Recovered::provenance) tells whether a synthesized closer ends the node
+//! or is somewhere inside it, so that later passes can skip or soften diagnostics there.
+
+use crate::predefined_node::{
+    AlwaysFail, AtomicRep, Bounded, CharRange, Empty, Insens, Negative, PeekSlice1, PeekSlice2,
+    Positive, Push, RepMin, RepMinMax, SameLine, Skip, SkipChar, Skipped, Str, ANY, DROP, EOI,
+    NEWLINE, PEEK, PEEK_ALL, POP, POP_ALL, SOI,
+};
+use crate::{
+    allocation::Allocation,
+    embed::{Delegate, Embed},
+    iterators::{Pair, Pairs},
+    lazy::Lazy,
+    position::Position,
+    span::Span,
+    tracker::Tracker,
+    RuleType, Stack, StringArrayWrapper, StringWrapper, TypedNode,
+};
+use alloc::{boxed::Box, vec::Vec};
+use core::{iter, ops};
+
+/// A closer `T` that's synthesized if it's missing at the end of a line or at the end of input.
+/// See [module-level documentation](self).
+///
+/// Closers are literals, so pairs are not iterated over.
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub struct Closer<'i, T> {
+    /// Matched closer, or [`None`] if it's synthesized.
+    pub matched: Option<T>,
+    /// Span of the closer, which is empty if it's synthesized.
+    pub span: Span<'i>,
+}
+
+impl<'i, T> Closer<'i, T> {
+    /// Whether the closer is missing in the input and synthesized.
+    pub fn is_synthesized(&self) -> bool {
+        self.matched.is_none()
+    }
+}
+
+/// Whether `pos` is at the end of a line or at the end of input.
+fn at_line_end(pos: Position<'_>) -> bool {
+    let rest = pos.rest();
+    rest.is_empty() || rest.starts_with('\n') || rest.starts_with("\r\n")
+}
+
+impl<'i, R: RuleType, T: TypedNode<'i, R>> TypedNode<'i, R> for Closer<'i, T> {
+    #[inline]
+    fn try_parse_with(
+        input: Position<'i>,
+        stack: &mut Stack<Span<'i>>,
+        tracker: &mut Tracker<'i, R>,
+    ) -> Result<(Position<'i>, Self), ()> {
+        match T::try_parse_with(input, stack, tracker) {
+            Ok((end, matched)) => Ok((
+                end,
+                Self {
+                    matched: Some(matched),
+                    span: input.span(&end),
+                },
+            )),
+            Err(()) if at_line_end(input) => Ok((
+                input,
+                Self {
+                    matched: None,
+                    span: input.span(&input),
+                },
+            )),
+            Err(()) => Err(()),
+        }
+    }
+}
+
+impl<'i: 'n, 'n, R: RuleType + 'n, T> Pairs<'i, 'n, R> for Closer<'i, T> {
+    type Iter = iter::Empty<&'n dyn Pair<'i, 'n, R>>;
+    type IntoIter = iter::Empty<Box<dyn Pair<'i, 'n, R> + 'n>>;
+
+    fn iter_pairs(&'n self) -> Self::Iter {
+        iter::empty()
+    }
+    fn into_iter_pairs(self) -> Self::IntoIter {
+        iter::empty()
+    }
+}
+
+/// Where a node comes from, as bit flags.
+#[derive(Clone, Copy, Debug, Default, Hash, PartialEq, Eq)]
+pub struct Provenance(u8);
+
+impl Provenance {
+    /// Entirely matched in the input.
+    pub const PARSED: Self = Self(0);
+    /// The closer of the node itself is missing in the input and synthesized.
+    pub const SYNTHESIZED: Self = Self(1);
+    /// A node inside is recovered.
+    pub const CONTAINS_RECOVERED: Self = Self(1 << 1);
+
+    /// Raw bits of the flags.
+    pub const fn bits(self) -> u8 {
+        self.0
+    }
+    /// Whether all flags in `other` are set.
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+    /// Whether any flag is set, that is, the node covers text that is not entirely matched.
+    pub const fn is_recovered(self) -> bool {
+        self.0 != 0
+    }
+}
+
+impl ops::BitOr for Provenance {
+    type Output = Self;
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl ops::BitOrAssign for Provenance {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+/// A node that knows whether it's matched inside error recovery.
+///
+/// Rule structs report their own closers with [`Provenance::SYNTHESIZED`]
+/// and recovered rules inside them with [`Provenance::CONTAINS_RECOVERED`],
+/// while all the other nodes combine flags of the rules inside them.
+pub trait Recovered {
+    /// Provenance of the node. See [`Provenance`].
+    fn provenance(&self) -> Provenance;
+    /// Whether the node is or contains a recovered node.
+    fn is_recovered(&self) -> bool {
+        self.provenance().is_recovered()
+    }
+}
+
+/// Provenance of a rule with its closer, if any, and its content.
+pub fn rule_provenance(synthesized: bool, content: Provenance) -> Provenance {
+    let mut res = Provenance::PARSED;
+    if synthesized {
+        res |= Provenance::SYNTHESIZED;
+    }
+    if content.is_recovered() {
+        res |= Provenance::CONTAINS_RECOVERED;
+    }
+    res
+}
+
+macro_rules! impl_leaf {
+    ($( ($($generics:tt)*) $ty:ty ),* $(,)?) => {
+        $(
+            impl<$($generics)*> Recovered for $ty {
+                #[inline]
+                fn provenance(&self) -> Provenance {
+                    Provenance::PARSED
+                }
+            }
+        )*
+    };
+}
+
+impl_leaf!(
+    () (),
+    (T: StringWrapper) Str<T>,
+    ('i, T: StringWrapper) Insens<'i, T>,
+    ('i, Strings: StringArrayWrapper) Skip<'i, Strings>,
+    ('i, const N: usize) SkipChar<'i, N>,
+    (const MIN: char, const MAX: char) CharRange<MIN, MAX>,
+    (T) Negative<T>,
+    () ANY,
+    ('i) SOI<'i>,
+    ('i) EOI<'i>,
+    () NEWLINE,
+    ('i) PEEK<'i>,
+    ('i) PEEK_ALL<'i>,
+    () DROP,
+    ('i) POP<'i>,
+    ('i) POP_ALL<'i>,
+    ('i) AlwaysFail<'i>,
+    ('i) Empty<'i>,
+    ('i, R, T, D: Delegate<'i>) Embed<'i, R, T, D>,
+    ('i, const START: i32, const END: i32) PeekSlice2<'i, START, END>,
+    ('i, const START: i32) PeekSlice1<'i, START>,
+    // Closers are reported by the rules that end with them.
+    ('i, T) Closer<'i, T>,
+);
+
+impl<T: Recovered> Recovered for Option<T> {
+    #[inline]
+    fn provenance(&self) -> Provenance {
+        self.as_ref().map_or(Provenance::PARSED, T::provenance)
+    }
+}
+
+impl<T: Recovered> Recovered for Box<T> {
+    #[inline]
+    fn provenance(&self) -> Provenance {
+        T::provenance(self)
+    }
+}
+
+impl<T: Recovered> Recovered for Vec<T> {
+    #[inline]
+    fn provenance(&self) -> Provenance {
+        items_provenance(self)
+    }
+}
+
+impl<T1: Recovered, T2: Recovered> Recovered for (T1, T2) {
+    #[inline]
+    fn provenance(&self) -> Provenance {
+        self.0.provenance() | self.1.provenance()
+    }
+}
+
+impl<T: Recovered, const N: usize> Recovered for [T; N] {
+    #[inline]
+    fn provenance(&self) -> Provenance {
+        items_provenance(self)
+    }
+}
+
+impl<T: Recovered, Skip: Recovered, const SKIP: usize> Recovered for Skipped<T, Skip, SKIP> {
+    #[inline]
+    fn provenance(&self) -> Provenance {
+        self.skipped.provenance() | self.matched.provenance()
+    }
+}
+
+impl<T: Recovered, End, Skip, const SKIP: usize, const START: bool, const END: bool> Recovered
+    for Bounded<T, End, Skip, SKIP, START, END>
+{
+    #[inline]
+    fn provenance(&self) -> Provenance {
+        self.content.provenance()
+    }
+}
+
+impl<N: Recovered> Recovered for Positive<N> {
+    #[inline]
+    fn provenance(&self) -> Provenance {
+        self.content.provenance()
+    }
+}
+
+impl<T: Recovered> Recovered for Push<T> {
+    #[inline]
+    fn provenance(&self) -> Provenance {
+        self.content.provenance()
+    }
+}
+
+impl<T: Recovered> Recovered for SameLine<T> {
+    #[inline]
+    fn provenance(&self) -> Provenance {
+        self.content.provenance()
+    }
+}
+
+fn items_provenance<T: Recovered>(items: &[T]) -> Provenance {
+    items
+        .iter()
+        .fold(Provenance::PARSED, |res, item| res | item.provenance())
+}
+
+impl<T: Recovered, A: Allocation> Recovered for AtomicRep<T, A> {
+    #[inline]
+    fn provenance(&self) -> Provenance {
+        items_provenance(&self.content)
+    }
+}
+
+impl<T: Recovered, const MIN: usize, A: Allocation> Recovered for RepMin<T, MIN, A> {
+    #[inline]
+    fn provenance(&self) -> Provenance {
+        items_provenance(&self.content)
+    }
+}
+
+impl<T: Recovered, const MIN: usize, const MAX: usize, A: Allocation> Recovered
+    for RepMinMax<T, MIN, MAX, A>
+{
+    #[inline]
+    fn provenance(&self) -> Provenance {
+        items_provenance(&self.content)
+    }
+}
+
+/// Subtrees that are not built yet are parsed later, and skipped here.
+impl<'i, R: RuleType, T: TypedNode<'i, R> + Recovered> Recovered for Lazy<'i, R, T> {
+    #[inline]
+    fn provenance(&self) -> Provenance {
+        match self.is_parsed() {
+            true => self.get().provenance(),
+            false => Provenance::PARSED,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{rule_provenance, Provenance, Recovered};
+    use crate::predefined_node::ANY;
+    use alloc::{vec, vec::Vec};
+
+    struct Rule(bool, Vec<Rule>);
+
+    impl Recovered for Rule {
+        fn provenance(&self) -> Provenance {
+            rule_provenance(self.0, self.1.provenance())
+        }
+    }
+
+    #[test]
+    fn provenance() {
+        assert_eq!(ANY { content: 'a' }.provenance(), Provenance::PARSED);
+        let tree = Rule(false, vec![Rule(false, vec![]), Rule(true, vec![])]);
+        assert_eq!(tree.provenance(), Provenance::CONTAINS_RECOVERED);
+        assert!(tree.is_recovered());
+        assert!(!tree.1[0].is_recovered());
+        let tree = Rule(true, vec![tree]);
+        let flags = tree.provenance();
+        assert!(flags.contains(Provenance::SYNTHESIZED | Provenance::CONTAINS_RECOVERED));
+        assert_eq!(flags.bits(), 3);
+    }
+}
@@ -0,0 +1,140 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Static description of a grammar.
+//!
+//! Generated by `emit_reflection` (see [pest_typed_derive](https://docs.rs/pest_typed_derive)) as `grammar::rules()`.
+
+use core::fmt;
+
+/// Kind of a rule, the same as [`pest_meta::ast::RuleType`](https://docs.rs/pest_meta/latest/pest_meta/ast/enum.RuleType.html).
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum RuleKind {
+    /// `{ ... }`.
+    Normal,
+    /// `_{ ... }`.
+    Silent,
+    /// `@{ ... }`.
+    Atomic,
+    /// `${ ... }`.
+    CompoundAtomic,
+    /// `!{ ... }`.
+    NonAtomic,
+}
+
+/// Optimized expression of a rule.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Expr {
+    /// Exact string, e.g. `"a"`.
+    Str(&'static str),
+    /// Case-insensitive string, e.g. `^"a"`.
+    Insens(&'static str),
+    /// Character range, e.g. `'a'..'z'`.
+    Range(char, char),
+    /// Referenced rule, e.g. `a`.
+    Ident(&'static str),
+    /// Slice of the stack, e.g. `PEEK[..]`.
+    PeekSlice(i32, Option<i32>),
+    /// Positive predicate, e.g. `&e`.
+    PosPred(&'static Expr),
+    /// Negative predicate, e.g. `!e`.
+    NegPred(&'static Expr),
+    /// Sequence, e.g. `e1 ~ e2`.
+    Seq(&'static Expr, &'static Expr),
+    /// Ordered choice, e.g. `e1 | e2`.
+    Choice(&'static Expr, &'static Expr),
+    /// Optional, e.g. `e?`.
+    Opt(&'static Expr),
+    /// Repetition, e.g. `e*`.
+    Rep(&'static Expr),
+    /// Repetition at least once, e.g. `e+`.
+    RepOnce(&'static Expr),
+    /// Skip until one of the strings.
+    Skip(&'static [&'static str]),
+    /// Push the matched content to the stack, e.g. `PUSH(e)`.
+    Push(&'static Expr),
+    /// Tagged expression, e.g. `#tag = e`.
+    NodeTag(&'static Expr, &'static str),
+    /// Restore the stack on failure.
+    RestoreOnErr(&'static Expr),
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Str(s) => write!(f, "{:?}", s),
+            Self::Insens(s) => write!(f, "^{:?}", s),
+            Self::Range(start, end) => write!(f, "{:?}..{:?}", start, end),
+            Self::Ident(name) => f.write_str(name),
+            Self::PeekSlice(start, None) => write!(f, "PEEK[{}..]", start),
+            Self::PeekSlice(start, Some(end)) => write!(f, "PEEK[{}..{}]", start, end),
+            Self::PosPred(expr) => write!(f, "&{}", expr),
+            Self::NegPred(expr) => write!(f, "!{}", expr),
+            Self::Seq(lhs, rhs) => write!(f, "({} ~ {})", lhs, rhs),
+            Self::Choice(lhs, rhs) => write!(f, "({} | {})", lhs, rhs),
+            Self::Opt(expr) => write!(f, "{}?", expr),
+            Self::Rep(expr) => write!(f, "{}*", expr),
+            Self::RepOnce(expr) => write!(f, "{}+", expr),
+            Self::Skip(strings) => {
+                f.write_str("(!(")?;
+                for (i, s) in strings.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(" | ")?;
+                    }
+                    write!(f, "{:?}", s)?;
+                }
+                f.write_str(") ~ ANY)*")
+            }
+            Self::Push(expr) => write!(f, "PUSH({})", expr),
+            Self::NodeTag(expr, tag) => write!(f, "(#{} = {})", tag, expr),
+            Self::RestoreOnErr(expr) => write!(f, "{}", expr),
+        }
+    }
+}
+
+/// A terminal that may start a match.
+///
+/// Implicit `WHITESPACE` and `COMMENT` are not taken into account.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Terminal {
+    /// Exact string.
+    Str(&'static str),
+    /// Case-insensitive string.
+    Insens(&'static str),
+    /// Character range.
+    Range(char, char),
+    /// Built-in rule, such as `ANY`, `ASCII_DIGIT` or `PEEK`.
+    Builtin(&'static str),
+}
+
+impl fmt::Display for Terminal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Str(s) => write!(f, "{:?}", s),
+            Self::Insens(s) => write!(f, "^{:?}", s),
+            Self::Range(start, end) => write!(f, "{:?}..{:?}", start, end),
+            Self::Builtin(name) => f.write_str(name),
+        }
+    }
+}
+
+/// Metadata of a rule.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct RuleInfo {
+    /// Rule name.
+    pub name: &'static str,
+    /// Rule kind.
+    pub kind: RuleKind,
+    /// Optimized expression.
+    pub expr: &'static Expr,
+    /// Terminals that may start a match, sorted and deduplicated.
+    pub first: &'static [Terminal],
+    /// Whether the rule may succeed without consuming any input.
+    pub nullable: bool,
+}
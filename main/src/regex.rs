@@ -0,0 +1,177 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Regular expressions in grammars, written as `re"..."` literals.
+//!
+//! With feature `regex` of the generator, each literal is compiled into a sparse DFA of
+//! [regex-automata](https://docs.rs/regex-automata) when the parser is generated,
+//! and the serialized DFA is embedded in the generated code as a [`Compiled`].
+//! [`Regex`] matches it at the current position, as if the pattern started with `^`,
+//! and takes the match preferred by the pattern, such as the longest one for greedy repetitions.
+//!
+//! DFAs are serialized and deserialized by regex-automata of the generator and of this crate,
+//! so they should be of the same major version.
+
+use crate::{
+    fold::Foldable,
+    iterators::Pair,
+    iterators::Pairs,
+    metrics::HeapSize,
+    position::Position,
+    recovery::{Provenance, Recovered},
+    span::Span,
+    tracker::Tracker,
+    RuleType, Stack, TypedNode,
+};
+use alloc::boxed::Box;
+use core::{fmt, hash, iter, marker::PhantomData};
+use regex_automata::{
+    dfa::{sparse::DFA, Automaton},
+    Anchored, Input,
+};
+use std::sync::OnceLock;
+
+/// A serialized DFA, which is deserialized on first use.
+pub struct Compiled {
+    bytes: &'static [u8],
+    dfa: OnceLock<DFA<&'static [u8]>>,
+}
+
+impl Compiled {
+    /// Wrap bytes serialized with the endianness of the target.
+    pub const fn new(bytes: &'static [u8]) -> Self {
+        Self {
+            bytes,
+            dfa: OnceLock::new(),
+        }
+    }
+    /// The deserialized DFA.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the bytes are not a valid anchored DFA,
+    /// such as ones serialized by an incompatible version of regex-automata.
+    pub fn dfa(&self) -> &DFA<&'static [u8]> {
+        self.dfa.get_or_init(|| match DFA::from_bytes(self.bytes) {
+            Ok((dfa, _)) => dfa,
+            Err(error) => panic!("Invalid compiled regular expression: {error}."),
+        })
+    }
+    /// End of the match starting at `start` in `input`.
+    pub fn find_at(&self, input: &str, start: usize) -> Option<usize> {
+        let input = Input::new(input).range(start..).anchored(Anchored::Yes);
+        match self.dfa().try_search_fwd(&input) {
+            Ok(matched) => matched.map(|matched| matched.offset()),
+            Err(error) => panic!("Failed to match a regular expression: {error}."),
+        }
+    }
+}
+
+/// A wrapper of a compiled regular expression.
+pub trait RegexWrapper {
+    /// The pattern as written in the grammar.
+    const PATTERN: &'static str;
+    /// The DFA compiled from [`RegexWrapper::PATTERN`].
+    fn compiled() -> &'static Compiled;
+}
+
+/// Text matched by a regular expression. See [module-level documentation](self).
+pub struct Regex<'i, T: RegexWrapper> {
+    /// Matched span.
+    pub span: Span<'i>,
+    _phantom: PhantomData<&'i T>,
+}
+
+impl<'i, T: RegexWrapper> Regex<'i, T> {
+    /// Matched text.
+    pub fn as_str(&self) -> &'i str {
+        self.span.as_str()
+    }
+}
+
+impl<'i, T: RegexWrapper> From<Span<'i>> for Regex<'i, T> {
+    fn from(span: Span<'i>) -> Self {
+        Self {
+            span,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<'i, R: RuleType, T: RegexWrapper> TypedNode<'i, R> for Regex<'i, T> {
+    #[inline]
+    fn try_parse_with(
+        input: Position<'i>,
+        _stack: &mut Stack<Span<'i>>,
+        _tracker: &mut Tracker<'i, R>,
+    ) -> Result<(Position<'i>, Self), ()> {
+        let end = T::compiled().find_at(input.input, input.pos()).ok_or(())?;
+        let span = Span::new(input.input, input.pos(), end).ok_or(())?;
+        Ok((span.end_pos(), Self::from(span)))
+    }
+}
+
+impl<'i: 'n, 'n, R: RuleType + 'n, T: RegexWrapper> Pairs<'i, 'n, R> for Regex<'i, T> {
+    type Iter = iter::Empty<&'n dyn Pair<'i, 'n, R>>;
+    type IntoIter = iter::Empty<Box<dyn Pair<'i, 'n, R> + 'n>>;
+
+    fn iter_pairs(&'n self) -> Self::Iter {
+        iter::empty()
+    }
+    fn into_iter_pairs(self) -> Self::IntoIter {
+        iter::empty()
+    }
+}
+
+impl<F: ?Sized, T: RegexWrapper> Foldable<F> for Regex<'_, T> {
+    #[inline]
+    fn fold_with(self, _folder: &mut F) -> Self {
+        self
+    }
+}
+
+impl<T: RegexWrapper> HeapSize for Regex<'_, T> {
+    #[inline]
+    fn heap_size(&self) -> usize {
+        0
+    }
+}
+
+impl<T: RegexWrapper> Recovered for Regex<'_, T> {
+    #[inline]
+    fn provenance(&self) -> Provenance {
+        Provenance::PARSED
+    }
+}
+
+impl<T: RegexWrapper> Clone for Regex<'_, T> {
+    fn clone(&self) -> Self {
+        Self::from(self.span)
+    }
+}
+
+impl<T: RegexWrapper> fmt::Debug for Regex<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Regex").field("span", &self.span).finish()
+    }
+}
+
+impl<T: RegexWrapper> PartialEq for Regex<'_, T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.span == other.span
+    }
+}
+
+impl<T: RegexWrapper> Eq for Regex<'_, T> {}
+
+impl<T: RegexWrapper> hash::Hash for Regex<'_, T> {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.span.hash(state);
+    }
+}
@@ -0,0 +1,324 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Reports of errors and diagnostics by [miette](https://docs.rs/miette).
+//!
+//! Errors don't keep their inputs, so a [`Report`] owns a copy of the input,
+//! and implements [`miette::Diagnostic`] with it as the source code,
+//! the location of the error as a label, and the rules being attempted as help text.
+//! [`WithSource::with_source`] converts errors in results,
+//! so that they can be returned with `?` as [`miette::Report`]s:
+//!
+//! ```ignore
+//! use pest_typed::report::WithSource;
+//!
+//! fn parse(input: &str) -> miette::Result<usize> {
+//!     let items = pairs::items::try_parse_with_context(input).with_source(input)?;
+//!     Ok(items.span.as_str().len())
+//! }
+//! ```
+
+use crate::{
+    controller::ControlledError,
+    diagnostic::{Diagnostic, Severity},
+    tracker::ContextualError,
+};
+use alloc::{boxed::Box, format, string::String, vec::Vec};
+use core::{fmt, ops::Range};
+use miette::{LabeledSpan, MietteError, MietteSpanContents, SourceCode, SourceSpan, SpanContents};
+use pest::{error::Error, RuleType};
+
+/// An input with its name.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+struct Input {
+    name: Option<String>,
+    text: String,
+}
+
+impl SourceCode for Input {
+    fn read_span<'a>(
+        &'a self,
+        span: &SourceSpan,
+        context_lines_before: usize,
+        context_lines_after: usize,
+    ) -> Result<Box<dyn SpanContents<'a> + 'a>, MietteError> {
+        let contents = self
+            .text
+            .read_span(span, context_lines_before, context_lines_after)?;
+        Ok(match &self.name {
+            Some(name) => Box::new(MietteSpanContents::new_named(
+                name.clone(),
+                contents.data(),
+                *contents.span(),
+                contents.line(),
+                contents.column(),
+                contents.line_count(),
+            )),
+            None => contents,
+        })
+    }
+}
+
+/// A labeled range.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+struct Label {
+    range: Range<usize>,
+    message: String,
+    primary: bool,
+}
+
+/// Everything of a [`Report`] except its message, boxed to keep results small.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+struct Details {
+    severity: Severity,
+    code: Option<String>,
+    labels: Vec<Label>,
+    help: Vec<String>,
+    input: Input,
+}
+
+/// An error or a diagnostic with its input, which implements [`miette::Diagnostic`].
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Report {
+    message: String,
+    details: Box<Details>,
+}
+
+impl Report {
+    /// Convert a diagnostic of `input`.
+    ///
+    /// Labels on other inputs are dropped.
+    /// Trailing notes and suggestions are written as help text.
+    pub fn new(diagnostic: &Diagnostic<'_>, input: &str) -> Self {
+        let labels = diagnostic
+            .labels
+            .iter()
+            .filter(|label| core::ptr::eq(label.span.get_input(), input))
+            .map(|label| Label {
+                range: label.span.start()..label.span.end(),
+                message: label.message.clone(),
+                primary: label.primary,
+            })
+            .collect();
+        let notes = diagnostic
+            .notes
+            .iter()
+            .map(|(severity, note)| match severity {
+                Severity::Help => note.clone(),
+                severity => format!("{}: {}", severity, note),
+            });
+        let suggestions = diagnostic
+            .suggestions
+            .iter()
+            .map(|suggestion| format!("{}", suggestion));
+        Self {
+            message: diagnostic.message.clone(),
+            details: Box::new(Details {
+                severity: diagnostic.severity,
+                code: diagnostic.code.clone(),
+                labels,
+                help: notes.chain(suggestions).collect(),
+                input: Input {
+                    name: None,
+                    text: String::from(input),
+                },
+            }),
+        }
+    }
+    /// Convert an error produced when parsing `input`, named by the path of the error if any.
+    pub fn from_error<R: RuleType>(error: &Error<R>, input: &str) -> Self {
+        let res = Self::new(&Diagnostic::from_error(error, input), input);
+        match error.path() {
+            Some(path) => res.with_name(path),
+            None => res,
+        }
+    }
+    /// Name the input, such as with its path.
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.details.input.name = Some(name.into());
+        self
+    }
+    /// Add a line of help text.
+    pub fn with_help(mut self, help: impl Into<String>) -> Self {
+        self.details.help.push(help.into());
+        self
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for Report {}
+
+impl miette::Diagnostic for Report {
+    fn code<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        self.details
+            .code
+            .as_ref()
+            .map(|code| Box::new(code) as Box<dyn fmt::Display>)
+    }
+    fn severity(&self) -> Option<miette::Severity> {
+        Some(match self.details.severity {
+            Severity::Error => miette::Severity::Error,
+            Severity::Warning => miette::Severity::Warning,
+            Severity::Note | Severity::Help => miette::Severity::Advice,
+        })
+    }
+    fn help<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        let help = &self.details.help;
+        match help.is_empty() {
+            true => None,
+            false => Some(Box::new(help.join("\n"))),
+        }
+    }
+    fn source_code(&self) -> Option<&dyn SourceCode> {
+        Some(&self.details.input)
+    }
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        let labels = &self.details.labels;
+        match labels.is_empty() {
+            true => None,
+            false => Some(Box::new(labels.iter().map(|label| {
+                let message = (!label.message.is_empty()).then(|| label.message.clone());
+                let span = SourceSpan::from(label.range.clone());
+                match label.primary {
+                    true => LabeledSpan::new_primary_with_span(message, span),
+                    false => LabeledSpan::new_with_span(message, span),
+                }
+            }))),
+        }
+    }
+}
+
+/// Errors that can be converted into [`Report`]s with their inputs.
+pub trait ToReport {
+    /// Convert with the input that produced the error.
+    fn to_report(&self, input: &str) -> Report;
+}
+
+impl<R: RuleType> ToReport for Error<R> {
+    fn to_report(&self, input: &str) -> Report {
+        Report::from_error(self, input)
+    }
+}
+
+/// Rules being attempted are written as help text, such as `while parsing items > item`.
+impl<R: RuleType> ToReport for ContextualError<R> {
+    fn to_report(&self, input: &str) -> Report {
+        let res = self.error().to_report(input);
+        match self.rule_stack() {
+            [] => res,
+            rules => {
+                let rules: Vec<_> = rules.iter().map(|rule| format!("{:?}", rule)).collect();
+                res.with_help(format!("while parsing {}", rules.join(" > ")))
+            }
+        }
+    }
+}
+
+impl<R: RuleType> ToReport for ControlledError<R> {
+    fn to_report(&self, input: &str) -> Report {
+        match self {
+            Self::Cancelled => Report::new(&Diagnostic::error(format!("{}", self)), input),
+            Self::Failed(error) => error.to_report(input),
+        }
+    }
+}
+
+/// Convert errors in results into [`Report`]s.
+pub trait WithSource<T> {
+    /// Convert the error with the input that produced it.
+    fn with_source(self, input: &str) -> Result<T, Report>;
+}
+
+impl<T, E: ToReport> WithSource<T> for Result<T, E> {
+    fn with_source(self, input: &str) -> Result<T, Report> {
+        self.map_err(|error| error.to_report(input))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Report, ToReport, WithSource};
+    use crate::{
+        controller::ControlledError,
+        diagnostic::{Diagnostic, Severity},
+        Span,
+    };
+    use alloc::{string::String, string::ToString, vec, vec::Vec};
+    use miette::{Diagnostic as _, SourceSpan};
+    use pest::error::{Error, ErrorVariant};
+
+    #[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+    enum Rule {
+        A,
+    }
+
+    #[test]
+    fn diagnostic() {
+        let input = "let x = 1;";
+        let diagnostic = Diagnostic::warning("`x` is unused")
+            .with_code("W01")
+            .with_primary(Span::new(input, 4, 5).unwrap(), "declared here")
+            .with_secondary(Span::new("other", 0, 5).unwrap(), "elsewhere")
+            .with_note("it's never read")
+            .with_suggestion(Span::new(input, 4, 5).unwrap(), "_x");
+        let report = Report::new(&diagnostic, input).with_name("main.txt");
+        assert_eq!(report.to_string(), "`x` is unused");
+        assert_eq!(report.code().unwrap().to_string(), "W01");
+        assert_eq!(report.severity(), Some(miette::Severity::Warning));
+        assert_eq!(
+            report.help().unwrap().to_string(),
+            "note: it's never read\nreplace `x` with `_x`"
+        );
+        let labels: Vec<_> = report.labels().unwrap().collect();
+        assert_eq!(labels.len(), 1);
+        assert!(labels[0].primary());
+        assert_eq!(labels[0].label(), Some("declared here"));
+        assert_eq!(labels[0].inner(), &SourceSpan::from(4..5));
+        let contents = report
+            .source_code()
+            .unwrap()
+            .read_span(labels[0].inner(), 0, 0)
+            .unwrap();
+        assert_eq!(contents.name(), Some("main.txt"));
+        assert_eq!(contents.data(), b"x");
+    }
+
+    #[test]
+    fn errors() {
+        let error = Error::new_from_pos(
+            ErrorVariant::<Rule>::ParsingError {
+                positives: vec![Rule::A],
+                negatives: vec![],
+            },
+            pest::Position::new("ab", 1).unwrap(),
+        );
+        let report = error.to_report("ab");
+        assert_eq!(report.to_string(), "expected A");
+        assert_eq!(report.details.severity, Severity::Error);
+        assert!(report.help().is_none());
+        let labels: Vec<_> = report.labels().unwrap().collect();
+        assert_eq!(labels[0].inner(), &SourceSpan::from(1..1));
+        assert_eq!(labels[0].label(), None);
+
+        let named = error.clone().with_path("a.txt").to_report("ab");
+        assert_eq!(named.details.input.name.as_deref(), Some("a.txt"));
+
+        let res: Result<(), _> = Err(ControlledError::<Rule>::Cancelled);
+        let report = res.with_source("ab").unwrap_err();
+        assert_eq!(report.to_string(), "Parsing cancelled.");
+        assert!(report.labels().is_none());
+        let report: miette::Report = report.into();
+        assert_eq!(report.to_string(), String::from("Parsing cancelled."));
+    }
+}
@@ -0,0 +1,277 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Round-trip checks of printers: parsing the printed text of a tree gives the same tree.
+//!
+//! [`round_trip!`](crate::round_trip!) parses an input, prints the tree with a printer,
+//! parses the printed text again, and compares the [`Shape`]s of both trees,
+//! so that ambiguities of the grammar and lossy printers are caught.
+//! Inputs that don't parse are skipped,
+//! so inputs can be generated by any property-based testing framework.
+//!
+//! With feature `proptest`, [`proptest_round_trip!`](crate::proptest_round_trip!) runs round trips
+//! of inputs generated by a [proptest](https://docs.rs/proptest) strategy,
+//! and panics with the smallest failing input that proptest finds:
+//!
+//! ```ignore
+//! use pest_typed::round_trip::proptest::string::string_regex;
+//!
+//! #[test]
+//! fn print() {
+//!     let inputs = string_regex("[a-z ,\\[\\]]*").unwrap();
+//!     pest_typed::proptest_round_trip!(pairs::list<'_>, inputs, |list: &pairs::list<'_>| print(list));
+//! }
+//! ```
+
+use crate::iterators::Pair;
+use alloc::{boxed::Box, string::String, vec::Vec};
+use core::fmt;
+use pest::{error::Error, RuleType};
+
+/// Rules and texts of a tree, without positions.
+///
+/// Nodes without inner nodes keep their texts, and other nodes only keep their rules,
+/// so trivia between nodes, such as whitespaces, doesn't matter.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Shape<R> {
+    /// Rule of the node.
+    pub rule: R,
+    /// Text of the node, if it has no inner nodes.
+    pub text: Option<String>,
+    /// Shapes of inner nodes.
+    pub children: Vec<Shape<R>>,
+}
+
+impl<R: RuleType> Shape<R> {
+    /// Shape of the tree under `pair`.
+    pub fn of<'i: 'n, 'n>(pair: &'n dyn Pair<'i, 'n, R>) -> Self
+    where
+        R: 'n,
+    {
+        let children: Vec<_> = pair.inner().map(Self::of).collect();
+        let text = children
+            .is_empty()
+            .then(|| String::from(pair.span().as_str()));
+        Self {
+            rule: pair.rule(),
+            text,
+            children,
+        }
+    }
+}
+
+impl<R: RuleType> fmt::Display for Shape<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "({:?}", self.rule)?;
+        if let Some(text) = &self.text {
+            write!(f, " {:?}", text)?;
+        }
+        for child in &self.children {
+            write!(f, " {}", child)?;
+        }
+        f.write_str(")")
+    }
+}
+
+/// Failure of a round trip.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum RoundTripError<R: RuleType> {
+    /// The printed text doesn't parse.
+    Reparse {
+        /// Original input.
+        input: String,
+        /// Printed text.
+        printed: String,
+        /// Error when parsing the printed text.
+        error: Error<R>,
+    },
+    /// The printed text parses into another tree.
+    Mismatch {
+        /// Original input.
+        input: String,
+        /// Printed text.
+        printed: String,
+        /// Shape of the original tree.
+        original: Shape<R>,
+        /// Shape of the tree of the printed text.
+        reparsed: Shape<R>,
+    },
+}
+
+impl<R: RuleType> fmt::Display for RoundTripError<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Reparse {
+                input,
+                printed,
+                error,
+            } => write!(
+                f,
+                "{:?} is printed as {:?}, which doesn't parse:\n{}",
+                input, printed, error
+            ),
+            Self::Mismatch {
+                input,
+                printed,
+                original,
+                reparsed,
+            } => write!(
+                f,
+                "{:?} is printed as {:?}, which parses into another tree:\n  original: {}\n  reparsed: {}",
+                input, printed, original, reparsed
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: RuleType> std::error::Error for RoundTripError<R> {}
+
+/// Call `print` on `tree`. Used by [`round_trip!`](crate::round_trip!) to infer types of closures.
+#[doc(hidden)]
+pub fn print<T>(tree: &T, print: impl FnOnce(&T) -> String) -> String {
+    print(tree)
+}
+
+/// Compare the original tree with the tree of the printed text.
+pub fn compare<R: RuleType>(
+    input: &str,
+    printed: String,
+    original: Shape<R>,
+    reparsed: Result<Shape<R>, Error<R>>,
+) -> Result<(), Box<RoundTripError<R>>> {
+    match reparsed {
+        Ok(reparsed) if reparsed == original => Ok(()),
+        Ok(reparsed) => Err(Box::new(RoundTripError::Mismatch {
+            input: String::from(input),
+            printed,
+            original,
+            reparsed,
+        })),
+        Err(error) => Err(Box::new(RoundTripError::Reparse {
+            input: String::from(input),
+            printed,
+            error,
+        })),
+    }
+}
+
+/// Check that parsing `input` as `$node`, printing the tree with `$print`,
+/// and parsing the printed text again gives the same [`Shape`](crate::round_trip::Shape).
+///
+/// `$print` is a function from a reference of `$node` to a string.
+/// Returns `Ok(false)` if `input` doesn't parse, `Ok(true)` if the round trip succeeds,
+/// and a boxed [`RoundTripError`](crate::round_trip::RoundTripError) otherwise.
+/// See [`round_trip`](crate::round_trip) for details.
+#[macro_export]
+macro_rules! round_trip {
+    ($node:ty, $input:expr, $print:expr) => {{
+        let input: &::core::primitive::str = $input;
+        match <$node as $crate::ParsableTypedNode<'_, _>>::try_parse(input) {
+            ::core::result::Result::Err(_) => ::core::result::Result::Ok(false),
+            ::core::result::Result::Ok(tree) => {
+                let printed = $crate::round_trip::print(&tree, $print);
+                let original = $crate::round_trip::Shape::of(&tree);
+                let reparsed = <$node as $crate::ParsableTypedNode<'_, _>>::try_parse(&printed)
+                    .map(|tree| $crate::round_trip::Shape::of(&tree));
+                $crate::round_trip::compare(input, printed, original, reparsed).map(|()| true)
+            }
+        }
+    }};
+}
+
+/// Check round trips of all `$inputs` with [`round_trip!`], and panic on the first failure.
+///
+/// `$inputs` is anything iterable over string slices.
+#[macro_export]
+macro_rules! assert_round_trip {
+    ($node:ty, $inputs:expr, $print:expr) => {{
+        for input in $inputs {
+            if let ::core::result::Result::Err(error) = $crate::round_trip!($node, input, $print) {
+                ::core::panic!("{}", error);
+            }
+        }
+    }};
+}
+
+/// Run `test` on inputs generated by `strategy` with a default [`TestRunner`](proptest::test_runner::TestRunner).
+///
+/// `test` is usually a [`round_trip!`](crate::round_trip!) of the input.
+/// Returns the smallest failing input and its error, after shrinking, if any round trip fails.
+#[cfg(feature = "proptest")]
+pub fn check<R: RuleType, S>(
+    strategy: S,
+    test: impl Fn(&str) -> Result<bool, Box<RoundTripError<R>>>,
+) -> Result<(), proptest::test_runner::TestError<String>>
+where
+    S: proptest::strategy::Strategy<Value = String>,
+{
+    use proptest::test_runner::{TestCaseError, TestRunner};
+    TestRunner::default().run(&strategy, |input| match test(&input) {
+        Ok(_) => Ok(()),
+        Err(error) => Err(TestCaseError::fail(alloc::format!("{}", error))),
+    })
+}
+
+#[cfg(feature = "proptest")]
+pub use ::proptest;
+
+/// Check round trips of inputs generated by the proptest strategy `$strategy` with [`round_trip!`],
+/// and panic with the smallest failing input.
+///
+/// `$strategy` generates [`String`]s. See [`check`](crate::round_trip::check).
+#[cfg(feature = "proptest")]
+#[macro_export]
+macro_rules! proptest_round_trip {
+    ($node:ty, $strategy:expr, $print:expr) => {{
+        if let ::core::result::Result::Err(error) =
+            $crate::round_trip::check($strategy, |input: &::core::primitive::str| {
+                $crate::round_trip!($node, input, $print)
+            })
+        {
+            ::core::panic!("{}", error);
+        }
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{compare, RoundTripError, Shape};
+    use alloc::{string::String, string::ToString, vec};
+
+    #[test]
+    fn display() {
+        let leaf = |text: &str| Shape {
+            rule: (),
+            text: Some(String::from(text)),
+            children: vec![],
+        };
+        let original = Shape {
+            rule: (),
+            text: None,
+            children: vec![leaf("a"), leaf("b")],
+        };
+        assert_eq!(original.to_string(), r#"(() (() "a") (() "b"))"#);
+        assert_eq!(
+            compare(
+                "a b",
+                String::from("a b"),
+                original.clone(),
+                Ok(original.clone())
+            ),
+            Ok(())
+        );
+        let error = compare("a b", String::from("ab"), original, Ok(leaf("ab"))).unwrap_err();
+        assert!(matches!(*error, RoundTripError::Mismatch { .. }));
+        assert_eq!(
+            error.to_string(),
+            "\"a b\" is printed as \"ab\", which parses into another tree:\n  original: (() (() \"a\") (() \"b\"))\n  reparsed: (() \"ab\")"
+        );
+    }
+}
@@ -13,6 +13,8 @@ use crate::{
     predefined_node::EOI, tracker::Tracker, NeverFailedTypedNode, Position, RuleType, Span, Stack,
     TypedNode,
 };
+use alloc::string::String;
+use core::fmt;
 
 /// Implement [`Pairs`](crate::iterators::Pairs) for a struct that is a [`Pair`](crate::iterators::Pair).
 ///
@@ -255,6 +257,9 @@ macro_rules! impl_pair_with_content {
 #[macro_export]
 macro_rules! impl_pair {
     ($name:ident, $Rule:ty, $rule:expr, $inner:ty, $atomicity:expr, Expression) => {};
+    ($name:ident, $Rule:ty, $rule:expr, $inner:ty, $atomicity:tt, Span) => {
+        ::pest_typed::impl_pair_with_empty!($name, $Rule, $rule);
+    };
     ($name:ident, $Rule:ty, $rule:expr, $inner:ty, true, $emit:tt) => {
         ::pest_typed::impl_pair_with_empty!($name, $Rule, $rule);
     };
@@ -416,17 +421,55 @@ macro_rules! impl_rule_wrapper {
 /// - `$($doc:literal)*`. A list of strings that is prepended to generated struct as document comments.
 /// - `$inner:ty`. Type of inner parsing expression.
 /// - `$emission:tt`. `Span`, `Expression` or `Both`.
+/// - `non_exhaustive`. Optional. Mark the struct `#[non_exhaustive]`, keep fields inside the crate,
+///   and read them with methods `content` and `span` instead.
 #[macro_export]
 macro_rules! declare_rule_struct {
-    ($name:ident, $($doc:literal)*, $inner:ty, Expression) => {
+    ($name:ident, $($doc:literal)*, $inner:ty, $emission:tt) => {
+        ::pest_typed::declare_rule_struct!(@$name, $($doc)*, $inner, $emission, pub,);
+    };
+    ($name:ident, $($doc:literal)*, $inner:ty, $emission:tt, non_exhaustive) => {
+        ::pest_typed::declare_rule_struct!(@$name, $($doc)*, $inner, $emission, pub(crate), #[non_exhaustive]);
+        ::pest_typed::declare_rule_struct!(@accessers $name, $inner, $emission);
+    };
+    (@accessers $name:ident, $inner:ty, Expression) => {
+        ::pest_typed::declare_rule_struct!(@content $name, $inner);
+    };
+    (@accessers $name:ident, $inner:ty, Span) => {
+        ::pest_typed::declare_rule_struct!(@span $name);
+    };
+    (@accessers $name:ident, $inner:ty, Both) => {
+        ::pest_typed::declare_rule_struct!(@content $name, $inner);
+        ::pest_typed::declare_rule_struct!(@span $name);
+    };
+    (@content $name:ident, $inner:ty) => {
+        impl<'i, const INHERITED: ::core::primitive::usize> $name<'i, INHERITED> {
+            /// Matched expression.
+            #[allow(dead_code, clippy::type_complexity)]
+            pub fn content(&self) -> &$inner {
+                &self.content
+            }
+        }
+    };
+    (@span $name:ident) => {
+        impl<'i, const INHERITED: ::core::primitive::usize> $name<'i, INHERITED> {
+            /// Span of matched expression.
+            #[allow(dead_code)]
+            pub fn span(&self) -> ::pest_typed::Span<'i> {
+                self.span
+            }
+        }
+    };
+    (@$name:ident, $($doc:literal)*, $inner:ty, Expression, $vis:vis, $(#[$attr:meta])*) => {
         $(
             #[doc = $doc]
         )*
         #[allow(non_camel_case_types)]
         #[derive(Clone, Hash, PartialEq, Eq)]
+        $(#[$attr])*
         pub struct $name<'i, const INHERITED: ::core::primitive::usize = 1> {
             /// Matched expression.
-            pub content: ::pest_typed::re_exported::Box<$inner>,
+            $vis content: ::pest_typed::re_exported::Box<$inner>,
             _phantom: ::core::marker::PhantomData<&'i ::core::primitive::str>,
         }
         impl<'i, const INHERITED: ::core::primitive::usize> ::core::fmt::Debug for $name<'i, INHERITED> {
@@ -436,16 +479,40 @@ macro_rules! declare_rule_struct {
                     .finish()
             }
         }
+        impl<'i, const INHERITED: ::core::primitive::usize> $name<'i, INHERITED> {
+            /// Create a node from its inner expression without parsing.
+            #[allow(dead_code, clippy::type_complexity)]
+            pub fn new(content: $inner) -> Self {
+                Self {
+                    content: ::pest_typed::re_exported::Box::new(content),
+                    _phantom: ::core::marker::PhantomData,
+                }
+            }
+            /// Rebuild the node by folding its inner expression with `folder`.
+            #[allow(dead_code)]
+            pub fn fold_children<F: ?::core::marker::Sized>(self, folder: &mut F) -> Self
+            where
+                $inner: ::pest_typed::fold::Foldable<F>,
+            {
+                Self::new(::pest_typed::fold::Foldable::fold_with(*self.content, folder))
+            }
+        }
+        impl<'i, const INHERITED: ::core::primitive::usize> ::pest_typed::metrics::HeapSize for $name<'i, INHERITED> {
+            fn heap_size(&self) -> ::core::primitive::usize {
+                ::pest_typed::metrics::HeapSize::heap_size(&self.content)
+            }
+        }
     };
-    ($name:ident, $($doc:literal)*, $inner:ty, Span) => {
+    (@$name:ident, $($doc:literal)*, $inner:ty, Span, $vis:vis, $(#[$attr:meta])*) => {
         $(
             #[doc = $doc]
         )*
         #[allow(non_camel_case_types)]
         #[derive(Clone, Hash, PartialEq, Eq)]
+        $(#[$attr])*
         pub struct $name<'i, const INHERITED: ::core::primitive::usize = 1> {
             /// Span of matched expression.
-            pub span: ::pest_typed::Span<'i>,
+            $vis span: ::pest_typed::Span<'i>,
         }
         impl<'i, const INHERITED: ::core::primitive::usize> ::core::fmt::Debug for $name<'i, INHERITED> {
             fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
@@ -454,18 +521,38 @@ macro_rules! declare_rule_struct {
                     .finish()
             }
         }
+        impl<'i, const INHERITED: ::core::primitive::usize> $name<'i, INHERITED> {
+            /// Create a node from its span without parsing.
+            ///
+            /// See [`Span::synthetic`](::pest_typed::Span::synthetic) for spans not taken from real inputs.
+            #[allow(dead_code)]
+            pub fn new(span: ::pest_typed::Span<'i>) -> Self {
+                Self { span }
+            }
+            /// Atomic rules have no inner nodes, so this returns the node itself.
+            #[allow(dead_code)]
+            pub fn fold_children<F: ?::core::marker::Sized>(self, _folder: &mut F) -> Self {
+                self
+            }
+        }
+        impl<'i, const INHERITED: ::core::primitive::usize> ::pest_typed::metrics::HeapSize for $name<'i, INHERITED> {
+            fn heap_size(&self) -> ::core::primitive::usize {
+                0
+            }
+        }
     };
-    ($name:ident, $($doc:literal)*, $inner:ty, Both) => {
+    (@$name:ident, $($doc:literal)*, $inner:ty, Both, $vis:vis, $(#[$attr:meta])*) => {
         $(
             #[doc = $doc]
         )*
         #[allow(non_camel_case_types)]
         #[derive(Clone, Hash, PartialEq, Eq)]
+        $(#[$attr])*
         pub struct $name<'i, const INHERITED: ::core::primitive::usize = 1> {
             /// Matched expression.
-            pub content: ::pest_typed::re_exported::Box<$inner>,
+            $vis content: ::pest_typed::re_exported::Box<$inner>,
             /// Span of matched expression.
-            pub span: ::pest_typed::Span<'i>,
+            $vis span: ::pest_typed::Span<'i>,
         }
         impl<'i, const INHERITED: ::core::primitive::usize> ::core::fmt::Debug for $name<'i, INHERITED> {
             fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
@@ -475,6 +562,31 @@ macro_rules! declare_rule_struct {
                     .finish()
             }
         }
+        impl<'i, const INHERITED: ::core::primitive::usize> $name<'i, INHERITED> {
+            /// Create a node from its inner expression and span without parsing.
+            ///
+            /// See [`Span::synthetic`](::pest_typed::Span::synthetic) for spans not taken from real inputs.
+            #[allow(dead_code, clippy::type_complexity)]
+            pub fn new(content: $inner, span: ::pest_typed::Span<'i>) -> Self {
+                Self {
+                    content: ::pest_typed::re_exported::Box::new(content),
+                    span,
+                }
+            }
+            /// Rebuild the node by folding its inner expression with `folder`.
+            #[allow(dead_code)]
+            pub fn fold_children<F: ?::core::marker::Sized>(self, folder: &mut F) -> Self
+            where
+                $inner: ::pest_typed::fold::Foldable<F>,
+            {
+                Self::new(::pest_typed::fold::Foldable::fold_with(*self.content, folder), self.span)
+            }
+        }
+        impl<'i, const INHERITED: ::core::primitive::usize> ::pest_typed::metrics::HeapSize for $name<'i, INHERITED> {
+            fn heap_size(&self) -> ::core::primitive::usize {
+                ::pest_typed::metrics::HeapSize::heap_size(&self.content)
+            }
+        }
     };
 }
 
@@ -514,6 +626,27 @@ macro_rules! tag {
                 }
             }
         }
+        impl<'i, const INHERITED: usize> $name<'i, INHERITED> {
+            /// Create a tagged node from its inner expression and span without parsing.
+            #[allow(dead_code, clippy::type_complexity)]
+            pub fn new(content: $inner, span: ::pest_typed::Span<'i>) -> Self {
+                Self { content, span }
+            }
+        }
+        impl<'i, const INHERITED: usize, F: ?::core::marker::Sized> ::pest_typed::fold::Foldable<F>
+            for $name<'i, INHERITED>
+        where
+            $inner: ::pest_typed::fold::Foldable<F>,
+        {
+            fn fold_with(self, folder: &mut F) -> Self {
+                Self::new(self.content.fold_with(folder), self.span)
+            }
+        }
+        impl<'i, const INHERITED: usize> ::pest_typed::metrics::HeapSize for $name<'i, INHERITED> {
+            fn heap_size(&self) -> usize {
+                self.content.heap_size()
+            }
+        }
         ::pest_typed::impl_deref_with_content!($name, $inner);
         ::pest_typed::impl_pairs_with_inner!($name, $Rule, $inner);
     };
@@ -534,6 +667,7 @@ macro_rules! tag {
 ///
 /// - `$atomicity:tt`. `true`, `false` or `INHERITED`.
 /// - `$emission:tt`. `Span`, `Expression` or `Both`.
+/// - `non_exhaustive`. Optional. See [declare_rule_struct](`crate::declare_rule_struct!`).
 ///
 /// See the below macros that reference this:
 /// - [atomic_rule](`crate::atomic_rule!`).
@@ -543,8 +677,8 @@ macro_rules! tag {
 /// - [silent_rule](`crate::silent_rule!`).
 #[macro_export]
 macro_rules! rule {
-    ($name:ident, $($doc:literal)*, $Rule:ty, $rule:expr, $inner:ty, $ignored:ty, $atomicity:tt, $emission:tt) => {
-        ::pest_typed::declare_rule_struct!($name, $($doc)*, $inner, $emission);
+    ($name:ident, $($doc:literal)*, $Rule:ty, $rule:expr, $inner:ty, $ignored:ty, $atomicity:tt, $emission:tt $(, $non_exhaustive:ident)?) => {
+        ::pest_typed::declare_rule_struct!($name, $($doc)*, $inner, $emission $(, $non_exhaustive)?);
         ::pest_typed::impl_rule_wrapper!($name, $Rule, $rule);
         ::pest_typed::impl_try_parse_with!($name, $Rule, $inner, $atomicity, $emission);
         ::pest_typed::impl_parse!($name, $Rule, $ignored, $atomicity);
@@ -655,14 +789,14 @@ macro_rules! rule_eoi {
         ::pest_typed::declare_rule_struct!(
             $name,
             "The rule for end of input.",
-            ::pest_typed::predefined_node::EOI,
+            ::pest_typed::predefined_node::EOI<'i>,
             Both
         );
         ::pest_typed::impl_rule_wrapper!($name, $Rule, <$Rule>::EOI);
         ::pest_typed::impl_try_parse_with!(
             $name,
             $Rule,
-            ::pest_typed::predefined_node::EOI,
+            ::pest_typed::predefined_node::EOI<'i>,
             INHERITED,
             Both
         );
@@ -683,7 +817,21 @@ macro_rules! rule_eoi {
                 )
             }
         }
-        ::pest_typed::impl_deref!($name, ::pest_typed::predefined_node::EOI, Expression);
+        impl<'i, const INHERITED: usize, F: ?::core::marker::Sized> ::pest_typed::fold::Foldable<F>
+            for $name<'i, INHERITED>
+        {
+            fn fold_with(self, _folder: &mut F) -> Self {
+                self
+            }
+        }
+        impl<'i, const INHERITED: usize> ::pest_typed::recovery::Recovered
+            for $name<'i, INHERITED>
+        {
+            fn provenance(&self) -> ::pest_typed::recovery::Provenance {
+                ::pest_typed::recovery::Provenance::PARSED
+            }
+        }
+        ::pest_typed::impl_deref!($name, ::pest_typed::predefined_node::EOI<'i>, Expression);
         ::pest_typed::impl_pairs_with_self!($name, $Rule);
         ::pest_typed::impl_pair_with_empty!($name, $Rule, <$Rule>::EOI);
     };
@@ -708,7 +856,11 @@ pub fn parse<
         Ok((input, res)) => (input, res),
         Err(_) => return Err(()),
     };
-    let (input, _) = IGNORED::parse_with(input, stack);
+    let input = if tracker.is_raw() {
+        input
+    } else {
+        IGNORED::parse_with(input, stack, tracker).0
+    };
     let (_, _) = match tracker.record_during_with(
         input,
         |tracker| EOI::try_parse_with(input, stack, tracker),
@@ -744,3 +896,20 @@ pub fn parse_without_ignore<'i, R: RuleType + 'i, _Self: TypedNode<'i, R>>(
     };
     Ok(res)
 }
+
+/// Error of parsing a name that isn't a rule of the grammar,
+/// returned by [`FromStr`](core::str::FromStr) of generated `Rule`s.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct UnknownRule {
+    /// The name.
+    pub name: String,
+}
+
+impl fmt::Display for UnknownRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown rule `{}`", self.name)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for UnknownRule {}
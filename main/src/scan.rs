@@ -0,0 +1,121 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Find where a rule matches next, without parsing the input before it.
+//!
+//! [`Scan`] is implemented for rule structs with `emit_scanner` (see [pest_typed_derive](https://docs.rs/pest_typed_derive)),
+//! with bytes that a match of the rule may start with, computed from its first set.
+//! [`find_next`] only tries to parse the rule at those bytes.
+
+use crate::{position::Position, tracker::Tracker, RuleType, Stack, TypedNode};
+
+/// A set of bytes.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct ByteSet([u64; 4]);
+
+impl ByteSet {
+    /// Create a set from its bits, where bit `b % 64` of `bits[b / 64]` is set if `b` is in the set.
+    pub const fn from_bits(bits: [u64; 4]) -> Self {
+        Self(bits)
+    }
+    /// Bits of the set. See [`ByteSet::from_bits`].
+    pub const fn bits(&self) -> [u64; 4] {
+        self.0
+    }
+    /// Add a byte.
+    pub fn insert(&mut self, byte: u8) {
+        self.0[(byte / 64) as usize] |= 1 << (byte % 64);
+    }
+    /// Add bytes in `start..=end`.
+    pub fn insert_range(&mut self, start: u8, end: u8) {
+        for byte in start..=end {
+            self.insert(byte);
+        }
+    }
+    /// Whether `byte` is in the set.
+    pub const fn contains(&self, byte: u8) -> bool {
+        self.0[(byte / 64) as usize] & (1 << (byte % 64)) != 0
+    }
+}
+
+/// A node that [`find_next`] can search for.
+pub trait Scan<'i, R: RuleType>: TypedNode<'i, R> {
+    /// Bytes that a non-empty match may start with.
+    ///
+    /// [`None`] if it may start with any byte, or match an empty string.
+    const FIRST_BYTES: Option<ByteSet>;
+}
+
+/// Find the first offset at or after `from` where `T` matches, and return the match.
+///
+/// Offsets are skipped if they are not char boundaries or `T` can't start with the byte there.
+/// The end of the input is always tried.
+///
+/// ```
+/// # use pest_typed::{scan::{find_next, ByteSet, Scan}, predefined_node::Str, StringWrapper};
+/// #[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+/// enum Rule {}
+/// #[derive(Clone, Debug, Hash, PartialEq, Eq)]
+/// struct Foo;
+/// impl StringWrapper for Foo {
+///     const CONTENT: &'static str = "foo";
+/// }
+/// type Node = Str<Foo>;
+/// impl<'i> Scan<'i, Rule> for Node {
+///     const FIRST_BYTES: Option<ByteSet> = Some(ByteSet::from_bits([0, 1 << (b'f' - 64), 0, 0]));
+/// }
+/// assert_eq!(find_next::<Rule, Node>("a fo foo", 0).map(|(pos, _)| pos), Some(5));
+/// assert!(find_next::<Rule, Node>("a fo foo", 6).is_none());
+/// ```
+pub fn find_next<'i, R: RuleType, T: Scan<'i, R>>(
+    input: &'i str,
+    from: usize,
+) -> Option<(usize, T)> {
+    find_next_with(input, from, &Tracker::new(Position::from_start(input)))
+}
+
+/// Find the first position at or after `from` where `T` matches,
+/// parsing in the raw mode and language version of `tracker`. See [`find_next`].
+pub fn find_next_with<'i, R: RuleType, T: Scan<'i, R>>(
+    input: &'i str,
+    from: usize,
+    tracker: &Tracker<'i, R>,
+) -> Option<(usize, T)> {
+    let bytes = input.as_bytes();
+    (from..=input.len())
+        .filter(|&pos| match (T::FIRST_BYTES, bytes.get(pos)) {
+            (_, None) => true,
+            (None, Some(_)) => input.is_char_boundary(pos),
+            (Some(first), Some(&byte)) => first.contains(byte) && input.is_char_boundary(pos),
+        })
+        .find_map(|pos| {
+            let start = Position::new(input, pos)?;
+            let mut stack = Stack::new();
+            let mut tracker = tracker.nested(start);
+            let (_, res) = T::try_parse_with(start, &mut stack, &mut tracker).ok()?;
+            Some((pos, res))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ByteSet;
+
+    #[test]
+    fn byte_set() {
+        let mut set = ByteSet::default();
+        set.insert(b'a');
+        set.insert_range(0xC2, 0xF4);
+        assert!(set.contains(b'a'));
+        assert!(!set.contains(b'b'));
+        assert!(set.contains(0xE4));
+        assert!(!set.contains(0xFF));
+        assert_eq!(ByteSet::from_bits(set.bits()), set);
+    }
+}
@@ -0,0 +1,266 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! A small CSS-like selector language over [`Pair`]s.
+//!
+//! Syntax:
+//!
+//! - `rule` matches a node of rule `rule` (compared with the [`Debug`](core::fmt::Debug) output of the rule enumeration).
+//! - `*` matches a node of any rule.
+//! - `a b` matches `b` that is a descendant of `a`.
+//! - `a > b` matches `b` that is a direct child of `a`.
+//! - `a, b` matches either `a` or `b`.
+//!
+//! Only nodes visible in the [Pairs API](crate::iterators::Pairs) can be selected, so silent rules are transparent.
+
+use crate::iterators::Pair;
+use alloc::{format, string::String, vec::Vec};
+use core::fmt;
+use pest::RuleType;
+
+/// Relation between a step and the step before it.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Combinator {
+    /// Any ancestor.
+    Descendant,
+    /// Direct parent.
+    Child,
+}
+
+/// The part of a selector that matches a single node.
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Matcher {
+    Any,
+    Rule(String),
+}
+
+impl Matcher {
+    fn matches<R: RuleType>(&self, rule: R) -> bool {
+        match self {
+            Self::Any => true,
+            Self::Rule(name) => format!("{:?}", rule) == *name,
+        }
+    }
+}
+
+/// A compound selector such as `a > b c`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct Path {
+    /// Steps from the outermost to the innermost. The first combinator is ignored.
+    steps: Vec<(Combinator, Matcher)>,
+}
+
+impl Path {
+    /// Whether the last node in `ancestors` is matched, given its ancestors.
+    fn matches<'i: 'n, 'n, R: RuleType + 'n>(&self, ancestors: &[&'n dyn Pair<'i, 'n, R>]) -> bool {
+        fn matches_from<'i: 'n, 'n, R: RuleType + 'n>(
+            steps: &[(Combinator, Matcher)],
+            ancestors: &[&'n dyn Pair<'i, 'n, R>],
+        ) -> bool {
+            let ((combinator, matcher), steps) = match steps.split_last() {
+                Some(split) => split,
+                None => return true,
+            };
+            let (node, ancestors) = match ancestors.split_last() {
+                Some(split) => split,
+                None => return false,
+            };
+            if !matcher.matches(node.rule()) {
+                return false;
+            }
+            if steps.is_empty() {
+                return true;
+            }
+            match combinator {
+                Combinator::Child => matches_from(steps, ancestors),
+                Combinator::Descendant => {
+                    (1..=ancestors.len()).any(|len| matches_from(steps, &ancestors[..len]))
+                }
+            }
+        }
+        matches_from(&self.steps, ancestors)
+    }
+}
+
+/// A parsed selector.
+///
+/// ```
+/// # use pest_typed::selector::Selector;
+/// let selector = Selector::parse("function > parameter ident").unwrap();
+/// assert!(Selector::parse("function >").is_err());
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Selector {
+    paths: Vec<Path>,
+}
+
+/// Error when parsing a [Selector].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SelectorError {
+    /// Byte offset in the selector.
+    pub offset: usize,
+    /// Description.
+    pub message: &'static str,
+}
+
+impl fmt::Display for SelectorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at offset {}", self.message, self.offset)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SelectorError {}
+
+impl Selector {
+    /// Parse a selector.
+    pub fn parse(selector: &str) -> Result<Self, SelectorError> {
+        let error = |offset, message| Err(SelectorError { offset, message });
+        let mut paths = Vec::new();
+        let mut steps = Vec::new();
+        let mut combinator = None;
+        let bytes = selector.as_bytes();
+        let mut i = 0;
+        while i < bytes.len() {
+            let c = bytes[i];
+            match c {
+                b' ' | b'\t' | b'\n' | b'\r' => {
+                    if !steps.is_empty() && combinator.is_none() {
+                        combinator = Some(Combinator::Descendant);
+                    }
+                    i += 1;
+                }
+                b'>' => {
+                    if steps.is_empty() || combinator == Some(Combinator::Child) {
+                        return error(i, "unexpected `>`");
+                    }
+                    combinator = Some(Combinator::Child);
+                    i += 1;
+                }
+                b',' => {
+                    if steps.is_empty() || combinator == Some(Combinator::Child) {
+                        return error(i, "unexpected `,`");
+                    }
+                    paths.push(Path {
+                        steps: core::mem::take(&mut steps),
+                    });
+                    combinator = None;
+                    i += 1;
+                }
+                b'*' => {
+                    if !steps.is_empty() && combinator.is_none() {
+                        return error(i, "expected a combinator");
+                    }
+                    steps.push((
+                        combinator.take().unwrap_or(Combinator::Descendant),
+                        Matcher::Any,
+                    ));
+                    i += 1;
+                }
+                c if c == b'_' || c.is_ascii_alphanumeric() => {
+                    if !steps.is_empty() && combinator.is_none() {
+                        return error(i, "expected a combinator");
+                    }
+                    let start = i;
+                    while i < bytes.len() && (bytes[i] == b'_' || bytes[i].is_ascii_alphanumeric())
+                    {
+                        i += 1;
+                    }
+                    steps.push((
+                        combinator.take().unwrap_or(Combinator::Descendant),
+                        Matcher::Rule(String::from(&selector[start..i])),
+                    ));
+                }
+                _ => return error(i, "unexpected character"),
+            }
+        }
+        if steps.is_empty() || combinator == Some(Combinator::Child) {
+            return error(selector.len(), "unexpected end of selector");
+        }
+        paths.push(Path { steps });
+        Ok(Self { paths })
+    }
+
+    /// Whether the last node in `ancestors` is selected, where `ancestors` starts from the root.
+    fn matches<'i: 'n, 'n, R: RuleType + 'n>(&self, ancestors: &[&'n dyn Pair<'i, 'n, R>]) -> bool {
+        self.paths.iter().any(|path| path.matches(ancestors))
+    }
+
+    /// Collect all nodes under `root` (including itself) that are selected, in pre-order.
+    pub fn select<'i: 'n, 'n, R: RuleType + 'n>(
+        &self,
+        root: &'n dyn Pair<'i, 'n, R>,
+    ) -> Vec<&'n dyn Pair<'i, 'n, R>> {
+        fn walk<'i: 'n, 'n, R: RuleType + 'n>(
+            selector: &Selector,
+            ancestors: &mut Vec<&'n dyn Pair<'i, 'n, R>>,
+            res: &mut Vec<&'n dyn Pair<'i, 'n, R>>,
+        ) {
+            let node = match ancestors.last() {
+                Some(node) => *node,
+                None => return,
+            };
+            if selector.matches(ancestors) {
+                res.push(node);
+            }
+            for child in node.inner() {
+                ancestors.push(child);
+                walk(selector, ancestors, res);
+                ancestors.pop();
+            }
+        }
+        let mut res = Vec::new();
+        let mut ancestors = alloc::vec![root];
+        walk(self, &mut ancestors, &mut res);
+        res
+    }
+}
+
+impl core::str::FromStr for Selector {
+    type Err = SelectorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Combinator, Matcher, Selector};
+    use alloc::{string::String, vec};
+
+    #[test]
+    fn parse() {
+        let selector = Selector::parse(" a > b  c,*").unwrap();
+        assert_eq!(selector.paths.len(), 2);
+        assert_eq!(
+            selector.paths[0].steps,
+            vec![
+                (Combinator::Descendant, Matcher::Rule(String::from("a"))),
+                (Combinator::Child, Matcher::Rule(String::from("b"))),
+                (Combinator::Descendant, Matcher::Rule(String::from("c"))),
+            ]
+        );
+        assert_eq!(
+            selector.paths[1].steps,
+            vec![(Combinator::Descendant, Matcher::Any)]
+        );
+    }
+
+    #[test]
+    fn errors() {
+        for (selector, offset) in [("", 0), ("> a", 0), ("a >", 3), ("a > > b", 4), ("a,", 2)] {
+            assert_eq!(Selector::parse(selector).unwrap_err().offset, offset);
+        }
+        assert_eq!(
+            Selector::parse("a ? b").unwrap_err().message,
+            "unexpected character"
+        );
+    }
+}
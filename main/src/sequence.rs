@@ -27,7 +27,7 @@ macro_rules! chains {
 #[macro_export]
 /// Chained iterator.
 ///
-/// Used by [`crate::seq`].
+/// Used by [`crate::seq`], and by [`crate::named_seq`] with `iter_fields` and `into_iter_fields`.
 macro_rules! chain {
     ($pest_typed:ident, $trait:ty, $self: ident, iter, $T0:ty, $t0:tt, ) => {
         <$T0 as $trait>::iter_pairs(&$self.content.$t0)
@@ -41,6 +41,18 @@ macro_rules! chain {
     ($pest_typed:ident, $trait:ty, $self: ident, into_iter, $T0:ty, $t0:tt, $($T:ty, $t:tt, )+) => {
         <$T0 as $trait>::into_iter_pairs($self.content.$t0).chain($pest_typed::chain!($pest_typed, $trait, $self, into_iter, $($T, $t, )*))
     };
+    ($pest_typed:ident, $trait:ty, $self: ident, iter_fields, $T0:ty, $f0:ident, ) => {
+        <$T0 as $trait>::iter_pairs(&$self.$f0)
+    };
+    ($pest_typed:ident, $trait:ty, $self: ident, iter_fields, $T0:ty, $f0:ident, $($T:ty, $f:ident, )+) => {
+        <$T0 as $trait>::iter_pairs(&$self.$f0).chain($pest_typed::chain!($pest_typed, $trait, $self, iter_fields, $($T, $f, )*))
+    };
+    ($pest_typed:ident, $trait:ty, $self: ident, into_iter_fields, $T0:ty, $f0:ident, ) => {
+        <$T0 as $trait>::into_iter_pairs($self.$f0)
+    };
+    ($pest_typed:ident, $trait:ty, $self: ident, into_iter_fields, $T0:ty, $f0:ident, $($T:ty, $f:ident, )+) => {
+        <$T0 as $trait>::into_iter_pairs($self.$f0).chain($pest_typed::chain!($pest_typed, $trait, $self, into_iter_fields, $($T, $f, )*))
+    };
 }
 
 #[macro_export]
@@ -92,11 +104,7 @@ macro_rules! seq {
                     },
                     $(
                         {
-                            let skipped = ::core::array::from_fn(|_| {
-                                let (next, skipped) = Skip::parse_with(input, stack);
-                                input = next;
-                                skipped
-                            });
+                            let skipped = $pest_typed::predefined_node::skip::<R, Skip, SKIP>(&mut input, stack, tracker);
                             let (next, matched) = $T::try_parse_with(input, stack, tracker)?;
                             input = next;
                             $pest_typed::predefined_node::Skipped { skipped, matched }
@@ -201,6 +209,31 @@ macro_rules! seq {
                 ( value.content.$t0, $(value.content.$t, )* )
             }
         }
+        impl<F: ?::core::marker::Sized, $T0: $pest_typed::fold::Foldable<F>, $($T: $pest_typed::fold::Foldable<F>, )*>
+            $pest_typed::fold::Foldable<F> for $name<$T0, $($T, )*>
+        {
+            fn fold_with(self, folder: &mut F) -> Self {
+                let content = self.content;
+                Self::from((
+                    content.$t0.fold_with(folder),
+                    $(content.$t.fold_with(folder), )*
+                ))
+            }
+        }
+        impl<$T0: $pest_typed::metrics::HeapSize, $($T: $pest_typed::metrics::HeapSize, )*>
+            $pest_typed::metrics::HeapSize for $name<$T0, $($T, )*>
+        {
+            fn heap_size(&self) -> ::core::primitive::usize {
+                self.content.$t0.heap_size() $(+ self.content.$t.heap_size())*
+            }
+        }
+        impl<$T0: $pest_typed::recovery::Recovered, $($T: $pest_typed::recovery::Recovered, )*>
+            $pest_typed::recovery::Recovered for $name<$T0, $($T, )*>
+        {
+            fn provenance(&self) -> $pest_typed::recovery::Provenance {
+                self.content.$t0.provenance() $(| self.content.$t.provenance())*
+            }
+        }
         impl<$T0: ::core::fmt::Debug, $($T: ::core::fmt::Debug, )*>
             ::core::fmt::Debug for $name<T0, $($T),*>
         {
@@ -214,6 +247,166 @@ macro_rules! seq {
     };
 }
 
+#[macro_export]
+/// Generate a sequence with named fields, used for named captures such as `key: ident ~ "=" ~ value: expr`.
+///
+/// Fields are elements with their skipped contents, like those of [`crate::seq`],
+/// and dereference to the matched elements.
+macro_rules! named_seq {
+    ($name:ident, $pest_typed:ident, $number:literal, $T0:ident, $f0:ident, $( $T:ident, $f:ident, )* ) => {
+        #[doc = ::core::stringify!(Match a sequence with $number named items.)]
+        #[allow(non_camel_case_types)]
+        #[derive(Clone)]
+        pub struct $name<$T0, $($T, )*> {
+            #[doc = ::core::concat!("Matched and skipped expression `", ::core::stringify!($f0), "`.")]
+            pub $f0: $T0,
+            $(
+                #[doc = ::core::concat!("Matched and skipped expression `", ::core::stringify!($f), "`.")]
+                pub $f: $T,
+            )*
+        }
+        impl<$T0, $($T, )*> ::core::convert::From<( $T0, $($T, )* )>
+            for $name<$T0, $($T, )*>
+        {
+            fn from(($f0, $($f, )*): ( $T0, $($T, )* )) -> Self {
+                Self { $f0, $($f, )* }
+            }
+        }
+        impl<
+                'i,
+                R: $pest_typed::RuleType,
+                $T0: $pest_typed::TypedNode<'i, R>,
+                $($T: $pest_typed::TypedNode<'i, R>, )*
+                Skip: $pest_typed::NeverFailedTypedNode<'i, R> + ::core::default::Default,
+                const SKIP: ::core::primitive::usize,
+            > $pest_typed::TypedNode<'i, R> for $name<
+                $pest_typed::predefined_node::Skipped<$T0, Skip, SKIP>,
+                $(
+                    $pest_typed::predefined_node::Skipped<$T, Skip, SKIP>,
+                )*
+            >
+        {
+            #[inline]
+            fn try_parse_with(
+                mut input: $pest_typed::Position<'i>,
+                stack: &mut $pest_typed::Stack<$pest_typed::Span<'i>>,
+                tracker: &mut $pest_typed::tracker::Tracker<'i, R>,
+            ) -> ::core::result::Result<($pest_typed::Position<'i>, Self), ()> {
+                let $f0 = {
+                    let skipped = ::core::array::from_fn(|_| Skip::default());
+                    let (next, matched) = $T0::try_parse_with(input, stack, tracker)?;
+                    input = next;
+                    $pest_typed::predefined_node::Skipped { skipped, matched }
+                };
+                $(
+                    let $f = {
+                        let skipped = $pest_typed::predefined_node::skip::<R, Skip, SKIP>(&mut input, stack, tracker);
+                        let (next, matched) = $T::try_parse_with(input, stack, tracker)?;
+                        input = next;
+                        $pest_typed::predefined_node::Skipped { skipped, matched }
+                    };
+                )*
+
+                Ok((input, Self { $f0, $($f, )* }))
+            }
+        }
+        impl<
+                'i: 'n,
+                'n,
+                R: $pest_typed::RuleType + 'n,
+                $T0: $pest_typed::iterators::Pairs<'i, 'n, R>,
+                $($T: $pest_typed::iterators::Pairs<'i, 'n, R>, )*
+            > $pest_typed::iterators::Pairs<'i, 'n, R> for $name<$T0, $($T, )*>
+        {
+            type Iter = $pest_typed::chains!($pest_typed, $pest_typed::iterators::Pairs<'i, 'n, R>, Iter, $T0, $($T, )*);
+            type IntoIter = $pest_typed::chains!($pest_typed, $pest_typed::iterators::Pairs<'i, 'n, R>, IntoIter, $T0, $($T, )*);
+
+            fn iter_pairs(&'n self) -> Self::Iter {
+                $pest_typed::chain!($pest_typed, $pest_typed::iterators::Pairs<'i, 'n, R>, self, iter_fields, $T0, $f0, $($T, $f, )*)
+            }
+            fn into_iter_pairs(self) -> Self::IntoIter {
+                $pest_typed::chain!($pest_typed, $pest_typed::iterators::Pairs<'i, 'n, R>, self, into_iter_fields, $T0, $f0, $($T, $f, )*)
+            }
+        }
+        impl<$T0: ::core::cmp::PartialEq, $($T: ::core::cmp::PartialEq, )*>
+            ::core::cmp::PartialEq for $name<$T0, $($T, )*>
+        {
+            fn eq(&self, other: &Self) -> ::core::primitive::bool {
+                self.$f0 == other.$f0
+                $(
+                    && self.$f == other.$f
+                )*
+            }
+        }
+        impl<$T0: ::core::cmp::Eq, $($T: ::core::cmp::Eq, )*>
+            ::core::cmp::Eq for $name<$T0, $($T, )*> {
+        }
+        impl<$T0: ::core::hash::Hash, $($T: ::core::hash::Hash, )*>
+            ::core::hash::Hash for $name<$T0, $($T, )*> {
+            fn hash<H: ::core::hash::Hasher>(&self, hasher: &mut H) {
+                ::core::hash::Hash::hash(&self.$f0, hasher);
+                $(
+                    ::core::hash::Hash::hash(&self.$f, hasher);
+                )*
+            }
+        }
+        impl<$T0, $($T, )* const SKIP: usize, IGNORED> $name<$pest_typed::predefined_node::Skipped<$T0, IGNORED, SKIP>, $($pest_typed::predefined_node::Skipped<$T, IGNORED, SKIP>, )*> {
+            /// Convert the reference of a sequence into a tuple of references of matched elements.
+            pub fn get_matched(&self) -> ( &$T0, $(&$T, )* ) {
+                ( &self.$f0.matched, $(&self.$f.matched, )* )
+            }
+            /// Convert a sequence into a tuple of matched elements.
+            pub fn into_matched(self) -> ( $T0, $($T, )* ) {
+                ( self.$f0.matched, $(self.$f.matched, )* )
+            }
+        }
+        impl<$T0, $($T, )*> $name<$T0, $($T, )*> {
+            /// Convert the reference of a sequence into a tuple of references of skipped and matched elements.
+            pub fn get_all(&self) -> ( &$T0, $(&$T, )* ) {
+                ( &self.$f0, $(&self.$f, )* )
+            }
+            /// Convert a sequence into a tuple of skipped and matched elements.
+            pub fn into_all(self) -> ( $T0, $($T, )* ) {
+                ( self.$f0, $(self.$f, )* )
+            }
+        }
+        impl<F: ?::core::marker::Sized, $T0: $pest_typed::fold::Foldable<F>, $($T: $pest_typed::fold::Foldable<F>, )*>
+            $pest_typed::fold::Foldable<F> for $name<$T0, $($T, )*>
+        {
+            fn fold_with(self, folder: &mut F) -> Self {
+                Self {
+                    $f0: self.$f0.fold_with(folder),
+                    $($f: self.$f.fold_with(folder), )*
+                }
+            }
+        }
+        impl<$T0: $pest_typed::metrics::HeapSize, $($T: $pest_typed::metrics::HeapSize, )*>
+            $pest_typed::metrics::HeapSize for $name<$T0, $($T, )*>
+        {
+            fn heap_size(&self) -> ::core::primitive::usize {
+                self.$f0.heap_size() $(+ self.$f.heap_size())*
+            }
+        }
+        impl<$T0: $pest_typed::recovery::Recovered, $($T: $pest_typed::recovery::Recovered, )*>
+            $pest_typed::recovery::Recovered for $name<$T0, $($T, )*>
+        {
+            fn provenance(&self) -> $pest_typed::recovery::Provenance {
+                self.$f0.provenance() $(| self.$f.provenance())*
+            }
+        }
+        impl<$T0: ::core::fmt::Debug, $($T: ::core::fmt::Debug, )*>
+            ::core::fmt::Debug for $name<$T0, $($T, )*>
+        {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                f.debug_struct(::core::stringify!($name))
+                    .field(::core::stringify!($f0), &self.$f0)
+                    $(.field(::core::stringify!($f), &self.$f))*
+                    .finish()
+            }
+        }
+    };
+}
+
 seq!(Seq2, crate, 2, T0, 0, T1, 1,);
 seq!(Seq3, crate, 3, T0, 0, T1, 1, T2, 2,);
 seq!(Seq4, crate, 4, T0, 0, T1, 1, T2, 2, T3, 3,);
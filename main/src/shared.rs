@@ -0,0 +1,167 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Texts that share the input they're taken from.
+//!
+//! A [`SharedStr`] is a range of an [`Arc<str>`],
+//! so that nodes of a tree can keep their texts without copying them,
+//! and the tree can be cloned and sent to other threads cheaply.
+//! Owned nodes generated with `emit_owned` (see [pest_typed_derive](https://docs.rs/pest_typed_derive)) keep texts this way.
+//!
+//! ```
+//! # use pest_typed::shared::SharedStr;
+//! # use std::sync::Arc;
+//! let input: Arc<str> = Arc::from("key = value");
+//! let key = SharedStr::slice(&input, 0..3);
+//! assert_eq!(key, "key");
+//! assert!(Arc::ptr_eq(key.input(), &input));
+//! ```
+
+use alloc::{string::String, sync::Arc};
+use core::{borrow::Borrow, cmp::Ordering, fmt, hash, ops::Deref, ops::Range};
+
+/// A range of a shared input. See [module-level documentation](self).
+///
+/// Compared and hashed by its text, like [`str`].
+#[derive(Clone)]
+pub struct SharedStr {
+    input: Arc<str>,
+    start: usize,
+    end: usize,
+}
+
+impl SharedStr {
+    /// The whole `input`.
+    pub fn new(input: Arc<str>) -> Self {
+        let end = input.len();
+        Self {
+            input,
+            start: 0,
+            end,
+        }
+    }
+    /// A range of `input`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `range` is out of `input`, or not on char boundaries.
+    #[track_caller]
+    pub fn slice(input: &Arc<str>, range: Range<usize>) -> Self {
+        assert!(
+            input.get(range.clone()).is_some(),
+            "Range {:?} is out of input of length {}, or not on char boundaries.",
+            range,
+            input.len(),
+        );
+        Self {
+            input: input.clone(),
+            start: range.start,
+            end: range.end,
+        }
+    }
+    /// The text.
+    pub fn as_str(&self) -> &str {
+        &self.input[self.start..self.end]
+    }
+    /// The input that the text is taken from.
+    pub fn input(&self) -> &Arc<str> {
+        &self.input
+    }
+    /// Range of the text in the input.
+    pub fn range(&self) -> Range<usize> {
+        self.start..self.end
+    }
+}
+
+impl Deref for SharedStr {
+    type Target = str;
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl AsRef<str> for SharedStr {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl Borrow<str> for SharedStr {
+    fn borrow(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl From<Arc<str>> for SharedStr {
+    fn from(input: Arc<str>) -> Self {
+        Self::new(input)
+    }
+}
+
+impl From<&str> for SharedStr {
+    fn from(text: &str) -> Self {
+        Self::new(Arc::from(text))
+    }
+}
+
+impl From<String> for SharedStr {
+    fn from(text: String) -> Self {
+        Self::new(Arc::from(text))
+    }
+}
+
+impl fmt::Debug for SharedStr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Display for SharedStr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl PartialEq for SharedStr {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl Eq for SharedStr {}
+
+impl PartialEq<str> for SharedStr {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for SharedStr {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl PartialOrd for SharedStr {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for SharedStr {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.as_str().cmp(other.as_str())
+    }
+}
+
+impl hash::Hash for SharedStr {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state)
+    }
+}
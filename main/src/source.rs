@@ -0,0 +1,315 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Named sources, such as files, that spans point into.
+//!
+//! A [`Span`] only knows byte offsets into its input.
+//! Inputs added to a [`SourceMap`] are given a [`SourceId`] and a name,
+//! and spans parsed from them can be traced back to their sources.
+//!
+//! Spans in different sources can be displayed together with [`SourceMap::display_labels`],
+//! such as a definition in one file and a conflicting one in another.
+
+use crate::{
+    formatter::{Annotation, FormatOption},
+    Span,
+};
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt;
+use pest::{error::Error, RuleType};
+
+/// Identifier of a source in a [`SourceMap`].
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct SourceId(usize);
+
+impl SourceId {
+    /// Index of the source in the order it's added.
+    pub fn index(self) -> usize {
+        self.0
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Source {
+    name: String,
+    input: String,
+}
+
+/// Owns inputs and their names.
+///
+/// ```
+/// # use pest_typed::{source::SourceMap, Span};
+/// let mut map = SourceMap::new();
+/// let id = map.add("main.txt", "let a = 1;\nlet b = 2;\n");
+/// let input = map.input(id);
+/// let span = Span::new(input, 15, 16).unwrap();
+/// assert_eq!(map.find(&span), Some(id));
+/// assert_eq!(
+///     map.display(&span).to_string(),
+///     " --> main.txt:2:5\n  |\n2 | let b = 2;␊\n  |     ^\n",
+/// );
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct SourceMap {
+    sources: Vec<Source>,
+}
+
+impl SourceMap {
+    /// Create an empty map.
+    pub const fn new() -> Self {
+        Self {
+            sources: Vec::new(),
+        }
+    }
+    /// Add an input with given name, such as its path.
+    pub fn add(&mut self, name: impl Into<String>, input: impl Into<String>) -> SourceId {
+        self.sources.push(Source {
+            name: name.into(),
+            input: input.into(),
+        });
+        SourceId(self.sources.len() - 1)
+    }
+    /// Number of sources.
+    pub fn len(&self) -> usize {
+        self.sources.len()
+    }
+    /// Whether there is no source.
+    pub fn is_empty(&self) -> bool {
+        self.sources.is_empty()
+    }
+    /// Name of the source.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id` is not from this map.
+    pub fn name(&self, id: SourceId) -> &str {
+        &self.sources[id.0].name
+    }
+    /// Input of the source. Parse this to get spans that can be found with [`SourceMap::find`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id` is not from this map.
+    pub fn input(&self, id: SourceId) -> &str {
+        &self.sources[id.0].input
+    }
+    /// Find the source that a span points into.
+    pub fn find(&self, span: &Span<'_>) -> Option<SourceId> {
+        self.sources
+            .iter()
+            .position(|source| core::ptr::eq(source.input.as_str(), span.get_input()))
+            .map(SourceId)
+    }
+    /// Display a span with a `--> name:line:col` header if its source is in this map.
+    pub fn display<'m, 'i>(&'m self, span: &Span<'i>) -> Located<'m, 'i> {
+        Located {
+            name: self.find(span).map(|id| self.name(id)),
+            span: *span,
+        }
+    }
+    /// Display labeled spans that may live in different sources, in the layout of rustc.
+    ///
+    /// Primary spans are marked with `^` and secondary ones with `-`.
+    /// A header with the location is written before the first label and whenever the source changes,
+    /// `-->` for primary spans and `:::` for secondary ones.
+    ///
+    /// ```
+    /// # use pest_typed::{source::{Label, SourceMap}, Span};
+    /// let mut map = SourceMap::new();
+    /// let a = map.add("a.txt", "x = 1");
+    /// let b = map.add("b.txt", "\n\nx = 2");
+    /// let first = Span::new(map.input(a), 0, 1).unwrap();
+    /// let second = Span::new(map.input(b), 2, 3).unwrap();
+    /// let labels = [
+    ///     Label::primary(second, "redefined here"),
+    ///     Label::secondary(first, "first defined here"),
+    /// ];
+    /// assert_eq!(
+    ///     map.display_labels(&labels).to_string(),
+    ///     [
+    ///         " --> b.txt:3:1",
+    ///         "  |",
+    ///         "3 | x = 2",
+    ///         "  | ^ redefined here",
+    ///         " ::: a.txt:1:1",
+    ///         "  |",
+    ///         "1 | x = 1",
+    ///         "  | - first defined here",
+    ///         "",
+    ///     ]
+    ///     .join("\n"),
+    /// );
+    /// ```
+    pub fn display_labels<'m, 'l, 'i>(&'m self, labels: &'l [Label<'i>]) -> Labels<'m, 'l, 'i> {
+        Labels { map: self, labels }
+    }
+    /// Attach the name of the source to an error from parsing its input,
+    /// so that it's printed in the `-->` line.
+    pub fn error<R: RuleType>(&self, id: SourceId, error: Error<R>) -> Error<R> {
+        error.with_path(self.name(id))
+    }
+}
+
+/// A span with a message.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Label<'i> {
+    /// Labeled span.
+    pub span: Span<'i>,
+    /// Message written after markers. May be empty.
+    pub message: String,
+    /// Whether the span is the primary cause.
+    pub primary: bool,
+}
+
+impl<'i> Label<'i> {
+    /// Create a primary label.
+    pub fn primary(span: Span<'i>, message: impl Into<String>) -> Self {
+        Self {
+            span,
+            message: message.into(),
+            primary: true,
+        }
+    }
+    /// Create a secondary label.
+    pub fn secondary(span: Span<'i>, message: impl Into<String>) -> Self {
+        Self {
+            span,
+            message: message.into(),
+            primary: false,
+        }
+    }
+}
+
+/// Labeled spans to display. See [`SourceMap::display_labels`].
+#[derive(Clone, Copy, Debug)]
+pub struct Labels<'m, 'l, 'i> {
+    map: &'m SourceMap,
+    labels: &'l [Label<'i>],
+}
+
+impl Labels<'_, '_, '_> {
+    /// Width of the widest line number.
+    pub(crate) fn width(&self) -> usize {
+        self.labels
+            .iter()
+            .map(|label| label.span.end_pos().line_col().0.to_string().len())
+            .max()
+            .unwrap_or(0)
+    }
+    pub(crate) fn display_with_width(&self, width: usize, f: &mut impl fmt::Write) -> fmt::Result {
+        let mut last = None;
+        for (i, label) in self.labels.iter().enumerate() {
+            let source = self.map.find(&label.span);
+            let path = if i == 0 || source != last {
+                source.map(|id| self.map.name(id))
+            } else {
+                None
+            };
+            last = source;
+            let annotation = Annotation {
+                arrow: if label.primary { "-->" } else { ":::" },
+                path,
+                primary: label.primary,
+                label: Some(label.message.as_str()).filter(|message| !message.is_empty()),
+                width,
+                mark_empty: true,
+            };
+            FormatOption::default().display_span_in(&label.span, annotation, f)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Labels<'_, '_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.display_with_width(self.width(), f)
+    }
+}
+
+/// A span with the name of its source. See [`SourceMap::display`].
+#[derive(Clone, Copy, Debug)]
+pub struct Located<'m, 'i> {
+    name: Option<&'m str>,
+    span: Span<'i>,
+}
+
+impl<'m, 'i> Located<'m, 'i> {
+    /// Name of the source, if it's found.
+    pub fn name(&self) -> Option<&'m str> {
+        self.name
+    }
+    /// The span.
+    pub fn span(&self) -> Span<'i> {
+        self.span
+    }
+}
+
+impl fmt::Display for Located<'_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let annotation = Annotation {
+            path: self.name,
+            ..Annotation::PLAIN
+        };
+        FormatOption::default().display_span_in(&self.span, annotation, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Label, SourceMap};
+    use crate::{error::ErrorVariant, Span};
+    use alloc::{format, string::ToString};
+
+    #[test]
+    fn sources() {
+        let mut map = SourceMap::new();
+        let a = map.add("a.txt", "abc");
+        let b = map.add("b.txt", "abc");
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.name(b), "b.txt");
+
+        let span = Span::new(map.input(b), 0, 3).unwrap();
+        assert_eq!(map.find(&span), Some(b));
+        assert_eq!(map.find(&Span::new("abc", 0, 3).unwrap()), None);
+        // Unknown spans are displayed without headers.
+        assert_eq!(
+            map.display(&Span::new("abc", 0, 3).unwrap()).to_string(),
+            Span::new("abc", 0, 3).unwrap().to_string(),
+        );
+
+        let error = pest::error::Error::<()>::new_from_pos(
+            ErrorVariant::CustomError {
+                message: "oops".to_string(),
+            },
+            pest::Position::new(map.input(a), 1).unwrap(),
+        );
+        let error = map.error(a, error);
+        assert!(format!("{error}").starts_with(" --> a.txt:1:2\n"));
+    }
+
+    #[test]
+    fn labels() {
+        let mut map = SourceMap::new();
+        let a = map.add("a.txt", "fn f() {}\n\n\n\n\n\n\n\n\nfn f() {}\n");
+        let first = Span::new(map.input(a), 3, 4).unwrap();
+        let second = Span::new(map.input(a), 21, 22).unwrap();
+        let labels = [
+            Label::primary(second, "redefined here"),
+            Label::secondary(first, ""),
+        ];
+        // Line numbers are aligned, and the header is not repeated in the same source.
+        assert_eq!(
+            map.display_labels(&labels).to_string(),
+            "  --> a.txt:10:4\n   |\n10 | fn f() {}␊\n   |    ^ redefined here\n   |\n 1 | fn f() {}␊\n   |    -\n",
+        );
+    }
+}
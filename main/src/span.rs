@@ -9,12 +9,13 @@
 
 use core::fmt::{self, Write};
 use core::hash::{Hash, Hasher};
-use core::ops::{Bound, RangeBounds};
+use core::ops::{Bound, Range, RangeBounds};
 use core::ptr;
 use core::str;
 
-use crate::formatter::FormatOption;
-use crate::position;
+use crate::char_index::CharIndex;
+use crate::formatter::{Annotation, FormatOption};
+use crate::position::{self, Snap};
 
 /// A span over a `&str`. It is created from either [two `Position`s] or from a [`Pair`].
 ///
@@ -63,6 +64,44 @@ impl<'i> Span<'i> {
         }
     }
 
+    /// Create a span with both ends rounded to char boundaries in given direction.
+    ///
+    /// Offsets beyond the end of the input are clamped to its end.
+    /// Returns `None` if `start` is after `end` once both are rounded.
+    ///
+    /// ```
+    /// # use pest_typed::{Snap, Span};
+    /// let input = "a💖b";
+    /// assert_eq!(Span::new(input, 0, 2), None);
+    /// assert_eq!(Span::new_snapped(input, 0, 2, Snap::Forward).unwrap().as_str(), "a💖");
+    /// assert_eq!(Span::new_snapped(input, 2, 3, Snap::Backward).unwrap().as_str(), "");
+    /// assert_eq!(Span::new_snapped(input, 4, 9, Snap::Nearest).unwrap().as_str(), "b");
+    /// ```
+    pub fn new_snapped(input: &str, start: usize, end: usize, snap: Snap) -> Option<Span<'_>> {
+        let start = position::Position::snap_to_char_boundary(input, start, snap).pos();
+        let end = position::Position::snap_to_char_boundary(input, end, snap).pos();
+        Span::new(input, start, end)
+    }
+
+    /// Create a span covering the whole of `text`.
+    ///
+    /// Useful for building typed nodes without parsing, such as in fixtures or tree transformations.
+    /// The span is not related to any parsed input, so it only compares equal to spans over the same `text`.
+    ///
+    /// ```
+    /// # use pest_typed::Span;
+    /// let span = Span::synthetic("foo");
+    /// assert_eq!(span.as_str(), "foo");
+    /// assert_eq!(span.start(), 0);
+    /// ```
+    pub fn synthetic(text: &'i str) -> Span<'i> {
+        Span {
+            input: text,
+            start: 0,
+            end: text.len(),
+        }
+    }
+
     /// Attempts to create a new span based on a sub-range.
     ///
     /// ```
@@ -94,6 +133,50 @@ impl<'i> Span<'i> {
         })
     }
 
+    /// Create a new span based on a sub-range, in bytes relative to the start of this span.
+    ///
+    /// Like [`Span::get`], but panics if the range is out of this span or not on char boundaries,
+    /// just like slicing a `&str`.
+    ///
+    /// ```
+    /// # use pest_typed::Span;
+    /// let input = "key = a, b";
+    /// let value = Span::new(input, 6, input.len()).unwrap();
+    /// let b = value.subspan(3..);
+    /// assert_eq!(b.as_str(), "b");
+    /// assert_eq!(b.start(), 9);
+    /// ```
+    #[track_caller]
+    pub fn subspan(&self, range: impl RangeBounds<usize> + fmt::Debug) -> Span<'i> {
+        match self.get((range.start_bound(), range.end_bound())) {
+            Some(span) => span,
+            None => panic!(
+                "Range {:?} is out of span {:?} of length {}, or not on char boundaries.",
+                range,
+                self.as_str(),
+                self.as_str().len(),
+            ),
+        }
+    }
+
+    /// Byte range of this span relative to the start of `parent`.
+    ///
+    /// Returns `None` if this span is not inside `parent`, or is over another input.
+    /// It is the reverse of [`Span::subspan`].
+    ///
+    /// ```
+    /// # use pest_typed::Span;
+    /// let input = "key = a, b";
+    /// let value = Span::new(input, 6, input.len()).unwrap();
+    /// let b = value.subspan(3..);
+    /// assert_eq!(b.relative_to(&value), Some(3..4));
+    /// assert_eq!(value.relative_to(&b), None);
+    /// ```
+    pub fn relative_to(&self, parent: &Span<'i>) -> Option<Range<usize>> {
+        (ptr::eq(self.input, parent.input) && parent.start <= self.start && self.end <= parent.end)
+            .then(|| self.start - parent.start..self.end - parent.start)
+    }
+
     /// Returns the `Span`'s start byte position as a `usize`.
     ///
     /// # Examples
@@ -168,6 +251,43 @@ impl<'i> Span<'i> {
         unsafe { position::Position::new_unchecked(self.input, self.end) }
     }
 
+    /// Returns the 1-based line numbers and columns (in UTF-16 code units) of the start and the end.
+    ///
+    /// See [`position::Position::line_col_utf16`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pest_typed::Span;
+    /// let span = Span::new("💖\nab", 0, 6).unwrap();
+    /// assert_eq!(span.line_col_utf16(), ((1, 1), (2, 2)));
+    /// ```
+    #[inline]
+    pub fn line_col_utf16(&self) -> ((usize, usize), (usize, usize)) {
+        (
+            self.start_pos().line_col_utf16(),
+            self.end_pos().line_col_utf16(),
+        )
+    }
+
+    /// Range of char offsets of the `Span`, looked up in `index` of its input.
+    ///
+    /// See [`position::Position::char_offset`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pest_typed::{char_index::CharIndex, Span};
+    /// let input = "💖ab";
+    /// let index = CharIndex::new(input);
+    /// assert_eq!(Span::new(input, 4, 6).unwrap().char_range(&index), 1..3);
+    /// ```
+    #[inline]
+    pub fn char_range(&self, index: &CharIndex<'_>) -> Range<usize> {
+        debug_assert!(ptr::eq(self.input, index.input()));
+        index.char_offset(self.start)..index.char_offset(self.end)
+    }
+
     /// Splits the `Span` into a pair of `Position`s.
     ///
     /// # Examples
@@ -325,6 +445,34 @@ impl<'i> Span<'i> {
     {
         opt.display_span(self, f)
     }
+    /// Format span with given option, and write `label` after the end marker.
+    ///
+    /// ```
+    /// # use pest_typed::{formatter::FormatOption, Span};
+    /// let span = Span::new("a\nb\n", 0, 3).unwrap();
+    /// let mut res = String::new();
+    /// let opt = FormatOption::default().with_multi_line_bars(true);
+    /// span.display_with_label(&mut res, opt, "here").unwrap();
+    /// assert_eq!(res, "  |\n1 | / a␊\n2 | | b␊\n  | |_^ here\n");
+    /// ```
+    pub fn display_with_label<Writer, SF, MF, NF>(
+        &self,
+        f: &mut Writer,
+        opt: FormatOption<SF, MF, NF>,
+        label: &str,
+    ) -> fmt::Result
+    where
+        Writer: Write,
+        SF: FnMut(&str, &mut Writer) -> fmt::Result,
+        MF: FnMut(&str, &mut Writer) -> fmt::Result,
+        NF: FnMut(&str, &mut Writer) -> fmt::Result,
+    {
+        let annotation = Annotation {
+            label: Some(label).filter(|label| !label.is_empty()),
+            ..Annotation::PLAIN
+        };
+        opt.display_span_in(self, annotation, f)
+    }
 }
 
 impl<'i> fmt::Display for Span<'i> {
@@ -467,6 +615,28 @@ mod tests {
         assert_eq!(span4.unwrap().as_str(), "");
     }
 
+    #[test]
+    fn subspan() {
+        let input = "abc123abc";
+        let span = Span::new(input, 3, input.len()).unwrap();
+        let sub = span.subspan(1..=3);
+        assert_eq!(sub.as_str(), "23a");
+        assert_eq!((sub.start(), sub.end()), (4, 7));
+        assert_eq!(sub.relative_to(&span), Some(1..4));
+        assert_eq!(span.subspan(..).relative_to(&span), Some(0..6));
+        assert_eq!(span.relative_to(&sub), None);
+        let other = alloc::string::String::from(input);
+        assert_eq!(sub.relative_to(&Span::new(&other, 0, 9).unwrap()), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Range 2..9 is out of span \"123abc\" of length 6")]
+    fn subspan_fails() {
+        let input = "abc123abc";
+        let span = Span::new(input, 3, input.len()).unwrap();
+        span.subspan(2..9);
+    }
+
     #[test]
     fn get_fails() {
         let input = "abc";
@@ -0,0 +1,72 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! A flat view of the leaves of a tree, for syntax highlighters and formatters.
+//!
+//! See [`PairTree::tokens`](crate::iterators::PairTree::tokens).
+
+use crate::{iterators::Pair, Span};
+use alloc::{format, vec, vec::Vec};
+use pest::RuleType;
+
+/// Whether to yield trivia, that is, nodes of rules `WHITESPACE` and `COMMENT`.
+///
+/// Trivia are only visible if their rules are not silent.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum Trivia {
+    /// Skip trivia and their inner nodes.
+    #[default]
+    Skip,
+    /// Yield trivia like other nodes.
+    Include,
+}
+
+/// Whether `rule` is `WHITESPACE` or `COMMENT`.
+pub fn is_trivia<R: RuleType>(rule: R) -> bool {
+    matches!(format!("{:?}", rule).as_str(), "WHITESPACE" | "COMMENT")
+}
+
+/// Iterator over nodes without inner nodes, in source order.
+pub struct Tokens<'i, 'n, R: RuleType> {
+    stack: Vec<vec::IntoIter<&'n dyn Pair<'i, 'n, R>>>,
+    trivia: Trivia,
+}
+
+impl<'i: 'n, 'n, R: RuleType + 'n> Tokens<'i, 'n, R> {
+    pub(crate) fn new(root: &'n dyn Pair<'i, 'n, R>, trivia: Trivia) -> Self {
+        Self {
+            stack: vec![vec![root].into_iter()],
+            trivia,
+        }
+    }
+}
+
+impl<'i: 'n, 'n, R: RuleType + 'n> Iterator for Tokens<'i, 'n, R> {
+    type Item = (R, Span<'i>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let pair = match self.stack.last_mut()?.next() {
+                Some(pair) => pair,
+                None => {
+                    self.stack.pop();
+                    continue;
+                }
+            };
+            if self.trivia == Trivia::Skip && is_trivia(pair.rule()) {
+                continue;
+            }
+            let inner = pair.inner();
+            if inner.len() == 0 {
+                return Some((pair.rule(), pair.span()));
+            }
+            self.stack.push(inner);
+        }
+    }
+}
@@ -8,23 +8,34 @@
 // modified, or distributed except according to those terms.
 
 //! Tracker for parsing failures.
+//!
+//! Errors are reported at the farthest position where a rule or a string failed to match,
+//! even if parsing backtracked from there, with everything expected at that position.
 
+#[cfg(feature = "coverage")]
+use crate::coverage::Coverage;
+#[cfg(feature = "profile")]
+use crate::profile::ParseStats;
 use crate::{
+    controller::ParseController,
     error::{Error, ErrorVariant},
     position::Position,
-    RuleType, RuleWrapper,
+    prefix::{Prefix, PrefixTracker},
+    RuleType, RuleWrapper, Span,
 };
 use alloc::{
-    borrow::ToOwned,
+    borrow::{Cow, ToOwned},
     collections::BTreeMap,
     format,
-    string::{String, ToString},
+    string::String,
+    sync::Arc,
     vec,
     vec::Vec,
 };
-use core::cmp::Ordering;
+use core::{cmp::Ordering, fmt};
 
 /// Some special errors that are not matching failures.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
 pub enum SpecialError {
     /// Peek slice out of bound.
     SliceOutOfBound(i32, Option<i32>),
@@ -34,21 +45,88 @@ pub enum SpecialError {
     EmptyStack,
 }
 
-impl ToString for SpecialError {
-    fn to_string(&self) -> String {
+impl fmt::Display for SpecialError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             SpecialError::SliceOutOfBound(start, end) => match end {
-                Some(end) => format!("Peek slice {}..{} out of bound.", start, end),
-                None => format!("Peek slice {}.. out of bound.", start),
+                Some(end) => write!(f, "Peek slice {}..{} out of bound.", start, end),
+                None => write!(f, "Peek slice {}.. out of bound.", start),
             },
-            SpecialError::RepeatTooManyTimes => "Repeated too many times.".to_owned(),
-            SpecialError::EmptyStack => "Nothing to pop or drop.".to_owned(),
+            SpecialError::RepeatTooManyTimes => f.write_str("Repeated too many times."),
+            SpecialError::EmptyStack => f.write_str("Nothing to pop or drop."),
         }
     }
 }
 
+#[cfg(feature = "std")]
+impl std::error::Error for SpecialError {}
+
 type Tracked<R> = (Vec<R>, Vec<R>, Vec<SpecialError>);
 
+/// A parsing error with the rules being attempted where parsing failed.
+///
+/// See [`Tracker::collect_with_context`].
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct ContextualError<R: RuleType> {
+    error: Error<R>,
+    rule_stack: Vec<R>,
+    prefix: Option<Prefix<R>>,
+}
+
+impl<R: RuleType> ContextualError<R> {
+    /// The error.
+    pub fn error(&self) -> &Error<R> {
+        &self.error
+    }
+    /// Convert into the error.
+    pub fn into_error(self) -> Error<R> {
+        self.error
+    }
+    /// Rules being attempted where parsing failed, from the outermost one to the failed one.
+    ///
+    /// Empty if no rule failed there, such as when the input doesn't end after the matched rule.
+    pub fn rule_stack(&self) -> &[R] {
+        &self.rule_stack
+    }
+    /// The longest prefix of the input that parsed, if it's tracked with [`Tracker::with_prefix`].
+    pub fn prefix(&self) -> Option<&Prefix<R>> {
+        self.prefix.as_ref()
+    }
+}
+
+impl<R: RuleType> fmt::Display for ContextualError<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.error.fmt(f)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: RuleType> std::error::Error for ContextualError<R> {}
+
+impl<R: RuleType> From<ContextualError<R>> for Error<R> {
+    fn from(error: ContextualError<R>) -> Self {
+        error.error
+    }
+}
+
+/// Settings of a parse that trackers of nested parses, such as of lazy nodes, take from the tracker outside.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub(crate) struct Mode {
+    /// Whether implicit whitespace and comments are not skipped in any rule.
+    raw: bool,
+    /// Language version that rules with `#[cfg(version ...)]` are selected by.
+    version: u32,
+}
+
+impl Default for Mode {
+    fn default() -> Self {
+        Self {
+            raw: false,
+            version: u32::MAX,
+        }
+    }
+}
+
 /// Error tracker.
 pub struct Tracker<'i, R: RuleType> {
     position: Position<'i>,
@@ -56,6 +134,56 @@ pub struct Tracker<'i, R: RuleType> {
     /// upper rule -> (positives, negatives)
     attempts: BTreeMap<Option<R>, Tracked<R>>,
     stack: Vec<(R, Position<'i>, bool)>,
+    controller: Option<Arc<ParseController>>,
+    next_report: usize,
+    cancelled: bool,
+    max_distance: usize,
+    /// (start of the word, the word, distance, the literal)
+    suggestion: Option<(usize, &'i str, usize, &'static str)>,
+    /// Span matched by a negative predicate at the failed position.
+    unexpected: Option<Span<'i>>,
+    /// Rules being attempted when the first failure at the failed position is recorded.
+    rule_stack: Vec<R>,
+    /// upper rule -> literals expected inside it, and whether they are case insensitive
+    ///
+    /// Literals of generated parsers are borrowed, so that recording them doesn't allocate.
+    literals: BTreeMap<Option<R>, Vec<(Cow<'static, str>, bool)>>,
+    /// Whether literals at the start of the innermost rule are expected as well, for completions.
+    completing: bool,
+    /// Whether no error will be built from it, so that literals are not recorded.
+    discarded: bool,
+    /// Raw mode and language version, which trackers of nested parses take as well.
+    mode: Mode,
+    /// Tree of matched rules, saved where parsing got farthest.
+    prefix: Option<PrefixTracker<R>>,
+    #[cfg(feature = "coverage")]
+    coverage: Option<Coverage<R>>,
+    #[cfg(feature = "profile")]
+    stats: Option<ParseStats<R>>,
+}
+
+/// Levenshtein distance between two strings, counted in characters.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = if ca == *cb {
+                diagonal
+            } else {
+                1 + diagonal.min(above).min(row[j])
+            };
+            diagonal = above;
+        }
+    }
+    row[b.len()]
+}
+
+pub(crate) fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
 }
 impl<'i, R: RuleType> Tracker<'i, R> {
     /// Create an empty tracker for attempts.
@@ -65,10 +193,248 @@ impl<'i, R: RuleType> Tracker<'i, R> {
             positive: true,
             attempts: BTreeMap::new(),
             stack: vec![],
+            controller: None,
+            next_report: 0,
+            cancelled: false,
+            max_distance: 0,
+            suggestion: None,
+            unexpected: None,
+            rule_stack: vec![],
+            literals: BTreeMap::new(),
+            completing: false,
+            discarded: false,
+            mode: Mode::default(),
+            prefix: None,
+            #[cfg(feature = "coverage")]
+            coverage: None,
+            #[cfg(feature = "profile")]
+            stats: None,
+        }
+    }
+    /// Record matched rules and alternatives into `coverage`.
+    ///
+    /// Get it back with [`Tracker::take_coverage`].
+    #[cfg(feature = "coverage")]
+    pub fn with_coverage(mut self, coverage: Coverage<R>) -> Self {
+        self.coverage = Some(coverage);
+        self
+    }
+    /// Take recorded coverage.
+    #[cfg(feature = "coverage")]
+    pub fn take_coverage(&mut self) -> Option<Coverage<R>> {
+        self.coverage.take()
+    }
+    /// Record attempts, failures and time of rules into `stats`.
+    ///
+    /// Get it back with [`Tracker::take_stats`].
+    #[cfg(feature = "profile")]
+    pub fn with_stats(mut self, stats: ParseStats<R>) -> Self {
+        self.stats = Some(stats);
+        self
+    }
+    /// Take recorded statistics.
+    #[cfg(feature = "profile")]
+    pub fn take_stats(&mut self) -> Option<ParseStats<R>> {
+        self.stats.take()
+    }
+    /// Record that `C`, a choice of `count` alternatives, matched its alternative `index`.
+    ///
+    /// Does nothing without feature `coverage`.
+    #[inline]
+    #[allow(unused_variables)]
+    pub fn choice_matched<C: ?Sized>(&mut self, index: usize, count: usize) {
+        #[cfg(feature = "coverage")]
+        if let Some(coverage) = &mut self.coverage {
+            let rule = self.stack.last().map(|(rule, _, _)| *rule);
+            coverage.choice(rule, core::any::type_name::<C>(), index, count);
+        }
+    }
+    /// Suggest keywords within `max_distance` edits from the word where parsing failed,
+    /// with a line `Did you mean `...`?` in the error.
+    ///
+    /// Keywords are string literals made of letters, digits and underscores that are attempted at the start of the word.
+    /// Suggestions are disabled if `max_distance` is 0.
+    pub fn with_suggestions(mut self, max_distance: usize) -> Self {
+        self.max_distance = max_distance;
+        self
+    }
+    /// Parse as language version `version`.
+    ///
+    /// Rules with `#[cfg(version ...)]` fail if `version` doesn't satisfy their conditions.
+    /// The version is [`u32::MAX`] by default, that is, the latest one.
+    pub fn with_version(mut self, version: u32) -> Self {
+        self.mode.version = version;
+        self
+    }
+    /// Keep the tree of rules matched until where parsing got farthest,
+    /// nested in at most `depth` rules, as [`ContextualError::prefix`].
+    ///
+    /// Rules deeper than `depth` are not tracked at all,
+    /// so a small one keeps parsing fast for large inputs.
+    pub fn with_prefix(mut self, depth: usize) -> Self {
+        self.prefix = Some(PrefixTracker::new(depth));
+        self
+    }
+    /// Parse in raw mode, where implicit `WHITESPACE` and `COMMENT` are not skipped in any rule,
+    /// as if all rules were atomic.
+    ///
+    /// Typed nodes are of the same types as in a normal parse, with nothing in their skipped contents,
+    /// so that a region, such as one inside a template, can be parsed without trivia
+    /// without atomic twins of rules in the grammar.
+    pub fn with_raw(mut self) -> Self {
+        self.mode.raw = true;
+        self
+    }
+    /// Whether parsing in raw mode. See [`Tracker::with_raw`].
+    pub fn is_raw(&self) -> bool {
+        self.mode.raw
+    }
+    /// Create an empty tracker at `pos` for a nested parse, such as of skipped contents,
+    /// which parses in the same raw mode and language version as this one.
+    ///
+    /// No error is built from it, so expected literals are not recorded.
+    pub fn nested(&self, pos: Position<'i>) -> Self {
+        Self {
+            discarded: true,
+            ..Self::new(pos).with_mode(self.mode)
+        }
+    }
+    pub(crate) fn mode(&self) -> Mode {
+        self.mode
+    }
+    pub(crate) fn with_mode(mut self, mode: Mode) -> Self {
+        self.mode = mode;
+        self
+    }
+    /// Language version being parsed. See [`Tracker::with_version`].
+    pub fn version(&self) -> u32 {
+        self.mode.version
+    }
+    /// Expect literals at the start of rules as well, so that they can be completed.
+    pub(crate) fn completing(mut self) -> Self {
+        self.completing = true;
+        self
+    }
+    /// Literals expected at the failed position, by upper rules.
+    pub(crate) fn expected_literals(&self) -> &BTreeMap<Option<R>, Vec<(Cow<'static, str>, bool)>> {
+        &self.literals
+    }
+    /// Record that `literal` doesn't match at `pos`.
+    ///
+    /// It's expected at `pos` unless `pos` is the start of the innermost rule, which is expected instead.
+    /// It's also suggested if it's close to the word there.
+    #[inline]
+    pub fn literal_failed(&mut self, pos: Position<'i>, literal: &'static str) {
+        self.expect_literal(pos, literal, false);
+        if self.max_distance == 0 || literal.is_empty() || !literal.chars().all(is_word_char) {
+            return;
+        }
+        let rest = &pos.input[pos.pos()..];
+        let word = &rest[..rest.find(|c| !is_word_char(c)).unwrap_or(rest.len())];
+        if word.is_empty() {
+            return;
+        }
+        let distance = edit_distance(word, literal);
+        if distance == 0 || distance > self.max_distance {
+            return;
+        }
+        match self.suggestion {
+            Some((_, _, best, _)) if best <= distance => (),
+            _ => self.suggestion = Some((pos.pos(), word, distance, literal)),
+        }
+    }
+    /// Record that `literal` doesn't match at `pos` case insensitively.
+    ///
+    /// See [`Tracker::literal_failed`].
+    #[inline]
+    pub fn insensitive_literal_failed(&mut self, pos: Position<'i>, literal: &'static str) {
+        self.expect_literal(pos, literal, true);
+    }
+    pub(crate) fn expect_literal(
+        &mut self,
+        pos: Position<'i>,
+        literal: &'static str,
+        insensitive: bool,
+    ) {
+        if let Some(literals) = self.expecting(pos, literal, insensitive) {
+            literals.push((Cow::Borrowed(literal), insensitive));
+        }
+    }
+    /// Same as [`Tracker::expect_literal`], for literals that are not `'static`,
+    /// which are only copied if they are recorded.
+    pub(crate) fn expect_owned_literal(
+        &mut self,
+        pos: Position<'i>,
+        literal: &str,
+        insensitive: bool,
+    ) {
+        if let Some(literals) = self.expecting(pos, literal, insensitive) {
+            literals.push((Cow::Owned(literal.to_owned()), insensitive));
+        }
+    }
+    /// Literals to push `literal` into, if it's expected at `pos` and not recorded yet.
+    fn expecting(
+        &mut self,
+        pos: Position<'i>,
+        literal: &str,
+        insensitive: bool,
+    ) -> Option<&mut Vec<(Cow<'static, str>, bool)>> {
+        if !self.positive || self.discarded {
+            return None;
+        }
+        let upper = match self.stack.last() {
+            Some((rule, start, _)) if *start != pos || self.completing => Some(*rule),
+            Some(_) => return None,
+            None => None,
+        };
+        if !self.prepare(pos) {
+            return None;
+        }
+        if self.rule_stack.is_empty() {
+            self.rule_stack
+                .extend(self.stack.iter().map(|(rule, _, _)| *rule));
+        }
+        let literals = self.literals.entry(upper).or_default();
+        let recorded = literals
+            .iter()
+            .any(|(expected, case)| expected == literal && *case == insensitive);
+        (!recorded).then_some(literals)
+    }
+    /// The suggestion, if the word is at or right before the failed position.
+    fn suggested(&self) -> Option<&'static str> {
+        let (start, word, _, literal) = self.suggestion?;
+        let pos = self.position.pos();
+        let end = start + word.len();
+        let between = self.position.input.get(end.min(pos)..pos)?;
+        (start <= pos && between.trim().is_empty()).then_some(literal)
+    }
+    /// Create an empty tracker that stops parsing when `controller` is cancelled.
+    pub fn with_controller(pos: Position<'i>, controller: Arc<ParseController>) -> Self {
+        Self {
+            controller: Some(controller),
+            ..Self::new(pos)
+        }
+    }
+    /// Whether parsing is stopped by a cancelled [`ParseController`].
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled
+    }
+    /// Report progress and check for cancellation.
+    #[inline]
+    fn interrupted(&mut self, pos: &Position<'i>) -> bool {
+        if let Some(controller) = &self.controller {
+            controller.report(pos.pos(), &mut self.next_report);
+            if controller.is_cancelled() {
+                self.cancelled = true;
+            }
         }
+        self.cancelled
     }
     fn clear(&mut self) {
         self.attempts.clear();
+        self.unexpected = None;
+        self.rule_stack.clear();
+        self.literals.clear();
     }
     fn prepare(&mut self, pos: Position<'i>) -> bool {
         match pos.cmp(&self.position) {
@@ -77,6 +443,9 @@ impl<'i, R: RuleType> Tracker<'i, R> {
             Ordering::Greater => {
                 self.clear();
                 self.position = pos;
+                if let Some(prefix) = &mut self.prefix {
+                    prefix.failed_at(pos.pos());
+                }
                 true
             }
         }
@@ -88,6 +457,10 @@ impl<'i, R: RuleType> Tracker<'i, R> {
         self.positive = original;
         res
     }
+    /// Set whether the tracker is positive, and return the original state.
+    pub(crate) fn set_positive(&mut self, positive: bool) -> bool {
+        core::mem::replace(&mut self.positive, positive)
+    }
     /// Set the tracker to positive during calling `f`.
     pub fn positive_during<Ret>(&mut self, f: impl FnOnce(&mut Self) -> Ret) -> Ret {
         self.during::<Ret, true>(f)
@@ -110,6 +483,20 @@ impl<'i, R: RuleType> Tracker<'i, R> {
         }
         self.attempts.entry(upper).or_default()
     }
+    /// Report that the expression of a negative predicate matched from `start` to `end`,
+    /// so the predicate failed.
+    pub fn negative_matched(&mut self, start: Position<'i>, end: Position<'i>) {
+        if self.prepare(start) && self.unexpected.is_none() {
+            self.unexpected = Some(start.span(&end));
+        }
+    }
+    /// Span matched by the expression of a negative predicate where parsing failed, if any,
+    /// such as a keyword used as an identifier.
+    ///
+    /// The span starts at the failed position, so it can be labeled as "unexpected ... here".
+    pub fn unexpected(&self) -> Option<Span<'i>> {
+        self.unexpected
+    }
     /// Report a repetition that exceeds the limit.
     pub fn repeat_too_many_times(&mut self, pos: Position<'i>) {
         if self.prepare(pos) {
@@ -141,6 +528,11 @@ impl<'i, R: RuleType> Tracker<'i, R> {
     #[inline]
     fn record(&mut self, rule: R, pos: Position<'i>, succeeded: bool) {
         if self.prepare(pos) && succeeded != self.positive {
+            if self.rule_stack.is_empty() {
+                self.rule_stack
+                    .extend(self.stack.iter().map(|(rule, _, _)| *rule));
+                self.rule_stack.push(rule);
+            }
             let positive = self.positive;
             let value = self.get_entry(&pos);
             let vec = if positive { &mut value.0 } else { &mut value.1 };
@@ -150,36 +542,80 @@ impl<'i, R: RuleType> Tracker<'i, R> {
         }
     }
     /// Record if the result doesn't match the state during calling `f`.
+    ///
+    /// Fails without calling `f` if parsing is cancelled.
     #[inline]
-    pub(crate) fn record_during_with<T, E>(
+    pub(crate) fn record_during_with<T, E: Default>(
         &mut self,
         pos: Position<'i>,
         f: impl FnOnce(&mut Self) -> Result<(Position<'i>, T), E>,
         rule: R,
     ) -> Result<(Position<'i>, T), E> {
+        if !self.enter(rule, pos) {
+            return Err(E::default());
+        }
+        let res = f(self);
+        self.leave(rule, pos, res.as_ref().ok().map(|(end, _)| *end));
+        res
+    }
+    /// Push `rule` onto the stack, unless parsing is cancelled.
+    #[inline]
+    pub(crate) fn enter(&mut self, rule: R, pos: Position<'i>) -> bool {
+        if self.interrupted(&pos) {
+            return false;
+        }
         if let Some((_, _, has_children)) = self.stack.last_mut() {
             *has_children = true;
         }
+        if let Some(prefix) = &mut self.prefix {
+            prefix.enter(rule, pos.pos(), self.stack.len());
+        }
         self.stack.push((rule, pos, false));
-        let res = f(self);
-        let succeeded = res.is_ok();
+        #[cfg(feature = "profile")]
+        if let Some(stats) = &mut self.stats {
+            stats.enter();
+        }
+        true
+    }
+    /// Pop `rule` from the stack, and record it if it has no children.
+    ///
+    /// `end` is where the rule matched until, or [`None`] if it failed.
+    #[inline]
+    pub(crate) fn leave(&mut self, rule: R, pos: Position<'i>, end: Option<Position<'i>>) {
         let (_r, _pos, has_children) = self.stack.pop().unwrap();
+        let succeeded = end.is_some();
+        if let Some(prefix) = &mut self.prefix {
+            prefix.leave(self.stack.len(), end.map(|end| end.pos()));
+        }
+        #[cfg(feature = "profile")]
+        if let Some(stats) = &mut self.stats {
+            stats.leave(rule, succeeded);
+        }
+        #[cfg(feature = "coverage")]
+        if let (Some(coverage), true) = (&mut self.coverage, succeeded) {
+            coverage.rule(rule);
+        }
         if !has_children {
             self.record(rule, pos, succeeded);
         }
-        res
     }
     /// Record if the result doesn't match the state during calling `f`.
     #[inline]
-    pub fn record_during<T: RuleWrapper<R>, E>(
+    pub fn record_during<T: RuleWrapper<R>, E: Default>(
         &mut self,
         pos: Position<'i>,
         f: impl FnOnce(&mut Self) -> Result<(Position<'i>, T), E>,
     ) -> Result<(Position<'i>, T), E> {
         self.record_during_with(pos, f, T::RULE)
     }
-    fn collect_to_message(self) -> String {
-        let (pos, attempts) = self.finish();
+    fn collect_to_message(mut self) -> String {
+        let suggested = self.suggested();
+        let unexpected = self.unexpected;
+        let mut literals = core::mem::take(&mut self.literals);
+        let (pos, mut attempts) = self.finish();
+        for upper in literals.keys() {
+            attempts.entry(*upper).or_default();
+        }
         // "{} | "
         // "{} = "
         let (line, col) = pos.line_col();
@@ -205,19 +641,36 @@ impl<'i, R: RuleType> Tracker<'i, R> {
                 positives.dedup();
                 negatives.sort();
                 negatives.dedup();
+                let expected = literals.remove(&rule).unwrap_or_default();
                 fn collect_rules<R: RuleType>(vec: Vec<R>) -> String {
                     format!("{:?}", vec)
                 }
+                fn collect_expected<R: RuleType>(
+                    rules: Vec<R>,
+                    literals: Vec<(Cow<'static, str>, bool)>,
+                ) -> String {
+                    let mut items: Vec<String> =
+                        rules.iter().map(|rule| format!("{:?}", rule)).collect();
+                    items.extend(literals.into_iter().map(|(literal, insensitive)| {
+                        format!("{}{:?}", if insensitive { "^" } else { "" }, literal)
+                    }));
+                    format!("[{}]", items.join(", "))
+                }
+                let has_positives = !positives.is_empty() || !expected.is_empty();
                 let _ = message.write_str(&spacing);
-                let _ = match (positives.is_empty(), negatives.is_empty()) {
-                    (true, true) => write!(message, "Unknown error (no rule tracked)"),
-                    (false, true) => write!(message, "Expected {}", collect_rules(positives)),
-                    (true, false) => write!(message, "Unexpected {}", collect_rules(negatives),),
-                    (false, false) => write!(
+                let _ = match (has_positives, negatives.is_empty()) {
+                    (false, true) => write!(message, "Unknown error (no rule tracked)"),
+                    (true, true) => write!(
+                        message,
+                        "Expected {}",
+                        collect_expected(positives, expected)
+                    ),
+                    (false, false) => write!(message, "Unexpected {}", collect_rules(negatives),),
+                    (true, false) => write!(
                         message,
                         "Unexpected {}, expected {}",
                         collect_rules(negatives),
-                        collect_rules(positives),
+                        collect_expected(positives, expected),
                     ),
                 };
                 if let Some(upper_rule) = rule {
@@ -227,7 +680,7 @@ impl<'i, R: RuleType> Tracker<'i, R> {
 
                 for special in special {
                     let _ = message.write_str(&spacing);
-                    let _ = write!(message, "{}", special.to_string());
+                    let _ = write!(message, "{}", special);
                     if let Some(upper_rule) = rule {
                         let _ = write!(message, " (By {:?})", upper_rule);
                     };
@@ -236,6 +689,14 @@ impl<'i, R: RuleType> Tracker<'i, R> {
         for attempt in attempts {
             write_message(attempt);
         }
+        if let Some(span) = unexpected.filter(|span| !span.as_str().is_empty()) {
+            let _ = message.write_str(&spacing);
+            let _ = write!(message, "Unexpected `{}`.", span.as_str().escape_debug());
+        }
+        if let Some(literal) = suggested {
+            let _ = message.write_str(&spacing);
+            let _ = write!(message, "Did you mean `{}`?", literal);
+        }
         message
     }
     /// Collect attempts to [`Error<R>`]
@@ -250,12 +711,34 @@ impl<'i, R: RuleType> Tracker<'i, R> {
             )
         }) {
             Ok(pos) => {
+                let span = self
+                    .unexpected
+                    .and_then(|span| pest::Span::new(span.get_input(), span.start(), span.end()))
+                    .filter(|span| span.start() < span.end());
                 let message = self.collect_to_message();
-                Error::new_from_pos(ErrorVariant::CustomError { message }, pos)
+                let variant = ErrorVariant::CustomError { message };
+                match span {
+                    Some(span) => Error::new_from_span(variant, span),
+                    None => Error::new_from_pos(variant, pos),
+                }
             }
             Err(err) => err,
         }
     }
+    /// Rules being attempted where parsing failed, from the outermost one to the failed one.
+    pub fn rule_stack(&self) -> &[R] {
+        &self.rule_stack
+    }
+    /// Collect attempts to [`ContextualError<R>`], which keeps [`Tracker::rule_stack`].
+    pub fn collect_with_context(mut self) -> ContextualError<R> {
+        let rule_stack = core::mem::take(&mut self.rule_stack);
+        let prefix = self.prefix.take().and_then(PrefixTracker::finish);
+        ContextualError {
+            error: self.collect(),
+            rule_stack,
+            prefix,
+        }
+    }
     /// Finish matching and convert the tracker into recorded information.
     ///
     /// Returned value is:
@@ -272,6 +755,7 @@ impl<'i, R: RuleType> Tracker<'i, R> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use alloc::string::ToString;
     #[derive(Clone, Copy, Debug, Hash, Eq, Ord, PartialEq, PartialOrd)]
     enum Rule {
         Program,
@@ -301,6 +785,59 @@ mod tests {
         wrap!(EOI);
     }
     #[test]
+    fn distance() {
+        assert_eq!(edit_distance("while", "while"), 0);
+        assert_eq!(edit_distance("whle", "while"), 1);
+        assert_eq!(edit_distance("whiel", "while"), 2);
+        assert_eq!(edit_distance("", "for"), 3);
+        assert_eq!(edit_distance("ψα", "ψβα"), 1);
+    }
+    #[test]
+    fn literals() {
+        let pos = Position::from_start("abc");
+        let mut tracker = Tracker::<'_, Rule>::new(pos);
+        tracker.literal_failed(pos, "x");
+        tracker.literal_failed(pos, "x");
+        tracker.expect_owned_literal(pos, "y", true);
+        assert!(matches!(
+            tracker.expected_literals()[&None][..],
+            [(Cow::Borrowed("x"), false), (Cow::Owned(ref y), true)] if y == "y",
+        ));
+        assert_eq!(
+            tracker.collect().to_string(),
+            " --> 1:1\n  |\n1 | abc\n  | ^---\n  |\n  = ^---\n    Expected [\"x\", ^\"y\"]."
+        );
+
+        let mut nested = Tracker::<'_, Rule>::new(pos).nested(pos);
+        nested.literal_failed(pos, "x");
+        assert!(nested.expected_literals().is_empty());
+    }
+    #[test]
+    fn rule_stack() {
+        let pos = Position::from_start("abc");
+        let mut next = pos;
+        assert!(next.skip(1));
+        let mut tracker = Tracker::<'_, Rule>::new(pos);
+        let _ = tracker.record_during(pos, |tracker| {
+            tracker.record_during(next, |tracker| {
+                let _ = tracker.record_during(next, |_| Err::<(_, rule_wrappers::Body), ()>(()));
+                let _ = tracker.record_during(next, |_| Err::<(_, rule_wrappers::EOI), ()>(()));
+                Err::<(_, rule_wrappers::Main), ()>(())
+            })?;
+            Ok::<_, ()>((pos, rule_wrappers::Program))
+        });
+        assert_eq!(
+            tracker.rule_stack(),
+            [Rule::Program, Rule::Main, Rule::Body]
+        );
+        let error = tracker.collect_with_context();
+        assert_eq!(error.rule_stack(), [Rule::Program, Rule::Main, Rule::Body]);
+        assert_eq!(
+            error.to_string(),
+            " --> 1:2\n  |\n1 | abc\n  |  ^---\n  |\n  = a^---\n    Expected [Body, EOI], by Program."
+        );
+    }
+    #[test]
     fn negative() -> Result<(), ()> {
         let pos = Position::from_start("abc\ndef\nghi");
         let mut tracker = Tracker::<'_, Rule>::new(pos);
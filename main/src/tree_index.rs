@@ -0,0 +1,189 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Node IDs and parent references of a tree, for tools that go from nodes back to their parents
+//! or keep information about nodes in maps.
+//!
+//! Typed nodes only own their children, so a [`TreeIndex`] is built once by a pre-order traversal,
+//! see [`PairTree::tree_index`](crate::iterators::PairTree::tree_index).
+//! Each node gets a [`NodeId`], which is its index in pre-order,
+//! so IDs are deterministic: the same tree always gets the same IDs, and the root is [`NodeId::ROOT`].
+
+use crate::{iterators::Pair, Span};
+use alloc::{vec, vec::Vec};
+use core::ptr;
+use pest::RuleType;
+
+/// ID of a node in a [`TreeIndex`], which is the index of the node in pre-order.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct NodeId(usize);
+
+impl NodeId {
+    /// ID of the root.
+    pub const ROOT: Self = Self(0);
+    /// Index of the node in pre-order.
+    pub fn index(self) -> usize {
+        self.0
+    }
+}
+
+struct Entry<'i, 'n, R> {
+    node: &'n dyn Pair<'i, 'n, R>,
+    parent: Option<NodeId>,
+    /// End of the subtree in pre-order, exclusive.
+    end: usize,
+}
+
+/// Nodes of a tree, with their IDs and parents. See [module-level documentation](self).
+pub struct TreeIndex<'i, 'n, R: RuleType> {
+    entries: Vec<Entry<'i, 'n, R>>,
+}
+
+impl<'i: 'n, 'n, R: RuleType + 'n> TreeIndex<'i, 'n, R> {
+    /// Index the tree under `root`.
+    pub fn new(root: &'n dyn Pair<'i, 'n, R>) -> Self {
+        enum Visit<'i, 'n, R> {
+            Enter(&'n dyn Pair<'i, 'n, R>, Option<NodeId>),
+            Exit(usize),
+        }
+        let mut entries: Vec<Entry<'i, 'n, R>> = Vec::new();
+        let mut stack = vec![Visit::Enter(root, None)];
+        while let Some(visit) = stack.pop() {
+            match visit {
+                Visit::Enter(node, parent) => {
+                    let id = entries.len();
+                    entries.push(Entry {
+                        node,
+                        parent,
+                        end: id + 1,
+                    });
+                    stack.push(Visit::Exit(id));
+                    let children: Vec<_> = node.inner().collect();
+                    stack.extend(
+                        children
+                            .into_iter()
+                            .rev()
+                            .map(|child| Visit::Enter(child, Some(NodeId(id)))),
+                    );
+                }
+                Visit::Exit(id) => entries[id].end = entries.len(),
+            }
+        }
+        Self { entries }
+    }
+    /// Number of nodes.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+    /// Whether there is no node, which never holds as there's always a root.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+    /// IDs of all nodes, in pre-order.
+    pub fn ids(&self) -> impl DoubleEndedIterator<Item = NodeId> + ExactSizeIterator {
+        (0..self.entries.len()).map(NodeId)
+    }
+    /// The node with ID `id`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id` is not from this index, which holds for all methods taking IDs.
+    pub fn node(&self, id: NodeId) -> &'n dyn Pair<'i, 'n, R> {
+        self.entries[id.0].node
+    }
+    /// Rule of the node.
+    pub fn rule(&self, id: NodeId) -> R {
+        self.node(id).rule()
+    }
+    /// Span of the node.
+    pub fn span(&self, id: NodeId) -> Span<'i> {
+        self.node(id).span()
+    }
+    /// Parent of the node, or [`None`] for the root.
+    pub fn parent(&self, id: NodeId) -> Option<NodeId> {
+        self.entries[id.0].parent
+    }
+    /// Children of the node, in source order.
+    pub fn children(&self, id: NodeId) -> Children<'_, 'i, 'n, R> {
+        Children {
+            index: self,
+            next: id.0 + 1,
+            end: self.entries[id.0].end,
+        }
+    }
+    /// Descendants of the node, in pre-order, excluding itself.
+    pub fn descendants(&self, id: NodeId) -> impl DoubleEndedIterator<Item = NodeId> {
+        (id.0 + 1..self.entries[id.0].end).map(NodeId)
+    }
+    /// Ancestors of the node, from its parent to the root.
+    pub fn ancestors(&self, id: NodeId) -> Ancestors<'_, 'i, 'n, R> {
+        Ancestors {
+            index: self,
+            next: self.parent(id),
+        }
+    }
+    /// Number of ancestors of the node, which is 0 for the root.
+    pub fn depth(&self, id: NodeId) -> usize {
+        self.ancestors(id).count()
+    }
+    /// ID of `node`, which is found by its address in the indexed tree.
+    pub fn id_of(&self, node: &dyn Pair<'i, 'n, R>) -> Option<NodeId> {
+        self.entries
+            .iter()
+            .position(|entry| ptr::addr_eq(entry.node, node) && entry.node.rule() == node.rule())
+            .map(NodeId)
+    }
+    /// Innermost node whose span contains byte offset `pos`.
+    pub fn node_at(&self, pos: usize) -> Option<NodeId> {
+        let contains = |id: NodeId| {
+            let span = self.span(id);
+            span.start() <= pos && pos < span.end()
+        };
+        let mut current = Some(NodeId::ROOT).filter(|&id| contains(id))?;
+        while let Some(child) = self.children(current).find(|&id| contains(id)) {
+            current = child;
+        }
+        Some(current)
+    }
+}
+
+/// Children of a node. See [`TreeIndex::children`].
+pub struct Children<'t, 'i, 'n, R: RuleType> {
+    index: &'t TreeIndex<'i, 'n, R>,
+    next: usize,
+    end: usize,
+}
+
+impl<'i: 'n, 'n, R: RuleType + 'n> Iterator for Children<'_, 'i, 'n, R> {
+    type Item = NodeId;
+
+    fn next(&mut self) -> Option<NodeId> {
+        (self.next < self.end).then(|| {
+            let id = self.next;
+            self.next = self.index.entries[id].end;
+            NodeId(id)
+        })
+    }
+}
+
+/// Ancestors of a node. See [`TreeIndex::ancestors`].
+pub struct Ancestors<'t, 'i, 'n, R: RuleType> {
+    index: &'t TreeIndex<'i, 'n, R>,
+    next: Option<NodeId>,
+}
+
+impl<'i: 'n, 'n, R: RuleType + 'n> Iterator for Ancestors<'_, 'i, 'n, R> {
+    type Item = NodeId;
+
+    fn next(&mut self) -> Option<NodeId> {
+        let id = self.next?;
+        self.next = self.index.parent(id);
+        Some(id)
+    }
+}
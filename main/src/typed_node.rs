@@ -8,10 +8,16 @@
 // modified, or distributed except according to those terms.
 
 use crate::{
-    error::Error, position::Position, predefined_node::restore_on_err, span::Span,
-    tracker::Tracker, RuleWrapper, Stack,
+    controller::{ControlledError, ParseController},
+    error::Error,
+    line_index::LineIndex,
+    position::Position,
+    predefined_node::restore_on_err,
+    span::Span,
+    tracker::{ContextualError, Tracker},
+    RuleWrapper, Stack,
 };
-use alloc::vec::Vec;
+use alloc::{sync::Arc, vec::Vec};
 use core::fmt::Debug;
 use pest::RuleType;
 
@@ -22,7 +28,14 @@ where
 {
     /// Create typed node.
     /// `ATOMIC` refers to the external status, and it can be overriden by rule definition.
-    fn parse_with(input: Position<'i>, stack: &mut Stack<Span<'i>>) -> (Position<'i>, Self);
+    ///
+    /// Attempts are not recorded in `tracker`, which only provides the mode to parse in.
+    /// See [`Tracker::nested`].
+    fn parse_with(
+        input: Position<'i>,
+        stack: &mut Stack<Span<'i>>,
+        tracker: &Tracker<'i, R>,
+    ) -> (Position<'i>, Self);
 }
 
 /// Node of concrete syntax tree.
@@ -49,27 +62,140 @@ pub trait ParsableTypedNode<'i, R: RuleType>: TypedNode<'i, R> {
         stack: &mut Stack<Span<'i>>,
         tracker: &mut Tracker<'i, R>,
     ) -> Result<Self, ()>;
+    /// Try to parse the whole input into given typed node, with a tracker configured by its `with_*` methods,
+    /// such as [`Tracker::with_raw`] and [`Tracker::with_version`], which can be combined.
+    ///
+    /// The tracker should be created at the start of `input`,
+    /// and keeps what it has recorded, so that the error can be collected from it on failure.
+    ///
+    /// ```ignore
+    /// let mut tracker = Tracker::new(Position::from_start(input)).with_raw().with_version(2);
+    /// let node = rules::expr::try_parse_with_tracker(input, &mut tracker)
+    ///     .map_err(|_| tracker.collect_with_context())?;
+    /// ```
+    #[allow(clippy::result_unit_err)]
+    fn try_parse_with_tracker(input: &'i str, tracker: &mut Tracker<'i, R>) -> Result<Self, ()> {
+        let mut stack = Stack::new();
+        Self::try_parse_with_until_end(Position::from_start(input), &mut stack, tracker)
+    }
+    /// Try to parse given typed node from `start` with a configured tracker, without requiring `EOI` after it,
+    /// and return it with the position where the rest of the input starts.
+    ///
+    /// The tracker should be created at `start`. See [`ParsableTypedNode::try_parse_with_tracker`].
+    #[allow(clippy::result_unit_err)]
+    fn try_parse_prefix_with_tracker(
+        start: Position<'i>,
+        tracker: &mut Tracker<'i, R>,
+    ) -> Result<(Self, Position<'i>), ()> {
+        let mut stack = Stack::new();
+        let (rest, res) = Self::try_parse_with(start, &mut stack, tracker)?;
+        Ok((res, rest))
+    }
     /// Try to parse the whole input into given typed node.
     /// A rule is not atomic by default.
     fn try_parse(input: &'i str) -> Result<Self, Error<R>> {
-        let mut stack = Stack::new();
-        let input = Position::from_start(input);
-        let mut tracker = Tracker::new(input);
-        match Self::try_parse_with_until_end(input, &mut stack, &mut tracker) {
-            Ok(res) => Ok(res),
-            Err(_) => Err(tracker.collect()),
+        let mut tracker = Tracker::new(Position::from_start(input));
+        Self::try_parse_with_tracker(input, &mut tracker).map_err(|_| tracker.collect())
+    }
+    /// Try to parse the whole input into given typed node,
+    /// and keep the rules being attempted where parsing failed.
+    ///
+    /// See [`ContextualError::rule_stack`].
+    fn try_parse_with_context(input: &'i str) -> Result<Self, ContextualError<R>> {
+        let mut tracker = Tracker::new(Position::from_start(input));
+        Self::try_parse_with_tracker(input, &mut tracker)
+            .map_err(|_| tracker.collect_with_context())
+    }
+    /// Try to parse the whole input into given typed node,
+    /// and keep the longest prefix that parsed, with rules nested in at most `depth` rules.
+    ///
+    /// See [`Tracker::with_prefix`] and [`ContextualError::prefix`].
+    fn try_parse_with_prefix(input: &'i str, depth: usize) -> Result<Self, ContextualError<R>> {
+        let mut tracker = Tracker::new(Position::from_start(input)).with_prefix(depth);
+        Self::try_parse_with_tracker(input, &mut tracker)
+            .map_err(|_| tracker.collect_with_context())
+    }
+    /// Try to parse the whole input into given typed node,
+    /// and suggest keywords within `max_distance` edits on failure.
+    ///
+    /// See [`Tracker::with_suggestions`].
+    fn try_parse_with_suggestions(input: &'i str, max_distance: usize) -> Result<Self, Error<R>> {
+        let mut tracker = Tracker::new(Position::from_start(input)).with_suggestions(max_distance);
+        Self::try_parse_with_tracker(input, &mut tracker).map_err(|_| tracker.collect())
+    }
+    /// Try to parse the whole input into given typed node as language version `version`.
+    ///
+    /// See [`Tracker::with_version`].
+    fn try_parse_with_version(input: &'i str, version: u32) -> Result<Self, Error<R>> {
+        let mut tracker = Tracker::new(Position::from_start(input)).with_version(version);
+        Self::try_parse_with_tracker(input, &mut tracker).map_err(|_| tracker.collect())
+    }
+    /// Try to parse the whole input into given typed node,
+    /// and add matched rules and alternatives to `coverage`.
+    #[cfg(feature = "coverage")]
+    fn try_parse_with_coverage(
+        input: &'i str,
+        coverage: &mut crate::coverage::Coverage<R>,
+    ) -> Result<Self, Error<R>> {
+        let mut tracker =
+            Tracker::new(Position::from_start(input)).with_coverage(core::mem::take(coverage));
+        let res = Self::try_parse_with_tracker(input, &mut tracker);
+        *coverage = tracker.take_coverage().unwrap_or_default();
+        res.map_err(|_| tracker.collect())
+    }
+    /// Try to parse the whole input into given typed node,
+    /// and add attempts, failures and time of rules to `stats`.
+    #[cfg(feature = "profile")]
+    fn try_parse_with_stats(
+        input: &'i str,
+        stats: &mut crate::profile::ParseStats<R>,
+    ) -> Result<Self, Error<R>> {
+        let mut tracker =
+            Tracker::new(Position::from_start(input)).with_stats(core::mem::take(stats));
+        let res = Self::try_parse_with_tracker(input, &mut tracker);
+        *stats = tracker.take_stats().unwrap_or_default();
+        res.map_err(|_| tracker.collect())
+    }
+    /// Try to parse the whole input into given typed node,
+    /// and stop if `controller` is cancelled.
+    fn try_parse_with_controller(
+        input: &'i str,
+        controller: &Arc<ParseController>,
+    ) -> Result<Self, ControlledError<R>> {
+        let mut tracker = Tracker::with_controller(Position::from_start(input), controller.clone());
+        let res = Self::try_parse_with_tracker(input, &mut tracker);
+        if tracker.is_cancelled() {
+            return Err(ControlledError::Cancelled);
         }
+        res.map_err(|_| ControlledError::Failed(tracker.collect()))
     }
-    /// Try to parse the whole input into given typed node.
+    /// Try to parse a prefix of the input into given typed node,
+    /// and return the position where it ends.
+    /// [`Position::rest`] of it is the remaining input.
     /// A rule is not atomic by default.
+    ///
+    /// To go on parsing from there, see [`Cursor`](crate::cursor::Cursor).
+    ///
+    /// Same as [`ParsableTypedNode::try_parse_prefix`] with the pair in the other order,
+    /// which is kept for compatibility.
     fn try_parse_partial(input: &'i str) -> Result<(Position<'i>, Self), Error<R>> {
-        let mut stack = Stack::new();
-        let input = Position::from_start(input);
-        let mut tracker = Tracker::new(input);
-        match Self::try_parse_with(input, &mut stack, &mut tracker) {
-            Ok((input, res)) => Ok((input, res)),
-            Err(_) => Err(tracker.collect()),
-        }
+        Self::try_parse_prefix(input).map(|(res, rest)| (rest, res))
+    }
+    /// Try to parse a prefix of the input into given typed node,
+    /// and return it with the position where the rest of the input starts.
+    ///
+    /// Unlike [`ParsableTypedNode::try_parse`], `EOI` is not required after the node,
+    /// so that the rest can be handed to another parser.
+    fn try_parse_prefix(input: &'i str) -> Result<(Self, Position<'i>), Error<R>> {
+        Self::try_parse_prefix_at(Position::from_start(input))
+    }
+    /// Try to parse given typed node from `start`, without requiring `EOI` after it.
+    ///
+    /// Positions and spans are still offsets into the whole input of `start`.
+    /// See [`ParsableTypedNode::try_parse_prefix`].
+    fn try_parse_prefix_at(start: Position<'i>) -> Result<(Self, Position<'i>), Error<R>> {
+        let mut tracker = Tracker::new(start);
+        Self::try_parse_prefix_with_tracker(start, &mut tracker).map_err(|_| tracker.collect())
     }
 }
 
@@ -90,7 +216,7 @@ pub trait NeverFailedParsableTypedNode<'i, R: RuleType>: NeverFailedTypedNode<'i
     fn parse_partial(input: &'i str) -> (Position<'i>, Self) {
         let mut stack = Stack::new();
         let input = Position::from_start(input);
-        Self::parse_with(input, &mut stack)
+        Self::parse_with(input, &mut stack, &Tracker::new(input))
     }
 }
 
@@ -107,6 +233,12 @@ impl<R: RuleType, T: RuleWrapper<R>> RuleStorage<R> for T {
 pub trait RuleStruct<'i, R: RuleType>: RuleStorage<R> {
     /// The span of a matched expression by a non-silent rule.
     fn span(&self) -> Span<'i>;
+    /// Lines and columns of both ends of [`RuleStruct::span`], looked up in an index of the input.
+    ///
+    /// Build the [`LineIndex`] once and pass it to every node, so that a pass over the tree stays linear.
+    fn line_col_range(&self, index: &LineIndex<'i>) -> ((usize, usize), (usize, usize)) {
+        index.line_col_range(&self.span())
+    }
 }
 
 /// Match `[T; N]`.
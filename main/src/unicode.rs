@@ -0,0 +1,129 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Unicode properties matched by builtin rules, such as `XID_START` and `GREEK`.
+//!
+//! Functions named after properties in upper case, such as [`XID_START`], are re-exported from [pest],
+//! and they are what generated code calls, so matching at runtime is the same as in grammars.
+//!
+//! Properties can also be looked up by names in the forms of `\p{...}` classes,
+//! which grammars may use in place of builtin rules:
+//!
+//! ```
+//! # use pest_typed::unicode::{has_property, is_xid_start, resolve, PropertyKind};
+//! assert!(is_xid_start('a'));
+//! assert_eq!(resolve("Script=Greek"), Some((PropertyKind::Script, "GREEK")));
+//! assert_eq!(resolve("gc=uppercase letter"), Some((PropertyKind::GeneralCategory, "UPPERCASE_LETTER")));
+//! assert_eq!(has_property("Greek", 'α'), Some(true));
+//! assert_eq!(has_property("Greek", 'a'), Some(false));
+//! assert_eq!(has_property("Klingon", 'a'), None);
+//! ```
+
+pub use pest::unicode::*;
+
+/// Kind of a Unicode property.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum PropertyKind {
+    /// Binary property, such as `Alphabetic`.
+    Binary,
+    /// Value of `General_Category`, such as `Uppercase_Letter`.
+    GeneralCategory,
+    /// Value of `Script`, such as `Greek`.
+    Script,
+}
+
+impl PropertyKind {
+    /// Names of all properties of this kind, as builtin rules.
+    pub fn names(self) -> &'static [&'static str] {
+        match self {
+            Self::Binary => BINARY_PROPERTY_NAMES,
+            Self::GeneralCategory => CATEGORY_PROPERTY_NAMES,
+            Self::Script => SCRIPT_PROPERTY_NAMES,
+        }
+    }
+}
+
+/// Compare names ignoring cases, spaces, `-` and `_`, as loose matching in Unicode.
+fn loose_eq(a: &str, b: &str) -> bool {
+    let normalized = |s: &'_ str| {
+        s.chars()
+            .filter(|c| !matches!(c, ' ' | '-' | '_'))
+            .map(|c| c.to_ascii_uppercase())
+            .collect::<alloc::vec::Vec<_>>()
+    };
+    normalized(a) == normalized(b)
+}
+
+/// Find a property by the content of a `\p{...}` class, and return its kind and builtin rule name.
+///
+/// The class is either a property name, such as `XID_Start` and `Greek`,
+/// or a property value, such as `Script=Greek` and `General_Category=Letter`.
+/// `sc` and `gc` are short for `Script` and `General_Category`.
+/// Names are loosely matched, so cases, spaces, `-` and `_` don't matter.
+pub fn resolve(class: &str) -> Option<(PropertyKind, &'static str)> {
+    let kinds: &[PropertyKind] = match class.split_once('=') {
+        Some((key, _)) if loose_eq(key, "Script") || loose_eq(key, "sc") => &[PropertyKind::Script],
+        Some((key, _)) if loose_eq(key, "General_Category") || loose_eq(key, "gc") => {
+            &[PropertyKind::GeneralCategory]
+        }
+        Some(_) => return None,
+        None => &[
+            PropertyKind::Binary,
+            PropertyKind::GeneralCategory,
+            PropertyKind::Script,
+        ],
+    };
+    let name = class
+        .split_once('=')
+        .map_or(class, |(_, value)| value)
+        .trim();
+    kinds.iter().find_map(|&kind| {
+        kind.names()
+            .iter()
+            .find(|candidate| loose_eq(candidate, name))
+            .map(|&candidate| (kind, candidate))
+    })
+}
+
+/// Whether `c` has the property named by `class`, or [`None`] if there is no such property.
+///
+/// See [`resolve`] for accepted names.
+pub fn has_property(class: &str, c: char) -> Option<bool> {
+    let (_, name) = resolve(class)?;
+    by_name(name).map(|f| f(c))
+}
+
+macro_rules! is {
+    ($($(#[$attr:meta])* $name:ident => $property:ident,)*) => {
+        $(
+            $(#[$attr])*
+            #[inline]
+            pub fn $name(c: char) -> bool {
+                $property(c)
+            }
+        )*
+    };
+}
+
+is! {
+    /// Whether `c` may start an identifier, as `XID_START`.
+    is_xid_start => XID_START,
+    /// Whether `c` may continue an identifier, as `XID_CONTINUE`.
+    is_xid_continue => XID_CONTINUE,
+    /// Whether `c` is alphabetic, as `ALPHABETIC`.
+    is_alphabetic => ALPHABETIC,
+    /// Whether `c` is lowercase, as `LOWERCASE`.
+    is_lowercase => LOWERCASE,
+    /// Whether `c` is uppercase, as `UPPERCASE`.
+    is_uppercase => UPPERCASE,
+    /// Whether `c` is a white space, as `WHITE_SPACE`.
+    is_white_space => WHITE_SPACE,
+    /// Whether `c` is an emoji, as `EMOJI`.
+    is_emoji => EMOJI,
+}
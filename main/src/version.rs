@@ -0,0 +1,130 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Rules that are only in some versions of a language.
+//!
+//! The generator wraps inner expressions of rules marked with `#[cfg(version >= 2)]` and the like in [`Gated`].
+//! The version being parsed is set on the entry point,
+//! with [`ParsableTypedNode::try_parse_with_version`](crate::ParsableTypedNode::try_parse_with_version)
+//! or [`Tracker::with_version`], and gated rules simply fail in other versions,
+//! so that one typed API serves all versions, where optional references to them are [`None`].
+//!
+//! ```
+//! # use pest_typed::{predefined_node::Str, version::Gated, Position, Stack, StringWrapper, TypedNode};
+//! # use pest_typed::tracker::Tracker;
+//! #[derive(Clone, Debug, Hash, PartialEq, Eq)]
+//! struct Async;
+//! impl StringWrapper for Async {
+//!     const CONTENT: &'static str = "async";
+//! }
+//! type Since2 = Gated<Str<Async>, 2, { u32::MAX }>;
+//! let input = Position::from_start("async");
+//! let mut tracker = Tracker::<()>::new(input).with_version(1);
+//! assert!(Since2::try_parse_with(input, &mut Stack::new(), &mut tracker).is_err());
+//! let mut tracker = Tracker::<()>::new(input).with_version(2);
+//! assert!(Since2::try_parse_with(input, &mut Stack::new(), &mut tracker).is_ok());
+//! ```
+//!
+//! Lazy subtrees are parsed later with the version of the parse they come from.
+
+use crate::{
+    fold::Foldable,
+    iterators::Pairs,
+    metrics::HeapSize,
+    position::Position,
+    recovery::{Provenance, Recovered},
+    span::Span,
+    tracker::Tracker,
+    RuleType, Stack, TypedNode,
+};
+use core::ops::Deref;
+
+/// Whether `version` is in `MIN..=MAX`.
+pub const fn enabled<const MIN: u32, const MAX: u32>(version: u32) -> bool {
+    MIN <= version && version <= MAX
+}
+
+/// `T` that is only matched in versions `MIN..=MAX`. See [module-level documentation](self).
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub struct Gated<T, const MIN: u32, const MAX: u32> {
+    // Named so that it's not confused with fields of `T` when dereferenced.
+    versioned: T,
+}
+
+impl<T, const MIN: u32, const MAX: u32> Gated<T, MIN, MAX> {
+    /// Take the inner node.
+    pub fn into_inner(self) -> T {
+        self.versioned
+    }
+}
+
+impl<T, const MIN: u32, const MAX: u32> From<T> for Gated<T, MIN, MAX> {
+    fn from(versioned: T) -> Self {
+        Self { versioned }
+    }
+}
+
+impl<T, const MIN: u32, const MAX: u32> Deref for Gated<T, MIN, MAX> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.versioned
+    }
+}
+
+impl<'i, R: RuleType, T: TypedNode<'i, R>, const MIN: u32, const MAX: u32> TypedNode<'i, R>
+    for Gated<T, MIN, MAX>
+{
+    #[inline]
+    fn try_parse_with(
+        input: Position<'i>,
+        stack: &mut Stack<Span<'i>>,
+        tracker: &mut Tracker<'i, R>,
+    ) -> Result<(Position<'i>, Self), ()> {
+        if !enabled::<MIN, MAX>(tracker.version()) {
+            return Err(());
+        }
+        let (input, versioned) = T::try_parse_with(input, stack, tracker)?;
+        Ok((input, Self { versioned }))
+    }
+}
+
+impl<'i: 'n, 'n, R: RuleType + 'n, T: Pairs<'i, 'n, R>, const MIN: u32, const MAX: u32>
+    Pairs<'i, 'n, R> for Gated<T, MIN, MAX>
+{
+    type Iter = T::Iter;
+    type IntoIter = T::IntoIter;
+
+    fn iter_pairs(&'n self) -> Self::Iter {
+        self.versioned.iter_pairs()
+    }
+    fn into_iter_pairs(self) -> Self::IntoIter {
+        self.versioned.into_iter_pairs()
+    }
+}
+
+impl<F: ?Sized, T: Foldable<F>, const MIN: u32, const MAX: u32> Foldable<F> for Gated<T, MIN, MAX> {
+    #[inline]
+    fn fold_with(self, folder: &mut F) -> Self {
+        Self::from(self.versioned.fold_with(folder))
+    }
+}
+
+impl<T: HeapSize, const MIN: u32, const MAX: u32> HeapSize for Gated<T, MIN, MAX> {
+    #[inline]
+    fn heap_size(&self) -> usize {
+        self.versioned.heap_size()
+    }
+}
+
+impl<T: Recovered, const MIN: u32, const MAX: u32> Recovered for Gated<T, MIN, MAX> {
+    #[inline]
+    fn provenance(&self) -> Provenance {
+        self.versioned.provenance()
+    }
+}
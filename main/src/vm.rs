@@ -0,0 +1,714 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! An interpreter for grammars loaded at runtime, such as for playgrounds, REPLs and tools that accept user-provided grammars.
+//!
+//! The grammar is not compiled into types, so parsing produces a dynamically typed [`Node`],
+//! which mirrors the structure of the types that would have been generated.
+//!
+//! With feature `grammar-extras`, node tags are accepted and don't change what is matched.
+//! `PUSH_LITERAL` is not supported, and [`Vm::new`] returns an error at rules that use it.
+//!
+//! ```
+//! use pest_typed::vm::{Node, Vm};
+//!
+//! let vm = Vm::new(r#"pair = { key ~ "=" ~ key } key = @{ ASCII_ALPHA+ } WHITESPACE = _{ " " }"#).unwrap();
+//! let pair = vm.parse("pair", "a = b").unwrap();
+//! let tokens = pair.tokens();
+//! assert_eq!(tokens[0].rule.0, "pair");
+//! assert_eq!(tokens[0].children.len(), 2);
+//! assert!(matches!(pair, Node::Rule { name: "pair", .. }));
+//!
+//! let error = vm.parse("pair", "a =").unwrap_err();
+//! assert_eq!(error.line_col, pest::error::LineColLocation::Pos((1, 4)));
+//! ```
+
+use crate::{
+    error::{Error, ErrorVariant},
+    iterators::Token,
+    predefined_node::{peek_spans, restore_on_err, stack_slice},
+    reflection::RuleKind,
+    tracker::Tracker,
+    Position, Span, Stack,
+};
+use alloc::{boxed::Box, collections::BTreeMap, format, string::String, vec, vec::Vec};
+use core::fmt;
+use pest_meta::{ast, parser};
+
+/// An error in the grammar.
+pub type GrammarError = Error<parser::Rule>;
+
+/// Name of a rule, used as the rule type of errors and tokens.
+#[derive(Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct RuleName<'g>(pub &'g str);
+
+impl fmt::Debug for RuleName<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.0)
+    }
+}
+
+impl fmt::Display for RuleName<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.0)
+    }
+}
+
+/// A parsed node.
+///
+/// Sequences and choices are flattened like the generated types, and silent rules are replaced by their content.
+/// Skipped comments and white spaces are not kept.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Node<'g, 'i> {
+    /// A non-silent rule.
+    Rule {
+        /// Rule name.
+        name: &'g str,
+        /// Matched span.
+        span: Span<'i>,
+        /// Matched expression.
+        content: Box<Self>,
+    },
+    /// A string, matched case sensitively.
+    Str(Span<'i>),
+    /// A string, matched case insensitively.
+    Insens(Span<'i>),
+    /// A character in a range.
+    Range(char),
+    /// A built-in rule, such as `ANY`, `POP` or unicode properties.
+    Builtin {
+        /// Rule name.
+        name: &'g str,
+        /// Matched span.
+        span: Span<'i>,
+    },
+    /// A sequence.
+    Seq(Vec<Self>),
+    /// The matched alternative of a choice.
+    Choice {
+        /// Index of the alternative.
+        index: usize,
+        /// Matched alternative.
+        content: Box<Self>,
+    },
+    /// An optional expression.
+    Opt(Option<Box<Self>>),
+    /// A repetition.
+    Rep(Vec<Self>),
+    /// A positive predicate.
+    PosPred(Box<Self>),
+    /// A negative predicate.
+    NegPred,
+    /// Skipped span of `(!(s1 | s2 | ...) ~ ANY)*`.
+    Skip(Span<'i>),
+    /// Expression pushed to the stack.
+    Push(Box<Self>),
+    /// A slice of the stack.
+    PeekSlice(Span<'i>),
+}
+
+impl<'g, 'i> Node<'g, 'i> {
+    /// Token trees of the outermost rules in this node, including itself.
+    ///
+    /// Predicates are not included, which is consistent with
+    /// [`Pairs`](crate::iterators::Pairs) of the generated types.
+    pub fn tokens(&self) -> Vec<Token<RuleName<'g>>> {
+        let mut res = vec![];
+        self.collect_tokens(&mut res);
+        res
+    }
+    fn collect_tokens(&self, res: &mut Vec<Token<RuleName<'g>>>) {
+        match self {
+            Self::Rule {
+                name,
+                span,
+                content,
+            } => res.push(Token {
+                rule: RuleName(name),
+                start: span.start(),
+                end: span.end(),
+                children: content.tokens(),
+            }),
+            Self::Seq(nodes) | Self::Rep(nodes) => {
+                nodes.iter().for_each(|node| node.collect_tokens(res))
+            }
+            Self::Choice { content, .. } | Self::Opt(Some(content)) | Self::Push(content) => {
+                content.collect_tokens(res)
+            }
+            _ => (),
+        }
+    }
+}
+
+enum Builtin {
+    Any,
+    Soi,
+    Eoi,
+    Newline,
+    Peek,
+    PeekAll,
+    Pop,
+    PopAll,
+    Drop,
+    Char(Box<dyn Fn(char) -> bool>),
+    Fail,
+}
+
+enum Expr {
+    Str(String),
+    Insens(String),
+    Range(char, char),
+    Rule(usize),
+    Builtin(String, Builtin),
+    PeekSlice(i32, Option<i32>),
+    PosPred(Box<Expr>),
+    NegPred(Box<Expr>),
+    Seq(Vec<Expr>),
+    Choice(Vec<Expr>),
+    Opt(Box<Expr>),
+    Rep(Box<Expr>, u32, Option<u32>),
+    Skip(Vec<String>),
+    Push(Box<Expr>),
+}
+
+struct Rule {
+    name: String,
+    kind: RuleKind,
+    expr: Expr,
+}
+
+/// A grammar loaded at runtime.
+pub struct Vm {
+    rules: Vec<Rule>,
+    indices: BTreeMap<String, usize>,
+    whitespace: Option<usize>,
+    comment: Option<usize>,
+}
+
+impl fmt::Debug for Vm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Vm")
+            .field("rules", &self.indices.keys())
+            .finish()
+    }
+}
+
+fn builtin(name: &str) -> Builtin {
+    fn ascii(f: fn(&char) -> bool) -> Builtin {
+        Builtin::Char(Box::new(move |c| f(&c)))
+    }
+    match name {
+        "ANY" => Builtin::Any,
+        "SOI" => Builtin::Soi,
+        "EOI" => Builtin::Eoi,
+        "NEWLINE" => Builtin::Newline,
+        "PEEK" => Builtin::Peek,
+        "PEEK_ALL" => Builtin::PeekAll,
+        "POP" => Builtin::Pop,
+        "POP_ALL" => Builtin::PopAll,
+        "DROP" => Builtin::Drop,
+        "ASCII_DIGIT" => ascii(char::is_ascii_digit),
+        "ASCII_NONZERO_DIGIT" => ascii(|c| ('1'..='9').contains(c)),
+        "ASCII_BIN_DIGIT" => ascii(|c| ('0'..='1').contains(c)),
+        "ASCII_OCT_DIGIT" => ascii(|c| ('0'..='7').contains(c)),
+        "ASCII_HEX_DIGIT" => ascii(char::is_ascii_hexdigit),
+        "ASCII_ALPHA_LOWER" => ascii(char::is_ascii_lowercase),
+        "ASCII_ALPHA_UPPER" => ascii(char::is_ascii_uppercase),
+        "ASCII_ALPHA" => ascii(char::is_ascii_alphabetic),
+        "ASCII_ALPHANUMERIC" => ascii(char::is_ascii_alphanumeric),
+        "ASCII" => ascii(char::is_ascii),
+        // Undefined `WHITESPACE` and `COMMENT`, and undefined rules, which are rejected by the validator.
+        name => crate::unicode::by_name(name).map_or(Builtin::Fail, Builtin::Char),
+    }
+}
+
+impl Vm {
+    /// Load a grammar.
+    pub fn new(grammar: &str) -> Result<Self, Vec<GrammarError>> {
+        let pairs = parser::parse(parser::Rule::grammar_rules, grammar).map_err(|e| vec![e])?;
+        pest_meta::validator::validate_pairs(pairs.clone())?;
+        let rules = parser::consume_rules(pairs.clone())?;
+        let indices: BTreeMap<String, usize> = rules
+            .iter()
+            .enumerate()
+            .map(|(i, rule)| (rule.name.clone(), i))
+            .collect();
+        let mut errors = vec![];
+        let mut converted = Vec::with_capacity(rules.len());
+        for rule in rules {
+            let expr = match Self::convert(rule.expr, &indices) {
+                Ok(expr) => expr,
+                Err(message) => {
+                    // Point at the definition of the rule.
+                    let span = pairs
+                        .clone()
+                        .flatten()
+                        .find(|pair| {
+                            pair.as_rule() == parser::Rule::grammar_rule
+                                && pair.clone().into_inner().next().map(|name| name.as_str())
+                                    == Some(rule.name.as_str())
+                        })
+                        .map_or_else(
+                            || pest::Span::new(grammar, 0, 0).unwrap(),
+                            |pair| pair.as_span(),
+                        );
+                    errors.push(Error::new_from_span(
+                        ErrorVariant::CustomError {
+                            message: String::from(message),
+                        },
+                        span,
+                    ));
+                    continue;
+                }
+            };
+            converted.push(Rule {
+                kind: match rule.ty {
+                    ast::RuleType::Normal => RuleKind::Normal,
+                    ast::RuleType::Silent => RuleKind::Silent,
+                    ast::RuleType::Atomic => RuleKind::Atomic,
+                    ast::RuleType::CompoundAtomic => RuleKind::CompoundAtomic,
+                    ast::RuleType::NonAtomic => RuleKind::NonAtomic,
+                },
+                expr,
+                name: rule.name,
+            });
+        }
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+        let rules = converted;
+        Ok(Self {
+            rules,
+            whitespace: indices.get("WHITESPACE").copied(),
+            comment: indices.get("COMMENT").copied(),
+            indices,
+        })
+    }
+    /// Convert an expression, or return why it's not supported.
+    fn convert(expr: ast::Expr, indices: &BTreeMap<String, usize>) -> Result<Expr, &'static str> {
+        let convert = |expr: Box<ast::Expr>| Self::convert(*expr, indices).map(Box::new);
+        let char_of = |s: String| s.chars().next().unwrap_or_default();
+        #[allow(unreachable_patterns)]
+        Ok(match expr {
+            ast::Expr::Str(s) => Expr::Str(s),
+            ast::Expr::Insens(s) => Expr::Insens(s),
+            ast::Expr::Range(start, end) => Expr::Range(char_of(start), char_of(end)),
+            ast::Expr::Ident(name) => match indices.get(&name) {
+                Some(index) => Expr::Rule(*index),
+                None => {
+                    let builtin = builtin(&name);
+                    Expr::Builtin(name, builtin)
+                }
+            },
+            ast::Expr::PeekSlice(start, end) => Expr::PeekSlice(start, end),
+            ast::Expr::PosPred(expr) => Expr::PosPred(convert(expr)?),
+            ast::Expr::NegPred(expr) => Expr::NegPred(convert(expr)?),
+            ast::Expr::Seq(lhs, rhs) => {
+                let mut seq = vec![Self::convert(*lhs, indices)?];
+                match Self::convert(*rhs, indices)? {
+                    Expr::Seq(rest) => seq.extend(rest),
+                    rhs => seq.push(rhs),
+                }
+                Expr::Seq(seq)
+            }
+            ast::Expr::Choice(lhs, rhs) => {
+                let mut choices = vec![Self::convert(*lhs, indices)?];
+                match Self::convert(*rhs, indices)? {
+                    Expr::Choice(rest) => choices.extend(rest),
+                    rhs => choices.push(rhs),
+                }
+                Expr::Choice(choices)
+            }
+            ast::Expr::Opt(expr) => Expr::Opt(convert(expr)?),
+            ast::Expr::Rep(expr) => Expr::Rep(convert(expr)?, 0, None),
+            ast::Expr::RepOnce(expr) => Expr::Rep(convert(expr)?, 1, None),
+            ast::Expr::RepExact(expr, n) => Expr::Rep(convert(expr)?, n, Some(n)),
+            ast::Expr::RepMin(expr, min) => Expr::Rep(convert(expr)?, min, None),
+            ast::Expr::RepMax(expr, max) => Expr::Rep(convert(expr)?, 0, Some(max)),
+            ast::Expr::RepMinMax(expr, min, max) => Expr::Rep(convert(expr)?, min, Some(max)),
+            ast::Expr::Skip(strings) => Expr::Skip(strings),
+            ast::Expr::Push(expr) => Expr::Push(convert(expr)?),
+            #[cfg(feature = "grammar-extras")]
+            ast::Expr::NodeTag(expr, _) => Self::convert(*expr, indices)?,
+            #[cfg(feature = "grammar-extras")]
+            ast::Expr::PushLiteral(_) => return Err("`PUSH_LITERAL` is not supported yet"),
+            // Expressions from `grammar-extras` of `pest_meta`, enabled by another crate.
+            _ => return Err("node tags and `PUSH_LITERAL` require feature `grammar-extras`"),
+        })
+    }
+    /// Names of all rules in the grammar.
+    pub fn rule_names(&self) -> impl Iterator<Item = &str> + '_ {
+        self.rules.iter().map(|rule| rule.name.as_str())
+    }
+    /// Kind of the rule.
+    pub fn rule_kind(&self, rule: &str) -> Option<RuleKind> {
+        self.indices.get(rule).map(|&index| self.rules[index].kind)
+    }
+    fn start<'g, 'i>(
+        &'g self,
+        rule: &str,
+        input: &'i str,
+    ) -> Result<(usize, Position<'i>, Tracker<'i, RuleName<'g>>), Error<RuleName<'g>>> {
+        let index = self.indices.get(rule).copied().ok_or_else(|| {
+            Error::new_from_pos(
+                ErrorVariant::CustomError {
+                    message: format!("Rule {} is not defined.", rule),
+                },
+                pest::Position::from_start(input),
+            )
+        })?;
+        let input = Position::from_start(input);
+        Ok((index, input, Tracker::new(input)))
+    }
+    /// Parse the whole input with the rule.
+    pub fn parse<'g, 'i>(
+        &'g self,
+        rule: &str,
+        input: &'i str,
+    ) -> Result<Node<'g, 'i>, Error<RuleName<'g>>> {
+        let (index, input, mut tracker) = self.start(rule, input)?;
+        let mut stack = Stack::new();
+        let Ok((mut input, node)) = self.rule(index, input, &mut stack, &mut tracker, false) else {
+            return Err(tracker.collect());
+        };
+        if !matches!(
+            self.rules[index].kind,
+            RuleKind::Atomic | RuleKind::CompoundAtomic
+        ) {
+            input = self.skip(input, &mut stack);
+        }
+        match tracker.record_during_with(
+            input,
+            |tracker| self.builtin(&Builtin::Eoi, "EOI", input, &mut stack, tracker),
+            RuleName("EOI"),
+        ) {
+            Ok(_) => Ok(node),
+            Err(_) => Err(tracker.collect()),
+        }
+    }
+    /// Parse a prefix of the input with the rule.
+    pub fn parse_partial<'g, 'i>(
+        &'g self,
+        rule: &str,
+        input: &'i str,
+    ) -> Result<(Position<'i>, Node<'g, 'i>), Error<RuleName<'g>>> {
+        let (index, input, mut tracker) = self.start(rule, input)?;
+        let mut stack = Stack::new();
+        self.rule(index, input, &mut stack, &mut tracker, false)
+            .map_err(|_| tracker.collect())
+    }
+    fn rule<'g, 'i>(
+        &'g self,
+        index: usize,
+        input: Position<'i>,
+        stack: &mut Stack<Span<'i>>,
+        tracker: &mut Tracker<'i, RuleName<'g>>,
+        atomic: bool,
+    ) -> Result<(Position<'i>, Node<'g, 'i>), ()> {
+        let rule = &self.rules[index];
+        let atomic = match rule.kind {
+            RuleKind::Atomic | RuleKind::CompoundAtomic => true,
+            RuleKind::NonAtomic => false,
+            RuleKind::Normal | RuleKind::Silent => atomic,
+        };
+        match rule.kind {
+            RuleKind::Silent => self.expr(&rule.expr, input, stack, tracker, atomic),
+            _ => tracker.record_during_with(
+                input,
+                |tracker| {
+                    let (end, content) = self.expr(&rule.expr, input, stack, tracker, atomic)?;
+                    let node = Node::Rule {
+                        name: &rule.name,
+                        span: input.span(&end),
+                        content: Box::new(content),
+                    };
+                    Ok((end, node))
+                },
+                RuleName(&rule.name),
+            ),
+        }
+    }
+    /// Skip comments and white spaces.
+    fn skip<'i>(&self, mut input: Position<'i>, stack: &mut Stack<Span<'i>>) -> Position<'i> {
+        let mut tracker = Tracker::new(input);
+        loop {
+            let next = [self.whitespace, self.comment]
+                .into_iter()
+                .flatten()
+                .find_map(|index| {
+                    restore_on_err(stack, |stack| {
+                        self.rule(index, input, stack, &mut tracker, true)
+                    })
+                    .ok()
+                });
+            match next {
+                Some((next, _)) if next != input => input = next,
+                _ => return input,
+            }
+        }
+    }
+    fn builtin<'g, 'i>(
+        &'g self,
+        builtin: &Builtin,
+        name: &'g str,
+        mut input: Position<'i>,
+        stack: &mut Stack<Span<'i>>,
+        tracker: &mut Tracker<'i, RuleName<'g>>,
+    ) -> Result<(Position<'i>, Node<'g, 'i>), ()> {
+        let start = input;
+        let matched = match builtin {
+            Builtin::Any => input.skip(1),
+            Builtin::Soi => input.at_start(),
+            Builtin::Eoi => input.at_end(),
+            Builtin::Newline => {
+                input.match_string("\r\n") || input.match_string("\n") || input.match_string("\r")
+            }
+            Builtin::Peek => match stack.peek() {
+                Some(span) => input.match_string(span.as_str()),
+                None => {
+                    tracker.empty_stack(input);
+                    false
+                }
+            },
+            Builtin::PeekAll | Builtin::PopAll => {
+                let spans = stack[0..stack.len()].iter().rev();
+                let (next, _) = peek_spans(input, spans, tracker)?;
+                input = next;
+                if let Builtin::PopAll = builtin {
+                    while stack.pop().is_some() {}
+                }
+                true
+            }
+            Builtin::Pop | Builtin::Drop => match stack.pop() {
+                Some(span) => matches!(builtin, Builtin::Drop) || input.match_string(span.as_str()),
+                None => {
+                    tracker.empty_stack(input);
+                    false
+                }
+            },
+            Builtin::Char(f) => input.match_char_by(f),
+            Builtin::Fail => false,
+        };
+        match matched {
+            true => Ok((
+                input,
+                Node::Builtin {
+                    name,
+                    span: start.span(&input),
+                },
+            )),
+            false => Err(()),
+        }
+    }
+    fn expr<'g, 'i>(
+        &'g self,
+        expr: &'g Expr,
+        mut input: Position<'i>,
+        stack: &mut Stack<Span<'i>>,
+        tracker: &mut Tracker<'i, RuleName<'g>>,
+        atomic: bool,
+    ) -> Result<(Position<'i>, Node<'g, 'i>), ()> {
+        let start = input;
+        match expr {
+            Expr::Str(s) => match input.match_string(s) {
+                true => Ok((input, Node::Str(start.span(&input)))),
+                false => {
+                    tracker.expect_owned_literal(input, s, false);
+                    Err(())
+                }
+            },
+            Expr::Insens(s) => match input.match_insensitive(s) {
+                true => Ok((input, Node::Insens(start.span(&input)))),
+                false => {
+                    tracker.expect_owned_literal(input, s, true);
+                    Err(())
+                }
+            },
+            Expr::Range(min, max) => match input.match_range(*min..*max) {
+                true => {
+                    let c = start.span(&input).as_str().chars().next().unwrap();
+                    Ok((input, Node::Range(c)))
+                }
+                false => Err(()),
+            },
+            Expr::Rule(index) => self.rule(*index, input, stack, tracker, atomic),
+            Expr::Builtin(name, builtin) => self.builtin(builtin, name, input, stack, tracker),
+            Expr::PeekSlice(start, end) => {
+                let spans = stack_slice(input, *start, *end, stack, tracker)?;
+                let (input, span) = peek_spans(input, spans, tracker)?;
+                Ok((input, Node::PeekSlice(span)))
+            }
+            Expr::PosPred(expr) => tracker.positive_during(|tracker| {
+                stack.snapshot();
+                let res = self.expr(expr, input, stack, tracker, atomic);
+                stack.restore();
+                let (_, node) = res?;
+                Ok((input, Node::PosPred(Box::new(node))))
+            }),
+            Expr::NegPred(expr) => tracker.negative_during(|tracker| {
+                stack.snapshot();
+                let res = self.expr(expr, input, stack, tracker, atomic);
+                stack.restore();
+                match res {
+                    Ok((end, _)) => {
+                        tracker.negative_matched(input, end);
+                        Err(())
+                    }
+                    Err(_) => Ok((input, Node::NegPred)),
+                }
+            }),
+            Expr::Seq(exprs) => {
+                let mut nodes = Vec::with_capacity(exprs.len());
+                for (i, expr) in exprs.iter().enumerate() {
+                    if i > 0 && !atomic {
+                        input = self.skip(input, stack);
+                    }
+                    let (next, node) = self.expr(expr, input, stack, tracker, atomic)?;
+                    input = next;
+                    nodes.push(node);
+                }
+                Ok((input, Node::Seq(nodes)))
+            }
+            Expr::Choice(exprs) => {
+                for (index, expr) in exprs.iter().enumerate() {
+                    if let Ok((input, node)) = restore_on_err(stack, |stack| {
+                        self.expr(expr, input, stack, tracker, atomic)
+                    }) {
+                        let content = Box::new(node);
+                        return Ok((input, Node::Choice { index, content }));
+                    }
+                }
+                Err(())
+            }
+            Expr::Opt(expr) => {
+                match restore_on_err(stack, |stack| {
+                    self.expr(expr, input, stack, tracker, atomic)
+                }) {
+                    Ok((input, node)) => Ok((input, Node::Opt(Some(Box::new(node))))),
+                    Err(_) => Ok((input, Node::Opt(None))),
+                }
+            }
+            Expr::Rep(expr, min, max) => {
+                let mut nodes = vec![];
+                while max.is_none_or(|max| nodes.len() < max as usize) {
+                    let res = restore_on_err(stack, |stack| {
+                        let mut input = input;
+                        if !nodes.is_empty() && !atomic {
+                            input = self.skip(input, stack);
+                        }
+                        self.expr(expr, input, stack, tracker, atomic)
+                    });
+                    match res {
+                        Ok((next, node)) => {
+                            input = next;
+                            nodes.push(node);
+                        }
+                        Err(_) => break,
+                    }
+                }
+                match nodes.len() >= *min as usize {
+                    true => Ok((input, Node::Rep(nodes))),
+                    false => Err(()),
+                }
+            }
+            Expr::Skip(strings) => {
+                let strings: Vec<&str> = strings.iter().map(String::as_str).collect();
+                match input.skip_until(&strings) {
+                    true => Ok((input, Node::Skip(start.span(&input)))),
+                    false => Err(()),
+                }
+            }
+            Expr::Push(expr) => {
+                let (input, node) = self.expr(expr, input, stack, tracker, atomic)?;
+                stack.push(start.span(&input));
+                Ok((input, Node::Push(Box::new(node))))
+            }
+        }
+    }
+}
+
+impl core::str::FromStr for Vm {
+    type Err = Vec<GrammarError>;
+
+    fn from_str(grammar: &str) -> Result<Self, Self::Err> {
+        Self::new(grammar)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Node, Vm};
+    use alloc::string::ToString;
+
+    #[test]
+    fn structure() {
+        let vm = Vm::new(
+            r#"
+            main = { (item ~ ("," ~ item)*)? ~ ^"end" }
+            item = _{ num | "x" }
+            num = @{ ASCII_DIGIT+ }
+            WHITESPACE = _{ " " }
+            "#,
+        )
+        .unwrap();
+        let node = vm.parse("main", "1 , x,23 END").unwrap();
+        let Node::Rule { content, .. } = &node else {
+            panic!()
+        };
+        let Node::Seq(seq) = content.as_ref() else {
+            panic!()
+        };
+        assert_eq!(seq.len(), 2);
+        assert!(matches!(&seq[1], Node::Insens(span) if span.as_str() == "END"));
+        let tokens = node.tokens();
+        let nums: alloc::vec::Vec<_> = tokens[0]
+            .children
+            .iter()
+            .map(|token| (token.rule.0, token.start, token.end))
+            .collect();
+        assert_eq!(nums, [("num", 0, 1), ("num", 6, 8)]);
+    }
+
+    #[test]
+    fn stack() {
+        let vm =
+            Vm::new(r##"raw = ${ PUSH("#"*) ~ "\"" ~ (!("\"" ~ PEEK) ~ ANY)* ~ "\"" ~ POP }"##)
+                .unwrap();
+        assert!(vm.parse("raw", r###"##"a"#b"##"###).is_ok());
+        assert!(vm.parse("raw", r###"##"a"#"###).is_err());
+    }
+
+    #[test]
+    fn errors() {
+        let vm = Vm::new(r#"a = { "a" ~ b } b = { "b" | "c" }"#).unwrap();
+        let error = vm.parse("a", "ad").unwrap_err();
+        assert!(
+            error.to_string().contains("Expected [b], by a."),
+            "{}",
+            error
+        );
+        assert!(vm.parse("c", "").is_err());
+        assert!(Vm::new("a = { b }").is_err());
+        assert_eq!(vm.rule_names().collect::<alloc::vec::Vec<_>>(), ["a", "b"]);
+    }
+
+    #[test]
+    #[cfg(feature = "grammar-extras")]
+    fn extras() {
+        let vm = Vm::new(r#"a = { #key = "a" ~ #value = b } b = { "b" }"#).unwrap();
+        assert!(vm.parse("a", "ab").is_ok());
+        let errors = Vm::new("a = { \"a\" }\nb = { PUSH_LITERAL(\"b\") ~ POP }").unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(
+            errors[0].variant.message(),
+            "`PUSH_LITERAL` is not supported yet"
+        );
+        assert_eq!(errors[0].line(), "b = { PUSH_LITERAL(\"b\") ~ POP }");
+    }
+}
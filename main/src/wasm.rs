@@ -0,0 +1,90 @@
+// pest-typed. A statically typed version of pest.
+// Copyright (c) 2023 黄博奕
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Bindings for JavaScript with [wasm-bindgen](https://docs.rs/wasm-bindgen).
+//!
+//! [`ParseOutput`] is a JavaScript class with the result of parsing as JSON,
+//! in the shapes of [`result_to_json`].
+//! With `emit_wasm`, the generator exports a function `parse(rule, input)` that returns it,
+//! so that a playground only needs to build the grammar crate with `wasm-pack`:
+//!
+//! ```js
+//! const output = parse("expr", "1 + 2");
+//! if (output.ok) {
+//!     console.log(JSON.parse(output.json).ok.children);
+//! }
+//! ```
+//!
+//! [`wasm_bindgen`] is re-exported for generated code,
+//! so grammar crates don't need to depend on it themselves.
+
+use crate::{error::Error, iterators::Token, json::result_to_json, RuleType};
+pub use ::wasm_bindgen;
+use ::wasm_bindgen::prelude::wasm_bindgen;
+use alloc::string::String;
+
+/// Result of parsing, for JavaScript.
+#[wasm_bindgen]
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct ParseOutput {
+    ok: bool,
+    json: String,
+}
+
+#[wasm_bindgen]
+impl ParseOutput {
+    /// Whether the input is parsed.
+    #[wasm_bindgen(getter)]
+    pub fn ok(&self) -> bool {
+        self.ok
+    }
+    /// `{"ok": token}` or `{"error": error}`. See [`result_to_json`].
+    #[wasm_bindgen(getter)]
+    pub fn json(&self) -> String {
+        self.json.clone()
+    }
+}
+
+impl ParseOutput {
+    /// Convert a token tree or an error produced from `input`.
+    pub fn new<R: RuleType>(result: &Result<Token<R>, Error<R>>, input: &str) -> Self {
+        Self {
+            ok: result.is_ok(),
+            json: result_to_json(result, input),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ParseOutput;
+    use crate::{dynamic::unknown_rule, iterators::Token};
+
+    #[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+    enum Rule {
+        A,
+    }
+
+    #[test]
+    fn output() {
+        let token = Token {
+            rule: Rule::A,
+            start: 0,
+            end: 1,
+            children: Default::default(),
+        };
+        let output = ParseOutput::new(&Ok(token), "a");
+        assert!(output.ok());
+        assert!(output.json().starts_with(r#"{"ok":{"rule":"A""#));
+
+        let output = ParseOutput::new::<Rule>(&Err(unknown_rule("b", "a")), "a");
+        assert!(!output.ok());
+        assert!(output.json().starts_with(r#"{"error":"#));
+    }
+}
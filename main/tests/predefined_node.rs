@@ -372,6 +372,11 @@ mod tests {
                         start: 3,
                         end: 6,
                     },
+                    pushed: Span {
+                        str: \"foO\",
+                        start: 0,
+                        end: 3,
+                    },
                 },
             ],
         },
@@ -388,6 +393,7 @@ mod tests {
         assert_eq!(head.get_content(), Foo::CONTENT);
         for i in following.iter_matched() {
             assert_eq!(i.span.as_str(), head.deref().content);
+            assert_eq!(i.pushed.start(), 0);
         }
 
         Rep_1_3::try_parse("fooFoo").unwrap_err();